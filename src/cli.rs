@@ -1,4 +1,16 @@
-use clap::{Parser, arg, command};
+use clap::{Parser, ValueEnum, arg, command};
+
+use crate::fs_service::DirectoryMode;
+
+/// Transport used to exchange MCP messages with the client.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum TransportKind {
+    /// Standard input/output of the process.
+    #[default]
+    Stdio,
+    /// Unix domain socket; each connection is served as a separate MCP session.
+    Unix,
+}
 
 #[derive(Parser, Debug)]
 #[command(name =  env!("CARGO_PKG_NAME"))]
@@ -34,6 +46,161 @@ pub struct CommandArguments {
     )]
     pub tools: Option<String>,
 
+    #[arg(
+        long,
+        action = clap::ArgAction::SetTrue,
+        value_parser = clap::value_parser!(bool),
+        help = "Enables the 'configure_tools' admin tool, which enables and disables tools at runtime. Defaults to disabled.",
+        env = "ENABLE_ADMIN_TOOLS"
+    )]
+    pub enable_admin_tools: bool,
+
+    #[arg(
+        long,
+        help = "Directory where persistent content-search indexes are stored. Defaults to the user's cache directory.",
+        env = "INDEX_DIR"
+    )]
+    pub index_dir: Option<String>,
+
+    #[arg(
+        long,
+        help = "Path of a TOML (or .json) policy file restricting individual tools: 'allow', 'deny' or 'readonly' access and the paths they may touch.",
+        env = "POLICY_FILE"
+    )]
+    pub policy_file: Option<String>,
+
+    #[arg(
+        long,
+        help = "Maximum number of heavy operations (content and AST searches, duplicate detection, directory walks) running at the same time. Further calls fail with a 'retry later' error. Unlimited by default.",
+        env = "MAX_CONCURRENT_HEAVY"
+    )]
+    pub max_concurrent_heavy: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Maximum number of calls of each tool within a minute. Further calls fail with a 'retry later' error. Unlimited by default.",
+        env = "MAX_CALLS_PER_MINUTE"
+    )]
+    pub max_calls_per_minute: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Maximum duration of a tool call in seconds. Calls running longer are aborted with a timeout error. Tools that modify files are cancelled at a safe point instead, and complete if they have none. Unlimited by default.",
+        env = "TOOL_TIMEOUT"
+    )]
+    pub tool_timeout: Option<u64>,
+
+    #[arg(
+        long,
+        help = "Maximum size in bytes of the content returned by a tool call. Larger responses are truncated and flagged with '_meta.truncated'. A call can lower it with a '_meta.maxResponseBytes' argument. Unlimited by default.",
+        env = "MAX_RESPONSE_BYTES"
+    )]
+    pub max_response_bytes: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Maximum number of bytes returned by 'read_text_file' when a call does not set 'max_bytes'. Longer files are truncated with a notice pointing to 'read_file_lines'. Defaults to 262144 (256 KiB).",
+        env = "READ_MAX_BYTES"
+    )]
+    pub read_max_bytes: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Number of threads used by parallel searches ('search_files_content', 'search_code_ast'). A call can override it with a 'threads' argument. Defaults to one per CPU core.",
+        env = "SEARCH_THREADS"
+    )]
+    pub search_threads: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Size in bytes above which 'search_code_ast' skips files, e.g. large generated sources. A call can override it with a 'max_file_size' argument. Defaults to 1048576 (1 MiB).",
+        env = "AST_MAX_FILE_SIZE"
+    )]
+    pub ast_max_file_size: Option<u64>,
+
+    #[arg(
+        long,
+        help = "Maximum number of files parsed by a 'search_code_ast' call, which then stops and reports truncated results. A call can override it with a 'max_files' argument. Defaults to 10000.",
+        env = "AST_MAX_FILES"
+    )]
+    pub ast_max_files: Option<usize>,
+
+    #[arg(
+        long,
+        action = clap::ArgAction::SetTrue,
+        value_parser = clap::value_parser!(bool),
+        help = "Run parallel searches on a single thread so that they do not compete with the host for CPU cores, unless a call sets 'low_priority' to false. Defaults to disabled.",
+        env = "LOW_PRIORITY_SEARCH"
+    )]
+    pub low_priority_search: bool,
+
+    #[arg(
+        long,
+        action = clap::ArgAction::SetTrue,
+        value_parser = clap::value_parser!(bool),
+        help = "Move files and directories deleted by tools to a '.mcp-trash' directory at the top of their allowed directory instead of unlinking them. They can be brought back with the 'restore_from_trash' tool.",
+        env = "USE_TRASH"
+    )]
+    pub use_trash: bool,
+
+    #[arg(
+        long,
+        action = clap::ArgAction::SetTrue,
+        value_parser = clap::value_parser!(bool),
+        help = "Records the operations of tools that modify files, with backups of overwritten and deleted files, so that they can be reverted with the 'undo_last_operation' tool. Defaults to disabled.",
+        env = "JOURNAL"
+    )]
+    pub journal: bool,
+
+    #[arg(
+        long,
+        action = clap::ArgAction::SetTrue,
+        value_parser = clap::value_parser!(bool),
+        help = "Write files in place instead of writing a temporary file next to them and renaming it over the target. Only needed for filesystems that do not support atomic renames; a crash mid-write may then leave a file partially written.",
+        env = "NO_ATOMIC_WRITES"
+    )]
+    pub no_atomic_writes: bool,
+
+    #[arg(
+        long,
+        help = "Directory where the previous content of every file overwritten by 'write_file' or 'edit_file' is backed up, mirroring its absolute path. Without it, backups are only made when a call sets 'backup', as '<name>.bak' next to the file.",
+        env = "BACKUP_DIR"
+    )]
+    pub backup_dir: Option<String>,
+
+    #[arg(
+        long = "deny-glob",
+        value_delimiter = ',',
+        help = "Glob patterns of paths that can be neither read nor written, even inside allowed directories (e.g. '**/.env', '**/secrets/**'). Can be repeated.",
+        env = "DENY_GLOB"
+    )]
+    pub deny_globs: Vec<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = TransportKind::Stdio,
+        help = "Transport used to communicate with MCP clients.",
+        env = "TRANSPORT"
+    )]
+    pub transport: TransportKind,
+
+    #[arg(
+        long,
+        help = "Path of the Unix domain socket to listen on. Required with '--transport unix'.",
+        env = "SOCKET"
+    )]
+    pub socket: Option<String>,
+
+    #[arg(
+        long,
+        default_value = "600",
+        value_parser = parse_socket_mode,
+        help = "Octal permission bits applied to the Unix domain socket (e.g. 600, 660).",
+        env = "SOCKET_MODE"
+    )]
+    pub socket_mode: u32,
+
     #[arg(
         long,
         help = "List all available tools and exit",
@@ -44,13 +211,37 @@ pub struct CommandArguments {
 
     #[arg(
         help = "List of directories that are permitted for the operation. It is required when 'enable-roots' is not provided OR client does not support Roots.",
-        long_help = concat!("Provide a space-separated list of directories that are permitted for the operation.\nThis list allows multiple directories to be provided.\n\nExample:  ", env!("CARGO_PKG_NAME"), " /path/to/dir1 /path/to/dir2 /path/to/dir3"),
+        long_help = concat!("Provide a space-separated list of directories that are permitted for the operation.\nThis list allows multiple directories to be provided.\nAppend ':rw' or ':ro' to a directory to make it writable or read-only regardless of '--allow-write'.\n\nExample:  ", env!("CARGO_PKG_NAME"), " /path/to/dir1 /path/to/dir2:rw /path/to/dir3:ro"),
         required = false
     )]
     pub allowed_directories: Vec<String>,
 }
 
 impl CommandArguments {
+    /// Splits the allowed directories into their paths and the modes given with a `:rw` or `:ro`
+    /// suffix.
+    pub fn directory_entries(&self) -> (Vec<String>, Vec<(String, DirectoryMode)>) {
+        let mut directories = Vec::with_capacity(self.allowed_directories.len());
+        let mut modes = vec![];
+        for entry in &self.allowed_directories {
+            let (path, mode) = parse_directory_entry(entry);
+            if let Some(mode) = mode {
+                modes.push((path.to_string(), mode));
+            }
+            directories.push(path.to_string());
+        }
+        (directories, modes)
+    }
+
+    /// Mode of directories without an explicit `:rw` or `:ro` suffix.
+    pub fn default_directory_mode(&self) -> DirectoryMode {
+        if self.allow_write {
+            DirectoryMode::ReadWrite
+        } else {
+            DirectoryMode::ReadOnly
+        }
+    }
+
     pub fn validate(&self) -> Result<(), String> {
         if !self.enable_roots && self.allowed_directories.is_empty() {
             return Err(format!(
@@ -58,6 +249,38 @@ impl CommandArguments {
                 env!("CARGO_PKG_NAME")
             ));
         }
+        if self.transport == TransportKind::Unix {
+            if cfg!(not(unix)) {
+                return Err("The `unix` transport is only available on Unix platforms.".into());
+            }
+            if self.socket.is_none() {
+                return Err(" `--socket` is required when `--transport unix` is used.".into());
+            }
+            // the sessions of a socket server share their allowed directories, which the roots
+            // of one client must not replace for the others
+            if self.enable_roots {
+                return Err(" `--enable-roots` cannot be used with `--transport unix`.".into());
+            }
+        }
         Ok(())
     }
 }
+
+fn parse_directory_entry(entry: &str) -> (&str, Option<DirectoryMode>) {
+    if let Some((path, suffix)) = entry.rsplit_once(':')
+        && !path.is_empty()
+        && let Ok(mode) = suffix.parse()
+    {
+        return (path, Some(mode));
+    }
+    (entry, None)
+}
+
+fn parse_socket_mode(value: &str) -> Result<u32, String> {
+    match u32::from_str_radix(value, 8) {
+        Ok(mode) if mode <= 0o777 => Ok(mode),
+        _ => Err(format!(
+            "invalid socket mode '{value}', expected octal permission bits such as 600 or 660"
+        )),
+    }
+}
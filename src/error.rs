@@ -1,5 +1,9 @@
-use rust_mcp_sdk::schema::{RpcError, schema_utils::SdkError};
+use rust_mcp_sdk::schema::{
+    CallToolResult, INVALID_PARAMS, RpcError, TextContent,
+    schema_utils::{CallToolError, SdkError, UnknownTool},
+};
 use rust_mcp_sdk::{TransportError, error::McpSdkError};
+use serde_json::json;
 
 use thiserror::Error;
 use tokio::io;
@@ -12,10 +16,26 @@ pub enum ServiceError {
         "Service is running in read-only mode. To enable write access, please run with the --allow-write flag."
     )]
     NoWriteAccess,
+    #[error("Access denied - {0} is in a read-only directory.")]
+    ReadOnlyPath(String),
+    #[error("Access denied - {0}")]
+    PathDenied(String),
     #[error(
         "Tool '{0}' is not enabled. Please add it to the --tools parameter or use --tools all to enable all tools."
     )]
     ToolNotEnabled(String),
+    #[error("Tool call denied by policy: {0}")]
+    PolicyDenied(String),
+    #[error("Rate limit exceeded: {0}")]
+    RateLimited(String),
+    #[error("{0}")]
+    InvalidPattern(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    InvalidArgument(String),
+    #[error("{0}")]
+    NoMatch(String),
     #[error("{0}")]
     FromString(String),
     #[error("{0}")]
@@ -30,6 +50,8 @@ pub enum ServiceError {
     SerdeJsonError(#[from] serde_json::Error),
     #[error("{0}")]
     ContentSearchError(#[from] grep::regex::Error),
+    #[error("Invalid JSONPath expression: {0}")]
+    JsonPathError(#[from] serde_json_path::ParseError),
     #[error("{0}")]
     McpSdkError(#[from] McpSdkError),
     // #[error("{0}")]
@@ -40,4 +62,146 @@ pub enum ServiceError {
     FileTooSmall(usize),
     #[error("The file is either not an image/audio type or is unsupported (mime:{0}).")]
     InvalidMediaFile(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    #[error("Operation was cancelled.")]
+    Cancelled,
+    #[error(
+        "Tool '{0}' timed out after {1} seconds. Partial results are not returned: narrow the request (a more specific path, pattern or file filter, or a lower depth) and call the tool again."
+    )]
+    Timeout(String, u64),
+}
+
+/// Stable category of an error, which clients can branch on instead of parsing the message.
+#[derive(::serde::Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    /// The path is outside the allowed directories or matches a denied pattern.
+    PathDenied,
+    /// The server or the directory of the path is read-only.
+    ReadOnly,
+    NotFound,
+    AlreadyExists,
+    /// The operating system refused the operation.
+    PermissionDenied,
+    TooLarge,
+    TooSmall,
+    /// A glob, regex, JSONPath, AST pattern or rule could not be parsed.
+    PatternInvalid,
+    /// The tool arguments do not match its input schema.
+    InvalidArgument,
+    /// The text to edit, the entries to list or the matches of a search were not found.
+    NoMatch,
+    /// The content could not be parsed, e.g. malformed JSON.
+    InvalidData,
+    UnsupportedMedia,
+    /// The target changed or exists, e.g. a failed hash precondition.
+    Conflict,
+    Cancelled,
+    Timeout,
+    RateLimited,
+    PolicyDenied,
+    ToolNotEnabled,
+    UnknownTool,
+    /// Any other I/O error.
+    IoError,
+    /// An error of the MCP transport or protocol.
+    Internal,
+    /// An error without a more specific category.
+    Other,
+}
+
+impl ErrorCode {
+    fn from_io_error(err: &io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::NotFound => Self::NotFound,
+            io::ErrorKind::AlreadyExists => Self::AlreadyExists,
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied,
+            io::ErrorKind::InvalidData => Self::InvalidData,
+            io::ErrorKind::IsADirectory | io::ErrorKind::NotADirectory => Self::InvalidArgument,
+            _ => Self::IoError,
+        }
+    }
+
+    /// Returns the code of an error returned by a tool.
+    pub fn of(err: &(dyn std::error::Error + 'static)) -> Self {
+        if let Some(err) = err.downcast_ref::<ServiceError>() {
+            err.code()
+        } else if let Some(err) = err.downcast_ref::<CallToolError>() {
+            Self::of(err.0.as_ref())
+        } else if let Some(err) = err.downcast_ref::<io::Error>() {
+            Self::from_io_error(err)
+        } else if err.is::<serde_json::Error>() {
+            Self::InvalidArgument
+        } else if err.is::<UnknownTool>() {
+            Self::UnknownTool
+        } else {
+            Self::Other
+        }
+    }
+}
+
+impl ServiceError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ServiceError::NoWriteAccess | ServiceError::ReadOnlyPath(_) => ErrorCode::ReadOnly,
+            ServiceError::PathDenied(_) => ErrorCode::PathDenied,
+            ServiceError::ToolNotEnabled(_) => ErrorCode::ToolNotEnabled,
+            ServiceError::PolicyDenied(_) => ErrorCode::PolicyDenied,
+            ServiceError::RateLimited(_) => ErrorCode::RateLimited,
+            ServiceError::InvalidPattern(_)
+            | ServiceError::ContentSearchError(_)
+            | ServiceError::JsonPathError(_) => ErrorCode::PatternInvalid,
+            ServiceError::NotFound(_) => ErrorCode::NotFound,
+            ServiceError::InvalidArgument(_) => ErrorCode::InvalidArgument,
+            ServiceError::NoMatch(_) => ErrorCode::NoMatch,
+            ServiceError::FromString(_) => ErrorCode::Other,
+            ServiceError::RpcError(err) if err.code == INVALID_PARAMS => ErrorCode::InvalidArgument,
+            ServiceError::TransportError(_)
+            | ServiceError::SdkError(_)
+            | ServiceError::RpcError(_)
+            | ServiceError::McpSdkError(_) => ErrorCode::Internal,
+            ServiceError::IoError(err) => ErrorCode::from_io_error(err),
+            ServiceError::SerdeJsonError(_) => ErrorCode::InvalidData,
+            ServiceError::FileTooLarge(_) => ErrorCode::TooLarge,
+            ServiceError::FileTooSmall(_) => ErrorCode::TooSmall,
+            ServiceError::InvalidMediaFile(_) => ErrorCode::UnsupportedMedia,
+            ServiceError::Conflict(_) => ErrorCode::Conflict,
+            ServiceError::Cancelled => ErrorCode::Cancelled,
+            ServiceError::Timeout(_, _) => ErrorCode::Timeout,
+        }
+    }
+}
+
+/// A failed tool call, which unlike [`CallToolError`] can be held across await points.
+#[derive(::serde::Serialize, Debug, Clone, PartialEq)]
+pub struct ToolFailure {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl From<&CallToolError> for ToolFailure {
+    fn from(err: &CallToolError) -> Self {
+        Self {
+            code: ErrorCode::of(err.0.as_ref()),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<ToolFailure> for CallToolResult {
+    /// The message stays the first content block, followed by the failure as JSON,
+    /// e.g. `{"error":{"code":"PATH_DENIED","message":"..."}}`.
+    fn from(failure: ToolFailure) -> Self {
+        let details = json!({ "error": failure });
+        CallToolResult {
+            content: vec![
+                TextContent::from(failure.message).into(),
+                TextContent::from(details.to_string()).into(),
+            ],
+            is_error: Some(true),
+            meta: None,
+            structured_content: None,
+        }
+    }
 }
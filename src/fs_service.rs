@@ -1,8 +1,49 @@
+mod baseline;
 mod core;
+mod disk;
+mod git;
+mod index;
 mod io;
+mod journal;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod operation;
+mod resources;
 mod search;
+mod trash;
 pub mod utils;
+mod watch;
+mod xattr;
 
-pub use core::FileSystemService;
-pub use io::FileInfo;
-pub use search::{AstFileSearchResult, AstMatchResult, FileSearchResult};
+pub use baseline::{Baseline, BaselineDiff, BaselineFile, BaselineSummary};
+pub use core::{DirectoryMode, FileSystemService, WalkOptions};
+pub use disk::DiskSpace;
+pub use git::{GitBlameLine, GitCommit, GitDiffFile, GitDiffResult};
+pub use index::{ContentIndex, IndexFilter, IndexSummary};
+pub use io::{
+    AudioInfo, ByteRangeRead, ChunkPosition, ColumnType, CsvColumn, CsvPreview,
+    DEFAULT_CHUNK_MAX_BYTES, DEFAULT_READ_MAX_BYTES, DiffHunk, DiffSummary, DocumentKind,
+    DocumentSection, DocumentText, EditResult, FileChunk, FileDiffSummary, FileInfo, ImageInfo,
+    ImageResizeOptions, MarkdownHeading, MediaInfo, OverwritePolicy, StructuredFormat, TailChunk,
+    TailPosition, TempPathKind, TextFileRead, markdown_outline, select_dotted_path,
+};
+pub use journal::{JournalChange, JournalEntry};
+pub use operation::{CancellationToken, OperationContext, ProgressUpdate};
+pub use resources::{
+    FileResource, MAX_RESOURCE_BYTES, RESOURCE_PAGE_SIZE, ResourceContent, file_uri_to_path,
+    path_to_file_uri,
+};
+pub use search::{
+    ArchiveSearchResult, AstFileSearchResult, AstMatchResult, AstRewriteResult, AstSearchLimits,
+    AstSearchOutcome, BrokenSymlink, BrokenSymlinkReason, CleanedFile, CleanupAction,
+    CleanupOutcome, CodeSymbol, ContentMatchResult, ContentSearchOptions, ContentSearchOutcome,
+    DEFAULT_AST_MAX_FILE_SIZE, DEFAULT_AST_MAX_FILES, DEFAULT_TODO_TAGS, DirectoryListing,
+    DirectorySortBy, DirectoryStatistics, DirectoryTreePage, DuplicateAction, DuplicateResolution,
+    EmptyDirectoryRemoval, ExtensionStatistics, FileSearchResult, FuzzyMatch, LongLineAction,
+    MatchRegion, MatchRegions, SearchFilesOutcome, SearchSortBy, SizeGroup, SizeGroupBy,
+    SizedEntry, SortOrder, TodoGroup, TodoItem, TreeFilter, code_outline, fuzzy_score,
+    sort_search_results,
+};
+pub use trash::{TRASH_DIR_NAME, TrashEntry};
+pub use watch::{FileChangeEvent, FileChangeKind};
+pub use xattr::ExtendedAttribute;
@@ -0,0 +1,280 @@
+use crate::{
+    error::{ServiceError, ServiceResult},
+    fs_service::{
+        FileSystemService, WalkOptions,
+        utils::{normalize_path, sha256_file_hex},
+    },
+};
+use chrono::Utc;
+use ignore::WalkBuilder;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs::Metadata,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::UNIX_EPOCH,
+};
+
+const BASELINE_VERSION: u32 = 1;
+
+/// The state of a file recorded in a [`Baseline`].
+#[derive(::serde::Deserialize, ::serde::Serialize, Debug, Clone, PartialEq)]
+pub struct BaselineFile {
+    pub size: u64,
+    /// Modification time in nanoseconds since the Unix epoch.
+    pub mtime: u128,
+    /// Lowercase hex SHA-256 digest of the content.
+    pub sha256: String,
+}
+
+/// A snapshot of the files of a directory, compared later with
+/// [`FileSystemService::compare_baseline`] to find what changed.
+#[derive(::serde::Deserialize, ::serde::Serialize, Debug, Clone, PartialEq)]
+pub struct Baseline {
+    pub version: u32,
+    pub root: PathBuf,
+    /// UTC time of the snapshot.
+    pub created_at: String,
+    pub respect_gitignore: bool,
+    pub include_hidden: bool,
+    /// Files keyed by their path relative to `root`.
+    pub files: BTreeMap<PathBuf, BaselineFile>,
+}
+
+/// Summary of a snapshot taken by [`FileSystemService::create_baseline`].
+#[derive(Debug)]
+pub struct BaselineSummary {
+    pub root: PathBuf,
+    pub baseline_path: PathBuf,
+    pub files: usize,
+}
+
+/// Changes since a baseline, as reported by [`FileSystemService::compare_baseline`]. Paths are
+/// relative to the root.
+#[derive(::serde::Serialize, Debug, Clone, Default, PartialEq)]
+pub struct BaselineDiff {
+    pub root: PathBuf,
+    /// UTC time of the baseline.
+    pub baseline_created_at: String,
+    pub added: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    /// Files whose content changed.
+    pub modified: Vec<PathBuf>,
+    pub unchanged: usize,
+}
+
+impl BaselineDiff {
+    pub fn has_changes(&self) -> bool {
+        !(self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty())
+    }
+}
+
+fn mtime_nanos(metadata: &Metadata) -> u128 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or_default()
+}
+
+impl FileSystemService {
+    /// Returns the default location of the baseline of the given (validated) root directory,
+    /// in the index directory.
+    fn default_baseline_path(&self, root_path: &Path) -> ServiceResult<PathBuf> {
+        let index_dir = self.index_dir().ok_or(ServiceError::FromString(
+            "No index directory is configured, a baseline file must be given.".to_string(),
+        ))?;
+        let hash = Sha256::digest(root_path.to_string_lossy().as_bytes());
+        let file_name = hash.iter().map(|b| format!("{b:02x}")).collect::<String>();
+        Ok(index_dir
+            .join("baselines")
+            .join(format!("{file_name}.json")))
+    }
+
+    /// Validates the root directory of a baseline and resolves where the baseline is stored:
+    /// `baseline_file` if given, otherwise in the index directory.
+    async fn baseline_location(
+        &self,
+        root_path: &Path,
+        baseline_file: Option<&Path>,
+        for_write: bool,
+    ) -> ServiceResult<(PathBuf, PathBuf)> {
+        let allowed_directories = self.allowed_directories().await;
+        let root = normalize_path(&self.validate_path(root_path, allowed_directories.clone())?);
+        if !root.is_dir() {
+            return Err(ServiceError::InvalidArgument(
+                "Root path must be a directory".into(),
+            ));
+        }
+        let baseline_path = match baseline_file {
+            Some(file) if for_write => self.validate_write_path(file, allowed_directories)?,
+            Some(file) => self.validate_path(file, allowed_directories)?,
+            None => self.default_baseline_path(&root)?,
+        };
+        Ok((root, baseline_path))
+    }
+
+    /// Records the size, modification time and SHA-256 digest of the files of `root_path`, in
+    /// `baseline_file` or in the index directory. Files are hashed in parallel.
+    pub async fn create_baseline(
+        &self,
+        root_path: &Path,
+        baseline_file: Option<&Path>,
+        walk_options: WalkOptions,
+    ) -> ServiceResult<BaselineSummary> {
+        let (root, baseline_path) = self
+            .baseline_location(root_path, baseline_file, true)
+            .await?;
+        let files = self.snapshot_files(
+            &root,
+            &baseline_path,
+            walk_options,
+            self.allowed_directories().await,
+            None,
+        )?;
+        let baseline = Baseline {
+            version: BASELINE_VERSION,
+            root: root.clone(),
+            created_at: Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            respect_gitignore: walk_options.respect_gitignore,
+            include_hidden: walk_options.include_hidden,
+            files,
+        };
+
+        if let Some(parent) = baseline_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&baseline_path, serde_json::to_vec(&baseline)?).await?;
+        Ok(BaselineSummary {
+            root,
+            baseline_path,
+            files: baseline.files.len(),
+        })
+    }
+
+    /// Compares the files of `root_path` with the baseline recorded by
+    /// [`Self::create_baseline`], walking them with the options of the baseline. Files whose
+    /// size and modification time did not change are not hashed again.
+    pub async fn compare_baseline(
+        &self,
+        root_path: &Path,
+        baseline_file: Option<&Path>,
+    ) -> ServiceResult<BaselineDiff> {
+        let (root, baseline_path) = self
+            .baseline_location(root_path, baseline_file, false)
+            .await?;
+        let baseline: Baseline = match tokio::fs::read(&baseline_path).await {
+            Ok(content) => serde_json::from_slice(&content)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Err(ServiceError::NotFound(format!(
+                    "No baseline was found for '{}', create one first.",
+                    root_path.display()
+                )));
+            }
+            Err(err) => return Err(err.into()),
+        };
+        if baseline.version != BASELINE_VERSION {
+            return Err(ServiceError::FromString(format!(
+                "The baseline at '{}' has an unsupported version, create it again.",
+                baseline_path.display()
+            )));
+        }
+        if baseline.root != root {
+            return Err(ServiceError::FromString(format!(
+                "The baseline at '{}' was created for '{}'.",
+                baseline_path.display(),
+                baseline.root.display()
+            )));
+        }
+
+        let walk_options = WalkOptions {
+            respect_gitignore: baseline.respect_gitignore,
+            include_hidden: baseline.include_hidden,
+            ..Default::default()
+        };
+        let current = self.snapshot_files(
+            &root,
+            &baseline_path,
+            walk_options,
+            self.allowed_directories().await,
+            Some(&baseline.files),
+        )?;
+
+        let mut diff = BaselineDiff {
+            root,
+            baseline_created_at: baseline.created_at,
+            ..Default::default()
+        };
+        for (path, file) in &current {
+            match baseline.files.get(path) {
+                None => diff.added.push(path.clone()),
+                Some(recorded) if recorded.sha256 != file.sha256 => {
+                    diff.modified.push(path.clone())
+                }
+                Some(_) => diff.unchanged += 1,
+            }
+        }
+        diff.removed = baseline
+            .files
+            .keys()
+            .filter(|path| !current.contains_key(*path))
+            .cloned()
+            .collect();
+        Ok(diff)
+    }
+
+    /// Records the files under `root`, except the baseline file itself. The digest of a file
+    /// that matches the size and modification time in `previous` is reused.
+    fn snapshot_files(
+        &self,
+        root: &Path,
+        baseline_path: &Path,
+        walk_options: WalkOptions,
+        allowed_directories: Arc<Vec<PathBuf>>,
+        previous: Option<&BTreeMap<PathBuf, BaselineFile>>,
+    ) -> ServiceResult<BTreeMap<PathBuf, BaselineFile>> {
+        let baseline_path = normalize_path(baseline_path);
+        let files: Vec<(PathBuf, PathBuf, Metadata)> = self
+            .configure_walker(
+                &mut WalkBuilder::new(root),
+                walk_options,
+                allowed_directories,
+            )
+            .build()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_some_and(|ft| ft.is_file()))
+            .filter(|entry| normalize_path(entry.path()) != baseline_path)
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                let relative = entry.path().strip_prefix(root).ok()?.to_path_buf();
+                Some((entry.path().to_path_buf(), relative, metadata))
+            })
+            .collect();
+
+        Ok(files
+            .into_par_iter()
+            .filter_map(|(path, relative, metadata)| {
+                let size = metadata.len();
+                let mtime = mtime_nanos(&metadata);
+                let sha256 = match previous.and_then(|previous| previous.get(&relative)) {
+                    Some(recorded) if recorded.size == size && recorded.mtime == mtime => {
+                        recorded.sha256.clone()
+                    }
+                    // files that cannot be read are left out
+                    _ => sha256_file_hex(&path).ok()?,
+                };
+                Some((
+                    relative,
+                    BaselineFile {
+                        size,
+                        mtime,
+                        sha256,
+                    },
+                ))
+            })
+            .collect())
+    }
+}
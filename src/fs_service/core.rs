@@ -1,19 +1,160 @@
 use crate::{
     error::{ServiceError, ServiceResult},
-    fs_service::utils::{contains_symlink, expand_home, normalize_path, parse_file_path},
+    fs_service::{
+        DEFAULT_AST_MAX_FILE_SIZE, DEFAULT_AST_MAX_FILES, DEFAULT_READ_MAX_BYTES,
+        index::ContentIndexCache,
+        journal::Journal,
+        search::AstCache,
+        trash::is_in_trash,
+        utils::{contains_symlink, expand_home, normalize_path, parse_file_path, resolve_path},
+        watch::FileWatcher,
+    },
 };
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
 use std::{
     collections::HashSet,
     env,
     path::{Path, PathBuf},
-    sync::Arc,
+    str::FromStr,
+    sync::{Arc, Mutex},
 };
 use tokio::sync::RwLock;
 
 type PathResultList = Vec<Result<PathBuf, ServiceError>>;
 
+/// Access mode of an allowed directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirectoryMode {
+    ReadOnly,
+    ReadWrite,
+}
+
+impl DirectoryMode {
+    /// Returns the short name used on the command line, `ro` or `rw`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ReadOnly => "ro",
+            Self::ReadWrite => "rw",
+        }
+    }
+}
+
+impl FromStr for DirectoryMode {
+    type Err = ServiceError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "ro" => Ok(Self::ReadOnly),
+            "rw" => Ok(Self::ReadWrite),
+            _ => Err(ServiceError::FromString(format!(
+                "Invalid directory mode '{value}', expected 'ro' or 'rw'."
+            ))),
+        }
+    }
+}
+
+/// Which entries a directory walk skips.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalkOptions {
+    /// Skip paths ignored by `.gitignore`, `.ignore`, the global gitignore and `.git/info/exclude`.
+    pub respect_gitignore: bool,
+    /// Walk hidden files and directories (dotfiles).
+    pub include_hidden: bool,
+    /// Follow symlinks whose targets are inside the allowed directories.
+    pub follow_symlinks: bool,
+    /// Maximum number of levels below the root to walk, `None` for the walk's own limit.
+    pub max_depth: Option<usize>,
+    /// Number of threads of parallel walks, `None` for the server setting and 0 for one per core.
+    pub threads: Option<usize>,
+    /// Run parallel walks on a single thread, `None` for the server setting.
+    pub low_priority: Option<bool>,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        Self {
+            respect_gitignore: true,
+            include_hidden: false,
+            follow_symlinks: false,
+            max_depth: None,
+            threads: None,
+            low_priority: None,
+        }
+    }
+}
+
+impl WalkOptions {
+    /// Creates the options from optional tool arguments, using the defaults for missing ones.
+    pub fn new(
+        respect_gitignore: Option<bool>,
+        include_hidden: Option<bool>,
+        follow_symlinks: Option<bool>,
+    ) -> Self {
+        let defaults = Self::default();
+        Self {
+            respect_gitignore: respect_gitignore.unwrap_or(defaults.respect_gitignore),
+            include_hidden: include_hidden.unwrap_or(defaults.include_hidden),
+            follow_symlinks: follow_symlinks.unwrap_or(defaults.follow_symlinks),
+            max_depth: defaults.max_depth,
+            threads: defaults.threads,
+            low_priority: defaults.low_priority,
+        }
+    }
+
+    /// Limits the walk to `max_depth` levels below the root, 1 walks only its direct children.
+    pub fn with_max_depth(self, max_depth: Option<usize>) -> Self {
+        Self { max_depth, ..self }
+    }
+
+    /// Overrides the server's thread count and low-priority setting for parallel walks.
+    pub fn with_threads(self, threads: Option<usize>, low_priority: Option<bool>) -> Self {
+        Self {
+            threads,
+            low_priority,
+            ..self
+        }
+    }
+}
+
 pub struct FileSystemService {
     allowed_path: RwLock<Arc<Vec<PathBuf>>>,
+    /// Refuse every modification, whatever the modes of the directories.
+    read_only: bool,
+    /// Modes of directories configured explicitly; the innermost matching directory wins.
+    directory_modes: Vec<(PathBuf, DirectoryMode)>,
+    /// Mode of allowed directories without an explicit mode, including client roots.
+    default_mode: DirectoryMode,
+    /// Paths that can be neither read nor written, even inside allowed directories.
+    deny_globs: Option<Arc<GlobSet>>,
+    /// The deny list patterns as configured, for reporting.
+    deny_patterns: Vec<String>,
+    index_dir: Option<PathBuf>,
+    /// Content indexes read from `index_dir`, reused until their file changes.
+    content_index_cache: ContentIndexCache,
+    file_watcher: FileWatcher,
+    /// Parse trees of AST searches, shared with the watcher so that observed changes drop them.
+    ast_cache: Arc<AstCache>,
+    /// Move deleted files to the trash of their allowed directory instead of unlinking them.
+    use_trash: bool,
+    /// Serializes updates of the trash manifests.
+    pub(crate) trash_lock: tokio::sync::Mutex<()>,
+    /// Journal of mutating operations, if enabled.
+    journal: Option<Journal>,
+    /// Write files through a staging file renamed over the target.
+    atomic_writes: bool,
+    /// Directory where the previous content of every written or edited file is backed up.
+    backup_dir: Option<PathBuf>,
+    /// Default maximum number of bytes returned by `read_text_file`.
+    read_max_bytes: usize,
+    /// Default number of threads of parallel searches, 0 for one per core.
+    search_threads: usize,
+    /// Run parallel searches on a single thread unless a call asks otherwise.
+    low_priority_search: bool,
+    /// Default size in bytes above which AST searches skip files.
+    ast_max_file_size: u64,
+    /// Default maximum number of files parsed by an AST search.
+    ast_max_files: usize,
 }
 
 impl FileSystemService {
@@ -30,11 +171,293 @@ impl FileSystemService {
             })
             .collect();
 
+        let ast_cache = Arc::new(AstCache::default());
         Ok(Self {
             allowed_path: RwLock::new(Arc::new(normalized_dirs)),
+            read_only: false,
+            directory_modes: vec![],
+            default_mode: DirectoryMode::ReadWrite,
+            deny_globs: None,
+            deny_patterns: vec![],
+            index_dir: dirs::cache_dir().map(|dir| dir.join(env!("CARGO_PKG_NAME")).join("index")),
+            content_index_cache: ContentIndexCache::default(),
+            file_watcher: FileWatcher::new(ast_cache.clone()),
+            ast_cache,
+            use_trash: false,
+            trash_lock: tokio::sync::Mutex::new(()),
+            journal: None,
+            atomic_writes: true,
+            backup_dir: None,
+            read_max_bytes: DEFAULT_READ_MAX_BYTES,
+            search_threads: 0,
+            low_priority_search: false,
+            ast_max_file_size: DEFAULT_AST_MAX_FILE_SIZE,
+            ast_max_files: DEFAULT_AST_MAX_FILES,
         })
     }
 
+    /// Moves deleted files and directories to a trash directory from which they can be restored,
+    /// instead of unlinking them.
+    pub fn with_trash(mut self, use_trash: bool) -> Self {
+        self.use_trash = use_trash;
+        self
+    }
+
+    pub fn uses_trash(&self) -> bool {
+        self.use_trash
+    }
+
+    /// Records mutating operations so that they can be undone, keeping the previous contents of
+    /// overwritten and deleted files in `backup_dir`.
+    pub fn with_journal(mut self, backup_dir: PathBuf) -> Self {
+        self.journal = Some(Journal::new(backup_dir));
+        self
+    }
+
+    pub(crate) fn journal(&self) -> Option<&Journal> {
+        self.journal.as_ref()
+    }
+
+    /// Writes files in place instead of renaming a staging file over them, for filesystems that
+    /// do not support atomic renames. Enabled by default.
+    pub fn with_atomic_writes(mut self, atomic_writes: bool) -> Self {
+        self.atomic_writes = atomic_writes;
+        self
+    }
+
+    pub fn atomic_writes(&self) -> bool {
+        self.atomic_writes
+    }
+
+    /// Backs up the previous content of every file written or edited by tools to `backup_dir`,
+    /// mirroring the absolute path of the file.
+    pub fn with_backup_dir(mut self, backup_dir: PathBuf) -> Self {
+        self.backup_dir = Some(expand_home(backup_dir));
+        self
+    }
+
+    pub fn backup_dir(&self) -> Option<&Path> {
+        self.backup_dir.as_deref()
+    }
+
+    /// Sets how many bytes `read_text_file` returns at most, unless a call sets its own limit.
+    pub fn with_read_max_bytes(mut self, read_max_bytes: usize) -> Self {
+        self.read_max_bytes = read_max_bytes;
+        self
+    }
+
+    pub fn read_max_bytes(&self) -> usize {
+        self.read_max_bytes
+    }
+
+    /// Sets how many threads parallel searches use, 0 for one per core, and whether they run on
+    /// a single thread to leave the host's cores to other work. Calls can override both.
+    pub fn with_search_threads(mut self, search_threads: usize, low_priority: bool) -> Self {
+        self.search_threads = search_threads;
+        self.low_priority_search = low_priority;
+        self
+    }
+
+    /// Number of threads of a parallel walk with `options`, 0 for one per core.
+    pub fn search_threads(&self, options: WalkOptions) -> usize {
+        if options.low_priority.unwrap_or(self.low_priority_search) {
+            return 1;
+        }
+        options.threads.unwrap_or(self.search_threads)
+    }
+
+    /// Sets the size in bytes above which AST searches skip files and the number of files after
+    /// which they stop, unless a call sets its own limits.
+    pub fn with_ast_limits(mut self, max_file_size: u64, max_files: usize) -> Self {
+        self.ast_max_file_size = max_file_size;
+        self.ast_max_files = max_files;
+        self
+    }
+
+    pub fn ast_max_file_size(&self) -> u64 {
+        self.ast_max_file_size
+    }
+
+    pub fn ast_max_files(&self) -> usize {
+        self.ast_max_files
+    }
+
+    /// Sets the directory where persistent content-search indexes are stored.
+    pub fn with_index_dir(mut self, index_dir: PathBuf) -> Self {
+        self.index_dir = Some(expand_home(index_dir));
+        self
+    }
+
+    /// Sets the access mode of allowed directories. Directories listed in `modes` use their own
+    /// mode, all others (including roots provided later by the client) use `default_mode`.
+    pub fn with_directory_modes(
+        mut self,
+        default_mode: DirectoryMode,
+        modes: Vec<(String, DirectoryMode)>,
+    ) -> Self {
+        self.default_mode = default_mode;
+        self.directory_modes = modes
+            .into_iter()
+            .map(|(dir, mode)| (normalize_path(&expand_home(dir.into())), mode))
+            .collect();
+        self
+    }
+
+    /// Denies access to every path matching one of `patterns`. Patterns that are neither absolute
+    /// nor start with `**` match at any depth, e.g. `secrets/**` behaves like `**/secrets/**`.
+    pub fn with_deny_globs(mut self, patterns: &[String]) -> ServiceResult<Self> {
+        let mut builder = GlobSetBuilder::new();
+        let mut deny_patterns = vec![];
+        for pattern in patterns.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+            let normalized = if pattern.starts_with('/') || pattern.starts_with("**") {
+                pattern.to_string()
+            } else {
+                format!("**/{pattern}")
+            };
+            let glob = GlobBuilder::new(&normalized)
+                .literal_separator(true)
+                .build()
+                .map_err(|err| {
+                    ServiceError::FromString(format!("Invalid deny glob '{pattern}': {err}"))
+                })?;
+            builder.add(glob);
+            deny_patterns.push(pattern.to_string());
+        }
+        let deny_globs = builder.build().map_err(|err| {
+            ServiceError::FromString(format!("Failed to build deny glob patterns: {err}"))
+        })?;
+        self.deny_globs = (!deny_globs.is_empty()).then(|| Arc::new(deny_globs));
+        self.deny_patterns = deny_patterns;
+        Ok(self)
+    }
+
+    /// Returns the patterns of the deny list.
+    pub fn deny_patterns(&self) -> &[String] {
+        &self.deny_patterns
+    }
+
+    /// Returns the compiled deny list, if any.
+    pub(crate) fn deny_globs(&self) -> Option<Arc<GlobSet>> {
+        self.deny_globs.clone()
+    }
+
+    /// Returns true if `path` matches the deny list.
+    pub fn is_denied(&self, path: &Path) -> bool {
+        self.deny_globs
+            .as_ref()
+            .is_some_and(|deny_globs| deny_globs.is_match(path))
+    }
+
+    /// Applies `options` to a directory walk and prunes paths matching the deny list.
+    ///
+    /// When symlinks are followed, links whose targets are outside `allowed_directories` are
+    /// pruned, and each linked directory is walked only once, which also breaks cycles.
+    pub(crate) fn configure_walker<'a>(
+        &self,
+        builder: &'a mut WalkBuilder,
+        options: WalkOptions,
+        allowed_directories: Arc<Vec<PathBuf>>,
+    ) -> &'a mut WalkBuilder {
+        builder
+            .git_ignore(options.respect_gitignore)
+            .git_global(options.respect_gitignore)
+            .git_exclude(options.respect_gitignore)
+            .ignore(options.respect_gitignore)
+            .parents(options.respect_gitignore)
+            .hidden(!options.include_hidden)
+            .follow_links(options.follow_symlinks)
+            .threads(self.search_threads(options));
+        if options.max_depth.is_some() {
+            builder.max_depth(options.max_depth);
+        }
+        if !options.follow_symlinks {
+            return self.exclude_denied(builder);
+        }
+
+        // a walk builder has a single entry filter, so the deny list is checked here as well
+        let deny_globs = self.deny_globs.clone();
+        let visited = Arc::new(Mutex::new(HashSet::new()));
+        builder.filter_entry(move |entry| {
+            if deny_globs
+                .as_ref()
+                .is_some_and(|deny_globs| deny_globs.is_match(entry.path()))
+            {
+                return false;
+            }
+            if !entry.path_is_symlink() {
+                return true;
+            }
+            let Some(target) = symlink_target_within(entry.path(), &allowed_directories) else {
+                return false;
+            };
+            if !target.is_dir() {
+                return true;
+            }
+            directory_identity(&target).is_some_and(|identity| {
+                visited
+                    .lock()
+                    .is_ok_and(|mut visited| visited.insert(identity))
+            })
+        })
+    }
+
+    /// Prunes paths matching the deny list from a directory walk.
+    pub(crate) fn exclude_denied<'a>(&self, builder: &'a mut WalkBuilder) -> &'a mut WalkBuilder {
+        if let Some(deny_globs) = self.deny_globs.clone() {
+            builder.filter_entry(move |entry| !deny_globs.is_match(entry.path()));
+        }
+        builder
+    }
+
+    /// Returns the access mode that applies to `path`.
+    pub fn directory_mode(&self, path: &Path) -> DirectoryMode {
+        let normalized = normalize_path(path);
+        self.directory_modes
+            .iter()
+            .filter(|(dir, _)| normalized.starts_with(dir))
+            .max_by_key(|(dir, _)| dir.components().count())
+            .map_or(self.default_mode, |(_, mode)| *mode)
+    }
+
+    /// Makes the service refuse every modification when `read_only` is true, including in
+    /// directories configured as writable.
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Returns true if no modification is allowed, because the service is read-only or no
+    /// directory is writable.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only || !self.has_writable_directories()
+    }
+
+    /// Fails with [`ServiceError::NoWriteAccess`] if the service is read-only. Checked by every
+    /// method that modifies files, in addition to the modes of the paths involved.
+    pub(crate) fn ensure_writable(&self) -> ServiceResult<()> {
+        if self.is_read_only() {
+            return Err(ServiceError::NoWriteAccess);
+        }
+        Ok(())
+    }
+
+    /// Returns true if at least one directory can be written to.
+    pub fn has_writable_directories(&self) -> bool {
+        self.default_mode == DirectoryMode::ReadWrite
+            || self
+                .directory_modes
+                .iter()
+                .any(|(_, mode)| *mode == DirectoryMode::ReadWrite)
+    }
+
+    pub fn index_dir(&self) -> Option<&Path> {
+        self.index_dir.as_deref()
+    }
+
+    pub(crate) fn file_watcher(&self) -> &FileWatcher {
+        &self.file_watcher
+    }
+
     pub async fn allowed_directories(&self) -> Arc<Vec<PathBuf>> {
         let guard = self.allowed_path.read().await;
         guard.clone()
@@ -42,7 +465,15 @@ impl FileSystemService {
 
     pub async fn update_allowed_paths(&self, valid_roots: Vec<PathBuf>) {
         let mut guard = self.allowed_path.write().await;
-        *guard = Arc::new(valid_roots)
+        *guard = Arc::new(valid_roots);
+    }
+
+    pub(crate) fn ast_cache(&self) -> &AstCache {
+        &self.ast_cache
+    }
+
+    pub(crate) fn content_index_cache(&self) -> &ContentIndexCache {
+        &self.content_index_cache
     }
 
     pub fn validate_path(
@@ -66,8 +497,8 @@ impl FileSystemService {
             env::current_dir().unwrap().join(&expanded_path)
         };
 
-        // Normalize the path
-        let normalized_requested = normalize_path(&absolute_path);
+        // Resolve the path, through symlinked directories even if the path does not exist yet
+        let normalized_requested = resolve_path(&absolute_path);
 
         // Check if path is within allowed directories
         if !allowed_directories.iter().any(|dir| {
@@ -80,8 +511,8 @@ impl FileSystemService {
             } else {
                 "path"
             };
-            return Err(ServiceError::FromString(format!(
-                "Access denied - {} is outside allowed directories: {} not in {}",
+            return Err(ServiceError::PathDenied(format!(
+                "{} is outside allowed directories: {} not in {}",
                 symlink_target,
                 absolute_path.display(),
                 allowed_directories
@@ -92,9 +523,38 @@ impl FileSystemService {
             )));
         }
 
+        if self.is_denied(&normalized_requested) {
+            return Err(ServiceError::PathDenied(format!(
+                "{} matches a denied path pattern.",
+                absolute_path.display()
+            )));
+        }
+
         Ok(absolute_path)
     }
 
+    /// Validates a path that is about to be modified: in addition to [`Self::validate_path`],
+    /// the directory containing it must not be read-only, and the path must not be inside a
+    /// trash directory, which only the trash operations modify.
+    pub fn validate_write_path(
+        &self,
+        requested_path: &Path,
+        allowed_directories: Arc<Vec<PathBuf>>,
+    ) -> ServiceResult<PathBuf> {
+        self.ensure_writable()?;
+        let valid_path = self.validate_path(requested_path, allowed_directories)?;
+        if is_in_trash(&valid_path) {
+            return Err(ServiceError::PathDenied(format!(
+                "{} is inside the trash, which is managed by the server.",
+                valid_path.display()
+            )));
+        }
+        if self.directory_mode(&valid_path) == DirectoryMode::ReadOnly {
+            return Err(ServiceError::ReadOnlyPath(valid_path.display().to_string()));
+        }
+        Ok(valid_path)
+    }
+
     pub fn valid_roots(&self, roots: Vec<&str>) -> ServiceResult<(Vec<PathBuf>, Option<String>)> {
         let paths: Vec<Result<PathBuf, ServiceError>> =
             roots.iter().map(|p| parse_file_path(p)).collect::<Vec<_>>();
@@ -138,3 +598,62 @@ fn fix_dockerhub_mcp_registry_gateway(input: &String) -> &str {
         input
     }
 }
+
+/// Resolves the symlink at `path`, returning its target if it is inside the allowed directories.
+pub(crate) fn symlink_target_within(
+    path: &Path,
+    allowed_directories: &[PathBuf],
+) -> Option<PathBuf> {
+    let target = path.canonicalize().ok()?;
+    allowed_directories
+        .iter()
+        .any(|dir| target.starts_with(dir) || target.starts_with(normalize_path(dir)))
+        .then_some(target)
+}
+
+/// Identity of a directory independent of the path it is reached by: its device and inode.
+#[cfg(unix)]
+pub(crate) type DirectoryIdentity = (u64, u64);
+#[cfg(not(unix))]
+pub(crate) type DirectoryIdentity = PathBuf;
+
+#[cfg(unix)]
+pub(crate) fn directory_identity(path: &Path) -> Option<DirectoryIdentity> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn directory_identity(path: &Path) -> Option<DirectoryIdentity> {
+    path.canonicalize().ok()
+}
+
+/// Returns true if the symlink at `path` leads to a directory that is not in `visited` yet,
+/// recording it as visited.
+pub(crate) fn first_visit_of_linked_dir(
+    path: &Path,
+    visited: &mut HashSet<DirectoryIdentity>,
+) -> bool {
+    path.is_dir() && directory_identity(path).is_some_and(|identity| visited.insert(identity))
+}
+
+/// Identity of a file shared by all of its hard links.
+#[cfg(unix)]
+pub(crate) type FileIdentity = (u64, u64);
+/// Identity of a file shared by all of its hard links: its volume serial number and file index.
+/// The handle keeps the file open, so identities should only be held for small batches of files.
+#[cfg(not(unix))]
+pub(crate) type FileIdentity = same_file::Handle;
+
+#[cfg(unix)]
+pub(crate) fn file_identity(path: &Path) -> Option<FileIdentity> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn file_identity(path: &Path) -> Option<FileIdentity> {
+    same_file::Handle::from_path(path).ok()
+}
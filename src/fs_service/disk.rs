@@ -0,0 +1,153 @@
+use crate::{error::ServiceResult, fs_service::FileSystemService};
+use std::path::Path;
+
+/// Capacity of the filesystem holding a directory.
+#[derive(::serde::Serialize, Debug, Clone, PartialEq)]
+pub struct DiskSpace {
+    pub total_bytes: u64,
+    pub free_bytes: u64,
+    /// Free bytes usable by the server, without the blocks reserved for privileged users.
+    pub available_bytes: u64,
+    /// Filesystem type, e.g. `ext4`, `apfs` or `NTFS`, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filesystem: Option<String>,
+}
+
+#[cfg(unix)]
+mod sys {
+    use super::DiskSpace;
+    use rustix::fs::statvfs;
+    use std::{io, path::Path};
+
+    pub(super) fn disk_space(path: &Path) -> io::Result<DiskSpace> {
+        let stat = statvfs(path)?;
+        let block_size = stat.f_frsize as u64;
+        Ok(DiskSpace {
+            total_bytes: stat.f_blocks as u64 * block_size,
+            free_bytes: stat.f_bfree as u64 * block_size,
+            available_bytes: stat.f_bavail as u64 * block_size,
+            filesystem: filesystem_type(path),
+        })
+    }
+
+    /// Names the filesystem from the magic number reported by `statfs`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn filesystem_type(path: &Path) -> Option<String> {
+        let name = match rustix::fs::statfs(path).ok()?.f_type as u32 {
+            0xEF53 => "ext4",
+            0x58465342 => "xfs",
+            0x9123683E => "btrfs",
+            0x2FC12FC1 => "zfs",
+            0x01021994 => "tmpfs",
+            0x794C7630 => "overlayfs",
+            0x6969 => "nfs",
+            0xFF534D42 => "cifs",
+            0xFE534D42 => "smb2",
+            0x65735546 => "fuse",
+            0x4D44 => "vfat",
+            0x2011BAB0 => "exfat",
+            0x5346544E => "ntfs",
+            0x9FA0 => "proc",
+            0x62656572 => "sysfs",
+            0x858458F6 => "ramfs",
+            0xF2F52010 => "f2fs",
+            0x73717368 => "squashfs",
+            0x01021997 => "9p",
+            _ => return None,
+        };
+        Some(name.to_string())
+    }
+
+    #[cfg(target_os = "macos")]
+    fn filesystem_type(path: &Path) -> Option<String> {
+        let stat = rustix::fs::statfs(path).ok()?;
+        let name: Vec<u8> = stat
+            .f_fstypename
+            .iter()
+            .take_while(|byte| **byte != 0)
+            .map(|byte| *byte as u8)
+            .collect();
+        Some(String::from_utf8_lossy(&name).into_owned())
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos")))]
+    fn filesystem_type(_path: &Path) -> Option<String> {
+        None
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use super::DiskSpace;
+    use std::{io, os::windows::ffi::OsStrExt, path::Path, ptr};
+    use windows_sys::Win32::Storage::FileSystem::{
+        GetDiskFreeSpaceExW, GetVolumeInformationW, GetVolumePathNameW,
+    };
+
+    fn wide(path: &Path) -> Vec<u16> {
+        path.as_os_str().encode_wide().chain([0]).collect()
+    }
+
+    pub(super) fn disk_space(path: &Path) -> io::Result<DiskSpace> {
+        let path = wide(path);
+        let (mut available, mut total, mut free) = (0u64, 0u64, 0u64);
+        // SAFETY: `path` is NUL-terminated and the out pointers are valid for writes.
+        if unsafe { GetDiskFreeSpaceExW(path.as_ptr(), &mut available, &mut total, &mut free) } == 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(DiskSpace {
+            total_bytes: total,
+            free_bytes: free,
+            available_bytes: available,
+            filesystem: filesystem_type(&path),
+        })
+    }
+
+    /// Returns the filesystem name of the volume holding the NUL-terminated `path`.
+    fn filesystem_type(path: &[u16]) -> Option<String> {
+        let mut volume = [0u16; 261];
+        let mut name = [0u16; 261];
+        // SAFETY: the buffers are valid for writes of the lengths given.
+        let found = unsafe {
+            GetVolumePathNameW(path.as_ptr(), volume.as_mut_ptr(), volume.len() as u32) != 0
+                && GetVolumeInformationW(
+                    volume.as_ptr(),
+                    ptr::null_mut(),
+                    0,
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                    name.as_mut_ptr(),
+                    name.len() as u32,
+                ) != 0
+        };
+        let len = name.iter().position(|c| *c == 0).unwrap_or(name.len());
+        found.then(|| String::from_utf16_lossy(&name[..len]))
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod sys {
+    use super::DiskSpace;
+    use std::{io, path::Path};
+
+    pub(super) fn disk_space(_path: &Path) -> io::Result<DiskSpace> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "disk space is not supported on this platform",
+        ))
+    }
+}
+
+impl FileSystemService {
+    /// Reports the size, free space and type of the filesystem holding `path`.
+    pub async fn disk_space(&self, path: &Path) -> ServiceResult<DiskSpace> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(path, allowed_directories)?;
+        let space = tokio::task::spawn_blocking(move || sys::disk_space(&valid_path))
+            .await
+            .map_err(|e| std::io::Error::other(e.to_string()))??;
+        Ok(space)
+    }
+}
@@ -0,0 +1,388 @@
+use crate::{
+    error::{ServiceError, ServiceResult},
+    fs_service::FileSystemService,
+};
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+use tokio::process::Command;
+
+/// Default number of commits returned by [`FileSystemService::git_log`].
+pub const DEFAULT_GIT_LOG_MAX_COUNT: usize = 20;
+
+/// Default size budget for the diff returned by [`FileSystemService::git_diff`].
+pub const DEFAULT_GIT_DIFF_MAX_BYTES: usize = 64 * 1024;
+
+/// Change summary of a single file in a [`GitDiffResult`].
+#[derive(::serde::Serialize, Debug, Clone, PartialEq)]
+pub struct GitDiffFile {
+    /// Path relative to the repository root, as reported by git.
+    pub path: String,
+    /// Number of added lines, `None` for binary files.
+    pub additions: Option<u64>,
+    /// Number of deleted lines, `None` for binary files.
+    pub deletions: Option<u64>,
+    /// True for untracked files, which have no diff.
+    pub untracked: bool,
+}
+
+/// Working tree or index changes returned by [`FileSystemService::git_diff`].
+#[derive(::serde::Serialize, Debug)]
+pub struct GitDiffResult {
+    pub repository: PathBuf,
+    pub files: Vec<GitDiffFile>,
+    /// Unified diff, limited to the requested size budget.
+    pub diff: String,
+    /// Files whose diff was left out because of the size budget.
+    pub omitted_files: Vec<String>,
+}
+
+/// Blame information of a single line returned by [`FileSystemService::git_blame`].
+#[derive(::serde::Serialize, Debug, Clone)]
+pub struct GitBlameLine {
+    /// 1-based line number in the current version of the file.
+    pub line: u64,
+    /// Full hash of the commit that last changed the line (all zeros for uncommitted lines).
+    pub commit: String,
+    pub author: String,
+    pub author_email: String,
+    /// Author date, formatted as `YYYY-MM-DD HH:MM:SS` in UTC.
+    pub date: String,
+    /// First line of the commit message.
+    pub summary: String,
+    pub content: String,
+}
+
+/// A commit returned by [`FileSystemService::git_log`].
+#[derive(::serde::Serialize, Debug, Clone)]
+pub struct GitCommit {
+    pub hash: String,
+    pub author: String,
+    pub author_email: String,
+    /// Author date, formatted as `YYYY-MM-DD HH:MM:SS` in UTC.
+    pub date: String,
+    pub subject: String,
+}
+
+/// Formats a unix timestamp reported by git as `YYYY-MM-DD HH:MM:SS` (UTC).
+fn format_git_time(seconds: &str) -> String {
+    seconds
+        .parse()
+        .ok()
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+        .map(|date| date.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+/// Settings forced on every git invocation. The repository config can be written by clients,
+/// so anything in it that makes git run a command must be overridden.
+const GIT_SAFE_CONFIG: [&str; 4] = [
+    "core.quotepath=off",
+    "core.fsmonitor=false",
+    "core.hooksPath=/dev/null",
+    "diff.external=",
+];
+
+/// Runs git with `args` in `dir` and returns its standard output.
+async fn run_git<I, S>(dir: &Path, args: I) -> ServiceResult<String>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<OsString>,
+{
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(GIT_SAFE_CONFIG.iter().flat_map(|setting| ["-c", setting]))
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .args(args.into_iter().map(Into::into))
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|err| ServiceError::FromString(format!("Unable to run git: {err}")))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ServiceError::FromString(format!(
+            "git command failed: {}",
+            stderr.trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Splits a unified diff into per-file chunks, each starting with `diff --git`.
+fn split_diff_by_file(diff: &str) -> Vec<&str> {
+    let mut starts: Vec<usize> = diff
+        .match_indices("diff --git ")
+        .filter(|(index, _)| *index == 0 || diff.as_bytes()[index - 1] == b'\n')
+        .map(|(index, _)| index)
+        .collect();
+    if starts.first() != Some(&0) {
+        starts.insert(0, 0);
+    }
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, start)| &diff[*start..starts.get(i + 1).copied().unwrap_or(diff.len())])
+        .filter(|chunk| !chunk.is_empty())
+        .collect()
+}
+
+/// Returns the file path of a `diff --git a/... b/...` chunk.
+fn chunk_path(chunk: &str) -> String {
+    let header = chunk.lines().next().unwrap_or_default();
+    header
+        .rsplit_once(" b/")
+        .map_or(header, |(_, path)| path)
+        .to_string()
+}
+
+/// Parses the output of `git blame --line-porcelain`.
+fn parse_line_porcelain(output: &str) -> Vec<GitBlameLine> {
+    let mut lines = Vec::new();
+    let mut current: Option<GitBlameLine> = None;
+    for row in output.lines() {
+        if let Some(content) = row.strip_prefix('\t') {
+            if let Some(mut line) = current.take() {
+                line.content = content.to_string();
+                lines.push(line);
+            }
+            continue;
+        }
+        let Some(blame_line) = current.as_mut() else {
+            // header: <commit> <original line> <final line> [<group size>]
+            let mut parts = row.split(' ');
+            let commit = parts.next().unwrap_or_default().to_string();
+            let line = parts
+                .nth(1)
+                .and_then(|n| n.parse().ok())
+                .unwrap_or_default();
+            current = Some(GitBlameLine {
+                line,
+                commit,
+                author: String::new(),
+                author_email: String::new(),
+                date: String::new(),
+                summary: String::new(),
+                content: String::new(),
+            });
+            continue;
+        };
+        let (key, value) = row.split_once(' ').unwrap_or((row, ""));
+        match key {
+            "author" => blame_line.author = value.to_string(),
+            "author-mail" => {
+                blame_line.author_email = value.trim_matches(['<', '>']).to_string();
+            }
+            "author-time" => blame_line.date = format_git_time(value),
+            "summary" => blame_line.summary = value.to_string(),
+            _ => {}
+        }
+    }
+    lines
+}
+
+impl FileSystemService {
+    /// Returns the root of the git repository containing `path`.
+    async fn git_repository_root(&self, path: &Path) -> ServiceResult<PathBuf> {
+        let dir = if path.is_dir() {
+            path
+        } else {
+            path.parent().unwrap_or(path)
+        };
+        let root = run_git(dir, ["rev-parse", "--show-toplevel"]).await?;
+        Ok(PathBuf::from(root.trim()))
+    }
+
+    /// Validates `paths` and returns the repository root they belong to.
+    async fn git_validate_paths(
+        &self,
+        paths: &[PathBuf],
+    ) -> ServiceResult<(PathBuf, Vec<PathBuf>)> {
+        if paths.is_empty() {
+            return Err(ServiceError::InvalidArgument(
+                "At least one path is required.".to_string(),
+            ));
+        }
+        let allowed_directories = self.allowed_directories().await;
+        let valid_paths = paths
+            .iter()
+            .map(|path| self.validate_path(path, allowed_directories.clone()))
+            .collect::<ServiceResult<Vec<_>>>()?;
+        let repository = self.git_repository_root(&valid_paths[0]).await?;
+        Ok((repository, valid_paths))
+    }
+
+    /// Returns the unstaged (or, with `staged`, the staged) changes of `paths` as a unified diff,
+    /// together with per-file line counts. Untracked files are listed in the summary.
+    ///
+    /// The diff is limited to `max_bytes` (64KB by default); files whose diff does not fit in the
+    /// remaining budget are left out and reported in `omitted_files`.
+    pub async fn git_diff(
+        &self,
+        paths: &[PathBuf],
+        staged: bool,
+        max_bytes: Option<usize>,
+    ) -> ServiceResult<GitDiffResult> {
+        let (repository, valid_paths) = self.git_validate_paths(paths).await?;
+        let max_bytes = max_bytes.unwrap_or(DEFAULT_GIT_DIFF_MAX_BYTES);
+
+        let mut diff_args: Vec<OsString> = vec![
+            "diff".into(),
+            "--no-color".into(),
+            "--no-ext-diff".into(),
+            "--no-textconv".into(),
+        ];
+        if staged {
+            diff_args.push("--cached".into());
+        }
+
+        let mut numstat_args = diff_args.clone();
+        numstat_args.extend(["--numstat".into(), "--".into()]);
+        numstat_args.extend(valid_paths.iter().map(|p| p.as_os_str().to_owned()));
+        let numstat = run_git(&repository, numstat_args).await?;
+
+        let mut files: Vec<GitDiffFile> = numstat
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(3, '\t');
+                let additions = parts.next()?;
+                let deletions = parts.next()?;
+                let path = parts.next()?;
+                Some(GitDiffFile {
+                    path: path.to_string(),
+                    additions: additions.parse().ok(),
+                    deletions: deletions.parse().ok(),
+                    untracked: false,
+                })
+            })
+            .filter(|file: &GitDiffFile| !self.is_denied(&repository.join(&file.path)))
+            .collect();
+
+        if !staged {
+            let mut untracked_args: Vec<OsString> = vec![
+                "ls-files".into(),
+                "--others".into(),
+                "--exclude-standard".into(),
+                "--".into(),
+            ];
+            untracked_args.extend(valid_paths.iter().map(|p| p.as_os_str().to_owned()));
+            let untracked = run_git(&repository, untracked_args).await?;
+            files.extend(
+                untracked
+                    .lines()
+                    .filter(|path| !self.is_denied(&repository.join(path)))
+                    .map(|path| GitDiffFile {
+                        path: path.to_string(),
+                        additions: None,
+                        deletions: None,
+                        untracked: true,
+                    }),
+            );
+        }
+
+        diff_args.push("--".into());
+        diff_args.extend(valid_paths.iter().map(|p| p.as_os_str().to_owned()));
+        let full_diff = run_git(&repository, diff_args).await?;
+
+        let mut diff = String::new();
+        let mut omitted_files = Vec::new();
+        for chunk in split_diff_by_file(&full_diff) {
+            // a directory may contain files the deny list hides
+            if self.is_denied(&repository.join(chunk_path(chunk))) {
+                continue;
+            }
+            if diff.len() + chunk.len() <= max_bytes {
+                diff.push_str(chunk);
+            } else {
+                omitted_files.push(chunk_path(chunk));
+            }
+        }
+
+        Ok(GitDiffResult {
+            repository,
+            files,
+            diff,
+            omitted_files,
+        })
+    }
+
+    /// Returns the commit, author and date that last changed each line of `path`, optionally
+    /// limited to the 1-based inclusive range `start_line..=end_line`.
+    pub async fn git_blame(
+        &self,
+        path: &Path,
+        start_line: Option<u64>,
+        end_line: Option<u64>,
+    ) -> ServiceResult<Vec<GitBlameLine>> {
+        let (repository, valid_paths) = self.git_validate_paths(&[path.to_path_buf()]).await?;
+        if !valid_paths[0].is_file() {
+            return Err(ServiceError::InvalidArgument(format!(
+                "'{}' is not a file.",
+                path.display()
+            )));
+        }
+
+        let mut args: Vec<OsString> = vec![
+            "blame".into(),
+            "--line-porcelain".into(),
+            "--no-textconv".into(),
+        ];
+        if start_line.is_some() || end_line.is_some() {
+            let start = start_line.unwrap_or(1).max(1);
+            let range = match end_line {
+                Some(end) if end < start => {
+                    return Err(ServiceError::InvalidArgument(format!(
+                        "Invalid line range: end line {end} is before start line {start}."
+                    )));
+                }
+                Some(end) => format!("{start},{end}"),
+                None => format!("{start},"),
+            };
+            args.extend(["-L".into(), range.into()]);
+        }
+        args.push("--".into());
+        args.push(valid_paths[0].as_os_str().to_owned());
+
+        let output = run_git(&repository, args).await?;
+        Ok(parse_line_porcelain(&output))
+    }
+
+    /// Returns the most recent commits (20 by default) that touched `path`, newest first.
+    /// Renames are followed when `path` is a file.
+    pub async fn git_log(
+        &self,
+        path: &Path,
+        max_count: Option<usize>,
+    ) -> ServiceResult<Vec<GitCommit>> {
+        let (repository, valid_paths) = self.git_validate_paths(&[path.to_path_buf()]).await?;
+        let max_count = max_count.unwrap_or(DEFAULT_GIT_LOG_MAX_COUNT);
+
+        let mut args: Vec<OsString> = vec![
+            "log".into(),
+            format!("--max-count={max_count}").into(),
+            "--format=%H%x1f%an%x1f%ae%x1f%at%x1f%s%x1e".into(),
+        ];
+        if valid_paths[0].is_file() {
+            args.push("--follow".into());
+        }
+        args.push("--".into());
+        args.push(valid_paths[0].as_os_str().to_owned());
+
+        let output = run_git(&repository, args).await?;
+        Ok(output
+            .split('\x1e')
+            .filter_map(|record| {
+                let mut fields = record.trim_start_matches('\n').split('\x1f');
+                Some(GitCommit {
+                    hash: fields.next().filter(|hash| !hash.is_empty())?.to_string(),
+                    author: fields.next()?.to_string(),
+                    author_email: fields.next()?.to_string(),
+                    date: format_git_time(fields.next()?),
+                    subject: fields.next()?.to_string(),
+                })
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,323 @@
+use crate::{
+    error::{ServiceError, ServiceResult},
+    fs_service::{FileSystemService, utils::normalize_path},
+};
+use ignore::WalkBuilder;
+use sha2::{Digest, Sha256};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fs::{self, Metadata},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+const INDEX_VERSION: u32 = 2;
+const MAX_INDEXED_FILE_SIZE: u64 = 1024 * 1024; // 1MB - larger files are always scanned directly
+
+/// A file recorded in a [`ContentIndex`], identified by its position in `ContentIndex::files`.
+#[derive(::serde::Deserialize, ::serde::Serialize, Debug, Clone)]
+pub struct IndexedFile {
+    /// Path relative to the indexed root.
+    pub path: PathBuf,
+    pub size: u64,
+    /// Modification time in nanoseconds since the Unix epoch.
+    pub mtime: u128,
+}
+
+/// A persistent trigram index over the text files of a directory.
+///
+/// Each trigram (three consecutive bytes of the lowercased content) maps to the sorted list of
+/// file ids that contain it. A literal query can only match files containing all of its trigrams,
+/// which lets content search skip reading every other file.
+#[derive(::serde::Deserialize, ::serde::Serialize, Debug)]
+pub struct ContentIndex {
+    pub version: u32,
+    pub root: PathBuf,
+    pub files: Vec<IndexedFile>,
+    pub trigrams: HashMap<u32, Vec<u32>>,
+}
+
+/// Summary of an index build returned by [`FileSystemService::build_content_index`].
+#[derive(Debug)]
+pub struct IndexSummary {
+    pub root: PathBuf,
+    pub index_path: PathBuf,
+    pub indexed_files: usize,
+    pub trigrams: usize,
+    pub elapsed_ms: u128,
+}
+
+/// Pre-computed view of a [`ContentIndex`] for a specific query, used by content search
+/// to decide which files can be skipped.
+#[derive(Debug)]
+pub struct IndexFilter {
+    root: PathBuf,
+    /// Files known to the index, keyed by relative path: (size, mtime, may_match)
+    files: HashMap<PathBuf, (u64, u128, bool)>,
+}
+
+impl IndexFilter {
+    /// Returns true if the index proves that `path` cannot contain the query.
+    /// Files that are unknown to the index or have changed since it was built are never skipped.
+    pub fn can_skip(&self, path: &Path, metadata: Option<&Metadata>) -> bool {
+        let Ok(relative_path) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        let Some((size, mtime, may_match)) = self.files.get(relative_path) else {
+            return false;
+        };
+        let Some(metadata) = metadata else {
+            return false;
+        };
+        !may_match && metadata.len() == *size && mtime_nanos(metadata) == Some(*mtime)
+    }
+}
+
+fn mtime_nanos(metadata: &Metadata) -> Option<u128> {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+}
+
+fn trigram_key(bytes: &[u8]) -> u32 {
+    (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32
+}
+
+/// Non-ASCII characters that case-insensitive matching folds to an ASCII letter.
+const ASCII_CASE_VARIANTS: [(&str, &[u8]); 2] = [("\u{17F}", b"s"), ("\u{212A}", b"k")];
+
+/// Replaces the characters of `ASCII_CASE_VARIANTS` with their ASCII letter, so that an ASCII
+/// query such as `kelvin` is not ruled out for content containing `\u{212A}elvin`.
+fn fold_case_variants(bytes: &[u8]) -> Cow<'_, [u8]> {
+    if bytes.is_ascii() {
+        return Cow::Borrowed(bytes);
+    }
+    let mut folded = Vec::with_capacity(bytes.len());
+    let mut rest = bytes;
+    'outer: while let Some((&first, tail)) = rest.split_first() {
+        for (variant, ascii) in ASCII_CASE_VARIANTS {
+            if let Some(after) = rest.strip_prefix(variant.as_bytes()) {
+                folded.extend_from_slice(ascii);
+                rest = after;
+                continue 'outer;
+            }
+        }
+        folded.push(first);
+        rest = tail;
+    }
+    Cow::Owned(folded)
+}
+
+/// Returns the set of lowercased trigrams contained in `bytes`.
+fn extract_trigrams(bytes: &[u8]) -> HashSet<u32> {
+    let lowercased = fold_case_variants(bytes).to_ascii_lowercase();
+    lowercased
+        .windows(3)
+        .filter(|w| !w.contains(&b'\n'))
+        .map(trigram_key)
+        .collect()
+}
+
+impl ContentIndex {
+    /// Builds a filter for a literal `query`, or `None` when the query cannot benefit from the index.
+    ///
+    /// Only ASCII queries are supported, since the index lowercases ASCII letters only while
+    /// content search matching is Unicode case-insensitive. The non-ASCII characters matched by
+    /// ASCII letters (`\u{17F}` and `\u{212A}`) are folded when the index is built.
+    pub fn filter_for_query(&self, query: &str) -> Option<IndexFilter> {
+        if !query.is_ascii() || query.contains('\n') {
+            return None;
+        }
+        let query_trigrams = extract_trigrams(query.as_bytes());
+        if query_trigrams.is_empty() {
+            return None;
+        }
+
+        // Intersect posting lists, starting from the rarest trigram
+        let mut postings: Vec<&Vec<u32>> = Vec::with_capacity(query_trigrams.len());
+        for trigram in &query_trigrams {
+            match self.trigrams.get(trigram) {
+                Some(list) => postings.push(list),
+                None => {
+                    postings.clear();
+                    break;
+                }
+            }
+        }
+        postings.sort_by_key(|list| list.len());
+
+        let mut candidates: HashSet<u32> = postings
+            .first()
+            .map(|list| list.iter().copied().collect())
+            .unwrap_or_default();
+        for list in postings.iter().skip(1) {
+            let other: HashSet<u32> = list.iter().copied().collect();
+            candidates.retain(|id| other.contains(id));
+        }
+
+        let files = self
+            .files
+            .iter()
+            .enumerate()
+            .map(|(id, file)| {
+                (
+                    file.path.clone(),
+                    (file.size, file.mtime, candidates.contains(&(id as u32))),
+                )
+            })
+            .collect();
+
+        Some(IndexFilter {
+            root: self.root.clone(),
+            files,
+        })
+    }
+}
+
+/// Content indexes loaded from disk, keyed by index file path along with the modification time
+/// of the file they were read from.
+#[derive(Default)]
+pub(crate) struct ContentIndexCache {
+    entries: Mutex<HashMap<PathBuf, (SystemTime, Arc<ContentIndex>)>>,
+}
+
+impl ContentIndexCache {
+    /// Returns the index stored at `index_path`, reading it only if it changed since it was last
+    /// loaded.
+    fn load(&self, index_path: &Path) -> Option<Arc<ContentIndex>> {
+        let modified = fs::metadata(index_path).and_then(|m| m.modified()).ok()?;
+        if let Ok(entries) = self.entries.lock()
+            && let Some((cached_modified, index)) = entries.get(index_path)
+            && *cached_modified == modified
+        {
+            return Some(index.clone());
+        }
+
+        let bytes = fs::read(index_path).ok()?;
+        let index: Arc<ContentIndex> = Arc::new(serde_json::from_slice(&bytes).ok()?);
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(index_path.to_path_buf(), (modified, index.clone()));
+        }
+        Some(index)
+    }
+
+    /// Forgets the index stored at `index_path`, to be called when it is rewritten.
+    fn invalidate(&self, index_path: &Path) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.remove(index_path);
+        }
+    }
+}
+
+impl FileSystemService {
+    /// Returns the path of the index file for the given (validated) root directory.
+    fn content_index_path(&self, root_path: &Path) -> ServiceResult<PathBuf> {
+        let index_dir = self.index_dir().ok_or(ServiceError::FromString(
+            "Content indexing is unavailable: no index directory is configured.".to_string(),
+        ))?;
+        let hash = Sha256::digest(root_path.to_string_lossy().as_bytes());
+        let file_name = hash.iter().map(|b| format!("{b:02x}")).collect::<String>();
+        Ok(index_dir.join(format!("{file_name}.json")))
+    }
+
+    /// Builds (or rebuilds) the persistent trigram index for `root_path`.
+    ///
+    /// Text files up to 1MB that are not excluded by gitignore rules are indexed. The index is
+    /// stored in the configured index directory and is picked up automatically by
+    /// `search_files_content` for searches within `root_path`.
+    pub async fn build_content_index(&self, root_path: &Path) -> ServiceResult<IndexSummary> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(root_path, allowed_directories)?;
+        let valid_path = normalize_path(&valid_path);
+        if !valid_path.is_dir() {
+            return Err(ServiceError::InvalidArgument(
+                "Root path must be a directory".into(),
+            ));
+        }
+        let index_path = self.content_index_path(&valid_path)?;
+
+        let started = Instant::now();
+        let root = valid_path.clone();
+        let mut walker = WalkBuilder::new(&root);
+        walker.follow_links(false);
+        let walk = self.exclude_denied(&mut walker).build();
+        let index = tokio::task::spawn_blocking(move || -> ServiceResult<ContentIndex> {
+            let mut files = Vec::new();
+            let mut trigrams: HashMap<u32, Vec<u32>> = HashMap::new();
+
+            for entry in walk
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_some_and(|ft| ft.is_file()))
+            {
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                if metadata.len() > MAX_INDEXED_FILE_SIZE {
+                    continue;
+                }
+                let Ok(content) = fs::read(entry.path()) else {
+                    continue;
+                };
+                // Skip binary files, consistent with content search binary detection
+                if content.contains(&0) {
+                    continue;
+                }
+
+                let id = files.len() as u32;
+                for trigram in extract_trigrams(&content) {
+                    trigrams.entry(trigram).or_default().push(id);
+                }
+                files.push(IndexedFile {
+                    path: entry
+                        .path()
+                        .strip_prefix(&root)
+                        .unwrap_or(entry.path())
+                        .to_path_buf(),
+                    size: metadata.len(),
+                    mtime: mtime_nanos(&metadata).unwrap_or_default(),
+                });
+            }
+
+            Ok(ContentIndex {
+                version: INDEX_VERSION,
+                root,
+                files,
+                trigrams,
+            })
+        });
+
+        let index = index
+            .await
+            .map_err(|e| std::io::Error::other(e.to_string()))??;
+
+        if let Some(parent) = index_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&index_path, serde_json::to_vec(&index)?).await?;
+        self.content_index_cache().invalidate(&index_path);
+
+        Ok(IndexSummary {
+            root: valid_path,
+            index_path,
+            indexed_files: index.files.len(),
+            trigrams: index.trigrams.len(),
+            elapsed_ms: started.elapsed().as_millis(),
+        })
+    }
+
+    /// Loads the index covering `path`, looking at `path` itself and then its ancestors.
+    /// Returns `None` if no compatible index exists. Loaded indexes are kept in memory until their
+    /// file changes.
+    pub fn load_content_index(&self, path: &Path) -> Option<Arc<ContentIndex>> {
+        let path = normalize_path(path);
+        path.ancestors().find_map(|dir| {
+            let index_path = self.content_index_path(dir).ok()?;
+            let index = self.content_index_cache().load(&index_path)?;
+            (index.version == INDEX_VERSION && index.root == dir).then_some(index)
+        })
+    }
+}
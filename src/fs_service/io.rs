@@ -1,5 +1,25 @@
+mod document;
 mod edit;
+mod markdown;
+mod media;
 mod read;
+mod structured;
+mod tabular;
+mod tail;
+mod temp;
 mod write;
 
-pub use read::FileInfo;
+pub use document::{DocumentKind, DocumentSection, DocumentText};
+pub use edit::{DiffHunk, DiffSummary, EditResult, FileDiffSummary};
+pub use markdown::{MarkdownHeading, markdown_outline};
+pub use media::{AudioInfo, ImageInfo, ImageResizeOptions, MediaInfo};
+pub use read::{
+    ByteRangeRead, ChunkPosition, DEFAULT_CHUNK_MAX_BYTES, DEFAULT_READ_MAX_BYTES, FileChunk,
+    FileInfo, TextFileRead,
+};
+pub use structured::{StructuredFormat, select_dotted_path};
+pub use tabular::{ColumnType, CsvColumn, CsvPreview};
+pub use tail::{TailChunk, TailPosition};
+pub use temp::TempPathKind;
+pub use write::OverwritePolicy;
+pub(crate) use write::{available_path, move_path};
@@ -0,0 +1,355 @@
+use crate::{
+    error::{ServiceError, ServiceResult},
+    fs_service::FileSystemService,
+};
+use quick_xml::{Reader, XmlVersion, escape::resolve_predefined_entity, events::Event};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+use zip::ZipArchive;
+
+/// Upper bound for a single XML part, protects against highly compressed archive entries.
+const MAX_XML_PART_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Office Open XML document types supported by [`FileSystemService::read_document_text`].
+#[derive(::serde::Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentKind {
+    Docx,
+    Xlsx,
+    Pptx,
+}
+
+/// A part of a document: the body of a .docx, a sheet of a .xlsx or a slide of a .pptx.
+#[derive(::serde::Serialize, Debug)]
+pub struct DocumentSection {
+    pub name: String,
+    pub text: String,
+}
+
+#[derive(::serde::Serialize, Debug)]
+pub struct DocumentText {
+    pub kind: DocumentKind,
+    pub sections: Vec<DocumentSection>,
+}
+
+type Archive = ZipArchive<BufReader<File>>;
+
+fn invalid_document(err: impl std::fmt::Display) -> ServiceError {
+    ServiceError::FromString(format!("Unable to read document: {err}"))
+}
+
+fn read_part(archive: &mut Archive, name: &str) -> ServiceResult<String> {
+    let part = archive.by_name(name).map_err(invalid_document)?;
+    let mut content = String::new();
+    part.take(MAX_XML_PART_SIZE).read_to_string(&mut content)?;
+    Ok(content)
+}
+
+/// Returns the value of attribute `name` (matched on its qualified name) of an element.
+fn attribute(element: &quick_xml::events::BytesStart, name: &str) -> Option<String> {
+    element
+        .try_get_attribute(name)
+        .ok()
+        .flatten()
+        .and_then(|attr| {
+            attr.normalized_value(XmlVersion::Implicit1_0)
+                .ok()
+                .map(|v| v.into_owned())
+        })
+}
+
+/// Extracts the text of `<*:t>` runs, separating `<*:p>` paragraphs with new lines.
+/// Used for both WordprocessingML and DrawingML parts.
+fn extract_paragraphs(xml: &str) -> ServiceResult<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut text = String::new();
+    let mut in_text = false;
+    loop {
+        match reader.read_event().map_err(invalid_document)? {
+            Event::Start(e) if e.local_name().as_ref() == "t" => in_text = true,
+            Event::End(e) => match e.local_name().as_ref() {
+                "t" => in_text = false,
+                "p" => text.push('\n'),
+                _ => {}
+            },
+            Event::Empty(e) => match e.local_name().as_ref() {
+                "tab" => text.push('\t'),
+                "br" | "cr" => text.push('\n'),
+                _ => {}
+            },
+            Event::Text(e) if in_text => text.push_str(&e.xml10_content()),
+            Event::GeneralRef(e) if in_text => push_entity(&mut text, &e),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(text)
+}
+
+fn push_entity(text: &mut String, entity: &quick_xml::events::BytesRef) {
+    if let Ok(Some(ch)) = entity.resolve_char_ref() {
+        text.push(ch);
+    } else if let Some(value) = resolve_predefined_entity(&entity.xml10_content()) {
+        text.push_str(value);
+    }
+}
+
+/// Number of columns of a worksheet, the last one being `XFD`.
+const MAX_SHEET_COLUMNS: usize = 16384;
+
+/// Converts a cell reference such as `AB12` to its 0-based column index. Returns `None` if it
+/// has no column letters or is past the last column of a worksheet.
+fn column_index(cell_reference: &str) -> Option<usize> {
+    let mut index = 0usize;
+    for letter in cell_reference.bytes().take_while(u8::is_ascii_alphabetic) {
+        let value = usize::from(letter.to_ascii_uppercase() - b'A') + 1;
+        index = index.checked_mul(26)?.checked_add(value)?;
+        if index > MAX_SHEET_COLUMNS {
+            return None;
+        }
+    }
+    index.checked_sub(1)
+}
+
+fn read_shared_strings(archive: &mut Archive) -> ServiceResult<Vec<String>> {
+    if archive.index_for_name("xl/sharedStrings.xml").is_none() {
+        return Ok(vec![]);
+    }
+    let xml = read_part(archive, "xl/sharedStrings.xml")?;
+    let mut reader = Reader::from_str(&xml);
+    let mut strings = Vec::new();
+    let mut current = String::new();
+    let mut in_text = false;
+    loop {
+        match reader.read_event().map_err(invalid_document)? {
+            Event::Start(e) if e.local_name().as_ref() == "si" => current.clear(),
+            Event::Start(e) if e.local_name().as_ref() == "t" => in_text = true,
+            Event::End(e) => match e.local_name().as_ref() {
+                "t" => in_text = false,
+                "si" => strings.push(std::mem::take(&mut current)),
+                _ => {}
+            },
+            Event::Empty(e) if e.local_name().as_ref() == "si" => strings.push(String::new()),
+            Event::Text(e) if in_text => current.push_str(&e.xml10_content()),
+            Event::GeneralRef(e) if in_text => push_entity(&mut current, &e),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(strings)
+}
+
+/// Renders a worksheet as tab-separated rows.
+fn extract_sheet(xml: &str, shared_strings: &[String]) -> ServiceResult<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut rows: Vec<String> = Vec::new();
+    let mut row: Vec<String> = Vec::new();
+    let mut cell_column = None;
+    let mut cell_type = String::new();
+    let mut cell_value = String::new();
+    let mut in_value = false;
+    loop {
+        match reader.read_event().map_err(invalid_document)? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                "row" => row.clear(),
+                "c" => {
+                    // cells past the last column are skipped rather than padding the row
+                    cell_column = match attribute(&e, "r") {
+                        Some(r) if r.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+                            column_index(&r)
+                        }
+                        _ => Some(row.len()).filter(|column| *column < MAX_SHEET_COLUMNS),
+                    };
+                    cell_type = attribute(&e, "t").unwrap_or_default();
+                    cell_value.clear();
+                }
+                "v" | "t" => in_value = true,
+                _ => {}
+            },
+            Event::End(e) => match e.local_name().as_ref() {
+                "v" | "t" => in_value = false,
+                "c" => {
+                    let value = match cell_type.as_str() {
+                        "s" => cell_value
+                            .trim()
+                            .parse::<usize>()
+                            .ok()
+                            .and_then(|i| shared_strings.get(i).cloned())
+                            .unwrap_or_default(),
+                        "b" => (cell_value.trim() == "1").to_string().to_uppercase(),
+                        _ => std::mem::take(&mut cell_value),
+                    };
+                    if let Some(cell_column) = cell_column {
+                        if row.len() <= cell_column {
+                            row.resize(cell_column + 1, String::new());
+                        }
+                        row[cell_column] = value;
+                    }
+                }
+                "row" => rows.push(row.join("\t")),
+                _ => {}
+            },
+            Event::Text(e) if in_value => cell_value.push_str(&e.xml10_content()),
+            Event::GeneralRef(e) if in_value => push_entity(&mut cell_value, &e),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(rows.join("\n"))
+}
+
+/// Returns (sheet name, part path) pairs in workbook order.
+fn read_sheet_list(archive: &mut Archive) -> ServiceResult<Vec<(String, String)>> {
+    let rels_xml = read_part(archive, "xl/_rels/workbook.xml.rels")?;
+    let mut targets = HashMap::new();
+    let mut reader = Reader::from_str(&rels_xml);
+    loop {
+        match reader.read_event().map_err(invalid_document)? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == "Relationship" => {
+                if let (Some(id), Some(target)) = (attribute(&e, "Id"), attribute(&e, "Target")) {
+                    let target = match target.strip_prefix('/') {
+                        Some(absolute) => absolute.to_string(),
+                        None => format!("xl/{target}"),
+                    };
+                    targets.insert(id, target);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let workbook_xml = read_part(archive, "xl/workbook.xml")?;
+    let mut sheets = Vec::new();
+    let mut reader = Reader::from_str(&workbook_xml);
+    loop {
+        match reader.read_event().map_err(invalid_document)? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == "sheet" => {
+                if let (Some(name), Some(target)) = (
+                    attribute(&e, "name"),
+                    attribute(&e, "r:id").and_then(|id| targets.get(&id).cloned()),
+                ) {
+                    sheets.push((name, target));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(sheets)
+}
+
+/// Returns true if the section at `index` (0-based) named `name` is selected.
+/// Selectors are section names (case-insensitive) or 1-based positions.
+fn is_selected(selection: Option<&[String]>, index: usize, name: &str) -> bool {
+    selection.is_none_or(|selection| {
+        selection.iter().any(|s| {
+            s.eq_ignore_ascii_case(name) || s.trim().parse::<usize>().is_ok_and(|n| n == index + 1)
+        })
+    })
+}
+
+impl FileSystemService {
+    /// Extracts plain text from a .docx, .xlsx or .pptx file.
+    ///
+    /// Spreadsheets produce one section per sheet (rows as tab-separated values) and
+    /// presentations one section per slide. `selection` restricts the output to the given
+    /// sheet/slide names or 1-based positions.
+    pub async fn read_document_text(
+        &self,
+        file_path: &Path,
+        selection: Option<Vec<String>>,
+    ) -> ServiceResult<DocumentText> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(file_path, allowed_directories)?;
+
+        let kind = match valid_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("docx") => DocumentKind::Docx,
+            Some("xlsx") => DocumentKind::Xlsx,
+            Some("pptx") => DocumentKind::Pptx,
+            _ => {
+                return Err(ServiceError::InvalidArgument(format!(
+                    "Unsupported document type: '{}'. Supported types are .docx, .xlsx and .pptx.",
+                    file_path.display()
+                )));
+            }
+        };
+
+        tokio::task::spawn_blocking(move || -> ServiceResult<DocumentText> {
+            let file = File::open(&valid_path)?;
+            let mut archive = ZipArchive::new(BufReader::new(file)).map_err(invalid_document)?;
+            let selection = selection.as_deref();
+
+            let mut sections = Vec::new();
+            match kind {
+                DocumentKind::Docx => {
+                    let xml = read_part(&mut archive, "word/document.xml")?;
+                    sections.push(DocumentSection {
+                        name: "document".to_string(),
+                        text: extract_paragraphs(&xml)?,
+                    });
+                }
+                DocumentKind::Xlsx => {
+                    let shared_strings = read_shared_strings(&mut archive)?;
+                    for (index, (name, part)) in
+                        read_sheet_list(&mut archive)?.into_iter().enumerate()
+                    {
+                        if !is_selected(selection, index, &name) {
+                            continue;
+                        }
+                        let xml = read_part(&mut archive, &part)?;
+                        sections.push(DocumentSection {
+                            name,
+                            text: extract_sheet(&xml, &shared_strings)?,
+                        });
+                    }
+                }
+                DocumentKind::Pptx => {
+                    let mut slides: Vec<(usize, String)> = archive
+                        .file_names()
+                        .filter_map(|name| {
+                            let name = name.ok()?;
+                            let number = name
+                                .strip_prefix("ppt/slides/slide")?
+                                .strip_suffix(".xml")?
+                                .parse::<usize>()
+                                .ok()?;
+                            Some((number, name.to_string()))
+                        })
+                        .collect();
+                    slides.sort();
+                    for (index, (number, part)) in slides.into_iter().enumerate() {
+                        let name = format!("Slide {number}");
+                        if !is_selected(selection, index, &name) {
+                            continue;
+                        }
+                        let xml = read_part(&mut archive, &part)?;
+                        sections.push(DocumentSection {
+                            name,
+                            text: extract_paragraphs(&xml)?,
+                        });
+                    }
+                }
+            }
+
+            if sections.is_empty() && selection.is_some() {
+                return Err(ServiceError::NotFound(
+                    "None of the requested sheets or slides were found in the document."
+                        .to_string(),
+                ));
+            }
+            Ok(DocumentText { kind, sections })
+        })
+        .await
+        .map_err(|e| std::io::Error::other(e.to_string()))?
+    }
+}
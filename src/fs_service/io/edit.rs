@@ -1,14 +1,15 @@
 use crate::{
-    error::ServiceResult,
+    error::{ServiceError, ServiceResult},
     fs_service::{
         FileSystemService,
-        utils::{detect_line_ending, normalize_line_endings},
+        io::write::check_content_hash,
+        journal::JournalChange,
+        utils::{detect_line_ending, normalize_line_endings, sha256_hex},
     },
-    tools::EditOperation,
+    tools::{EditOperation, MatchMode, Occurrence, OccurrenceMode},
 };
-use rust_mcp_sdk::schema::RpcError;
-use similar::TextDiff;
-use std::path::Path;
+use similar::{DiffTag, TextDiff};
+use std::path::{Path, PathBuf};
 
 impl FileSystemService {
     pub fn create_unified_diff(
@@ -38,165 +39,685 @@ impl FileSystemService {
         format!("Index: {}\n{}\n{}", file_name, "=".repeat(68), patch)
     }
 
+    /// Applies `edits` to `file_path` and returns the diff with its summary. If `backup` is true or a backup
+    /// directory is configured, the previous content is backed up first and the path of the
+    /// backup is reported after the diff.
+    /// With `if_match_sha256`, the edits are only applied if the current content of the file has
+    /// that hash, and the hash of the new content is reported after the diff.
     pub async fn apply_file_edits(
         &self,
         file_path: &Path,
         edits: Vec<EditOperation>,
         dry_run: Option<bool>,
         save_to: Option<&Path>,
-    ) -> ServiceResult<String> {
+        backup: bool,
+        if_match_sha256: Option<&str>,
+    ) -> ServiceResult<EditResult> {
         let allowed_directories = self.allowed_directories().await;
-        let valid_path = self.validate_path(file_path, allowed_directories)?;
+        let valid_path = if dry_run.unwrap_or(false) || save_to.is_some() {
+            self.validate_path(file_path, allowed_directories.clone())?
+        } else {
+            self.validate_write_path(file_path, allowed_directories.clone())?
+        };
+        let save_to = match save_to {
+            Some(save_to) if !dry_run.unwrap_or(false) => {
+                Some(self.validate_write_path(save_to, allowed_directories)?)
+            }
+            _ => None,
+        };
 
         // Read file content and normalize line endings
         let content_str = tokio::fs::read_to_string(&valid_path).await?;
+        if let Some(expected) = if_match_sha256 {
+            check_content_hash(file_path, Some(content_str.as_bytes()), expected)?;
+        }
         let original_line_ending = detect_line_ending(&content_str);
         let content_str = normalize_line_endings(&content_str);
 
-        // Apply edits sequentially
-        let mut modified_content = content_str.clone();
+        let (modified_content, outcomes) = apply_edits(&content_str, edits)?;
 
-        for edit in edits {
-            let normalized_old = normalize_line_endings(&edit.old_text);
-            let normalized_new = normalize_line_endings(&edit.new_text);
-            // If exact match exists, use it
-            if modified_content.contains(&normalized_old) {
-                modified_content = modified_content.replacen(&normalized_old, &normalized_new, 1);
-                continue;
+        let diff = self.create_unified_diff(
+            &content_str,
+            &modified_content,
+            Some(valid_path.display().to_string()),
+        );
+
+        let mut formatted_diff = fence_diff(&diff);
+        formatted_diff.push_str(&format_outcomes(&outcomes));
+        let summary = DiffSummary::from_files(vec![FileDiffSummary::new(
+            valid_path.display().to_string(),
+            &content_str,
+            &modified_content,
+        )]);
+
+        let is_dry_run = dry_run.unwrap_or(false);
+
+        if !is_dry_run {
+            let target = save_to.as_deref().unwrap_or(valid_path.as_path());
+            let modified_content = modified_content.replace("\n", original_line_ending);
+            if (backup || self.backup_dir().is_some())
+                && let Some(backup_path) = self.backup_file(target).await?
+            {
+                formatted_diff.push_str(&format!(
+                    "\nPrevious content backed up to {}",
+                    backup_path.display()
+                ));
+            }
+            let backup = self.journal_backup(target).await?;
+            self.write_content(target, modified_content.as_bytes())
+                .await?;
+            if if_match_sha256.is_some() {
+                formatted_diff.push_str(&format!(
+                    "\nsha256 of the new content: {}",
+                    sha256_hex(modified_content.as_bytes())
+                ));
             }
+            self.journal_record(
+                "edit_file",
+                vec![JournalChange::Write {
+                    path: target.to_path_buf(),
+                    backup,
+                }],
+            );
+        }
 
-            // Otherwise, try line-by-line matching with flexibility for whitespace
-            let old_lines: Vec<String> = normalized_old
-                .trim_end()
-                .split('\n')
-                .map(|s| s.to_string())
-                .collect();
-
-            let content_lines: Vec<String> = modified_content
-                .trim_end()
-                .split('\n')
-                .map(|s| s.to_string())
-                .collect();
-
-            let mut match_found = false;
-
-            // skip when the match is impossible:
-            if old_lines.len() > content_lines.len() {
-                let error_message = format!(
-                    "Cannot apply edit: the original text spans more lines ({}) than the file content ({}).",
-                    old_lines.len(),
-                    content_lines.len()
-                );
+        Ok(EditResult {
+            diff: formatted_diff,
+            summary,
+        })
+    }
+}
+
+/// The diff of an edit and its summary.
+#[derive(Debug, Clone)]
+pub struct EditResult {
+    /// Fenced unified diff, followed by what each edit matched.
+    pub diff: String,
+    pub summary: DiffSummary,
+}
 
-                return Err(RpcError::internal_error()
-                    .with_message(error_message)
-                    .into());
+/// Machine-readable summary of the changes made by an edit.
+#[derive(::serde::Serialize, Debug, Clone, Default, PartialEq)]
+pub struct DiffSummary {
+    /// Number of files whose content changed.
+    pub files_changed: usize,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+    pub files: Vec<FileDiffSummary>,
+}
+
+impl DiffSummary {
+    fn from_files(files: Vec<FileDiffSummary>) -> Self {
+        Self {
+            files_changed: files.iter().filter(|file| !file.hunks.is_empty()).count(),
+            lines_added: files.iter().map(|file| file.lines_added).sum(),
+            lines_removed: files.iter().map(|file| file.lines_removed).sum(),
+            files,
+        }
+    }
+}
+
+/// Summary of the changes made to a single file.
+#[derive(::serde::Serialize, Debug, Clone, Default, PartialEq)]
+pub struct FileDiffSummary {
+    pub path: String,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// A hunk of the diff, with the lines it covers before and after the change.
+/// Like in a unified diff, line numbers start at 1 and hunks include up to 4 lines of context.
+#[derive(::serde::Serialize, Debug, Clone, PartialEq)]
+pub struct DiffHunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+}
+
+impl FileDiffSummary {
+    fn new(path: String, original: &str, modified: &str) -> Self {
+        let diff = TextDiff::from_lines(original, modified);
+        let mut summary = Self {
+            path,
+            ..Default::default()
+        };
+        for group in diff.grouped_ops(4) {
+            let (Some(first), Some(last)) = (group.first(), group.last()) else {
+                continue;
+            };
+            let old_range = first.old_range().start..last.old_range().end;
+            let new_range = first.new_range().start..last.new_range().end;
+            summary.hunks.push(DiffHunk {
+                old_start: old_range.start + 1,
+                old_lines: old_range.len(),
+                new_start: new_range.start + 1,
+                new_lines: new_range.len(),
+            });
+            for op in &group {
+                let (tag, old_range, new_range) = op.as_tag_tuple();
+                if matches!(tag, DiffTag::Delete | DiffTag::Replace) {
+                    summary.lines_removed += old_range.len();
+                }
+                if matches!(tag, DiffTag::Insert | DiffTag::Replace) {
+                    summary.lines_added += new_range.len();
+                }
             }
+        }
+        summary
+    }
+}
 
-            let max_start = content_lines.len().saturating_sub(old_lines.len());
-            for i in 0..=max_start {
-                let potential_match = &content_lines[i..i + old_lines.len()];
+/// A file whose edits were validated by [`FileSystemService::apply_edits_batch`].
+struct StagedEdit {
+    path: PathBuf,
+    original: String,
+    modified: String,
+}
 
-                // Compare lines with normalized whitespace
-                let is_match = old_lines.iter().enumerate().all(|(j, old_line)| {
-                    let content_line = &potential_match[j];
-                    old_line.trim() == content_line.trim()
-                });
+impl FileSystemService {
+    /// Applies the edits of several files as a whole: every file is edited in memory first, then
+    /// the new contents are staged next to their files and renamed into place. If any edit does
+    /// not apply nothing is written; if a file cannot be replaced, the files already replaced
+    /// are restored. Returns the diffs of all files with their summary.
+    pub async fn apply_edits_batch(
+        &self,
+        files: Vec<(PathBuf, Vec<EditOperation>)>,
+        dry_run: bool,
+    ) -> ServiceResult<EditResult> {
+        let allowed_directories = self.allowed_directories().await;
+        let mut staged_edits: Vec<StagedEdit> = Vec::with_capacity(files.len());
+        let mut diffs = String::new();
+        let mut summaries = Vec::with_capacity(files.len());
+        for (file_path, edits) in files {
+            let valid_path = if dry_run {
+                self.validate_path(&file_path, allowed_directories.clone())?
+            } else {
+                self.validate_write_path(&file_path, allowed_directories.clone())?
+            };
+            if staged_edits.iter().any(|staged| staged.path == valid_path) {
+                return Err(ServiceError::InvalidArgument(format!(
+                    "'{}' is listed more than once, combine its edits into a single entry.",
+                    file_path.display()
+                )));
+            }
+            let original = tokio::fs::read_to_string(&valid_path).await?;
+            let content = normalize_line_endings(&original);
+            let (modified, outcomes) = apply_edits(&content, edits).map_err(|err| {
+                let message = format!(
+                    "No file was changed, the edits of '{}' do not apply: {err}",
+                    file_path.display()
+                );
+                match err {
+                    ServiceError::NoMatch(_) => ServiceError::NoMatch(message),
+                    ServiceError::InvalidArgument(_) => ServiceError::InvalidArgument(message),
+                    _ => ServiceError::FromString(message),
+                }
+            })?;
+            diffs.push_str(&fence_diff(&self.create_unified_diff(
+                &content,
+                &modified,
+                Some(valid_path.display().to_string()),
+            )));
+            diffs.push_str(&format_outcomes(&outcomes));
+            summaries.push(FileDiffSummary::new(
+                valid_path.display().to_string(),
+                &content,
+                &modified,
+            ));
+            let modified = modified.replace("\n", detect_line_ending(&original));
+            staged_edits.push(StagedEdit {
+                path: valid_path,
+                original,
+                modified,
+            });
+        }
+        let result = EditResult {
+            diff: diffs,
+            summary: DiffSummary::from_files(summaries),
+        };
+        if dry_run {
+            return Ok(result);
+        }
 
-                if is_match {
-                    // Preserve original indentation of first line
-                    let original_indent = content_lines[i]
-                        .chars()
-                        .take_while(|&c| c.is_whitespace())
-                        .collect::<String>();
-
-                    let new_lines: Vec<String> = normalized_new
-                        .split('\n')
-                        .enumerate()
-                        .map(|(j, line)| {
-                            // Keep indentation of the first line
-                            if j == 0 {
-                                return format!("{}{}", original_indent, line.trim_start());
-                            }
-
-                            // For subsequent lines, preserve relative indentation and original whitespace type
-                            let old_indent = old_lines
-                                .get(j)
-                                .map(|line| {
-                                    line.chars()
-                                        .take_while(|&c| c.is_whitespace())
-                                        .collect::<String>()
-                                })
-                                .unwrap_or_default();
-
-                            let new_indent = line
-                                .chars()
-                                .take_while(|&c| c.is_whitespace())
-                                .collect::<String>();
-
-                            // Use the same whitespace character as original_indent (tabs or spaces)
-                            let indent_char = if original_indent.contains('\t') {
-                                "\t"
-                            } else {
-                                " "
-                            };
-                            let relative_indent = if new_indent.len() >= old_indent.len() {
-                                new_indent.len() - old_indent.len()
-                            } else {
-                                0 // Don't reduce indentation below original
-                            };
-                            format!(
-                                "{}{}{}",
-                                &original_indent,
-                                &indent_char.repeat(relative_indent),
-                                line.trim_start()
-                            )
-                        })
-                        .collect();
-
-                    let mut content_lines = content_lines.clone();
-                    content_lines.splice(i..i + old_lines.len(), new_lines);
-                    modified_content = content_lines.join("\n");
-                    match_found = true;
+        // stage every file before replacing any of them
+        let mut staged_files = Vec::with_capacity(staged_edits.len());
+        for edit in &staged_edits {
+            match self.stage_file(&edit.path, edit.modified.as_bytes()).await {
+                Ok(staged) => staged_files.push(staged),
+                Err(err) => {
+                    for staged in &staged_files {
+                        staged.discard().await;
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
+        let mut changes = Vec::with_capacity(staged_edits.len());
+        let mut replaced = 0;
+        let mut failure = None;
+        for (edit, staged) in staged_edits.iter().zip(&staged_files) {
+            let renamed = match self.journal_backup(&edit.path).await {
+                Ok(backup) => staged.commit().await.map(|_| backup).map_err(Into::into),
+                Err(err) => Err(err),
+            };
+            match renamed {
+                Ok(backup) => {
+                    changes.push(JournalChange::Write {
+                        path: edit.path.clone(),
+                        backup,
+                    });
+                    replaced += 1;
+                }
+                Err(err) => {
+                    failure = Some((edit.path.clone(), err));
                     break;
                 }
             }
-            if !match_found {
-                return Err(RpcError::internal_error()
-                    .with_message(format!(
-                        "Could not find exact match for edit:\n{}",
-                        edit.old_text
-                    ))
-                    .into());
+        }
+
+        let Some((failed_path, err)) = failure else {
+            self.journal_record("apply_edits_batch", changes);
+            return Ok(result);
+        };
+        for staged in &staged_files[replaced..] {
+            staged.discard().await;
+        }
+        // roll back the files that were already replaced
+        let mut not_restored = vec![];
+        for edit in &staged_edits[..replaced] {
+            let restored = match self.stage_file(&edit.path, edit.original.as_bytes()).await {
+                Ok(staged) => staged.commit().await.is_ok(),
+                Err(_) => false,
+            };
+            if !restored {
+                not_restored.push(edit.path.display().to_string());
+            }
+        }
+        if !not_restored.is_empty() {
+            // the journal still allows undoing the replacements that could not be rolled back
+            self.journal_record("apply_edits_batch", changes);
+            return Err(ServiceError::FromString(format!(
+                "Unable to replace '{}': {err}. Rolling back failed for: {}",
+                failed_path.display(),
+                not_restored.join(", ")
+            )));
+        }
+        Err(ServiceError::FromString(format!(
+            "Unable to replace '{}': {err}. All files were rolled back.",
+            failed_path.display()
+        )))
+    }
+}
+
+/// Minimum similarity of a fuzzy match, unless an edit sets its own threshold.
+const DEFAULT_SIMILARITY_THRESHOLD: f64 = 0.9;
+
+/// What an edit of [`apply_edits`] matched, reported after the diff.
+pub(crate) enum EditOutcome {
+    /// `found` occurrences of the text to replace were found with `mode`, `replaced` of them
+    /// replaced. `similarity` is the best similarity of a fuzzy match.
+    Replaced {
+        found: usize,
+        replaced: usize,
+        mode: MatchMode,
+        similarity: Option<f64>,
+    },
+    /// The lines `start_line..=end_line` were replaced.
+    LineRange { start_line: u64, end_line: u64 },
+}
+
+/// Describes the outcome of each edit, one line per edit.
+pub(crate) fn format_outcomes(outcomes: &[EditOutcome]) -> String {
+    outcomes
+        .iter()
+        .enumerate()
+        .map(|(index, outcome)| match outcome {
+            EditOutcome::Replaced {
+                found,
+                replaced,
+                mode,
+                similarity,
+            } => format!(
+                "Edit {}: {found} occurrence{} found, {replaced} replaced ({}{}).\n",
+                index + 1,
+                if *found == 1 { "" } else { "s" },
+                match mode {
+                    MatchMode::Exact => "exact match",
+                    MatchMode::WhitespaceInsensitive => "whitespace-insensitive match",
+                    MatchMode::Fuzzy => "fuzzy match",
+                },
+                similarity
+                    .map(|similarity| format!(", similarity {similarity:.2}"))
+                    .unwrap_or_default()
+            ),
+            EditOutcome::LineRange {
+                start_line,
+                end_line,
+            } => format!(
+                "Edit {}: lines {start_line}-{end_line} replaced.\n",
+                index + 1
+            ),
+        })
+        .collect()
+}
+
+/// Returns the indexes of the matches selected by `occurrence` among `found` matches.
+fn select_occurrences(
+    found: usize,
+    occurrence: Option<Occurrence>,
+    old_text: &str,
+) -> ServiceResult<Vec<usize>> {
+    match occurrence.unwrap_or_default() {
+        Occurrence::Mode(OccurrenceMode::First) => Ok(vec![0]),
+        Occurrence::Mode(OccurrenceMode::All) => Ok((0..found).collect()),
+        Occurrence::Nth(nth) if nth >= 1 && nth as usize <= found => Ok(vec![nth as usize - 1]),
+        Occurrence::Nth(nth) => Err(ServiceError::NoMatch(format!(
+            "Cannot replace occurrence {nth}: {found} occurrence{} found for edit:\n{old_text}",
+            if found == 1 { "" } else { "s" }
+        ))),
+    }
+}
+
+/// Applies `edits` in order to `content`, whose line endings are normalized to `\n`.
+/// Returns the modified content and what each edit matched.
+pub(crate) fn apply_edits(
+    content: &str,
+    edits: Vec<EditOperation>,
+) -> ServiceResult<(String, Vec<EditOutcome>)> {
+    // Apply edits sequentially
+    let mut modified_content = content.to_string();
+    let mut outcomes = Vec::with_capacity(edits.len());
+
+    for edit in edits {
+        if let Some(start_line) = edit.start_line {
+            if !edit.old_text.is_empty() || edit.occurrence.is_some() {
+                return Err(ServiceError::InvalidArgument(
+                    "An edit with a line range cannot also have 'oldText' or 'occurrence'."
+                        .to_string(),
+                ));
+            }
+            modified_content = apply_line_range_edit(&modified_content, start_line, &edit)?;
+            outcomes.push(EditOutcome::LineRange {
+                start_line,
+                end_line: edit.end_line.unwrap_or(start_line),
+            });
+            continue;
+        }
+        let match_mode = edit.match_mode.unwrap_or_default();
+        let threshold = edit
+            .similarity_threshold
+            .unwrap_or(DEFAULT_SIMILARITY_THRESHOLD);
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err(ServiceError::InvalidArgument(format!(
+                "Invalid similarity threshold {threshold}: it must be between 0 and 1."
+            )));
+        }
+        let normalized_old = normalize_line_endings(&edit.old_text);
+        let normalized_new = normalize_line_endings(&edit.new_text);
+        // If exact match exists, use it
+        let exact_matches: Vec<usize> = modified_content
+            .match_indices(&normalized_old)
+            .map(|(index, _)| index)
+            .collect();
+        if !exact_matches.is_empty() {
+            let selected =
+                select_occurrences(exact_matches.len(), edit.occurrence, &edit.old_text)?;
+            // replace from the end so that the indexes of earlier matches stay valid
+            for &selected in selected.iter().rev() {
+                let index = exact_matches[selected];
+                modified_content
+                    .replace_range(index..index + normalized_old.len(), &normalized_new);
             }
+            outcomes.push(EditOutcome::Replaced {
+                found: exact_matches.len(),
+                replaced: selected.len(),
+                mode: MatchMode::Exact,
+                similarity: None,
+            });
+            continue;
+        }
+        if match_mode == MatchMode::Exact {
+            return Err(ServiceError::NoMatch(format!(
+                "Could not find exact match for edit:\n{}",
+                edit.old_text
+            )));
         }
 
-        let diff = self.create_unified_diff(
-            &content_str,
-            &modified_content,
-            Some(valid_path.display().to_string()),
-        );
+        // Otherwise, try line-by-line matching with flexibility for whitespace
+        let old_lines: Vec<String> = normalized_old
+            .trim_end()
+            .split('\n')
+            .map(|s| s.to_string())
+            .collect();
 
-        // Format diff with appropriate number of backticks
-        let mut num_backticks = 3;
-        while diff.contains(&"`".repeat(num_backticks)) {
-            num_backticks += 1;
+        let content_lines: Vec<String> = modified_content
+            .trim_end()
+            .split('\n')
+            .map(|s| s.to_string())
+            .collect();
+
+        // skip when the match is impossible:
+        if old_lines.len() > content_lines.len() {
+            let error_message = format!(
+                "Cannot apply edit: the original text spans more lines ({}) than the file content ({}).",
+                old_lines.len(),
+                content_lines.len()
+            );
+
+            return Err(ServiceError::NoMatch(error_message));
         }
-        let formatted_diff = format!(
-            "{}diff\n{}{}\n\n",
-            "`".repeat(num_backticks),
-            diff,
-            "`".repeat(num_backticks)
-        );
 
-        let is_dry_run = dry_run.unwrap_or(false);
+        // start lines of the non-overlapping matches, compared with normalized whitespace
+        let mut matches = whitespace_insensitive_matches(&content_lines, &old_lines);
+        let mut mode = MatchMode::WhitespaceInsensitive;
+        let mut similarity = None;
+        if matches.is_empty() && match_mode == MatchMode::Fuzzy {
+            let fuzzy_matches = fuzzy_matches(&content_lines, &old_lines, threshold);
+            similarity = fuzzy_matches
+                .iter()
+                .map(|(_, score)| *score)
+                .reduce(f64::max);
+            matches = fuzzy_matches.into_iter().map(|(i, _)| i).collect();
+            mode = MatchMode::Fuzzy;
+        }
+        if matches.is_empty() {
+            return Err(ServiceError::NoMatch(format!(
+                "Could not find {} match for edit:\n{}",
+                if match_mode == MatchMode::Fuzzy {
+                    "a close enough"
+                } else {
+                    "exact"
+                },
+                edit.old_text
+            )));
+        }
 
-        if !is_dry_run {
-            let target = save_to.unwrap_or(valid_path.as_path());
-            let modified_content = modified_content.replace("\n", original_line_ending);
-            tokio::fs::write(target, modified_content).await?;
+        let selected = select_occurrences(matches.len(), edit.occurrence, &edit.old_text)?;
+        let mut content_lines = content_lines;
+        for &selected in selected.iter().rev() {
+            let i = matches[selected];
+            let new_lines = reindent_lines(&content_lines[i], &old_lines, &normalized_new);
+            content_lines.splice(i..i + old_lines.len(), new_lines);
+        }
+        modified_content = content_lines.join("\n");
+        outcomes.push(EditOutcome::Replaced {
+            found: matches.len(),
+            replaced: selected.len(),
+            mode,
+            similarity,
+        });
+    }
+    Ok((modified_content, outcomes))
+}
+
+/// Returns the start lines of the non-overlapping blocks of `content_lines` equal to
+/// `old_lines` once the whitespace around each line is trimmed.
+fn whitespace_insensitive_matches(content_lines: &[String], old_lines: &[String]) -> Vec<usize> {
+    let mut matches = vec![];
+    let max_start = content_lines.len().saturating_sub(old_lines.len());
+    let mut i = 0;
+    while i <= max_start {
+        let potential_match = &content_lines[i..i + old_lines.len()];
+        let is_match = old_lines
+            .iter()
+            .zip(potential_match)
+            .all(|(old_line, content_line)| old_line.trim() == content_line.trim());
+        if is_match {
+            matches.push(i);
+            i += old_lines.len();
+        } else {
+            i += 1;
         }
+    }
+    matches
+}
+
+/// Returns the start lines of the non-overlapping blocks of `content_lines` whose similarity
+/// with `old_lines` is at least `threshold`, with their similarity. Overlapping candidates are
+/// resolved in favor of the most similar one.
+fn fuzzy_matches(
+    content_lines: &[String],
+    old_lines: &[String],
+    threshold: f64,
+) -> Vec<(usize, f64)> {
+    let trimmed = |lines: &[String]| {
+        lines
+            .iter()
+            .map(|line| line.trim())
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let old_text = trimmed(old_lines);
+    let max_start = content_lines.len().saturating_sub(old_lines.len());
+    let mut candidates: Vec<(usize, f64)> = (0..=max_start)
+        .map(|i| {
+            let window = trimmed(&content_lines[i..i + old_lines.len()]);
+            let similarity = TextDiff::from_chars(old_text.as_str(), window.as_str()).ratio();
+            (i, f64::from(similarity))
+        })
+        .filter(|(_, similarity)| *similarity >= threshold)
+        .collect();
+    candidates.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    let mut matches: Vec<(usize, f64)> = vec![];
+    for (i, similarity) in candidates {
+        if matches
+            .iter()
+            .all(|(start, _)| i + old_lines.len() <= *start || start + old_lines.len() <= i)
+        {
+            matches.push((i, similarity));
+        }
+    }
+    matches.sort_by_key(|(i, _)| *i);
+    matches
+}
+
+/// Returns the lines of `new_text` indented like `first_line`, the first line of the matched
+/// block, keeping their indentation relative to `old_lines`.
+fn reindent_lines(first_line: &str, old_lines: &[String], new_text: &str) -> Vec<String> {
+    // Preserve original indentation of first line
+    let original_indent = first_line
+        .chars()
+        .take_while(|&c| c.is_whitespace())
+        .collect::<String>();
+
+    new_text
+        .split('\n')
+        .enumerate()
+        .map(|(j, line)| {
+            // Keep indentation of the first line
+            if j == 0 {
+                return format!("{}{}", original_indent, line.trim_start());
+            }
+
+            // For subsequent lines, preserve relative indentation and original whitespace type
+            let old_indent = old_lines
+                .get(j)
+                .map(|line| {
+                    line.chars()
+                        .take_while(|&c| c.is_whitespace())
+                        .collect::<String>()
+                })
+                .unwrap_or_default();
+
+            let new_indent = line
+                .chars()
+                .take_while(|&c| c.is_whitespace())
+                .collect::<String>();
+
+            // Use the same whitespace character as original_indent (tabs or spaces)
+            let indent_char = if original_indent.contains('\t') {
+                "\t"
+            } else {
+                " "
+            };
+            let relative_indent = if new_indent.len() >= old_indent.len() {
+                new_indent.len() - old_indent.len()
+            } else {
+                0 // Don't reduce indentation below original
+            };
+            format!(
+                "{}{}{}",
+                &original_indent,
+                &indent_char.repeat(relative_indent),
+                line.trim_start()
+            )
+        })
+        .collect()
+}
+
+/// Replaces the lines `start_line..=end_line` of `content` with the new text of `edit`,
+/// after checking them against `range_sha256` if it is set.
+fn apply_line_range_edit(
+    content: &str,
+    start_line: u64,
+    edit: &EditOperation,
+) -> ServiceResult<String> {
+    let end_line = edit.end_line.unwrap_or(start_line);
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    if start_line == 0 || end_line < start_line || end_line as usize > lines.len() {
+        return Err(ServiceError::InvalidArgument(format!(
+            "Invalid line range {start_line}-{end_line}: the file has {} lines.",
+            lines.len()
+        )));
+    }
+    let (start, end) = (start_line as usize - 1, end_line as usize);
+
+    let replaced = lines[start..end].concat();
+    if let Some(expected) = &edit.range_sha256 {
+        let actual = sha256_hex(replaced.strip_suffix('\n').unwrap_or(&replaced).as_bytes());
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            return Err(ServiceError::Conflict(format!(
+                "Lines {start_line}-{end_line} changed: expected sha256 {expected}, found {actual}. Read the lines again before editing them."
+            )));
+        }
+    }
+
+    let mut new_text = normalize_line_endings(&edit.new_text);
+    // the line break ending the range is kept, unless the lines are deleted
+    if replaced.ends_with('\n') && !new_text.is_empty() && !new_text.ends_with('\n') {
+        new_text.push('\n');
+    }
+    Ok(format!(
+        "{}{new_text}{}",
+        lines[..start].concat(),
+        lines[end..].concat()
+    ))
+}
 
-        Ok(formatted_diff)
+/// Wraps a unified diff in a ```diff block, with enough backticks to fence it.
+pub(crate) fn fence_diff(diff: &str) -> String {
+    let mut num_backticks = 3;
+    while diff.contains(&"`".repeat(num_backticks)) {
+        num_backticks += 1;
     }
+    format!(
+        "{}diff\n{}{}\n\n",
+        "`".repeat(num_backticks),
+        diff,
+        "`".repeat(num_backticks)
+    )
 }
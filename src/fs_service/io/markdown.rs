@@ -0,0 +1,142 @@
+use crate::{error::ServiceResult, fs_service::FileSystemService};
+use std::path::Path;
+
+/// A Markdown heading with the 1-based line range of its section.
+#[derive(::serde::Serialize, Debug, Clone, PartialEq)]
+pub struct MarkdownHeading {
+    pub level: u8,
+    pub text: String,
+    /// Line of the heading itself.
+    pub line: usize,
+    /// Last line of the section, i.e. the line before the next heading of the same or a higher level.
+    pub end_line: usize,
+}
+
+/// Returns the level and text of an ATX heading (`## Title ##`).
+fn parse_atx_heading(line: &str) -> Option<(u8, String)> {
+    // up to three spaces of indentation are allowed
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+    let trimmed = &line[indent..];
+    let level = trimmed.bytes().take_while(|b| *b == b'#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &trimmed[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    // strip the optional closing sequence of '#'
+    let mut text = rest.trim();
+    let without_closing = text.trim_end_matches('#');
+    if without_closing.is_empty() || without_closing.ends_with([' ', '\t']) {
+        text = without_closing.trim_end();
+    }
+    Some((level as u8, text.to_string()))
+}
+
+/// Returns the heading level for a setext underline (`===` or `---`).
+fn parse_setext_underline(line: &str) -> Option<u8> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() || line.len() - line.trim_start().len() > 3 {
+        return None;
+    }
+    if trimmed.bytes().all(|b| b == b'=') {
+        Some(1)
+    } else if trimmed.bytes().all(|b| b == b'-') {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// Returns the fence marker if `line` opens or closes a fenced code block.
+fn code_fence(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    ["```", "~~~"]
+        .into_iter()
+        .find(|fence| trimmed.starts_with(fence))
+}
+
+/// Extracts the heading hierarchy of a Markdown document.
+/// Headings inside fenced code blocks and YAML front matter are ignored.
+pub fn markdown_outline(content: &str) -> Vec<MarkdownHeading> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut headings: Vec<MarkdownHeading> = Vec::new();
+    let mut open_fence: Option<&str> = None;
+    let mut start = 0;
+
+    // skip front matter
+    if lines.first().is_some_and(|l| l.trim_end() == "---")
+        && let Some(end) = lines.iter().skip(1).position(|l| l.trim_end() == "---")
+    {
+        start = end + 2;
+    }
+
+    let mut index = start;
+    while index < lines.len() {
+        let line = lines[index];
+        if let Some(fence) = open_fence {
+            if code_fence(line) == Some(fence) {
+                open_fence = None;
+            }
+        } else if let Some(fence) = code_fence(line) {
+            open_fence = Some(fence);
+        } else if let Some((level, text)) = parse_atx_heading(line) {
+            headings.push(MarkdownHeading {
+                level,
+                text,
+                line: index + 1,
+                end_line: 0,
+            });
+        } else if !line.trim().is_empty()
+            && let Some(level) = lines.get(index + 1).and_then(|l| parse_setext_underline(l))
+            // a paragraph line, not a list item or block quote
+            && !line.trim_start().starts_with(['-', '*', '+', '>'])
+        {
+            headings.push(MarkdownHeading {
+                level,
+                text: line.trim().to_string(),
+                line: index + 1,
+                end_line: 0,
+            });
+            index += 1;
+        }
+        index += 1;
+    }
+
+    // compute section ends
+    for i in 0..headings.len() {
+        let level = headings[i].level;
+        let next_line = headings[i + 1..]
+            .iter()
+            .find(|h| h.level <= level)
+            .map(|h| h.line);
+        headings[i].end_line = next_line.map_or(lines.len(), |line| line - 1);
+    }
+    headings
+}
+
+impl FileSystemService {
+    /// Returns the headings of a Markdown file, optionally limited to `max_level`.
+    pub async fn markdown_outline(
+        &self,
+        file_path: &Path,
+        max_level: Option<u8>,
+    ) -> ServiceResult<Vec<MarkdownHeading>> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(file_path, allowed_directories)?;
+        let content = tokio::fs::read_to_string(valid_path).await?;
+
+        let mut headings = markdown_outline(&content);
+        if let Some(max_level) = max_level {
+            headings.retain(|h| h.level <= max_level);
+        }
+        Ok(headings)
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{
+    error::{ServiceError, ServiceResult},
+    fs_service::{FileSystemService, utils::mime_from_path},
+};
+use image::{
+    ImageDecoder, ImageFormat, ImageReader, codecs::jpeg::JpegEncoder, imageops::FilterType,
+};
+use lofty::{config::ParseOptions, file::AudioFile, probe::Probe};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, Cursor},
+    path::{Path, PathBuf},
+};
+
+/// EXIF values longer than this (typically binary blobs) are omitted.
+const MAX_EXIF_VALUE_LENGTH: usize = 256;
+
+/// Options for downscaling and re-encoding an image before it is returned.
+#[derive(Debug, Clone, Default)]
+pub struct ImageResizeOptions {
+    pub max_width: Option<u32>,
+    pub max_height: Option<u32>,
+    /// JPEG quality (1-100). When set, the image is always re-encoded as JPEG.
+    pub quality: Option<u8>,
+}
+
+#[derive(::serde::Serialize, Debug)]
+pub struct ImageInfo {
+    pub format: String,
+    pub width: u32,
+    pub height: u32,
+    pub color_type: Option<String>,
+    pub bits_per_pixel: Option<u16>,
+    pub channels: Option<u8>,
+}
+
+#[derive(::serde::Serialize, Debug)]
+pub struct AudioInfo {
+    pub duration_ms: u128,
+    pub overall_bitrate_kbps: Option<u32>,
+    pub audio_bitrate_kbps: Option<u32>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u8>,
+    pub bit_depth: Option<u8>,
+}
+
+/// Header-level information about an image or audio file, returned by
+/// [`FileSystemService::get_media_info`].
+#[derive(::serde::Serialize, Debug)]
+pub struct MediaInfo {
+    pub path: PathBuf,
+    pub mime_type: String,
+    pub size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<ImageInfo>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub exif: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio: Option<AudioInfo>,
+}
+
+fn read_image_info(path: &Path, extension: &str) -> ServiceResult<ImageInfo> {
+    let reader = ImageReader::open(path)?.with_guessed_format()?;
+    let format = reader
+        .format()
+        .map(|f| format!("{f:?}").to_lowercase())
+        .unwrap_or_else(|| extension.to_string());
+
+    // Creating the decoder only parses the image header
+    let decoder = reader
+        .into_decoder()
+        .map_err(|err| ServiceError::FromString(format!("Unable to read image header: {err}")))?;
+    let (width, height) = decoder.dimensions();
+    let color_type = decoder.original_color_type();
+
+    Ok(ImageInfo {
+        format,
+        width,
+        height,
+        color_type: Some(format!("{color_type:?}")),
+        bits_per_pixel: Some(color_type.bits_per_pixel()),
+        channels: Some(color_type.channel_count()),
+    })
+}
+
+/// Decodes the image at `path`, downscales it to fit within the configured bounds (preserving
+/// the aspect ratio, never upscaling) and re-encodes it.
+///
+/// The image is encoded as JPEG when a quality is given, otherwise in its original format
+/// if supported, falling back to PNG.
+pub(crate) fn resize_image(path: &Path, options: &ImageResizeOptions) -> ServiceResult<Vec<u8>> {
+    let reader = ImageReader::open(path)?.with_guessed_format()?;
+    let format = reader.format();
+    let mut image = reader
+        .decode()
+        .map_err(|err| ServiceError::FromString(format!("Unable to decode image: {err}")))?;
+
+    let max_width = options.max_width.unwrap_or(u32::MAX).max(1);
+    let max_height = options.max_height.unwrap_or(u32::MAX).max(1);
+    if image.width() > max_width || image.height() > max_height {
+        image = image.resize(max_width, max_height, FilterType::Triangle);
+    }
+
+    let mut bytes = Vec::new();
+    let result = match (options.quality, format) {
+        (Some(quality), _) => JpegEncoder::new_with_quality(&mut bytes, quality.clamp(1, 100))
+            .encode_image(&image.to_rgb8()),
+        (None, Some(format)) if format.writing_enabled() => {
+            image.write_to(&mut Cursor::new(&mut bytes), format)
+        }
+        (None, _) => image.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png),
+    };
+    result.map_err(|err| ServiceError::FromString(format!("Unable to encode image: {err}")))?;
+    Ok(bytes)
+}
+
+fn read_exif(path: &Path) -> BTreeMap<String, String> {
+    let Ok(file) = File::open(path) else {
+        return BTreeMap::new();
+    };
+    let Ok(exif) = exif::Reader::new().read_from_container(&mut BufReader::new(file)) else {
+        return BTreeMap::new();
+    };
+    exif.fields()
+        .filter(|field| field.ifd_num == exif::In::PRIMARY && field.tag != exif::Tag::MakerNote)
+        .map(|field| {
+            (
+                field.tag.to_string(),
+                field.display_value().with_unit(&exif).to_string(),
+            )
+        })
+        .filter(|(_, value)| value.len() <= MAX_EXIF_VALUE_LENGTH)
+        .collect()
+}
+
+fn read_audio_info(path: &Path) -> ServiceResult<AudioInfo> {
+    let tagged_file = Probe::open(path)
+        .and_then(|probe| {
+            probe
+                .options(ParseOptions::new().read_tags(false).read_cover_art(false))
+                .read()
+        })
+        .map_err(|err| ServiceError::FromString(format!("Unable to read audio header: {err}")))?;
+    let properties = tagged_file.properties();
+
+    Ok(AudioInfo {
+        duration_ms: properties.duration().as_millis(),
+        overall_bitrate_kbps: properties.overall_bitrate(),
+        audio_bitrate_kbps: properties.audio_bitrate(),
+        sample_rate: properties.sample_rate(),
+        channels: properties.channels(),
+        bit_depth: properties.bit_depth(),
+    })
+}
+
+impl FileSystemService {
+    /// Returns format, dimensions, color depth and EXIF fields of an image, or duration,
+    /// bitrate and sample format of an audio file. Only file headers are parsed.
+    pub async fn get_media_info(&self, file_path: &Path) -> ServiceResult<MediaInfo> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(file_path, allowed_directories)?;
+
+        tokio::task::spawn_blocking(move || -> ServiceResult<MediaInfo> {
+            let size = std::fs::metadata(&valid_path)?.len();
+            let kind = mime_from_path(&valid_path)?;
+            let mime_type = kind.mime_type().to_string();
+
+            let mut info = MediaInfo {
+                path: valid_path.clone(),
+                mime_type: mime_type.clone(),
+                size,
+                image: None,
+                exif: BTreeMap::new(),
+                audio: None,
+            };
+
+            match kind.matcher_type() {
+                // SVG is a text format without a binary header to inspect
+                infer::MatcherType::Image if kind.extension() == "svg" => {}
+                infer::MatcherType::Image => {
+                    info.image = Some(read_image_info(&valid_path, kind.extension())?);
+                    info.exif = read_exif(&valid_path);
+                }
+                infer::MatcherType::Audio => {
+                    info.audio = Some(read_audio_info(&valid_path)?);
+                }
+                _ => return Err(ServiceError::InvalidMediaFile(mime_type)),
+            }
+            Ok(info)
+        })
+        .await
+        .map_err(|e| std::io::Error::other(e.to_string()))?
+    }
+}
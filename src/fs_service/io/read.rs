@@ -1,24 +1,114 @@
+#[cfg(unix)]
+use crate::fs_service::utils::{group_name, user_name};
 use crate::{
-    error::ServiceResult,
+    error::{ServiceError, ServiceResult},
     fs_service::{
         FileSystemService,
+        io::media::{ImageResizeOptions, resize_image},
         utils::{
-            format_permissions, format_system_time, mime_from_path, read_file_as_base64,
-            validate_file_size,
+            detect_mime_type, format_permissions, format_system_time, mime_from_path,
+            read_file_as_base64, sha256_file_hex, validate_file_size,
         },
     },
 };
+use base64::{Engine, engine::general_purpose};
 use futures::{StreamExt, stream};
+use serde_json::Value;
+use serde_json_path::JsonPath;
 use std::fs::{self};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 use tokio::{
     fs::File,
-    io::{AsyncBufReadExt, BufReader},
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt, BufReader},
 };
 
 const MAX_CONCURRENT_FILE_READ: usize = 5;
 
+/// Maximum number of bytes returned by `read_text_file` unless the server or the call sets
+/// another limit, about 64k tokens.
+pub const DEFAULT_READ_MAX_BYTES: usize = 256 * 1024;
+
+/// Default number of bytes returned by a single [`FileSystemService::read_file_chunk`] call.
+pub const DEFAULT_CHUNK_MAX_BYTES: usize = 64 * 1024;
+
+/// The beginning of a text file read by [`FileSystemService::read_text_file_limited`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextFileRead {
+    pub content: String,
+    /// Whether the content stops before the end of the file.
+    pub truncated: bool,
+    /// Size of the whole file in bytes.
+    pub total_bytes: u64,
+    /// Number of lines in `content`, counting a final partial line.
+    pub returned_lines: usize,
+}
+
+/// A byte range of a text file read by [`FileSystemService::read_file_byte_range`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ByteRangeRead {
+    pub content: String,
+    /// Offset of the first byte of `content`, moved past the rest of a character the requested
+    /// offset fell into.
+    pub start: u64,
+    /// Offset just past the last byte of `content`, where the next read resumes.
+    pub end: u64,
+    /// Size of the whole file in bytes.
+    pub total_bytes: u64,
+}
+
+/// Where the next chunk of a file starts, returned by [`FileSystemService::read_file_chunk`].
+#[derive(::serde::Serialize, ::serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct ChunkPosition {
+    /// Byte offset of the first byte not returned yet.
+    pub offset: u64,
+    /// 1-based number of the line at `offset`.
+    pub line: u64,
+    /// Modification time of the file when the position was returned, in nanoseconds since the
+    /// Unix epoch, used to detect changes between chunks.
+    pub modified: Option<u128>,
+}
+
+/// A chunk of a file read by [`FileSystemService::read_file_chunk`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileChunk {
+    pub content: String,
+    /// 1-based number of the line the chunk starts in.
+    pub start_line: u64,
+    /// Position of the next chunk.
+    pub position: ChunkPosition,
+    /// Size of the whole file in bytes.
+    pub total_bytes: u64,
+    /// True if the chunk reaches the end of the file.
+    pub eof: bool,
+}
+
+fn modified_nanos(metadata: &std::fs::Metadata) -> Option<u128> {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|duration| duration.as_nanos())
+}
+
+/// Returns whether `byte` continues a multi-byte UTF-8 character.
+fn is_utf8_continuation(byte: u8) -> bool {
+    byte & 0b1100_0000 == 0b1000_0000
+}
+
+/// Prefixes each line of `text` with its line number, starting at `first_line`, right-aligned
+/// and followed by a tab like `cat -n`. Line endings are kept.
+fn number_lines(text: &str, first_line: usize) -> String {
+    let line_count = text.split_inclusive('\n').count();
+    let width = (first_line + line_count.saturating_sub(1))
+        .to_string()
+        .len();
+    text.split_inclusive('\n')
+        .enumerate()
+        .map(|(index, line)| format!("{:>width$}\t{line}", first_line + index))
+        .collect()
+}
+
 impl FileSystemService {
     pub async fn read_text_file(&self, file_path: &Path) -> ServiceResult<String> {
         let allowed_directories = self.allowed_directories().await;
@@ -27,6 +117,82 @@ impl FileSystemService {
         Ok(content)
     }
 
+    /// Reads a text file, stopping after `max_bytes` (the server's limit by default) or
+    /// `max_lines` lines. The file is only read up to the limit. A content cut by `max_bytes`
+    /// ends at the last complete line, unless its first line alone is over the limit.
+    pub async fn read_text_file_limited(
+        &self,
+        file_path: &Path,
+        max_bytes: Option<usize>,
+        max_lines: Option<usize>,
+    ) -> ServiceResult<TextFileRead> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(file_path, allowed_directories)?;
+        let max_bytes = max_bytes.unwrap_or(self.read_max_bytes());
+
+        let file = File::open(&valid_path).await?;
+        let total_bytes = file.metadata().await?.len();
+        let mut buffer = Vec::with_capacity(max_bytes.min(total_bytes as usize) + 1);
+        // one byte past the limit tells whether the file was cut
+        file.take(
+            u64::try_from(max_bytes)
+                .unwrap_or(u64::MAX)
+                .saturating_add(1),
+        )
+        .read_to_end(&mut buffer)
+        .await?;
+        let mut truncated = buffer.len() > max_bytes;
+        buffer.truncate(max_bytes);
+
+        let mut content = match String::from_utf8(buffer) {
+            Ok(content) => content,
+            // the limit may fall in the middle of a multi-byte character
+            Err(err) if truncated && err.utf8_error().error_len().is_none() => {
+                let valid_up_to = err.utf8_error().valid_up_to();
+                let mut bytes = err.into_bytes();
+                bytes.truncate(valid_up_to);
+                String::from_utf8(bytes)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?
+            }
+            Err(err) => {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, err).into());
+            }
+        };
+        if truncated && let Some(last_newline) = content.rfind('\n') {
+            content.truncate(last_newline + 1);
+        }
+
+        if let Some(max_lines) = max_lines
+            && let Some((end, _)) = content.match_indices('\n').nth(max_lines.saturating_sub(1))
+            && end + 1 < content.len()
+        {
+            content.truncate(end + 1);
+            truncated = true;
+        }
+        if max_lines == Some(0) {
+            truncated = truncated || !content.is_empty();
+            content.clear();
+        }
+
+        Ok(TextFileRead {
+            returned_lines: content.lines().count(),
+            content,
+            truncated,
+            total_bytes,
+        })
+    }
+
+    /// Returns the lowercase hex SHA-256 digest of the whole content of `file_path`, as checked by
+    /// the `if_match_sha256` and `if_none_match_sha256` parameters of the tools.
+    pub async fn file_sha256(&self, file_path: &Path) -> ServiceResult<String> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(file_path, allowed_directories)?;
+        let hash = tokio::task::spawn_blocking(move || sha256_file_hex(&valid_path))
+            .await
+            .map_err(|e| std::io::Error::other(e.to_string()))??;
+        Ok(hash)
+    }
+
     /// Reads lines from a text file with flexible positioning options, preserving line endings.
     /// Args:
     ///     path: Path to the file
@@ -61,6 +227,11 @@ impl FileSystemService {
                 use std::fs::File;
                 use std::io::{BufRead, BufReader};
 
+                #[cfg(feature = "mmap")]
+                if let Some(map) = crate::fs_service::mmap::map_large_file(&valid_path_clone)? {
+                    return Ok(crate::fs_service::mmap::last_lines(&map, offset, limit)?);
+                }
+
                 // Read file content to detect line ending style
                 let file_content = std::fs::read(&valid_path_clone)?;
                 let has_crlf = file_content.windows(2).any(|w| w == b"\r\n");
@@ -104,7 +275,7 @@ impl FileSystemService {
                 Ok(result)
             })
             .await
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))??;
+            .map_err(|e| std::io::Error::other(e.to_string()))??;
 
             Ok(result)
         } else {
@@ -149,60 +320,285 @@ impl FileSystemService {
         }
     }
 
+    /// Same as [`Self::read_file_lines`], with each returned line prefixed by its absolute
+    /// (1-based) line number.
+    pub async fn read_file_lines_numbered(
+        &self,
+        path: &Path,
+        offset: usize,
+        limit: Option<usize>,
+        from_end: bool,
+    ) -> ServiceResult<String> {
+        let content = self.read_file_lines(path, offset, limit, from_end).await?;
+        if content.is_empty() {
+            return Ok(content);
+        }
+        let first_line = if from_end {
+            let allowed_directories = self.allowed_directories().await;
+            let valid_path = self.validate_path(path, allowed_directories)?;
+            let file_content = tokio::fs::read(&valid_path).await?;
+            let mut total_lines = file_content.iter().filter(|byte| **byte == b'\n').count();
+            if file_content.last().is_some_and(|byte| *byte != b'\n') {
+                total_lines += 1;
+            }
+            total_lines - offset - content.split_inclusive('\n').count() + 1
+        } else {
+            offset + 1
+        };
+        Ok(number_lines(&content, first_line))
+    }
+
+    /// Reads up to `byte_limit` bytes (to the end of the file by default) of a text file starting
+    /// at `byte_offset`, seeking there directly instead of counting lines. With `max_lines`, the
+    /// read also stops after that many lines. The range is adjusted so that it does not split a
+    /// UTF-8 character, the returned `end` is where to resume reading.
+    pub async fn read_file_byte_range(
+        &self,
+        path: &Path,
+        byte_offset: u64,
+        byte_limit: Option<u64>,
+        max_lines: Option<usize>,
+    ) -> ServiceResult<ByteRangeRead> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(path, allowed_directories)?;
+
+        let mut file = File::open(&valid_path).await?;
+        let total_bytes = file.metadata().await?.len();
+        let start = byte_offset.min(total_bytes);
+        file.seek(std::io::SeekFrom::Start(start)).await?;
+        let to_read = byte_limit.unwrap_or(u64::MAX).min(total_bytes - start);
+        let mut buffer = Vec::with_capacity(to_read as usize);
+        file.take(to_read).read_to_end(&mut buffer).await?;
+
+        // skip the rest of a character the offset fell into
+        let skipped = buffer
+            .iter()
+            .take(3)
+            .take_while(|byte| is_utf8_continuation(**byte))
+            .count();
+        buffer.drain(..skipped);
+        let start = start + skipped as u64;
+
+        if let Some(max_lines) = max_lines {
+            let end = match max_lines {
+                0 => Some(0),
+                _ => buffer
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, byte)| **byte == b'\n')
+                    .nth(max_lines - 1)
+                    .map(|(index, _)| index + 1),
+            };
+            if let Some(end) = end {
+                buffer.truncate(end);
+            }
+        }
+
+        let content = match String::from_utf8(buffer) {
+            Ok(content) => content,
+            // the limit may fall in the middle of a multi-byte character, which is then left for
+            // the next read
+            Err(err)
+                if err.utf8_error().error_len().is_none() && err.utf8_error().valid_up_to() > 0 =>
+            {
+                let valid_up_to = err.utf8_error().valid_up_to();
+                let mut bytes = err.into_bytes();
+                bytes.truncate(valid_up_to);
+                String::from_utf8(bytes)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?
+            }
+            Err(err) => String::from_utf8_lossy(err.as_bytes()).into_owned(),
+        };
+        Ok(ByteRangeRead {
+            end: start + content.len() as u64,
+            content,
+            start,
+            total_bytes,
+        })
+    }
+
+    /// Reads the chunk of a text file starting at `from` (the beginning of the file by default):
+    /// at most `max_bytes` bytes, and with `max_lines` at most that many lines, ending on a
+    /// line boundary unless a single line exceeds `max_bytes`. Pass the returned position back
+    /// to read the next chunk. Fails if the file was modified since `from` was returned.
+    pub async fn read_file_chunk(
+        &self,
+        path: &Path,
+        from: Option<ChunkPosition>,
+        max_bytes: Option<usize>,
+        max_lines: Option<usize>,
+    ) -> ServiceResult<FileChunk> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(path, allowed_directories)?;
+        let metadata = tokio::fs::metadata(&valid_path).await?;
+        if !metadata.is_file() {
+            return Err(ServiceError::InvalidArgument(format!(
+                "'{}' is not a file.",
+                path.display()
+            )));
+        }
+        let modified = modified_nanos(&metadata);
+        let (offset, start_line) = match from {
+            Some(from) if from.modified != modified || from.offset > metadata.len() => {
+                return Err(ServiceError::FromString(format!(
+                    "'{}' was modified since the cursor was returned, read it again from the start.",
+                    path.display()
+                )));
+            }
+            Some(from) => (from.offset, from.line),
+            None => (0, 1),
+        };
+
+        let max_bytes = max_bytes.unwrap_or(DEFAULT_CHUNK_MAX_BYTES).max(1) as u64;
+        let mut read = self
+            .read_file_byte_range(&valid_path, offset, Some(max_bytes), max_lines)
+            .await?;
+        // end on a line boundary, unless the chunk holds less than one line
+        if max_lines.is_some()
+            && read.end < read.total_bytes
+            && !read.content.ends_with('\n')
+            && let Some(index) = read.content.rfind('\n')
+        {
+            read.content.truncate(index + 1);
+            read.end = read.start + read.content.len() as u64;
+        }
+
+        let lines = read.content.matches('\n').count() as u64;
+        Ok(FileChunk {
+            start_line,
+            position: ChunkPosition {
+                offset: read.end,
+                line: start_line + lines,
+                modified,
+            },
+            total_bytes: read.total_bytes,
+            eof: read.end >= read.total_bytes,
+            content: read.content,
+        })
+    }
+
+    /// Reads a JSON file and evaluates a JSONPath expression (RFC 9535) against it.
+    /// Returns the selected nodes in document order; an empty vector means nothing matched.
+    pub async fn read_json_path(&self, file_path: &Path, query: &str) -> ServiceResult<Vec<Value>> {
+        // Parse the query before touching the file so syntax errors are reported first
+        let json_path = JsonPath::parse(query)?;
+
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(file_path, allowed_directories)?;
+        let content = tokio::fs::read(valid_path).await?;
+
+        let document: Value = serde_json::from_slice(&content)?;
+        Ok(json_path
+            .query(&document)
+            .all()
+            .into_iter()
+            .cloned()
+            .collect())
+    }
+
+    /// Reads several media files, returning the result of each path in the order of `paths`,
+    /// so that a failed read does not hide the others.
     pub async fn read_media_files(
         &self,
         paths: Vec<String>,
         max_bytes: Option<usize>,
-    ) -> ServiceResult<Vec<(infer::Type, String)>> {
-        let results = stream::iter(paths)
+    ) -> Vec<(String, ServiceResult<(infer::Type, String)>)> {
+        stream::iter(paths)
+            .map(|path| async {
+                let result = self.read_media_file(Path::new(&path), max_bytes).await;
+                (path, result)
+            })
+            .buffered(MAX_CONCURRENT_FILE_READ) // Process up to MAX_CONCURRENT_FILE_READ files concurrently
+            .collect::<Vec<_>>()
+            .await
+    }
+
+    /// Reads several text files, returning the result of each path in the order of `paths`,
+    /// so that a failed read does not hide the others.
+    pub async fn read_text_files(
+        &self,
+        paths: Vec<String>,
+    ) -> Vec<(String, ServiceResult<String>)> {
+        stream::iter(paths)
             .map(|path| async {
-                self.read_media_file(Path::new(&path), max_bytes)
-                    .await
-                    .map_err(|e| (path, e))
+                let result = self.read_text_file(Path::new(&path)).await;
+                (path, result)
             })
-            .buffer_unordered(MAX_CONCURRENT_FILE_READ) // Process up to MAX_CONCURRENT_FILE_READ files concurrently
-            .filter_map(|result| async move { result.ok() })
+            .buffered(MAX_CONCURRENT_FILE_READ)
             .collect::<Vec<_>>()
-            .await;
-        Ok(results)
+            .await
     }
 
     pub async fn read_media_file(
         &self,
         file_path: &Path,
         max_bytes: Option<usize>,
+    ) -> ServiceResult<(infer::Type, String)> {
+        self.read_media_file_with_options(file_path, max_bytes, None)
+            .await
+    }
+
+    /// Reads a media file as base64, optionally downscaling and re-encoding images first.
+    /// When `resize` is provided for a raster image, `max_bytes` applies to the re-encoded
+    /// image rather than the original file.
+    pub async fn read_media_file_with_options(
+        &self,
+        file_path: &Path,
+        max_bytes: Option<usize>,
+        resize: Option<&ImageResizeOptions>,
     ) -> ServiceResult<(infer::Type, String)> {
         let allowed_directories = self.allowed_directories().await;
         let valid_path = self.validate_path(file_path, allowed_directories)?;
-        validate_file_size(&valid_path, None, max_bytes).await?;
         let kind = mime_from_path(&valid_path)?;
+
+        if let Some(resize) = resize
+            && kind.matcher_type() == infer::MatcherType::Image
+            && kind.extension() != "svg"
+        {
+            let resize = resize.clone();
+            let bytes = tokio::task::spawn_blocking(move || resize_image(&valid_path, &resize))
+                .await
+                .map_err(|e| std::io::Error::other(e.to_string()))??;
+            if let Some(max_bytes) = max_bytes
+                && bytes.len() > max_bytes
+            {
+                return Err(ServiceError::FileTooLarge(max_bytes));
+            }
+            let kind = infer::get(&bytes).unwrap_or(kind);
+            return Ok((kind, general_purpose::STANDARD.encode(bytes)));
+        }
+
+        validate_file_size(&valid_path, None, max_bytes).await?;
         let content = read_file_as_base64(&valid_path).await?;
         Ok((kind, content))
     }
 
-    // Get file stats
-    pub async fn get_file_stats(&self, file_path: &Path) -> ServiceResult<FileInfo> {
+    /// Returns the metadata of `file_path`. With `follow_symlink` a symlink is described by its
+    /// target, otherwise by the link itself, whose target then only needs to exist to be reported.
+    pub async fn get_file_stats(
+        &self,
+        file_path: &Path,
+        follow_symlink: bool,
+    ) -> ServiceResult<FileInfo> {
         let allowed_directories = self.allowed_directories().await;
-        let valid_path = self.validate_path(file_path, allowed_directories)?;
+        let valid_path = if follow_symlink {
+            self.validate_path(file_path, allowed_directories)?
+        } else {
+            // the link itself must be in an allowed directory, wherever it points to
+            match (file_path.parent(), file_path.file_name()) {
+                (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => {
+                    self.validate_path(parent, allowed_directories)?.join(name)
+                }
+                _ => self.validate_path(file_path, allowed_directories)?,
+            }
+        };
 
-        let metadata = std::fs::metadata(valid_path)?;
-
-        let size = metadata.len();
-        let created = metadata.created().ok();
-        let modified = metadata.modified().ok();
-        let accessed = metadata.accessed().ok();
-        let is_directory = metadata.is_dir();
-        let is_file = metadata.is_file();
-
-        Ok(FileInfo {
-            size,
-            created,
-            modified,
-            accessed,
-            is_directory,
-            is_file,
-            metadata,
-        })
+        let metadata = if follow_symlink {
+            std::fs::metadata(&valid_path)?
+        } else {
+            std::fs::symlink_metadata(&valid_path)?
+        };
+        Ok(FileInfo::new(&valid_path, metadata))
     }
 }
 
@@ -214,9 +610,65 @@ pub struct FileInfo {
     pub accessed: Option<SystemTime>,
     pub is_directory: bool,
     pub is_file: bool,
+    pub is_symlink: bool,
+    /// Where the symlink points to, when the metadata is the link's own.
+    pub symlink_target: Option<PathBuf>,
+    /// MIME type of a file, detected from its content.
+    pub mime_type: Option<String>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub user: Option<String>,
+    pub group: Option<String>,
+    pub inode: Option<u64>,
+    pub device: Option<u64>,
+    pub hard_links: Option<u64>,
     pub metadata: fs::Metadata,
 }
 
+impl FileInfo {
+    /// Builds the info of the file at `path` from its `metadata`, which may be a symlink's own.
+    pub fn new(path: &Path, metadata: fs::Metadata) -> Self {
+        let is_symlink = metadata.file_type().is_symlink();
+        #[cfg(unix)]
+        let (uid, gid, inode, device, hard_links) = {
+            use std::os::unix::fs::MetadataExt;
+            (
+                Some(metadata.uid()),
+                Some(metadata.gid()),
+                Some(metadata.ino()),
+                Some(metadata.dev()),
+                Some(metadata.nlink()),
+            )
+        };
+        #[cfg(not(unix))]
+        let (uid, gid, inode, device, hard_links) = (None, None, None, None, None);
+        #[cfg(unix)]
+        let (user, group) = (uid.and_then(user_name), gid.and_then(group_name));
+        #[cfg(not(unix))]
+        let (user, group) = (None, None);
+
+        Self {
+            size: metadata.len(),
+            created: metadata.created().ok(),
+            modified: metadata.modified().ok(),
+            accessed: metadata.accessed().ok(),
+            is_directory: metadata.is_dir(),
+            is_file: metadata.is_file(),
+            is_symlink,
+            symlink_target: is_symlink.then(|| fs::read_link(path).ok()).flatten(),
+            mime_type: metadata.is_file().then(|| detect_mime_type(path)).flatten(),
+            uid,
+            gid,
+            user,
+            group,
+            inode,
+            device,
+            hard_links,
+            metadata,
+        }
+    }
+}
+
 impl std::fmt::Display for FileInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -236,6 +688,31 @@ permissions: {}
             self.is_directory,
             self.is_file,
             format_permissions(&self.metadata)
-        )
+        )?;
+        if self.is_symlink {
+            writeln!(f, "isSymlink: true")?;
+        }
+        if let Some(target) = &self.symlink_target {
+            writeln!(f, "symlinkTarget: {}", target.display())?;
+        }
+        if let Some(mime_type) = &self.mime_type {
+            writeln!(f, "mimeType: {mime_type}")?;
+        }
+        if let Some(uid) = self.uid {
+            writeln!(f, "owner: {} ({uid})", self.user.as_deref().unwrap_or("?"))?;
+        }
+        if let Some(gid) = self.gid {
+            writeln!(f, "group: {} ({gid})", self.group.as_deref().unwrap_or("?"))?;
+        }
+        if let Some(inode) = self.inode {
+            writeln!(f, "inode: {inode}")?;
+        }
+        if let Some(device) = self.device {
+            writeln!(f, "device: {device}")?;
+        }
+        if let Some(hard_links) = self.hard_links {
+            writeln!(f, "hardLinks: {hard_links}")?;
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,112 @@
+use crate::{
+    error::{ServiceError, ServiceResult},
+    fs_service::FileSystemService,
+};
+use rust_mcp_sdk::macros::JsonSchema;
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// Formats supported by [`FileSystemService::read_structured_file`].
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub enum StructuredFormat {
+    #[serde(rename = "json")]
+    Json,
+    #[serde(rename = "yaml")]
+    Yaml,
+    #[serde(rename = "toml")]
+    Toml,
+}
+
+impl StructuredFormat {
+    /// Detects the format from the file extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+
+    /// Parses `content` into a JSON value.
+    pub fn parse(&self, content: &str) -> ServiceResult<Value> {
+        match self {
+            Self::Json => Ok(serde_json::from_str(content)?),
+            Self::Yaml => serde_yaml::from_str(content)
+                .map_err(|err| ServiceError::FromString(format!("Invalid YAML: {err}"))),
+            Self::Toml => toml::from_str::<toml::Table>(content)
+                .map(|table| toml_to_json(toml::Value::Table(table)))
+                .map_err(|err| ServiceError::FromString(format!("Invalid TOML: {err}"))),
+        }
+    }
+}
+
+/// Converts a TOML value to JSON, rendering datetimes as RFC 3339 strings.
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(dt) => Value::String(dt.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(k, v)| (k, toml_to_json(v)))
+                .collect::<Map<String, Value>>(),
+        ),
+    }
+}
+
+/// Selects a value using a dotted path such as `package.name` or `servers.0.port`.
+/// Numeric segments index into arrays; any other segment is treated as an object key.
+pub fn select_dotted_path<'a>(value: &'a Value, dotted_path: &str) -> ServiceResult<&'a Value> {
+    let mut current = value;
+    for segment in dotted_path.split('.').filter(|s| !s.is_empty()) {
+        let next = match current {
+            Value::Object(map) => map.get(segment),
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        };
+        current = next.ok_or_else(|| {
+            ServiceError::NotFound(format!(
+                "Path '{dotted_path}' not found: no value at segment '{segment}'."
+            ))
+        })?;
+    }
+    Ok(current)
+}
+
+impl FileSystemService {
+    /// Reads a JSON, YAML or TOML file and returns it as a JSON value.
+    /// The format is detected from the file extension unless `format` is provided.
+    /// If `dotted_path` is set, only the selected value is returned.
+    pub async fn read_structured_file(
+        &self,
+        file_path: &Path,
+        format: Option<StructuredFormat>,
+        dotted_path: Option<&str>,
+    ) -> ServiceResult<Value> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(file_path, allowed_directories)?;
+
+        let format = format
+            .or_else(|| StructuredFormat::from_path(&valid_path))
+            .ok_or_else(|| {
+                ServiceError::InvalidArgument(format!(
+                    "Unable to detect the format of '{}'. Please specify one of: json, yaml, toml.",
+                    file_path.display()
+                ))
+            })?;
+
+        let content = tokio::fs::read_to_string(&valid_path).await?;
+        let document = format.parse(&content)?;
+
+        match dotted_path {
+            Some(dotted_path) => Ok(select_dotted_path(&document, dotted_path)?.clone()),
+            None => Ok(document),
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use crate::{
+    error::{ServiceError, ServiceResult},
+    fs_service::FileSystemService,
+};
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+/// Number of rows read from the start of the file to infer column types and estimate the row count.
+const SAMPLE_ROWS: usize = 1000;
+/// Initial and maximum number of bytes read from the end of the file to collect the last rows.
+const TAIL_CHUNK_SIZE: u64 = 64 * 1024;
+const MAX_TAIL_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+const DELIMITER_CANDIDATES: [u8; 4] = [b',', b'\t', b';', b'|'];
+
+#[derive(::serde::Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnType {
+    /// No non-empty value was seen in the sample.
+    Empty,
+    Boolean,
+    Integer,
+    Float,
+    Date,
+    DateTime,
+    String,
+}
+
+impl ColumnType {
+    fn of(value: &str) -> Self {
+        let value = value.trim();
+        if value.is_empty() {
+            Self::Empty
+        } else if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+            Self::Boolean
+        } else if value.parse::<i64>().is_ok() {
+            Self::Integer
+        } else if value.parse::<f64>().is_ok() {
+            Self::Float
+        } else if chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok() {
+            Self::Date
+        } else if chrono::DateTime::parse_from_rfc3339(value).is_ok()
+            || chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").is_ok()
+            || chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S").is_ok()
+        {
+            Self::DateTime
+        } else {
+            Self::String
+        }
+    }
+
+    /// Combines the types of two values of the same column.
+    fn merge(self, other: Self) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Self::Empty, t) | (t, Self::Empty) => t,
+            (Self::Integer, Self::Float) | (Self::Float, Self::Integer) => Self::Float,
+            (Self::Date, Self::DateTime) | (Self::DateTime, Self::Date) => Self::DateTime,
+            _ => Self::String,
+        }
+    }
+}
+
+#[derive(::serde::Serialize, Debug)]
+pub struct CsvColumn {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub column_type: ColumnType,
+}
+
+/// Summary of a CSV/TSV file returned by [`FileSystemService::preview_csv`].
+#[derive(::serde::Serialize, Debug)]
+pub struct CsvPreview {
+    pub path: PathBuf,
+    pub delimiter: String,
+    pub columns: Vec<CsvColumn>,
+    /// Number of data rows, excluding the header.
+    pub row_count: u64,
+    /// False when `row_count` is an estimate based on the average size of the sampled rows.
+    pub row_count_exact: bool,
+    pub first_rows: Vec<Vec<String>>,
+    pub last_rows: Vec<Vec<String>>,
+}
+
+/// Picks the candidate delimiter that occurs most often in the first line of `sample`.
+fn detect_delimiter(path: &Path, sample: &[u8]) -> u8 {
+    if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("tsv"))
+    {
+        return b'\t';
+    }
+    let first_line = sample.split(|b| *b == b'\n').next().unwrap_or_default();
+    DELIMITER_CANDIDATES
+        .into_iter()
+        .map(|d| (d, first_line.iter().filter(|b| **b == d).count()))
+        .filter(|(_, count)| *count > 0)
+        .max_by_key(|(_, count)| *count)
+        .map_or(b',', |(d, _)| d)
+}
+
+fn record_to_row(record: &csv::StringRecord) -> Vec<String> {
+    record.iter().map(|field| field.to_string()).collect()
+}
+
+/// Reads the last `count` records of the file, starting no earlier than `min_offset`
+/// (a record boundary). Reads progressively larger chunks from the end of the file.
+fn read_last_rows(
+    file: &mut File,
+    file_size: u64,
+    min_offset: u64,
+    delimiter: u8,
+    count: usize,
+) -> ServiceResult<Vec<Vec<String>>> {
+    let mut chunk_size = TAIL_CHUNK_SIZE;
+    loop {
+        let start = file_size.saturating_sub(chunk_size).max(min_offset);
+        file.seek(SeekFrom::Start(start))?;
+        let mut buffer = Vec::with_capacity((file_size - start) as usize);
+        file.take(file_size - start).read_to_end(&mut buffer)?;
+
+        // Unless we're at a known record boundary, drop the first (likely partial) line
+        let data = if start == min_offset {
+            &buffer[..]
+        } else {
+            match buffer.iter().position(|b| *b == b'\n') {
+                Some(pos) => &buffer[pos + 1..],
+                None => &[],
+            }
+        };
+
+        let mut rows: VecDeque<Vec<String>> = VecDeque::with_capacity(count + 1);
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(data);
+        for record in reader.records().filter_map(|r| r.ok()) {
+            rows.push_back(record_to_row(&record));
+            if rows.len() > count {
+                rows.pop_front();
+            }
+        }
+
+        if rows.len() >= count || start == min_offset || chunk_size >= MAX_TAIL_CHUNK_SIZE {
+            return Ok(rows.into());
+        }
+        chunk_size *= 4;
+    }
+}
+
+impl FileSystemService {
+    /// Returns the columns, inferred column types, row count and the first/last rows of a
+    /// CSV or TSV file.
+    ///
+    /// Only the first rows (up to 1000) and a chunk at the end of the file are read. For
+    /// larger files the row count is estimated from the average size of the sampled rows.
+    /// The delimiter is detected from the file extension and the header line unless provided.
+    pub async fn preview_csv(
+        &self,
+        file_path: &Path,
+        delimiter: Option<u8>,
+        has_header: bool,
+        head: usize,
+        tail: usize,
+    ) -> ServiceResult<CsvPreview> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(file_path, allowed_directories)?;
+
+        tokio::task::spawn_blocking(move || -> ServiceResult<CsvPreview> {
+            let mut file = File::open(&valid_path)?;
+            let file_size = file.metadata()?.len();
+
+            let delimiter = match delimiter {
+                Some(delimiter) => delimiter,
+                None => {
+                    let mut sample = Vec::new();
+                    (&mut file).take(8 * 1024).read_to_end(&mut sample)?;
+                    file.seek(SeekFrom::Start(0))?;
+                    detect_delimiter(&valid_path, &sample)
+                }
+            };
+
+            let mut reader = csv::ReaderBuilder::new()
+                .delimiter(delimiter)
+                .has_headers(has_header)
+                .flexible(true)
+                .from_reader(BufReader::new(&mut file));
+
+            let header = if has_header {
+                Some(record_to_row(reader.headers().map_err(|err| {
+                    ServiceError::FromString(format!("Unable to read CSV header: {err}"))
+                })?))
+            } else {
+                None
+            };
+            let data_start = reader.position().byte();
+
+            let mut column_types: Vec<ColumnType> = Vec::new();
+            let mut first_rows = Vec::new();
+            let mut last_rows: VecDeque<Vec<String>> = VecDeque::new();
+            let mut sampled_rows: u64 = 0;
+            let mut reached_eof = true;
+
+            let mut sample_end = data_start;
+            let mut record = csv::StringRecord::new();
+            loop {
+                if sampled_rows as usize >= SAMPLE_ROWS {
+                    // Stop sampling unless the file is exhausted
+                    reached_eof = !reader.read_record(&mut record).is_ok_and(|more| more);
+                    break;
+                }
+                match reader.read_record(&mut record) {
+                    Ok(true) => {}
+                    Ok(false) => break,
+                    Err(err) => {
+                        return Err(ServiceError::FromString(format!(
+                            "Unable to parse CSV record {}: {err}",
+                            sampled_rows + 1
+                        )));
+                    }
+                }
+                sampled_rows += 1;
+                sample_end = reader.position().byte();
+
+                if column_types.len() < record.len() {
+                    column_types.resize(record.len(), ColumnType::Empty);
+                }
+                for (column_type, value) in column_types.iter_mut().zip(record.iter()) {
+                    *column_type = column_type.merge(ColumnType::of(value));
+                }
+
+                let row = record_to_row(&record);
+                if first_rows.len() < head {
+                    first_rows.push(row.clone());
+                }
+                if tail > 0 {
+                    last_rows.push_back(row);
+                    if last_rows.len() > tail {
+                        last_rows.pop_front();
+                    }
+                }
+            }
+            drop(reader);
+
+            let (row_count, last_rows) = if reached_eof {
+                (sampled_rows, last_rows.into())
+            } else {
+                let average_row_size = (sample_end - data_start) as f64 / sampled_rows as f64;
+                let estimate = ((file_size - data_start) as f64 / average_row_size).round() as u64;
+                let last_rows = if tail > 0 {
+                    read_last_rows(&mut file, file_size, sample_end, delimiter, tail)?
+                } else {
+                    vec![]
+                };
+                (estimate, last_rows)
+            };
+
+            let column_count = column_types
+                .len()
+                .max(header.as_ref().map_or(0, |h| h.len()));
+            let columns = (0..column_count)
+                .map(|i| CsvColumn {
+                    name: header
+                        .as_ref()
+                        .and_then(|h| h.get(i).cloned())
+                        .unwrap_or_else(|| format!("column_{}", i + 1)),
+                    column_type: column_types.get(i).copied().unwrap_or(ColumnType::Empty),
+                })
+                .collect();
+
+            Ok(CsvPreview {
+                path: valid_path,
+                delimiter: (delimiter as char).to_string(),
+                columns,
+                row_count,
+                row_count_exact: reached_eof,
+                first_rows,
+                last_rows,
+            })
+        })
+        .await
+        .map_err(|e| std::io::Error::other(e.to_string()))?
+    }
+}
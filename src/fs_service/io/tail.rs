@@ -0,0 +1,141 @@
+use crate::{
+    error::{ServiceError, ServiceResult},
+    fs_service::FileSystemService,
+};
+use std::{io::SeekFrom, path::Path};
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt},
+};
+
+/// Default number of bytes returned by a single [`FileSystemService::tail_file`] call.
+pub const DEFAULT_TAIL_MAX_BYTES: usize = 64 * 1024;
+
+/// Bytes read from the end of the file to find the initial lines of a tail.
+const INITIAL_TAIL_WINDOW: u64 = 64 * 1024;
+
+/// A read position within a followed file, returned by [`FileSystemService::tail_file`].
+#[derive(::serde::Serialize, ::serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct TailPosition {
+    /// Byte offset of the first byte not returned yet.
+    pub offset: u64,
+    /// Identity of the file (inode on unix), used to detect rotation.
+    pub file_id: Option<u64>,
+}
+
+/// Content appended to a file since a previous [`TailPosition`].
+#[derive(Debug, Clone)]
+pub struct TailChunk {
+    pub content: String,
+    /// Position to pass to the next call to continue reading.
+    pub position: TailPosition,
+    /// True if the file was truncated or replaced since the previous position,
+    /// in which case reading restarted from the beginning of the file.
+    pub rotated: bool,
+    /// True if more content is available beyond the returned chunk.
+    pub has_more: bool,
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &std::fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &std::fs::Metadata) -> Option<u64> {
+    None
+}
+
+impl FileSystemService {
+    /// Reads content appended to a file since `from`, returning at most `max_bytes` bytes of whole
+    /// lines. Without a previous position, the last `initial_lines` lines are returned.
+    ///
+    /// Intended for polling growing files such as logs: pass the returned position back on the
+    /// next call to receive only the new content.
+    pub async fn tail_file(
+        &self,
+        path: &Path,
+        from: Option<TailPosition>,
+        initial_lines: usize,
+        max_bytes: Option<usize>,
+    ) -> ServiceResult<TailChunk> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(path, allowed_directories)?;
+        let max_bytes = max_bytes.unwrap_or(DEFAULT_TAIL_MAX_BYTES).max(1);
+
+        let mut file = File::open(&valid_path).await?;
+        let metadata = file.metadata().await?;
+        if !metadata.is_file() {
+            return Err(ServiceError::InvalidArgument(format!(
+                "'{}' is not a file.",
+                path.display()
+            )));
+        }
+        let size = metadata.len();
+        let file_id = file_id(&metadata);
+
+        let Some(from) = from else {
+            // first call: return the last lines and continue from the current end
+            let start = size.saturating_sub(INITIAL_TAIL_WINDOW);
+            file.seek(SeekFrom::Start(start)).await?;
+            let mut buffer = Vec::with_capacity((size - start) as usize);
+            (&mut file)
+                .take(size - start)
+                .read_to_end(&mut buffer)
+                .await?;
+
+            let text = String::from_utf8_lossy(&buffer);
+            let trimmed = text.strip_suffix('\n').unwrap_or(&text);
+            let content = if initial_lines == 0 {
+                String::new()
+            } else {
+                let begin = trimmed
+                    .rmatch_indices('\n')
+                    .nth(initial_lines - 1)
+                    .map_or(0, |(index, _)| index + 1);
+                text[begin..].to_string()
+            };
+            return Ok(TailChunk {
+                content,
+                position: TailPosition {
+                    offset: size,
+                    file_id,
+                },
+                rotated: false,
+                has_more: false,
+            });
+        };
+
+        let rotated = size < from.offset || (from.file_id.is_some() && from.file_id != file_id);
+        let offset = if rotated { 0 } else { from.offset };
+
+        file.seek(SeekFrom::Start(offset)).await?;
+        let available = size - offset;
+        let mut buffer = Vec::with_capacity(available.min(max_bytes as u64) as usize);
+        (&mut file)
+            .take(available.min(max_bytes as u64))
+            .read_to_end(&mut buffer)
+            .await?;
+
+        // only return whole lines, unless a single line exceeds the budget
+        if !buffer.ends_with(b"\n") {
+            if let Some(last_newline) = buffer.iter().rposition(|byte| *byte == b'\n') {
+                buffer.truncate(last_newline + 1);
+            } else if buffer.len() < max_bytes {
+                buffer.clear();
+            }
+        }
+        let next_offset = offset + buffer.len() as u64;
+
+        Ok(TailChunk {
+            content: String::from_utf8_lossy(&buffer).into_owned(),
+            position: TailPosition {
+                offset: next_offset,
+                file_id,
+            },
+            rotated,
+            has_more: next_offset < size,
+        })
+    }
+}
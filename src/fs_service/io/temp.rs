@@ -0,0 +1,128 @@
+use crate::{
+    error::{ServiceError, ServiceResult},
+    fs_service::{FileSystemService, journal::JournalChange},
+};
+use rust_mcp_sdk::macros::JsonSchema;
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime},
+};
+
+/// Attempts at finding a free name before giving up.
+pub(crate) const MAX_ATTEMPTS: usize = 64;
+
+/// What [`FileSystemService::create_temp_path`] creates.
+#[derive(
+    ::serde::Deserialize, ::serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema,
+)]
+pub enum TempPathKind {
+    /// An empty file.
+    #[default]
+    #[serde(rename = "file")]
+    File,
+    /// An empty directory.
+    #[serde(rename = "directory")]
+    Directory,
+}
+
+/// Returns 12 hex digits that differ between calls and between processes.
+pub(crate) fn random_token() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    hasher.write_u32(std::process::id());
+    if let Ok(elapsed) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        hasher.write_u128(elapsed.as_nanos());
+    }
+    format!("{:012x}", hasher.finish() & 0xffff_ffff_ffff)
+}
+
+fn validate_affix(affix: &str) -> ServiceResult<()> {
+    if affix.contains(['/', '\\', '\0']) || affix == "." || affix == ".." {
+        return Err(ServiceError::InvalidArgument(format!(
+            "Invalid temporary name affix '{affix}', it must not contain path separators."
+        )));
+    }
+    Ok(())
+}
+
+impl FileSystemService {
+    /// Creates an empty file or directory with a unique name `{prefix}{random}{suffix}` inside
+    /// `directory` and returns its path. With a `ttl`, the path is removed once it has elapsed,
+    /// unless the server stops first.
+    pub async fn create_temp_path(
+        &self,
+        directory: &Path,
+        kind: TempPathKind,
+        prefix: &str,
+        suffix: &str,
+        ttl: Option<Duration>,
+    ) -> ServiceResult<PathBuf> {
+        validate_affix(prefix)?;
+        validate_affix(suffix)?;
+        let allowed_directories = self.allowed_directories().await;
+        let valid_directory = self.validate_write_path(directory, allowed_directories.clone())?;
+        if !valid_directory.is_dir() {
+            return Err(ServiceError::InvalidArgument(format!(
+                "'{}' is not a directory.",
+                directory.display()
+            )));
+        }
+
+        let mut created = None;
+        for _ in 0..MAX_ATTEMPTS {
+            let candidate = valid_directory.join(format!("{prefix}{}{suffix}", random_token()));
+            // the name is checked like any other path, e.g. against the deny list
+            let candidate = self.validate_write_path(&candidate, allowed_directories.clone())?;
+            let result = match kind {
+                TempPathKind::File => tokio::fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(&candidate)
+                    .await
+                    .map(|_| ()),
+                TempPathKind::Directory => tokio::fs::create_dir(&candidate).await,
+            };
+            match result {
+                Ok(()) => {
+                    created = Some(candidate);
+                    break;
+                }
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        let path = created.ok_or_else(|| {
+            ServiceError::FromString(format!(
+                "Failed to find a free temporary name in '{}'.",
+                directory.display()
+            ))
+        })?;
+        self.journal_record(
+            "create_temp_path",
+            vec![match kind {
+                TempPathKind::File => JournalChange::Write {
+                    path: path.clone(),
+                    backup: None,
+                },
+                TempPathKind::Directory => JournalChange::CreateDirectory { path: path.clone() },
+            }],
+        );
+
+        if let Some(ttl) = ttl {
+            let expired = path.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(ttl).await;
+                let _ = match kind {
+                    TempPathKind::File => tokio::fs::remove_file(&expired).await,
+                    TempPathKind::Directory => tokio::fs::remove_dir_all(&expired).await,
+                };
+            });
+        }
+        Ok(path)
+    }
+}
@@ -1,26 +1,554 @@
-use crate::{error::ServiceResult, fs_service::FileSystemService};
-use std::path::Path;
+use crate::{
+    error::{ServiceError, ServiceResult},
+    fs_service::{
+        FileSystemService, WalkOptions,
+        io::temp::{MAX_ATTEMPTS, random_token},
+        journal::{JournalChange, missing_directories},
+        search::glob_utils::compile_exclude_glob,
+        utils::sha256_hex,
+    },
+};
+use ignore::WalkBuilder;
+use rust_mcp_sdk::macros::JsonSchema;
+use std::path::{Component, Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+/// New content of a file, ready to replace its current content with [`Self::commit`].
+pub(crate) enum StagedFile {
+    /// The content was flushed to `staged`, next to `target`, and is renamed over it.
+    Staged { staged: PathBuf, target: PathBuf },
+    /// The content is written to `target` in place, for files a rename would detach from their
+    /// other hard links or owner, and when atomic writes are disabled.
+    InPlace { target: PathBuf, content: Vec<u8> },
+}
+
+impl StagedFile {
+    /// Replaces the content of the file. The staging file is removed if that fails.
+    pub(crate) async fn commit(&self) -> std::io::Result<()> {
+        match self {
+            StagedFile::Staged { staged, target } => {
+                let renamed = tokio::fs::rename(staged, target).await;
+                if renamed.is_err() {
+                    let _ = tokio::fs::remove_file(staged).await;
+                }
+                renamed
+            }
+            StagedFile::InPlace { target, content } => tokio::fs::write(target, content).await,
+        }
+    }
+
+    /// Drops the new content without touching the file.
+    pub(crate) async fn discard(&self) {
+        if let StagedFile::Staged { staged, .. } = self {
+            let _ = tokio::fs::remove_file(staged).await;
+        }
+    }
+}
+
+/// Returns the file a write to `path` modifies: the target of `path` if it is a symlink, so
+/// that the symlink is written through rather than replaced.
+async fn write_target(path: &Path) -> PathBuf {
+    match tokio::fs::symlink_metadata(path).await {
+        Ok(metadata) if metadata.is_symlink() => tokio::fs::canonicalize(path)
+            .await
+            .unwrap_or_else(|_| path.to_path_buf()),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Creates a staging file with a unique name next to `target`, so that renaming it over
+/// `target` stays on the same filesystem.
+async fn create_staging_file(target: &Path) -> std::io::Result<(tokio::fs::File, PathBuf)> {
+    let file_name = target.file_name().unwrap_or_default().to_string_lossy();
+    for _ in 0..MAX_ATTEMPTS {
+        let staged = target.with_file_name(format!(".{file_name}.{}.mcp-tmp", random_token()));
+        match tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&staged)
+            .await
+        {
+            Ok(file) => return Ok((file, staged)),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Err(std::io::Error::new(
+        std::io::ErrorKind::AlreadyExists,
+        format!("no free staging name next to '{}'", target.display()),
+    ))
+}
+
+/// Gives the staging file the permissions and, on unix, the owner of the file it replaces.
+/// Returns false if the file cannot be replaced without losing its owner or hard links.
+async fn copy_file_attributes(
+    staged: &Path,
+    metadata: &std::fs::Metadata,
+) -> std::io::Result<bool> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        if metadata.nlink() > 1
+            || std::os::unix::fs::chown(staged, Some(metadata.uid()), Some(metadata.gid())).is_err()
+        {
+            return Ok(false);
+        }
+    }
+    tokio::fs::set_permissions(staged, metadata.permissions()).await?;
+    Ok(true)
+}
 
 impl FileSystemService {
-    pub async fn write_file(&self, file_path: &Path, content: &String) -> ServiceResult<()> {
-        let allowed_directories = self.allowed_directories().await;
-        let valid_path = self.validate_path(file_path, allowed_directories)?;
-        tokio::fs::write(valid_path, content).await?;
+    /// Prepares `content` to replace the content of the file at `path`. Unless atomic writes
+    /// are disabled, it is written and flushed to a staging file next to the file, which
+    /// [`StagedFile::commit`] renames over it so the file never ends up partially written.
+    /// Symlinks are written through, and files with other hard links or an owner that cannot
+    /// be kept are written in place.
+    pub(crate) async fn stage_file(
+        &self,
+        path: &Path,
+        content: &[u8],
+    ) -> ServiceResult<StagedFile> {
+        let target = write_target(path).await;
+        let in_place = |target: PathBuf| StagedFile::InPlace {
+            target,
+            content: content.to_vec(),
+        };
+        if !self.atomic_writes() {
+            return Ok(in_place(target));
+        }
+        let metadata = tokio::fs::metadata(&target).await.ok();
+        let (mut file, staged) = create_staging_file(&target).await?;
+        let written = async {
+            if let Some(metadata) = &metadata
+                && !copy_file_attributes(&staged, metadata).await?
+            {
+                return Ok(false);
+            }
+            file.write_all(content).await?;
+            file.sync_all().await?;
+            Ok::<_, std::io::Error>(true)
+        }
+        .await;
+        match written {
+            Ok(true) => Ok(StagedFile::Staged { staged, target }),
+            Ok(false) => {
+                let _ = tokio::fs::remove_file(&staged).await;
+                Ok(in_place(target))
+            }
+            Err(err) => {
+                let _ = tokio::fs::remove_file(&staged).await;
+                Err(err.into())
+            }
+        }
+    }
+}
+
+/// Fails with a conflict error unless `content`, the current content of `path` (`None` if it
+/// does not exist), has the SHA-256 hash `expected`.
+pub(crate) fn check_content_hash(
+    path: &Path,
+    content: Option<&[u8]>,
+    expected: &str,
+) -> ServiceResult<()> {
+    let Some(content) = content else {
+        return Err(ServiceError::Conflict(format!(
+            "'{}' does not exist anymore, expected content with sha256 {expected}.",
+            path.display()
+        )));
+    };
+    let actual = sha256_hex(content);
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        return Err(ServiceError::Conflict(format!(
+            "'{}' was modified since it was last read: expected sha256 {expected}, found {actual}. Read the file again before modifying it.",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+/// What [`FileSystemService::move_file`] and [`FileSystemService::copy_file`] do when the
+/// destination already exists.
+#[derive(
+    ::serde::Deserialize, ::serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema,
+)]
+pub enum OverwritePolicy {
+    /// Fail without moving anything.
+    #[default]
+    #[serde(rename = "error")]
+    Error,
+    /// Replace the destination, if it is a file or an empty directory.
+    #[serde(rename = "replace", alias = "overwrite")]
+    Replace,
+    /// Move to the first free name like `name (1).ext` next to the destination.
+    #[serde(rename = "rename", alias = "auto_rename")]
+    Rename,
+}
+
+/// Returns `path` if nothing exists there, otherwise the first free path named like
+/// `stem (1).ext`, `stem (2).ext`... in the same directory.
+pub(crate) fn available_path(path: &Path) -> PathBuf {
+    if path.symlink_metadata().is_err() {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| path.with_file_name(format!("{stem} ({n}){extension}")))
+        .find(|candidate| candidate.symlink_metadata().is_err())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// Renames `src` to `dest`, falling back to copying then deleting `src` when they are on
+/// different filesystems.
+pub(crate) async fn move_path(src: &Path, dest: &Path) -> std::io::Result<()> {
+    match tokio::fs::rename(src, dest).await {
+        Err(err) if err.kind() == std::io::ErrorKind::CrossesDevices => {
+            let (src, dest) = (src.to_path_buf(), dest.to_path_buf());
+            tokio::task::spawn_blocking(move || {
+                if let Err(err) = copy_recursively(&src, &dest, &|_| false) {
+                    // leave the source as it was, without a partial copy
+                    let _ = if dest.symlink_metadata().is_ok_and(|meta| meta.is_dir()) {
+                        std::fs::remove_dir_all(&dest)
+                    } else {
+                        std::fs::remove_file(&dest)
+                    };
+                    return Err(err);
+                }
+                if src.symlink_metadata()?.is_dir() {
+                    std::fs::remove_dir_all(&src)
+                } else {
+                    std::fs::remove_file(&src)
+                }
+            })
+            .await?
+        }
+        result => result,
+    }
+}
+
+/// Copies a file, symlink or directory tree, keeping permissions. Entries of the tree for which
+/// `skip` returns true are left out.
+fn copy_recursively(src: &Path, dest: &Path, skip: &dyn Fn(&Path) -> bool) -> std::io::Result<()> {
+    let metadata = src.symlink_metadata()?;
+    if metadata.is_symlink() {
+        let target = std::fs::read_link(src)?;
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&target, dest)?;
+        #[cfg(windows)]
+        if src.is_dir() {
+            std::os::windows::fs::symlink_dir(&target, dest)?;
+        } else {
+            std::os::windows::fs::symlink_file(&target, dest)?;
+        }
+    } else if metadata.is_dir() {
+        std::fs::create_dir(dest)?;
+        for entry in std::fs::read_dir(src)? {
+            let entry = entry?;
+            if !skip(&entry.path()) {
+                copy_recursively(&entry.path(), &dest.join(entry.file_name()), skip)?;
+            }
+        }
+        std::fs::set_permissions(dest, metadata.permissions())?;
+    } else {
+        std::fs::copy(src, dest)?;
+    }
+    Ok(())
+}
+
+impl FileSystemService {
+    /// Replaces the content of the file at `path`, creating it if needed, as described in
+    /// [`Self::stage_file`].
+    pub(crate) async fn write_content(&self, path: &Path, content: &[u8]) -> ServiceResult<()> {
+        self.ensure_writable()?;
+        self.stage_file(path, content).await?.commit().await?;
         Ok(())
     }
 
+    /// Copies the current content of the file at `path` before it is modified, either into the
+    /// `--backup-dir` tree or next to it as `<name>.bak`, replacing any previous backup.
+    /// Returns `None` if `path` is not an existing file.
+    pub(crate) async fn backup_file(&self, path: &Path) -> ServiceResult<Option<PathBuf>> {
+        self.ensure_writable()?;
+        if !tokio::fs::metadata(path)
+            .await
+            .is_ok_and(|metadata| metadata.is_file())
+        {
+            return Ok(None);
+        }
+        let backup_path = match self.backup_dir() {
+            Some(backup_dir) => {
+                let relative: PathBuf = path
+                    .components()
+                    .filter(|component| matches!(component, Component::Normal(_)))
+                    .collect();
+                backup_dir.join(relative)
+            }
+            None => {
+                let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+                path.with_file_name(format!("{file_name}.bak"))
+            }
+        };
+        if let Some(parent) = backup_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::copy(path, &backup_path).await?;
+        Ok(Some(backup_path))
+    }
+
+    /// Writes `content` to `file_path`. If `backup` is true or a backup directory is configured,
+    /// the previous content is backed up first and the path of the backup is returned.
+    /// With `if_match_sha256`, the file is only written if its current content has that hash.
+    pub async fn write_file(
+        &self,
+        file_path: &Path,
+        content: &String,
+        backup: bool,
+        if_match_sha256: Option<&str>,
+    ) -> ServiceResult<Option<PathBuf>> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_write_path(file_path, allowed_directories)?;
+        if let Some(expected) = if_match_sha256 {
+            let current = match tokio::fs::read(&valid_path).await {
+                Ok(current) => Some(current),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+                Err(err) => return Err(err.into()),
+            };
+            check_content_hash(file_path, current.as_deref(), expected)?;
+        }
+        let backup_path = if backup || self.backup_dir().is_some() {
+            self.backup_file(&valid_path).await?
+        } else {
+            None
+        };
+        let backup = self.journal_backup(&valid_path).await?;
+        self.write_content(&valid_path, content.as_bytes()).await?;
+        self.journal_record(
+            "write_file",
+            vec![JournalChange::Write {
+                path: valid_path,
+                backup,
+            }],
+        );
+        Ok(backup_path)
+    }
+
     pub async fn create_directory(&self, file_path: &Path) -> ServiceResult<()> {
         let allowed_directories = self.allowed_directories().await;
-        let valid_path = self.validate_path(file_path, allowed_directories)?;
+        let valid_path = self.validate_write_path(file_path, allowed_directories)?;
+        let created = missing_directories(&valid_path);
         tokio::fs::create_dir_all(valid_path).await?;
+        self.journal_record(
+            "create_directory",
+            created
+                .into_iter()
+                .map(|path| JournalChange::CreateDirectory { path })
+                .collect(),
+        );
         Ok(())
     }
 
-    pub async fn move_file(&self, src_path: &Path, dest_path: &Path) -> ServiceResult<()> {
+    /// Recreates the directory hierarchy of `src_path` under `dest_path`, without any files, down
+    /// to `max_depth` levels below `src_path` (all levels if `None`). Directories matching
+    /// `exclude_patterns` are skipped along with their subdirectories. Returns the directories
+    /// created, outermost first; directories that already exist are kept.
+    pub async fn clone_directory_structure(
+        &self,
+        src_path: &Path,
+        dest_path: &Path,
+        max_depth: Option<usize>,
+        exclude_patterns: Vec<String>,
+        walk_options: WalkOptions,
+    ) -> ServiceResult<Vec<PathBuf>> {
         let allowed_directories = self.allowed_directories().await;
         let valid_src_path = self.validate_path(src_path, allowed_directories.clone())?;
-        let valid_dest_path = self.validate_path(dest_path, allowed_directories)?;
-        tokio::fs::rename(valid_src_path, valid_dest_path).await?;
-        Ok(())
+        let valid_dest_path = self.validate_write_path(dest_path, allowed_directories.clone())?;
+        if !valid_src_path.is_dir() {
+            return Err(ServiceError::InvalidArgument(format!(
+                "'{}' is not a directory.",
+                src_path.display()
+            )));
+        }
+        let exclude_glob = compile_exclude_glob(Some(&exclude_patterns), false)?;
+
+        let root = valid_src_path.clone();
+        let dest_for_filter = valid_dest_path.clone();
+        let relative_dirs: Vec<PathBuf> = self
+            .configure_walker(
+                &mut WalkBuilder::new(&valid_src_path),
+                walk_options,
+                allowed_directories.clone(),
+            )
+            .max_depth(max_depth)
+            .filter_entry(move |entry| {
+                let path = entry.path();
+                if path == root {
+                    return true;
+                }
+                // a destination inside the source is not cloned into itself
+                if path.starts_with(&dest_for_filter) {
+                    return false;
+                }
+                let relative_path = path.strip_prefix(&root).unwrap_or(path);
+                !exclude_glob
+                    .as_ref()
+                    .is_some_and(|excludes| excludes.is_match(relative_path))
+            })
+            .build()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_some_and(|ft| ft.is_dir()))
+            .filter_map(|entry| {
+                let relative = entry.path().strip_prefix(&valid_src_path).ok()?;
+                (!relative.as_os_str().is_empty()).then(|| relative.to_path_buf())
+            })
+            .collect();
+
+        let mut created = missing_directories(&valid_dest_path);
+        tokio::fs::create_dir_all(&valid_dest_path).await?;
+        for relative in relative_dirs {
+            let dir = valid_dest_path.join(relative);
+            let missing = missing_directories(&dir);
+            tokio::fs::create_dir_all(&dir).await?;
+            created.extend(missing);
+        }
+        self.journal_record(
+            "clone_directory_structure",
+            created
+                .iter()
+                .map(|path| JournalChange::CreateDirectory { path: path.clone() })
+                .collect(),
+        );
+        Ok(created)
+    }
+
+    /// Moves `src_path` to `dest_path` and returns the path it was moved to. What happens when
+    /// the destination exists depends on `overwrite`. Moves across filesystems fall back to
+    /// copying then deleting the source.
+    pub async fn move_file(
+        &self,
+        src_path: &Path,
+        dest_path: &Path,
+        overwrite: OverwritePolicy,
+    ) -> ServiceResult<PathBuf> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_src_path = self.validate_write_path(src_path, allowed_directories.clone())?;
+        let mut valid_dest_path =
+            self.validate_write_path(dest_path, allowed_directories.clone())?;
+
+        let mut changes = vec![];
+        if tokio::fs::symlink_metadata(&valid_dest_path).await.is_ok() {
+            match overwrite {
+                OverwritePolicy::Error => {
+                    return Err(ServiceError::Conflict(format!(
+                        "'{}' already exists. Set 'overwrite' to 'replace' to replace it, or to 'rename' to move to a new name next to it.",
+                        dest_path.display()
+                    )));
+                }
+                OverwritePolicy::Rename => {
+                    valid_dest_path = available_path(&valid_dest_path);
+                }
+                OverwritePolicy::Replace => {
+                    // a replaced file can only be brought back from a backup or the trash
+                    changes.push(self.remove_entry(&valid_dest_path).await?);
+                }
+            }
+        }
+
+        let moved = move_path(&valid_src_path, &valid_dest_path).await;
+        if let Err(err) = moved {
+            if let Some(replaced) = changes.pop() {
+                let _ = self.revert_change(&replaced, &allowed_directories).await;
+            }
+            return Err(err.into());
+        }
+        changes.push(JournalChange::Move {
+            from: valid_src_path,
+            to: valid_dest_path.clone(),
+        });
+        self.journal_record("move_file", changes);
+        Ok(valid_dest_path)
+    }
+
+    /// Copies the file, symlink or directory tree `src_path` to `dest_path` and returns the path
+    /// it was copied to. What happens when the destination exists depends on `overwrite`.
+    pub async fn copy_file(
+        &self,
+        src_path: &Path,
+        dest_path: &Path,
+        overwrite: OverwritePolicy,
+    ) -> ServiceResult<PathBuf> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_src_path = self.validate_path(src_path, allowed_directories.clone())?;
+        let mut valid_dest_path =
+            self.validate_write_path(dest_path, allowed_directories.clone())?;
+        if valid_dest_path.starts_with(&valid_src_path) {
+            return Err(ServiceError::InvalidArgument(format!(
+                "Cannot copy '{}' into itself.",
+                src_path.display()
+            )));
+        }
+
+        let mut changes = vec![];
+        if tokio::fs::symlink_metadata(&valid_dest_path).await.is_ok() {
+            match overwrite {
+                OverwritePolicy::Error => {
+                    return Err(ServiceError::Conflict(format!(
+                        "'{}' already exists. Set 'overwrite' to 'replace' to replace it, or to 'rename' to copy to a new name next to it.",
+                        dest_path.display()
+                    )));
+                }
+                OverwritePolicy::Rename => {
+                    valid_dest_path = available_path(&valid_dest_path);
+                }
+                OverwritePolicy::Replace => {
+                    changes.push(self.remove_entry(&valid_dest_path).await?);
+                }
+            }
+        }
+
+        let (src, dest) = (valid_src_path.clone(), valid_dest_path.clone());
+        let deny_globs = self.deny_globs();
+        let copied = tokio::task::spawn_blocking(move || {
+            // denied paths inside a copied directory are not copied along
+            let skip = |path: &Path| deny_globs.as_ref().is_some_and(|deny| deny.is_match(path));
+            let copied = copy_recursively(&src, &dest, &skip);
+            if copied.is_err() {
+                // leave no partial copy behind
+                let _ = if dest.symlink_metadata().is_ok_and(|meta| meta.is_dir()) {
+                    std::fs::remove_dir_all(&dest)
+                } else {
+                    std::fs::remove_file(&dest)
+                };
+            }
+            copied
+        })
+        .await
+        .map_err(|e| std::io::Error::other(e.to_string()))?;
+        if let Err(err) = copied {
+            if let Some(replaced) = changes.pop() {
+                let _ = self.revert_change(&replaced, &allowed_directories).await;
+            }
+            return Err(err.into());
+        }
+
+        // parents come before their content, so that undoing removes the content first
+        changes.extend(
+            walkdir::WalkDir::new(&valid_dest_path)
+                .into_iter()
+                .filter_map(|entry| entry.ok())
+                .map(|entry| {
+                    let path = entry.path().to_path_buf();
+                    if entry.file_type().is_dir() {
+                        JournalChange::CreateDirectory { path }
+                    } else {
+                        JournalChange::Write { path, backup: None }
+                    }
+                }),
+        );
+        self.journal_record("copy_file", changes);
+        Ok(valid_dest_path)
     }
 }
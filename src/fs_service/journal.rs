@@ -0,0 +1,258 @@
+use crate::{
+    error::{ServiceError, ServiceResult},
+    fs_service::{FileSystemService, io::move_path},
+};
+use chrono::Utc;
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+/// Number of operations kept in the journal. The backups of older operations are deleted.
+const MAX_JOURNAL_ENTRIES: usize = 100;
+
+/// A single change made by a journaled operation, with what is needed to revert it.
+#[derive(::serde::Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JournalChange {
+    /// `path` was written. Its previous content is in `backup`, or it did not exist.
+    Write {
+        path: PathBuf,
+        backup: Option<PathBuf>,
+    },
+    /// `from` was moved to `to`.
+    Move { from: PathBuf, to: PathBuf },
+    /// The directory `path` was created.
+    CreateDirectory { path: PathBuf },
+    /// `path` was deleted. A file's content is in `backup`, unless it went to the trash.
+    Delete {
+        path: PathBuf,
+        backup: Option<PathBuf>,
+        trashed: bool,
+    },
+}
+
+/// A mutating operation recorded in the journal.
+#[derive(::serde::Serialize, Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    pub id: u64,
+    /// Name of the operation, e.g. `write_file`.
+    pub operation: String,
+    /// UTC time of the operation.
+    pub timestamp: String,
+    /// Changes in the order they were made.
+    pub changes: Vec<JournalChange>,
+}
+
+/// Journal of the mutating operations of this server, kept in memory. Previous contents of
+/// overwritten and deleted files are copied to `backup_dir`.
+pub(crate) struct Journal {
+    backup_dir: PathBuf,
+    entries: Mutex<VecDeque<JournalEntry>>,
+    next_id: AtomicU64,
+}
+
+impl Journal {
+    pub(crate) fn new(backup_dir: PathBuf) -> Self {
+        Self {
+            backup_dir,
+            entries: Mutex::new(VecDeque::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+}
+
+fn remove_backups(entry: &JournalEntry) {
+    for change in &entry.changes {
+        if let JournalChange::Write {
+            backup: Some(backup),
+            ..
+        }
+        | JournalChange::Delete {
+            backup: Some(backup),
+            ..
+        } = change
+        {
+            let _ = std::fs::remove_file(backup);
+        }
+    }
+}
+
+/// Returns the directories `create_dir_all(path)` is about to create, outermost first.
+pub(crate) fn missing_directories(path: &Path) -> Vec<PathBuf> {
+    let mut missing: Vec<PathBuf> = path
+        .ancestors()
+        .take_while(|dir| !dir.exists())
+        .map(Path::to_path_buf)
+        .collect();
+    missing.reverse();
+    missing
+}
+
+impl FileSystemService {
+    /// Copies the current content of the file at `path` to the journal before it is
+    /// overwritten or deleted. Returns `None` if the journal is disabled or `path` is not a file.
+    pub(crate) async fn journal_backup(&self, path: &Path) -> ServiceResult<Option<PathBuf>> {
+        let Some(journal) = self.journal() else {
+            return Ok(None);
+        };
+        if !tokio::fs::symlink_metadata(path)
+            .await
+            .is_ok_and(|metadata| metadata.is_file())
+        {
+            return Ok(None);
+        }
+        tokio::fs::create_dir_all(&journal.backup_dir).await?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let backup = journal.backup_dir.join(format!(
+            "{}-{name}",
+            journal.next_id.fetch_add(1, Ordering::Relaxed)
+        ));
+        tokio::fs::copy(path, &backup).await?;
+        Ok(Some(backup))
+    }
+
+    /// Records an operation made of `changes`. Does nothing if the journal is disabled or no
+    /// change was made.
+    pub(crate) fn journal_record(&self, operation: &str, changes: Vec<JournalChange>) {
+        let Some(journal) = self.journal() else {
+            return;
+        };
+        if changes.is_empty() {
+            return;
+        }
+        let Ok(mut entries) = journal.entries.lock() else {
+            return;
+        };
+        entries.push_back(JournalEntry {
+            id: journal.next_id.fetch_add(1, Ordering::Relaxed),
+            operation: operation.to_string(),
+            timestamp: Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            changes,
+        });
+        while entries.len() > MAX_JOURNAL_ENTRIES {
+            if let Some(evicted) = entries.pop_front() {
+                remove_backups(&evicted);
+            }
+        }
+    }
+
+    /// Returns the journaled operations, most recent first.
+    pub fn journal_entries(&self) -> Vec<JournalEntry> {
+        self.journal()
+            .and_then(|journal| journal.entries.lock().ok())
+            .map(|entries| entries.iter().rev().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Reverts the most recent journaled operation, undoing its changes in reverse order.
+    /// If a change cannot be reverted, the operation stays in the journal with the changes
+    /// that were not reverted yet.
+    pub async fn undo_last_operation(&self) -> ServiceResult<JournalEntry> {
+        self.ensure_writable()?;
+        let journal = self.journal().ok_or_else(|| {
+            ServiceError::FromString(
+                "The operation journal is disabled. Start the server with '--journal' to enable undo."
+                    .to_string(),
+            )
+        })?;
+        let entry = journal
+            .entries
+            .lock()
+            .ok()
+            .and_then(|mut entries| entries.pop_back())
+            .ok_or_else(|| {
+                ServiceError::FromString("There is no operation to undo.".to_string())
+            })?;
+
+        let allowed_directories = self.allowed_directories().await;
+        let mut remaining = entry.changes.clone();
+        while let Some(change) = remaining.last() {
+            if let Err(err) = self.revert_change(change, &allowed_directories).await {
+                let undone = entry.changes.len() - remaining.len();
+                if let Ok(mut entries) = journal.entries.lock() {
+                    entries.push_back(JournalEntry {
+                        changes: remaining,
+                        ..entry.clone()
+                    });
+                }
+                return Err(ServiceError::FromString(format!(
+                    "Unable to undo '{}' ({undone} of {} changes reverted): {err}",
+                    entry.operation,
+                    entry.changes.len()
+                )));
+            }
+            remaining.pop();
+        }
+
+        remove_backups(&entry);
+        Ok(entry)
+    }
+
+    pub(crate) async fn revert_change(
+        &self,
+        change: &JournalChange,
+        allowed_directories: &std::sync::Arc<Vec<PathBuf>>,
+    ) -> ServiceResult<()> {
+        let must_not_exist = |path: &Path| {
+            if path.exists() {
+                return Err(ServiceError::FromString(format!(
+                    "'{}' exists again.",
+                    path.display()
+                )));
+            }
+            Ok(())
+        };
+        match change {
+            JournalChange::Write { path, backup } => {
+                let path = self.validate_write_path(path, allowed_directories.clone())?;
+                // the file is replaced rather than overwritten, in case it became a hard link
+                match tokio::fs::remove_file(&path).await {
+                    Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                        return Err(err.into());
+                    }
+                    _ => {}
+                }
+                if let Some(backup) = backup {
+                    tokio::fs::copy(backup, &path).await?;
+                }
+            }
+            JournalChange::Move { from, to } => {
+                let from = self.validate_write_path(from, allowed_directories.clone())?;
+                let to = self.validate_write_path(to, allowed_directories.clone())?;
+                must_not_exist(&from)?;
+                move_path(&to, &from).await?;
+            }
+            JournalChange::CreateDirectory { path } => {
+                let path = self.validate_write_path(path, allowed_directories.clone())?;
+                tokio::fs::remove_dir(&path).await?;
+            }
+            JournalChange::Delete {
+                path,
+                backup,
+                trashed,
+            } => {
+                let path = self.validate_write_path(path, allowed_directories.clone())?;
+                if *trashed {
+                    self.restore_from_trash(&path).await?;
+                    return Ok(());
+                }
+                must_not_exist(&path)?;
+                match backup {
+                    Some(backup) => {
+                        tokio::fs::copy(backup, &path).await?;
+                    }
+                    None => tokio::fs::create_dir(&path).await?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
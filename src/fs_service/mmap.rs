@@ -0,0 +1,62 @@
+use grep::searcher::MmapChoice;
+use memmap2::Mmap;
+use std::{fs::File, io, path::Path};
+
+/// Files at least this large are memory mapped instead of being read into buffers.
+pub(crate) const MMAP_THRESHOLD: u64 = 1024 * 1024;
+
+/// Returns how `grep` should read `path` when searching it: memory mapped if it is at least
+/// [`MMAP_THRESHOLD`] bytes, through buffers otherwise.
+pub(crate) fn mmap_choice(path: &Path) -> MmapChoice {
+    match std::fs::metadata(path) {
+        // SAFETY: the map is only read. As with every memory map, a file truncated by another
+        // process during the search may abort the process, which is why mapping is opt-in.
+        Ok(metadata) if metadata.len() >= MMAP_THRESHOLD => unsafe { MmapChoice::auto() },
+        _ => MmapChoice::never(),
+    }
+}
+
+/// Memory maps the file at `path` if it is at least [`MMAP_THRESHOLD`] bytes.
+pub(crate) fn map_large_file(path: &Path) -> io::Result<Option<Mmap>> {
+    let file = File::open(path)?;
+    if file.metadata()?.len() < MMAP_THRESHOLD {
+        return Ok(None);
+    }
+    // SAFETY: see `mmap_choice`.
+    Ok(Some(unsafe { Mmap::map(&file)? }))
+}
+
+/// Returns the lines of `bytes` selected by `read_file_lines` in `from_end` mode, scanning
+/// backwards from the end without splitting the whole content into lines first.
+pub(crate) fn last_lines(bytes: &[u8], offset: usize, limit: Option<usize>) -> io::Result<String> {
+    let has_crlf = bytes.windows(2).any(|w| w == b"\r\n");
+    let line_ending = if has_crlf { "\r\n" } else { "\n" };
+    let ends_with_newline = bytes.last() == Some(&b'\n');
+    let body = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+
+    let mut selected = body
+        .rsplit(|byte| *byte == b'\n')
+        .enumerate()
+        .skip(offset)
+        .take(limit.unwrap_or(usize::MAX))
+        .map(|(index, line)| {
+            // like `BufRead::lines`, only a line ending is stripped from the last line
+            let line = if index > 0 || ends_with_newline {
+                line.strip_suffix(b"\r").unwrap_or(line)
+            } else {
+                line
+            };
+            std::str::from_utf8(line).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+        })
+        .collect::<io::Result<Vec<&str>>>()?;
+    if selected.is_empty() {
+        return Ok(String::new());
+    }
+    selected.reverse();
+
+    let mut result = selected.join(line_ending);
+    if offset == 0 && ends_with_newline {
+        result.push_str(line_ending);
+    }
+    Ok(result)
+}
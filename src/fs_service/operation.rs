@@ -0,0 +1,150 @@
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use crate::error::{ServiceError, ServiceResult};
+
+/// Minimum delay between two progress updates of the same operation.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Progress of a long-running operation, reported through an [`OperationContext`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressUpdate {
+    /// Amount of work done so far, e.g. the number of files scanned.
+    pub progress: f64,
+    /// Total amount of work, if known.
+    pub total: Option<f64>,
+    pub message: Option<String>,
+}
+
+type ProgressCallback = Arc<dyn Fn(ProgressUpdate) + Send + Sync>;
+
+/// Shared flag used to abort a running operation. Clones observe the same state.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Per-request hooks passed to long-running service operations.
+#[derive(Clone, Default)]
+pub struct OperationContext {
+    progress: Option<ProgressCallback>,
+    cancellation: CancellationToken,
+}
+
+impl OperationContext {
+    /// Reports progress of the operation to `callback`.
+    pub fn with_progress(
+        mut self,
+        callback: impl Fn(ProgressUpdate) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(Arc::new(callback));
+        self
+    }
+
+    /// Aborts the operation once `token` is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    /// Returns [`ServiceError::Cancelled`] if the operation has been cancelled.
+    pub fn check_cancelled(&self) -> ServiceResult<()> {
+        if self.is_cancelled() {
+            return Err(ServiceError::Cancelled);
+        }
+        Ok(())
+    }
+
+    pub fn reports_progress(&self) -> bool {
+        self.progress.is_some()
+    }
+
+    pub fn report_progress(&self, update: ProgressUpdate) {
+        if let Some(progress) = &self.progress {
+            progress(update);
+        }
+    }
+}
+
+/// Counts scanned files and matches of a search running on several threads and reports them
+/// through an [`OperationContext`], at most once per [`PROGRESS_INTERVAL`].
+pub(crate) struct SearchProgress<'a> {
+    context: &'a OperationContext,
+    files_scanned: AtomicUsize,
+    matches: AtomicUsize,
+    last_report: Mutex<Instant>,
+}
+
+impl<'a> SearchProgress<'a> {
+    pub(crate) fn new(context: &'a OperationContext) -> Self {
+        Self {
+            context,
+            files_scanned: AtomicUsize::new(0),
+            matches: AtomicUsize::new(0),
+            last_report: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Records a scanned file and the number of matches found in it.
+    pub(crate) fn file_scanned(&self, matches: usize) {
+        let files_scanned = self.files_scanned.fetch_add(1, Ordering::Relaxed) + 1;
+        let matches = self.matches.fetch_add(matches, Ordering::Relaxed) + matches;
+        if !self.context.reports_progress() {
+            return;
+        }
+        // skip the update if another thread is reporting right now
+        let Ok(mut last_report) = self.last_report.try_lock() else {
+            return;
+        };
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            *last_report = Instant::now();
+            self.report(files_scanned, matches);
+        }
+    }
+
+    /// Number of files scanned so far.
+    pub(crate) fn files_scanned(&self) -> usize {
+        self.files_scanned.load(Ordering::Relaxed)
+    }
+
+    /// Reports the final counts.
+    pub(crate) fn finish(&self) {
+        if self.context.reports_progress() {
+            self.report(
+                self.files_scanned.load(Ordering::Relaxed),
+                self.matches.load(Ordering::Relaxed),
+            );
+        }
+    }
+
+    fn report(&self, files_scanned: usize, matches: usize) {
+        self.context.report_progress(ProgressUpdate {
+            progress: files_scanned as f64,
+            total: None,
+            message: Some(format!(
+                "Scanned {files_scanned} files, {matches} matches so far"
+            )),
+        });
+    }
+}
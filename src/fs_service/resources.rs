@@ -0,0 +1,184 @@
+use crate::{
+    error::{ServiceError, ServiceResult},
+    fs_service::{FileSystemService, utils::validate_file_size},
+};
+use base64::{Engine, engine::general_purpose};
+use ignore::WalkBuilder;
+use std::path::{Path, PathBuf};
+
+/// Number of resources returned per `resources/list` page.
+pub const RESOURCE_PAGE_SIZE: usize = 200;
+
+/// Largest file that can be read through `resources/read`.
+pub const MAX_RESOURCE_BYTES: usize = 10 * 1024 * 1024;
+
+/// A file within the allowed directories exposed as an MCP resource.
+#[derive(Debug, Clone)]
+pub struct FileResource {
+    pub uri: String,
+    /// Path relative to the allowed directory containing the file.
+    pub name: String,
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+/// Content of a file read as a resource.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResourceContent {
+    Text(String),
+    /// Base64 encoded binary content.
+    Blob(String),
+}
+
+/// Converts an absolute path into a `file://` URI, percent-encoding reserved characters.
+pub fn path_to_file_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    uri
+}
+
+/// Converts a `file://` URI back into a path, decoding percent-encoded characters.
+pub fn file_uri_to_path(uri: &str) -> ServiceResult<PathBuf> {
+    let invalid_uri = || ServiceError::InvalidArgument(format!("Invalid file URI: '{uri}'"));
+    let encoded = uri.strip_prefix("file://").ok_or_else(invalid_uri)?;
+    // an optional empty or "localhost" authority is allowed before the path
+    let encoded = encoded.strip_prefix("localhost").unwrap_or(encoded);
+
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut input = encoded.bytes();
+    while let Some(byte) = input.next() {
+        if byte == b'%' {
+            let hex = [input.next(), input.next()];
+            let [Some(high), Some(low)] = hex else {
+                return Err(invalid_uri());
+            };
+            let hex = std::str::from_utf8(&[high, low])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(invalid_uri)?;
+            bytes.push(hex);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    let path = String::from_utf8(bytes).map_err(|_| invalid_uri())?;
+
+    // Windows paths are written as file:///C:/...
+    let path = match path.as_bytes() {
+        [b'/', drive, b':', ..] if cfg!(windows) && drive.is_ascii_alphabetic() => &path[1..],
+        _ => path.as_str(),
+    };
+    Ok(PathBuf::from(path))
+}
+
+/// Returns the MIME type of a text file, based on its extension.
+fn text_mime_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("md" | "markdown") => "text/markdown",
+        Some("html" | "htm") => "text/html",
+        Some("css") => "text/css",
+        Some("csv") => "text/csv",
+        Some("js" | "mjs" | "cjs") => "text/javascript",
+        Some("json") => "application/json",
+        Some("xml") => "application/xml",
+        Some("yaml" | "yml") => "application/yaml",
+        Some("toml") => "application/toml",
+        Some("svg") => "image/svg+xml",
+        _ => "text/plain",
+    }
+}
+
+impl FileSystemService {
+    /// Lists the files within the allowed directories as resources, skipping `offset` files and
+    /// returning at most `limit`. Gitignore rules and hidden files are respected.
+    ///
+    /// Returns the resources and the offset of the next page, if any.
+    pub async fn list_file_resources(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> ServiceResult<(Vec<FileResource>, Option<usize>)> {
+        let allowed_directories = self.allowed_directories().await;
+
+        let mut files = allowed_directories.iter().flat_map(|root| {
+            self.exclude_denied(&mut WalkBuilder::new(root))
+                .sort_by_file_name(|a, b| a.cmp(b))
+                .build()
+                .filter_map(Result::ok)
+                .filter(|entry| entry.file_type().is_some_and(|ft| ft.is_file()))
+                .map(move |entry| (root, entry))
+        });
+
+        let resources: Vec<FileResource> = files
+            .by_ref()
+            .skip(offset)
+            .take(limit)
+            .map(|(root, entry)| {
+                let path = entry.into_path();
+                let name = path
+                    .strip_prefix(root)
+                    .unwrap_or(&path)
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                FileResource {
+                    uri: path_to_file_uri(&path),
+                    name,
+                    size: path.metadata().map(|m| m.len()).unwrap_or_default(),
+                    path,
+                }
+            })
+            .collect();
+
+        let next_offset = files.next().map(|_| offset + resources.len());
+        Ok((resources, next_offset))
+    }
+
+    /// Reads the file identified by a `file://` URI, returning its MIME type and content.
+    /// Valid UTF-8 files are returned as text, anything else as a base64 blob.
+    pub async fn read_file_resource(&self, uri: &str) -> ServiceResult<(String, ResourceContent)> {
+        let path = file_uri_to_path(uri)?;
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(&path, allowed_directories)?;
+        if !valid_path.is_file() {
+            return Err(ServiceError::NotFound(format!(
+                "Resource not found: '{uri}'"
+            )));
+        }
+        validate_file_size(&valid_path, None, Some(MAX_RESOURCE_BYTES)).await?;
+
+        let bytes = match String::from_utf8(tokio::fs::read(&valid_path).await?) {
+            Ok(text) if !text.contains('\0') => {
+                return Ok((
+                    text_mime_type(&valid_path).to_string(),
+                    ResourceContent::Text(text),
+                ));
+            }
+            Ok(text) => text.into_bytes(),
+            Err(err) => err.into_bytes(),
+        };
+
+        let mime_type = infer::get(&bytes)
+            .map(|kind| kind.mime_type())
+            .unwrap_or("application/octet-stream")
+            .to_string();
+        Ok((
+            mime_type,
+            ResourceContent::Blob(general_purpose::STANDARD.encode(bytes)),
+        ))
+    }
+}
@@ -1,8 +1,39 @@
+mod archive;
+mod around;
 pub mod ast;
+mod cleanup;
 mod content;
 mod files;
+mod fuzzy;
 pub(crate) mod glob_utils;
+pub(crate) mod outline;
+mod stats;
+mod symlinks;
+mod todos;
 mod tree;
 
-pub use ast::{AstFileSearchResult, AstMatchResult};
-pub use content::FileSearchResult;
+pub use archive::ArchiveSearchResult;
+pub use around::{MatchRegion, MatchRegions};
+pub(crate) use ast::AstCache;
+pub use ast::{
+    AstFileSearchResult, AstMatchResult, AstRewriteResult, AstSearchLimits, AstSearchOutcome,
+    DEFAULT_AST_MAX_FILE_SIZE, DEFAULT_AST_MAX_FILES,
+};
+pub use cleanup::{CleanedFile, CleanupAction, CleanupOutcome};
+pub use content::{
+    ContentMatchResult, ContentSearchOptions, ContentSearchOutcome, FileSearchResult,
+    LongLineAction,
+};
+pub use files::{
+    DuplicateAction, DuplicateResolution, SearchFilesOutcome, SearchSortBy, SortOrder,
+    sort_search_results,
+};
+pub use fuzzy::{FuzzyMatch, fuzzy_score};
+pub use outline::{CodeSymbol, code_outline};
+pub use stats::{DirectoryStatistics, ExtensionStatistics};
+pub use symlinks::{BrokenSymlink, BrokenSymlinkReason};
+pub use todos::{DEFAULT_TODO_TAGS, TodoGroup, TodoItem};
+pub use tree::{
+    DirectoryListing, DirectorySortBy, DirectoryTreePage, EmptyDirectoryRemoval, SizeGroup,
+    SizeGroupBy, SizedEntry, TreeFilter,
+};
@@ -0,0 +1,175 @@
+use crate::{
+    error::{ServiceError, ServiceResult},
+    fs_service::{
+        FileSystemService, WalkOptions,
+        search::{
+            content::{ContentMatchResult, SNIPPET_BACKWARD_CHARS, SNIPPET_MAX_LENGTH},
+            glob_utils::compile_single_glob,
+        },
+        utils::escape_regex,
+    },
+};
+use grep::{
+    matcher::Matcher,
+    regex::{RegexMatcher, RegexMatcherBuilder},
+    searcher::{BinaryDetection, Searcher, sinks::Lossy},
+};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::{Path, PathBuf},
+};
+use zip::ZipArchive;
+
+/// Entries larger than this (uncompressed) are not searched, to bound memory use.
+const MAX_ARCHIVE_ENTRY_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Matches found in one entry of a zip archive by [`FileSystemService::search_archive_content`].
+#[derive(Debug, Clone)]
+pub struct ArchiveSearchResult {
+    pub archive_path: PathBuf,
+    /// Path of the entry within the archive.
+    pub entry: String,
+    pub matches: Vec<ContentMatchResult>,
+}
+
+impl ArchiveSearchResult {
+    /// Location of the entry in the `archive.zip!entry/path` notation.
+    pub fn location(&self) -> String {
+        format!("{}!{}", self.archive_path.display(), self.entry)
+    }
+}
+
+/// Searches the text entries of the zip archive at `archive_path` whose name matches
+/// `entry_glob`. Unreadable archives and entries, and binary entries, are skipped.
+fn search_archive(
+    archive_path: &Path,
+    entry_glob: &globset::GlobSet,
+    matcher: &RegexMatcher,
+) -> Vec<ArchiveSearchResult> {
+    let Ok(file) = File::open(archive_path) else {
+        return vec![];
+    };
+    let Ok(mut archive) = ZipArchive::new(BufReader::new(file)) else {
+        return vec![];
+    };
+    let mut searcher = Searcher::new();
+    searcher.set_binary_detection(BinaryDetection::quit(b'\x00'));
+
+    let mut results = vec![];
+    for index in 0..archive.len() {
+        let Ok(entry) = archive.by_index(index) else {
+            continue;
+        };
+        if entry.is_dir() || entry.size() > MAX_ARCHIVE_ENTRY_SIZE {
+            continue;
+        }
+        let Ok(name) = entry.name().map(|name| name.to_string()) else {
+            continue;
+        };
+        if !entry_glob.is_match(&name) {
+            continue;
+        }
+        let mut content = Vec::with_capacity(entry.size() as usize);
+        if entry
+            .take(MAX_ARCHIVE_ENTRY_SIZE)
+            .read_to_end(&mut content)
+            .is_err()
+        {
+            continue;
+        }
+
+        let mut matches = vec![];
+        let searched = searcher.search_slice(
+            matcher,
+            &content,
+            Lossy(|line_number, line| {
+                if let Some(found) = matcher.find(line.as_bytes())? {
+                    matches.push(ContentMatchResult {
+                        line_number,
+                        start_pos: found.start(),
+                        end_pos: found.end(),
+                        line_text: FileSystemService::extract_snippet_static(
+                            line,
+                            found,
+                            SNIPPET_MAX_LENGTH,
+                            SNIPPET_BACKWARD_CHARS,
+                            None,
+                        ),
+                    });
+                }
+                Ok(true)
+            }),
+        );
+        if searched.is_ok() && !matches.is_empty() {
+            results.push(ArchiveSearchResult {
+                archive_path: archive_path.to_path_buf(),
+                entry: name,
+                matches,
+            });
+        }
+    }
+    results
+}
+
+impl FileSystemService {
+    /// Searches the content of the entries of zip archives without extracting them. `root_path`
+    /// is either a zip file or a directory whose files matching `archive_pattern` (default:
+    /// `*.zip`) are searched. Only entries whose path matches `entry_pattern` (default: all) are
+    /// searched; like [`Self::search_files_content`], the query is case-insensitive. Archives are
+    /// searched in parallel and results are ordered by archive and entry.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn search_archive_content(
+        &self,
+        root_path: &Path,
+        archive_pattern: Option<&str>,
+        entry_pattern: Option<&str>,
+        query: &str,
+        is_regex: bool,
+        exclude_patterns: Vec<String>,
+        walk_options: WalkOptions,
+    ) -> ServiceResult<Vec<ArchiveSearchResult>> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(root_path, allowed_directories)?;
+        let entry_glob = compile_single_glob(entry_pattern.unwrap_or_default(), "*", false)?;
+        let search_query = if is_regex {
+            query.to_string()
+        } else {
+            escape_regex(query)
+        };
+        let matcher = RegexMatcherBuilder::new()
+            .case_insensitive(true)
+            .build(&search_query)?;
+
+        let archives: Vec<PathBuf> = if valid_path.is_file() {
+            vec![valid_path]
+        } else if valid_path.is_dir() {
+            self.search_files_iter(
+                &valid_path,
+                archive_pattern.unwrap_or("*.zip").to_string(),
+                exclude_patterns,
+                None,
+                None,
+                None,
+                walk_options,
+            )
+            .await?
+            .filter(|entry| entry.file_type().is_some_and(|ft| ft.is_file()))
+            .map(|entry| entry.into_path())
+            .collect()
+        } else {
+            return Err(ServiceError::InvalidArgument(format!(
+                "'{}' is neither a zip file nor a directory.",
+                root_path.display()
+            )));
+        };
+
+        let mut results: Vec<ArchiveSearchResult> = archives
+            .into_par_iter()
+            .flat_map_iter(|archive_path| search_archive(&archive_path, &entry_glob, &matcher))
+            .collect();
+        results.sort_by(|a, b| (&a.archive_path, &a.entry).cmp(&(&b.archive_path, &b.entry)));
+        Ok(results)
+    }
+}
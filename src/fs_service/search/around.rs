@@ -0,0 +1,101 @@
+use crate::{
+    error::{ServiceError, ServiceResult},
+    fs_service::{FileSystemService, utils::escape_regex},
+};
+use grep::{matcher::Matcher, regex::RegexMatcherBuilder};
+use std::path::Path;
+
+/// A window of lines around one or more matches, see [`FileSystemService::read_around_match`].
+#[derive(::serde::Serialize, Debug, Clone, PartialEq)]
+pub struct MatchRegion {
+    /// 1-based number of the first line of the region.
+    pub start_line: u64,
+    /// 1-based number of the last line of the region.
+    pub end_line: u64,
+    /// 1-based numbers of the matching lines within the region.
+    pub match_lines: Vec<u64>,
+    /// The lines of the region, without line endings.
+    pub lines: Vec<String>,
+}
+
+/// Regions around the matches of a file, see [`FileSystemService::read_around_match`].
+#[derive(::serde::Serialize, Debug, Clone, Default, PartialEq)]
+pub struct MatchRegions {
+    pub regions: Vec<MatchRegion>,
+    /// Number of matching lines in the file, including those beyond `max_matches`.
+    pub total_matches: usize,
+}
+
+impl FileSystemService {
+    /// Finds the lines of the text file at `path` matching `query` (case-insensitive, a regular
+    /// expression if `is_regex`) and returns them with `context_lines` lines before and after
+    /// each. Overlapping or adjacent windows are merged into one region. With `max_matches`, only
+    /// the first matches get a region.
+    pub async fn read_around_match(
+        &self,
+        path: &Path,
+        query: &str,
+        is_regex: bool,
+        context_lines: usize,
+        max_matches: Option<usize>,
+    ) -> ServiceResult<MatchRegions> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(path, allowed_directories)?;
+
+        let pattern = if is_regex {
+            query.to_string()
+        } else {
+            escape_regex(query)
+        };
+        let matcher = RegexMatcherBuilder::new()
+            .case_insensitive(true)
+            .build(&pattern)?;
+
+        let content = tokio::fs::read(&valid_path).await?;
+        if content.contains(&0) {
+            return Err(ServiceError::FromString(format!(
+                "'{}' looks like a binary file.",
+                path.display()
+            )));
+        }
+        let content = String::from_utf8_lossy(&content);
+        let lines: Vec<&str> = content.lines().collect();
+
+        let matches: Vec<usize> = lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| matcher.is_match(line.as_bytes()).unwrap_or_default())
+            .map(|(index, _)| index)
+            .collect();
+
+        let mut result = MatchRegions {
+            total_matches: matches.len(),
+            ..Default::default()
+        };
+        let mut windows: Vec<(usize, usize, Vec<u64>)> = vec![];
+        for index in matches.into_iter().take(max_matches.unwrap_or(usize::MAX)) {
+            let start = index.saturating_sub(context_lines);
+            let end = (index + context_lines).min(lines.len() - 1);
+            match windows.last_mut() {
+                Some(last) if last.1 + 1 >= start => {
+                    last.1 = end;
+                    last.2.push(index as u64 + 1);
+                }
+                _ => windows.push((start, end, vec![index as u64 + 1])),
+            }
+        }
+        result.regions = windows
+            .into_iter()
+            .map(|(start, end, match_lines)| MatchRegion {
+                start_line: start as u64 + 1,
+                end_line: end as u64 + 1,
+                match_lines,
+                lines: lines[start..=end]
+                    .iter()
+                    .map(|line| line.to_string())
+                    .collect(),
+            })
+            .collect();
+        Ok(result)
+    }
+}
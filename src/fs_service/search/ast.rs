@@ -1,22 +1,60 @@
+use crate::fs_service::WalkOptions;
 use crate::{
     error::{ServiceError, ServiceResult},
     fs_service::{
         FileSystemService,
-        search::glob_utils::{compile_exclude_glob, compile_single_glob},
+        journal::JournalChange,
+        operation::{OperationContext, SearchProgress},
+        search::{
+            glob_utils::{compile_exclude_glob, compile_single_glob},
+            outline::enclosing_symbols,
+        },
     },
 };
-use ast_grep_core::Pattern;
+use ast_grep_config::{DeserializeEnv, RuleCore, SerializableRuleCore};
+use ast_grep_core::{
+    AstGrep, Matcher, NodeMatch, Pattern, meta_var::MetaVariable, replacer::TemplateFix,
+    tree_sitter::StrDoc,
+};
 use ast_grep_language::{LanguageExt, SupportLang};
 use ignore::WalkBuilder;
+use lru::LruCache;
 use std::{
+    collections::BTreeMap,
+    num::NonZeroUsize,
     path::{Path, PathBuf},
     sync::{
-        Arc,
+        Arc, Mutex,
         atomic::{AtomicUsize, Ordering},
         mpsc,
     },
+    time::SystemTime,
 };
 
+/// Files larger than this many bytes are skipped by AST searches unless configured otherwise.
+pub const DEFAULT_AST_MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Maximum number of files parsed by an AST search unless configured otherwise.
+pub const DEFAULT_AST_MAX_FILES: usize = 10000;
+
+/// Maximum total estimated memory of the parse trees kept by [`AstCache`].
+const AST_CACHE_MAX_BYTES: usize = 128 * 1024 * 1024;
+
+/// Maximum number of parse trees kept by [`AstCache`].
+const AST_CACHE_MAX_ENTRIES: NonZeroUsize = NonZeroUsize::new(4096).unwrap();
+
+/// Estimated memory of a node of a parse tree, which is kept along with the source text.
+const AST_NODE_COST: usize = 64;
+
+/// Per-call overrides of the server's AST search limits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AstSearchLimits {
+    /// Skip files larger than this many bytes, `None` for the server setting.
+    pub max_file_size: Option<u64>,
+    /// Stop the search after parsing this many files, `None` for the server setting.
+    pub max_files: Option<usize>,
+}
+
 /// Represents a single AST match found in a file.
 #[derive(Debug, Clone)]
 pub struct AstMatchResult {
@@ -28,6 +66,82 @@ pub struct AstMatchResult {
     pub column: usize,
     /// The byte range of the match
     pub byte_range: (usize, usize),
+    /// The symbols enclosing the match, e.g. `class View > method render`
+    pub enclosing: Option<String>,
+    /// Source lines preceding the match, when context lines are requested
+    pub context_before: Vec<String>,
+    /// Source lines following the match, when context lines are requested
+    pub context_after: Vec<String>,
+    /// Text captured by each named meta-variable of the pattern (`$NAME`, `$$$ARGS`),
+    /// keyed by the variable name without the `$` prefix
+    pub captures: BTreeMap<String, String>,
+}
+
+impl AstMatchResult {
+    /// Builds a match result for `node`, with up to `context_lines` lines of surrounding
+    /// source taken from `lines` (the lines of the searched file).
+    fn from_node(
+        node_match: &NodeMatch<StrDoc<SupportLang>>,
+        lang: SupportLang,
+        lines: &[&str],
+        context_lines: usize,
+    ) -> Self {
+        let node = node_match.get_node();
+        let range = node.range();
+        let start_pos = node.start_pos();
+        let start_line = start_pos.line();
+        let end_line = node.end_pos().line();
+
+        let to_strings = |slice: &[&str]| slice.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        let (context_before, context_after) = if context_lines > 0 {
+            let before_start = start_line.saturating_sub(context_lines);
+            let after_start = (end_line + 1).min(lines.len());
+            let after_end = (after_start + context_lines).min(lines.len());
+            (
+                to_strings(&lines[before_start.min(lines.len())..start_line.min(lines.len())]),
+                to_strings(&lines[after_start..after_end]),
+            )
+        } else {
+            (vec![], vec![])
+        };
+
+        AstMatchResult {
+            matched_code: node.text().to_string(),
+            line_number: start_line + 1,        // Convert to 1-based
+            column: start_pos.column(node) + 1, // Convert to 1-based
+            byte_range: (range.start, range.end),
+            enclosing: enclosing_symbols(node, lang),
+            context_before,
+            context_after,
+            captures: captured_variables(node_match),
+        }
+    }
+}
+
+/// Collects the text bound to each named meta-variable of a match.
+fn captured_variables(node_match: &NodeMatch<StrDoc<SupportLang>>) -> BTreeMap<String, String> {
+    let env = node_match.get_env();
+    env.get_matched_variables()
+        .filter_map(|var| match var {
+            MetaVariable::Capture(name, _) => {
+                let text = env.get_match(&name)?.text().to_string();
+                Some((name, text))
+            }
+            MetaVariable::MultiCapture(name) => {
+                let nodes = env.get_multiple_matches(&name);
+                // Take the source span covering all nodes, including separators
+                let text = match (nodes.first(), nodes.last()) {
+                    (Some(first), Some(last)) => {
+                        let source = node_match.get_node().root().get_text();
+                        source[first.range().start..last.range().end].to_string()
+                    }
+                    _ => String::new(),
+                };
+                Some((name, text))
+            }
+            _ => None,
+        })
+        .collect()
 }
 
 /// Represents all AST matches found in a specific file.
@@ -39,6 +153,68 @@ pub struct AstFileSearchResult {
     pub matches: Vec<AstMatchResult>,
 }
 
+/// Results of [`FileSystemService::search_files_ast_with_outcome`], with the number of files
+/// parsed.
+#[derive(Debug, Clone, Default)]
+pub struct AstSearchOutcome {
+    pub results: Vec<AstFileSearchResult>,
+    /// Files parsed and searched.
+    pub files_scanned: usize,
+    /// The search stopped at the maximum number of files, so results may be incomplete.
+    pub reached_file_limit: bool,
+    /// Files skipped because they are larger than the maximum file size.
+    pub skipped_large_files: usize,
+    /// The maximum file size applied, in bytes.
+    pub max_file_size: u64,
+    /// The maximum number of files applied.
+    pub max_files: usize,
+}
+
+/// The outcome of rewriting a single file with [`FileSystemService::rewrite_files_ast`].
+#[derive(Debug, Clone)]
+pub struct AstRewriteResult {
+    /// The path to the rewritten file
+    pub file_path: PathBuf,
+    /// Number of matches that were replaced
+    pub replacements: usize,
+    /// Unified diff between the original and the rewritten content
+    pub diff: String,
+}
+
+/// Replaces every match of `pattern` in `content` using the `fix` template.
+/// Returns the new content and the number of replacements; matches nested inside an
+/// already replaced match are skipped.
+fn rewrite_content(
+    content: &str,
+    lang: SupportLang,
+    pattern: &Pattern,
+    fix: &TemplateFix,
+) -> ServiceResult<(String, usize)> {
+    let root = lang.ast_grep(content);
+    let mut edits = Vec::new();
+    let mut last_end = 0;
+    for node_match in root.root().find_all(pattern) {
+        let edit = node_match.make_edit(pattern, fix);
+        if edit.position < last_end {
+            continue;
+        }
+        last_end = edit.position + edit.deleted_length;
+        edits.push(edit);
+    }
+
+    let mut bytes = content.as_bytes().to_vec();
+    for edit in edits.iter().rev() {
+        bytes.splice(
+            edit.position..edit.position + edit.deleted_length,
+            edit.inserted_text.iter().copied(),
+        );
+    }
+    let new_content = String::from_utf8(bytes).map_err(|err| {
+        ServiceError::FromString(format!("Rewrite produced invalid UTF-8: {err}"))
+    })?;
+    Ok((new_content, edits.len()))
+}
+
 impl FileSystemService {
     /// Searches code using AST pattern matching.
     ///
@@ -82,18 +258,7 @@ impl FileSystemService {
         let matches: Vec<_> = root
             .root()
             .find_all(pattern)
-            .map(|node_match| {
-                let node = node_match.get_node();
-                let range = node.range();
-                let start_pos = node.start_pos();
-
-                AstMatchResult {
-                    matched_code: node.text().to_string(),
-                    line_number: start_pos.line() + 1, // Convert to 1-based
-                    column: start_pos.column(&node) + 1, // Convert to 1-based
-                    byte_range: (range.start, range.end),
-                }
-            })
+            .map(|node_match| AstMatchResult::from_node(&node_match, lang, &[], 0))
             .collect();
 
         if matches.is_empty() {
@@ -106,6 +271,106 @@ impl FileSystemService {
         }))
     }
 
+    /// Rewrites code matching an AST pattern across multiple files.
+    ///
+    /// Files are selected the same way as in [`Self::search_files_ast`]. Each match is replaced
+    /// by the `rewrite` template, in which `$VAR`/`$$$VAR` meta-variables captured by the pattern
+    /// are substituted. When `dry_run` is true, files are left untouched and only the diffs are
+    /// returned.
+    ///
+    /// `limits` applies as in [`Self::search_files_ast_with_outcome`]. Nothing is rewritten or
+    /// previewed if the search stops at the maximum number of files, since matching files past
+    /// the limit would be silently left unchanged.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn rewrite_files_ast(
+        &self,
+        root_path: impl AsRef<Path>,
+        file_pattern: &str,
+        ast_pattern: &str,
+        rewrite: &str,
+        language: &str,
+        exclude_patterns: Option<Vec<String>>,
+        file_extensions: Option<Vec<String>>,
+        limits: AstSearchLimits,
+        dry_run: bool,
+    ) -> ServiceResult<Vec<AstRewriteResult>> {
+        let lang = self.parse_language(language)?;
+        let fix = TemplateFix::try_new(rewrite, &lang).map_err(|err| {
+            ServiceError::InvalidPattern(format!("Invalid rewrite template: {err}"))
+        })?;
+
+        let outcome = self
+            .search_files_ast_with_outcome(
+                root_path,
+                file_pattern,
+                ast_pattern,
+                language,
+                None,
+                exclude_patterns,
+                file_extensions,
+                None,
+                WalkOptions::default(),
+                limits,
+                &OperationContext::default(),
+            )
+            .await?;
+        if outcome.reached_file_limit {
+            return Err(ServiceError::InvalidArgument(format!(
+                "Stopped after {} files, so matching files past the limit would be left unchanged and nothing was rewritten. Narrow the files to rewrite or raise the maximum number of files.",
+                outcome.max_files
+            )));
+        }
+        let matching_files = outcome.results;
+
+        // refuse the whole rewrite up-front if any file lies in a read-only directory
+        if !dry_run {
+            let allowed_directories = self.allowed_directories().await;
+            for file in &matching_files {
+                self.validate_write_path(&file.file_path, allowed_directories.clone())?;
+            }
+        }
+
+        let pattern = Pattern::new(ast_pattern, lang);
+        let mut results = Vec::with_capacity(matching_files.len());
+        let mut changes = vec![];
+        let rewritten: ServiceResult<()> = async {
+            for file in matching_files {
+                let content = tokio::fs::read_to_string(&file.file_path).await?;
+                let (new_content, replacements) = rewrite_content(&content, lang, &pattern, &fix)?;
+                if new_content == content {
+                    continue;
+                }
+
+                let diff = self.create_unified_diff(
+                    &content,
+                    &new_content,
+                    Some(file.file_path.display().to_string()),
+                );
+                if !dry_run {
+                    let backup = self.journal_backup(&file.file_path).await?;
+                    self.write_content(&file.file_path, new_content.as_bytes())
+                        .await?;
+                    changes.push(JournalChange::Write {
+                        path: file.file_path.clone(),
+                        backup,
+                    });
+                }
+                results.push(AstRewriteResult {
+                    file_path: file.file_path,
+                    replacements,
+                    diff,
+                });
+            }
+            Ok(())
+        }
+        .await;
+        // files rewritten before a failure are journaled as well
+        self.journal_record("rewrite_code_ast", changes);
+        rewritten?;
+        results.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        Ok(results)
+    }
+
     /// Validates an AST pattern by attempting to parse it.
     /// Returns an error if the pattern is invalid.
     fn validate_pattern(&self, pattern: &str, lang: SupportLang) -> ServiceResult<()> {
@@ -115,29 +380,29 @@ impl FileSystemService {
         // Check if the pattern parsed to an empty AST (complete failure)
         let root_text = root.root().text();
         if root_text.is_empty() && !pattern.trim().is_empty() {
-            return Err(ServiceError::FromString(format!(
+            return Err(ServiceError::InvalidPattern(format!(
                 "Invalid AST pattern syntax. The pattern could not be parsed as valid {} code.\n\
                 Please check the pattern syntax and ensure it follows the language grammar.\n\
                 Documentation: https://ast-grep.github.io/guide/pattern-syntax.html",
-                lang.to_string()
+                lang
             )));
         }
 
         // Check if the parsed AST contains ERROR nodes, which indicates syntax errors
         let pattern_ast = Pattern::new(pattern, lang);
         if pattern_ast.has_error() {
-            return Err(ServiceError::FromString(format!(
+            return Err(ServiceError::InvalidPattern(format!(
                 "Invalid AST pattern syntax. The pattern contains syntax errors (ERROR nodes).\n\
                 Please verify the pattern follows valid {} syntax.\n\
                 Documentation: https://ast-grep.github.io/guide/pattern-syntax.html",
-                lang.to_string()
+                lang
             )));
         }
 
         Ok(())
     }
     /// Parse language string to ast-grep Language
-    fn parse_language(&self, language: &str) -> ServiceResult<SupportLang> {
+    pub(crate) fn parse_language(&self, language: &str) -> ServiceResult<SupportLang> {
         let lang = match language.to_lowercase().as_str() {
             "typescript" | "ts" => SupportLang::TypeScript,
             "tsx" => SupportLang::Tsx,
@@ -166,7 +431,7 @@ impl FileSystemService {
             "nix" => SupportLang::Nix,
             "hcl" | "terraform" => SupportLang::Hcl,
             _ => {
-                return Err(ServiceError::FromString(format!(
+                return Err(ServiceError::InvalidArgument(format!(
                     "Unsupported language: {}",
                     language
                 )));
@@ -193,6 +458,9 @@ impl FileSystemService {
     /// * `language` - The programming language
     /// * `exclude_patterns` - Optional patterns to exclude (applied during file traversal)
     /// * `file_extensions` - Optional file extensions filter (e.g., ["ts", "tsx"])
+    /// * `context_lines` - Optional number of source lines to include before and after each match
+    /// * `operation` - Receives progress updates (files scanned, matches so far)
+    #[allow(clippy::too_many_arguments)]
     pub async fn search_files_ast(
         &self,
         root_path: impl AsRef<Path>,
@@ -201,16 +469,215 @@ impl FileSystemService {
         language: &str,
         exclude_patterns: Option<Vec<String>>,
         file_extensions: Option<Vec<String>>,
+        context_lines: Option<usize>,
+        walk_options: WalkOptions,
+        operation: &OperationContext,
     ) -> ServiceResult<Vec<AstFileSearchResult>> {
-        const MAX_FILES_WARNING: usize = 2000;
-        const MAX_FILES_LIMIT: usize = 10000;
-        const MAX_FILE_SIZE: u64 = 1024 * 1024; // 1MB - skip very large files
-
-        let root_path = root_path.as_ref();
+        let outcome = self
+            .search_files_ast_with_outcome(
+                root_path,
+                file_pattern,
+                ast_pattern,
+                language,
+                None,
+                exclude_patterns,
+                file_extensions,
+                context_lines,
+                walk_options,
+                AstSearchLimits::default(),
+                operation,
+            )
+            .await?;
+        Ok(outcome.results)
+    }
 
+    /// Like [`Self::search_files_ast`], also returning the number of files searched, the files
+    /// skipped for their size and whether the search stopped at the maximum number of files.
+    /// `limits` overrides the server's maximum file size and number of files.
+    ///
+    /// With `injected_language`, files are parsed as `language` and the pattern, written in the
+    /// injected language, is matched against the code embedded in them, e.g. the JavaScript of
+    /// the `<script>` elements of HTML files.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn search_files_ast_with_outcome(
+        &self,
+        root_path: impl AsRef<Path>,
+        file_pattern: &str,
+        ast_pattern: &str,
+        language: &str,
+        injected_language: Option<&str>,
+        exclude_patterns: Option<Vec<String>>,
+        file_extensions: Option<Vec<String>>,
+        context_lines: Option<usize>,
+        walk_options: WalkOptions,
+        limits: AstSearchLimits,
+        operation: &OperationContext,
+    ) -> ServiceResult<AstSearchOutcome> {
         // Parse language and validate pattern upfront before searching files
         let lang = self.parse_language(language)?;
-        self.validate_pattern(ast_pattern, lang)?;
+        let injected = self.parse_injected_language(lang, injected_language)?;
+        self.validate_pattern(ast_pattern, injected.unwrap_or(lang))?;
+
+        // Create pattern once for reuse
+        let pattern_obj = Pattern::new(ast_pattern, injected.unwrap_or(lang));
+
+        self.search_files_with_matcher(
+            root_path.as_ref(),
+            file_pattern,
+            pattern_obj,
+            lang,
+            injected,
+            exclude_patterns,
+            file_extensions,
+            context_lines.unwrap_or(0),
+            walk_options,
+            limits,
+            operation,
+        )
+        .await
+    }
+
+    /// Search across multiple files using an ast-grep YAML rule.
+    ///
+    /// The rule uses the ast-grep rule syntax (https://ast-grep.github.io/reference/rule.html):
+    /// a `rule` object combining atomic rules (`pattern`, `kind`, `regex`), relational rules
+    /// (`inside`, `has`, `follows`, `precedes`) and composite rules (`all`, `any`, `not`,
+    /// `matches`), with optional `constraints`, `utils` and `transform` sections.
+    /// Complete rule files (with `id`, `language`, `message`, ...) are accepted as well;
+    /// only the matching-related sections are used.
+    ///
+    /// Files are selected the same way as in [`Self::search_files_ast`].
+    #[allow(clippy::too_many_arguments)]
+    pub async fn search_files_ast_rule(
+        &self,
+        root_path: impl AsRef<Path>,
+        file_pattern: &str,
+        rule_yaml: &str,
+        language: &str,
+        exclude_patterns: Option<Vec<String>>,
+        file_extensions: Option<Vec<String>>,
+        context_lines: Option<usize>,
+        walk_options: WalkOptions,
+        operation: &OperationContext,
+    ) -> ServiceResult<Vec<AstFileSearchResult>> {
+        let outcome = self
+            .search_files_ast_rule_with_outcome(
+                root_path,
+                file_pattern,
+                rule_yaml,
+                language,
+                None,
+                exclude_patterns,
+                file_extensions,
+                context_lines,
+                walk_options,
+                AstSearchLimits::default(),
+                operation,
+            )
+            .await?;
+        Ok(outcome.results)
+    }
+
+    /// Like [`Self::search_files_ast_rule`], with the search outcome and injected language of
+    /// [`Self::search_files_ast_with_outcome`].
+    #[allow(clippy::too_many_arguments)]
+    pub async fn search_files_ast_rule_with_outcome(
+        &self,
+        root_path: impl AsRef<Path>,
+        file_pattern: &str,
+        rule_yaml: &str,
+        language: &str,
+        injected_language: Option<&str>,
+        exclude_patterns: Option<Vec<String>>,
+        file_extensions: Option<Vec<String>>,
+        context_lines: Option<usize>,
+        walk_options: WalkOptions,
+        limits: AstSearchLimits,
+        operation: &OperationContext,
+    ) -> ServiceResult<AstSearchOutcome> {
+        let lang = self.parse_language(language)?;
+        let injected = self.parse_injected_language(lang, injected_language)?;
+        let rule = Self::parse_rule(rule_yaml, injected.unwrap_or(lang))?;
+
+        self.search_files_with_matcher(
+            root_path.as_ref(),
+            file_pattern,
+            rule,
+            lang,
+            injected,
+            exclude_patterns,
+            file_extensions,
+            context_lines.unwrap_or(0),
+            walk_options,
+            limits,
+            operation,
+        )
+        .await
+    }
+
+    /// Parses the language embedded in files of `host` that a search matches against, checking
+    /// that `host` files can contain it.
+    fn parse_injected_language(
+        &self,
+        host: SupportLang,
+        injected_language: Option<&str>,
+    ) -> ServiceResult<Option<SupportLang>> {
+        let Some(injected_language) = injected_language else {
+            return Ok(None);
+        };
+        let injected = self.parse_language(injected_language)?;
+        let injectable: Vec<SupportLang> = host
+            .injectable_languages()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|name| name.parse().ok())
+            .collect();
+        if !injectable.contains(&injected) {
+            let supported = if injectable.is_empty() {
+                "none".to_string()
+            } else {
+                injectable
+                    .iter()
+                    .map(|lang| lang.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            return Err(ServiceError::InvalidArgument(format!(
+                "{injected} code embedded in {host} files is not supported. Languages embedded in {host}: {supported}."
+            )));
+        }
+        Ok(Some(injected))
+    }
+
+    /// Parses an ast-grep YAML rule into a matcher for the given language.
+    fn parse_rule(rule_yaml: &str, lang: SupportLang) -> ServiceResult<RuleCore> {
+        let rule_core: SerializableRuleCore = ast_grep_config::from_str(rule_yaml)
+            .map_err(|err| ServiceError::InvalidPattern(format!("Invalid rule YAML: {err}")))?;
+        rule_core
+            .get_matcher(DeserializeEnv::new(lang))
+            .map_err(|err| ServiceError::InvalidPattern(format!("Invalid rule: {err}")))
+    }
+
+    /// Walks the files under `root_path` that pass the glob, exclude and extension filters
+    /// and collects the nodes matched by `matcher` in each of them, or in the code of the
+    /// `injected` language embedded in them.
+    #[allow(clippy::too_many_arguments)]
+    async fn search_files_with_matcher<M: Matcher + Send + Sync>(
+        &self,
+        root_path: &Path,
+        file_pattern: &str,
+        matcher: M,
+        lang: SupportLang,
+        injected: Option<SupportLang>,
+        exclude_patterns: Option<Vec<String>>,
+        file_extensions: Option<Vec<String>>,
+        context_lines: usize,
+        walk_options: WalkOptions,
+        limits: AstSearchLimits,
+        operation: &OperationContext,
+    ) -> ServiceResult<AstSearchOutcome> {
+        let max_file_size = limits.max_file_size.unwrap_or(self.ast_max_file_size());
+        let max_files = limits.max_files.unwrap_or(self.ast_max_files());
 
         // Validate root path
         self.validate_path(root_path, self.allowed_directories().await)?;
@@ -230,14 +697,8 @@ impl FileSystemService {
 
         // Build walker with ignore crate
         let mut builder = WalkBuilder::new(root_path);
-        builder
-            .follow_links(false)
-            .max_depth(Some(20))
-            .git_ignore(true)
-            .git_global(true)
-            .git_exclude(true)
-            .ignore(true)
-            .hidden(true);
+        builder.max_depth(Some(20));
+        self.configure_walker(&mut builder, walk_options, self.allowed_directories().await);
 
         // Use channel for result collection (no lock contention)
         let (tx, rx) = mpsc::channel::<AstFileSearchResult>();
@@ -245,13 +706,14 @@ impl FileSystemService {
         // Use atomic counter (no lock contention)
         let file_count = Arc::new(AtomicUsize::new(0));
         let file_count_clone = Arc::clone(&file_count);
+        let skipped_large_files = AtomicUsize::new(0);
 
         // Clone data for the parallel closure
         let root_path_buf = root_path.to_path_buf();
 
-        // Create pattern once for reuse
-        let pattern_obj = Pattern::new(ast_pattern, lang);
-        let pattern_obj = Arc::new(pattern_obj);
+        let pattern_obj = Arc::new(matcher);
+        let progress = SearchProgress::new(operation);
+        let ast_cache = self.ast_cache();
 
         // Use build_parallel for concurrent directory traversal + AST search
         builder.build_parallel().run(|| {
@@ -262,10 +724,16 @@ impl FileSystemService {
             let include_glob = Arc::clone(&include_glob);
             let exclude_glob = exclude_glob.clone();
             let extension_filters = extension_filters.clone();
+            let progress = &progress;
+            let skipped_large_files = &skipped_large_files;
 
             Box::new(move |entry_result| {
                 use ignore::WalkState;
 
+                if operation.is_cancelled() {
+                    return WalkState::Quit;
+                }
+
                 let entry = match entry_result {
                     Ok(entry) => entry,
                     Err(_) => return WalkState::Continue,
@@ -290,10 +758,10 @@ impl FileSystemService {
                 }
 
                 // Apply exclude patterns to full path
-                if let Some(ref excludes) = exclude_glob {
-                    if excludes.is_match(path) {
-                        return WalkState::Continue;
-                    }
+                if let Some(ref excludes) = exclude_glob
+                    && excludes.is_match(path)
+                {
+                    return WalkState::Continue;
                 }
 
                 // Apply file extension filter
@@ -307,50 +775,60 @@ impl FileSystemService {
                 }
 
                 // Apply file size filter
-                if let Ok(metadata) = entry.metadata() {
-                    if metadata.len() > MAX_FILE_SIZE {
-                        return WalkState::Continue;
-                    }
+                let metadata = entry.metadata().ok();
+                if let Some(metadata) = &metadata
+                    && metadata.len() > max_file_size
+                {
+                    skipped_large_files.fetch_add(1, Ordering::Relaxed);
+                    return WalkState::Continue;
                 }
 
                 // Count only files that pass all filters and will be AST-parsed
                 // Use atomic operation (no lock)
                 let count = file_count.fetch_add(1, Ordering::Relaxed);
-                if count >= MAX_FILES_LIMIT {
+                if count >= max_files {
                     return WalkState::Quit;
                 }
 
                 // Perform AST search on this file
-                if let Ok(content) = std::fs::read_to_string(path) {
-                    if !content.is_empty() {
-                        let root = lang.ast_grep(&content);
+                let mut match_count = 0;
+                if let Some(root) = ast_cache.parse(path, lang, metadata.as_ref()) {
+                    let lines: Vec<&str> = if context_lines > 0 {
+                        root.get_text().lines().collect()
+                    } else {
+                        vec![]
+                    };
+                    let find_matches = |root: &ParsedSource, lang: SupportLang| {
                         // Use reference instead of clone (performance fix)
-                        let matches: Vec<_> = root
-                            .root()
+                        root.root()
                             .find_all(pattern_obj.as_ref())
                             .map(|node_match| {
-                                let node = node_match.get_node();
-                                let range = node.range();
-                                let start_pos = node.start_pos();
-
-                                AstMatchResult {
-                                    matched_code: node.text().to_string(),
-                                    line_number: start_pos.line() + 1,
-                                    column: start_pos.column(&node) + 1,
-                                    byte_range: (range.start, range.end),
-                                }
+                                AstMatchResult::from_node(&node_match, lang, &lines, context_lines)
                             })
-                            .collect();
-
-                        if !matches.is_empty() {
-                            // Send via channel (no lock contention)
-                            let _ = tx.send(AstFileSearchResult {
-                                file_path: path.to_path_buf(),
-                                matches,
-                            });
-                        }
+                            .collect::<Vec<_>>()
+                    };
+                    let matches: Vec<_> = match injected {
+                        None => find_matches(&root, lang),
+                        // the embedded code keeps the positions of the host file
+                        Some(injected) => root
+                            .get_injections(|name| {
+                                name.parse().ok().filter(|lang| *lang == injected)
+                            })
+                            .iter()
+                            .flat_map(|injection| find_matches(injection, injected))
+                            .collect(),
+                    };
+
+                    match_count = matches.len();
+                    if !matches.is_empty() {
+                        // Send via channel (no lock contention)
+                        let _ = tx.send(AstFileSearchResult {
+                            file_path: path.to_path_buf(),
+                            matches,
+                        });
                     }
                 }
+                progress.file_scanned(match_count);
 
                 WalkState::Continue
             })
@@ -358,25 +836,149 @@ impl FileSystemService {
 
         // Drop sender to close channel
         drop(tx);
-
-        // Collect results from channel
-        let results: Vec<AstFileSearchResult> = rx.iter().collect();
+        operation.check_cancelled()?;
+        progress.finish();
+
+        // Collect results from channel, sorted since walker threads finish files in any order
+        let mut results: Vec<AstFileSearchResult> = rx.iter().collect();
+        results.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        for result in &mut results {
+            result.matches.sort_by_key(|m| (m.line_number, m.column));
+        }
 
         let final_count = file_count.load(Ordering::Relaxed);
 
-        // Provide feedback
-        if final_count >= MAX_FILES_LIMIT {
-            eprintln!(
-                "Warning: AST search hit maximum file limit of {}. Results may be incomplete.",
-                MAX_FILES_LIMIT
-            );
-            eprintln!(
-                "Consider narrowing your search with more specific patterns or exclude patterns."
-            );
-        } else if final_count >= MAX_FILES_WARNING {
-            eprintln!("Info: Searched {} files.", final_count);
+        Ok(AstSearchOutcome {
+            results,
+            files_scanned: progress.files_scanned(),
+            // the file counter is incremented once more by the file that stops the walk
+            reached_file_limit: final_count > max_files,
+            skipped_large_files: skipped_large_files.load(Ordering::Relaxed),
+            max_file_size,
+            max_files,
+        })
+    }
+}
+
+/// A parsed source file.
+type ParsedSource = AstGrep<StrDoc<SupportLang>>;
+
+struct CachedAst {
+    lang: SupportLang,
+    modified: SystemTime,
+    len: u64,
+    root: Arc<ParsedSource>,
+    /// Estimated memory of the tree, see [`AST_NODE_COST`].
+    cost: usize,
+}
+
+struct AstCacheState {
+    entries: LruCache<PathBuf, CachedAst>,
+    /// Total cost of `entries`.
+    cost: usize,
+}
+
+impl Default for AstCacheState {
+    fn default() -> Self {
+        Self {
+            entries: LruCache::new(AST_CACHE_MAX_ENTRIES),
+            cost: 0,
         }
+    }
+}
 
-        Ok(results)
+impl AstCacheState {
+    fn remove(&mut self, path: &Path) {
+        if let Some(entry) = self.entries.pop(path) {
+            self.cost -= entry.cost;
+        }
+    }
+}
+
+/// A bounded LRU cache of parse trees, so that repeated AST searches over unchanged files do not
+/// parse them again. Entries are keyed by path and only reused while the modification time and
+/// size of the file are unchanged; watched changes drop them right away.
+#[derive(Default)]
+pub(crate) struct AstCache {
+    state: Mutex<AstCacheState>,
+}
+
+impl AstCache {
+    /// Returns the parse tree of the file at `path` as `lang`, parsing it unless a tree of the
+    /// same version of the file is cached. `metadata` is the file's current metadata.
+    /// Returns `None` if the file cannot be read as text or is empty.
+    fn parse(
+        &self,
+        path: &Path,
+        lang: SupportLang,
+        metadata: Option<&std::fs::Metadata>,
+    ) -> Option<Arc<ParsedSource>> {
+        let version =
+            metadata.and_then(|metadata| Some((metadata.modified().ok()?, metadata.len())));
+        if let Some((modified, len)) = version
+            && let Ok(mut state) = self.state.lock()
+        {
+            if let Some(entry) = state.entries.get(path)
+                && entry.lang == lang
+                && entry.modified == modified
+                && entry.len == len
+            {
+                return Some(entry.root.clone());
+            }
+            state.remove(path);
+        }
+
+        // parse outside of the lock, so that walker threads do not wait for each other
+        let content = std::fs::read_to_string(path).ok()?;
+        if content.is_empty() {
+            return None;
+        }
+        let root = Arc::new(lang.ast_grep(content));
+        let Some((modified, len)) = version else {
+            return Some(root);
+        };
+        let cost = root.get_text().len() + root.root().dfs().count() * AST_NODE_COST;
+        if cost > AST_CACHE_MAX_BYTES {
+            return Some(root);
+        }
+
+        if let Ok(mut state) = self.state.lock() {
+            state.remove(path);
+            while state.cost + cost > AST_CACHE_MAX_BYTES {
+                match state.entries.pop_lru() {
+                    Some((_, evicted)) => state.cost -= evicted.cost,
+                    None => break,
+                }
+            }
+            let entry = CachedAst {
+                lang,
+                modified,
+                len,
+                root: root.clone(),
+                cost,
+            };
+            // the least recently used entry is evicted once the cache holds the maximum number
+            if let Some((_, evicted)) = state.entries.push(path.to_path_buf(), entry) {
+                state.cost -= evicted.cost;
+            }
+            state.cost += cost;
+        }
+        Some(root)
+    }
+
+    /// Drops the parse trees of `path` and of the files below it.
+    pub(crate) fn invalidate(&self, path: &Path) {
+        if let Ok(mut state) = self.state.lock() {
+            let stale: Vec<_> = state
+                .entries
+                .iter()
+                .map(|(cached, _)| cached)
+                .filter(|cached| cached.starts_with(path))
+                .cloned()
+                .collect();
+            for cached in stale {
+                state.remove(&cached);
+            }
+        }
     }
 }
@@ -0,0 +1,184 @@
+use crate::{
+    error::{ServiceError, ServiceResult},
+    fs_service::{
+        FileSystemService, WalkOptions,
+        io::{available_path, move_path},
+        journal::{JournalChange, missing_directories},
+        utils::format_system_time,
+    },
+};
+use rust_mcp_sdk::macros::JsonSchema;
+use std::{
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+/// What [`FileSystemService::cleanup_old_files`] does with the files it finds.
+#[derive(
+    ::serde::Deserialize, ::serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanupAction {
+    /// Delete the files, or move them to the trash when it is enabled.
+    #[default]
+    Delete,
+    /// Move the files into an archive directory, keeping their path relative to the root.
+    Archive,
+}
+
+/// A file selected by [`FileSystemService::cleanup_old_files`].
+#[derive(::serde::Serialize, Debug, Clone, PartialEq)]
+pub struct CleanedFile {
+    pub path: String,
+    pub size: u64,
+    /// Local time of the last modification.
+    pub modified: String,
+    /// Where the file was archived to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archived_to: Option<String>,
+}
+
+/// The planned or applied outcome of [`FileSystemService::cleanup_old_files`].
+#[derive(::serde::Serialize, Debug, Clone, Default, PartialEq)]
+pub struct CleanupOutcome {
+    /// Files deleted or archived, or that would be on a dry run, oldest first.
+    pub files: Vec<CleanedFile>,
+    /// Total size of `files`.
+    pub bytes: u64,
+    /// Files that could not be cleaned up, with the reason.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<(String, String)>,
+}
+
+impl FileSystemService {
+    /// Deletes or archives the files below `root_path` whose name matches `pattern` and that were
+    /// last modified more than `older_than` ago. Archived files are moved below `archive_dir`,
+    /// which is skipped by the walk. With `dry_run` nothing is changed and the plan is returned.
+    /// Files that cannot be cleaned up are reported in [`CleanupOutcome::errors`] and do not stop
+    /// the others.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn cleanup_old_files(
+        &self,
+        root_path: &Path,
+        pattern: String,
+        exclude_patterns: Vec<String>,
+        older_than: Duration,
+        action: CleanupAction,
+        archive_dir: Option<&Path>,
+        dry_run: bool,
+        walk_options: WalkOptions,
+    ) -> ServiceResult<CleanupOutcome> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_root = self.validate_path(root_path, allowed_directories.clone())?;
+        let archive_dir = match (action, archive_dir) {
+            (CleanupAction::Archive, Some(archive_dir)) => {
+                Some(self.validate_write_path(archive_dir, allowed_directories.clone())?)
+            }
+            (CleanupAction::Archive, None) => {
+                return Err(ServiceError::InvalidArgument(
+                    "An 'archive_dir' is required to archive files.".to_string(),
+                ));
+            }
+            (CleanupAction::Delete, _) => None,
+        };
+        let cutoff = SystemTime::now()
+            .checked_sub(older_than)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+
+        let mut candidates: Vec<_> = self
+            .search_files_iter(
+                &valid_root,
+                pattern,
+                exclude_patterns,
+                None,
+                None,
+                None,
+                walk_options,
+            )
+            .await?
+            .filter(|entry| entry.file_type().is_some_and(|ft| ft.is_file()))
+            .filter(|entry| {
+                archive_dir
+                    .as_ref()
+                    .is_none_or(|archive_dir| !entry.path().starts_with(archive_dir))
+            })
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                let modified = metadata.modified().ok()?;
+                (modified < cutoff).then(|| (entry.into_path(), modified, metadata.len()))
+            })
+            .collect();
+        candidates.sort_by(|(a_path, a_time, _), (b_path, b_time, _)| {
+            a_time.cmp(b_time).then(a_path.cmp(b_path))
+        });
+
+        let mut outcome = CleanupOutcome::default();
+        let mut changes = vec![];
+        for (path, modified, size) in candidates {
+            let result = match self.validate_write_path(&path, allowed_directories.clone()) {
+                Ok(_) if dry_run => Ok(None),
+                Ok(valid_path) => match &archive_dir {
+                    Some(archive_dir) => {
+                        let relative = valid_path.strip_prefix(&valid_root).unwrap_or(&valid_path);
+                        let target = available_path(&archive_dir.join(relative));
+                        match self.validate_write_path(&target, allowed_directories.clone()) {
+                            Ok(target) => self
+                                .archive_file(&valid_path, &target, &mut changes)
+                                .await
+                                .map(|()| Some(target.display().to_string())),
+                            Err(err) => Err(err),
+                        }
+                    }
+                    None => self.remove_entry(&valid_path).await.map(|change| {
+                        changes.push(change);
+                        None
+                    }),
+                },
+                Err(err) => Err(err),
+            };
+            let path = path.display().to_string();
+            match result {
+                Ok(archived_to) => {
+                    outcome.bytes += size;
+                    outcome.files.push(CleanedFile {
+                        path,
+                        size,
+                        modified: format_system_time(modified),
+                        archived_to,
+                    });
+                }
+                Err(err) => outcome.errors.push((path, err.to_string())),
+            }
+        }
+
+        if !dry_run {
+            self.journal_record("cleanup_old_files", changes);
+        }
+        Ok(outcome)
+    }
+
+    /// Moves `path` to `target`, creating the missing parents of `target`. The changes made are
+    /// appended to `changes`.
+    async fn archive_file(
+        &self,
+        path: &Path,
+        target: &Path,
+        changes: &mut Vec<JournalChange>,
+    ) -> ServiceResult<()> {
+        if let Some(parent) = target.parent() {
+            let created = missing_directories(parent);
+            tokio::fs::create_dir_all(parent).await?;
+            changes.extend(
+                created
+                    .into_iter()
+                    .map(|path| JournalChange::CreateDirectory { path }),
+            );
+        }
+        move_path(path, target).await?;
+        changes.push(JournalChange::Move {
+            from: path.to_path_buf(),
+            to: target.to_path_buf(),
+        });
+        Ok(())
+    }
+}
@@ -1,7 +1,11 @@
+use crate::fs_service::WalkOptions;
+#[cfg(feature = "mmap")]
+use crate::fs_service::mmap;
 use crate::{
     error::ServiceResult,
     fs_service::{
         FileSystemService,
+        operation::{OperationContext, SearchProgress},
         search::glob_utils::{compile_exclude_glob, compile_single_glob},
         utils::escape_regex,
     },
@@ -9,16 +13,21 @@ use crate::{
 use grep::{
     matcher::{Match, Matcher},
     regex::{RegexMatcher, RegexMatcherBuilder},
-    searcher::{BinaryDetection, Searcher, sinks::UTF8},
+    searcher::{BinaryDetection, Searcher, SearcherBuilder, Sink, SinkMatch, sinks::UTF8},
 };
 use ignore::WalkBuilder;
+use rust_mcp_sdk::macros::JsonSchema;
 use std::{
     path::{Path, PathBuf},
-    sync::{Arc, mpsc},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
 };
 
-const SNIPPET_MAX_LENGTH: usize = 200;
-const SNIPPET_BACKWARD_CHARS: usize = 30;
+pub(super) const SNIPPET_MAX_LENGTH: usize = 200;
+pub(super) const SNIPPET_BACKWARD_CHARS: usize = 30;
 
 /// Represents a single match found in a file's content.
 #[derive(Debug, Clone)]
@@ -26,6 +35,8 @@ pub struct ContentMatchResult {
     /// The line number where the match occurred (1-based).
     pub line_number: u64,
     pub start_pos: usize,
+    /// Byte offset in the line just past the match.
+    pub end_pos: usize,
     /// The line of text containing the match.
     /// If the line exceeds 255 characters (excluding the search term), only a truncated portion will be shown.
     pub line_text: String,
@@ -40,6 +51,143 @@ pub struct FileSearchResult {
     pub matches: Vec<ContentMatchResult>,
 }
 
+/// What [`FileSystemService::search_files_content_with_options`] does with files that have a
+/// matching line longer than [`ContentSearchOptions::max_line_length`], typically minified
+/// bundles.
+#[derive(
+    ::serde::Deserialize, ::serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema,
+)]
+pub enum LongLineAction {
+    /// Skip the whole file.
+    #[default]
+    #[serde(rename = "skip")]
+    Skip,
+    /// Keep the matches, with snippets cut to the maximum line length.
+    #[serde(rename = "truncate")]
+    Truncate,
+}
+
+/// How a content search treats binary files and very long lines, and how it presents matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentSearchOptions {
+    /// Skip files that contain NUL bytes. Otherwise they are searched as lossy UTF-8 text.
+    pub skip_binary: bool,
+    /// Length in characters above which a matching line is handled by `long_lines`.
+    pub max_line_length: Option<usize>,
+    pub long_lines: LongLineAction,
+    /// Markers inserted before and after the matched text in snippets, e.g. `«` and `»`.
+    pub highlight: Option<(String, String)>,
+    /// Report the lines that do NOT match the query, like `grep -v`. Their match is empty.
+    pub invert_match: bool,
+    /// Only search files whose path relative to the root matches this regex.
+    pub path_regex: Option<String>,
+}
+
+impl Default for ContentSearchOptions {
+    fn default() -> Self {
+        Self {
+            skip_binary: true,
+            max_line_length: None,
+            long_lines: LongLineAction::default(),
+            highlight: None,
+            invert_match: false,
+            path_regex: None,
+        }
+    }
+}
+
+/// Results of [`FileSystemService::search_files_content_with_options`], with the number of
+/// files left out.
+#[derive(Debug, Clone, Default)]
+pub struct ContentSearchOutcome {
+    pub results: Vec<FileSearchResult>,
+    /// Files whose content was searched, including skipped ones.
+    pub files_scanned: usize,
+    /// Binary files skipped.
+    pub skipped_binary_files: usize,
+    /// Files skipped because of a matching line longer than the maximum line length.
+    pub skipped_long_line_files: usize,
+}
+
+/// Outcome of searching a single file.
+enum FileScan {
+    Matches(Option<FileSearchResult>),
+    Binary,
+    LongLines,
+}
+
+/// Collects the matches of a file, noting whether it turned out binary or has a matching line
+/// that is too long.
+struct MatchSink<'a> {
+    matcher: &'a RegexMatcher,
+    options: &'a ContentSearchOptions,
+    matches: Vec<ContentMatchResult>,
+    binary: bool,
+    long_lines: bool,
+}
+
+impl Sink for MatchSink<'_> {
+    type Error = std::io::Error;
+
+    fn matched(
+        &mut self,
+        _searcher: &Searcher,
+        sink_match: &SinkMatch<'_>,
+    ) -> Result<bool, Self::Error> {
+        let line = String::from_utf8_lossy(sink_match.bytes());
+        let found = if self.options.invert_match {
+            // an inverted match has no span, it is placed at the start of the text
+            let indent = line.len() - line.trim_start().len();
+            Match::new(indent, indent)
+        } else {
+            let Some(found) = self
+                .matcher
+                .find(line.as_bytes())
+                .map_err(std::io::Error::other)?
+            else {
+                return Ok(true);
+            };
+            found
+        };
+        let mut max_length = SNIPPET_MAX_LENGTH;
+        if let Some(max_line_length) = self.options.max_line_length
+            && line.trim_end().chars().count() > max_line_length
+        {
+            if self.options.long_lines == LongLineAction::Skip {
+                self.long_lines = true;
+                return Ok(false);
+            }
+            max_length = max_length.min(max_line_length);
+        }
+        self.matches.push(ContentMatchResult {
+            line_number: sink_match.line_number().unwrap_or_default(),
+            start_pos: found.start(),
+            end_pos: found.end(),
+            line_text: FileSystemService::extract_snippet_static(
+                &line,
+                found,
+                max_length,
+                SNIPPET_BACKWARD_CHARS.min(max_length / 2),
+                self.options
+                    .highlight
+                    .as_ref()
+                    .filter(|_| !self.options.invert_match)
+                    .map(|(start, end)| (start.as_str(), end.as_str())),
+            ),
+        });
+        Ok(true)
+    }
+
+    fn binary_data(
+        &mut self,
+        _searcher: &Searcher,
+        _binary_byte_offset: u64,
+    ) -> Result<bool, Self::Error> {
+        self.binary = true;
+        Ok(false)
+    }
+}
+
 impl FileSystemService {
     // Searches the content of a file for occurrences of the given query string.
     ///
@@ -65,14 +213,16 @@ impl FileSystemService {
             .case_insensitive(true)
             .build(query.as_str())?;
 
-        let mut searcher = Searcher::new();
+        let mut builder = SearcherBuilder::new();
+        builder.binary_detection(BinaryDetection::quit(b'\x00'));
+        #[cfg(feature = "mmap")]
+        builder.memory_map(mmap::mmap_choice(file_path.as_ref()));
+        let mut searcher = builder.build();
         let mut result = FileSearchResult {
             file_path: file_path.as_ref().to_path_buf(),
             matches: vec![],
         };
 
-        searcher.set_binary_detection(BinaryDetection::quit(b'\x00'));
-
         searcher.search_path(
             &matcher,
             file_path,
@@ -82,6 +232,7 @@ impl FileSystemService {
                 result.matches.push(ContentMatchResult {
                     line_number,
                     start_pos: actual_match.start(),
+                    end_pos: actual_match.end(),
                     line_text: self.extract_snippet(line, actual_match, None, None),
                 });
                 Ok(true)
@@ -98,11 +249,13 @@ impl FileSystemService {
     /// Extracts a snippet from a given line of text around a match.
     ///
     /// Static helper function that doesn't depend on self, enabling use in parallel contexts.
-    fn extract_snippet_static(
+    /// With `markers`, the part of the match within the snippet is wrapped in them.
+    pub(super) fn extract_snippet_static(
         line: &str,
         match_result: Match,
         max_length: usize,
         backward_chars: usize,
+        markers: Option<(&str, &str)>,
     ) -> String {
         // Calculate the number of leading whitespace bytes to adjust for trimmed input
         let start_pos = line.len() - line.trim_start().len();
@@ -149,7 +302,21 @@ impl FileSystemService {
             result.push_str("...");
         }
 
-        result.push_str(snippet);
+        // Wrap the part of the match inside the snippet in the markers
+        let match_start = match_result.start().saturating_sub(start_pos);
+        let match_end = match_result.end().saturating_sub(start_pos);
+        match markers {
+            Some((open, close)) if match_start < snippet_end && match_end >= snippet_start => {
+                let highlight_start = match_start.clamp(snippet_start, snippet_end);
+                let highlight_end = match_end.clamp(highlight_start, snippet_end);
+                result.push_str(&line[snippet_start..highlight_start]);
+                result.push_str(open);
+                result.push_str(&line[highlight_start..highlight_end]);
+                result.push_str(close);
+                result.push_str(&line[highlight_end..snippet_end]);
+            }
+            _ => result.push_str(snippet),
+        }
 
         // Add trailing ellipsis if the snippet doesn't reach the end of the trimmed line
         if snippet_end < line.len() {
@@ -173,9 +340,14 @@ impl FileSystemService {
             match_result,
             max_length.unwrap_or(SNIPPET_MAX_LENGTH),
             backward_chars.unwrap_or(SNIPPET_BACKWARD_CHARS),
+            None,
         )
     }
 
+    /// Searches the content of the files under `root_path` whose name matches `pattern`. A
+    /// pattern containing a path separator, like `src/**/*.rs`, is matched against the path
+    /// relative to `root_path` (or the absolute path) instead of the file name.
+    /// Progress (files scanned, matches so far) is reported through `operation`.
     #[allow(clippy::too_many_arguments)]
     pub async fn search_files_content(
         &self,
@@ -186,13 +358,49 @@ impl FileSystemService {
         exclude_patterns: Option<Vec<String>>,
         min_bytes: Option<u64>,
         max_bytes: Option<u64>,
+        walk_options: WalkOptions,
+        operation: &OperationContext,
     ) -> ServiceResult<Vec<FileSearchResult>> {
+        let outcome = self
+            .search_files_content_with_options(
+                root_path,
+                pattern,
+                query,
+                is_regex,
+                exclude_patterns,
+                min_bytes,
+                max_bytes,
+                walk_options,
+                ContentSearchOptions::default(),
+                operation,
+            )
+            .await?;
+        Ok(outcome.results)
+    }
+
+    /// Like [`Self::search_files_content`], handling binary files and long lines as set in
+    /// `options` and counting the files skipped because of them.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn search_files_content_with_options(
+        &self,
+        root_path: impl AsRef<Path>,
+        pattern: &str,
+        query: &str,
+        is_regex: bool,
+        exclude_patterns: Option<Vec<String>>,
+        min_bytes: Option<u64>,
+        max_bytes: Option<u64>,
+        walk_options: WalkOptions,
+        options: ContentSearchOptions,
+        operation: &OperationContext,
+    ) -> ServiceResult<ContentSearchOutcome> {
         let root_path = root_path.as_ref();
 
         // Validate root path once
         self.validate_path(root_path, self.allowed_directories().await)?;
 
         let include_glob = Arc::new(compile_single_glob(pattern, "*", false)?);
+        let match_paths = pattern.contains('/') || pattern.contains(std::path::MAIN_SEPARATOR);
         let exclude_glob = compile_exclude_glob(exclude_patterns.as_deref(), false)?;
         let exclude_glob = exclude_glob.map(Arc::new);
 
@@ -206,18 +414,32 @@ impl FileSystemService {
             .case_insensitive(true)
             .build(&search_query)?;
         let matcher = Arc::new(matcher);
+        let path_matcher = options
+            .path_regex
+            .as_deref()
+            .map(RegexMatcher::new)
+            .transpose()?;
+
+        // Use a persistent content index covering root_path (if any) to skip files that cannot match.
+        // Regex queries cannot be decomposed into trigrams, so they always scan every file, and
+        // an inverted search wants precisely the files without the query.
+        let index_filter = if is_regex || options.invert_match {
+            None
+        } else {
+            self.load_content_index(root_path)
+                .and_then(|index| index.filter_for_query(query))
+                .map(Arc::new)
+        };
 
         let (tx, rx) = mpsc::channel::<FileSearchResult>();
+        let progress = SearchProgress::new(operation);
+        let skipped_binary_files = AtomicUsize::new(0);
+        let skipped_long_line_files = AtomicUsize::new(0);
 
         // Build parallel walker with ignore crate
         let mut builder = WalkBuilder::new(root_path);
-        builder
-            .follow_links(false)
-            .max_depth(Some(20))
-            .git_ignore(true)
-            .git_global(true)
-            .git_exclude(true)
-            .ignore(true);
+        builder.max_depth(Some(20));
+        self.configure_walker(&mut builder, walk_options, self.allowed_directories().await);
 
         // Use build_parallel for concurrent directory traversal + content search
         builder.build_parallel().run(|| {
@@ -225,10 +447,20 @@ impl FileSystemService {
             let include_glob = Arc::clone(&include_glob);
             let exclude_glob = exclude_glob.clone();
             let matcher = Arc::clone(&matcher);
+            let index_filter = index_filter.clone();
+            let progress = &progress;
+            let skipped_binary_files = &skipped_binary_files;
+            let skipped_long_line_files = &skipped_long_line_files;
+            let options = &options;
+            let path_matcher = path_matcher.as_ref();
 
             Box::new(move |entry_result| {
                 use ignore::WalkState;
 
+                if operation.is_cancelled() {
+                    return WalkState::Quit;
+                }
+
                 let entry = match entry_result {
                     Ok(entry) => entry,
                     Err(_) => return WalkState::Continue,
@@ -246,44 +478,81 @@ impl FileSystemService {
 
                 let path = entry.path();
 
-                // Apply file pattern filter - match against filename only, not full path
-                let filename = match path.file_name().and_then(|n| n.to_str()) {
-                    Some(name) => name,
-                    None => return WalkState::Continue,
-                };
-                if !include_glob.is_match(filename) {
-                    return WalkState::Continue;
+                // Apply file pattern filter - match against the relative (or absolute) path for
+                // patterns with a separator, against the filename otherwise
+                if match_paths {
+                    let relative_path = path.strip_prefix(root_path).unwrap_or(path);
+                    if !include_glob.is_match(relative_path) && !include_glob.is_match(path) {
+                        return WalkState::Continue;
+                    }
+                } else {
+                    let filename = match path.file_name().and_then(|n| n.to_str()) {
+                        Some(name) => name,
+                        None => return WalkState::Continue,
+                    };
+                    if !include_glob.is_match(filename) {
+                        return WalkState::Continue;
+                    }
                 }
 
-                // Apply exclude patterns
-                if let Some(ref excludes) = exclude_glob {
-                    if excludes.is_match(path) {
+                // Apply the path regex to the relative path, with `/` separators
+                if let Some(path_matcher) = path_matcher {
+                    let relative_path = path.strip_prefix(root_path).unwrap_or(path);
+                    let relative_path = relative_path.to_string_lossy().replace('\\', "/");
+                    if !path_matcher
+                        .is_match(relative_path.as_bytes())
+                        .unwrap_or(false)
+                    {
                         return WalkState::Continue;
                     }
                 }
 
+                // Apply exclude patterns
+                if let Some(ref excludes) = exclude_glob
+                    && excludes.is_match(path)
+                {
+                    return WalkState::Continue;
+                }
+
                 // Apply file size filters
-                if min_bytes.is_some() || max_bytes.is_some() {
-                    if let Ok(metadata) = entry.metadata() {
-                        let size = metadata.len();
-                        if let Some(min) = min_bytes {
-                            if size < min {
-                                return WalkState::Continue;
-                            }
-                        }
-                        if let Some(max) = max_bytes {
-                            if size > max {
-                                return WalkState::Continue;
-                            }
-                        }
+                if (min_bytes.is_some() || max_bytes.is_some())
+                    && let Ok(metadata) = entry.metadata()
+                {
+                    let size = metadata.len();
+                    if let Some(min) = min_bytes
+                        && size < min
+                    {
+                        return WalkState::Continue;
                     }
+                    if let Some(max) = max_bytes
+                        && size > max
+                    {
+                        return WalkState::Continue;
+                    }
+                }
+
+                // Skip files the content index proves cannot match
+                if let Some(ref index_filter) = index_filter
+                    && index_filter.can_skip(path, entry.metadata().ok().as_ref())
+                {
+                    return WalkState::Continue;
                 }
 
                 // Perform content search on this file
-                if let Ok(file_result) = Self::search_file_content_static(matcher.as_ref(), path) {
-                    if let Some(file_result) = file_result {
+                match Self::search_file_content_static(matcher.as_ref(), path, options) {
+                    Ok(FileScan::Matches(Some(file_result))) => {
+                        progress.file_scanned(file_result.matches.len());
                         let _ = tx.send(file_result);
                     }
+                    Ok(FileScan::Binary) => {
+                        skipped_binary_files.fetch_add(1, Ordering::Relaxed);
+                        progress.file_scanned(0);
+                    }
+                    Ok(FileScan::LongLines) => {
+                        skipped_long_line_files.fetch_add(1, Ordering::Relaxed);
+                        progress.file_scanned(0);
+                    }
+                    Ok(FileScan::Matches(None)) | Err(_) => progress.file_scanned(0),
                 }
 
                 WalkState::Continue
@@ -291,10 +560,22 @@ impl FileSystemService {
         });
 
         drop(tx);
+        operation.check_cancelled()?;
+        progress.finish();
+
+        // Walker threads finish files in any order, sort for reproducible results
+        let mut results: Vec<FileSearchResult> = rx.iter().collect();
+        results.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        for result in &mut results {
+            result.matches.sort_by_key(|m| (m.line_number, m.start_pos));
+        }
 
-        let results: Vec<FileSearchResult> = rx.iter().collect();
-
-        Ok(results)
+        Ok(ContentSearchOutcome {
+            results,
+            files_scanned: progress.files_scanned(),
+            skipped_binary_files: skipped_binary_files.into_inner(),
+            skipped_long_line_files: skipped_long_line_files.into_inner(),
+        })
     }
 
     /// Static helper method for searching file content (used in parallel walker).
@@ -302,42 +583,42 @@ impl FileSystemService {
     fn search_file_content_static(
         matcher: &RegexMatcher,
         file_path: &Path,
-    ) -> ServiceResult<Option<FileSearchResult>> {
-        let mut searcher = Searcher::new();
-        searcher.set_binary_detection(BinaryDetection::quit(b'\x00'));
-
-        let mut matches = Vec::new();
-
-        searcher.search_path(
+        options: &ContentSearchOptions,
+    ) -> ServiceResult<FileScan> {
+        let mut builder = SearcherBuilder::new();
+        builder
+            .invert_match(options.invert_match)
+            .binary_detection(if options.skip_binary {
+                BinaryDetection::quit(b'\x00')
+            } else {
+                BinaryDetection::none()
+            });
+        #[cfg(feature = "mmap")]
+        builder.memory_map(mmap::mmap_choice(file_path));
+        let mut searcher = builder.build();
+
+        let mut sink = MatchSink {
             matcher,
-            file_path,
-            UTF8(|line_number, line| {
-                if let Ok(Some(m)) = matcher.find(line.as_bytes()) {
-                    let start_pos = m.start();
-                    let line_text = Self::extract_snippet_static(
-                        line,
-                        m,
-                        SNIPPET_MAX_LENGTH,
-                        SNIPPET_BACKWARD_CHARS,
-                    );
-
-                    matches.push(ContentMatchResult {
-                        line_number,
-                        start_pos,
-                        line_text,
-                    });
-                }
-                Ok(true)
-            }),
-        )?;
+            options,
+            matches: Vec::new(),
+            binary: false,
+            long_lines: false,
+        };
+        searcher.search_path(matcher, file_path, &mut sink)?;
 
-        if matches.is_empty() {
-            return Ok(None);
+        if sink.binary {
+            return Ok(FileScan::Binary);
+        }
+        if sink.long_lines {
+            return Ok(FileScan::LongLines);
+        }
+        if sink.matches.is_empty() {
+            return Ok(FileScan::Matches(None));
         }
 
-        Ok(Some(FileSearchResult {
+        Ok(FileScan::Matches(Some(FileSearchResult {
             file_path: file_path.to_path_buf(),
-            matches,
-        }))
+            matches: sink.matches,
+        })))
     }
 }
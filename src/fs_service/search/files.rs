@@ -1,16 +1,120 @@
+use crate::fs_service::WalkOptions;
 use crate::{
     error::ServiceResult,
     fs_service::{
         FileSystemService,
+        core::file_identity,
+        journal::JournalChange,
+        operation::{OperationContext, ProgressUpdate},
         search::glob_utils::{compile_exclude_glob, compile_single_glob},
         utils::filesize_in_range,
     },
 };
 use ignore::WalkBuilder;
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use rust_mcp_sdk::macros::JsonSchema;
 use sha2::{Digest, Sha256};
-use std::{collections::HashMap, path::Path};
-use tokio::{fs::File, io::AsyncReadExt};
+use std::{
+    collections::{HashMap, HashSet},
+    io::Read,
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant, SystemTime},
+};
+use tokio::task::JoinSet;
+
+/// Number of files hashed at the same time when the number of CPUs is unknown.
+const DEFAULT_HASH_CONCURRENCY: usize = 4;
+
+/// Minimum delay between two progress updates while hashing.
+const HASH_PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Results of [`FileSystemService::search_files_with_outcome`], with the number of entries
+/// walked.
+#[derive(Debug, Default)]
+pub struct SearchFilesOutcome {
+    pub entries: Vec<ignore::DirEntry>,
+    /// Files and directories visited by the walk, matching or not.
+    pub entries_scanned: usize,
+}
+
+/// Sort key of [`sort_search_results`].
+#[derive(
+    ::serde::Deserialize, ::serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema,
+)]
+pub enum SearchSortBy {
+    /// By full path.
+    #[default]
+    #[serde(rename = "path")]
+    Path,
+    /// By file size, directories count as empty.
+    #[serde(rename = "size")]
+    Size,
+    /// By modification time.
+    #[serde(rename = "mtime")]
+    Mtime,
+}
+
+impl SearchSortBy {
+    /// The order used when none is given: paths ascending, the largest or most recently
+    /// modified entries first.
+    pub fn default_order(self) -> SortOrder {
+        match self {
+            SearchSortBy::Path => SortOrder::Asc,
+            SearchSortBy::Size | SearchSortBy::Mtime => SortOrder::Desc,
+        }
+    }
+}
+
+/// Direction of a sort.
+#[derive(
+    ::serde::Deserialize, ::serde::Serialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema,
+)]
+pub enum SortOrder {
+    #[serde(rename = "asc")]
+    Asc,
+    #[serde(rename = "desc")]
+    Desc,
+}
+
+/// Sorts search results by `sort_by` in `order`. Entries with the same size or modification
+/// time are ordered by path, so the order is deterministic.
+pub fn sort_search_results(
+    entries: &mut Vec<ignore::DirEntry>,
+    sort_by: SearchSortBy,
+    order: SortOrder,
+) {
+    let mut keyed: Vec<(u64, Option<SystemTime>, ignore::DirEntry)> = entries
+        .drain(..)
+        .map(|entry| {
+            let metadata = match sort_by {
+                SearchSortBy::Path => None,
+                _ => entry.metadata().ok(),
+            };
+            let size = metadata
+                .as_ref()
+                .filter(|metadata| metadata.is_file())
+                .map_or(0, |metadata| metadata.len());
+            let modified = metadata.and_then(|metadata| metadata.modified().ok());
+            (size, modified, entry)
+        })
+        .collect();
+    keyed.sort_by(|a, b| {
+        let ordering = match sort_by {
+            SearchSortBy::Path => a.2.path().cmp(b.2.path()),
+            SearchSortBy::Size => a.0.cmp(&b.0),
+            SearchSortBy::Mtime => a.1.cmp(&b.1),
+        };
+        let ordering = match order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        };
+        ordering.then_with(|| a.2.path().cmp(b.2.path()))
+    });
+    entries.extend(keyed.into_iter().map(|(.., entry)| entry));
+}
 
 impl FileSystemService {
     /// Searches for files in the directory tree starting at `root_path` that match the given `pattern`,
@@ -25,6 +129,7 @@ impl FileSystemService {
     /// # Returns
     /// A `ServiceResult` containing a vector of`walkdir::DirEntry` objects for matching files,
     /// or a `ServiceError` if an error occurs.
+    #[allow(clippy::too_many_arguments)]
     pub async fn search_files(
         &self,
         root_path: &Path,
@@ -33,6 +138,7 @@ impl FileSystemService {
         file_extensions: Option<Vec<String>>,
         min_bytes: Option<u64>,
         max_bytes: Option<u64>,
+        walk_options: WalkOptions,
     ) -> ServiceResult<Vec<ignore::DirEntry>> {
         let result = self
             .search_files_iter(
@@ -42,11 +148,44 @@ impl FileSystemService {
                 file_extensions,
                 min_bytes,
                 max_bytes,
+                walk_options,
             )
             .await?;
         Ok(result.collect::<Vec<ignore::DirEntry>>())
     }
 
+    /// Like [`Self::search_files`], also counting the entries walked.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn search_files_with_outcome(
+        &self,
+        root_path: &Path,
+        pattern: String,
+        exclude_patterns: Vec<String>,
+        file_extensions: Option<Vec<String>>,
+        min_bytes: Option<u64>,
+        max_bytes: Option<u64>,
+        walk_options: WalkOptions,
+    ) -> ServiceResult<SearchFilesOutcome> {
+        let entries_scanned = Arc::new(AtomicUsize::new(0));
+        let entries = self
+            .search_files_iter_counting(
+                root_path,
+                pattern,
+                exclude_patterns,
+                file_extensions,
+                min_bytes,
+                max_bytes,
+                walk_options,
+                Some(entries_scanned.clone()),
+            )
+            .await?
+            .collect();
+        Ok(SearchFilesOutcome {
+            entries,
+            entries_scanned: entries_scanned.load(Ordering::Relaxed),
+        })
+    }
+
     /// Returns an iterator over files in the directory tree starting at `root_path` that match
     /// the given `pattern`, excluding paths that match any of the `exclude_patterns`.
     ///
@@ -58,6 +197,7 @@ impl FileSystemService {
     /// # Returns
     /// A `ServiceResult` containing an iterator yielding `walkdir::DirEntry` objects for matching files,
     /// or a `ServiceError` if an error occurs.
+    #[allow(clippy::too_many_arguments)]
     pub async fn search_files_iter<'a>(
         &'a self,
         // root_path: impl Into<PathBuf>,
@@ -67,9 +207,36 @@ impl FileSystemService {
         file_extensions: Option<Vec<String>>,
         min_bytes: Option<u64>,
         max_bytes: Option<u64>,
+        walk_options: WalkOptions,
+    ) -> ServiceResult<impl Iterator<Item = ignore::DirEntry> + 'a> {
+        self.search_files_iter_counting(
+            root_path,
+            pattern,
+            exclude_patterns,
+            file_extensions,
+            min_bytes,
+            max_bytes,
+            walk_options,
+            None,
+        )
+        .await
+    }
+
+    /// Like [`Self::search_files_iter`], adding every entry walked to `entries_scanned`.
+    #[allow(clippy::too_many_arguments)]
+    async fn search_files_iter_counting<'a>(
+        &'a self,
+        root_path: &'a Path,
+        pattern: String,
+        exclude_patterns: Vec<String>,
+        file_extensions: Option<Vec<String>>,
+        min_bytes: Option<u64>,
+        max_bytes: Option<u64>,
+        walk_options: WalkOptions,
+        entries_scanned: Option<Arc<AtomicUsize>>,
     ) -> ServiceResult<impl Iterator<Item = ignore::DirEntry> + 'a> {
         let allowed_directories = self.allowed_directories().await;
-        let valid_path = self.validate_path(root_path, allowed_directories)?;
+        let valid_path = self.validate_path(root_path, allowed_directories.clone())?;
 
         let mut normalized_pattern = pattern;
         if !normalized_pattern.contains('*') && !normalized_pattern.contains('?') {
@@ -80,17 +247,20 @@ impl FileSystemService {
 
         let valid_path_for_filter = valid_path.clone();
 
-        let result = WalkBuilder::new(valid_path)
-            .follow_links(false) // Disable follow_links to prevent infinite loops
-            .max_depth(Some(20)) // Limit maximum depth to prevent excessive traversal
-            .git_ignore(true) // Respect .gitignore files (default: true)
-            .git_global(true) // Respect global gitignore (default: true)
-            .git_exclude(true) // Respect .git/info/exclude (default: true)
-            .ignore(true) // Respect .ignore files (default: true)
-            .hidden(true) // Skip hidden files (default: true)
-            .parents(true) // Read ignore files from parent directories (default: true)
+        let result = self
+            .configure_walker(
+                // Limit maximum depth to prevent excessive traversal, unless a depth is given
+                WalkBuilder::new(valid_path).max_depth(Some(20)),
+                walk_options,
+                allowed_directories,
+            )
             .build()
             .filter_map(|v| v.ok())
+            .inspect(move |_| {
+                if let Some(entries_scanned) = &entries_scanned {
+                    entries_scanned.fetch_add(1, Ordering::Relaxed);
+                }
+            })
             .filter(move |entry| {
                 let path = entry.path();
 
@@ -131,7 +301,7 @@ impl FileSystemService {
 
                 // Only check file size constraints if specified and entry is a file
                 if (min_bytes.is_some() || max_bytes.is_some())
-                    && entry.file_type().map_or(false, |ft| ft.is_file())
+                    && entry.file_type().is_some_and(|ft| ft.is_file())
                 {
                     if let Ok(metadata) = entry.metadata() {
                         return filesize_in_range(metadata.len(), min_bytes, max_bytes);
@@ -148,6 +318,12 @@ impl FileSystemService {
     /// Finds groups of duplicate files within the given root path.
     /// Returns a vector of vectors, where each inner vector contains paths to files with identical content.
     /// Files are considered duplicates if they have the same size and SHA-256 hash.
+    /// Hard links of the same file share their storage, so unless `skip_hardlinks` is false only
+    /// one of them (the first in path order) is considered.
+    /// At most `concurrency` files (default: the number of CPUs) are hashed at the same time on
+    /// blocking threads, and the number of hashed files is reported as progress.
+    /// Hashing stops early with [`crate::error::ServiceError::Cancelled`] once `operation` is cancelled.
+    #[allow(clippy::too_many_arguments)]
     pub async fn find_duplicate_files(
         &self,
         root_path: &Path,
@@ -155,13 +331,18 @@ impl FileSystemService {
         exclude_patterns: Option<Vec<String>>,
         min_bytes: Option<u64>,
         max_bytes: Option<u64>,
+        skip_hardlinks: bool,
+        concurrency: Option<usize>,
+        operation: &OperationContext,
     ) -> ServiceResult<Vec<Vec<String>>> {
         // Validate root path against allowed directories
         let allowed_directories = self.allowed_directories().await;
         let valid_path = self.validate_path(root_path, allowed_directories)?;
 
-        // Get Tokio runtime handle
-        let rt = tokio::runtime::Handle::current();
+        let concurrency = concurrency
+            .or_else(|| std::thread::available_parallelism().ok().map(usize::from))
+            .unwrap_or(DEFAULT_HASH_CONCURRENCY)
+            .max(1);
 
         // Step 1: Collect files and group by size
         let mut size_map: HashMap<u64, Vec<String>> = HashMap::new();
@@ -173,11 +354,13 @@ impl FileSystemService {
                 None, // No file extension filter
                 min_bytes,
                 max_bytes,
+                WalkOptions::default(),
             )
             .await?
-            .filter(|e| e.file_type().map_or(false, |ft| ft.is_file())); // Only files
+            .filter(|e| e.file_type().is_some_and(|ft| ft.is_file())); // Only files
 
         for entry in entries {
+            operation.check_cancelled()?;
             if let Ok(metadata) = entry.metadata()
                 && let Some(path_str) = entry.path().to_str()
             {
@@ -189,74 +372,42 @@ impl FileSystemService {
         }
 
         // Filter out sizes with only one file (no duplicates possible)
-        let size_groups: Vec<Vec<String>> = size_map
-            .into_iter()
-            .collect::<Vec<_>>() // Collect into Vec to enable parallel iteration
-            .into_par_iter()
-            .filter(|(_, paths)| paths.len() > 1)
-            .map(|(_, paths)| paths)
+        let size_groups: Vec<String> = size_map
+            .into_values()
+            .filter(|paths| paths.len() > 1)
+            .map(|paths| {
+                if skip_hardlinks {
+                    without_hardlinks(paths)
+                } else {
+                    paths
+                }
+            })
+            .filter(|paths| paths.len() > 1)
+            .flatten()
             .collect();
 
-        // Step 2: Group by quick hash (first 4KB)
+        // Step 2: Group by quick hash (first 4KB), files of different sizes never share a group
+        // because the size is part of the key
+        let quick_hashes = hash_files(size_groups, HashScope::Head, concurrency, operation).await?;
         let mut quick_hash_map: HashMap<Vec<u8>, Vec<String>> = HashMap::new();
-        for paths in size_groups.into_iter() {
-            let quick_hashes: Vec<(String, Vec<u8>)> = paths
-                .into_par_iter()
-                .filter_map(|path| {
-                    let rt = rt.clone(); // Clone the runtime handle for this task
-                    rt.block_on(async {
-                        let file = File::open(&path).await.ok()?;
-                        let mut reader = tokio::io::BufReader::new(file);
-                        let mut buffer = vec![0u8; 4096]; // Read first 4KB
-                        let bytes_read = reader.read(&mut buffer).await.ok()?;
-                        let mut hasher = Sha256::new();
-                        hasher.update(&buffer[..bytes_read]);
-                        Some((path, hasher.finalize().to_vec()))
-                    })
-                })
-                .collect();
-
-            for (path, hash) in quick_hashes {
-                quick_hash_map.entry(hash).or_default().push(path);
-            }
+        for (path, hash) in quick_hashes {
+            quick_hash_map.entry(hash).or_default().push(path);
         }
 
         // Step 3: Group by full hash for groups with multiple files
-        let mut full_hash_map: HashMap<Vec<u8>, Vec<String>> = HashMap::new();
-        let filtered_quick_hashes: Vec<(Vec<u8>, Vec<String>)> = quick_hash_map
-            .into_iter()
-            .collect::<Vec<_>>()
-            .into_par_iter()
-            .filter(|(_, paths)| paths.len() > 1)
+        let candidates: Vec<String> = quick_hash_map
+            .into_values()
+            .filter(|paths| paths.len() > 1)
+            .flatten()
             .collect();
-
-        for (_quick_hash, paths) in filtered_quick_hashes {
-            let full_hashes: Vec<(String, Vec<u8>)> = paths
-                .into_par_iter()
-                .filter_map(|path| {
-                    let rt = rt.clone(); // Clone the runtime handle for this task
-                    rt.block_on(async {
-                        let file = File::open(&path).await.ok()?;
-                        let mut reader = tokio::io::BufReader::new(file);
-                        let mut hasher = Sha256::new();
-                        let mut buffer = vec![0u8; 8192]; // 8KB chunks
-                        loop {
-                            let bytes_read = reader.read(&mut buffer).await.ok()?;
-                            if bytes_read == 0 {
-                                break;
-                            }
-                            hasher.update(&buffer[..bytes_read]);
-                        }
-                        Some((path, hasher.finalize().to_vec()))
-                    })
-                })
-                .collect();
-
-            for (path, hash) in full_hashes {
-                full_hash_map.entry(hash).or_default().push(path);
-            }
+        let full_hashes = hash_files(candidates, HashScope::Full, concurrency, operation).await?;
+        let mut full_hash_map: HashMap<Vec<u8>, Vec<String>> = HashMap::new();
+        for (path, hash) in full_hashes {
+            full_hash_map.entry(hash).or_default().push(path);
         }
 
+        operation.check_cancelled()?;
+
         // Collect groups of duplicates (only groups with more than one file)
         let duplicates: Vec<Vec<String>> = full_hash_map
             .into_values()
@@ -266,3 +417,235 @@ impl FileSystemService {
         Ok(duplicates)
     }
 }
+
+/// What [`FileSystemService::resolve_duplicates`] does with each group of duplicate files.
+#[derive(
+    ::serde::Deserialize, ::serde::Serialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateAction {
+    /// Only report the duplicates.
+    Report,
+    /// Keep the oldest file of each group and delete the others.
+    DeleteNewest,
+    /// Keep the newest file of each group and delete the others.
+    DeleteOldest,
+    /// Keep the oldest file of each group and replace the others with hard links to it.
+    Hardlink,
+}
+
+/// The planned or applied resolution of a group of duplicate files.
+#[derive(::serde::Serialize, Debug, Clone, PartialEq)]
+pub struct DuplicateResolution {
+    /// The copy that is retained.
+    pub kept: String,
+    /// Files deleted or replaced by a hard link to `kept`.
+    pub resolved: Vec<String>,
+    /// Bytes of storage reclaimed by resolving the group.
+    pub reclaimed_bytes: u64,
+    /// Files that could not be resolved, with the reason.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<(String, String)>,
+}
+
+impl FileSystemService {
+    /// Reclaims the space used by `groups` of duplicate files as returned by
+    /// [`Self::find_duplicate_files`], always retaining one copy per group.
+    /// With `dry_run` nothing is changed and the plan is returned. Files that cannot be resolved
+    /// are reported in [`DuplicateResolution::errors`] and do not stop the others.
+    pub async fn resolve_duplicates(
+        &self,
+        groups: Vec<Vec<String>>,
+        action: DuplicateAction,
+        dry_run: bool,
+    ) -> ServiceResult<Vec<DuplicateResolution>> {
+        if action == DuplicateAction::Report {
+            return Ok(vec![]);
+        }
+        let allowed_directories = self.allowed_directories().await;
+        let mut resolutions = Vec::with_capacity(groups.len());
+        let mut changes = vec![];
+
+        for group in groups {
+            let mut files = Vec::with_capacity(group.len());
+            let mut errors = vec![];
+            for path in group {
+                match tokio::fs::metadata(&path)
+                    .await
+                    .and_then(|metadata| Ok((metadata.modified()?, metadata.len())))
+                {
+                    Ok((modified, size)) => files.push((path, modified, size)),
+                    Err(err) => errors.push((path, err.to_string())),
+                }
+            }
+            // order from the copy to keep to the copies to resolve, ties broken by path
+            files.sort_by(|(a_path, a_time, _), (b_path, b_time, _)| match action {
+                DuplicateAction::DeleteOldest => b_time.cmp(a_time).then(a_path.cmp(b_path)),
+                _ => a_time.cmp(b_time).then(a_path.cmp(b_path)),
+            });
+
+            let mut files = files.into_iter();
+            let Some((kept, _, _)) = files.next() else {
+                continue;
+            };
+            let mut resolution = DuplicateResolution {
+                kept,
+                resolved: vec![],
+                reclaimed_bytes: 0,
+                errors,
+            };
+            for (path, _, size) in files {
+                let outcome =
+                    match self.validate_write_path(Path::new(&path), allowed_directories.clone()) {
+                        Ok(_) if dry_run => Ok(None),
+                        Ok(valid_path) => match action {
+                            DuplicateAction::Hardlink => self
+                                .replace_with_hard_link(Path::new(&resolution.kept), &valid_path)
+                                .await
+                                .map(Some),
+                            _ => self.remove_entry(&valid_path).await.map(Some),
+                        },
+                        Err(err) => Err(err),
+                    };
+                match outcome {
+                    Ok(change) => {
+                        changes.extend(change);
+                        resolution.resolved.push(path);
+                        resolution.reclaimed_bytes += size;
+                    }
+                    Err(err) => resolution.errors.push((path, err.to_string())),
+                }
+            }
+            resolutions.push(resolution);
+        }
+
+        if !dry_run {
+            self.journal_record("find_duplicate_files", changes);
+        }
+        Ok(resolutions)
+    }
+
+    /// Replaces `path` with a hard link to `target`. The link is created next to `path` first and
+    /// then renamed over it, so `path` is never missing.
+    async fn replace_with_hard_link(
+        &self,
+        target: &Path,
+        path: &Path,
+    ) -> ServiceResult<JournalChange> {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let temp_path = path.with_file_name(format!(".{file_name}.mcp-link"));
+        let backup = self.journal_backup(path).await?;
+        tokio::fs::hard_link(target, &temp_path).await?;
+        if let Err(err) = tokio::fs::rename(&temp_path, path).await {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return Err(err.into());
+        }
+        Ok(JournalChange::Write {
+            path: path.to_path_buf(),
+            backup,
+        })
+    }
+}
+
+/// Keeps a single path, the first in path order, of the files that are hard links of each other.
+/// Paths whose identity cannot be determined are kept.
+fn without_hardlinks(mut paths: Vec<String>) -> Vec<String> {
+    paths.sort();
+    let mut seen = HashSet::new();
+    paths
+        .retain(|path| file_identity(Path::new(path)).is_none_or(|identity| seen.insert(identity)));
+    paths
+}
+
+/// Part of a file hashed by [`hash_files`].
+#[derive(Clone, Copy)]
+enum HashScope {
+    /// The size and the first 4KB, enough to tell most files apart.
+    Head,
+    /// The whole content.
+    Full,
+}
+
+/// Hashes `paths` on blocking threads, at most `concurrency` at a time, reporting the number of
+/// hashed files through `operation`. Files that cannot be read are skipped.
+async fn hash_files(
+    paths: Vec<String>,
+    scope: HashScope,
+    concurrency: usize,
+    operation: &OperationContext,
+) -> ServiceResult<Vec<(String, Vec<u8>)>> {
+    let total = paths.len();
+    let mut pending = paths.into_iter();
+    let mut tasks = JoinSet::new();
+    let mut hashes = Vec::with_capacity(total);
+    let mut hashed = 0;
+    let mut last_report = Instant::now();
+
+    loop {
+        while tasks.len() < concurrency
+            && let Some(path) = pending.next()
+        {
+            operation.check_cancelled()?;
+            let operation = operation.clone();
+            tasks.spawn_blocking(move || {
+                let hash = hash_file(Path::new(&path), scope, &operation).ok()?;
+                Some((path, hash))
+            });
+        }
+
+        let Some(joined) = tasks.join_next().await else {
+            break;
+        };
+        if let Ok(Some(hash)) = joined {
+            hashes.push(hash);
+        }
+        hashed += 1;
+        if operation.reports_progress()
+            && (hashed == total || last_report.elapsed() >= HASH_PROGRESS_INTERVAL)
+        {
+            last_report = Instant::now();
+            let stage = match scope {
+                HashScope::Head => "Compared",
+                HashScope::Full => "Hashed",
+            };
+            operation.report_progress(ProgressUpdate {
+                progress: hashed as f64,
+                total: Some(total as f64),
+                message: Some(format!("{stage} {hashed} of {total} candidate files")),
+            });
+        }
+    }
+
+    operation.check_cancelled()?;
+    Ok(hashes)
+}
+
+fn hash_file(
+    path: &Path,
+    scope: HashScope,
+    operation: &OperationContext,
+) -> ServiceResult<Vec<u8>> {
+    let file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    match scope {
+        HashScope::Head => {
+            hasher.update(file.metadata()?.len().to_le_bytes());
+            let mut buffer = Vec::with_capacity(4096); // Read first 4KB
+            file.take(4096).read_to_end(&mut buffer)?;
+            hasher.update(&buffer);
+        }
+        HashScope::Full => {
+            let mut reader = std::io::BufReader::new(file);
+            let mut buffer = vec![0u8; 8192]; // 8KB chunks
+            loop {
+                operation.check_cancelled()?;
+                let bytes_read = reader.read(&mut buffer)?;
+                if bytes_read == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..bytes_read]);
+            }
+        }
+    }
+    Ok(hasher.finalize().to_vec())
+}
@@ -0,0 +1,148 @@
+use crate::fs_service::WalkOptions;
+use crate::{error::ServiceResult, fs_service::FileSystemService};
+use std::path::{Path, PathBuf};
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_CONSECUTIVE: i64 = 24;
+const BONUS_BOUNDARY: i64 = 20;
+const BONUS_FILE_NAME: i64 = 8;
+const BONUS_FIRST_CHAR: i64 = 12;
+const PENALTY_GAP: i64 = 2;
+const PENALTY_GAP_MAX: i64 = 12;
+
+/// Represents a file ranked by its fuzzy match score.
+#[derive(Debug, Clone)]
+pub struct FuzzyMatch {
+    /// The path of the matched file.
+    pub path: PathBuf,
+    /// The fuzzy match score, higher is better.
+    pub score: i64,
+}
+
+/// Computes an fzf-style fuzzy score of `query` against `candidate`.
+///
+/// Every query character must appear in the candidate in order (case-insensitive), otherwise `None`
+/// is returned. Matches are rewarded for being consecutive, for starting at word boundaries
+/// (after `/`, `_`, `-`, `.`, whitespace or at a camelCase hump) and for falling within the file name,
+/// and penalized for the gaps between them.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .filter_map(|c| c.to_lowercase().next())
+        .collect();
+    if query.is_empty() {
+        return None;
+    }
+
+    let chars: Vec<char> = candidate.chars().collect();
+    let file_name_start = chars
+        .iter()
+        .rposition(|c| *c == '/' || *c == '\\')
+        .map_or(0, |i| i + 1);
+
+    // Greedy matching from the leftmost occurrence can miss a better alignment (e.g. "cli" in
+    // "src/cli.rs"), so try every occurrence of the first query character and keep the best.
+    let best = chars
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.to_lowercase().next() == Some(query[0]))
+        .filter_map(|(start, _)| score_from(&query, &chars, start, file_name_start))
+        .max()?;
+
+    // Prefer shorter candidates when scores are otherwise equal
+    Some(best * 100 - chars.len() as i64)
+}
+
+fn score_from(query: &[char], chars: &[char], start: usize, file_name_start: usize) -> Option<i64> {
+    let mut score = 0;
+    let mut query_idx = 0;
+    let mut prev_match: Option<usize> = None;
+
+    for (idx, ch) in chars.iter().enumerate().skip(start) {
+        if query_idx == query.len() {
+            break;
+        }
+        if ch.to_lowercase().next() != Some(query[query_idx]) {
+            continue;
+        }
+
+        score += SCORE_MATCH;
+
+        let prev_char = idx.checked_sub(1).map(|i| chars[i]);
+        let at_boundary = match prev_char {
+            None => true,
+            Some(prev) => {
+                matches!(prev, '/' | '\\' | '_' | '-' | '.' | ' ')
+                    || (prev.is_lowercase() && ch.is_uppercase())
+            }
+        };
+        if at_boundary {
+            score += BONUS_BOUNDARY;
+        }
+        if idx >= file_name_start {
+            score += BONUS_FILE_NAME;
+        }
+
+        match prev_match {
+            Some(prev) if prev + 1 == idx => score += BONUS_CONSECUTIVE,
+            Some(prev) => score -= ((idx - prev - 1) as i64 * PENALTY_GAP).min(PENALTY_GAP_MAX),
+            None if idx == file_name_start => score += BONUS_FIRST_CHAR,
+            None => {}
+        }
+
+        prev_match = Some(idx);
+        query_idx += 1;
+    }
+
+    (query_idx == query.len()).then_some(score)
+}
+
+impl FileSystemService {
+    /// Ranks files under `root_path` by their fuzzy match score against `query`.
+    ///
+    /// The query is matched against the path relative to `root_path`, so both file names
+    /// and directory names contribute to the score. Results are sorted by descending score
+    /// and limited to `max_results` entries.
+    pub async fn fuzzy_find_files(
+        &self,
+        root_path: &Path,
+        query: &str,
+        exclude_patterns: Option<Vec<String>>,
+        max_results: usize,
+    ) -> ServiceResult<Vec<FuzzyMatch>> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(root_path, allowed_directories)?;
+
+        let mut matches: Vec<FuzzyMatch> = self
+            .search_files_iter(
+                &valid_path,
+                "**/*".to_string(),
+                exclude_patterns.unwrap_or_default(),
+                None,
+                None,
+                None,
+                WalkOptions::default(),
+            )
+            .await?
+            .filter(|entry| entry.file_type().is_some_and(|ft| ft.is_file()))
+            .filter_map(|entry| {
+                let relative_path = entry
+                    .path()
+                    .strip_prefix(&valid_path)
+                    .unwrap_or(entry.path())
+                    .to_string_lossy()
+                    .into_owned();
+                fuzzy_score(query, &relative_path).map(|score| FuzzyMatch {
+                    path: entry.path().to_path_buf(),
+                    score,
+                })
+            })
+            .collect();
+
+        matches.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+        matches.truncate(max_results);
+
+        Ok(matches)
+    }
+}
@@ -20,11 +20,11 @@ pub(crate) fn compile_single_glob(
     }
 
     let glob = glob_builder.build().map_err(|err| {
-        ServiceError::FromString(format!("Invalid glob pattern '{normalized}': {err}"))
+        ServiceError::InvalidPattern(format!("Invalid glob pattern '{normalized}': {err}"))
     })?;
 
     builder.add(glob).build().map_err(|err| {
-        ServiceError::FromString(format!(
+        ServiceError::InvalidPattern(format!(
             "Failed to build glob matcher for pattern '{normalized}': {err}"
         ))
     })
@@ -62,7 +62,7 @@ pub(crate) fn compile_exclude_glob(
         }
 
         let glob = glob_builder.build().map_err(|err| {
-            ServiceError::FromString(format!("Invalid exclude glob pattern '{pattern}': {err}"))
+            ServiceError::InvalidPattern(format!("Invalid exclude glob pattern '{pattern}': {err}"))
         })?;
 
         builder.add(glob);
@@ -74,6 +74,6 @@ pub(crate) fn compile_exclude_glob(
     }
 
     builder.build().map(Some).map_err(|err| {
-        ServiceError::FromString(format!("Failed to build exclude glob patterns: {err}"))
+        ServiceError::InvalidPattern(format!("Failed to build exclude glob patterns: {err}"))
     })
 }
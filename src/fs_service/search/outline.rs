@@ -0,0 +1,282 @@
+use crate::{
+    error::{ServiceError, ServiceResult},
+    fs_service::FileSystemService,
+};
+use ast_grep_core::{Node, tree_sitter::StrDoc};
+use ast_grep_language::{Language, LanguageExt, SupportLang};
+use std::path::Path;
+
+pub(crate) type AstNode<'r> = Node<'r, StrDoc<SupportLang>>;
+
+/// A symbol (function, class, struct, ...) declared in a source file.
+#[derive(::serde::Serialize, Debug, Clone)]
+pub struct CodeSymbol {
+    pub kind: &'static str,
+    pub name: String,
+    /// First line of the declaration (1-based).
+    pub start_line: usize,
+    /// Last line of the declaration (1-based).
+    pub end_line: usize,
+    /// Nesting depth among symbols, 0 for top-level symbols.
+    pub depth: usize,
+}
+
+/// Maps tree-sitter node kinds to symbol kinds for a language.
+/// `function` is reported as `method` when nested inside a class-like symbol.
+fn symbol_kinds(lang: SupportLang) -> Option<&'static [(&'static str, &'static str)]> {
+    let kinds: &'static [(&'static str, &'static str)] = match lang {
+        SupportLang::Rust => &[
+            ("function_item", "function"),
+            ("function_signature_item", "function"),
+            ("struct_item", "struct"),
+            ("enum_item", "enum"),
+            ("union_item", "union"),
+            ("trait_item", "trait"),
+            ("impl_item", "impl"),
+            ("mod_item", "module"),
+            ("macro_definition", "macro"),
+            ("type_item", "type"),
+            ("const_item", "constant"),
+            ("static_item", "constant"),
+        ],
+        SupportLang::Python => &[
+            ("function_definition", "function"),
+            ("class_definition", "class"),
+        ],
+        SupportLang::JavaScript | SupportLang::TypeScript | SupportLang::Tsx => &[
+            ("function_declaration", "function"),
+            ("generator_function_declaration", "function"),
+            ("class_declaration", "class"),
+            ("abstract_class_declaration", "class"),
+            ("method_definition", "method"),
+            ("interface_declaration", "interface"),
+            ("type_alias_declaration", "type"),
+            ("enum_declaration", "enum"),
+            ("internal_module", "module"),
+        ],
+        SupportLang::Go => &[
+            ("function_declaration", "function"),
+            ("method_declaration", "method"),
+            ("type_spec", "type"),
+        ],
+        SupportLang::Java => &[
+            ("class_declaration", "class"),
+            ("interface_declaration", "interface"),
+            ("enum_declaration", "enum"),
+            ("record_declaration", "record"),
+            ("method_declaration", "method"),
+            ("constructor_declaration", "constructor"),
+        ],
+        SupportLang::CSharp => &[
+            ("class_declaration", "class"),
+            ("struct_declaration", "struct"),
+            ("interface_declaration", "interface"),
+            ("enum_declaration", "enum"),
+            ("record_declaration", "record"),
+            ("namespace_declaration", "namespace"),
+            ("method_declaration", "method"),
+            ("constructor_declaration", "constructor"),
+        ],
+        SupportLang::C | SupportLang::Cpp => &[
+            ("function_definition", "function"),
+            ("struct_specifier", "struct"),
+            ("union_specifier", "union"),
+            ("enum_specifier", "enum"),
+            ("class_specifier", "class"),
+            ("namespace_definition", "namespace"),
+        ],
+        SupportLang::Kotlin => &[
+            ("class_declaration", "class"),
+            ("object_declaration", "object"),
+            ("function_declaration", "function"),
+        ],
+        SupportLang::Swift => &[
+            ("class_declaration", "class"),
+            ("protocol_declaration", "protocol"),
+            ("function_declaration", "function"),
+        ],
+        SupportLang::Scala => &[
+            ("class_definition", "class"),
+            ("object_definition", "object"),
+            ("trait_definition", "trait"),
+            ("function_definition", "function"),
+        ],
+        SupportLang::Ruby => &[
+            ("class", "class"),
+            ("module", "module"),
+            ("method", "method"),
+            ("singleton_method", "method"),
+        ],
+        SupportLang::Php => &[
+            ("function_definition", "function"),
+            ("class_declaration", "class"),
+            ("interface_declaration", "interface"),
+            ("trait_declaration", "trait"),
+            ("enum_declaration", "enum"),
+            ("method_declaration", "method"),
+        ],
+        SupportLang::Lua => &[("function_declaration", "function")],
+        _ => return None,
+    };
+    Some(kinds)
+}
+
+const CONTAINER_KINDS: &[&str] = &[
+    "class",
+    "struct",
+    "trait",
+    "impl",
+    "interface",
+    "object",
+    "protocol",
+    "record",
+    "enum",
+];
+
+/// Finds the name of a declaration node.
+fn symbol_name(node: &AstNode, kind: &str) -> Option<String> {
+    // `impl Trait for Type` / `impl Type`
+    if kind == "impl" {
+        let type_name = node.field("type")?.text().to_string();
+        return Some(match node.field("trait") {
+            Some(trait_node) => format!("{} for {type_name}", trait_node.text()),
+            None => type_name,
+        });
+    }
+    if let Some(name) = node.field("name") {
+        return Some(name.text().to_string());
+    }
+    // C/C++ functions: the name is nested in a chain of declarators
+    let mut current = node.field("declarator");
+    while let Some(declarator) = current {
+        match declarator.field("declarator") {
+            Some(inner) => current = Some(inner),
+            None => {
+                let text = declarator.text();
+                let name = text.split('(').next().unwrap_or(&text).trim();
+                return Some(name.to_string());
+            }
+        }
+    }
+    // Grammars without a `name` field (e.g. Kotlin, Swift)
+    node.children()
+        .find(|child| {
+            let child_kind = child.kind();
+            child.is_named() && (child_kind.ends_with("identifier") || child_kind.ends_with("name"))
+        })
+        .map(|child| child.text().to_string())
+}
+
+fn collect_symbols(
+    node: &AstNode,
+    kinds: &[(&str, &'static str)],
+    depth: usize,
+    parent_kind: Option<&str>,
+    symbols: &mut Vec<CodeSymbol>,
+) {
+    for child in node.children() {
+        let child_kind = child.kind();
+        let symbol_kind = kinds
+            .iter()
+            .find(|(node_kind, _)| *node_kind == child_kind)
+            .map(|(_, kind)| *kind);
+
+        // C/C++ type specifiers without a body are references such as `struct foo *x`,
+        // not declarations
+        let is_type_reference = child_kind.ends_with("_specifier") && child.field("body").is_none();
+
+        let symbol = symbol_kind.filter(|_| !is_type_reference).and_then(|kind| {
+            let name = symbol_name(&child, kind)?;
+            let kind = match (kind, parent_kind) {
+                ("function", Some(parent)) if CONTAINER_KINDS.contains(&parent) => "method",
+                _ => kind,
+            };
+            Some((kind, name))
+        });
+
+        match symbol {
+            Some((kind, name)) => {
+                symbols.push(CodeSymbol {
+                    kind,
+                    name,
+                    start_line: child.start_pos().line() + 1,
+                    end_line: child.end_pos().line() + 1,
+                    depth,
+                });
+                collect_symbols(&child, kinds, depth + 1, Some(kind), symbols);
+            }
+            None => collect_symbols(&child, kinds, depth, parent_kind, symbols),
+        }
+    }
+}
+
+/// Describes the symbols enclosing `node`, from the outermost to the innermost,
+/// e.g. `class View > method render`. Returns `None` for top-level code.
+pub(crate) fn enclosing_symbols(node: &AstNode, lang: SupportLang) -> Option<String> {
+    let kinds = symbol_kinds(lang)?;
+    let mut chain: Vec<(&'static str, String)> = node
+        .ancestors()
+        .filter_map(|ancestor| {
+            let ancestor_kind = ancestor.kind();
+            let (_, kind) = kinds.iter().find(|(k, _)| *k == ancestor_kind)?;
+            Some((*kind, symbol_name(&ancestor, kind)?))
+        })
+        .collect();
+    if chain.is_empty() {
+        return None;
+    }
+    chain.reverse();
+
+    let mut parent_kind: Option<&str> = None;
+    let parts: Vec<String> = chain
+        .into_iter()
+        .map(|(kind, name)| {
+            let kind = match (kind, parent_kind) {
+                ("function", Some(parent)) if CONTAINER_KINDS.contains(&parent) => "method",
+                _ => kind,
+            };
+            parent_kind = Some(kind);
+            format!("{kind} {name}")
+        })
+        .collect();
+    Some(parts.join(" > "))
+}
+
+/// Returns the symbols declared in `content`, in source order.
+pub fn code_outline(content: &str, lang: SupportLang) -> ServiceResult<Vec<CodeSymbol>> {
+    let kinds = symbol_kinds(lang).ok_or_else(|| {
+        ServiceError::InvalidArgument(format!("Code outline is not supported for {lang}."))
+    })?;
+    let root = lang.ast_grep(content);
+    let mut symbols = Vec::new();
+    collect_symbols(&root.root(), kinds, 0, None, &mut symbols);
+    Ok(symbols)
+}
+
+impl FileSystemService {
+    /// Lists the functions, classes, structs, methods and other symbols declared in a source file.
+    /// The language is detected from the file extension unless `language` is provided.
+    pub async fn get_code_outline(
+        &self,
+        file_path: &Path,
+        language: Option<&str>,
+    ) -> ServiceResult<Vec<CodeSymbol>> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(file_path, allowed_directories)?;
+
+        let lang = match language {
+            Some(language) => self.parse_language(language)?,
+            None => SupportLang::from_path(&valid_path).ok_or_else(|| {
+                ServiceError::InvalidArgument(format!(
+                    "Unable to detect the language of '{}'. Please specify the language.",
+                    file_path.display()
+                ))
+            })?,
+        };
+
+        let content = tokio::fs::read_to_string(&valid_path).await?;
+        tokio::task::spawn_blocking(move || code_outline(&content, lang))
+            .await
+            .map_err(|e| std::io::Error::other(e.to_string()))?
+    }
+}
@@ -0,0 +1,165 @@
+use crate::{
+    error::ServiceResult,
+    fs_service::{FileSystemService, WalkOptions},
+};
+use rayon::iter::{ParallelBridge, ParallelIterator};
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    fs::File,
+    io::Read,
+    path::Path,
+};
+
+/// Number of leading bytes checked for a NUL byte to tell binary files from text files.
+const BINARY_SNIFF_BYTES: usize = 8192;
+
+/// Statistics of the files of one extension, as reported by
+/// [`FileSystemService::directory_stats`].
+#[derive(::serde::Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ExtensionStatistics {
+    /// Lowercase extension, `(no extension)` for files without one.
+    pub extension: String,
+    pub files: u64,
+    pub bytes: u64,
+    /// Lines of the text files, when lines are counted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<u64>,
+}
+
+impl ExtensionStatistics {
+    fn add(&mut self, other: &ExtensionStatistics) {
+        self.files += other.files;
+        self.bytes += other.bytes;
+        if let (Some(lines), Some(other_lines)) = (self.lines.as_mut(), other.lines) {
+            *lines += other_lines;
+        }
+    }
+}
+
+/// Statistics of a directory tree, see [`FileSystemService::directory_stats`].
+#[derive(::serde::Serialize, Debug, Clone, Default, PartialEq)]
+pub struct DirectoryStatistics {
+    pub files: u64,
+    pub directories: u64,
+    pub bytes: u64,
+    /// Lines of the text files, when lines are counted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<u64>,
+    /// Per-extension statistics, largest first.
+    pub by_extension: Vec<ExtensionStatistics>,
+}
+
+/// Counts the lines of the file at `path`, or returns `None` if it looks binary.
+fn count_file_lines(path: &Path) -> Option<u64> {
+    let mut file = File::open(path).ok()?;
+    let mut buffer = [0; BINARY_SNIFF_BYTES];
+    let mut lines = 0;
+    let mut first_chunk = true;
+    let mut last_byte = b'\n';
+    loop {
+        let read = file.read(&mut buffer).ok()?;
+        if read == 0 {
+            break;
+        }
+        let chunk = &buffer[..read];
+        if first_chunk && chunk.contains(&0) {
+            return None;
+        }
+        first_chunk = false;
+        lines += chunk.iter().filter(|byte| **byte == b'\n').count() as u64;
+        last_byte = chunk[read - 1];
+    }
+    // a last line without a line ending counts too
+    if last_byte != b'\n' {
+        lines += 1;
+    }
+    Some(lines)
+}
+
+impl FileSystemService {
+    /// Counts the files and directories under `root_path`, and the files and their sizes per
+    /// extension. With `count_lines`, the lines of text files are counted as well. Files are
+    /// processed in parallel.
+    pub async fn directory_stats(
+        &self,
+        root_path: &Path,
+        exclude_patterns: Vec<String>,
+        count_lines: bool,
+        walk_options: WalkOptions,
+    ) -> ServiceResult<DirectoryStatistics> {
+        let entries = self
+            .search_files_iter(
+                root_path,
+                "**/*".to_string(),
+                exclude_patterns,
+                None,
+                None,
+                None,
+                walk_options,
+            )
+            .await?;
+
+        let (directories, by_extension) = entries
+            .par_bridge()
+            .fold(
+                || (0u64, HashMap::<String, ExtensionStatistics>::new()),
+                |(mut directories, mut by_extension), entry| {
+                    let Some(file_type) = entry.file_type() else {
+                        return (directories, by_extension);
+                    };
+                    if file_type.is_dir() {
+                        directories += 1;
+                    } else if file_type.is_file()
+                        && let Ok(metadata) = entry.metadata()
+                    {
+                        let extension = entry
+                            .path()
+                            .extension()
+                            .map(|extension| extension.to_string_lossy().to_lowercase())
+                            .unwrap_or_else(|| "(no extension)".to_string());
+                        let stats = by_extension.entry(extension.clone()).or_insert_with(|| {
+                            ExtensionStatistics {
+                                extension,
+                                lines: count_lines.then_some(0),
+                                ..Default::default()
+                            }
+                        });
+                        stats.files += 1;
+                        stats.bytes += metadata.len();
+                        if let Some(lines) = stats.lines.as_mut() {
+                            *lines += count_file_lines(entry.path()).unwrap_or_default();
+                        }
+                    }
+                    (directories, by_extension)
+                },
+            )
+            .reduce(
+                || (0, HashMap::new()),
+                |(directories, mut by_extension), (other_directories, other)| {
+                    for (extension, other) in other {
+                        match by_extension.entry(extension) {
+                            Entry::Occupied(mut stats) => stats.get_mut().add(&other),
+                            Entry::Vacant(slot) => {
+                                slot.insert(other);
+                            }
+                        }
+                    }
+                    (directories + other_directories, by_extension)
+                },
+            );
+
+        let mut by_extension: Vec<ExtensionStatistics> = by_extension.into_values().collect();
+        by_extension.sort_by(|a, b| {
+            b.bytes
+                .cmp(&a.bytes)
+                .then_with(|| a.extension.cmp(&b.extension))
+        });
+        Ok(DirectoryStatistics {
+            files: by_extension.iter().map(|stats| stats.files).sum(),
+            directories,
+            bytes: by_extension.iter().map(|stats| stats.bytes).sum(),
+            lines: count_lines.then(|| by_extension.iter().filter_map(|stats| stats.lines).sum()),
+            by_extension,
+        })
+    }
+}
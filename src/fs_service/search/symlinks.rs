@@ -0,0 +1,75 @@
+use crate::{
+    error::ServiceResult,
+    fs_service::{FileSystemService, WalkOptions, core::symlink_target_within},
+};
+use std::path::Path;
+
+/// Why a symlink reported by [`FileSystemService::find_broken_symlinks`] is broken.
+#[derive(::serde::Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BrokenSymlinkReason {
+    /// The target does not exist, or resolving it loops.
+    MissingTarget,
+    /// The target exists but is outside the allowed directories.
+    OutsideAllowedDirectories,
+}
+
+/// A symlink whose target cannot be used.
+#[derive(::serde::Serialize, Debug, Clone, PartialEq)]
+pub struct BrokenSymlink {
+    pub path: String,
+    /// The target as stored in the link, possibly relative to the link's directory.
+    pub target: String,
+    pub reason: BrokenSymlinkReason,
+}
+
+impl FileSystemService {
+    /// Finds the symlinks under `root_path` whose targets no longer exist or point outside the
+    /// allowed directories. Symlinks are not followed while walking.
+    pub async fn find_broken_symlinks(
+        &self,
+        root_path: &Path,
+        exclude_patterns: Option<Vec<String>>,
+        walk_options: WalkOptions,
+    ) -> ServiceResult<Vec<BrokenSymlink>> {
+        let allowed_directories = self.allowed_directories().await;
+        let walk_options = WalkOptions {
+            follow_symlinks: false,
+            ..walk_options
+        };
+        let links = self
+            .search_files_iter(
+                root_path,
+                "**/*".to_string(),
+                exclude_patterns.unwrap_or_default(),
+                None,
+                None,
+                None,
+                walk_options,
+            )
+            .await?
+            .filter(|entry| entry.path_is_symlink());
+
+        let mut broken = vec![];
+        for entry in links {
+            let path = entry.path();
+            let reason = if std::fs::metadata(path).is_err() {
+                BrokenSymlinkReason::MissingTarget
+            } else if symlink_target_within(path, &allowed_directories).is_none() {
+                BrokenSymlinkReason::OutsideAllowedDirectories
+            } else {
+                continue;
+            };
+            let target = std::fs::read_link(path)
+                .map(|target| target.display().to_string())
+                .unwrap_or_default();
+            broken.push(BrokenSymlink {
+                path: path.display().to_string(),
+                target,
+                reason,
+            });
+        }
+        broken.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(broken)
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{
+    error::{ServiceError, ServiceResult},
+    fs_service::{FileSystemService, WalkOptions, utils::escape_regex},
+};
+use grep::{
+    matcher::Matcher,
+    regex::RegexMatcherBuilder,
+    searcher::{BinaryDetection, Searcher, sinks::UTF8},
+};
+use rayon::iter::{ParallelBridge, ParallelIterator};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+/// Markers collected by [`FileSystemService::collect_todos`] unless other tags are given.
+pub const DEFAULT_TODO_TAGS: [&str; 3] = ["TODO", "FIXME", "HACK"];
+
+/// A marker comment found by [`FileSystemService::collect_todos`].
+#[derive(::serde::Serialize, Debug, Clone, PartialEq)]
+pub struct TodoItem {
+    pub path: PathBuf,
+    /// 1-based line number.
+    pub line: u64,
+    /// What follows the tag on its line, e.g. `handle errors` for `// TODO: handle errors`.
+    pub text: String,
+}
+
+/// The markers of one tag, see [`FileSystemService::collect_todos`].
+#[derive(::serde::Serialize, Debug, Clone, PartialEq)]
+pub struct TodoGroup {
+    pub tag: String,
+    /// Items ordered by path and line.
+    pub items: Vec<TodoItem>,
+}
+
+/// Returns the text following a marker, without the separators usually written after it.
+fn marker_text(rest: &str) -> String {
+    rest.trim_start_matches(|c: char| c == ':' || c == '-' || c.is_whitespace())
+        .trim_end()
+        .trim_end_matches("*/")
+        .trim_end()
+        .to_string()
+}
+
+impl FileSystemService {
+    /// Scans the text files under `root_path` for the marker `tags` (case-sensitive, whole words)
+    /// and returns the markers found, grouped by tag in the order of `tags`. Tags without any
+    /// marker are omitted. Files are scanned in parallel.
+    pub async fn collect_todos(
+        &self,
+        root_path: &Path,
+        tags: &[String],
+        exclude_patterns: Vec<String>,
+        walk_options: WalkOptions,
+    ) -> ServiceResult<Vec<TodoGroup>> {
+        if tags.is_empty() || tags.iter().any(|tag| tag.trim().is_empty()) {
+            return Err(ServiceError::InvalidArgument(
+                "Tags must be non-empty strings.".to_string(),
+            ));
+        }
+        let pattern = format!(
+            r"\b(?:{})\b",
+            tags.iter()
+                .map(|tag| escape_regex(tag.trim()))
+                .collect::<Vec<_>>()
+                .join("|")
+        );
+        let matcher = RegexMatcherBuilder::new().build(&pattern)?;
+
+        let entries = self
+            .search_files_iter(
+                root_path,
+                "**/*".to_string(),
+                exclude_patterns,
+                None,
+                None,
+                None,
+                walk_options,
+            )
+            .await?
+            .filter(|entry| entry.file_type().is_some_and(|ft| ft.is_file()));
+
+        let mut found: Vec<(String, TodoItem)> = entries
+            .par_bridge()
+            .flat_map_iter(|entry| {
+                let mut items = vec![];
+                let mut searcher = Searcher::new();
+                searcher.set_binary_detection(BinaryDetection::quit(b'\x00'));
+                // unreadable files are skipped
+                let _ = searcher.search_path(
+                    &matcher,
+                    entry.path(),
+                    UTF8(|line_number, line| {
+                        if let Some(found) = matcher.find(line.as_bytes())? {
+                            items.push((
+                                line[found.start()..found.end()].to_string(),
+                                TodoItem {
+                                    path: entry.path().to_path_buf(),
+                                    line: line_number,
+                                    text: marker_text(&line[found.end()..]),
+                                },
+                            ));
+                        }
+                        Ok(true)
+                    }),
+                );
+                items
+            })
+            .collect();
+        found.sort_by(|a, b| (&a.1.path, a.1.line).cmp(&(&b.1.path, b.1.line)));
+
+        let mut seen = HashSet::new();
+        Ok(tags
+            .iter()
+            .map(|tag| tag.trim())
+            .filter(|tag| seen.insert(*tag))
+            .filter_map(|tag| {
+                let items: Vec<TodoItem> = found
+                    .iter()
+                    .filter(|(found_tag, _)| found_tag == tag)
+                    .map(|(_, item)| item.clone())
+                    .collect();
+                (!items.is_empty()).then(|| TodoGroup {
+                    tag: tag.to_string(),
+                    items,
+                })
+            })
+            .collect())
+    }
+}
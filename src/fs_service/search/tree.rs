@@ -1,15 +1,162 @@
+use crate::fs_service::WalkOptions;
+use crate::fs_service::core::{DirectoryIdentity, first_visit_of_linked_dir};
+use crate::fs_service::search::glob_utils::{compile_exclude_glob, compile_single_glob};
 use crate::{
     error::{ServiceError, ServiceResult},
-    fs_service::{FileSystemService, utils::is_system_metadata_file},
+    fs_service::{FileSystemService, journal::JournalChange, utils::is_system_metadata_file},
 };
+use chrono::{DateTime, Utc};
+use globset::GlobSet;
+use ignore::WalkBuilder;
 use rayon::iter::{ParallelBridge, ParallelIterator};
+use rust_mcp_sdk::macros::JsonSchema;
 use serde_json::{Value, json};
 use std::{
+    collections::{HashMap, HashSet, VecDeque},
     fs::{self},
     path::{Path, PathBuf},
     sync::Arc,
 };
-use ignore::WalkBuilder;
+
+/// The outcome of [`FileSystemService::delete_empty_directories`].
+#[derive(::serde::Serialize, Debug, Clone, Default, PartialEq)]
+pub struct EmptyDirectoryRemoval {
+    /// Directories that were removed, deepest first.
+    pub removed: Vec<String>,
+    /// Directories that could not be removed, with the reason.
+    pub skipped: Vec<(String, String)>,
+}
+
+/// Order of the entries returned by [`FileSystemService::list_directory_page`].
+#[derive(
+    ::serde::Deserialize, ::serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema,
+)]
+pub enum DirectorySortBy {
+    /// By name, ascending.
+    #[default]
+    #[serde(rename = "name")]
+    Name,
+    /// Largest first, directories count as empty.
+    #[serde(rename = "size")]
+    Size,
+    /// Most recently modified first.
+    #[serde(rename = "mtime")]
+    Mtime,
+}
+
+/// A page of the entries of a directory.
+#[derive(Debug)]
+pub struct DirectoryListing {
+    pub entries: Vec<tokio::fs::DirEntry>,
+    /// Number of entries matching the filter, in all pages.
+    pub entries_total: usize,
+    /// Number of entries to skip to get the next page, if there are more entries.
+    pub next_offset: Option<usize>,
+}
+
+/// How [`FileSystemService::directory_size_breakdown`] groups the files.
+#[derive(
+    ::serde::Deserialize, ::serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema,
+)]
+pub enum SizeGroupBy {
+    /// A single group with all the files.
+    #[default]
+    #[serde(rename = "none")]
+    None,
+    /// By lowercase file extension.
+    #[serde(rename = "extension")]
+    Extension,
+    /// By the entry of the root directory the files are in.
+    #[serde(rename = "top_level_dir")]
+    TopLevelDir,
+}
+
+/// The files of a group of [`FileSystemService::directory_size_breakdown`].
+#[derive(::serde::Serialize, Debug, Clone, PartialEq)]
+pub struct SizeGroup {
+    /// The extension (`(no extension)` if none), the top-level entry (`.` for the files of
+    /// the root itself), or `*` when files are not grouped.
+    pub group: String,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// An entry listed by [`FileSystemService::list_directory_with_sizes`].
+#[derive(::serde::Serialize, Debug, Clone, PartialEq)]
+pub struct SizedEntry {
+    pub name: String,
+    pub is_directory: bool,
+    /// Size of a file, or total size of the files in a directory when sizes are rolled up.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Entries of a directory, when more than one level is listed.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<SizedEntry>,
+}
+
+/// A page of a breadth-first directory tree listing.
+pub struct DirectoryTreePage {
+    /// Entries as JSON objects whose `n` is the path relative to the root, with `/` appended to
+    /// directories and `@` to symlinks.
+    pub entries: Vec<Value>,
+    /// Number of entries to skip to get the next page, if there are more entries.
+    pub next_offset: Option<usize>,
+    /// True if directories beyond the maximum depth were skipped.
+    pub reached_max_depth: bool,
+}
+
+/// Entries left out of [`FileSystemService::directory_tree`] and
+/// [`FileSystemService::directory_tree_page`], matched against their path relative to the root.
+#[derive(Debug, Default)]
+pub struct TreeFilter {
+    exclude: Option<GlobSet>,
+    include: Option<GlobSet>,
+    /// Match the include glob against relative paths instead of file names.
+    include_matches_paths: bool,
+}
+
+impl TreeFilter {
+    /// Leaves out the entries matching one of `exclude_patterns`, with the contents of excluded
+    /// directories, and the files not matching `include_pattern`. The include glob is matched
+    /// against file names, or against relative paths if it contains a separator like `src/**/*.rs`.
+    /// Directories are listed regardless of it.
+    pub fn new(
+        exclude_patterns: Option<&[String]>,
+        include_pattern: Option<&str>,
+    ) -> ServiceResult<Self> {
+        Ok(Self {
+            exclude: compile_exclude_glob(exclude_patterns, false)?,
+            include: include_pattern
+                .map(|pattern| compile_single_glob(pattern, "*", false))
+                .transpose()?,
+            include_matches_paths: include_pattern.is_some_and(|pattern| pattern.contains('/')),
+        })
+    }
+
+    /// Whether the entry at `relative_path` is listed; `is_dir` is true for the directories
+    /// the tree descends into.
+    fn lists(&self, relative_path: &Path, is_dir: bool) -> bool {
+        if self
+            .exclude
+            .as_ref()
+            .is_some_and(|exclude| exclude.is_match(relative_path))
+        {
+            return false;
+        }
+        match &self.include {
+            Some(include) if !is_dir => {
+                if self.include_matches_paths {
+                    include.is_match(relative_path)
+                } else {
+                    relative_path
+                        .file_name()
+                        .is_some_and(|name| include.is_match(name))
+                }
+            }
+            _ => true,
+        }
+    }
+}
 
 impl FileSystemService {
     /// Generates a JSON representation of a directory tree starting at the given path.
@@ -23,38 +170,76 @@ impl FileSystemService {
     /// - `max_depth`: Limits the depth of directory traversal.
     /// - `max_files`: Limits the total number of entries (files and directories).
     ///
+    /// With `include_metadata`, entries also get `s` (size in bytes, files only) and `m`
+    /// (modification time, UTC). With `walk_options.follow_symlinks`, symlinked directories
+    /// inside the allowed directories are expanded too, each of them once. Entries rejected by
+    /// `filter` are left out and not counted.
+    ///
     /// # IMPORTANT NOTE
     ///
     /// use max_depth or max_files could lead to partial or skewed representations of actual directory tree
+    #[allow(clippy::too_many_arguments)]
     pub fn directory_tree<P: AsRef<Path>>(
         &self,
         root_path: P,
         max_depth: Option<usize>,
         max_files: Option<usize>,
+        include_metadata: bool,
         current_count: &mut usize,
         allowed_directories: Arc<Vec<PathBuf>>,
+        walk_options: WalkOptions,
+        filter: &TreeFilter,
     ) -> ServiceResult<(Value, bool)> {
         let valid_path = self.validate_path(root_path.as_ref(), allowed_directories.clone())?;
 
         let metadata = fs::metadata(&valid_path)?;
         if !metadata.is_dir() {
-            return Err(ServiceError::FromString(
+            return Err(ServiceError::InvalidArgument(
                 "Root path must be a directory".into(),
             ));
         }
 
+        let mut followed_links = HashSet::new();
+        self.tree_children(
+            &valid_path,
+            &valid_path,
+            max_depth,
+            max_files,
+            include_metadata,
+            current_count,
+            &allowed_directories,
+            walk_options,
+            filter,
+            &mut followed_links,
+        )
+    }
+
+    /// Lists the children of `dir` for [`Self::directory_tree`], recursing into subdirectories.
+    /// `followed_links` holds the directories already expanded through symlinks.
+    #[allow(clippy::too_many_arguments)]
+    fn tree_children(
+        &self,
+        root: &Path,
+        dir: &Path,
+        max_depth: Option<usize>,
+        max_files: Option<usize>,
+        include_metadata: bool,
+        current_count: &mut usize,
+        allowed_directories: &Arc<Vec<PathBuf>>,
+        walk_options: WalkOptions,
+        filter: &TreeFilter,
+        followed_links: &mut HashSet<DirectoryIdentity>,
+    ) -> ServiceResult<(Value, bool)> {
         let mut children = Vec::new();
         let mut reached_max_depth = false;
 
         if max_depth != Some(0) {
-            for entry in WalkBuilder::new(&valid_path)
-                .follow_links(false)
-                .git_ignore(true)
-                .git_global(true)
-                .git_exclude(true)
-                .ignore(true)
-                .hidden(true)
-                .parents(true)
+            for entry in self
+                .configure_walker(
+                    &mut WalkBuilder::new(dir),
+                    walk_options,
+                    allowed_directories.clone(),
+                )
                 .max_depth(Some(1))
                 .build()
                 .filter_map(|e| e.ok())
@@ -62,7 +247,7 @@ impl FileSystemService {
                 let child_path = entry.path();
 
                 // Skip the root directory itself
-                if child_path == valid_path.as_path() {
+                if child_path == dir {
                     continue;
                 }
 
@@ -70,6 +255,13 @@ impl FileSystemService {
                 let metadata = fs::symlink_metadata(child_path)?;
                 let file_type = metadata.file_type();
 
+                let relative_path = child_path.strip_prefix(root).unwrap_or(child_path);
+                let is_tree_dir =
+                    file_type.is_dir() || (file_type.is_symlink() && child_path.is_dir());
+                if !filter.lists(relative_path, is_tree_dir) {
+                    continue;
+                }
+
                 let mut entry_name = child_path
                     .file_name()
                     .ok_or(ServiceError::FromString("Invalid path".to_string()))?
@@ -99,16 +291,27 @@ impl FileSystemService {
                 let mut json_entry = json!({
                     "n": entry_name
                 });
+                if include_metadata {
+                    add_entry_metadata(&mut json_entry, &metadata);
+                }
 
-                // Only recurse into real directories, not symlinks
-                if is_dir && !is_symlink {
+                // Only recurse into real directories, and into symlinked ones when following links
+                let follow = is_symlink
+                    && walk_options.follow_symlinks
+                    && first_visit_of_linked_dir(child_path, followed_links);
+                if (is_dir && !is_symlink) || follow {
                     let next_depth = max_depth.map(|d| d - 1);
-                    let (child_children, child_reached_max_depth) = self.directory_tree(
+                    let (child_children, child_reached_max_depth) = self.tree_children(
+                        root,
                         child_path,
                         next_depth,
                         max_files,
+                        include_metadata,
                         current_count,
-                        allowed_directories.clone(),
+                        allowed_directories,
+                        walk_options,
+                        filter,
+                        followed_links,
                     )?;
                     json_entry
                         .as_object_mut()
@@ -125,6 +328,105 @@ impl FileSystemService {
         Ok((Value::Array(children), reached_max_depth))
     }
 
+    /// Lists a directory tree breadth-first, one page at a time.
+    ///
+    /// Entries are ordered by depth and, within a directory, by name, so that a listing can be
+    /// resumed by skipping the `offset` entries returned by previous pages. At most `limit`
+    /// entries are returned; `next_offset` is set when the tree has more.
+    /// `include_metadata` adds sizes and modification times and `filter` leaves out entries as
+    /// in [`Self::directory_tree`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn directory_tree_page<P: AsRef<Path>>(
+        &self,
+        root_path: P,
+        max_depth: Option<usize>,
+        offset: usize,
+        limit: usize,
+        include_metadata: bool,
+        allowed_directories: Arc<Vec<PathBuf>>,
+        walk_options: WalkOptions,
+        filter: &TreeFilter,
+    ) -> ServiceResult<DirectoryTreePage> {
+        let valid_path = self.validate_path(root_path.as_ref(), allowed_directories.clone())?;
+        if !fs::metadata(&valid_path)?.is_dir() {
+            return Err(ServiceError::InvalidArgument(
+                "Root path must be a directory".into(),
+            ));
+        }
+
+        let mut page = DirectoryTreePage {
+            entries: Vec::new(),
+            next_offset: None,
+            reached_max_depth: false,
+        };
+        let mut position = 0;
+        let mut pending = VecDeque::from([(valid_path.clone(), 0)]);
+        let mut followed_links = HashSet::new();
+
+        'walk: while let Some((dir, depth)) = pending.pop_front() {
+            if max_depth.is_some_and(|max_depth| depth >= max_depth) {
+                page.reached_max_depth = true;
+                continue;
+            }
+            for entry in self
+                .configure_walker(
+                    &mut WalkBuilder::new(&dir),
+                    walk_options,
+                    allowed_directories.clone(),
+                )
+                .max_depth(Some(1))
+                .sort_by_file_name(|a, b| a.cmp(b))
+                .build()
+                .filter_map(|e| e.ok())
+            {
+                let child_path = entry.path();
+                if child_path == dir.as_path() {
+                    continue;
+                }
+
+                let metadata = fs::symlink_metadata(child_path)?;
+                let file_type = metadata.file_type();
+                let relative_path = child_path.strip_prefix(&valid_path).unwrap_or(child_path);
+                let is_tree_dir =
+                    file_type.is_dir() || (file_type.is_symlink() && child_path.is_dir());
+                if !filter.lists(relative_path, is_tree_dir) {
+                    continue;
+                }
+                let is_dir = file_type.is_dir() && !file_type.is_symlink();
+                let follow = file_type.is_symlink()
+                    && walk_options.follow_symlinks
+                    && first_visit_of_linked_dir(child_path, &mut followed_links);
+                if is_dir || follow {
+                    pending.push_back((child_path.to_path_buf(), depth + 1));
+                }
+
+                if position >= offset {
+                    if page.entries.len() == limit {
+                        page.next_offset = Some(position);
+                        break 'walk;
+                    }
+                    let mut name = child_path
+                        .strip_prefix(&valid_path)
+                        .unwrap_or(child_path)
+                        .to_string_lossy()
+                        .into_owned();
+                    if file_type.is_symlink() {
+                        name.push('@');
+                    } else if is_dir {
+                        name.push('/');
+                    }
+                    let mut json_entry = json!({ "n": name });
+                    if include_metadata {
+                        add_entry_metadata(&mut json_entry, &metadata);
+                    }
+                    page.entries.push(json_entry);
+                }
+                position += 1;
+            }
+        }
+        Ok(page)
+    }
+
     /// Calculates the total size (in bytes) of all files within a directory tree.
     ///
     /// This function recursively searches the specified `root_path` for files,
@@ -143,9 +445,17 @@ impl FileSystemService {
     /// - Parallel iteration is used to speed up the metadata fetching and summation.
     pub async fn calculate_directory_size(&self, root_path: &Path) -> ServiceResult<u64> {
         let entries = self
-            .search_files_iter(root_path, "**/*".to_string(), vec![], None, None, None)
+            .search_files_iter(
+                root_path,
+                "**/*".to_string(),
+                vec![],
+                None,
+                None,
+                None,
+                WalkOptions::default(),
+            )
             .await?
-            .filter(|e| e.file_type().map_or(false, |ft| ft.is_file())); // Only process files
+            .filter(|e| e.file_type().is_some_and(|ft| ft.is_file())); // Only process files
 
         // Use rayon to parallelize size summation
         let total_size: u64 = entries
@@ -156,6 +466,80 @@ impl FileSystemService {
         Ok(total_size)
     }
 
+    /// Calculates the size of the files within a directory tree like
+    /// [`Self::calculate_directory_size`], grouped by `group_by`. Groups are sorted by size,
+    /// largest first.
+    pub async fn directory_size_breakdown(
+        &self,
+        root_path: &Path,
+        group_by: SizeGroupBy,
+    ) -> ServiceResult<Vec<SizeGroup>> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(root_path, allowed_directories)?;
+        let entries = self
+            .search_files_iter(
+                root_path,
+                "**/*".to_string(),
+                vec![],
+                None,
+                None,
+                None,
+                WalkOptions::default(),
+            )
+            .await?
+            .filter(|e| e.file_type().is_some_and(|ft| ft.is_file()));
+
+        let group_of = |path: &Path| -> String {
+            match group_by {
+                SizeGroupBy::None => "*".to_string(),
+                SizeGroupBy::Extension => path
+                    .extension()
+                    .map(|extension| extension.to_string_lossy().to_lowercase())
+                    .unwrap_or_else(|| "(no extension)".to_string()),
+                SizeGroupBy::TopLevelDir => {
+                    let relative = path.strip_prefix(&valid_path).unwrap_or(path);
+                    let mut components = relative.components();
+                    match (components.next(), components.next()) {
+                        (Some(first), Some(_)) => first.as_os_str().to_string_lossy().to_string(),
+                        _ => ".".to_string(),
+                    }
+                }
+            }
+        };
+
+        let totals: HashMap<String, (u64, u64)> = entries
+            .par_bridge()
+            .filter_map(|entry| {
+                let size = entry.metadata().ok()?.len();
+                Some((group_of(entry.path()), size))
+            })
+            .fold(HashMap::new, |mut totals, (group, size)| {
+                let total = totals.entry(group).or_insert((0, 0));
+                total.0 += 1;
+                total.1 += size;
+                totals
+            })
+            .reduce(HashMap::new, |mut totals, other| {
+                for (group, (files, bytes)) in other {
+                    let total = totals.entry(group).or_insert((0, 0));
+                    total.0 += files;
+                    total.1 += bytes;
+                }
+                totals
+            });
+
+        let mut groups: Vec<SizeGroup> = totals
+            .into_iter()
+            .map(|(group, (files, bytes))| SizeGroup {
+                group,
+                files,
+                bytes,
+            })
+            .collect();
+        groups.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.group.cmp(&b.group)));
+        Ok(groups)
+    }
+
     /// Recursively finds all empty directories within the given root path.
     ///
     /// A directory is considered empty if it contains no files in itself or any of its subdirectories
@@ -202,9 +586,10 @@ impl FileSystemService {
                 None, // No file extension filter
                 None,
                 None,
+                WalkOptions::default(),
             )
             .await?
-            .filter(|e| e.file_type().map_or(false, |ft| ft.is_dir())); // Only directories
+            .filter(|e| e.file_type().is_some_and(|ft| ft.is_dir())); // Only directories
 
         let mut empty_dirs = Vec::new();
 
@@ -219,7 +604,10 @@ impl FileSystemService {
                 .parents(true)
                 .build()
                 .filter_map(|e| e.ok())
-                .all(|e| !e.file_type().map_or(false, |ft| ft.is_file()) || is_system_metadata_file(e.file_name())); // Directory is empty if no files are found in it or subdirs, ".DS_Store" will be ignores on Mac
+                .all(|e| {
+                    !e.file_type().is_some_and(|ft| ft.is_file())
+                        || is_system_metadata_file(e.file_name())
+                }); // Directory is empty if no files are found in it or subdirs, ".DS_Store" will be ignores on Mac
 
             if is_empty && let Some(path_str) = entry.path().to_str() {
                 empty_dirs.push(path_str.to_string());
@@ -229,6 +617,70 @@ impl FileSystemService {
         Ok(empty_dirs)
     }
 
+    /// Removes the directories returned by [`Self::find_empty_directories`], deepest first so that
+    /// parents containing only empty directories can be removed too. System metadata files
+    /// (`.DS_Store`, `Thumbs.db`) are deleted along with them, directories that contain anything
+    /// else (e.g. hidden or ignored files) are skipped.
+    pub async fn delete_empty_directories(
+        &self,
+        mut empty_dirs: Vec<String>,
+    ) -> ServiceResult<EmptyDirectoryRemoval> {
+        let allowed_directories = self.allowed_directories().await;
+        empty_dirs.sort_by_key(|dir| std::cmp::Reverse(Path::new(dir).components().count()));
+
+        let mut removal = EmptyDirectoryRemoval::default();
+        let mut changes = vec![];
+        for dir in empty_dirs {
+            let result =
+                match self.validate_write_path(Path::new(&dir), allowed_directories.clone()) {
+                    Ok(valid_path) => self.remove_empty_directory(&valid_path, &mut changes).await,
+                    Err(err) => Err(err),
+                };
+            match result {
+                Ok(()) => removal.removed.push(dir),
+                Err(err) => removal.skipped.push((dir, err.to_string())),
+            }
+        }
+        self.journal_record("find_empty_directories", changes);
+        Ok(removal)
+    }
+
+    /// Removes `dir` after deleting the system metadata files it contains. Fails if anything
+    /// else is left in it. The changes made are appended to `changes`.
+    async fn remove_empty_directory(
+        &self,
+        dir: &Path,
+        changes: &mut Vec<JournalChange>,
+    ) -> ServiceResult<()> {
+        let mut entries = tokio::fs::read_dir(dir).await?;
+        let mut metadata_files = vec![];
+        let mut has_other_entries = false;
+        while let Some(entry) = entries.next_entry().await? {
+            if is_system_metadata_file(&entry.file_name()) && entry.file_type().await?.is_file() {
+                metadata_files.push(entry.path());
+            } else {
+                has_other_entries = true;
+            }
+        }
+        if has_other_entries {
+            return Err(ServiceError::FromString(format!(
+                "Unable to remove '{}': the directory is not empty.",
+                dir.display()
+            )));
+        }
+        for file in metadata_files {
+            let backup = self.journal_backup(&file).await?;
+            tokio::fs::remove_file(&file).await?;
+            changes.push(JournalChange::Delete {
+                path: file,
+                backup,
+                trashed: false,
+            });
+        }
+        changes.push(self.remove_entry(dir).await?);
+        Ok(())
+    }
+
     pub async fn list_directory(&self, dir_path: &Path) -> ServiceResult<Vec<tokio::fs::DirEntry>> {
         let allowed_directories = self.allowed_directories().await;
 
@@ -245,4 +697,133 @@ impl FileSystemService {
 
         Ok(entries)
     }
+
+    /// Lists the files and directories of `dir_path` by name with the size of the files.
+    /// With a `depth`, directories get the total size of their files like `du`, and their
+    /// entries are listed too down to `depth` levels.
+    pub async fn list_directory_with_sizes(
+        &self,
+        dir_path: &Path,
+        depth: Option<usize>,
+    ) -> ServiceResult<Vec<SizedEntry>> {
+        let mut entries = self.list_directory(dir_path).await?;
+        entries.sort_by_key(|entry| entry.file_name());
+
+        let mut sized = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let path = entry.path();
+            if path.is_dir() {
+                let (size, children) = match depth {
+                    Some(depth) => (
+                        Some(self.calculate_directory_size(&path).await?),
+                        if depth > 1 {
+                            Box::pin(self.list_directory_with_sizes(&path, Some(depth - 1))).await?
+                        } else {
+                            vec![]
+                        },
+                    ),
+                    None => (None, vec![]),
+                };
+                sized.push(SizedEntry {
+                    name,
+                    is_directory: true,
+                    size,
+                    children,
+                });
+            } else if path.is_file() {
+                sized.push(SizedEntry {
+                    name,
+                    is_directory: false,
+                    size: Some(entry.metadata().await?.len()),
+                    children: vec![],
+                });
+            }
+        }
+        Ok(sized)
+    }
+
+    /// Lists the entries of `dir_path` whose name matches `filter_glob`, ordered by `sort_by`,
+    /// skipping the first `offset` entries and returning at most `limit` of them.
+    pub async fn list_directory_page(
+        &self,
+        dir_path: &Path,
+        sort_by: DirectorySortBy,
+        filter_glob: Option<&str>,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> ServiceResult<DirectoryListing> {
+        let filter = filter_glob
+            .map(|pattern| compile_single_glob(pattern, "*", false))
+            .transpose()?;
+        let entries = self.list_directory(dir_path).await?;
+
+        let mut keyed = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let name = entry.file_name();
+            if filter
+                .as_ref()
+                .is_some_and(|filter| !filter.is_match(&name))
+            {
+                continue;
+            }
+            let (size, modified) = match sort_by {
+                DirectorySortBy::Name => (0, None),
+                _ => match entry.metadata().await {
+                    Ok(metadata) => (
+                        if metadata.is_file() {
+                            metadata.len()
+                        } else {
+                            0
+                        },
+                        metadata.modified().ok(),
+                    ),
+                    Err(_) => (0, None),
+                },
+            };
+            keyed.push((entry, name, size, modified));
+        }
+        match sort_by {
+            DirectorySortBy::Name => keyed.sort_by(|a, b| a.1.cmp(&b.1)),
+            DirectorySortBy::Size => {
+                keyed.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.1.cmp(&b.1)))
+            }
+            DirectorySortBy::Mtime => {
+                keyed.sort_by(|a, b| b.3.cmp(&a.3).then_with(|| a.1.cmp(&b.1)))
+            }
+        }
+
+        let entries_total = keyed.len();
+        let end = limit.map_or(entries_total, |limit| {
+            offset.saturating_add(limit).min(entries_total)
+        });
+        let entries = keyed
+            .into_iter()
+            .skip(offset)
+            .take(end.saturating_sub(offset))
+            .map(|(entry, ..)| entry)
+            .collect();
+        Ok(DirectoryListing {
+            entries,
+            entries_total,
+            next_offset: (end < entries_total).then_some(end),
+        })
+    }
+}
+
+/// Adds the size (`s`, files only) and the modification time (`m`) of an entry to its JSON object.
+fn add_entry_metadata(entry: &mut Value, metadata: &fs::Metadata) {
+    let Some(entry) = entry.as_object_mut() else {
+        return;
+    };
+    if metadata.is_file() {
+        entry.insert("s".to_string(), json!(metadata.len()));
+    }
+    if let Ok(modified) = metadata.modified() {
+        let modified: DateTime<Utc> = modified.into();
+        entry.insert(
+            "m".to_string(),
+            json!(modified.format("%Y-%m-%dT%H:%M:%SZ").to_string()),
+        );
+    }
 }
@@ -0,0 +1,202 @@
+use crate::{
+    error::{ServiceError, ServiceResult},
+    fs_service::{FileSystemService, journal::JournalChange, utils::normalize_path},
+};
+use chrono::Utc;
+use std::path::{Component, Path, PathBuf};
+
+/// Name of the trash directory created at the top of an allowed directory.
+pub const TRASH_DIR_NAME: &str = ".mcp-trash";
+const TRASH_ITEMS_DIR: &str = "items";
+const TRASH_MANIFEST: &str = "manifest.json";
+
+/// A file or directory moved to the trash, as recorded in the trash manifest.
+#[derive(::serde::Serialize, ::serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct TrashEntry {
+    /// Name of the item in the trash directory.
+    pub id: String,
+    /// Where the item was before it was deleted.
+    pub original_path: PathBuf,
+    /// UTC time of the deletion.
+    pub deleted_at: String,
+}
+
+async fn read_manifest(trash_dir: &Path) -> ServiceResult<Vec<TrashEntry>> {
+    match tokio::fs::read(trash_dir.join(TRASH_MANIFEST)).await {
+        Ok(content) => Ok(serde_json::from_slice(&content)?),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+        Err(err) => Err(err.into()),
+    }
+}
+
+async fn write_manifest(trash_dir: &Path, entries: &[TrashEntry]) -> ServiceResult<()> {
+    tokio::fs::write(
+        trash_dir.join(TRASH_MANIFEST),
+        serde_json::to_vec_pretty(entries)?,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Returns the path of the trash item `id`. The manifest is a file on disk, so an id that is
+/// not a plain name or an item reached through a symlinked directory is refused: either could
+/// move a file from outside the allowed directories back into them.
+async fn trash_item_path(trash_dir: &Path, id: &str) -> ServiceResult<PathBuf> {
+    let mut components = Path::new(id).components();
+    let (Some(Component::Normal(_)), None) = (components.next(), components.next()) else {
+        return Err(ServiceError::PathDenied(format!(
+            "'{id}' is not a valid trash item."
+        )));
+    };
+    let items_dir = trash_dir.join(TRASH_ITEMS_DIR);
+    // the item itself may be a trashed symlink, so only the directory containing it is resolved
+    let canonical_items_dir = tokio::fs::canonicalize(&items_dir).await?;
+    if canonical_items_dir
+        != tokio::fs::canonicalize(trash_dir)
+            .await?
+            .join(TRASH_ITEMS_DIR)
+    {
+        return Err(ServiceError::PathDenied(format!(
+            "'{}' is not a trash directory.",
+            items_dir.display()
+        )));
+    }
+    Ok(canonical_items_dir.join(id))
+}
+
+/// Returns true if `path` is inside a trash directory, which only the server may modify.
+pub(crate) fn is_in_trash(path: &Path) -> bool {
+    path.components()
+        .any(|component| component.as_os_str() == TRASH_DIR_NAME)
+}
+
+impl FileSystemService {
+    /// Returns the trash directory of the innermost allowed directory containing `path`.
+    async fn trash_dir_for(&self, path: &Path) -> ServiceResult<PathBuf> {
+        let allowed_directories = self.allowed_directories().await;
+        let normalized = normalize_path(path);
+        allowed_directories
+            .iter()
+            .filter(|dir| path.starts_with(dir) || normalized.starts_with(normalize_path(dir)))
+            .max_by_key(|dir| dir.components().count())
+            .map(|dir| dir.join(TRASH_DIR_NAME))
+            .ok_or_else(|| {
+                ServiceError::PathDenied(format!(
+                    "'{}' is outside allowed directories.",
+                    path.display()
+                ))
+            })
+    }
+
+    /// Deletes a validated file or empty directory, moving it to the trash if the trash is
+    /// enabled. Returns the change to record in the journal.
+    pub(crate) async fn remove_entry(&self, path: &Path) -> ServiceResult<JournalChange> {
+        self.ensure_writable()?;
+        let change = if self.uses_trash() {
+            self.move_to_trash(path).await?;
+            JournalChange::Delete {
+                path: path.to_path_buf(),
+                backup: None,
+                trashed: true,
+            }
+        } else {
+            let backup = self.journal_backup(path).await?;
+            if tokio::fs::symlink_metadata(path).await?.is_dir() {
+                tokio::fs::remove_dir(path).await?;
+            } else {
+                tokio::fs::remove_file(path).await?;
+            }
+            JournalChange::Delete {
+                path: path.to_path_buf(),
+                backup,
+                trashed: false,
+            }
+        };
+        Ok(change)
+    }
+
+    /// Moves a validated path to the trash directory at the top of its allowed directory and
+    /// records it in the trash manifest.
+    pub async fn move_to_trash(&self, path: &Path) -> ServiceResult<TrashEntry> {
+        self.ensure_writable()?;
+        let trash_dir = self.trash_dir_for(path).await?;
+        if path.starts_with(&trash_dir) {
+            return Err(ServiceError::FromString(format!(
+                "'{}' is already in the trash.",
+                path.display()
+            )));
+        }
+        let _guard = self.trash_lock.lock().await;
+        let items_dir = trash_dir.join(TRASH_ITEMS_DIR);
+        tokio::fs::create_dir_all(&items_dir).await?;
+
+        let now = Utc::now();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut id = format!("{}-{name}", now.format("%Y%m%dT%H%M%S%.3f"));
+        let mut suffix = 1;
+        while tokio::fs::symlink_metadata(items_dir.join(&id))
+            .await
+            .is_ok()
+        {
+            suffix += 1;
+            id = format!("{}-{suffix}-{name}", now.format("%Y%m%dT%H%M%S%.3f"));
+        }
+
+        tokio::fs::rename(path, items_dir.join(&id)).await?;
+        let entry = TrashEntry {
+            id,
+            original_path: path.to_path_buf(),
+            deleted_at: now.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        };
+        let mut entries = read_manifest(&trash_dir).await?;
+        entries.push(entry.clone());
+        write_manifest(&trash_dir, &entries).await?;
+        Ok(entry)
+    }
+
+    /// Lists the items in the trash of every allowed directory, oldest first.
+    pub async fn list_trash(&self) -> ServiceResult<Vec<TrashEntry>> {
+        let allowed_directories = self.allowed_directories().await;
+        let mut entries = vec![];
+        for dir in allowed_directories.iter() {
+            entries.extend(read_manifest(&dir.join(TRASH_DIR_NAME)).await?);
+        }
+        entries.sort_by(|a, b| a.deleted_at.cmp(&b.deleted_at));
+        Ok(entries)
+    }
+
+    /// Moves the most recently deleted item that was at `path` back from the trash.
+    /// Fails if something exists at `path` again.
+    pub async fn restore_from_trash(&self, path: &Path) -> ServiceResult<TrashEntry> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_write_path(path, allowed_directories)?;
+        let trash_dir = self.trash_dir_for(&valid_path).await?;
+
+        let _guard = self.trash_lock.lock().await;
+        let mut entries = read_manifest(&trash_dir).await?;
+        let position = entries
+            .iter()
+            .rposition(|entry| entry.original_path == valid_path)
+            .ok_or_else(|| {
+                ServiceError::NotFound(format!("'{}' was not found in the trash.", path.display()))
+            })?;
+        if tokio::fs::symlink_metadata(&valid_path).await.is_ok() {
+            return Err(ServiceError::FromString(format!(
+                "Cannot restore '{}': the path already exists.",
+                path.display()
+            )));
+        }
+
+        let source = trash_item_path(&trash_dir, &entries[position].id).await?;
+        let entry = entries.remove(position);
+        if let Some(parent) = valid_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::rename(source, &valid_path).await?;
+        write_manifest(&trash_dir, &entries).await?;
+        Ok(entry)
+    }
+}
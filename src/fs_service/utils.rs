@@ -1,9 +1,10 @@
 use crate::error::{ServiceError, ServiceResult};
-use base64::{engine::general_purpose, write::EncoderWriter};
+use base64::{Engine, engine::general_purpose, write::EncoderWriter};
 use chrono::{DateTime, Local};
 use dirs::home_dir;
 use rust_mcp_sdk::macros::JsonSchema;
-use std::io::Write;
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 #[cfg(windows)]
@@ -12,7 +13,7 @@ use std::{
     ffi::OsStr,
     fs::{self},
     path::{Component, Path, PathBuf, Prefix},
-    time::SystemTime,
+    time::{Instant, SystemTime},
 };
 use tokio::io::AsyncReadExt;
 use tokio::{
@@ -75,6 +76,30 @@ pub fn normalize_path(path: &Path) -> PathBuf {
     path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
 }
 
+/// Resolves `path` like [`normalize_path`], including a path that does not exist yet: its
+/// longest existing ancestor is canonicalized and the remaining components are appended, so
+/// that symlinked directories on the way are resolved as well.
+pub fn resolve_path(path: &Path) -> PathBuf {
+    let components: Vec<Component> = path.components().collect();
+    for end in (1..=components.len()).rev() {
+        let ancestor: PathBuf = components[..end].iter().collect();
+        let Ok(mut resolved) = ancestor.canonicalize() else {
+            continue;
+        };
+        for component in &components[end..] {
+            match component {
+                Component::ParentDir => {
+                    resolved.pop();
+                }
+                Component::CurDir => {}
+                component => resolved.push(component),
+            }
+        }
+        return resolved;
+    }
+    path.to_path_buf()
+}
+
 pub fn expand_home(path: PathBuf) -> PathBuf {
     if let Some(home_dir) = home_dir()
         && path.starts_with("~")
@@ -101,6 +126,30 @@ pub fn format_bytes(bytes: u64) -> String {
     format!("{bytes} bytes")
 }
 
+/// Returns the lowercase hex SHA-256 digest of `content`.
+pub fn sha256_hex(content: &[u8]) -> String {
+    hex_digest(&Sha256::digest(content))
+}
+
+/// Returns the lowercase hex SHA-256 digest of the file at `path`, read in chunks.
+pub fn sha256_file_hex(path: &Path) -> std::io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex_digest(&hasher.finalize()))
+}
+
+fn hex_digest(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
 pub fn normalize_line_endings(text: &str) -> String {
     text.replace("\r\n", "\n").replace('\r', "\n")
 }
@@ -211,6 +260,62 @@ pub fn mime_from_path(path: &Path) -> ServiceResult<infer::Type> {
     Ok(kind)
 }
 
+/// Detects the MIME type of the file at `path` from its content, falling back to `text/plain`
+/// for UTF-8 text and `application/octet-stream` for anything else.
+pub fn detect_mime_type(path: &Path) -> Option<String> {
+    if let Ok(kind) = mime_from_path(path) {
+        return Some(kind.mime_type().to_string());
+    }
+    let mut head = Vec::with_capacity(8192);
+    fs::File::open(path)
+        .ok()?
+        .take(8192)
+        .read_to_end(&mut head)
+        .ok()?;
+    let is_text = !head.contains(&0)
+        && match std::str::from_utf8(&head) {
+            Ok(_) => true,
+            // a multi-byte character may be cut at the end of the sample
+            Err(err) => err.error_len().is_none(),
+        };
+    Some(
+        if is_text {
+            "text/plain"
+        } else {
+            "application/octet-stream"
+        }
+        .to_string(),
+    )
+}
+
+/// Looks up the name of `id` in an account database such as `/etc/passwd` or `/etc/group`,
+/// whose lines are `name:password:id:...`.
+#[cfg(unix)]
+fn account_name(database: &str, id: u32) -> Option<String> {
+    fs::read_to_string(database)
+        .ok()?
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .find_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let entry_id = fields.nth(1)?.parse::<u32>().ok()?;
+            (entry_id == id).then(|| name.to_string())
+        })
+}
+
+/// Returns the name of the user `uid`, if it is a local account.
+#[cfg(unix)]
+pub fn user_name(uid: u32) -> Option<String> {
+    account_name("/etc/passwd", uid)
+}
+
+/// Returns the name of the group `gid`, if it is a local group.
+#[cfg(unix)]
+pub fn group_name(gid: u32) -> Option<String> {
+    account_name("/etc/group", gid)
+}
+
 pub fn escape_regex(text: &str) -> String {
     // Covers special characters in regex engines (RE2, PCRE, JS, Python)
     const SPECIAL_CHARS: &[char] = &[
@@ -264,3 +369,40 @@ pub fn parse_file_path(input: &str) -> ServiceResult<PathBuf> {
         input.strip_prefix("file://").unwrap_or(input).trim(),
     ))
 }
+
+/// Encodes a resumable state into an opaque, URL-safe continuation cursor.
+pub fn encode_cursor<T: ::serde::Serialize>(state: &T) -> ServiceResult<String> {
+    let bytes = serde_json::to_vec(state)?;
+    Ok(general_purpose::URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// Decodes a continuation cursor previously produced by [`encode_cursor`].
+pub fn decode_cursor<T: ::serde::de::DeserializeOwned>(cursor: &str) -> ServiceResult<T> {
+    general_purpose::URL_SAFE_NO_PAD
+        .decode(cursor.trim())
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or(ServiceError::InvalidArgument(format!(
+            "Invalid or expired cursor: '{cursor}'"
+        )))
+}
+
+/// Returns the `_meta` fields describing the coverage of a search started at `started`.
+pub fn search_summary_meta(
+    files_scanned: usize,
+    files_matched: usize,
+    matches: usize,
+    started: Instant,
+    truncated: bool,
+) -> serde_json::Map<String, serde_json::Value> {
+    let mut meta = serde_json::Map::new();
+    meta.insert("files_scanned".to_string(), files_scanned.into());
+    meta.insert("files_matched".to_string(), files_matched.into());
+    meta.insert("matches".to_string(), matches.into());
+    meta.insert(
+        "duration_ms".to_string(),
+        (started.elapsed().as_millis() as u64).into(),
+    );
+    meta.insert("truncated".to_string(), truncated.into());
+    meta
+}
@@ -0,0 +1,264 @@
+use crate::{
+    error::{ServiceError, ServiceResult},
+    fs_service::{FileSystemService, resources::file_uri_to_path, search::AstCache},
+};
+use globset::GlobSet;
+use notify::{
+    EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+    event::{ModifyKind, RenameMode},
+};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use tokio::sync::broadcast;
+
+/// Number of change events buffered for each subscriber before older events are dropped.
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// The kind of change reported by a [`FileChangeEvent`].
+#[derive(::serde::Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChangeKind {
+    Created,
+    Modified,
+    Removed,
+    Renamed,
+}
+
+/// A change observed under a watched directory.
+#[derive(::serde::Serialize, Debug, Clone)]
+pub struct FileChangeEvent {
+    pub path: PathBuf,
+    pub kind: FileChangeKind,
+    /// The watched directory that reported the change.
+    pub watch_root: PathBuf,
+}
+
+/// Keeps the active filesystem watchers and broadcasts their events to subscribers.
+pub struct FileWatcher {
+    watchers: Mutex<HashMap<PathBuf, RecommendedWatcher>>,
+    events: broadcast::Sender<FileChangeEvent>,
+    /// Watchers of files subscribed to as resources, keyed by file path.
+    resource_watchers: Mutex<HashMap<PathBuf, RecommendedWatcher>>,
+    /// URIs of subscribed resources that changed.
+    resource_updates: broadcast::Sender<String>,
+    /// Drops the parse trees of changed files.
+    ast_cache: Arc<AstCache>,
+}
+
+fn change_kind(kind: &EventKind) -> Option<FileChangeKind> {
+    match kind {
+        EventKind::Create(_) => Some(FileChangeKind::Created),
+        EventKind::Remove(_) => Some(FileChangeKind::Removed),
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => Some(FileChangeKind::Removed),
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => Some(FileChangeKind::Created),
+        EventKind::Modify(ModifyKind::Name(_)) => Some(FileChangeKind::Renamed),
+        EventKind::Modify(_) => Some(FileChangeKind::Modified),
+        EventKind::Access(_) | EventKind::Any | EventKind::Other => None,
+    }
+}
+
+impl FileWatcher {
+    pub(crate) fn new(ast_cache: Arc<AstCache>) -> Self {
+        Self {
+            watchers: Mutex::new(HashMap::new()),
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            resource_watchers: Mutex::new(HashMap::new()),
+            resource_updates: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            ast_cache,
+        }
+    }
+
+    /// Starts watching `path`. Changes of paths matching `deny_globs` are not reported.
+    /// Returns false if it was already being watched.
+    pub fn watch(
+        &self,
+        path: &Path,
+        recursive: bool,
+        deny_globs: Option<Arc<GlobSet>>,
+    ) -> ServiceResult<bool> {
+        let mut watchers = self.watchers.lock().unwrap();
+        if watchers.contains_key(path) {
+            return Ok(false);
+        }
+
+        let sender = self.events.clone();
+        let ast_cache = self.ast_cache.clone();
+        let watch_root = path.to_path_buf();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let Ok(event) = event else {
+                    return;
+                };
+                let Some(kind) = change_kind(&event.kind) else {
+                    return;
+                };
+                for path in event.paths {
+                    ast_cache.invalidate(&path);
+                    if deny_globs
+                        .as_ref()
+                        .is_some_and(|deny_globs| deny_globs.is_match(&path))
+                    {
+                        continue;
+                    }
+                    // an error only means there are no subscribers at the moment
+                    let _ = sender.send(FileChangeEvent {
+                        path,
+                        kind,
+                        watch_root: watch_root.clone(),
+                    });
+                }
+            })
+            .map_err(|err| ServiceError::FromString(format!("Unable to create watcher: {err}")))?;
+
+        let mode = if recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        watcher.watch(path, mode).map_err(|err| {
+            ServiceError::FromString(format!("Unable to watch '{}': {err}", path.display()))
+        })?;
+        watchers.insert(path.to_path_buf(), watcher);
+        Ok(true)
+    }
+
+    /// Stops watching `path`. Returns false if it was not being watched.
+    pub fn unwatch(&self, path: &Path) -> bool {
+        // dropping the watcher stops it
+        self.watchers.lock().unwrap().remove(path).is_some()
+    }
+
+    pub fn watched_paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<_> = self.watchers.lock().unwrap().keys().cloned().collect();
+        paths.sort();
+        paths
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<FileChangeEvent> {
+        self.events.subscribe()
+    }
+
+    /// Reports changes of the file at `path` as updates of `uri`. The parent directory is
+    /// watched rather than the file itself, so the subscription survives the file being
+    /// replaced (e.g. by an atomic save) or deleted and re-created. Nothing is reported if
+    /// `path` matches `deny_globs`.
+    pub fn watch_resource(
+        &self,
+        path: &Path,
+        uri: String,
+        deny_globs: Option<Arc<GlobSet>>,
+    ) -> ServiceResult<bool> {
+        let mut watchers = self.resource_watchers.lock().unwrap();
+        if watchers.contains_key(path) {
+            return Ok(false);
+        }
+        let parent = path.parent().unwrap_or(path);
+        let denied = deny_globs.is_some_and(|deny_globs| deny_globs.is_match(path));
+
+        let sender = self.resource_updates.clone();
+        let file_path = path.to_path_buf();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let Ok(event) = event else {
+                    return;
+                };
+                if !denied && change_kind(&event.kind).is_some() && event.paths.contains(&file_path)
+                {
+                    let _ = sender.send(uri.clone());
+                }
+            })
+            .map_err(|err| ServiceError::FromString(format!("Unable to create watcher: {err}")))?;
+
+        watcher
+            .watch(parent, RecursiveMode::NonRecursive)
+            .map_err(|err| {
+                ServiceError::FromString(format!("Unable to watch '{}': {err}", path.display()))
+            })?;
+        watchers.insert(path.to_path_buf(), watcher);
+        Ok(true)
+    }
+
+    /// Stops reporting changes of a file passed to [`Self::watch_resource`].
+    pub fn unwatch_resource(&self, path: &Path) -> bool {
+        self.resource_watchers
+            .lock()
+            .unwrap()
+            .remove(path)
+            .is_some()
+    }
+
+    pub fn subscribe_resource_updates(&self) -> broadcast::Receiver<String> {
+        self.resource_updates.subscribe()
+    }
+}
+
+impl FileSystemService {
+    /// Starts watching a directory within the allowed directories for changes.
+    /// Events are delivered to receivers returned by [`Self::subscribe_file_changes`].
+    ///
+    /// Returns the validated path and whether a new watch was started.
+    pub async fn watch_directory(
+        &self,
+        path: &Path,
+        recursive: bool,
+    ) -> ServiceResult<(PathBuf, bool)> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(path, allowed_directories)?;
+        if !valid_path.is_dir() {
+            return Err(ServiceError::InvalidArgument(format!(
+                "'{}' is not a directory.",
+                path.display()
+            )));
+        }
+        let started = self
+            .file_watcher()
+            .watch(&valid_path, recursive, self.deny_globs())?;
+        Ok((valid_path, started))
+    }
+
+    /// Stops watching a directory previously passed to [`Self::watch_directory`].
+    pub async fn unwatch_directory(&self, path: &Path) -> ServiceResult<(PathBuf, bool)> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(path, allowed_directories)?;
+        let stopped = self.file_watcher().unwatch(&valid_path);
+        Ok((valid_path, stopped))
+    }
+
+    pub fn watched_directories(&self) -> Vec<PathBuf> {
+        self.file_watcher().watched_paths()
+    }
+
+    pub fn subscribe_file_changes(&self) -> broadcast::Receiver<FileChangeEvent> {
+        self.file_watcher().subscribe()
+    }
+
+    /// Subscribes to changes of the file identified by a `file://` resource URI.
+    /// Updates are delivered to receivers returned by [`Self::subscribe_resource_updates`].
+    pub async fn subscribe_resource(&self, uri: &str) -> ServiceResult<bool> {
+        let path = file_uri_to_path(uri)?;
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(&path, allowed_directories)?;
+        if valid_path.is_dir() {
+            return Err(ServiceError::InvalidArgument(format!(
+                "Cannot subscribe to a directory: '{uri}'"
+            )));
+        }
+        self.file_watcher()
+            .watch_resource(&valid_path, uri.to_string(), self.deny_globs())
+    }
+
+    /// Cancels a subscription made with [`Self::subscribe_resource`].
+    pub async fn unsubscribe_resource(&self, uri: &str) -> ServiceResult<bool> {
+        let path = file_uri_to_path(uri)?;
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(&path, allowed_directories)?;
+        Ok(self.file_watcher().unwatch_resource(&valid_path))
+    }
+
+    pub fn subscribe_resource_updates(&self) -> broadcast::Receiver<String> {
+        self.file_watcher().subscribe_resource_updates()
+    }
+}
@@ -0,0 +1,199 @@
+use crate::{
+    error::{ServiceError, ServiceResult},
+    fs_service::FileSystemService,
+};
+use std::path::Path;
+
+/// An extended attribute of a file: a Unix xattr, or an NTFS alternate data stream on Windows.
+#[derive(::serde::Serialize, Debug, Clone, PartialEq)]
+pub struct ExtendedAttribute {
+    pub name: String,
+    /// Size of the value in bytes.
+    pub size: u64,
+}
+
+#[cfg(unix)]
+mod sys {
+    use rustix::fs::{XattrFlags, getxattr, listxattr, removexattr, setxattr};
+    use std::{io, path::Path};
+
+    /// Calls `read` with a buffer sized by a first call with an empty buffer, retrying if the
+    /// value grew in between.
+    fn read_sized(read: impl Fn(&mut [u8]) -> rustix::io::Result<usize>) -> io::Result<Vec<u8>> {
+        loop {
+            let size = read(&mut [])?;
+            let mut buffer = vec![0; size];
+            match read(&mut buffer) {
+                Ok(len) => {
+                    buffer.truncate(len);
+                    return Ok(buffer);
+                }
+                Err(rustix::io::Errno::RANGE) => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    pub(super) fn list(path: &Path) -> io::Result<Vec<String>> {
+        let names = read_sized(|buffer| listxattr(path, buffer))?;
+        Ok(names
+            .split(|byte| *byte == 0)
+            .filter(|name| !name.is_empty())
+            .map(|name| String::from_utf8_lossy(name).to_string())
+            .collect())
+    }
+
+    /// The errno of a missing attribute differs between platforms (`ENODATA`, `ENOATTR`), it is
+    /// reported as `NotFound` when the attribute is not listed.
+    fn not_found_unless_listed(err: io::Error, path: &Path, name: &str) -> io::Error {
+        if list(path).is_ok_and(|names| !names.iter().any(|listed| listed == name)) {
+            return io::ErrorKind::NotFound.into();
+        }
+        err
+    }
+
+    pub(super) fn get(path: &Path, name: &str) -> io::Result<Vec<u8>> {
+        read_sized(|buffer| getxattr(path, name, buffer))
+            .map_err(|err| not_found_unless_listed(err, path, name))
+    }
+
+    pub(super) fn set(path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+        Ok(setxattr(path, name, value, XattrFlags::empty())?)
+    }
+
+    pub(super) fn remove(path: &Path, name: &str) -> io::Result<()> {
+        removexattr(path, name).map_err(|err| not_found_unless_listed(err.into(), path, name))
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::{ffi::OsString, io, path::Path};
+
+    /// Path of the alternate data stream `name` of `path`, i.e. `path:name`.
+    fn stream_path(path: &Path, name: &str) -> std::path::PathBuf {
+        let mut stream = OsString::from(path.as_os_str());
+        stream.push(":");
+        stream.push(name);
+        stream.into()
+    }
+
+    pub(super) fn list(_path: &Path) -> io::Result<Vec<String>> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "listing alternate data streams is not supported, read a stream by its name instead",
+        ))
+    }
+
+    pub(super) fn get(path: &Path, name: &str) -> io::Result<Vec<u8>> {
+        std::fs::read(stream_path(path, name))
+    }
+
+    pub(super) fn set(path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+        std::fs::write(stream_path(path, name), value)
+    }
+
+    pub(super) fn remove(path: &Path, name: &str) -> io::Result<()> {
+        std::fs::remove_file(stream_path(path, name))
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod sys {
+    use std::{io, path::Path};
+
+    fn unsupported<T>() -> io::Result<T> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "extended attributes are not supported on this platform",
+        ))
+    }
+
+    pub(super) fn list(_path: &Path) -> io::Result<Vec<String>> {
+        unsupported()
+    }
+
+    pub(super) fn get(_path: &Path, _name: &str) -> io::Result<Vec<u8>> {
+        unsupported()
+    }
+
+    pub(super) fn set(_path: &Path, _name: &str, _value: &[u8]) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub(super) fn remove(_path: &Path, _name: &str) -> io::Result<()> {
+        unsupported()
+    }
+}
+
+fn validate_attribute_name(name: &str) -> ServiceResult<()> {
+    if name.is_empty() || name.contains(['\0', '/', '\\', ':']) {
+        return Err(ServiceError::InvalidArgument(format!(
+            "Invalid extended attribute name '{name}'."
+        )));
+    }
+    Ok(())
+}
+
+/// Reports a missing attribute by name rather than with the raw OS error.
+fn attribute_error(err: std::io::Error, path: &Path, name: &str) -> ServiceError {
+    if err.kind() == std::io::ErrorKind::NotFound && path.exists() {
+        return ServiceError::NotFound(format!(
+            "'{}' has no extended attribute '{name}'.",
+            path.display()
+        ));
+    }
+    err.into()
+}
+
+impl FileSystemService {
+    /// Lists the extended attributes of `path` with the size of their values.
+    pub async fn list_extended_attributes(
+        &self,
+        path: &Path,
+    ) -> ServiceResult<Vec<ExtendedAttribute>> {
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(path, allowed_directories)?;
+        let mut attributes: Vec<ExtendedAttribute> = sys::list(&valid_path)?
+            .into_iter()
+            .filter_map(|name| {
+                // an attribute removed since it was listed is skipped
+                sys::get(&valid_path, &name)
+                    .ok()
+                    .map(|value| ExtendedAttribute {
+                        name,
+                        size: value.len() as u64,
+                    })
+            })
+            .collect();
+        attributes.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(attributes)
+    }
+
+    /// Returns the value of the extended attribute `name` of `path`.
+    pub async fn get_extended_attribute(&self, path: &Path, name: &str) -> ServiceResult<Vec<u8>> {
+        validate_attribute_name(name)?;
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_path(path, allowed_directories)?;
+        sys::get(&valid_path, name).map_err(|err| attribute_error(err, &valid_path, name))
+    }
+
+    /// Sets the extended attribute `name` of `path` to `value`, or removes it if `value` is
+    /// `None`.
+    pub async fn set_extended_attribute(
+        &self,
+        path: &Path,
+        name: &str,
+        value: Option<&[u8]>,
+    ) -> ServiceResult<()> {
+        validate_attribute_name(name)?;
+        let allowed_directories = self.allowed_directories().await;
+        let valid_path = self.validate_write_path(path, allowed_directories)?;
+        match value {
+            Some(value) => sys::set(&valid_path, name, value)?,
+            None => sys::remove(&valid_path, name)
+                .map_err(|err| attribute_error(err, &valid_path, name))?,
+        }
+        Ok(())
+    }
+}
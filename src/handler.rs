@@ -1,30 +1,210 @@
 use crate::cli::CommandArguments;
-use crate::error::ServiceError;
+use crate::error::{ServiceError, ToolFailure};
+use crate::fs_service::utils::{decode_cursor, encode_cursor};
+use crate::fs_service::{
+    CancellationToken, DEFAULT_AST_MAX_FILE_SIZE, DEFAULT_AST_MAX_FILES, DirectoryMode,
+    OperationContext, RESOURCE_PAGE_SIZE, ResourceContent, path_to_file_uri,
+};
 use crate::invoke_tools;
+use crate::limits::ToolLimits;
+use crate::logging::McpLogger;
+use crate::policy::ToolPolicies;
 use crate::{error::ServiceResult, fs_service::FileSystemService, tools::*};
 use async_trait::async_trait;
 use rust_mcp_sdk::McpServer;
 use rust_mcp_sdk::mcp_server::ServerHandler;
 use rust_mcp_sdk::schema::RootsListChangedNotification;
 use rust_mcp_sdk::schema::{
-    CallToolRequest, CallToolResult, InitializeRequest, InitializeResult, ListToolsRequest,
-    ListToolsResult, RpcError, schema_utils::CallToolError,
+    BlobResourceContents, CallToolRequest, CallToolRequestParams, CallToolResult,
+    CancelledNotification, InitializeRequest, InitializeResult, ListResourceTemplatesRequest,
+    ListResourceTemplatesResult, ListResourcesRequest, ListResourcesResult, ListToolsRequest,
+    ListToolsResult, LoggingLevel, ProgressNotification, ProgressNotificationParams, ProgressToken,
+    ReadResourceRequest, ReadResourceResult, Resource, ResourceTemplate,
+    ResourceUpdatedNotificationParams, Result as RpcResult, RpcError, SetLevelRequest,
+    SubscribeRequest, TextResourceContents, Tool, UnsubscribeRequest, schema_utils::CallToolError,
 };
+use serde_json::json;
 use std::cmp::Ordering;
-use std::collections::HashSet;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+
+#[derive(::serde::Serialize, ::serde::Deserialize)]
+struct ResourceListCursor {
+    offset: usize,
+}
 
+/// Handles the requests of an MCP session. The sessions of a server created with
+/// [`FileSystemHandler::new_session`] share its service, limits, policies and statistics, while
+/// the enabled tools, log level and running calls belong to each session.
 pub struct FileSystemHandler {
-    readonly: bool,
     mcp_roots_support: bool,
     fs_service: Arc<FileSystemService>,
-    enabled_tools: Option<HashSet<String>>,
+    tools: ToolRegistry,
+    logger: Arc<McpLogger>,
+    metrics: Arc<ServerMetrics>,
+    policy: Option<Arc<ToolPolicies>>,
+    limits: Arc<ToolLimits>,
+    tool_timeout: Option<Duration>,
+    max_response_bytes: Option<usize>,
+    /// Cancellation tokens of the tool calls currently running, keyed by a local sequence number.
+    in_flight: Mutex<HashMap<u64, CancellationToken>>,
+    next_operation_id: AtomicU64,
+    /// Number of sessions subscribed to each resource URI, shared by the sessions of a server so
+    /// that the file is watched until the last of them unsubscribes.
+    resource_subscribers: Arc<tokio::sync::Mutex<HashMap<String, usize>>>,
+    /// Resource URIs this session subscribed to.
+    subscribed_resources: Arc<Mutex<HashSet<String>>>,
+}
+
+/// Keeps a tool call registered as in flight until it completes or its future is dropped.
+struct InFlightOperation<'a> {
+    registry: &'a Mutex<HashMap<u64, CancellationToken>>,
+    id: u64,
+}
+
+impl Drop for InFlightOperation<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut in_flight) = self.registry.lock() {
+            in_flight.remove(&self.id);
+        }
+    }
+}
+
+/// Releases one session's subscription to `uri`, and stops watching the file once no session
+/// is subscribed to it anymore.
+async fn release_resource(
+    fs_service: &FileSystemService,
+    subscribers: &mut HashMap<String, usize>,
+    uri: &str,
+) -> ServiceResult<()> {
+    let Some(count) = subscribers.get_mut(uri) else {
+        return Ok(());
+    };
+    *count -= 1;
+    if *count == 0 {
+        subscribers.remove(uri);
+        fs_service.unsubscribe_resource(uri).await?;
+    }
+    Ok(())
+}
+
+impl Drop for FileSystemHandler {
+    /// Releases the resource subscriptions the session left behind when it ends.
+    fn drop(&mut self) {
+        let uris: Vec<String> = match self.subscribed_resources.lock() {
+            Ok(mut subscribed) => subscribed.drain().collect(),
+            Err(_) => return,
+        };
+        if uris.is_empty() {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let fs_service = self.fs_service.clone();
+        let resource_subscribers = self.resource_subscribers.clone();
+        runtime.spawn(async move {
+            let mut subscribers = resource_subscribers.lock().await;
+            for uri in uris {
+                let _ = release_resource(&fs_service, &mut subscribers, &uri).await;
+            }
+        });
+    }
 }
 
 impl FileSystemHandler {
     pub fn new(args: &CommandArguments) -> ServiceResult<Self> {
-        let fs_service = FileSystemService::try_new(&args.allowed_directories)?;
+        let (allowed_directories, directory_modes) = args.directory_entries();
+        let mut fs_service = FileSystemService::try_new(&allowed_directories)?
+            .with_directory_modes(args.default_directory_mode(), directory_modes)
+            .with_deny_globs(&args.deny_globs)?
+            .with_trash(args.use_trash)
+            .with_atomic_writes(!args.no_atomic_writes)
+            .with_search_threads(
+                args.search_threads.unwrap_or_default(),
+                args.low_priority_search,
+            )
+            .with_ast_limits(
+                args.ast_max_file_size.unwrap_or(DEFAULT_AST_MAX_FILE_SIZE),
+                args.ast_max_files.unwrap_or(DEFAULT_AST_MAX_FILES),
+            );
+        if let Some(index_dir) = &args.index_dir {
+            fs_service = fs_service.with_index_dir(index_dir.into());
+        }
+        if let Some(read_max_bytes) = args.read_max_bytes {
+            fs_service = fs_service.with_read_max_bytes(read_max_bytes);
+        }
+        if let Some(backup_dir) = &args.backup_dir {
+            fs_service = fs_service.with_backup_dir(backup_dir.into());
+        }
+        if args.journal {
+            fs_service = fs_service.with_journal(std::env::temp_dir().join(format!(
+                "{}-journal-{}",
+                env!("CARGO_PKG_NAME"),
+                std::process::id()
+            )));
+        }
 
+        let policy = args
+            .policy_file
+            .as_ref()
+            .map(|path| ToolPolicies::load(Path::new(path)))
+            .transpose()?;
+
+        Ok(Self {
+            fs_service: Arc::new(fs_service),
+            mcp_roots_support: args.enable_roots,
+            tools: Self::tool_registry(args),
+            logger: Arc::new(McpLogger::default()),
+            metrics: Arc::new(ServerMetrics::default()),
+            policy: policy.map(Arc::new),
+            limits: Arc::new(ToolLimits::new(
+                args.max_concurrent_heavy,
+                args.max_calls_per_minute,
+            )),
+            tool_timeout: args.tool_timeout.map(Duration::from_secs),
+            max_response_bytes: args.max_response_bytes,
+            in_flight: Mutex::new(HashMap::new()),
+            next_operation_id: AtomicU64::new(0),
+            resource_subscribers: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+            subscribed_resources: Arc::new(Mutex::new(HashSet::new())),
+        })
+    }
+
+    /// Creates the handler of another session of the same server. Reconnecting does not reset
+    /// rate limits or the cap on heavy operations, and the journal, trash and watchers are those
+    /// of the server.
+    pub fn new_session(&self, args: &CommandArguments) -> Self {
+        Self {
+            fs_service: self.fs_service.clone(),
+            mcp_roots_support: self.mcp_roots_support,
+            tools: Self::tool_registry(args),
+            logger: Arc::new(McpLogger::default()),
+            metrics: self.metrics.clone(),
+            policy: self.policy.clone(),
+            limits: self.limits.clone(),
+            tool_timeout: self.tool_timeout,
+            max_response_bytes: self.max_response_bytes,
+            in_flight: Mutex::new(HashMap::new()),
+            next_operation_id: AtomicU64::new(0),
+            resource_subscribers: self.resource_subscribers.clone(),
+            subscribed_resources: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    /// Overrides the `--tool-timeout` of the handler.
+    pub fn with_tool_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.tool_timeout = timeout;
+        self
+    }
+
+    /// Returns the registry of the tools selected by `--tools`.
+    fn tool_registry(args: &CommandArguments) -> ToolRegistry {
         // Parse enabled tools from command arguments
         let enabled_tools = args.tools.as_ref().and_then(|tools_str| {
             let trimmed = tools_str.trim();
@@ -44,26 +224,55 @@ impl FileSystemHandler {
             }
         });
 
-        Ok(Self {
-            fs_service: Arc::new(fs_service),
-            readonly: !args.allow_write,
-            mcp_roots_support: args.enable_roots,
-            enabled_tools,
-        })
+        ToolRegistry::new(enabled_tools, args.enable_admin_tools)
     }
 
     pub fn assert_write_access(&self) -> std::result::Result<(), CallToolError> {
-        if self.readonly {
+        // tools that modify files stay available if any directory is writable,
+        // read-only directories are then enforced by the service
+        if self.fs_service.is_read_only() {
             Err(CallToolError::new(ServiceError::NoWriteAccess))
         } else {
             Ok(())
         }
     }
 
+    /// Checks that a tool call is permitted: the tool is enabled, the policy allows the call,
+    /// the server has write access if the tool needs it and the tool is within its rate limit.
+    fn check_tool_call(
+        &self,
+        tool_params: &FileSystemTools,
+    ) -> std::result::Result<(), CallToolError> {
+        let tool_name = tool_params.tool_name();
+        if !self.tools.is_enabled(&tool_name) {
+            return Err(CallToolError::new(ServiceError::ToolNotEnabled(tool_name)));
+        }
+
+        // Apply the per-tool policy, if configured
+        if let Some(policy) = &self.policy {
+            policy
+                .check(
+                    &tool_name,
+                    tool_params.require_write_access(),
+                    tool_params.path_arguments().as_deref(),
+                )
+                .map_err(CallToolError::new)?;
+        }
+
+        // Verify write access for tools that modify the file system
+        if tool_params.require_write_access() {
+            self.assert_write_access()?;
+        }
+
+        self.limits
+            .check_rate(&tool_name)
+            .map_err(CallToolError::new)
+    }
+
     pub async fn startup_message(&self) -> String {
         let common_message = format!(
             "Secure MCP Filesystem Server running in \"{}\" mode {} \"MCP Roots\" support.",
-            if !self.readonly {
+            if !self.fs_service.is_read_only() {
                 "read/write"
             } else {
                 "readonly"
@@ -83,7 +292,12 @@ impl FileSystemHandler {
                 "Allowed directories:\n{}",
                 allowed_directories
                     .iter()
-                    .map(|p| p.display().to_string())
+                    .map(|p| match self.fs_service.directory_mode(p) {
+                        DirectoryMode::ReadOnly if !self.fs_service.is_read_only() => {
+                            format!("{} (read-only)", p.display())
+                        }
+                        _ => p.display().to_string(),
+                    })
                     .collect::<Vec<String>>()
                     .join(",\n")
             )
@@ -92,6 +306,138 @@ impl FileSystemHandler {
         format!("{common_message}\n{sub_message}")
     }
 
+    /// Returns the progress token of a tool call, if the client asked for progress notifications.
+    /// The request `_meta` is not kept by the SDK, so the token is read from a `_meta` object
+    /// passed along with the tool arguments.
+    fn progress_token(request: &CallToolRequest) -> Option<ProgressToken> {
+        let token = request
+            .params
+            .arguments
+            .as_ref()?
+            .get("_meta")?
+            .get("progressToken")?;
+        serde_json::from_value(token.clone()).ok()
+    }
+
+    /// Returns the response size budget of a tool call: a `maxResponseBytes` value in the `_meta`
+    /// object of the tool arguments, which can only lower the server-wide `--max-response-bytes`.
+    fn max_response_bytes(&self, request: &CallToolRequest) -> Option<usize> {
+        let requested = request
+            .params
+            .arguments
+            .as_ref()
+            .and_then(|arguments| arguments.get("_meta")?.get("maxResponseBytes")?.as_u64())
+            .map(|max_bytes| usize::try_from(max_bytes).unwrap_or(usize::MAX));
+        match (requested, self.max_response_bytes) {
+            (Some(requested), Some(server_max)) => Some(requested.min(server_max)),
+            (requested, server_max) => requested.or(server_max),
+        }
+    }
+
+    /// Creates the context of a tool call, sending its progress updates to the client as
+    /// progress notifications when a progress token was provided.
+    pub(crate) fn operation_context(
+        request: &CallToolRequest,
+        runtime: Arc<dyn McpServer>,
+    ) -> OperationContext {
+        let Some(progress_token) = Self::progress_token(request) else {
+            return OperationContext::default();
+        };
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            // ends once the operation context, and with it the sender, is dropped
+            while let Some(update) = rx.recv().await {
+                let notification = ProgressNotification::new(update);
+                if runtime
+                    .send_notification(notification.into())
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        OperationContext::default().with_progress(move |update| {
+            let _ = tx.send(ProgressNotificationParams {
+                message: update.message,
+                progress: update.progress,
+                progress_token: progress_token.clone(),
+                total: update.total,
+            });
+        })
+    }
+
+    /// Registers a tool call as in flight and returns the token that aborts it.
+    fn begin_operation(&self) -> (InFlightOperation<'_>, CancellationToken) {
+        let id = self.next_operation_id.fetch_add(1, AtomicOrdering::Relaxed);
+        let token = CancellationToken::new();
+        if let Ok(mut in_flight) = self.in_flight.lock() {
+            in_flight.insert(id, token.clone());
+        }
+        let guard = InFlightOperation {
+            registry: &self.in_flight,
+            id,
+        };
+        (guard, token)
+    }
+
+    /// Forwards change events of watched directories to the client as logging notifications.
+    /// Directory watches are server-wide, so every session receives the events of all of them.
+    /// The task only holds a weak reference to `runtime` and stops once the session has ended.
+    pub(crate) fn forward_file_changes(&self, runtime: Arc<dyn McpServer>) {
+        let mut events = self.fs_service.subscribe_file_changes();
+        let logger = self.logger.clone();
+        let runtime = Arc::downgrade(&runtime);
+        tokio::spawn(async move {
+            loop {
+                let (level, data) = match events.recv().await {
+                    Ok(event) => match serde_json::to_value(&event) {
+                        Ok(data) => (LoggingLevel::Info, data),
+                        Err(_) => continue,
+                    },
+                    Err(RecvError::Lagged(skipped)) => (
+                        LoggingLevel::Warning,
+                        format!("{skipped} file change events were dropped.").into(),
+                    ),
+                    Err(RecvError::Closed) => break,
+                };
+                let Some(runtime) = runtime.upgrade() else {
+                    break;
+                };
+                logger.log(&runtime, level, "file_watcher", data).await;
+            }
+        });
+    }
+
+    /// Notifies the client when a file it subscribed to as a resource changes. Updates of
+    /// resources subscribed to by other sessions only are skipped.
+    pub(crate) fn forward_resource_updates(&self, runtime: Arc<dyn McpServer>) {
+        let mut updates = self.fs_service.subscribe_resource_updates();
+        let subscribed_resources = self.subscribed_resources.clone();
+        let runtime = Arc::downgrade(&runtime);
+        tokio::spawn(async move {
+            loop {
+                let uri = match updates.recv().await {
+                    Ok(uri) => uri,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                };
+                if !subscribed_resources.lock().unwrap().contains(&uri) {
+                    continue;
+                }
+                let Some(runtime) = runtime.upgrade() else {
+                    break;
+                };
+                let params = ResourceUpdatedNotificationParams { uri };
+                if runtime.send_resource_updated(params).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
     pub(crate) async fn update_allowed_directories(&self, runtime: Arc<dyn McpServer>) {
         // return if roots_support is not enabled
         if !self.mcp_roots_support {
@@ -105,12 +451,14 @@ impl FileSystemHandler {
             if !allowed_directories.is_empty() {
                 // display message only if mcp_roots_support is enabled, otherwise this message will be redundant
                 if self.mcp_roots_support {
-                    let _ = runtime.stderr_message("Client does not support MCP Roots. Allowed directories passed from command-line will be used.".to_string()).await;
+                    self.logger.log(&runtime, LoggingLevel::Info, "roots", "Client does not support MCP Roots. Allowed directories passed from command-line will be used.").await;
                 }
             } else {
                 // root lists not supported AND allowed directories are empty
                 let message = "Server cannot operate: No allowed directories available. Server was started without command-line directories and client does not support MCP roots protocol. Please either: 1) Start server with directory arguments, or 2) Use a client that supports MCP roots protocol and provides valid root directories.";
-                let _ = runtime.stderr_message(message.to_string()).await;
+                self.logger
+                    .log(&runtime, LoggingLevel::Critical, "roots", message)
+                    .await;
                 std::process::exit(1); // exit the server
             }
         } else {
@@ -132,7 +480,9 @@ impl FileSystemHandler {
                 match fs_service.valid_roots(roots) {
                     Ok((roots, skipped)) => {
                         if let Some(message) = skipped {
-                            let _ = runtime.stderr_message(message.to_string()).await;
+                            self.logger
+                                .log(&runtime, LoggingLevel::Warning, "roots", message)
+                                .await;
                         }
                         roots
                     }
@@ -141,19 +491,27 @@ impl FileSystemHandler {
             };
 
             if valid_roots.is_empty() {
-                let message = if allowed_directories.is_empty() {
-                    "Server cannot operate: No allowed directories available. Server was started without command-line directories and client provided empty roots. Please either: 1) Start server with directory arguments, or 2) Use a client that supports MCP roots protocol and provides valid root directories."
+                let (level, message) = if allowed_directories.is_empty() {
+                    (
+                        LoggingLevel::Error,
+                        "Server cannot operate: No allowed directories available. Server was started without command-line directories and client provided empty roots. Please either: 1) Start server with directory arguments, or 2) Use a client that supports MCP roots protocol and provides valid root directories.",
+                    )
                 } else {
-                    "Client provided empty roots. Allowed directories passed from command-line will be used."
+                    (
+                        LoggingLevel::Warning,
+                        "Client provided empty roots. Allowed directories passed from command-line will be used.",
+                    )
                 };
-                let _ = runtime.stderr_message(message.to_string()).await;
+                self.logger.log(&runtime, level, "roots", message).await;
             } else {
                 let num_valid_roots = valid_roots.len();
                 fs_service.update_allowed_paths(valid_roots).await;
                 let message = format!(
                     "Updated allowed directories from MCP roots: {num_valid_roots} valid directories",
                 );
-                let _ = runtime.stderr_message(message.to_string()).await;
+                self.logger
+                    .log(&runtime, LoggingLevel::Info, "roots", message)
+                    .await;
             }
         }
     }
@@ -161,7 +519,16 @@ impl FileSystemHandler {
 #[async_trait]
 impl ServerHandler for FileSystemHandler {
     async fn on_initialized(&self, runtime: Arc<dyn McpServer>) {
-        let _ = runtime.stderr_message(self.startup_message().await).await;
+        self.logger
+            .log(
+                &runtime,
+                LoggingLevel::Info,
+                "server",
+                self.startup_message().await,
+            )
+            .await;
+        self.forward_file_changes(runtime.clone());
+        self.forward_resource_updates(runtime.clone());
         self.update_allowed_directories(runtime).await;
     }
 
@@ -174,9 +541,10 @@ impl ServerHandler for FileSystemHandler {
             self.update_allowed_directories(runtime).await;
         } else {
             let message =
-                "Skipping ROOTS client updates, server launched without the --enable-roots flag."
-                    .to_string();
-            let _ = runtime.stderr_message(message).await;
+                "Skipping ROOTS client updates, server launched without the --enable-roots flag.";
+            self.logger
+                .log(&runtime, LoggingLevel::Notice, "roots", message)
+                .await;
         };
         Ok(())
     }
@@ -188,15 +556,12 @@ impl ServerHandler for FileSystemHandler {
     ) -> std::result::Result<ListToolsResult, RpcError> {
         let all_tools = FileSystemTools::tools();
 
-        // Filter tools based on enabled_tools configuration
-        let filtered_tools = if let Some(enabled) = &self.enabled_tools {
-            all_tools
-                .into_iter()
-                .filter(|tool| enabled.contains(&tool.name.to_lowercase()))
-                .collect()
-        } else {
-            all_tools
-        };
+        // Filter tools based on the tools enabled at this time
+        let filtered_tools = all_tools
+            .into_iter()
+            .filter(|tool| self.tools.is_enabled(&tool.name))
+            .map(with_meta_argument)
+            .collect();
 
         Ok(ListToolsResult {
             tools: filtered_tools,
@@ -205,6 +570,195 @@ impl ServerHandler for FileSystemHandler {
         })
     }
 
+    async fn handle_list_resources_request(
+        &self,
+        request: ListResourcesRequest,
+        _: Arc<dyn McpServer>,
+    ) -> std::result::Result<ListResourcesResult, RpcError> {
+        let offset = match request.params.and_then(|params| params.cursor) {
+            Some(cursor) => {
+                decode_cursor::<ResourceListCursor>(&cursor)
+                    .map_err(|err| RpcError::invalid_params().with_message(format!("{err}")))?
+                    .offset
+            }
+            None => 0,
+        };
+
+        let (files, next_offset) = self
+            .fs_service
+            .list_file_resources(offset, RESOURCE_PAGE_SIZE)
+            .await
+            .map_err(|err| RpcError::internal_error().with_message(format!("{err}")))?;
+
+        let next_cursor = next_offset
+            .map(|offset| encode_cursor(&ResourceListCursor { offset }))
+            .transpose()
+            .map_err(|err| RpcError::internal_error().with_message(format!("{err}")))?;
+
+        Ok(ListResourcesResult {
+            resources: files
+                .into_iter()
+                .map(|file| Resource {
+                    annotations: None,
+                    description: None,
+                    meta: None,
+                    mime_type: None,
+                    name: file.name,
+                    size: i64::try_from(file.size).ok(),
+                    title: None,
+                    uri: file.uri,
+                })
+                .collect(),
+            meta: None,
+            next_cursor,
+        })
+    }
+
+    async fn handle_list_resource_templates_request(
+        &self,
+        _: ListResourceTemplatesRequest,
+        _: Arc<dyn McpServer>,
+    ) -> std::result::Result<ListResourceTemplatesResult, RpcError> {
+        let allowed_directories = self.fs_service.allowed_directories().await;
+        Ok(ListResourceTemplatesResult {
+            resource_templates: allowed_directories
+                .iter()
+                .map(|dir| ResourceTemplate {
+                    annotations: None,
+                    description: Some(format!("Files within {}", dir.display())),
+                    meta: None,
+                    mime_type: None,
+                    name: dir
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_else(|| dir.display().to_string()),
+                    title: None,
+                    uri_template: format!(
+                        "{}/{{+path}}",
+                        path_to_file_uri(dir).trim_end_matches('/')
+                    ),
+                })
+                .collect(),
+            meta: None,
+            next_cursor: None,
+        })
+    }
+
+    async fn handle_read_resource_request(
+        &self,
+        request: ReadResourceRequest,
+        _: Arc<dyn McpServer>,
+    ) -> std::result::Result<ReadResourceResult, RpcError> {
+        let uri = request.params.uri;
+        let (mime_type, content) = self
+            .fs_service
+            .read_file_resource(&uri)
+            .await
+            .map_err(|err| RpcError::invalid_params().with_message(format!("{err}")))?;
+
+        let contents = match content {
+            ResourceContent::Text(text) => TextResourceContents {
+                meta: None,
+                mime_type: Some(mime_type),
+                text,
+                uri,
+            }
+            .into(),
+            ResourceContent::Blob(blob) => BlobResourceContents {
+                blob,
+                meta: None,
+                mime_type: Some(mime_type),
+                uri,
+            }
+            .into(),
+        };
+
+        Ok(ReadResourceResult {
+            contents: vec![contents],
+            meta: None,
+        })
+    }
+
+    async fn handle_subscribe_request(
+        &self,
+        request: SubscribeRequest,
+        _: Arc<dyn McpServer>,
+    ) -> std::result::Result<RpcResult, RpcError> {
+        let uri = request.params.uri;
+        let mut subscribers = self.resource_subscribers.lock().await;
+        if self.subscribed_resources.lock().unwrap().contains(&uri) {
+            return Ok(RpcResult::default());
+        }
+        if !subscribers.contains_key(&uri) {
+            self.fs_service
+                .subscribe_resource(&uri)
+                .await
+                .map_err(|err| RpcError::invalid_params().with_message(format!("{err}")))?;
+        }
+        *subscribers.entry(uri.clone()).or_default() += 1;
+        self.subscribed_resources.lock().unwrap().insert(uri);
+        Ok(RpcResult::default())
+    }
+
+    async fn handle_unsubscribe_request(
+        &self,
+        request: UnsubscribeRequest,
+        _: Arc<dyn McpServer>,
+    ) -> std::result::Result<RpcResult, RpcError> {
+        let uri = request.params.uri;
+        let mut subscribers = self.resource_subscribers.lock().await;
+        if !self.subscribed_resources.lock().unwrap().remove(&uri) {
+            return Ok(RpcResult::default());
+        }
+        release_resource(&self.fs_service, &mut subscribers, &uri)
+            .await
+            .map_err(|err| RpcError::invalid_params().with_message(format!("{err}")))?;
+        Ok(RpcResult::default())
+    }
+
+    /// The SDK does not pass the JSON-RPC id of a request to its handler, so `requestId` cannot be
+    /// matched against the running tool calls. The cancellation is applied only when exactly one
+    /// tool call is in flight, which is then necessarily the one being cancelled.
+    async fn handle_cancelled_notification(
+        &self,
+        notification: CancelledNotification,
+        runtime: Arc<dyn McpServer>,
+    ) -> std::result::Result<(), RpcError> {
+        let several_in_flight = {
+            let Ok(in_flight) = self.in_flight.lock() else {
+                return Ok(());
+            };
+            let mut tokens = in_flight.values();
+            match (tokens.next(), tokens.next()) {
+                (Some(token), None) => {
+                    token.cancel();
+                    false
+                }
+                (None, _) => false,
+                _ => true,
+            }
+        };
+        if several_in_flight {
+            let message = format!(
+                "Ignoring cancellation of request {:?}: several tool calls are in flight.",
+                notification.params.request_id
+            );
+            self.logger
+                .log(&runtime, LoggingLevel::Warning, "server", message)
+                .await;
+        }
+        Ok(())
+    }
+
+    async fn handle_set_level_request(
+        &self,
+        request: SetLevelRequest,
+        _: Arc<dyn McpServer>,
+    ) -> std::result::Result<RpcResult, RpcError> {
+        self.logger.set_level(request.params.level);
+        Ok(RpcResult::default())
+    }
+
     async fn handle_initialize_request(
         &self,
         initialize_request: InitializeRequest,
@@ -230,47 +784,270 @@ impl ServerHandler for FileSystemHandler {
     async fn handle_call_tool_request(
         &self,
         request: CallToolRequest,
-        _: Arc<dyn McpServer>,
+        runtime: Arc<dyn McpServer>,
     ) -> std::result::Result<CallToolResult, CallToolError> {
-        let tool_params: FileSystemTools =
-            FileSystemTools::try_from(request.params).map_err(CallToolError::new)?;
-
-        // Check if the tool is enabled
-        if let Some(enabled) = &self.enabled_tools {
-            let tool_name = tool_params.tool_name();
-            if !enabled.contains(&tool_name.to_lowercase()) {
-                return Err(CallToolError::new(ServiceError::ToolNotEnabled(tool_name)));
-            }
-        }
+        let (_in_flight, cancellation) = self.begin_operation();
+        Ok(self.call_tool(request, runtime, cancellation).await)
+    }
+}
 
-        // Verify write access for tools that modify the file system
-        if tool_params.require_write_access() {
-            self.assert_write_access()?;
+impl FileSystemHandler {
+    /// Checks and runs a tool call, logging it and recording its metrics. Steps of
+    /// `batch_execute` go through here as well, sharing the cancellation token of the batch.
+    /// A failure is returned as an error result carrying its [`ErrorCode`](crate::error::ErrorCode).
+    async fn call_tool(
+        &self,
+        request: CallToolRequest,
+        runtime: Arc<dyn McpServer>,
+        cancellation: CancellationToken,
+    ) -> CallToolResult {
+        let admin = ToolAdminContext {
+            registry: &self.tools,
+            runtime: runtime.clone(),
+        };
+        let batch = BatchExecutor {
+            handler: self,
+            runtime: runtime.clone(),
+            cancellation: cancellation.clone(),
+        };
+        let operation = Self::operation_context(&request, runtime.clone())
+            .with_cancellation(cancellation.clone());
+        let request_arguments = request.params.arguments.clone();
+        let max_response_bytes = self.max_response_bytes(&request);
+        let tool_params = match FileSystemTools::try_from(request.params) {
+            Ok(tool_params) => tool_params,
+            Err(err) => return ToolFailure::from(&err).into(),
+        };
+
+        let tool_name = tool_params.tool_name();
+        let modifies_files = tool_params.require_write_access();
+        let bytes_written = if modifies_files {
+            written_size(request_arguments.as_ref())
+        } else {
+            0
+        };
+        if let Err(err) = self.check_tool_call(&tool_params) {
+            self.metrics.record(&tool_name, true, 0, 0);
+            return ToolFailure::from(&err).into();
         }
+        let _heavy_permit = if tool_params.is_heavy() {
+            match self.limits.acquire_heavy() {
+                Ok(permit) => permit,
+                Err(err) => {
+                    self.metrics.record(&tool_name, true, 0, 0);
+                    return ToolFailure::from(&CallToolError::new(err)).into();
+                }
+            }
+        } else {
+            None
+        };
+
+        self.logger
+            .log(
+                &runtime,
+                LoggingLevel::Debug,
+                "tools",
+                json!({ "tool": tool_name, "status": "started" }),
+            )
+            .await;
+        let started = Instant::now();
 
-        invoke_tools!(
+        let invocation = async {
+            invoke_tools!(
             tool_params,
             &self.fs_service,
+            with_context(&operation)[SearchFilesContent, SearchCodeAst, FindDuplicateFiles],
+            with_context(&admin)[ConfigureTools],
+            with_context(&self.metrics)[GetServerStats],
+            with_context(&batch)[BatchExecute];
             ReadMediaFile,
             ReadMultipleMediaFiles,
             ReadTextFile,
             ReadMultipleTextFiles,
             WriteFile,
             EditFile,
+            ApplyEditsBatch,
             CreateDirectory,
+            CloneDirectoryStructure,
             ListDirectory,
             DirectoryTree,
             MoveFile,
             SearchFiles,
             GetFileInfo,
             ListAllowedDirectories,
-            SearchFilesContent,
-            SearchCodeAst,
             ListDirectoryWithSizes,
             ReadFileLines,
+            ReadFileChunk,
+            ReadAroundMatch,
             FindEmptyDirectories,
+            FindBrokenSymlinks,
             CalculateDirectorySize,
-            FindDuplicateFiles
-        )
+            FuzzyFindFile,
+            IndexDirectory,
+            ReadJsonPath,
+            ReadStructuredFile,
+            PreviewCsv,
+            GetMediaInfo,
+            ReadDocumentText,
+            GetMarkdownOutline,
+            GetCodeOutline,
+            RewriteCodeAst,
+            GitDiff,
+            GitBlame,
+            GitFileHistory,
+            WatchDirectory,
+            UnwatchDirectory,
+            RestoreFromTrash,
+            UndoLastOperation,
+            TailFile,
+            ListExtendedAttributes,
+            GetExtendedAttribute,
+            SetExtendedAttribute,
+            DirectoryStats,
+            CollectTodos,
+            SearchArchiveContent,
+            CreateBaseline,
+            CompareBaseline,
+            GetDiskSpace,
+            CreateTempPath,
+            CopyFile,
+            CleanupOldFiles
+            )
+        };
+        let result = match self.tool_timeout {
+            None => invocation.await,
+            Some(timeout) => {
+                // cancelling the operation also stops walkers that block the runtime thread,
+                // which the timeout alone cannot interrupt. The timer waits on the blocking pool:
+                // a task spawned from this worker could not run until the walker returns.
+                let deadline_started = Instant::now();
+                let (call_finished, finished) = std::sync::mpsc::channel::<()>();
+                tokio::task::spawn_blocking(move || {
+                    if let Err(RecvTimeoutError::Timeout) = finished.recv_timeout(timeout) {
+                        cancellation.cancel();
+                    }
+                });
+                // tools that modify files only stop where they check the cancellation: dropping
+                // them midway could leave a batch half-applied or a move half-copied
+                let result = if modifies_files {
+                    Ok(invocation.await)
+                } else {
+                    tokio::time::timeout(timeout, invocation).await
+                };
+                let timed_out = deadline_started.elapsed() >= timeout;
+                drop(call_finished);
+                // past the deadline, a result is usually the cancelled or partial outcome of the
+                // stopped operation; changes that were completed are still reported as such
+                match result {
+                    Ok(Ok(result))
+                        if !timed_out
+                            || (modifies_files && !result.is_error.unwrap_or_default()) =>
+                    {
+                        Ok(result)
+                    }
+                    Ok(Err(err)) if !timed_out => Err(err),
+                    _ => Err(CallToolError::new(ServiceError::Timeout(
+                        tool_name.clone(),
+                        timeout.as_secs(),
+                    ))),
+                }
+            }
+        };
+
+        let result = match max_response_bytes {
+            Some(max_bytes) => result.map(|result| limit_response_size(result, max_bytes)),
+            None => result,
+        };
+
+        // `CallToolError` is not `Send`, so a failure is kept as a `ToolFailure` while logging.
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        let result = result.map_err(|err| ToolFailure::from(&err));
+        match &result {
+            Ok(result) if !result.is_error.unwrap_or_default() => {
+                let bytes_read = if bytes_written == 0 {
+                    result_size(result)
+                } else {
+                    0
+                };
+                self.metrics
+                    .record(&tool_name, false, bytes_read, bytes_written);
+            }
+            _ => self.metrics.record(&tool_name, true, 0, 0),
+        }
+        let (level, data) = match &result {
+            Ok(_) => (
+                LoggingLevel::Info,
+                json!({ "tool": tool_name, "status": "completed", "elapsed_ms": elapsed_ms }),
+            ),
+            Err(failure) => (
+                LoggingLevel::Warning,
+                json!({
+                    "tool": tool_name,
+                    "status": "failed",
+                    "elapsed_ms": elapsed_ms,
+                    "error": failure.message,
+                    "code": failure.code,
+                }),
+            ),
+        };
+        self.logger.log(&runtime, level, "tools", data).await;
+        result.unwrap_or_else(CallToolResult::from)
+    }
+}
+
+/// Runs the steps of `batch_execute` through [`FileSystemHandler::call_tool`].
+struct BatchExecutor<'a> {
+    handler: &'a FileSystemHandler,
+    runtime: Arc<dyn McpServer>,
+    cancellation: CancellationToken,
+}
+
+#[async_trait]
+impl ToolExecutor for BatchExecutor<'_> {
+    async fn execute(
+        &self,
+        tool: String,
+        arguments: Option<serde_json::Map<String, serde_json::Value>>,
+    ) -> std::result::Result<CallToolResult, String> {
+        let request = CallToolRequest::new(CallToolRequestParams {
+            arguments,
+            name: tool,
+        });
+        Ok(self
+            .handler
+            .call_tool(request, self.runtime.clone(), self.cancellation.clone())
+            .await)
     }
+
+    fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+}
+
+/// Adds the `_meta` argument read by the handler to the input schema of `tool`. The SDK does not
+/// keep the `_meta` of requests, so progress tokens and response budgets are accepted among the
+/// tool arguments instead.
+fn with_meta_argument(mut tool: Tool) -> Tool {
+    let serde_json::Value::Object(meta) = json!({
+        "type": "object",
+        "description": "Metadata of the call, not passed to the tool.",
+        "properties": {
+            "progressToken": {
+                "type": ["string", "integer"],
+                "description": "Token of the progress notifications sent while the tool runs."
+            },
+            "maxResponseBytes": {
+                "type": "integer",
+                "minimum": 0,
+                "description": "Maximum size in bytes of the returned content, at most the server's limit. Larger responses are truncated and flagged with '_meta.truncated'."
+            }
+        }
+    }) else {
+        return tool;
+    };
+    tool.input_schema
+        .properties
+        .get_or_insert_with(HashMap::new)
+        .insert("_meta".to_string(), meta);
+    tool
 }
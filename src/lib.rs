@@ -2,6 +2,9 @@ pub mod cli;
 pub mod error;
 pub mod fs_service;
 pub mod handler;
+pub mod limits;
+pub mod logging;
 pub mod macros;
+pub mod policy;
 pub mod server;
 pub mod tools;
@@ -0,0 +1,75 @@
+use crate::error::{ServiceError, ServiceResult};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Limits on tool calls that protect shared machines from clients issuing calls in a tight loop:
+/// a cap on heavy operations (searches, hashing, directory walks) running at the same time and a
+/// cap on the calls of each tool within a minute.
+#[derive(Default)]
+pub struct ToolLimits {
+    max_concurrent_heavy: Option<usize>,
+    heavy_operations: Option<Arc<Semaphore>>,
+    max_calls_per_minute: Option<usize>,
+    /// Start times of the recent calls of each tool, oldest first.
+    recent_calls: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl ToolLimits {
+    /// Creates the limits, `None` leaving the corresponding dimension unlimited.
+    pub fn new(max_concurrent_heavy: Option<usize>, max_calls_per_minute: Option<usize>) -> Self {
+        Self {
+            max_concurrent_heavy,
+            heavy_operations: max_concurrent_heavy.map(|max| Arc::new(Semaphore::new(max))),
+            max_calls_per_minute,
+            recent_calls: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records a call of `tool_name`, failing if the tool was already called
+    /// `max_calls_per_minute` times within the last minute.
+    pub fn check_rate(&self, tool_name: &str) -> ServiceResult<()> {
+        let Some(max_calls) = self.max_calls_per_minute else {
+            return Ok(());
+        };
+        let Ok(mut recent_calls) = self.recent_calls.lock() else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        let calls = recent_calls.entry(tool_name.to_string()).or_default();
+        while calls
+            .front()
+            .is_some_and(|started| now.duration_since(*started) >= RATE_WINDOW)
+        {
+            calls.pop_front();
+        }
+        if calls.len() >= max_calls {
+            let retry_after = calls.front().map_or(RATE_WINDOW, |oldest| {
+                RATE_WINDOW.saturating_sub(now.duration_since(*oldest))
+            });
+            return Err(ServiceError::RateLimited(format!(
+                "tool '{tool_name}' was called {max_calls} times within the last minute. Retry in {} seconds.",
+                retry_after.as_secs().max(1)
+            )));
+        }
+        calls.push_back(now);
+        Ok(())
+    }
+
+    /// Reserves a slot for a heavy operation, held until the returned permit is dropped.
+    /// Fails right away instead of queueing the call when every slot is taken.
+    pub fn acquire_heavy(&self) -> ServiceResult<Option<OwnedSemaphorePermit>> {
+        let Some(semaphore) = &self.heavy_operations else {
+            return Ok(None);
+        };
+        semaphore.clone().try_acquire_owned().map(Some).map_err(|_| {
+            ServiceError::RateLimited(format!(
+                "{} heavy operations (searches, duplicate detection, directory walks) are already running. Retry once one of them has completed.",
+                self.max_concurrent_heavy.unwrap_or_default()
+            ))
+        })
+    }
+}
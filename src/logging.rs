@@ -0,0 +1,82 @@
+use rust_mcp_sdk::McpServer;
+use rust_mcp_sdk::schema::{LoggingLevel, LoggingMessageNotificationParams};
+use serde_json::{Value, json};
+use std::sync::{Arc, RwLock};
+
+/// Severity of a logging level, from `debug` (0) to `emergency` (7), as in RFC 5424.
+fn severity(level: &LoggingLevel) -> u8 {
+    match level {
+        LoggingLevel::Debug => 0,
+        LoggingLevel::Info => 1,
+        LoggingLevel::Notice => 2,
+        LoggingLevel::Warning => 3,
+        LoggingLevel::Error => 4,
+        LoggingLevel::Critical => 5,
+        LoggingLevel::Alert => 6,
+        LoggingLevel::Emergency => 7,
+    }
+}
+
+/// Sends log messages to the client as `notifications/message`, dropping those below the
+/// minimum level chosen by the client with `logging/setLevel`.
+pub struct McpLogger {
+    level: RwLock<LoggingLevel>,
+}
+
+impl Default for McpLogger {
+    fn default() -> Self {
+        Self::new(LoggingLevel::Info)
+    }
+}
+
+impl McpLogger {
+    pub fn new(level: LoggingLevel) -> Self {
+        Self {
+            level: RwLock::new(level),
+        }
+    }
+
+    pub fn level(&self) -> LoggingLevel {
+        self.level.read().map_or(LoggingLevel::Info, |level| *level)
+    }
+
+    pub fn set_level(&self, level: LoggingLevel) {
+        if let Ok(mut current) = self.level.write() {
+            *current = level;
+        }
+    }
+
+    /// Returns true if messages of `level` are sent to the client.
+    pub fn enabled(&self, level: &LoggingLevel) -> bool {
+        severity(level) >= severity(&self.level())
+    }
+
+    /// Sends `data` to the client if `level` is enabled. A plain string is wrapped into
+    /// `{"message": ...}` so that every message is a JSON object.
+    /// Messages of level `error` and above are also written to stderr.
+    pub async fn log(
+        &self,
+        runtime: &Arc<dyn McpServer>,
+        level: LoggingLevel,
+        logger: &str,
+        data: impl Into<Value>,
+    ) {
+        let data = match data.into() {
+            Value::String(message) => json!({ "message": message }),
+            data => data,
+        };
+        if severity(&level) >= severity(&LoggingLevel::Error) {
+            let _ = runtime.stderr_message(data.to_string()).await;
+        }
+        if !self.enabled(&level) {
+            return;
+        }
+        let _ = runtime
+            .send_logging_message(LoggingMessageNotificationParams {
+                data,
+                level,
+                logger: Some(logger.to_string()),
+            })
+            .await;
+    }
+}
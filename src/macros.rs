@@ -13,13 +13,13 @@
 /// # Usage
 /// The macro is typically used within a method that dispatches filesystem operations based on a `FileSystemTools` enum.
 /// Each tool must have a `run_tool` method with the signature:
-/// ```rust
+/// ```rust,ignore
 /// async fn run_tool(params: ParamsType, fs_service: &FsService) -> ServiceResult<()>
 /// ```
 /// where `ParamsType` is the parameter type for the specific tool, and `FsService` is the filesystem service type.
 ///
 /// # Example
-/// ```rust
+/// ```rust,ignore
 /// match_filesystem_tools!(
 ///     tool_params,
 ///     &self.fs_service,
@@ -28,13 +28,27 @@
 /// )
 /// ```
 /// This expands to:
-/// ```rust
+/// ```rust,ignore
 /// match tool_params {
 ///     FileSystemTools::ReadMediaFileTool(params) => ReadMediaFileTool::run_tool(params, &self.fs_service).await,
 ///     FileSystemTools::WriteFileTool(params) => WriteFileTool::run_tool(params, &self.fs_service).await,
 /// }
 /// ```
 ///
+/// Tools that need more than the filesystem service can be listed in leading `with_context(...) [...]` groups;
+/// they are dispatched to `run_tool_with_context`, which additionally receives the group's context
+/// (e.g. the `OperationContext` of tools that report progress or can be cancelled). The groups are
+/// separated by commas and followed by a semicolon:
+/// ```rust,ignore
+/// invoke_tools!(
+///     tool_params,
+///     &self.fs_service,
+///     with_context(&operation) [SearchFilesContent],
+///     with_context(&admin) [ConfigureTools];
+///     WriteFileTool
+/// )
+/// ```
+///
 /// # Notes
 /// - Ensure each tool identifier matches a variant of the `FileSystemTools` enum and has a corresponding `run_tool` method.
 /// - The macro assumes all `run_tool` methods are `async` and return `ServiceResult<()>`.
@@ -49,4 +63,18 @@ macro_rules! invoke_tools {
             )*
         }
     };
+    ($params:expr, $fs_service:expr, $(with_context($context:expr) [$($context_tool:ident),* $(,)?]),+; $($tool:ident),* $(,)?) => {
+        match $params {
+            $(
+                $(
+                    FileSystemTools::$context_tool(params) => {
+                        $context_tool::run_tool_with_context(params, $fs_service, $context).await
+                    }
+                )*
+            )+
+            $(
+                FileSystemTools::$tool(params) => $tool::run_tool(params, $fs_service).await,
+            )*
+        }
+    };
 }
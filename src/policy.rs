@@ -0,0 +1,157 @@
+use crate::error::{ServiceError, ServiceResult};
+use crate::fs_service::utils::{self, expand_home};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// What a tool is allowed to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolAccess {
+    Allow,
+    Deny,
+    /// The tool may only be called if the call does not modify the file system.
+    Readonly,
+}
+
+/// Policy of a single tool.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ToolPolicy {
+    pub access: ToolAccess,
+    /// If set, every path argument of the tool must be inside one of these directories.
+    #[serde(default)]
+    pub paths: Option<Vec<PathBuf>>,
+}
+
+/// Per-tool permissions loaded from a policy file, e.g.
+///
+/// ```toml
+/// default = "allow"
+///
+/// [tools.write_file]
+/// access = "allow"
+/// paths = ["/workspace/out"]
+///
+/// [tools.move_file]
+/// access = "deny"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct ToolPolicies {
+    /// Access of tools that are not listed in `tools`.
+    #[serde(default = "default_access")]
+    pub default: ToolAccess,
+    #[serde(default)]
+    pub tools: HashMap<String, ToolPolicy>,
+}
+
+fn default_access() -> ToolAccess {
+    ToolAccess::Allow
+}
+
+impl ToolPolicies {
+    /// Loads a policy file, parsed as JSON if it has a `.json` extension and as TOML otherwise.
+    pub fn load(path: &Path) -> ServiceResult<Self> {
+        let content = std::fs::read_to_string(path).map_err(|err| {
+            ServiceError::FromString(format!(
+                "Failed to read policy file '{}': {err}",
+                path.display()
+            ))
+        })?;
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let mut policies: Self = if is_json {
+            serde_json::from_str(&content).map_err(|err| err.to_string())
+        } else {
+            toml::from_str(&content).map_err(|err| err.to_string())
+        }
+        .map_err(|err| {
+            ServiceError::FromString(format!("Invalid policy file '{}': {err}", path.display()))
+        })?;
+
+        policies.tools = policies
+            .tools
+            .into_iter()
+            .map(|(name, mut policy)| {
+                policy.paths = policy
+                    .paths
+                    .map(|paths| paths.into_iter().map(resolve_path).collect());
+                (name.to_lowercase(), policy)
+            })
+            .collect();
+        Ok(policies)
+    }
+
+    /// Checks whether a call of `tool_name` with the given path arguments is permitted.
+    /// `path_arguments` is `None` for calls that access paths not named in their arguments,
+    /// which are denied if the tool is restricted to `paths`.
+    pub fn check(
+        &self,
+        tool_name: &str,
+        requires_write: bool,
+        path_arguments: Option<&[String]>,
+    ) -> ServiceResult<()> {
+        let policy = self.tools.get(&tool_name.to_lowercase());
+        let access = policy.map_or(self.default, |policy| policy.access);
+        match access {
+            ToolAccess::Deny => {
+                return Err(ServiceError::PolicyDenied(format!(
+                    "tool '{tool_name}' is denied."
+                )));
+            }
+            ToolAccess::Readonly if requires_write => {
+                return Err(ServiceError::PolicyDenied(format!(
+                    "tool '{tool_name}' is restricted to read-only use."
+                )));
+            }
+            _ => {}
+        }
+
+        let Some(allowed_paths) = policy.and_then(|policy| policy.paths.as_ref()) else {
+            return Ok(());
+        };
+        let Some(path_arguments) = path_arguments else {
+            return Err(ServiceError::PolicyDenied(format!(
+                "tool '{tool_name}' is restricted to some paths, but this call accesses paths \
+                 that are not in its arguments."
+            )));
+        };
+        for path in path_arguments {
+            let resolved = resolve_path(PathBuf::from(path));
+            if !allowed_paths.iter().any(|dir| resolved.starts_with(dir)) {
+                return Err(ServiceError::PolicyDenied(format!(
+                    "tool '{tool_name}' may not access '{path}'. Allowed paths: {}",
+                    allowed_paths
+                        .iter()
+                        .map(|p| p.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Makes `path` absolute and resolves `..` lexically, so that paths that do not exist yet cannot
+/// escape a directory through parent components. Symlinks are resolved in the longest existing
+/// ancestor of the path.
+fn resolve_path(path: PathBuf) -> PathBuf {
+    let path = expand_home(path);
+    let absolute = if path.is_absolute() {
+        path
+    } else {
+        std::env::current_dir().unwrap_or_default().join(path)
+    };
+    let mut resolved = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::CurDir => {}
+            component => resolved.push(component),
+        }
+    }
+    utils::resolve_path(&resolved)
+}
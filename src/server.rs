@@ -1,9 +1,16 @@
 use rust_mcp_sdk::schema::{
     Implementation, InitializeResult, LATEST_PROTOCOL_VERSION, ServerCapabilities,
-    ServerCapabilitiesTools,
+    ServerCapabilitiesResources, ServerCapabilitiesTools,
 };
 use rust_mcp_sdk::{McpServer, StdioTransport, TransportOptions, mcp_server::server_runtime};
 
+#[cfg(unix)]
+mod unix_socket;
+
+#[cfg(unix)]
+pub use unix_socket::{UnixSocketTransport, bind_socket};
+
+use crate::cli::TransportKind;
 use crate::handler::FileSystemHandler;
 use crate::{cli::CommandArguments, error::ServiceResult};
 
@@ -16,10 +23,15 @@ pub fn server_details() -> InitializeResult {
         },
         capabilities: ServerCapabilities {
             experimental: None,
-            logging: None,
+            logging: Some(serde_json::Map::new()),
             prompts: None,
-            resources: None,
-            tools: Some(ServerCapabilitiesTools { list_changed: None }),
+            resources: Some(ServerCapabilitiesResources {
+                list_changed: None,
+                subscribe: Some(true),
+            }),
+            tools: Some(ServerCapabilitiesTools {
+                list_changed: Some(true),
+            }),
             completions: None,
         },
         instructions: None,
@@ -29,6 +41,18 @@ pub fn server_details() -> InitializeResult {
 }
 
 pub async fn start_server(args: CommandArguments) -> ServiceResult<()> {
+    match args.transport {
+        TransportKind::Stdio => start_stdio_server(args).await,
+        #[cfg(unix)]
+        TransportKind::Unix => start_unix_socket_server(args).await,
+        #[cfg(not(unix))]
+        TransportKind::Unix => Err(crate::error::ServiceError::FromString(
+            "The `unix` transport is only available on Unix platforms.".to_string(),
+        )),
+    }
+}
+
+async fn start_stdio_server(args: CommandArguments) -> ServiceResult<()> {
     let transport = StdioTransport::new(TransportOptions::default())?;
 
     let handler = FileSystemHandler::new(&args)?;
@@ -38,3 +62,38 @@ pub async fn start_server(args: CommandArguments) -> ServiceResult<()> {
 
     Ok(())
 }
+
+/// Listens on the Unix domain socket given by `--socket` and serves every accepted connection
+/// as a separate MCP session. The sessions share the service, rate limits and statistics of the
+/// server.
+#[cfg(unix)]
+async fn start_unix_socket_server(args: CommandArguments) -> ServiceResult<()> {
+    let socket_path = args.socket.as_deref().ok_or_else(|| {
+        crate::error::ServiceError::FromString(
+            "`--socket` is required when `--transport unix` is used.".to_string(),
+        )
+    })?;
+    let listener = bind_socket(std::path::Path::new(socket_path), args.socket_mode)?;
+    eprintln!("Listening on Unix domain socket {socket_path}");
+
+    let handler = FileSystemHandler::new(&args)?;
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(error) => {
+                // e.g. the process ran out of file descriptors, which later connections may not
+                eprintln!("Unable to accept a connection: {error}");
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        let handler = handler.new_session(&args);
+        let transport = UnixSocketTransport::new(stream, TransportOptions::default());
+        let server = server_runtime::create_server(server_details(), transport, handler);
+        tokio::spawn(async move {
+            if let Err(error) = server.start().await {
+                eprintln!("{error}");
+            }
+        });
+    }
+}
@@ -0,0 +1,251 @@
+use async_trait::async_trait;
+use rust_mcp_sdk::schema::RequestId;
+use rust_mcp_sdk::schema::schema_utils::{
+    ClientMessage, ClientMessages, MessageFromServer, SdkError, ServerMessage, ServerMessages,
+};
+use rust_mcp_sdk::{
+    IoStream, McpDispatch, MessageDispatcher, SessionId, Transport, TransportDispatcher,
+    TransportError, TransportOptions, TransportResult,
+};
+use std::collections::HashMap;
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{Mutex, Notify, RwLock, oneshot};
+use tokio::task::JoinHandle;
+
+const CHANNEL_CAPACITY: usize = 36;
+
+/// Binds a Unix domain socket at `path` with the given permission bits.
+///
+/// An existing socket is only replaced if no server accepts connections on it anymore; any other
+/// existing file is an error. The socket is bound in a private directory and moved to `path` once
+/// its permissions are set, so that it is never reachable with looser ones.
+pub fn bind_socket(path: &Path, mode: u32) -> std::io::Result<UnixListener> {
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("'{}' exists and is not a socket", path.display()),
+            ));
+        }
+        match std::os::unix::net::UnixStream::connect(path) {
+            Ok(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AddrInUse,
+                    format!("'{}' is in use by a running server", path.display()),
+                ));
+            }
+            // nothing listens on a socket left behind by a previous run
+            Err(err) if err.kind() == std::io::ErrorKind::ConnectionRefused => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    let file_name = path.file_name().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("'{}' is not a valid socket path", path.display()),
+        )
+    })?;
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let private_dir = parent.join(format!(
+        ".{}.{}",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+    std::fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&private_dir)?;
+
+    // kept short, socket paths are limited to about a hundred bytes
+    let staged_path = private_dir.join("s");
+    let bound = UnixListener::bind(&staged_path).and_then(|listener| {
+        std::fs::set_permissions(&staged_path, std::fs::Permissions::from_mode(mode))?;
+        std::fs::rename(&staged_path, path)?;
+        Ok(listener)
+    });
+    let _ = std::fs::remove_file(&staged_path);
+    let _ = std::fs::remove_dir(&private_dir);
+    bound
+}
+
+/// Serves one MCP session over a connected Unix domain socket, using newline-delimited
+/// JSON-RPC messages just like the stdio transport.
+pub struct UnixSocketTransport {
+    stream: Mutex<Option<UnixStream>>,
+    options: TransportOptions,
+    shutdown: Arc<Notify>,
+    is_shut_down: Mutex<bool>,
+    message_sender: Arc<RwLock<Option<MessageDispatcher<ClientMessage>>>>,
+    error_stream: RwLock<Option<IoStream>>,
+    pending_requests: Arc<Mutex<HashMap<RequestId, oneshot::Sender<ClientMessage>>>>,
+}
+
+impl UnixSocketTransport {
+    pub fn new(stream: UnixStream, options: TransportOptions) -> Self {
+        Self {
+            stream: Mutex::new(Some(stream)),
+            options,
+            shutdown: Arc::new(Notify::new()),
+            is_shut_down: Mutex::new(false),
+            message_sender: Arc::new(RwLock::new(None)),
+            error_stream: RwLock::new(None),
+            pending_requests: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+#[async_trait]
+impl Transport<ClientMessages, MessageFromServer, ClientMessage, ServerMessages, ServerMessage>
+    for UnixSocketTransport
+{
+    async fn start(&self) -> TransportResult<tokio_stream::wrappers::ReceiverStream<ClientMessages>>
+    where
+        MessageDispatcher<ClientMessage>:
+            McpDispatch<ClientMessages, ServerMessages, ClientMessage, ServerMessage>,
+    {
+        let stream = self.stream.lock().await.take().ok_or_else(|| {
+            TransportError::Internal("Unix socket transport already started".to_string())
+        })?;
+        let (read_half, write_half) = stream.into_split();
+
+        let (tx, rx) = tokio::sync::mpsc::channel::<ClientMessages>(CHANNEL_CAPACITY);
+        let shutdown = self.shutdown.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(read_half).lines();
+            loop {
+                tokio::select! {
+                    _ = shutdown.notified() => break,
+                    line = lines.next_line() => match line {
+                        Ok(Some(line)) => {
+                            // malformed messages are skipped, as with stdio
+                            let Ok(message) = serde_json::from_str::<ClientMessages>(&line) else {
+                                continue;
+                            };
+                            if tx.send(message).await.is_err() {
+                                break;
+                            }
+                        }
+                        // connection closed by the client
+                        Ok(None) | Err(_) => break,
+                    },
+                }
+            }
+        });
+
+        let sender = MessageDispatcher::new(
+            self.pending_requests.clone(),
+            Mutex::new(Box::pin(write_half)),
+            self.options.timeout,
+        );
+        *self.message_sender.write().await = Some(sender);
+        *self.error_stream.write().await = Some(IoStream::Writable(Box::pin(tokio::io::stderr())));
+
+        Ok(tokio_stream::wrappers::ReceiverStream::new(rx))
+    }
+
+    fn message_sender(&self) -> Arc<RwLock<Option<MessageDispatcher<ClientMessage>>>> {
+        self.message_sender.clone()
+    }
+
+    fn error_stream(&self) -> &RwLock<Option<IoStream>> {
+        &self.error_stream
+    }
+
+    async fn shut_down(&self) -> TransportResult<()> {
+        self.shutdown.notify_one();
+        *self.is_shut_down.lock().await = true;
+        Ok(())
+    }
+
+    async fn is_shut_down(&self) -> bool {
+        *self.is_shut_down.lock().await
+    }
+
+    async fn consume_string_payload(&self, _payload: &str) -> TransportResult<()> {
+        Err(TransportError::Internal(
+            "Invalid invocation of consume_string_payload() function in UnixSocketTransport"
+                .to_string(),
+        ))
+    }
+
+    async fn pending_request_tx(
+        &self,
+        request_id: &RequestId,
+    ) -> Option<oneshot::Sender<ClientMessage>> {
+        self.pending_requests.lock().await.remove(request_id)
+    }
+
+    async fn keep_alive(
+        &self,
+        _interval: Duration,
+        _disconnect_tx: oneshot::Sender<()>,
+    ) -> TransportResult<JoinHandle<()>> {
+        Err(TransportError::Internal(
+            "Invalid invocation of keep_alive() function for UnixSocketTransport".to_string(),
+        ))
+    }
+
+    async fn session_id(&self) -> Option<SessionId> {
+        None
+    }
+}
+
+#[async_trait]
+impl McpDispatch<ClientMessages, ServerMessages, ClientMessage, ServerMessage>
+    for UnixSocketTransport
+{
+    async fn send_message(
+        &self,
+        message: ServerMessages,
+        request_timeout: Option<Duration>,
+    ) -> TransportResult<Option<ClientMessages>> {
+        let sender = self.message_sender.read().await;
+        let sender = sender.as_ref().ok_or(SdkError::connection_closed())?;
+        sender.send_message(message, request_timeout).await
+    }
+
+    async fn send(
+        &self,
+        message: ServerMessage,
+        request_timeout: Option<Duration>,
+    ) -> TransportResult<Option<ClientMessage>> {
+        let sender = self.message_sender.read().await;
+        let sender = sender.as_ref().ok_or(SdkError::connection_closed())?;
+        sender.send(message, request_timeout).await
+    }
+
+    async fn send_batch(
+        &self,
+        message: Vec<ServerMessage>,
+        request_timeout: Option<Duration>,
+    ) -> TransportResult<Option<Vec<ClientMessage>>> {
+        let sender = self.message_sender.read().await;
+        let sender = sender.as_ref().ok_or(SdkError::connection_closed())?;
+        sender.send_batch(message, request_timeout).await
+    }
+
+    async fn write_str(&self, payload: &str, skip_store: bool) -> TransportResult<()> {
+        let sender = self.message_sender.read().await;
+        let sender = sender.as_ref().ok_or(SdkError::connection_closed())?;
+        sender.write_str(payload, skip_store).await
+    }
+}
+
+impl
+    TransportDispatcher<
+        ClientMessages,
+        MessageFromServer,
+        ClientMessage,
+        ServerMessages,
+        ServerMessage,
+    > for UnixSocketTransport
+{
+}
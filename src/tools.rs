@@ -1,44 +1,123 @@
+mod apply_edits_batch;
+mod batch_execute;
 mod calculate_directory_size;
+mod cleanup_old_files;
+mod clone_directory_structure;
+mod collect_todos;
+mod compare_baseline;
+mod configure_tools;
+mod copy_file;
+mod create_baseline;
 mod create_directory;
+mod create_temp_path;
+mod directory_stats;
 mod directory_tree;
 mod edit_file;
+mod find_broken_symlinks;
 mod find_duplicate_files;
 mod find_empty_directories;
+mod fuzzy_find_file;
+mod get_code_outline;
+mod get_disk_space;
+mod get_extended_attribute;
 mod get_file_info;
+mod get_markdown_outline;
+mod get_media_info;
+mod get_server_stats;
+mod git_blame;
+mod git_diff;
+mod git_file_history;
+mod index_directory;
 mod list_allowed_directories;
 mod list_directory;
 mod list_directory_with_sizes;
+mod list_extended_attributes;
 mod move_file;
+mod pagination;
+mod preview_csv;
+mod read_around_match;
+mod read_document_text;
+mod read_file_chunk;
 mod read_file_lines;
+mod read_json_path;
 mod read_media_file;
 mod read_multiple_media_files;
 mod read_multiple_text_files;
+mod read_structured_file;
 mod read_text_file;
+mod response;
+mod restore_from_trash;
+mod rewrite_code_ast;
+mod search_archive_content;
 mod search_code_ast;
 mod search_file;
 mod search_files_content;
+mod set_extended_attribute;
+mod tail_file;
+mod undo_last_operation;
+mod unwatch_directory;
+mod watch_directory;
 mod write_file;
 
+pub use apply_edits_batch::{ApplyEditsBatch, FileEdits};
+pub use batch_execute::{BatchExecute, BatchStep, MAX_BATCH_STEPS, ToolArguments, ToolExecutor};
 pub use calculate_directory_size::{CalculateDirectorySize, FileSizeOutputFormat};
+pub use cleanup_old_files::CleanupOldFiles;
+pub use clone_directory_structure::CloneDirectoryStructure;
+pub use collect_todos::CollectTodos;
+pub use compare_baseline::CompareBaseline;
+pub use configure_tools::{ConfigureTools, ToolAdminContext, ToolRegistry};
+pub use copy_file::CopyFile;
+pub use create_baseline::CreateBaseline;
 pub use create_directory::CreateDirectory;
-pub use directory_tree::DirectoryTree;
-pub use edit_file::{EditFile, EditOperation};
+pub use create_temp_path::CreateTempPath;
+pub use directory_stats::DirectoryStats;
+pub use directory_tree::{DirectoryTree, TreeOutputFormat};
+pub use edit_file::{EditFile, EditOperation, MatchMode, Occurrence, OccurrenceMode};
+pub use find_broken_symlinks::FindBrokenSymlinks;
 pub use find_duplicate_files::FindDuplicateFiles;
 pub use find_empty_directories::FindEmptyDirectories;
+pub use fuzzy_find_file::FuzzyFindFile;
+pub use get_code_outline::GetCodeOutline;
+pub use get_disk_space::GetDiskSpace;
+pub use get_extended_attribute::GetExtendedAttribute;
 pub use get_file_info::GetFileInfo;
+pub use get_markdown_outline::GetMarkdownOutline;
+pub use get_media_info::GetMediaInfo;
+pub use get_server_stats::{GetServerStats, ServerMetrics, ToolStats, written_size};
+pub use git_blame::GitBlame;
+pub use git_diff::GitDiff;
+pub use git_file_history::GitFileHistory;
+pub use index_directory::IndexDirectory;
 pub use list_allowed_directories::ListAllowedDirectories;
 pub use list_directory::ListDirectory;
 pub use list_directory_with_sizes::ListDirectoryWithSizes;
+pub use list_extended_attributes::ListExtendedAttributes;
 pub use move_file::MoveFile;
+pub use preview_csv::PreviewCsv;
+pub use read_around_match::ReadAroundMatch;
+pub use read_document_text::ReadDocumentText;
+pub use read_file_chunk::ReadFileChunk;
 pub use read_file_lines::ReadFileLines;
+pub use read_json_path::ReadJsonPath;
 pub use read_media_file::ReadMediaFile;
 pub use read_multiple_media_files::ReadMultipleMediaFiles;
 pub use read_multiple_text_files::ReadMultipleTextFiles;
+pub use read_structured_file::ReadStructuredFile;
 pub use read_text_file::ReadTextFile;
+pub use response::{limit_response_size, result_size};
+pub use restore_from_trash::RestoreFromTrash;
+pub use rewrite_code_ast::RewriteCodeAst;
 pub use rust_mcp_sdk::tool_box;
-pub use search_code_ast::SearchCodeAst;
+pub use search_archive_content::SearchArchiveContent;
+pub use search_code_ast::{AstOutputMode, SearchCodeAst};
 pub use search_file::SearchFiles;
 pub use search_files_content::SearchFilesContent;
+pub use set_extended_attribute::{AttributeEncoding, SetExtendedAttribute};
+pub use tail_file::TailFile;
+pub use undo_last_operation::UndoLastOperation;
+pub use unwatch_directory::UnwatchDirectory;
+pub use watch_directory::WatchDirectory;
 pub use write_file::WriteFile;
 //Generate FileSystemTools enum , tools() function, and TryFrom<CallToolRequestParams> trait implementation
 tool_box!(
@@ -46,8 +125,10 @@ tool_box!(
     [
         ReadTextFile,
         CreateDirectory,
+        CloneDirectoryStructure,
         DirectoryTree,
         EditFile,
+        ApplyEditsBatch,
         GetFileInfo,
         ListAllowedDirectories,
         ListDirectory,
@@ -61,21 +142,179 @@ tool_box!(
         ReadMediaFile,
         ReadMultipleMediaFiles,
         ReadFileLines,
+        ReadFileChunk,
+        ReadAroundMatch,
         FindEmptyDirectories,
+        FindBrokenSymlinks,
         CalculateDirectorySize,
-        FindDuplicateFiles
+        FindDuplicateFiles,
+        FuzzyFindFile,
+        IndexDirectory,
+        ReadJsonPath,
+        ReadStructuredFile,
+        PreviewCsv,
+        GetMediaInfo,
+        ReadDocumentText,
+        GetMarkdownOutline,
+        GetCodeOutline,
+        RewriteCodeAst,
+        GitDiff,
+        GitBlame,
+        GitFileHistory,
+        WatchDirectory,
+        UnwatchDirectory,
+        TailFile,
+        RestoreFromTrash,
+        UndoLastOperation,
+        ListExtendedAttributes,
+        GetExtendedAttribute,
+        SetExtendedAttribute,
+        DirectoryStats,
+        CollectTodos,
+        SearchArchiveContent,
+        CreateBaseline,
+        CompareBaseline,
+        ConfigureTools,
+        GetServerStats,
+        GetDiskSpace,
+        CreateTempPath,
+        CopyFile,
+        CleanupOldFiles,
+        BatchExecute
     ]
 );
 
 impl FileSystemTools {
+    /// Returns `true` for tools that may walk or read large parts of the file system,
+    /// which are subject to the `--max-concurrent-heavy` limit.
+    pub fn is_heavy(&self) -> bool {
+        matches!(
+            self,
+            FileSystemTools::SearchFilesContent(_)
+                | FileSystemTools::SearchCodeAst(_)
+                | FileSystemTools::RewriteCodeAst(_)
+                | FileSystemTools::SearchFiles(_)
+                | FileSystemTools::FuzzyFindFile(_)
+                | FileSystemTools::FindDuplicateFiles(_)
+                | FileSystemTools::FindEmptyDirectories(_)
+                | FileSystemTools::FindBrokenSymlinks(_)
+                | FileSystemTools::CalculateDirectorySize(_)
+                | FileSystemTools::DirectoryStats(_)
+                | FileSystemTools::CollectTodos(_)
+                | FileSystemTools::SearchArchiveContent(_)
+                | FileSystemTools::CreateBaseline(_)
+                | FileSystemTools::CompareBaseline(_)
+                | FileSystemTools::CloneDirectoryStructure(_)
+                | FileSystemTools::CopyFile(_)
+                | FileSystemTools::CleanupOldFiles(_)
+                | FileSystemTools::DirectoryTree(_)
+                | FileSystemTools::IndexDirectory(_)
+        )
+    }
+
+    /// Returns the paths named in the arguments of the tool, checked against the `paths`
+    /// restriction of a tool policy. Returns `None` for calls that access paths which are not
+    /// in their arguments, e.g. every allowed directory or the paths recorded in the journal.
+    pub fn path_arguments(&self) -> Option<Vec<String>> {
+        let paths = match self {
+            FileSystemTools::ReadTextFile(params) => vec![params.path.clone()],
+            FileSystemTools::CreateDirectory(params) => vec![params.path.clone()],
+            FileSystemTools::DirectoryTree(params) => vec![params.path.clone()],
+            FileSystemTools::EditFile(params) => vec![params.path.clone()],
+            FileSystemTools::GetFileInfo(params) => vec![params.path.clone()],
+            FileSystemTools::ListDirectory(params) => vec![params.path.clone()],
+            FileSystemTools::SearchFiles(params) => vec![params.path.clone()],
+            FileSystemTools::SearchCodeAst(params) => vec![params.path.clone()],
+            FileSystemTools::WriteFile(params) => vec![params.path.clone()],
+            FileSystemTools::SearchFilesContent(params) => vec![params.path.clone()],
+            FileSystemTools::ListDirectoryWithSizes(params) => vec![params.path.clone()],
+            FileSystemTools::ReadMediaFile(params) => vec![params.path.clone()],
+            FileSystemTools::ReadFileLines(params) => vec![params.path.clone()],
+            FileSystemTools::ReadFileChunk(params) => vec![params.path.clone()],
+            FileSystemTools::ReadAroundMatch(params) => vec![params.path.clone()],
+            FileSystemTools::FindEmptyDirectories(params) => vec![params.path.clone()],
+            FileSystemTools::FindBrokenSymlinks(params) => vec![params.path.clone()],
+            FileSystemTools::FuzzyFindFile(params) => vec![params.path.clone()],
+            FileSystemTools::IndexDirectory(params) => vec![params.path.clone()],
+            FileSystemTools::ReadJsonPath(params) => vec![params.path.clone()],
+            FileSystemTools::ReadStructuredFile(params) => vec![params.path.clone()],
+            FileSystemTools::PreviewCsv(params) => vec![params.path.clone()],
+            FileSystemTools::GetMediaInfo(params) => vec![params.path.clone()],
+            FileSystemTools::ReadDocumentText(params) => vec![params.path.clone()],
+            FileSystemTools::GetMarkdownOutline(params) => vec![params.path.clone()],
+            FileSystemTools::GetCodeOutline(params) => vec![params.path.clone()],
+            FileSystemTools::RewriteCodeAst(params) => vec![params.path.clone()],
+            FileSystemTools::GitBlame(params) => vec![params.path.clone()],
+            FileSystemTools::GitFileHistory(params) => vec![params.path.clone()],
+            FileSystemTools::WatchDirectory(params) => vec![params.path.clone()],
+            FileSystemTools::UnwatchDirectory(params) => vec![params.path.clone()],
+            FileSystemTools::TailFile(params) => vec![params.path.clone()],
+            FileSystemTools::ListExtendedAttributes(params) => vec![params.path.clone()],
+            FileSystemTools::GetExtendedAttribute(params) => vec![params.path.clone()],
+            FileSystemTools::SetExtendedAttribute(params) => vec![params.path.clone()],
+            FileSystemTools::DirectoryStats(params) => vec![params.path.clone()],
+            FileSystemTools::CollectTodos(params) => vec![params.path.clone()],
+            FileSystemTools::SearchArchiveContent(params) => vec![params.path.clone()],
+            FileSystemTools::CalculateDirectorySize(params) => vec![params.root_path.clone()],
+            FileSystemTools::FindDuplicateFiles(params) => vec![params.root_path.clone()],
+            FileSystemTools::CreateTempPath(params) => vec![params.directory.clone()],
+            FileSystemTools::ReadMultipleTextFiles(params) => params.paths.clone(),
+            FileSystemTools::ReadMultipleMediaFiles(params) => params.paths.clone(),
+            FileSystemTools::GitDiff(params) => params.paths.clone(),
+            FileSystemTools::MoveFile(params) => {
+                vec![params.source.clone(), params.destination.clone()]
+            }
+            FileSystemTools::CopyFile(params) => {
+                vec![params.source.clone(), params.destination.clone()]
+            }
+            FileSystemTools::CloneDirectoryStructure(params) => {
+                vec![params.source.clone(), params.destination.clone()]
+            }
+            FileSystemTools::ApplyEditsBatch(params) => {
+                params.files.iter().map(|file| file.path.clone()).collect()
+            }
+            FileSystemTools::CleanupOldFiles(params) => std::iter::once(params.path.clone())
+                .chain(params.archive_dir.clone())
+                .collect(),
+            FileSystemTools::CreateBaseline(params) => std::iter::once(params.path.clone())
+                .chain(params.baseline_file.clone())
+                .collect(),
+            FileSystemTools::CompareBaseline(params) => std::iter::once(params.path.clone())
+                .chain(params.baseline_file.clone())
+                .collect(),
+            // without a path, these report on every allowed directory
+            FileSystemTools::GetDiskSpace(params) => vec![params.path.clone()?],
+            FileSystemTools::RestoreFromTrash(params) => vec![params.path.clone()?],
+            FileSystemTools::UndoLastOperation(_) => return None,
+            // the steps of a batch are checked one by one
+            FileSystemTools::ListAllowedDirectories(_)
+            | FileSystemTools::ConfigureTools(_)
+            | FileSystemTools::GetServerStats(_)
+            | FileSystemTools::BatchExecute(_) => vec![],
+        };
+        Some(paths)
+    }
+
     // Determines whether the filesystem tool requires write access to the filesystem.
     // Returns `true` for tools that modify files or directories, and `false` otherwise.
     pub fn require_write_access(&self) -> bool {
         match self {
             FileSystemTools::CreateDirectory(_)
+            | FileSystemTools::CreateTempPath(_)
+            | FileSystemTools::CloneDirectoryStructure(_)
             | FileSystemTools::MoveFile(_)
+            | FileSystemTools::CopyFile(_)
+            | FileSystemTools::CleanupOldFiles(_)
             | FileSystemTools::WriteFile(_)
-            | FileSystemTools::EditFile(_) => true,
+            | FileSystemTools::EditFile(_)
+            | FileSystemTools::ApplyEditsBatch(_)
+            | FileSystemTools::RewriteCodeAst(_)
+            | FileSystemTools::RestoreFromTrash(_)
+            | FileSystemTools::SetExtendedAttribute(_)
+            | FileSystemTools::UndoLastOperation(_) => true,
+            FileSystemTools::FindDuplicateFiles(params) => params.modifies_files(),
+            FileSystemTools::FindEmptyDirectories(params) => params.delete.unwrap_or(false),
+            FileSystemTools::CreateBaseline(params) => params.baseline_file.is_some(),
             FileSystemTools::ReadTextFile(_)
             | FileSystemTools::DirectoryTree(_)
             | FileSystemTools::GetFileInfo(_)
@@ -87,11 +326,37 @@ impl FileSystemTools {
             | FileSystemTools::ReadMediaFile(_)
             | FileSystemTools::ReadMultipleMediaFiles(_)
             | FileSystemTools::ReadFileLines(_)
-            | FileSystemTools::FindEmptyDirectories(_)
+            | FileSystemTools::ReadFileChunk(_)
+            | FileSystemTools::ReadAroundMatch(_)
+            | FileSystemTools::FindBrokenSymlinks(_)
             | FileSystemTools::CalculateDirectorySize(_)
-            | FileSystemTools::FindDuplicateFiles(_)
             | FileSystemTools::SearchFiles(_)
-            | FileSystemTools::SearchCodeAst(_) => false,
+            | FileSystemTools::SearchCodeAst(_)
+            | FileSystemTools::FuzzyFindFile(_)
+            | FileSystemTools::IndexDirectory(_)
+            | FileSystemTools::ReadJsonPath(_)
+            | FileSystemTools::ReadStructuredFile(_)
+            | FileSystemTools::PreviewCsv(_)
+            | FileSystemTools::GetMediaInfo(_)
+            | FileSystemTools::ReadDocumentText(_)
+            | FileSystemTools::GetMarkdownOutline(_)
+            | FileSystemTools::GetCodeOutline(_)
+            | FileSystemTools::GitDiff(_)
+            | FileSystemTools::GitBlame(_)
+            | FileSystemTools::GitFileHistory(_)
+            | FileSystemTools::WatchDirectory(_)
+            | FileSystemTools::UnwatchDirectory(_)
+            | FileSystemTools::TailFile(_)
+            | FileSystemTools::ListExtendedAttributes(_)
+            | FileSystemTools::GetExtendedAttribute(_)
+            | FileSystemTools::DirectoryStats(_)
+            | FileSystemTools::CollectTodos(_)
+            | FileSystemTools::SearchArchiveContent(_)
+            | FileSystemTools::CompareBaseline(_)
+            | FileSystemTools::ConfigureTools(_)
+            | FileSystemTools::GetServerStats(_)
+            | FileSystemTools::GetDiskSpace(_)
+            | FileSystemTools::BatchExecute(_) => false,
         }
     }
 }
@@ -0,0 +1,63 @@
+use std::path::PathBuf;
+
+use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
+use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
+
+use crate::fs_service::FileSystemService;
+use crate::tools::EditOperation;
+use crate::tools::edit_file::edit_result;
+
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+/// The edits to apply to a single file.
+pub struct FileEdits {
+    /// The path of the file to edit.
+    pub path: String,
+    /// The list of edit operations to apply to this file.
+    pub edits: Vec<EditOperation>,
+}
+
+#[mcp_tool(
+    name = "apply_edits_batch",
+    title="Apply edits batch",
+    description = concat!("Apply edits to multiple text files as a single atomic change. ",
+    "Each edit replaces exact line sequences with new content, like 'edit_file'. ",
+    "All edits are validated before any file is written: if one of them does not apply, no file is changed. ",
+    "Files are then replaced together, and already replaced files are rolled back if one of them cannot be written. ",
+    "Returns a git-style diff for each file. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = false
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct ApplyEditsBatch {
+    /// The files to edit, each with its list of edit operations.
+    pub files: Vec<FileEdits>,
+    /// Preview changes using git-style diff format without applying them.
+    #[serde(
+        rename = "dryRun",
+        default,
+        skip_serializing_if = "std::option::Option::is_none"
+    )]
+    pub dry_run: Option<bool>,
+}
+
+impl ApplyEditsBatch {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let files = params
+            .files
+            .into_iter()
+            .map(|file| (PathBuf::from(file.path), file.edits))
+            .collect();
+        let result = context
+            .apply_edits_batch(files, params.dry_run.unwrap_or(false))
+            .await
+            .map_err(CallToolError::new)?;
+
+        edit_result(result)
+    }
+}
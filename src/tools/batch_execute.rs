@@ -0,0 +1,142 @@
+use async_trait::async_trait;
+use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
+use rust_mcp_sdk::schema::{
+    CallToolResult, ContentBlock, TextContent, schema_utils::CallToolError,
+};
+use serde_json::{Map, Value, json};
+
+use crate::fs_service::{CancellationToken, FileSystemService};
+
+/// Maximum number of steps of a batch.
+pub const MAX_BATCH_STEPS: usize = 100;
+
+/// The arguments of a tool call, an arbitrary JSON object.
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, Default)]
+#[serde(transparent)]
+pub struct ToolArguments(pub Map<String, Value>);
+
+impl ToolArguments {
+    pub fn json_schema() -> Map<String, Value> {
+        let mut map = Map::new();
+        map.insert("type".to_string(), json!("object"));
+        map
+    }
+}
+
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+/// A single tool call of a batch.
+pub struct BatchStep {
+    /// The name of the tool to call, e.g. 'read_text_file'.
+    pub tool: String,
+    /// The arguments of the tool, as they would be passed to it directly.
+    pub arguments: Option<ToolArguments>,
+}
+
+/// Runs the steps of a batch, applying the same checks as to calls made by the client.
+#[async_trait]
+pub trait ToolExecutor: Send + Sync {
+    /// Calls `tool` with `arguments`, returning the error message if the call fails.
+    async fn execute(
+        &self,
+        tool: String,
+        arguments: Option<Map<String, Value>>,
+    ) -> Result<CallToolResult, String>;
+
+    /// The token cancelling the whole batch.
+    fn cancellation(&self) -> &CancellationToken;
+}
+
+#[mcp_tool(
+    name = "batch_execute",
+    title="Batch execute",
+    description = concat!("Calls several tools of this server in order within a single request, e.g. to read many small files ",
+    "or run a scripted sequence of edits. Each step names a `tool` and its `arguments`, exactly as they would be passed ",
+    "to the tool directly. Steps are subject to the same permissions and limits as direct calls. ",
+    "The result holds the output of every step after a '## Step N: tool (ok|error)' header, ",
+    "and a summary of the steps as structured content. ",
+    "Remaining steps are skipped after a failing one unless `stop_on_error` is false. ",
+    "At most 100 steps per batch, which cannot be nested."),
+    destructive_hint = true,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = false
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct BatchExecute {
+    /// The tool calls to make, in order.
+    pub steps: Vec<BatchStep>,
+    /// Skip the remaining steps once one fails (default: true).
+    #[json_schema(default = "true")]
+    pub stop_on_error: Option<bool>,
+}
+
+impl BatchExecute {
+    pub async fn run_tool_with_context(
+        params: Self,
+        _: &FileSystemService,
+        executor: &dyn ToolExecutor,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        if params.steps.is_empty() || params.steps.len() > MAX_BATCH_STEPS {
+            return Err(CallToolError::from_message(format!(
+                "A batch must have between 1 and {MAX_BATCH_STEPS} steps."
+            )));
+        }
+        let stop_on_error = params.stop_on_error.unwrap_or(true);
+        let total = params.steps.len();
+
+        let mut content = vec![];
+        let mut summary = vec![];
+        let mut failed = 0;
+        for (index, step) in params.steps.into_iter().enumerate() {
+            if executor.cancellation().is_cancelled() {
+                break;
+            }
+            let result = if step.tool == Self::tool_name() {
+                Err("Batches cannot be nested.".to_string())
+            } else {
+                executor
+                    .execute(
+                        step.tool.clone(),
+                        step.arguments.map(|arguments| arguments.0),
+                    )
+                    .await
+            };
+            let ok = result
+                .as_ref()
+                .is_ok_and(|result| !result.is_error.unwrap_or_default());
+            content.push(ContentBlock::TextContent(TextContent::from(format!(
+                "## Step {}: {} ({})",
+                index + 1,
+                step.tool,
+                if ok { "ok" } else { "error" }
+            ))));
+            let mut step_summary = json!({ "step": index + 1, "tool": step.tool, "ok": ok });
+            match result {
+                Ok(result) => content.extend(result.content),
+                Err(error) => {
+                    content.push(ContentBlock::TextContent(TextContent::from(error.clone())));
+                    step_summary["error"] = json!(error);
+                }
+            }
+            summary.push(step_summary);
+            if !ok {
+                failed += 1;
+                if stop_on_error {
+                    break;
+                }
+            }
+        }
+
+        let mut structured = Map::new();
+        structured.insert("steps".to_string(), json!(summary));
+        structured.insert("completed".to_string(), json!(summary.len() - failed));
+        structured.insert("failed".to_string(), json!(failed));
+        structured.insert("skipped".to_string(), json!(total - summary.len()));
+        Ok(CallToolResult {
+            content,
+            is_error: None,
+            meta: None,
+            structured_content: Some(structured),
+        })
+    }
+}
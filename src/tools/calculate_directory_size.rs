@@ -1,8 +1,9 @@
-use crate::fs_service::{FileSystemService, utils::format_bytes};
+use crate::fs_service::{FileSystemService, SizeGroupBy, utils::format_bytes};
 use rust_mcp_sdk::{
     macros::{JsonSchema, mcp_tool},
     schema::{CallToolResult, TextContent, schema_utils::CallToolError},
 };
+use std::fmt::Write;
 use std::path::Path;
 
 #[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
@@ -18,7 +19,9 @@ pub enum FileSizeOutputFormat {
     title="Calculate directory size",
     description = concat!("Calculates the total size of a directory specified by `root_path`.",
     "It recursively searches for files and sums their sizes. ",
-    "The result can be returned in either a `human-readable` format or as `bytes`, depending on the specified `output_format` argument.",
+    "The result can be returned in either a `human-readable` format or as `bytes`, depending on the specified `output_format` argument. ",
+    "Set `group_by` to `extension` or `top_level_dir` to get a breakdown table sorted by size, largest first, ",
+    "showing which file types or subdirectories use the space. ",
     "Only works within allowed directories."),
     destructive_hint = false,
     idempotent_hint = false,
@@ -32,6 +35,9 @@ pub struct CalculateDirectorySize {
     /// Defines the output format, which can be either `human-readable` or `bytes`.
     #[json_schema(default = "human-readable")]
     pub output_format: Option<FileSizeOutputFormat>,
+    /// Breaks the total down by `extension` or `top_level_dir` (default: none).
+    #[json_schema(default = "none")]
+    pub group_by: Option<SizeGroupBy>,
 }
 
 impl CalculateDirectorySize {
@@ -39,19 +45,48 @@ impl CalculateDirectorySize {
         params: Self,
         context: &FileSystemService,
     ) -> std::result::Result<CallToolResult, CallToolError> {
-        let total_bytes = context
-            .calculate_directory_size(Path::new(&params.root_path))
-            .await
-            .map_err(CallToolError::new)?;
-
-        let output_content = match params
+        let output_format = params
             .output_format
-            .unwrap_or(FileSizeOutputFormat::HumanReadable)
-        {
-            FileSizeOutputFormat::HumanReadable => format_bytes(total_bytes),
-            FileSizeOutputFormat::Bytes => format!("{total_bytes}"),
+            .unwrap_or(FileSizeOutputFormat::HumanReadable);
+        let format_size = |bytes: u64| match output_format {
+            FileSizeOutputFormat::HumanReadable => format_bytes(bytes),
+            FileSizeOutputFormat::Bytes => format!("{bytes}"),
         };
 
+        let group_by = params.group_by.unwrap_or_default();
+        if group_by == SizeGroupBy::None {
+            let total_bytes = context
+                .calculate_directory_size(Path::new(&params.root_path))
+                .await
+                .map_err(CallToolError::new)?;
+            return Ok(CallToolResult::text_content(vec![TextContent::from(
+                format_size(total_bytes),
+            )]));
+        }
+
+        let groups = context
+            .directory_size_breakdown(Path::new(&params.root_path), group_by)
+            .await
+            .map_err(CallToolError::new)?;
+        let mut output_content = String::new();
+        for group in &groups {
+            writeln!(
+                output_content,
+                "{:<30} {:>8} files {:>12}",
+                group.group,
+                group.files,
+                format_size(group.bytes)
+            )
+            .map_err(CallToolError::new)?;
+        }
+        writeln!(
+            output_content,
+            "\nTotal: {} files, {}",
+            groups.iter().map(|group| group.files).sum::<u64>(),
+            format_size(groups.iter().map(|group| group.bytes).sum())
+        )
+        .map_err(CallToolError::new)?;
+
         Ok(CallToolResult::text_content(vec![TextContent::from(
             output_content,
         )]))
@@ -0,0 +1,157 @@
+use crate::fs_service::{
+    CleanupAction, CleanupOutcome, FileSystemService, WalkOptions,
+    utils::{OutputFormat, format_bytes},
+};
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+use std::fmt::Write;
+use std::path::Path;
+use std::time::Duration;
+
+#[mcp_tool(
+    name = "cleanup_old_files",
+    title="Clean up old files",
+    description = concat!("Deletes or archives the files below `path` that were last modified more than `older_than_days` days ago ",
+    "and whose name matches the glob `pattern`, e.g. '*.log'. Useful to prune logs, caches and build leftovers. ",
+    "With `action` set to `archive`, files are moved below `archive_dir`, keeping their path relative to `path`. ",
+    "Only returns the list of files and their total size unless `dry_run` is set to false, review it before applying it. ",
+    "Ignored files are included unless `respect_gitignore` is true, since logs and caches are usually ignored. ",
+    "Deleted files are moved to the trash when the server runs with `--use-trash`. ",
+    "Only works within allowed directories."),
+    destructive_hint = true,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = false
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct CleanupOldFiles {
+    /// The root directory path.
+    pub path: String,
+    /// Glob pattern matched against file names, e.g. '*.log' (default: every file).
+    pub pattern: Option<String>,
+    /// Optional list of glob patterns to exclude.
+    pub exclude_patterns: Option<Vec<String>>,
+    /// Minimum age of the files in days since their last modification, fractions are allowed.
+    pub older_than_days: f64,
+    /// What to do with the files: `delete` or `archive` (default: delete).
+    #[json_schema(default = "delete")]
+    pub action: Option<CleanupAction>,
+    /// Directory the files are moved to with the `archive` action.
+    pub archive_dir: Option<String>,
+    /// Only list the files without changing anything (default: true).
+    #[json_schema(default = "true")]
+    pub dry_run: Option<bool>,
+    /// Skip files ignored by .gitignore and .ignore files (default: false).
+    #[json_schema(default = "false")]
+    pub respect_gitignore: Option<bool>,
+    /// Include hidden files and directories (default: false).
+    #[json_schema(default = "false")]
+    pub include_hidden: Option<bool>,
+    /// Specify the output format, accepts either `text` or `json` (default: text).
+    pub output_format: Option<OutputFormat>,
+}
+
+impl CleanupOldFiles {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let older_than =
+            Duration::try_from_secs_f64(params.older_than_days * 86_400.0).map_err(|_| {
+                CallToolError::from_message(format!(
+                    "Invalid 'older_than_days' value {}.",
+                    params.older_than_days
+                ))
+            })?;
+        let action = params.action.unwrap_or_default();
+        let dry_run = params.dry_run.unwrap_or(true);
+        let outcome = context
+            .cleanup_old_files(
+                Path::new(&params.path),
+                params.pattern.unwrap_or_else(|| "*".to_string()),
+                params.exclude_patterns.unwrap_or_default(),
+                older_than,
+                action,
+                params.archive_dir.as_deref().map(Path::new),
+                dry_run,
+                WalkOptions::new(
+                    Some(params.respect_gitignore.unwrap_or(false)),
+                    params.include_hidden,
+                    None,
+                ),
+            )
+            .await
+            .map_err(CallToolError::new)?;
+
+        let content = Self::format_output(
+            &outcome,
+            action,
+            dry_run,
+            params.output_format.unwrap_or(OutputFormat::Text),
+        )?;
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            content,
+        )]))
+    }
+
+    fn format_output(
+        outcome: &CleanupOutcome,
+        action: CleanupAction,
+        dry_run: bool,
+        output_format: OutputFormat,
+    ) -> std::result::Result<String, CallToolError> {
+        if let OutputFormat::Json = output_format {
+            return serde_json::to_string_pretty(&serde_json::json!({
+                "action": action,
+                "dry_run": dry_run,
+                "files": outcome.files,
+                "bytes": outcome.bytes,
+                "errors": outcome.errors,
+            }))
+            .map_err(CallToolError::new);
+        }
+
+        let verb = match (action, dry_run) {
+            (CleanupAction::Delete, true) => "Delete",
+            (CleanupAction::Delete, false) => "Deleted",
+            (CleanupAction::Archive, true) => "Archive",
+            (CleanupAction::Archive, false) => "Archived",
+        };
+        let mut output = String::new();
+        for file in &outcome.files {
+            write!(
+                output,
+                "{verb}: {} ({}, modified {})",
+                file.path,
+                format_bytes(file.size),
+                file.modified
+            )
+            .map_err(CallToolError::new)?;
+            if let Some(archived_to) = &file.archived_to {
+                write!(output, " -> {archived_to}").map_err(CallToolError::new)?;
+            }
+            output.push('\n');
+        }
+        for (file, error) in &outcome.errors {
+            writeln!(output, "Failed: {file} ({error})").map_err(CallToolError::new)?;
+        }
+        let files = outcome.files.len();
+        let bytes = format_bytes(outcome.bytes);
+        let done = match action {
+            CleanupAction::Delete => "deleted",
+            CleanupAction::Archive => "archived",
+        };
+        if dry_run {
+            write!(
+                output,
+                "Dry run: {files} file(s) totalling {bytes} would be {done}. Set `dry_run` to false to apply it."
+            )
+        } else {
+            write!(output, "{files} file(s) totalling {bytes} {done}.")
+        }
+        .map_err(CallToolError::new)?;
+        Ok(output)
+    }
+}
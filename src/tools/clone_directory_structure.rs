@@ -0,0 +1,72 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
+use rust_mcp_sdk::schema::TextContent;
+use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
+
+use crate::fs_service::{FileSystemService, WalkOptions};
+
+#[mcp_tool(
+    name = "clone_directory_structure",
+    title="Clone directory structure",
+    description = concat!("Recreates the directory hierarchy of a source directory inside a destination directory, ",
+    "without copying any files. Useful to prepare output trees that mirror input trees. ",
+    "Use `max_depth` to limit how many levels below the source are cloned (default: all levels). ",
+    "Directories that already exist in the destination are kept. ",
+    "The optional exclude_patterns argument accepts glob-style patterns to skip directories and their subdirectories. ",
+    "Respects gitignore rules and skips hidden directories unless `respect_gitignore` is false or `include_hidden` is true. ",
+    "Both source and destination must be within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = true,
+    open_world_hint = false,
+    read_only_hint = false
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct CloneDirectoryStructure {
+    /// The directory whose hierarchy is cloned.
+    pub source: String,
+    /// The directory to create the hierarchy in, created if missing.
+    pub destination: String,
+    /// How many levels below the source to clone, 1 clones only its direct subdirectories.
+    pub max_depth: Option<u64>,
+    /// Optional list of glob patterns of directories to skip.
+    pub exclude_patterns: Option<Vec<String>>,
+    /// Skip directories ignored by .gitignore and .ignore files (default: true).
+    #[json_schema(default = "true")]
+    pub respect_gitignore: Option<bool>,
+    /// Include hidden directories (default: false).
+    #[json_schema(default = "false")]
+    pub include_hidden: Option<bool>,
+}
+
+impl CloneDirectoryStructure {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let created = context
+            .clone_directory_structure(
+                Path::new(&params.source),
+                Path::new(&params.destination),
+                params.max_depth.map(|depth| depth as usize),
+                params.exclude_patterns.unwrap_or_default(),
+                WalkOptions::new(params.respect_gitignore, params.include_hidden, None),
+            )
+            .await
+            .map_err(CallToolError::new)?;
+
+        let mut output = format!(
+            "Cloned the directory structure of {} into {}, {} directories created.",
+            &params.source,
+            &params.destination,
+            created.len()
+        );
+        for dir in &created {
+            write!(output, "\n{}", dir.display()).map_err(CallToolError::new)?;
+        }
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            output,
+        )]))
+    }
+}
@@ -0,0 +1,100 @@
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::fs_service::{
+    DEFAULT_TODO_TAGS, FileSystemService, TodoGroup, WalkOptions, utils::OutputFormat,
+};
+
+#[mcp_tool(
+    name = "collect_todos",
+    title="Collect TODO comments",
+    description = concat!("Scans the text files of a directory for TODO, FIXME and HACK markers ",
+    "and returns the file, line number and text of each one, grouped by tag. ",
+    "Set `tags` to look for other markers (case-sensitive, whole words), e.g. ['TODO', 'XXX', 'SAFETY']. ",
+    "The optional exclude_patterns argument accepts glob-style patterns to exclude paths. ",
+    "Respects gitignore rules and skips hidden files unless `respect_gitignore` is false or `include_hidden` is true. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct CollectTodos {
+    /// The directory path to scan.
+    pub path: String,
+    /// Markers to look for (default: TODO, FIXME, HACK).
+    pub tags: Option<Vec<String>>,
+    /// Optional list of glob patterns to exclude from the scan.
+    pub exclude_patterns: Option<Vec<String>>,
+    /// Skip files ignored by .gitignore and .ignore files (default: true).
+    #[json_schema(default = "true")]
+    pub respect_gitignore: Option<bool>,
+    /// Include hidden files and directories (default: false).
+    #[json_schema(default = "false")]
+    pub include_hidden: Option<bool>,
+    /// Specify the output format, accepts either `text` or `json` (default: text).
+    pub output_format: Option<OutputFormat>,
+}
+
+impl CollectTodos {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let tags = params.tags.unwrap_or_else(|| {
+            DEFAULT_TODO_TAGS
+                .iter()
+                .map(|tag| tag.to_string())
+                .collect()
+        });
+        let groups = context
+            .collect_todos(
+                Path::new(&params.path),
+                &tags,
+                params.exclude_patterns.unwrap_or_default(),
+                WalkOptions::new(params.respect_gitignore, params.include_hidden, None),
+            )
+            .await
+            .map_err(CallToolError::new)?;
+
+        let content =
+            Self::format_output(&groups, params.output_format.unwrap_or(OutputFormat::Text))?;
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            content,
+        )]))
+    }
+
+    fn format_output(
+        groups: &[TodoGroup],
+        output_format: OutputFormat,
+    ) -> std::result::Result<String, CallToolError> {
+        if let OutputFormat::Json = output_format {
+            return serde_json::to_string_pretty(groups).map_err(CallToolError::new);
+        }
+
+        if groups.is_empty() {
+            return Ok("No markers were found.".to_string());
+        }
+        let mut output = String::new();
+        for group in groups {
+            writeln!(output, "{} ({}):", group.tag, group.items.len())
+                .map_err(CallToolError::new)?;
+            for item in &group.items {
+                writeln!(
+                    output,
+                    "  {}:{}: {}",
+                    item.path.display(),
+                    item.line,
+                    item.text
+                )
+                .map_err(CallToolError::new)?;
+            }
+        }
+        Ok(output)
+    }
+}
@@ -0,0 +1,89 @@
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::fs_service::{BaselineDiff, FileSystemService, utils::OutputFormat};
+
+#[mcp_tool(
+    name = "compare_baseline",
+    title="Compare with integrity baseline",
+    description = concat!("Compares the files of a directory with the baseline recorded by `create_baseline` ",
+    "and reports which files were added, removed or modified since then. ",
+    "Files are compared by SHA-256 hash, files whose size and modification time did not change are not read again. ",
+    "Pass the same `baseline_file` as to `create_baseline`, if any. Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct CompareBaseline {
+    /// The root directory the baseline was created for.
+    pub path: String,
+    /// Where the baseline is stored, if it was created with a `baseline_file`.
+    pub baseline_file: Option<String>,
+    /// Specify the output format, accepts either `text` or `json` (default: text).
+    pub output_format: Option<OutputFormat>,
+}
+
+impl CompareBaseline {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let diff = context
+            .compare_baseline(
+                Path::new(&params.path),
+                params.baseline_file.as_deref().map(Path::new),
+            )
+            .await
+            .map_err(CallToolError::new)?;
+
+        let content =
+            Self::format_output(&diff, params.output_format.unwrap_or(OutputFormat::Text))?;
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            content,
+        )]))
+    }
+
+    fn format_output(
+        diff: &BaselineDiff,
+        output_format: OutputFormat,
+    ) -> std::result::Result<String, CallToolError> {
+        if let OutputFormat::Json = output_format {
+            return serde_json::to_string_pretty(diff).map_err(CallToolError::new);
+        }
+
+        if !diff.has_changes() {
+            return Ok(format!(
+                "No changes since the baseline of {} ({} files).",
+                diff.baseline_created_at, diff.unchanged
+            ));
+        }
+        let mut output = format!(
+            "Changes since the baseline of {}: {} added, {} removed, {} modified, {} unchanged.\n",
+            diff.baseline_created_at,
+            diff.added.len(),
+            diff.removed.len(),
+            diff.modified.len(),
+            diff.unchanged
+        );
+        for (label, paths) in [
+            ("Added", &diff.added),
+            ("Removed", &diff.removed),
+            ("Modified", &diff.modified),
+        ] {
+            if paths.is_empty() {
+                continue;
+            }
+            writeln!(output, "\n{label}:").map_err(CallToolError::new)?;
+            for path in paths {
+                writeln!(output, "  {}", path.display()).map_err(CallToolError::new)?;
+            }
+        }
+        Ok(output)
+    }
+}
@@ -0,0 +1,162 @@
+use crate::error::{ServiceError, ServiceResult};
+use crate::fs_service::FileSystemService;
+use crate::tools::FileSystemTools;
+use rust_mcp_sdk::McpServer;
+use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
+use rust_mcp_sdk::schema::TextContent;
+use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+
+/// Tools that are always enabled and cannot be disabled at runtime.
+const PROTECTED_TOOLS: [&str; 2] = ["list_allowed_directories", "configure_tools"];
+
+#[mcp_tool(
+    name = "configure_tools",
+    title="Configure tools",
+    description = concat!("Enables or disables tools of this server at runtime. ",
+    "`enable` and `disable` take lists of tool names; disabled tools disappear from the tool list ",
+    "and calls to them are rejected. Clients are notified that the tool list changed. ",
+    "Returns the tools that are enabled afterwards. ",
+    "'list_allowed_directories' and 'configure_tools' cannot be disabled. ",
+    "Only available when the server runs with --enable-admin-tools."),
+    destructive_hint = false,
+    idempotent_hint = true,
+    open_world_hint = false,
+    read_only_hint = false
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct ConfigureTools {
+    /// Names of tools to enable.
+    pub enable: Option<Vec<String>>,
+    /// Names of tools to disable.
+    pub disable: Option<Vec<String>>,
+}
+
+/// The set of tools enabled at runtime, shared by the handler and `configure_tools`.
+pub struct ToolRegistry {
+    enabled: RwLock<HashSet<String>>,
+}
+
+impl ToolRegistry {
+    /// Creates the registry from the `--tools` selection (`None` enables every tool).
+    /// `configure_tools` itself is only enabled when `admin` is set.
+    pub fn new(selected: Option<HashSet<String>>, admin: bool) -> Self {
+        let mut enabled: HashSet<String> = selected.unwrap_or_else(|| {
+            FileSystemTools::tools()
+                .into_iter()
+                .map(|tool| tool.name)
+                .collect()
+        });
+        enabled.insert("list_allowed_directories".to_string());
+        if admin {
+            enabled.insert(ConfigureTools::tool_name());
+        } else {
+            enabled.remove(&ConfigureTools::tool_name());
+        }
+        Self {
+            enabled: RwLock::new(enabled),
+        }
+    }
+
+    pub fn is_enabled(&self, tool_name: &str) -> bool {
+        self.enabled
+            .read()
+            .is_ok_and(|enabled| enabled.contains(&tool_name.to_lowercase()))
+    }
+
+    /// Returns the names of the enabled tools, sorted.
+    pub fn enabled_tools(&self) -> Vec<String> {
+        let mut tools: Vec<String> = self
+            .enabled
+            .read()
+            .map(|enabled| enabled.iter().cloned().collect())
+            .unwrap_or_default();
+        tools.sort();
+        tools
+    }
+
+    /// Enables and disables the given tools, returning true if the set of enabled tools changed.
+    pub fn configure(&self, enable: &[String], disable: &[String]) -> ServiceResult<bool> {
+        let known: HashSet<String> = FileSystemTools::tools()
+            .into_iter()
+            .map(|tool| tool.name)
+            .collect();
+        let normalize = |names: &[String]| -> ServiceResult<Vec<String>> {
+            names
+                .iter()
+                .map(|name| {
+                    let name = name.trim().to_lowercase();
+                    if known.contains(&name) {
+                        Ok(name)
+                    } else {
+                        Err(ServiceError::InvalidArgument(format!(
+                            "Unknown tool '{name}'."
+                        )))
+                    }
+                })
+                .collect()
+        };
+        let enable = normalize(enable)?;
+        let disable = normalize(disable)?;
+        if let Some(name) = disable
+            .iter()
+            .find(|name| PROTECTED_TOOLS.contains(&name.as_str()))
+        {
+            return Err(ServiceError::InvalidArgument(format!(
+                "Tool '{name}' cannot be disabled."
+            )));
+        }
+
+        let mut enabled = self
+            .enabled
+            .write()
+            .map_err(|_| ServiceError::FromString("Tool registry is unavailable.".to_string()))?;
+        let mut changed = false;
+        for name in enable {
+            changed |= enabled.insert(name);
+        }
+        for name in disable {
+            changed |= enabled.remove(&name);
+        }
+        Ok(changed)
+    }
+}
+
+/// What `configure_tools` operates on besides the file system service.
+pub struct ToolAdminContext<'a> {
+    pub registry: &'a ToolRegistry,
+    pub runtime: Arc<dyn McpServer>,
+}
+
+impl ConfigureTools {
+    /// Applies the changes to `context.registry` and notifies the client if the tool list changed.
+    pub async fn run_tool_with_context(
+        params: Self,
+        _: &FileSystemService,
+        context: &ToolAdminContext<'_>,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let changed = context
+            .registry
+            .configure(
+                &params.enable.unwrap_or_default(),
+                &params.disable.unwrap_or_default(),
+            )
+            .map_err(CallToolError::new)?;
+
+        if changed {
+            context
+                .runtime
+                .send_tool_list_changed(None)
+                .await
+                .map_err(CallToolError::new)?;
+        }
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            format!(
+                "Enabled tools:\n{}",
+                context.registry.enabled_tools().join("\n")
+            ),
+        )]))
+    }
+}
@@ -0,0 +1,58 @@
+use std::path::Path;
+
+use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
+use rust_mcp_sdk::schema::TextContent;
+use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
+
+use crate::fs_service::{FileSystemService, OverwritePolicy};
+
+#[mcp_tool(
+    name = "copy_file",
+    title="Copy file",
+    description = concat!("Copy a file, symlink or directory tree to a new location, keeping permissions. ",
+"If the destination exists, the operation fails unless 'overwrite' is 'replace' (replace the destination) ",
+"or 'rename' (copy to a free name like 'name (1).ext'). Returns the final destination. ",
+"Both source and destination must be within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = false
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct CopyFile {
+    /// The source path of the file or directory to copy.
+    pub source: String,
+    /// The destination path to copy to.
+    pub destination: String,
+    /// What to do when the destination exists: "error" (default), "replace" or "rename".
+    #[serde(
+        default,
+        alias = "on_conflict",
+        skip_serializing_if = "std::option::Option::is_none"
+    )]
+    pub overwrite: Option<OverwritePolicy>,
+}
+
+impl CopyFile {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let destination = context
+            .copy_file(
+                Path::new(&params.source),
+                Path::new(&params.destination),
+                params.overwrite.unwrap_or_default(),
+            )
+            .await
+            .map_err(CallToolError::new)?;
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            format!(
+                "Successfully copied {} to {}",
+                &params.source,
+                destination.display()
+            ),
+        )]))
+    }
+}
@@ -0,0 +1,60 @@
+use std::path::Path;
+
+use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
+use rust_mcp_sdk::schema::TextContent;
+use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
+
+use crate::fs_service::{FileSystemService, WalkOptions};
+
+#[mcp_tool(
+    name = "create_baseline",
+    title="Create integrity baseline",
+    description = concat!("Records the path, size, modification time and SHA-256 hash of every file in a directory ",
+    "as a baseline, to be compared later with `compare_baseline` to find which files were added, removed or modified, ",
+    "without needing git. The baseline is stored in the server's index directory, or in `baseline_file` if given, ",
+    "which then requires write access. Creating a baseline again replaces the previous one. ",
+    "Respects gitignore rules and skips hidden files unless `respect_gitignore` is false or `include_hidden` is true. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = false
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct CreateBaseline {
+    /// The root directory to snapshot.
+    pub path: String,
+    /// Where to store the baseline, within allowed directories (optional).
+    pub baseline_file: Option<String>,
+    /// Skip files ignored by .gitignore and .ignore files (default: true).
+    #[json_schema(default = "true")]
+    pub respect_gitignore: Option<bool>,
+    /// Include hidden files and directories (default: false).
+    #[json_schema(default = "false")]
+    pub include_hidden: Option<bool>,
+}
+
+impl CreateBaseline {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let summary = context
+            .create_baseline(
+                Path::new(&params.path),
+                params.baseline_file.as_deref().map(Path::new),
+                WalkOptions::new(params.respect_gitignore, params.include_hidden, None),
+            )
+            .await
+            .map_err(CallToolError::new)?;
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            format!(
+                "Recorded {} files under {}.\nBaseline stored at: {}",
+                summary.files,
+                summary.root.display(),
+                summary.baseline_path.display()
+            ),
+        )]))
+    }
+}
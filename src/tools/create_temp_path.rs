@@ -0,0 +1,70 @@
+use std::path::Path;
+use std::time::Duration;
+
+use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
+use rust_mcp_sdk::schema::TextContent;
+use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
+
+use crate::fs_service::{FileSystemService, TempPathKind};
+
+#[mcp_tool(
+    name = "create_temp_path",
+    title="Create temporary path",
+    description = concat!("Creates an empty file or directory with a unique name inside `directory`, ",
+    "named `{prefix}{random}{suffix}`, and returns its path. ",
+    "Use it as a scratch area for intermediate results instead of inventing file names. ",
+    "With `ttl_seconds`, the path and its content are removed once the time has elapsed, ",
+    "as long as the server is still running. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = false
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct CreateTempPath {
+    /// The directory in which the temporary path is created.
+    pub directory: String,
+    /// Create a `file` or a `directory` (default: file).
+    pub kind: Option<TempPathKind>,
+    /// Text the name starts with, e.g. 'build-'.
+    pub prefix: Option<String>,
+    /// Text the name ends with, e.g. '.json'.
+    pub suffix: Option<String>,
+    /// Remove the path after this many seconds.
+    pub ttl_seconds: Option<u64>,
+}
+
+impl CreateTempPath {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let kind = params.kind.unwrap_or_default();
+        let path = context
+            .create_temp_path(
+                Path::new(&params.directory),
+                kind,
+                params.prefix.as_deref().unwrap_or_default(),
+                params.suffix.as_deref().unwrap_or_default(),
+                params.ttl_seconds.map(Duration::from_secs),
+            )
+            .await
+            .map_err(CallToolError::new)?;
+
+        let mut result = format!(
+            "Successfully created temporary {} {}",
+            match kind {
+                TempPathKind::File => "file",
+                TempPathKind::Directory => "directory",
+            },
+            path.display()
+        );
+        if let Some(ttl_seconds) = params.ttl_seconds {
+            result.push_str(&format!(", removed after {ttl_seconds} seconds"));
+        }
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            result,
+        )]))
+    }
+}
@@ -0,0 +1,113 @@
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::fs_service::{
+    DirectoryStatistics, FileSystemService, WalkOptions,
+    utils::{OutputFormat, format_bytes},
+};
+
+#[mcp_tool(
+    name = "directory_stats",
+    title="Directory statistics",
+    description = concat!("Reports statistics of a directory tree: the number of files and directories, ",
+    "and the number and total size of files per extension, largest first. ",
+    "Set `count_lines` to also count the lines of text files per extension, like a lightweight cloc. ",
+    "The optional exclude_patterns argument accepts glob-style patterns to exclude paths, e.g. 'node_modules'. ",
+    "Respects gitignore rules and skips hidden files unless `respect_gitignore` is false or `include_hidden` is true. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct DirectoryStats {
+    /// The root directory path.
+    pub path: String,
+    /// Optional list of glob patterns to exclude.
+    pub exclude_patterns: Option<Vec<String>>,
+    /// Count the lines of text files (default: false).
+    #[json_schema(default = "false")]
+    pub count_lines: Option<bool>,
+    /// Skip files ignored by .gitignore and .ignore files (default: true).
+    #[json_schema(default = "true")]
+    pub respect_gitignore: Option<bool>,
+    /// Include hidden files and directories (default: false).
+    #[json_schema(default = "false")]
+    pub include_hidden: Option<bool>,
+    /// Specify the output format, accepts either `text` or `json` (default: text).
+    pub output_format: Option<OutputFormat>,
+}
+
+impl DirectoryStats {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let stats = context
+            .directory_stats(
+                Path::new(&params.path),
+                params.exclude_patterns.unwrap_or_default(),
+                params.count_lines.unwrap_or_default(),
+                WalkOptions::new(params.respect_gitignore, params.include_hidden, None),
+            )
+            .await
+            .map_err(CallToolError::new)?;
+
+        let content =
+            Self::format_output(&stats, params.output_format.unwrap_or(OutputFormat::Text))?;
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            content,
+        )]))
+    }
+
+    fn format_output(
+        stats: &DirectoryStatistics,
+        output_format: OutputFormat,
+    ) -> std::result::Result<String, CallToolError> {
+        if let OutputFormat::Json = output_format {
+            return serde_json::to_string_pretty(stats).map_err(CallToolError::new);
+        }
+
+        let mut output = format!(
+            "Files: {}, directories: {}, total size: {}",
+            stats.files,
+            stats.directories,
+            format_bytes(stats.bytes)
+        );
+        if let Some(lines) = stats.lines {
+            write!(output, ", lines: {lines}").map_err(CallToolError::new)?;
+        }
+        if stats.by_extension.is_empty() {
+            return Ok(output);
+        }
+
+        write!(
+            output,
+            "\n\n{:<20} {:>8} {:>12}",
+            "Extension", "Files", "Size"
+        )
+        .map_err(CallToolError::new)?;
+        if stats.lines.is_some() {
+            write!(output, " {:>10}", "Lines").map_err(CallToolError::new)?;
+        }
+        for extension in &stats.by_extension {
+            write!(
+                output,
+                "\n{:<20} {:>8} {:>12}",
+                extension.extension,
+                extension.files,
+                format_bytes(extension.bytes)
+            )
+            .map_err(CallToolError::new)?;
+            if let Some(lines) = extension.lines {
+                write!(output, " {lines:>10}").map_err(CallToolError::new)?;
+            }
+        }
+        Ok(output)
+    }
+}
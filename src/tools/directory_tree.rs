@@ -3,8 +3,25 @@ use rust_mcp_sdk::schema::TextContent;
 use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
 use serde_json::{Map, Value, json};
 
-use crate::error::ServiceError;
-use crate::fs_service::FileSystemService;
+use crate::error::{ServiceError, ServiceResult};
+use crate::fs_service::utils::{decode_cursor, encode_cursor, format_bytes};
+use crate::fs_service::{FileSystemService, TreeFilter, WalkOptions};
+
+/// Number of entries of a page when paginating without `max_entries`.
+const DEFAULT_PAGE_SIZE: usize = 500;
+
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub enum TreeOutputFormat {
+    /// Compact nested JSON.
+    #[serde(rename = "json")]
+    Json,
+    /// Indented drawing like the Unix `tree` command.
+    #[serde(rename = "tree")]
+    Tree,
+    /// One path relative to the root per line.
+    #[serde(rename = "flat")]
+    Flat,
+}
 
 #[mcp_tool(
     name = "directory_tree",
@@ -12,7 +29,18 @@ use crate::fs_service::FileSystemService;
     description = concat!("Get a recursive tree view of files and directories as a JSON structure, respect gitignore rules. ",
     "Use `max_depth` to limit dir depth, recommend default to 2 levels. ",
     "As a result, the returned directory structure may be incomplete or provide a skewed representation of the full directory tree, since deeper-level files and subdirectories beyond the specified depth will be excluded. ",
-    "Output format: JSON array with objects containing 'n' (name, dirs end with /), 'c' (children array for dirs). Compact format for token efficiency. Only works within allowed directories."),
+    "Output format: JSON array with objects containing 'n' (name, dirs end with /), 'c' (children array for dirs). Compact format for token efficiency. ",
+    "For huge trees, set `max_entries` to list the tree breadth-first in pages: each page is a JSON array of objects whose 'n' is the path relative to the root, ",
+    "and the 'next_cursor' value from the result '_meta' is passed as 'cursor' to fetch the next page. ",
+    "Set `output_format` to `tree` for an indented drawing like the Unix `tree` command, or to `flat` for one relative path per line; ",
+    "both take far fewer tokens than JSON. ",
+    "Use `excludePatterns` to leave out entries such as 'node_modules' or '**/target' with their contents, and `include_pattern` to only list the files matching a glob ",
+    "(matched against file names, or against relative paths when it contains '/'); directories are still listed. ",
+    "Set `max_output_bytes` to fit the output in a size budget: the deepest and largest subtrees are then replaced with entries such as {\"n\": \"dir/\", \"omitted\": 1234} ",
+    "counting the entries left out, until the output fits, and the elided directories are listed in the result '_meta'. ",
+    "Set `include_metadata` to annotate entries with 's' (size in bytes, files only) and 'm' (modification time, UTC). ",
+    "Set `respect_gitignore` to false to include git-ignored paths, and `include_hidden` to true to include dotfiles. `follow_symlinks` follows symlinks that stay within allowed directories, visiting each linked directory once. ",
+    "Only works within allowed directories."),
     destructive_hint = false,
     idempotent_hint = false,
     open_world_hint = false,
@@ -24,37 +52,114 @@ pub struct DirectoryTree {
     pub path: String,
     /// Limits the depth of directory traversal
     pub max_depth: Option<u64>,
+    /// Maximum number of entries per page. Enables breadth-first pagination (optional).
+    pub max_entries: Option<u64>,
+    /// Opaque continuation cursor returned as `next_cursor` in the `_meta` of a previous page.
+    pub cursor: Option<String>,
+    #[serde(rename = "excludePatterns")]
+    /// Optional list of glob patterns of entries to leave out, matched against paths relative to `path`.
+    pub exclude_patterns: Option<Vec<String>>,
+    /// Only list the files matching this glob, e.g. "*.rs" or "src/**/*.rs" (optional).
+    pub include_pattern: Option<String>,
+    /// Annotates entries with their size and modification time (default: false).
+    pub include_metadata: Option<bool>,
+    /// Skip paths ignored by .gitignore and .ignore files (default: true).
+    pub respect_gitignore: Option<bool>,
+    /// Include hidden files and directories (default: false).
+    pub include_hidden: Option<bool>,
+    /// Follow symlinks whose targets are inside the allowed directories (default: false).
+    pub follow_symlinks: Option<bool>,
+    /// Maximum size of the output in bytes; larger trees are shrunk by eliding subtrees (optional, ignored when paginating).
+    pub max_output_bytes: Option<u64>,
+    /// Output format: `json`, `tree` or `flat` (default: json).
+    #[json_schema(default = "json")]
+    pub output_format: Option<TreeOutputFormat>,
+}
+
+/// Resumable position within a paginated directory tree.
+#[derive(::serde::Deserialize, ::serde::Serialize)]
+struct DirectoryTreeCursor {
+    /// Number of entries already returned by previous pages.
+    offset: usize,
 }
 impl DirectoryTree {
+    fn filter(&self) -> ServiceResult<TreeFilter> {
+        TreeFilter::new(
+            self.exclude_patterns.as_deref(),
+            self.include_pattern.as_deref(),
+        )
+    }
+
     pub async fn run_tool(
         params: Self,
         context: &FileSystemService,
     ) -> std::result::Result<CallToolResult, CallToolError> {
+        if params.max_entries.is_some() || params.cursor.is_some() {
+            return Self::run_paginated(params, context).await;
+        }
+
         let mut entry_counter: usize = 0;
 
         let allowed_directories = context.allowed_directories().await;
 
         let (entries, reached_max_depth) = context
             .directory_tree(
-                params.path,
+                &params.path,
                 params.max_depth.map(|v| v as usize).or(Some(2)),
                 None,
+                params.include_metadata.unwrap_or_default(),
                 &mut entry_counter,
                 allowed_directories,
+                WalkOptions::new(
+                    params.respect_gitignore,
+                    params.include_hidden,
+                    params.follow_symlinks,
+                ),
+                &params.filter().map_err(CallToolError::new)?,
             )
             .map_err(CallToolError::new)?;
 
         if entry_counter == 0 {
-            return Err(CallToolError::new(ServiceError::FromString(
+            return Err(CallToolError::new(ServiceError::NoMatch(
                 "Could not find any entries".to_string(),
             )));
         }
 
-        let json_str = serde_json::to_string(&json!(entries)).map_err(CallToolError::new)?;
+        let output_format = params.output_format.unwrap_or(TreeOutputFormat::Json);
+        let render = |entries: &Value| match output_format {
+            TreeOutputFormat::Json => entries.to_string(),
+            TreeOutputFormat::Tree => {
+                let mut output = format!("{}\n", params.path);
+                render_tree(entries, "", &mut output);
+                output
+            }
+            TreeOutputFormat::Flat => {
+                let mut output = String::new();
+                render_flat(entries, "", &mut output);
+                output
+            }
+        };
+        let mut entries = entries;
+        let (output, elided) = match params.max_output_bytes {
+            Some(max_bytes) => shrink_to_budget(&mut entries, max_bytes as usize, render),
+            None => (render(&entries), vec![]),
+        };
 
+        let mut meta = Map::new();
+        if !elided.is_empty() {
+            let omitted: usize = elided.iter().map(|dir| dir.omitted).sum();
+            meta.insert("truncated".to_string(), Value::Bool(true));
+            meta.insert("omitted_entries".to_string(), omitted.into());
+            meta.insert(
+                "elided".to_string(),
+                elided
+                    .iter()
+                    .map(|dir| json!({ "path": dir.path, "omitted": dir.omitted }))
+                    .collect(),
+            );
+        }
         // Include meta flag to denote that max depth was hit; some files and directories might be omitted
-        let meta = if reached_max_depth {
-            let mut meta = Map::new();
+        if reached_max_depth {
             meta.insert(
                 "warning".to_string(),
                 Value::String(
@@ -62,11 +167,315 @@ impl DirectoryTree {
                         .to_string(),
                 ),
             );
-            Some(meta)
+        }
+
+        Ok(
+            CallToolResult::text_content(vec![TextContent::from(output)])
+                .with_meta((!meta.is_empty()).then_some(meta)),
+        )
+    }
+
+    /// Lists one breadth-first page of the tree.
+    async fn run_paginated(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let offset = match params.cursor.as_deref() {
+            Some(cursor) => {
+                decode_cursor::<DirectoryTreeCursor>(cursor)
+                    .map_err(CallToolError::new)?
+                    .offset
+            }
+            None => 0,
+        };
+        let limit = params
+            .max_entries
+            .map_or(DEFAULT_PAGE_SIZE, |max| max.max(1) as usize);
+
+        let page = context
+            .directory_tree_page(
+                &params.path,
+                params.max_depth.map(|v| v as usize).or(Some(2)),
+                offset,
+                limit,
+                params.include_metadata.unwrap_or_default(),
+                context.allowed_directories().await,
+                WalkOptions::new(
+                    params.respect_gitignore,
+                    params.include_hidden,
+                    params.follow_symlinks,
+                ),
+                &params.filter().map_err(CallToolError::new)?,
+            )
+            .map_err(CallToolError::new)?;
+
+        if page.entries.is_empty() && offset == 0 {
+            return Err(CallToolError::new(ServiceError::NoMatch(
+                "Could not find any entries".to_string(),
+            )));
+        }
+
+        // pages are flat by nature, so the `tree` format is listed like `flat`
+        let output =
+            if params.output_format.unwrap_or(TreeOutputFormat::Json) == TreeOutputFormat::Json {
+                serde_json::to_string(&page.entries).map_err(CallToolError::new)?
+            } else {
+                page.entries
+                    .iter()
+                    .map(entry_label)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+
+        let mut meta = Map::new();
+        if let Some(offset) = page.next_offset {
+            meta.insert("truncated".to_string(), Value::Bool(true));
+            meta.insert(
+                "next_cursor".to_string(),
+                Value::String(
+                    encode_cursor(&DirectoryTreeCursor { offset }).map_err(CallToolError::new)?,
+                ),
+            );
+        }
+        if page.reached_max_depth {
+            meta.insert(
+                "warning".to_string(),
+                Value::String(
+                    "Incomplete listing: subdirectories beyond the maximum depth were skipped."
+                        .to_string(),
+                ),
+            );
+        }
+
+        Ok(
+            CallToolResult::text_content(vec![TextContent::from(output)])
+                .with_meta((!meta.is_empty()).then_some(meta)),
+        )
+    }
+}
+
+fn entry_name(entry: &Value) -> &str {
+    entry.get("n").and_then(Value::as_str).unwrap_or_default()
+}
+
+/// Formats the size and modification time of an entry as ` [size, time]`, if present, and the
+/// number of entries elided from it.
+fn entry_annotation(entry: &Value) -> String {
+    let annotations: Vec<String> = [
+        entry.get("s").and_then(Value::as_u64).map(format_bytes),
+        entry.get("m").and_then(Value::as_str).map(str::to_string),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let mut annotation = if annotations.is_empty() {
+        String::new()
+    } else {
+        format!(" [{}]", annotations.join(", "))
+    };
+    if let Some(omitted) = entry.get("omitted").and_then(Value::as_u64) {
+        annotation.push_str(&format!(" ({omitted} entries omitted)"));
+    }
+    annotation
+}
+
+fn entry_label(entry: &Value) -> String {
+    format!("{}{}", entry_name(entry), entry_annotation(entry))
+}
+
+/// Appends `entries` to `output` as an indented drawing, like the Unix `tree` command.
+fn render_tree(entries: &Value, prefix: &str, output: &mut String) {
+    let Some(entries) = entries.as_array() else {
+        return;
+    };
+    for (index, entry) in entries.iter().enumerate() {
+        let is_last = index + 1 == entries.len();
+        let (branch, indent) = if is_last {
+            ("└── ", "    ")
         } else {
-            None
+            ("├── ", "│   ")
+        };
+        output.push_str(&format!("{prefix}{branch}{}\n", entry_label(entry)));
+        if let Some(children) = entry.get("c") {
+            render_tree(children, &format!("{prefix}{indent}"), output);
+        }
+    }
+}
+
+/// Appends the path relative to the root of each entry to `output`, one per line.
+fn render_flat(entries: &Value, parent: &str, output: &mut String) {
+    let Some(entries) = entries.as_array() else {
+        return;
+    };
+    for entry in entries {
+        // directory names already end with '/'
+        let path = format!("{parent}{}", entry_name(entry));
+        output.push_str(&format!("{path}{}\n", entry_annotation(entry)));
+        if let Some(children) = entry.get("c") {
+            render_flat(children, &path, output);
+        }
+    }
+}
+
+/// A directory whose contents were left out to fit the output in its budget.
+struct ElidedDirectory {
+    /// Path relative to the root.
+    path: String,
+    /// Number of entries below the directory.
+    omitted: usize,
+}
+
+/// A directory of the tree with children, addressed by its indices from the root.
+struct Subtree {
+    indices: Vec<usize>,
+    path: String,
+    depth: usize,
+    descendants: usize,
+}
+
+/// Shrinks `entries` until `render` returns at most `max_bytes`, replacing the children of
+/// directories with the number of entries omitted. The deepest levels are elided first, the
+/// largest directories of a level before the others; if the top-level entries alone are too
+/// large, the last ones are replaced with a single `...` entry. Returns the rendered output and
+/// the directories elided.
+fn shrink_to_budget(
+    entries: &mut Value,
+    max_bytes: usize,
+    render: impl Fn(&Value) -> String,
+) -> (String, Vec<ElidedDirectory>) {
+    let mut output = render(entries);
+    let mut elided: Vec<(Vec<usize>, ElidedDirectory)> = vec![];
+    let finish = |elided: Vec<(Vec<usize>, ElidedDirectory)>| {
+        elided.into_iter().map(|(_, dir)| dir).collect()
+    };
+    if output.len() <= max_bytes {
+        return (output, vec![]);
+    }
+
+    let mut subtrees = vec![];
+    collect_subtrees(entries, &mut vec![], "", 0, &mut subtrees);
+    let max_depth = subtrees.iter().map(|subtree| subtree.depth).max();
+    for depth in (0..=max_depth.unwrap_or(0)).rev() {
+        let mut level: Vec<&Subtree> = subtrees
+            .iter()
+            .filter(|subtree| subtree.depth == depth)
+            .collect();
+        level.sort_by(|a, b| b.descendants.cmp(&a.descendants));
+
+        // elide 1, 2, 4, ... directories at a time to render the tree only a few times
+        let mut start = 0;
+        let mut batch = 1;
+        while start < level.len() {
+            let end = (start + batch).min(level.len());
+            for subtree in &level[start..end] {
+                if let Some(entry) = entry_at_mut(entries, &subtree.indices)
+                    && let Some(entry) = entry.as_object_mut()
+                {
+                    entry.remove("c");
+                    entry.insert("omitted".to_string(), subtree.descendants.into());
+                }
+                // directories elided inside this one are now part of it
+                elided.retain(|(indices, _)| !indices.starts_with(&subtree.indices));
+                elided.push((
+                    subtree.indices.clone(),
+                    ElidedDirectory {
+                        path: subtree.path.clone(),
+                        omitted: subtree.descendants,
+                    },
+                ));
+            }
+            output = render(entries);
+            if output.len() <= max_bytes {
+                return (output, finish(elided));
+            }
+            start = end;
+            batch *= 2;
+        }
+    }
+
+    // only top-level entries are left, keep as many as fit
+    let Some(top_level) = entries.as_array().cloned() else {
+        return (output, finish(elided));
+    };
+    let mut kept = top_level.len();
+    let mut cut = None;
+    while output.len() > max_bytes && kept > 0 {
+        kept /= 2;
+        let omitted = count_entries(&Value::Array(top_level[kept..].to_vec()));
+        let mut shrunk = top_level[..kept].to_vec();
+        shrunk.push(json!({ "n": "...", "omitted": omitted }));
+        *entries = Value::Array(shrunk);
+        output = render(entries);
+        cut = Some((kept, omitted));
+    }
+    if let Some((kept, omitted)) = cut {
+        elided.retain(|(indices, _)| indices[0] < kept);
+        elided.push((
+            vec![kept],
+            ElidedDirectory {
+                path: "...".to_string(),
+                omitted,
+            },
+        ));
+    }
+    (output, finish(elided))
+}
+
+/// Counts `entries` and their descendants, including those already elided.
+fn count_entries(entries: &Value) -> usize {
+    entries.as_array().map_or(0, |entries| {
+        entries
+            .iter()
+            .map(|entry| {
+                let omitted = entry
+                    .get("omitted")
+                    .and_then(Value::as_u64)
+                    .unwrap_or_default() as usize;
+                1 + omitted + entry.get("c").map_or(0, count_entries)
+            })
+            .sum()
+    })
+}
+
+/// Collects the directories of `entries` that have children, `indices` and `parent` leading to
+/// them from the root.
+fn collect_subtrees(
+    entries: &Value,
+    indices: &mut Vec<usize>,
+    parent: &str,
+    depth: usize,
+    subtrees: &mut Vec<Subtree>,
+) {
+    let Some(entries) = entries.as_array() else {
+        return;
+    };
+    for (index, entry) in entries.iter().enumerate() {
+        let Some(children) = entry
+            .get("c")
+            .filter(|children| count_entries(children) > 0)
+        else {
+            continue;
         };
+        // directory names already end with '/'
+        let path = format!("{parent}{}", entry_name(entry));
+        indices.push(index);
+        subtrees.push(Subtree {
+            indices: indices.clone(),
+            path: path.clone(),
+            depth,
+            descendants: count_entries(children),
+        });
+        collect_subtrees(children, indices, &path, depth + 1, subtrees);
+        indices.pop();
+    }
+}
 
-        Ok(CallToolResult::text_content(vec![TextContent::from(json_str)]).with_meta(meta))
+/// Returns the entry reached by following `indices` from the root entries.
+fn entry_at_mut<'a>(entries: &'a mut Value, indices: &[usize]) -> Option<&'a mut Value> {
+    let (first, rest) = indices.split_first()?;
+    let mut entry = entries.get_mut(*first)?;
+    for index in rest {
+        entry = entry.get_mut("c")?.get_mut(*index)?;
     }
+    Some(entry)
 }
@@ -4,17 +4,87 @@ use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
 use rust_mcp_sdk::schema::TextContent;
 use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
 
-use crate::fs_service::FileSystemService;
+use crate::fs_service::{EditResult, FileSystemService};
+use serde_json::Value;
 
-#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, Default, JsonSchema)]
 /// Represents a text replacement operation.
+/// The text to replace is either quoted in `oldText`, or given as a range of lines with
+/// `start_line` and `end_line`.
 pub struct EditOperation {
     /// Text to search for - must match exactly.
-    #[serde(rename = "oldText")]
+    #[serde(rename = "oldText", default)]
     pub old_text: String,
     #[serde(rename = "newText")]
     /// Text to replace the matched text with.
     pub new_text: String,
+    /// First line (1-based) of the lines to replace, instead of quoting them in `oldText`.
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub start_line: Option<u64>,
+    /// Last line (1-based, inclusive) of the lines to replace. Defaults to `start_line`.
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub end_line: Option<u64>,
+    /// SHA-256 hash (hex) of the lines from `start_line` to `end_line` joined with '\n',
+    /// without the final line break. When set, the edit fails if those lines changed.
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub range_sha256: Option<String>,
+    /// Which occurrences of `oldText` to replace when it appears more than once: "first"
+    /// (default), "all", or the 1-based number of the occurrence to replace.
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub occurrence: Option<Occurrence>,
+    /// How `oldText` is matched: "exact", "whitespace_insensitive" (default, falls back to
+    /// comparing lines with their surrounding whitespace trimmed) or "fuzzy" (also falls back
+    /// to the most similar blocks of lines).
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub match_mode: Option<MatchMode>,
+    /// Minimum similarity, between 0 and 1, of a block matched in "fuzzy" mode. Defaults to 0.9.
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub similarity_threshold: Option<f64>,
+}
+
+#[derive(
+    ::serde::Deserialize, ::serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema,
+)]
+/// How the text to replace is matched against the file content.
+pub enum MatchMode {
+    /// Only the exact text.
+    #[serde(rename = "exact")]
+    Exact,
+    /// The exact text, or lines equal once their surrounding whitespace is trimmed.
+    #[default]
+    #[serde(rename = "whitespace_insensitive")]
+    WhitespaceInsensitive,
+    /// Like `whitespace_insensitive`, then blocks of lines similar enough to the text.
+    #[serde(rename = "fuzzy")]
+    Fuzzy,
+}
+
+#[derive(
+    ::serde::Deserialize, ::serde::Serialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema,
+)]
+#[serde(untagged)]
+/// The occurrences of the text to replace that an edit applies to.
+pub enum Occurrence {
+    /// The first or all occurrences.
+    Mode(OccurrenceMode),
+    /// Only the nth occurrence, counting from 1.
+    Nth(u64),
+}
+
+impl Default for Occurrence {
+    fn default() -> Self {
+        Occurrence::Mode(OccurrenceMode::First)
+    }
+}
+
+#[derive(
+    ::serde::Deserialize, ::serde::Serialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema,
+)]
+pub enum OccurrenceMode {
+    #[serde(rename = "first")]
+    First,
+    #[serde(rename = "all")]
+    All,
 }
 
 #[mcp_tool(
@@ -22,7 +92,10 @@ pub struct EditOperation {
     title="Edit file",
     description = concat!("Make line-based edits to a text file. ",
     "Each edit replaces exact line sequences with new content. ",
-    "Returns a git-style diff showing the changes made. ",
+    "When the text to replace appears more than once, 'occurrence' selects the first (default), all or the nth occurrence. ",
+    "'match_mode' controls how the text is matched: exactly, ignoring whitespace around lines (default), or fuzzily above a 'similarity_threshold'. ",
+    "Instead of quoting the text to replace, an edit can give a 'start_line'/'end_line' range, optionally checked against the 'range_sha256' of those lines. ",
+    "Returns a git-style diff showing the changes made, followed by the number of occurrences found and replaced by each edit and how they matched. ",
     "Only works within allowed directories."),
     destructive_hint = false,
     idempotent_hint = false,
@@ -43,6 +116,14 @@ pub struct EditFile {
         skip_serializing_if = "std::option::Option::is_none"
     )]
     pub dry_run: Option<bool>,
+    /// Save the previous content of the file as '<name>.bak' (or into the server's backup
+    /// directory) before editing it.
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub backup: Option<bool>,
+    /// SHA-256 hash (hex) of the file content as last read. The edits are only applied if the
+    /// file still has this content, otherwise a conflict error is returned.
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub if_match_sha256: Option<String>,
 }
 
 impl EditFile {
@@ -51,10 +132,30 @@ impl EditFile {
         context: &FileSystemService,
     ) -> std::result::Result<CallToolResult, CallToolError> {
         let diff = context
-            .apply_file_edits(Path::new(&params.path), params.edits, params.dry_run, None)
+            .apply_file_edits(
+                Path::new(&params.path),
+                params.edits,
+                params.dry_run,
+                None,
+                params.backup.unwrap_or(false),
+                params.if_match_sha256.as_deref(),
+            )
             .await
             .map_err(CallToolError::new)?;
 
-        Ok(CallToolResult::text_content(vec![TextContent::from(diff)]))
+        edit_result(diff)
     }
 }
+
+/// Returns the diff of an edit as text, with its summary as structured content.
+pub(crate) fn edit_result(result: EditResult) -> Result<CallToolResult, CallToolError> {
+    let Value::Object(summary) =
+        serde_json::to_value(&result.summary).map_err(CallToolError::new)?
+    else {
+        return Err(CallToolError::from_message("Invalid diff summary."));
+    };
+    Ok(
+        CallToolResult::text_content(vec![TextContent::from(result.diff)])
+            .with_structured_content(summary),
+    )
+}
@@ -0,0 +1,86 @@
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::fs_service::{
+    BrokenSymlink, BrokenSymlinkReason, FileSystemService, WalkOptions, utils::OutputFormat,
+};
+
+#[mcp_tool(
+    name = "find_broken_symlinks",
+    title="Find broken symlinks",
+    description = concat!("Recursively finds symlinks within the given path whose targets no longer exist ",
+    "or point outside the allowed directories, e.g. dead links that break builds. ",
+    "Symlinks are not followed. The optional exclude_patterns argument accepts glob-style patterns to exclude specific paths from the search. ",
+    "Respects gitignore rules and skips hidden files unless `respect_gitignore` is false or `include_hidden` is true. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct FindBrokenSymlinks {
+    /// The directory path to search in.
+    pub path: String,
+    /// Optional list of glob patterns to exclude from the search.
+    pub exclude_patterns: Option<Vec<String>>,
+    /// Skip files ignored by .gitignore and .ignore files (default: true).
+    #[json_schema(default = "true")]
+    pub respect_gitignore: Option<bool>,
+    /// Include hidden files and directories (default: false).
+    #[json_schema(default = "false")]
+    pub include_hidden: Option<bool>,
+    /// Specify the output format, accepts either `text` or `json` (default: text).
+    pub output_format: Option<OutputFormat>,
+}
+
+impl FindBrokenSymlinks {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let broken = context
+            .find_broken_symlinks(
+                Path::new(&params.path),
+                params.exclude_patterns,
+                WalkOptions::new(params.respect_gitignore, params.include_hidden, None),
+            )
+            .await
+            .map_err(CallToolError::new)?;
+
+        let content =
+            Self::format_output(&broken, params.output_format.unwrap_or(OutputFormat::Text))?;
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            content,
+        )]))
+    }
+
+    fn format_output(
+        broken: &[BrokenSymlink],
+        output_format: OutputFormat,
+    ) -> std::result::Result<String, CallToolError> {
+        if let OutputFormat::Json = output_format {
+            return serde_json::to_string_pretty(broken).map_err(CallToolError::new);
+        }
+
+        if broken.is_empty() {
+            return Ok("No broken symlinks were found.".to_string());
+        }
+        let mut output = format!("Found {} broken symlinks:\n", broken.len());
+        for link in broken {
+            let reason = match link.reason {
+                BrokenSymlinkReason::MissingTarget => "target does not exist",
+                BrokenSymlinkReason::OutsideAllowedDirectories => {
+                    "target is outside allowed directories"
+                }
+            };
+            writeln!(output, "  {} -> {} ({reason})", link.path, link.target)
+                .map_err(CallToolError::new)?;
+        }
+        Ok(output)
+    }
+}
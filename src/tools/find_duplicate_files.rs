@@ -1,4 +1,7 @@
-use crate::fs_service::{FileSystemService, utils::OutputFormat};
+use crate::fs_service::{
+    DuplicateAction, DuplicateResolution, FileSystemService, OperationContext,
+    utils::{OutputFormat, format_bytes},
+};
 use rust_mcp_sdk::{
     macros::{JsonSchema, mcp_tool},
     schema::{CallToolResult, TextContent, schema_utils::CallToolError},
@@ -14,11 +17,17 @@ use std::{collections::BTreeMap, fmt::Write};
     "Optional `exclude_patterns` can be used to exclude certain files matching a glob.",
     "`min_bytes` and `max_bytes` are optional arguments that can be used to restrict the search to files with sizes within a specified range.",
     "The output_format argument specifies the format of the output and accepts either `text` or `json` (default: text).",
+    "Hard links of the same file share their storage and are not reported as duplicates of each other unless `skip_hardlinks` is false.",
+    "Set `action` to reclaim space: `delete_newest` keeps the oldest file of each group and deletes the others, ",
+    "`delete_oldest` keeps the newest one, `hardlink` keeps the oldest one and replaces the others with hard links to it (default: `report`). ",
+    "Actions require write access and only return the plan unless `dry_run` is set to false, review it before applying it. ",
+    "Deleted items are moved to the trash when the server runs with `--use-trash`. ",
+    "Optional `concurrency` limits the number of files hashed at the same time (default: the number of CPUs).",
     "Only works within allowed directories."),
-    destructive_hint = false,
+    destructive_hint = true,
     idempotent_hint = false,
     open_world_hint = false,
-    read_only_hint = true
+    read_only_hint = false
 )]
 #[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
 pub struct FindDuplicateFiles {
@@ -33,12 +42,76 @@ pub struct FindDuplicateFiles {
     pub min_bytes: Option<u64>,
     /// Maximum file size (in bytes) to include in the search (optional).
     pub max_bytes: Option<u64>,
+    /// Consider only one of the hard links of a file (default: true).
+    #[json_schema(default = "true")]
+    pub skip_hardlinks: Option<bool>,
+    /// Maximum number of files hashed at the same time (default: the number of CPUs).
+    pub concurrency: Option<u64>,
+    /// What to do with the duplicates: `report`, `delete_newest`, `delete_oldest` or `hardlink` (default: report).
+    #[json_schema(default = "report")]
+    pub action: Option<DuplicateAction>,
+    /// Only return the plan of the `action` without applying it (default: true).
+    #[json_schema(default = "true")]
+    pub dry_run: Option<bool>,
     /// Specify the output format, accepts either `text` or `json` (default: text).
     #[json_schema(default = "text")]
     pub output_format: Option<OutputFormat>,
 }
 
 impl FindDuplicateFiles {
+    /// Returns true if the call deletes or replaces files, or plans to.
+    pub fn modifies_files(&self) -> bool {
+        self.action
+            .is_some_and(|action| action != DuplicateAction::Report)
+    }
+
+    fn format_resolutions(
+        resolutions: &[DuplicateResolution],
+        action: DuplicateAction,
+        dry_run: bool,
+        output_format: OutputFormat,
+    ) -> std::result::Result<String, CallToolError> {
+        if let OutputFormat::Json = output_format {
+            return serde_json::to_string_pretty(&serde_json::json!({
+                "action": action,
+                "dry_run": dry_run,
+                "groups": resolutions,
+            }))
+            .map_err(CallToolError::new);
+        }
+
+        let verb = match (action, dry_run) {
+            (DuplicateAction::Hardlink, true) => "Link",
+            (DuplicateAction::Hardlink, false) => "Linked",
+            (_, true) => "Delete",
+            (_, false) => "Deleted",
+        };
+        let mut output = String::new();
+        for (i, resolution) in resolutions.iter().enumerate() {
+            writeln!(output, "Duplicated Group {}:", i + 1).map_err(CallToolError::new)?;
+            writeln!(output, "  Keep: {}", resolution.kept).map_err(CallToolError::new)?;
+            for file in &resolution.resolved {
+                writeln!(output, "  {verb}: {file}").map_err(CallToolError::new)?;
+            }
+            for (file, error) in &resolution.errors {
+                writeln!(output, "  Failed: {file} ({error})").map_err(CallToolError::new)?;
+            }
+            output.push('\n');
+        }
+        let files: usize = resolutions.iter().map(|r| r.resolved.len()).sum();
+        let reclaimed = format_bytes(resolutions.iter().map(|r| r.reclaimed_bytes).sum());
+        if dry_run {
+            write!(
+                output,
+                "Dry run: {files} file(s) would be resolved, reclaiming {reclaimed}. Set `dry_run` to false to apply the plan."
+            )
+        } else {
+            write!(output, "Resolved {files} file(s), reclaimed {reclaimed}.")
+        }
+        .map_err(CallToolError::new)?;
+        Ok(output)
+    }
+
     fn format_output(
         duplicate_files: Vec<Vec<String>>,
         output_format: OutputFormat,
@@ -80,6 +153,15 @@ impl FindDuplicateFiles {
     pub async fn run_tool(
         params: Self,
         context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        Self::run_tool_with_context(params, context, &OperationContext::default()).await
+    }
+
+    /// Finds the duplicates, stopping early once `operation` is cancelled.
+    pub async fn run_tool_with_context(
+        params: Self,
+        context: &FileSystemService,
+        operation: &OperationContext,
     ) -> std::result::Result<CallToolResult, CallToolError> {
         let duplicate_files = context
             .find_duplicate_files(
@@ -88,15 +170,25 @@ impl FindDuplicateFiles {
                 params.exclude_patterns.clone(),
                 params.min_bytes.or(Some(1)),
                 params.max_bytes,
+                params.skip_hardlinks.unwrap_or(true),
+                params.concurrency.map(|v| v as usize),
+                operation,
             )
             .await
             .map_err(CallToolError::new)?;
 
-        let result_content = Self::format_output(
-            duplicate_files,
-            params.output_format.unwrap_or(OutputFormat::Text),
-        )
-        .map_err(CallToolError::new)?;
+        let output_format = params.output_format.unwrap_or(OutputFormat::Text);
+        let action = params.action.unwrap_or(DuplicateAction::Report);
+        let result_content = if action == DuplicateAction::Report || duplicate_files.is_empty() {
+            Self::format_output(duplicate_files, output_format)?
+        } else {
+            let dry_run = params.dry_run.unwrap_or(true);
+            let resolutions = context
+                .resolve_duplicates(duplicate_files, action, dry_run)
+                .await
+                .map_err(CallToolError::new)?;
+            Self::format_resolutions(&resolutions, action, dry_run, output_format)?
+        };
 
         Ok(CallToolResult::text_content(vec![TextContent::from(
             result_content,
@@ -5,7 +5,7 @@ use rust_mcp_sdk::{
 use std::fmt::Write;
 use std::path::Path;
 
-use crate::fs_service::{FileSystemService, utils::OutputFormat};
+use crate::fs_service::{EmptyDirectoryRemoval, FileSystemService, utils::OutputFormat};
 
 // find_empty_directories
 #[mcp_tool(
@@ -15,11 +15,14 @@ use crate::fs_service::{FileSystemService, utils::OutputFormat};
     "A directory is considered empty if it contains no files in itself or any of its subdirectories.",
     "Operating system metadata files `.DS_Store` (macOS) and `Thumbs.db` (Windows) will be ignored.",
     "The optional exclude_patterns argument accepts glob-style patterns to exclude specific paths from the search.",
+    "Set `delete` to true to remove the directories that were found, deepest first (requires write access). ",
+    "Deleted items are moved to the trash when the server runs with `--use-trash`. ",
+    "Directories that still contain hidden or ignored files are skipped and reported with the reason.",
     "Only works within allowed directories."),
-    destructive_hint = false,
+    destructive_hint = true,
     idempotent_hint = false,
     open_world_hint = false,
-    read_only_hint = true
+    read_only_hint = false
 )]
 #[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
 pub struct FindEmptyDirectories {
@@ -29,6 +32,9 @@ pub struct FindEmptyDirectories {
     pub exclude_patterns: Option<Vec<String>>,
     /// Specify the output format, accepts either `text` or `json` (default: text).
     pub output_format: Option<OutputFormat>,
+    /// Remove the empty directories that were found (default: false).
+    #[json_schema(default = "false")]
+    pub delete: Option<bool>,
 }
 
 impl FindEmptyDirectories {
@@ -41,9 +47,16 @@ impl FindEmptyDirectories {
             .await
             .map_err(CallToolError::new)?;
 
-        let content =
-            Self::format_output(result, params.output_format.unwrap_or(OutputFormat::Text))
+        let output_format = params.output_format.unwrap_or(OutputFormat::Text);
+        let content = if params.delete.unwrap_or(false) {
+            let removal = context
+                .delete_empty_directories(result)
+                .await
                 .map_err(CallToolError::new)?;
+            Self::format_removal(removal, output_format)?
+        } else {
+            Self::format_output(result, output_format).map_err(CallToolError::new)?
+        };
 
         Ok(CallToolResult::text_content(vec![TextContent::from(
             content,
@@ -85,4 +98,26 @@ impl FindEmptyDirectories {
 
         Ok(output)
     }
+
+    fn format_removal(
+        removal: EmptyDirectoryRemoval,
+        output_format: OutputFormat,
+    ) -> std::result::Result<String, CallToolError> {
+        if let OutputFormat::Json = output_format {
+            return serde_json::to_string_pretty(&removal).map_err(CallToolError::new);
+        }
+
+        let mut output = format!("Removed {} empty directories.\n", removal.removed.len());
+        for dir in &removal.removed {
+            writeln!(output, "  {dir}").map_err(CallToolError::new)?;
+        }
+        if !removal.skipped.is_empty() {
+            writeln!(output, "Skipped {} directories:", removal.skipped.len())
+                .map_err(CallToolError::new)?;
+            for (dir, reason) in &removal.skipped {
+                writeln!(output, "  {dir} ({reason})").map_err(CallToolError::new)?;
+            }
+        }
+        Ok(output)
+    }
 }
@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
+use rust_mcp_sdk::schema::TextContent;
+use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
+
+use crate::fs_service::FileSystemService;
+
+const DEFAULT_MAX_RESULTS: u64 = 20;
+
+#[mcp_tool(
+    name = "fuzzy_find_file",
+    title="Fuzzy find file",
+    description = concat!("Finds files whose path fuzzy-matches a query (fzf-style) and returns the best matching paths, ranked by score. ",
+    "Characters of the query must appear in order, but not necessarily next to each other, e.g. 'hndlr' matches 'src/handler.rs'. ",
+    "Use this tool when you only partially remember a file name and a glob pattern would be too strict. ",
+    "Respects gitignore rules. Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct FuzzyFindFile {
+    /// The directory path to search in.
+    pub path: String,
+    /// The fuzzy query to match against file paths (e.g., "usrctrl" for "user_controller.ts").
+    pub query: String,
+    /// Maximum number of paths to return (default: 20).
+    #[json_schema(default = "20")]
+    pub max_results: Option<u64>,
+    #[serde(rename = "excludePatterns")]
+    /// Optional list of glob patterns to exclude from the search.
+    pub exclude_patterns: Option<Vec<String>>,
+}
+
+impl FuzzyFindFile {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let matches = context
+            .fuzzy_find_files(
+                Path::new(&params.path),
+                &params.query,
+                params.exclude_patterns,
+                params.max_results.unwrap_or(DEFAULT_MAX_RESULTS) as usize,
+            )
+            .await
+            .map_err(CallToolError::new)?;
+
+        let result = if !matches.is_empty() {
+            matches
+                .iter()
+                .map(|m| m.path.display().to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        } else {
+            "No matches found".to_string()
+        };
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            result,
+        )]))
+    }
+}
@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+
+use crate::fs_service::{CodeSymbol, FileSystemService};
+
+#[mcp_tool(
+    name = "get_code_outline",
+    title="Get the outline of a source file",
+    description = concat!("Lists the symbols declared in a source file: functions, methods, classes, structs, enums, traits, interfaces, modules, etc. ",
+    "Each symbol is shown with its kind, name and line range, indented by nesting level, giving a table of contents of the file ",
+    "that is far cheaper than reading it entirely. Use read_file_lines to read a specific symbol afterwards. ",
+    "The language is detected from the file extension unless specified. ",
+    "Supported languages: rust, python, javascript, typescript, tsx, go, java, csharp, c, cpp, kotlin, swift, scala, ruby, php, lua. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct GetCodeOutline {
+    /// The path of the source file.
+    pub path: String,
+    /// Optional programming language (e.g., 'rust', 'python'). Detected from the file extension when omitted.
+    pub language: Option<String>,
+}
+
+impl GetCodeOutline {
+    fn format_output(symbols: &[CodeSymbol]) -> String {
+        if symbols.is_empty() {
+            return "No symbols found.".to_string();
+        }
+        symbols
+            .iter()
+            .map(|s| {
+                format!(
+                    "{}{} {} (lines {}-{})",
+                    "  ".repeat(s.depth),
+                    s.kind,
+                    s.name,
+                    s.start_line,
+                    s.end_line
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let symbols = context
+            .get_code_outline(Path::new(&params.path), params.language.as_deref())
+            .await
+            .map_err(CallToolError::new)?;
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            Self::format_output(&symbols),
+        )]))
+    }
+}
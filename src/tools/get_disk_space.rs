@@ -0,0 +1,87 @@
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+use crate::fs_service::{
+    FileSystemService,
+    utils::{OutputFormat, format_bytes},
+};
+
+#[mcp_tool(
+    name = "get_disk_space",
+    title="Get disk space",
+    description = concat!("Reports the total, free and available bytes and the filesystem type ",
+    "of the filesystem holding each allowed directory, or only the one holding `path` if given. ",
+    "Available bytes exclude the space reserved for privileged users and are what the server can write. ",
+    "Use this to check capacity before writing large files. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct GetDiskSpace {
+    /// Optional path to report on instead of every allowed directory.
+    pub path: Option<String>,
+    /// Specify the output format, accepts either `text` or `json` (default: text).
+    pub output_format: Option<OutputFormat>,
+}
+
+impl GetDiskSpace {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let paths: Vec<PathBuf> = match &params.path {
+            Some(path) => vec![PathBuf::from(path)],
+            None => context.allowed_directories().await.to_vec(),
+        };
+
+        let mut lines = Vec::with_capacity(paths.len());
+        let mut entries = Vec::with_capacity(paths.len());
+        for path in &paths {
+            match context.disk_space(Path::new(path)).await {
+                Ok(space) => {
+                    lines.push(format!(
+                        "{}: {} available, {} free of {}{}",
+                        path.display(),
+                        format_bytes(space.available_bytes),
+                        format_bytes(space.free_bytes),
+                        format_bytes(space.total_bytes),
+                        space
+                            .filesystem
+                            .as_ref()
+                            .map(|filesystem| format!(" ({filesystem})"))
+                            .unwrap_or_default()
+                    ));
+                    let mut entry = serde_json::to_value(&space).map_err(CallToolError::new)?;
+                    entry["path"] = json!(path);
+                    entries.push(entry);
+                }
+                // a single requested path fails the call, a failing allowed directory is reported
+                Err(err) if params.path.is_some() => return Err(CallToolError::new(err)),
+                Err(err) => {
+                    lines.push(format!("{}: {err}", path.display()));
+                    entries.push(json!({ "path": path, "error": err.to_string() }));
+                }
+            }
+        }
+
+        let content = match params.output_format.unwrap_or(OutputFormat::Text) {
+            OutputFormat::Json => {
+                serde_json::to_string_pretty(&entries).map_err(CallToolError::new)?
+            }
+            OutputFormat::Text if lines.is_empty() => {
+                "Allowed directories list is empty!".to_string()
+            }
+            OutputFormat::Text => lines.join("\n"),
+        };
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            content,
+        )]))
+    }
+}
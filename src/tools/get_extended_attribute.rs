@@ -0,0 +1,55 @@
+use base64::{Engine, engine::general_purpose};
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+use serde_json::{Map, json};
+use std::path::Path;
+
+use crate::fs_service::FileSystemService;
+
+#[mcp_tool(
+    name = "get_extended_attribute",
+    title="Get extended attribute",
+    description = concat!("Reads the value of an extended attribute (xattr) of a file or directory, ",
+    "or of an NTFS alternate data stream on Windows. ",
+    "The value is returned as text if it is valid UTF-8, base64 encoded otherwise. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct GetExtendedAttribute {
+    /// The path of the file or directory.
+    pub path: String,
+    /// The name of the attribute, e.g. 'user.comment' or 'com.apple.quarantine'.
+    pub name: String,
+}
+
+impl GetExtendedAttribute {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let value = context
+            .get_extended_attribute(Path::new(&params.path), &params.name)
+            .await
+            .map_err(CallToolError::new)?;
+
+        let size = value.len();
+        let (encoding, value) = match String::from_utf8(value) {
+            Ok(text) => ("utf8", text),
+            Err(err) => ("base64", general_purpose::STANDARD.encode(err.into_bytes())),
+        };
+
+        let mut structured = Map::new();
+        structured.insert("name".to_string(), json!(params.name));
+        structured.insert("value".to_string(), json!(value));
+        structured.insert("encoding".to_string(), json!(encoding));
+        structured.insert("size".to_string(), json!(size));
+        Ok(CallToolResult::text_content(vec![TextContent::from(value)])
+            .with_structured_content(structured))
+    }
+}
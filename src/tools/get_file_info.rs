@@ -11,7 +11,9 @@ use crate::fs_service::FileSystemService;
     title="Get file info",
     description = concat!("Retrieve detailed metadata about a file or directory. ",
     "Returns comprehensive information including size, creation time, ",
-    "last modified time, permissions, and type. ",
+    "last modified time, permissions, and type, as well as owner and group, inode, device, ",
+    "hard link count, symlink target and MIME type where the platform provides them. ",
+    "Symlinks are followed unless 'follow_symlink' is false, which describes the link itself. ",
     "This tool is perfect for understanding file characteristics without ",
     "reading the actual content. Only works within allowed directories."),
     destructive_hint = false,
@@ -23,6 +25,9 @@ use crate::fs_service::FileSystemService;
 pub struct GetFileInfo {
     /// The path of the file to get information for.
     pub path: String,
+    /// Whether a symlink is described by its target (default) or by the link itself.
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub follow_symlink: Option<bool>,
 }
 
 impl GetFileInfo {
@@ -31,7 +36,10 @@ impl GetFileInfo {
         context: &FileSystemService,
     ) -> std::result::Result<CallToolResult, CallToolError> {
         let stats = context
-            .get_file_stats(Path::new(&params.path))
+            .get_file_stats(
+                Path::new(&params.path),
+                params.follow_symlink.unwrap_or(true),
+            )
             .await
             .map_err(CallToolError::new)?;
         Ok(CallToolResult::text_content(vec![TextContent::from(
@@ -0,0 +1,65 @@
+use std::path::Path;
+
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+
+use crate::fs_service::{FileSystemService, MarkdownHeading};
+
+#[mcp_tool(
+    name = "get_markdown_outline",
+    title="Get the outline of a Markdown file",
+    description = concat!("Parses a Markdown file and returns its heading hierarchy, indented by level. ",
+    "Each heading includes the range of lines covered by its section (up to the next heading of the same or higher level), ",
+    "so the relevant section can then be read with read_file_lines. ",
+    "Headings inside fenced code blocks and front matter are ignored. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct GetMarkdownOutline {
+    /// The path of the Markdown file.
+    pub path: String,
+    /// Optional maximum heading level to include (1-6), e.g. 2 returns only '#' and '##' headings.
+    pub max_level: Option<u8>,
+}
+
+impl GetMarkdownOutline {
+    fn format_output(headings: &[MarkdownHeading]) -> String {
+        if headings.is_empty() {
+            return "No headings found.".to_string();
+        }
+        headings
+            .iter()
+            .map(|h| {
+                format!(
+                    "{}{} {} (lines {}-{})",
+                    "  ".repeat(h.level as usize - 1),
+                    "#".repeat(h.level as usize),
+                    h.text,
+                    h.line,
+                    h.end_line
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let headings = context
+            .markdown_outline(Path::new(&params.path), params.max_level)
+            .await
+            .map_err(CallToolError::new)?;
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            Self::format_output(&headings),
+        )]))
+    }
+}
@@ -0,0 +1,45 @@
+use std::path::Path;
+
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+
+use crate::fs_service::FileSystemService;
+
+#[mcp_tool(
+    name = "get_media_info",
+    title="Get media file information",
+    description = concat!("Returns information about an image or audio file as JSON without returning its content. ",
+    "For images: format, width, height, color type, bits per pixel and EXIF fields (camera, orientation, date taken, GPS, etc.). ",
+    "For audio: duration, bitrate, sample rate, channels and bit depth. ",
+    "Only file headers are parsed, so this is much cheaper than read_media_file when the content itself is not needed. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct GetMediaInfo {
+    /// The path of the image or audio file.
+    pub path: String,
+}
+
+impl GetMediaInfo {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let info = context
+            .get_media_info(Path::new(&params.path))
+            .await
+            .map_err(CallToolError::new)?;
+
+        let content = serde_json::to_string_pretty(&info).map_err(CallToolError::new)?;
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            content,
+        )]))
+    }
+}
@@ -0,0 +1,126 @@
+use crate::error::ServiceResult;
+use crate::fs_service::FileSystemService;
+use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
+use rust_mcp_sdk::schema::TextContent;
+use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+#[mcp_tool(
+    name = "get_server_stats",
+    title="Get server statistics",
+    description = concat!("Reports statistics of this server since it started: the uptime and, for each tool, ",
+    "the number of calls, the number of failed calls, the bytes returned by read-only tools ",
+    "and the bytes of content passed to tools that write files. ",
+    "Useful to spot tools that are called over and over."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct GetServerStats {}
+
+/// Counters of a single tool.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ToolStats {
+    pub calls: u64,
+    pub errors: u64,
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+}
+
+/// Per-tool metrics collected by the handler for every tool call.
+pub struct ServerMetrics {
+    started: Instant,
+    tools: Mutex<BTreeMap<String, ToolStats>>,
+}
+
+impl Default for ServerMetrics {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            tools: Mutex::new(BTreeMap::new()),
+        }
+    }
+}
+
+impl ServerMetrics {
+    /// Records one call of `tool_name`.
+    pub fn record(&self, tool_name: &str, failed: bool, bytes_read: u64, bytes_written: u64) {
+        let Ok(mut tools) = self.tools.lock() else {
+            return;
+        };
+        let stats = tools.entry(tool_name.to_string()).or_default();
+        stats.calls += 1;
+        stats.errors += u64::from(failed);
+        stats.bytes_read += bytes_read;
+        stats.bytes_written += bytes_written;
+    }
+
+    /// Returns the counters of every tool that was called, ordered by tool name.
+    pub fn tool_stats(&self) -> BTreeMap<String, ToolStats> {
+        self.tools
+            .lock()
+            .map(|tools| tools.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn uptime_secs(&self) -> u64 {
+        self.started.elapsed().as_secs()
+    }
+}
+
+/// Size of the content a tool call asks to write: the `content` argument and the `newText` of
+/// each entry of `edits`.
+pub fn written_size(arguments: Option<&Map<String, Value>>) -> u64 {
+    let Some(arguments) = arguments else {
+        return 0;
+    };
+    let content = arguments
+        .get("content")
+        .and_then(Value::as_str)
+        .map_or(0, str::len);
+    let edits: usize = arguments
+        .get("edits")
+        .and_then(Value::as_array)
+        .map_or(0, |edits| {
+            edits
+                .iter()
+                .filter_map(|edit| edit.get("newText").and_then(Value::as_str))
+                .map(str::len)
+                .sum()
+        });
+    (content + edits) as u64
+}
+
+impl GetServerStats {
+    pub async fn run_tool_with_context(
+        _: Self,
+        _: &FileSystemService,
+        metrics: &ServerMetrics,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let stats = Self::format_stats(metrics).map_err(CallToolError::new)?;
+        Ok(CallToolResult::text_content(vec![TextContent::from(stats)]))
+    }
+
+    fn format_stats(metrics: &ServerMetrics) -> ServiceResult<String> {
+        let tools = metrics.tool_stats();
+        let total = tools
+            .values()
+            .fold(ToolStats::default(), |total, stats| ToolStats {
+                calls: total.calls + stats.calls,
+                errors: total.errors + stats.errors,
+                bytes_read: total.bytes_read + stats.bytes_read,
+                bytes_written: total.bytes_written + stats.bytes_written,
+            });
+        Ok(serde_json::to_string_pretty(&serde_json::json!({
+            "uptime_secs": metrics.uptime_secs(),
+            "total": total,
+            "tools": tools,
+        }))?)
+    }
+}
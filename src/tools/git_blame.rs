@@ -0,0 +1,86 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+
+use crate::fs_service::{FileSystemService, GitBlameLine};
+
+#[mcp_tool(
+    name = "git_blame",
+    title="Show git blame for lines of a file",
+    description = concat!("Shows which commit last changed each line of a file in a git repository, ",
+    "with the commit hash, author and date of every line, followed by the summary of each commit involved. ",
+    "Use 'start_line' and 'end_line' (1-based, inclusive) to limit the output to a range of lines. ",
+    "Useful to understand why a piece of code exists before editing it. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct GitBlame {
+    /// The path of the file to blame.
+    pub path: String,
+    /// Optional first line to include (1-based).
+    pub start_line: Option<u64>,
+    /// Optional last line to include (1-based, inclusive).
+    pub end_line: Option<u64>,
+}
+
+impl GitBlame {
+    fn format_output(lines: &[GitBlameLine]) -> String {
+        let mut output = String::new();
+        let mut commits: Vec<&GitBlameLine> = vec![];
+        for line in lines {
+            let _ = writeln!(
+                output,
+                "{} ({} {}) {}: {}",
+                &line.commit[..line.commit.len().min(8)],
+                line.author,
+                line.date,
+                line.line,
+                line.content
+            );
+            if !commits.iter().any(|c| c.commit == line.commit) {
+                commits.push(line);
+            }
+        }
+
+        let _ = writeln!(output, "\nCommits:");
+        for commit in commits {
+            let _ = writeln!(
+                output,
+                "{} {} <{}>: {}",
+                &commit.commit[..commit.commit.len().min(8)],
+                commit.author,
+                commit.author_email,
+                commit.summary
+            );
+        }
+        output
+    }
+
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let lines = context
+            .git_blame(Path::new(&params.path), params.start_line, params.end_line)
+            .await
+            .map_err(CallToolError::new)?;
+
+        if lines.is_empty() {
+            return Ok(CallToolResult::text_content(vec![TextContent::from(
+                "No lines to blame.".to_string(),
+            )]));
+        }
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            Self::format_output(&lines),
+        )]))
+    }
+}
@@ -0,0 +1,94 @@
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+
+use crate::fs_service::{FileSystemService, GitDiffResult};
+
+#[mcp_tool(
+    name = "git_diff",
+    title="Show git changes",
+    description = concat!("Shows the uncommitted changes of files in a git repository as a unified diff, ",
+    "preceded by a per-file summary of added and deleted lines. ",
+    "By default the unstaged working tree changes are returned (including a list of untracked files); ",
+    "set 'staged' to true to return the changes staged in the index instead. ",
+    "Paths may be files or directories within the same repository. ",
+    "The diff is limited to 'max_bytes' (default 65536); files that do not fit are listed as omitted. ",
+    "Useful to review edits made with write_file or edit_file. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct GitDiff {
+    /// Files or directories to show changes for.
+    pub paths: Vec<String>,
+    /// Show changes staged in the index instead of unstaged working tree changes. Default: false.
+    pub staged: Option<bool>,
+    /// Maximum size of the returned diff in bytes. Default: 65536.
+    #[json_schema(default = "65536")]
+    pub max_bytes: Option<u64>,
+}
+
+impl GitDiff {
+    fn format_output(diff: &GitDiffResult) -> String {
+        if diff.files.is_empty() {
+            return "No changes.".to_string();
+        }
+
+        let mut output = String::new();
+        for file in &diff.files {
+            let _ = match (file.untracked, file.additions, file.deletions) {
+                (true, _, _) => writeln!(output, "{} (untracked)", file.path),
+                (false, Some(additions), Some(deletions)) => {
+                    writeln!(output, "{} (+{additions} -{deletions})", file.path)
+                }
+                _ => writeln!(output, "{} (binary)", file.path),
+            };
+        }
+
+        if !diff.diff.is_empty() {
+            // Format diff with appropriate number of backticks
+            let mut num_backticks = 3;
+            while diff.diff.contains(&"`".repeat(num_backticks)) {
+                num_backticks += 1;
+            }
+            let fence = "`".repeat(num_backticks);
+            let _ = write!(output, "\n{fence}diff\n{}{fence}\n", diff.diff);
+        }
+
+        if !diff.omitted_files.is_empty() {
+            let _ = write!(
+                output,
+                "\nDiff of {} file(s) omitted due to the size limit: {}",
+                diff.omitted_files.len(),
+                diff.omitted_files.join(", ")
+            );
+        }
+        output
+    }
+
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let paths: Vec<PathBuf> = params.paths.iter().map(PathBuf::from).collect();
+        let diff = context
+            .git_diff(
+                &paths,
+                params.staged.unwrap_or(false),
+                params.max_bytes.map(|max| max as usize),
+            )
+            .await
+            .map_err(CallToolError::new)?;
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            Self::format_output(&diff),
+        )]))
+    }
+}
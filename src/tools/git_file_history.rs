@@ -0,0 +1,68 @@
+use std::path::Path;
+
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+
+use crate::fs_service::FileSystemService;
+
+#[mcp_tool(
+    name = "git_file_history",
+    title="Show the git history of a path",
+    description = concat!("Lists the most recent git commits that touched a file or directory, newest first, ",
+    "with the commit hash, author, date and subject of each commit. ",
+    "Renames are followed when the path is a file. ",
+    "Use 'max_count' to control how many commits are returned (default 20). ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct GitFileHistory {
+    /// The file or directory to show the history of.
+    pub path: String,
+    /// Maximum number of commits to return. Default: 20.
+    #[json_schema(default = "20")]
+    pub max_count: Option<u64>,
+}
+
+impl GitFileHistory {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let commits = context
+            .git_log(
+                Path::new(&params.path),
+                params.max_count.map(|count| count as usize),
+            )
+            .await
+            .map_err(CallToolError::new)?;
+
+        let output = if commits.is_empty() {
+            "No commits found.".to_string()
+        } else {
+            commits
+                .iter()
+                .map(|commit| {
+                    format!(
+                        "{} {} {} <{}>: {}",
+                        commit.hash,
+                        commit.date,
+                        commit.author,
+                        commit.author_email,
+                        commit.subject
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            output,
+        )]))
+    }
+}
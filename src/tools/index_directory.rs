@@ -0,0 +1,49 @@
+use std::path::Path;
+
+use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
+use rust_mcp_sdk::schema::TextContent;
+use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
+
+use crate::fs_service::FileSystemService;
+
+#[mcp_tool(
+    name = "index_directory",
+    title="Index directory",
+    description = concat!("Builds or refreshes a persistent full-text (trigram) index for a directory to speed up repeated content searches. ",
+    "Once indexed, `search_files_content` automatically uses the index for literal (non-regex) queries within that directory ",
+    "and only reads files that may contain the query. Files added or modified after indexing are still searched directly, ",
+    "so results stay correct; re-run this tool after large changes to restore the speedup. ",
+    "Respects gitignore rules. Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = false
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct IndexDirectory {
+    /// The root directory to index.
+    pub path: String,
+}
+
+impl IndexDirectory {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let summary = context
+            .build_content_index(Path::new(&params.path))
+            .await
+            .map_err(CallToolError::new)?;
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            format!(
+                "Indexed {} files ({} trigrams) under {} in {} ms.\nIndex stored at: {}",
+                summary.indexed_files,
+                summary.trigrams,
+                summary.root.display(),
+                summary.elapsed_ms,
+                summary.index_path.display()
+            ),
+        )]))
+    }
+}
@@ -1,14 +1,19 @@
 use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
 use rust_mcp_sdk::schema::TextContent;
 use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
+use serde_json::json;
 
 use crate::fs_service::FileSystemService;
+use crate::fs_service::utils::{OutputFormat, format_bytes};
 
 #[mcp_tool(
     name = "list_allowed_directories",
     title="List allowed directories",
     description = concat!("Returns a list of directories that the server has permission ",
     "to access Subdirectories within these allowed directories are also accessible. ",
+    "Each directory is reported with its access mode ('ro' or 'rw'), whether it exists ",
+    "and the space available on its filesystem, followed by the glob patterns of paths that are denied ",
+    "even inside allowed directories. ",
     "Use this to identify which directories and their nested paths are available ",
     "before attempting to access files."),
     destructive_hint = false,
@@ -17,26 +22,64 @@ use crate::fs_service::FileSystemService;
     read_only_hint = true
 )]
 #[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
-pub struct ListAllowedDirectories {}
+pub struct ListAllowedDirectories {
+    /// Specify the output format, accepts either `text` or `json` (default: text).
+    pub output_format: Option<OutputFormat>,
+}
 
 impl ListAllowedDirectories {
     pub async fn run_tool(
-        _: Self,
+        params: Self,
         context: &FileSystemService,
     ) -> std::result::Result<CallToolResult, CallToolError> {
         let allowed_directories = context.allowed_directories().await;
 
-        let result = if allowed_directories.is_empty() {
-            "Allowed directories list is empty!".to_string()
-        } else {
-            format!(
-                "Allowed directories:\n{}",
-                allowed_directories
-                    .iter()
-                    .map(|entry| entry.display().to_string())
-                    .collect::<Vec<_>>()
-                    .join("\n")
-            )
+        let mut lines = Vec::with_capacity(allowed_directories.len());
+        let mut directories = Vec::with_capacity(allowed_directories.len());
+        for dir in allowed_directories.iter() {
+            let mode = context.directory_mode(dir).as_str();
+            let exists = tokio::fs::metadata(dir)
+                .await
+                .is_ok_and(|metadata| metadata.is_dir());
+            let available_bytes = match exists {
+                true => context
+                    .disk_space(dir)
+                    .await
+                    .ok()
+                    .map(|space| space.available_bytes),
+                false => None,
+            };
+            let status = match (exists, available_bytes) {
+                (false, _) => "missing".to_string(),
+                (true, Some(bytes)) => format!("{} available", format_bytes(bytes)),
+                (true, None) => "available space unknown".to_string(),
+            };
+            lines.push(format!("{} ({mode}, {status})", dir.display()));
+            directories.push(json!({
+                "path": dir,
+                "mode": mode,
+                "exists": exists,
+                "available_bytes": available_bytes,
+            }));
+        }
+        let deny_globs = context.deny_patterns();
+
+        let result = match params.output_format.unwrap_or(OutputFormat::Text) {
+            OutputFormat::Json => serde_json::to_string_pretty(&json!({
+                "directories": directories,
+                "deny_globs": deny_globs,
+            }))
+            .map_err(CallToolError::new)?,
+            OutputFormat::Text if allowed_directories.is_empty() => {
+                "Allowed directories list is empty!".to_string()
+            }
+            OutputFormat::Text => {
+                let mut result = format!("Allowed directories:\n{}", lines.join("\n"));
+                if !deny_globs.is_empty() {
+                    result.push_str(&format!("\nDenied paths: {}", deny_globs.join(", ")));
+                }
+                result
+            }
         };
         Ok(CallToolResult::text_content(vec![TextContent::from(
             result,
@@ -3,8 +3,10 @@ use std::path::Path;
 use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
 use rust_mcp_sdk::schema::TextContent;
 use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
+use serde_json::{Map, Value};
 
-use crate::fs_service::FileSystemService;
+use crate::fs_service::utils::{decode_cursor, encode_cursor};
+use crate::fs_service::{DirectorySortBy, FileSystemService};
 
 #[mcp_tool(
     name = "list_directory",
@@ -12,7 +14,11 @@ use crate::fs_service::FileSystemService;
     description = concat!("Get a detailed listing of all files and directories in a specified path. ",
 "Results clearly distinguish between files and directories with [FILE] and [DIR] ",
 "prefixes. This tool is essential for understanding directory structure and ",
-"finding specific files within a directory. Only works within allowed directories."),
+"finding specific files within a directory. ",
+"For large directories, `filter_glob` keeps entries whose name matches a glob (e.g. '*.json'), `sort_by` orders them ",
+"by `name`, `size` (largest first) or `mtime` (most recent first), and `limit` returns them in pages: ",
+"the 'next_cursor' value from the result '_meta' is passed as 'cursor' to fetch the next page, ",
+"and 'entries_total' gives the number of matching entries. Only works within allowed directories."),
     destructive_hint = false,
     idempotent_hint = false,
     open_world_hint = false,
@@ -22,6 +28,21 @@ use crate::fs_service::FileSystemService;
 pub struct ListDirectory {
     /// The path of the directory to list.
     pub path: String,
+    /// Order of the entries: `name`, `size` or `mtime` (default: name).
+    pub sort_by: Option<DirectorySortBy>,
+    /// Only list entries whose name matches this glob pattern.
+    pub filter_glob: Option<String>,
+    /// Maximum number of entries per page (optional).
+    pub limit: Option<u64>,
+    /// Opaque continuation cursor returned as `next_cursor` in the `_meta` of a previous page.
+    pub cursor: Option<String>,
+}
+
+/// Resumable position within a paginated directory listing.
+#[derive(::serde::Deserialize, ::serde::Serialize)]
+struct ListDirectoryCursor {
+    /// Number of entries already returned by previous pages.
+    offset: usize,
 }
 
 impl ListDirectory {
@@ -29,12 +50,27 @@ impl ListDirectory {
         params: Self,
         context: &FileSystemService,
     ) -> std::result::Result<CallToolResult, CallToolError> {
-        let entries = context
-            .list_directory(Path::new(&params.path))
+        let offset = match params.cursor.as_deref() {
+            Some(cursor) => {
+                decode_cursor::<ListDirectoryCursor>(cursor)
+                    .map_err(CallToolError::new)?
+                    .offset
+            }
+            None => 0,
+        };
+        let listing = context
+            .list_directory_page(
+                Path::new(&params.path),
+                params.sort_by.unwrap_or_default(),
+                params.filter_glob.as_deref(),
+                offset,
+                params.limit.map(|limit| limit.max(1) as usize),
+            )
             .await
             .map_err(CallToolError::new)?;
 
-        let formatted: Vec<_> = entries
+        let formatted: Vec<_> = listing
+            .entries
             .iter()
             .map(|entry| {
                 format!(
@@ -49,8 +85,24 @@ impl ListDirectory {
             })
             .collect();
 
-        Ok(CallToolResult::text_content(vec![TextContent::from(
-            formatted.join("\n"),
-        )]))
+        let mut meta = Map::new();
+        meta.insert(
+            "entries_total".to_string(),
+            Value::from(listing.entries_total),
+        );
+        if let Some(offset) = listing.next_offset {
+            meta.insert("truncated".to_string(), Value::Bool(true));
+            meta.insert(
+                "next_cursor".to_string(),
+                Value::String(
+                    encode_cursor(&ListDirectoryCursor { offset }).map_err(CallToolError::new)?,
+                ),
+            );
+        }
+
+        Ok(
+            CallToolResult::text_content(vec![TextContent::from(formatted.join("\n"))])
+                .with_meta(Some(meta)),
+        )
     }
 }
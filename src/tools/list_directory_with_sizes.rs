@@ -1,11 +1,12 @@
 use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
 use rust_mcp_sdk::schema::TextContent;
 use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
+use serde_json::json;
 use std::fmt::Write;
 use std::path::Path;
 
-use crate::fs_service::FileSystemService;
-use crate::fs_service::utils::format_bytes;
+use crate::fs_service::utils::{OutputFormat, format_bytes};
+use crate::fs_service::{FileSystemService, SizedEntry};
 
 #[mcp_tool(
     name = "list_directory_with_sizes",
@@ -13,7 +14,10 @@ use crate::fs_service::utils::format_bytes;
     description = concat!("Get a detailed listing of all files and directories in a specified path, including sizes. " ,
         "Results clearly distinguish between files and directories with [FILE] and [DIR] prefixes. " ,
         "This tool is useful for understanding directory structure and " ,
-        "finding specific files within a directory. Only works within allowed directories."),
+        "finding specific files within a directory. ",
+        "Set `depth` to roll up the total size of each subdirectory like `du`: 1 lists the immediate entries, ",
+        "higher values also list the entries of subdirectories down to that many levels. ",
+        "Set `output_format` to `json` for a machine-readable listing. Only works within allowed directories."),
     destructive_hint = false,
     idempotent_hint = false,
     open_world_hint = false,
@@ -23,56 +27,37 @@ use crate::fs_service::utils::format_bytes;
 pub struct ListDirectoryWithSizes {
     /// The path of the directory to list.
     pub path: String,
+    /// Number of levels to list, with the total size of each subdirectory (optional).
+    pub depth: Option<u64>,
+    /// Specify the output format, accepts either `text` or `json` (default: text).
+    pub output_format: Option<OutputFormat>,
 }
 
 impl ListDirectoryWithSizes {
-    async fn format_directory_entries(
-        &self,
-        mut entries: Vec<tokio::fs::DirEntry>,
-    ) -> std::result::Result<String, CallToolError> {
-        let mut file_count = 0;
-        let mut dir_count = 0;
-        let mut total_size: u64 = 0;
-
-        // Estimate initial capacity: assume ~50 bytes per entry + summary
-        let mut output = String::with_capacity(entries.len() * 50 + 120);
-
-        // Sort entries by file name
-        entries.sort_by_key(|a| a.file_name());
-
-        // build the output string
-        for entry in &entries {
-            let file_name = entry.file_name();
-            let file_name = file_name.to_string_lossy();
-
-            if entry.path().is_dir() {
-                writeln!(output, "[DIR]  {file_name:<30}").map_err(CallToolError::new)?;
-                dir_count += 1;
-            } else if entry.path().is_file() {
-                let metadata = entry.metadata().await.map_err(CallToolError::new)?;
-
-                let file_size = metadata.len();
-                writeln!(
+    fn format_directory_entries(
+        output: &mut String,
+        entries: &[SizedEntry],
+        level: usize,
+    ) -> std::fmt::Result {
+        let indent = "  ".repeat(level);
+        for entry in entries {
+            let name = &entry.name;
+            match (entry.is_directory, entry.size) {
+                (true, None) => writeln!(output, "{indent}[DIR]  {name:<30}")?,
+                (true, Some(size)) => writeln!(
                     output,
-                    "[FILE] {:<30} {:>10}",
-                    file_name,
-                    format_bytes(file_size)
-                )
-                .map_err(CallToolError::new)?;
-                file_count += 1;
-                total_size += file_size;
+                    "{indent}[DIR]  {name:<30} {:>10}",
+                    format_bytes(size)
+                )?,
+                (false, size) => writeln!(
+                    output,
+                    "{indent}[FILE] {name:<30} {:>10}",
+                    format_bytes(size.unwrap_or_default())
+                )?,
             }
+            Self::format_directory_entries(output, &entry.children, level + 1)?;
         }
-
-        // Append summary
-        writeln!(
-            output,
-            "\nTotal: {file_count} files, {dir_count} directories"
-        )
-        .map_err(CallToolError::new)?;
-        writeln!(output, "Total size: {}", format_bytes(total_size)).map_err(CallToolError::new)?;
-
-        Ok(output)
+        Ok(())
     }
 
     pub async fn run_tool(
@@ -80,14 +65,40 @@ impl ListDirectoryWithSizes {
         context: &FileSystemService,
     ) -> std::result::Result<CallToolResult, CallToolError> {
         let entries = context
-            .list_directory(Path::new(&params.path))
+            .list_directory_with_sizes(
+                Path::new(&params.path),
+                params.depth.map(|depth| depth.max(1) as usize),
+            )
             .await
             .map_err(CallToolError::new)?;
 
-        let output = params
-            .format_directory_entries(entries)
-            .await
+        let file_count = entries.iter().filter(|entry| !entry.is_directory).count();
+        let dir_count = entries.len() - file_count;
+        let total_size: u64 = entries.iter().filter_map(|entry| entry.size).sum();
+
+        let output = if let Some(OutputFormat::Json) = params.output_format {
+            serde_json::to_string_pretty(&json!({
+                "path": params.path,
+                "entries": entries,
+                "total_files": file_count,
+                "total_directories": dir_count,
+                "total_size": total_size,
+            }))
+            .map_err(CallToolError::new)?
+        } else {
+            // Estimate initial capacity: assume ~50 bytes per entry + summary
+            let mut output = String::with_capacity(entries.len() * 50 + 120);
+            Self::format_directory_entries(&mut output, &entries, 0).map_err(CallToolError::new)?;
+            writeln!(
+                output,
+                "\nTotal: {file_count} files, {dir_count} directories"
+            )
             .map_err(CallToolError::new)?;
+            writeln!(output, "Total size: {}", format_bytes(total_size))
+                .map_err(CallToolError::new)?;
+            output
+        };
+
         Ok(CallToolResult::text_content(vec![TextContent::from(
             output,
         )]))
@@ -0,0 +1,61 @@
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+use serde_json::{Map, json};
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::fs_service::FileSystemService;
+
+#[mcp_tool(
+    name = "list_extended_attributes",
+    title="List extended attributes",
+    description = concat!("Lists the extended attributes (xattrs) of a file or directory with the size of their values, ",
+    "e.g. the 'com.apple.quarantine' attribute of downloaded files on macOS or 'user.*' tags on Linux. ",
+    "Use 'get_extended_attribute' to read a value. ",
+    "On Windows, NTFS alternate data streams cannot be listed, only read by name. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct ListExtendedAttributes {
+    /// The path of the file or directory.
+    pub path: String,
+}
+
+impl ListExtendedAttributes {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let attributes = context
+            .list_extended_attributes(Path::new(&params.path))
+            .await
+            .map_err(CallToolError::new)?;
+
+        let mut output = if attributes.is_empty() {
+            format!("'{}' has no extended attributes.", params.path)
+        } else {
+            format!(
+                "{} extended attributes on '{}':\n",
+                attributes.len(),
+                params.path
+            )
+        };
+        for attribute in &attributes {
+            writeln!(output, "  {} ({} bytes)", attribute.name, attribute.size)
+                .map_err(CallToolError::new)?;
+        }
+
+        let mut structured = Map::new();
+        structured.insert("attributes".to_string(), json!(attributes));
+        Ok(
+            CallToolResult::text_content(vec![TextContent::from(output)])
+                .with_structured_content(structured),
+        )
+    }
+}
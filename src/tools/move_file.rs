@@ -4,15 +4,16 @@ use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
 use rust_mcp_sdk::schema::TextContent;
 use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
 
-use crate::fs_service::FileSystemService;
+use crate::fs_service::{FileSystemService, OverwritePolicy};
 
 #[mcp_tool(
     name = "move_file",
     title="Move file",
     description = concat!("Move or rename files and directories. Can move files between directories ",
 "and rename them in a single operation. If the destination exists, the ",
-"operation will fail. Works across different directories and can be used ",
-"for simple renaming within the same directory. ",
+"operation fails unless 'overwrite' is 'replace' (replace the destination) or 'rename' ",
+"(move to a free name like 'name (1).ext'). Works across different directories and filesystems, and can be used ",
+"for simple renaming within the same directory. Returns the final destination. ",
 "Both source and destination must be within allowed directories."),
     destructive_hint = false,
     idempotent_hint = false,
@@ -25,6 +26,13 @@ pub struct MoveFile {
     pub source: String,
     /// The destination path to move the file to.
     pub destination: String,
+    /// What to do when the destination exists: "error" (default), "replace" or "rename".
+    #[serde(
+        default,
+        alias = "on_conflict",
+        skip_serializing_if = "std::option::Option::is_none"
+    )]
+    pub overwrite: Option<OverwritePolicy>,
 }
 
 impl MoveFile {
@@ -32,15 +40,20 @@ impl MoveFile {
         params: Self,
         context: &FileSystemService,
     ) -> std::result::Result<CallToolResult, CallToolError> {
-        context
-            .move_file(Path::new(&params.source), Path::new(&params.destination))
+        let destination = context
+            .move_file(
+                Path::new(&params.source),
+                Path::new(&params.destination),
+                params.overwrite.unwrap_or_default(),
+            )
             .await
             .map_err(CallToolError::new)?;
 
         Ok(CallToolResult::text_content(vec![TextContent::from(
             format!(
                 "Successfully moved {} to {}",
-                &params.source, &params.destination
+                &params.source,
+                destination.display()
             ),
         )]))
     }
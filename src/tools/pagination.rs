@@ -0,0 +1,166 @@
+use crate::error::ServiceError;
+use crate::fs_service::{
+    AstFileSearchResult, AstMatchResult, ContentMatchResult, FileSearchResult,
+    utils::{decode_cursor, encode_cursor},
+};
+use rust_mcp_sdk::schema::schema_utils::CallToolError;
+use serde_json::{Map, Value};
+
+/// The matches of a single file in search results paginated by [`MatchPage`].
+pub(crate) trait FileMatches {
+    type Match;
+
+    fn matches(&self) -> &[Self::Match];
+
+    fn matches_mut(&mut self) -> &mut Vec<Self::Match>;
+}
+
+impl FileMatches for FileSearchResult {
+    type Match = ContentMatchResult;
+
+    fn matches(&self) -> &[Self::Match] {
+        &self.matches
+    }
+
+    fn matches_mut(&mut self) -> &mut Vec<Self::Match> {
+        &mut self.matches
+    }
+}
+
+impl FileMatches for AstFileSearchResult {
+    type Match = AstMatchResult;
+
+    fn matches(&self) -> &[Self::Match] {
+        &self.matches
+    }
+
+    fn matches_mut(&mut self) -> &mut Vec<Self::Match> {
+        &mut self.matches
+    }
+}
+
+/// Resumable position within paginated search results.
+#[derive(::serde::Deserialize, ::serde::Serialize)]
+struct MatchCursor {
+    /// Number of matches already returned by previous pages.
+    offset: usize,
+}
+
+/// Returns the number of matches already returned before `cursor`, 0 for the first page.
+pub(crate) fn cursor_offset(cursor: Option<&str>) -> Result<usize, CallToolError> {
+    match cursor {
+        Some(cursor) => Ok(decode_cursor::<MatchCursor>(cursor)
+            .map_err(CallToolError::new)?
+            .offset),
+        None => Ok(0),
+    }
+}
+
+/// Checks the `max_results` and `max_matches_per_file` arguments of a paginated search, which
+/// would otherwise return an empty page, and converts them to counts.
+pub(crate) fn page_limits(
+    max_results: Option<u64>,
+    max_matches_per_file: Option<u64>,
+) -> Result<(Option<usize>, Option<usize>), CallToolError> {
+    for (name, value) in [
+        ("max_results", max_results),
+        ("max_matches_per_file", max_matches_per_file),
+    ] {
+        if value == Some(0) {
+            return Err(CallToolError::new(ServiceError::InvalidArgument(format!(
+                "'{name}' must be at least 1."
+            ))));
+        }
+    }
+    Ok((
+        max_results.map(|v| v as usize),
+        max_matches_per_file.map(|v| v as usize),
+    ))
+}
+
+/// A single page of search results, counted in matches rather than files.
+pub(crate) struct MatchPage<T> {
+    pub results: Vec<T>,
+    pub total_files: usize,
+    pub total_matches: usize,
+    pub returned_matches: usize,
+    pub next_offset: Option<usize>,
+    pub capped_files: usize,
+}
+
+impl<T: FileMatches> MatchPage<T> {
+    /// Applies the per-file cap to results sorted by path (so that cursors are stable across
+    /// calls) and returns the page of matches starting at `offset`.
+    pub(crate) fn new(
+        mut results: Vec<T>,
+        max_results: Option<usize>,
+        max_matches_per_file: Option<usize>,
+        offset: usize,
+    ) -> Self {
+        let mut capped_files = 0;
+        if let Some(max_per_file) = max_matches_per_file {
+            for file_result in results.iter_mut() {
+                if file_result.matches().len() > max_per_file {
+                    file_result.matches_mut().truncate(max_per_file);
+                    capped_files += 1;
+                }
+            }
+        }
+
+        let total_files = results.len();
+        let total_matches: usize = results.iter().map(|r| r.matches().len()).sum();
+        let mut remaining = max_results.unwrap_or(usize::MAX);
+        let mut skip = offset;
+        let mut page = Vec::new();
+
+        for mut file_result in results {
+            if remaining == 0 {
+                break;
+            }
+            let matches = file_result.matches_mut();
+            if skip >= matches.len() {
+                skip -= matches.len();
+                continue;
+            }
+            matches.drain(..skip);
+            skip = 0;
+            matches.truncate(remaining);
+            remaining -= matches.len();
+            page.push(file_result);
+        }
+
+        let returned_matches: usize = page.iter().map(|r| r.matches().len()).sum();
+        let next_offset = (offset + returned_matches < total_matches && returned_matches > 0)
+            .then_some(offset + returned_matches);
+
+        Self {
+            results: page,
+            total_files,
+            total_matches,
+            returned_matches,
+            next_offset,
+            capped_files,
+        }
+    }
+
+    /// Returns the `_meta` fields of a page that leaves matches out, with the cursor of the
+    /// next page if there is one, or `None` if every match is returned.
+    pub(crate) fn meta(&self) -> Result<Option<Map<String, Value>>, CallToolError> {
+        if self.next_offset.is_none() && self.capped_files == 0 {
+            return Ok(None);
+        }
+
+        let mut meta = Map::new();
+        meta.insert("truncated".to_string(), Value::Bool(true));
+        meta.insert("total_matches".to_string(), self.total_matches.into());
+        meta.insert("returned_matches".to_string(), self.returned_matches.into());
+        if self.capped_files > 0 {
+            meta.insert("capped_files".to_string(), self.capped_files.into());
+        }
+        if let Some(offset) = self.next_offset {
+            let cursor = encode_cursor(&MatchCursor { offset }).map_err(CallToolError::new)?;
+            meta.insert("next_cursor".to_string(), Value::String(cursor));
+        }
+        Ok(Some(meta))
+    }
+}
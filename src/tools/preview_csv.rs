@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+
+use crate::{error::ServiceError, fs_service::FileSystemService};
+
+#[mcp_tool(
+    name = "preview_csv",
+    title="Preview a CSV file",
+    description = concat!("Returns an overview of a CSV or TSV file as JSON: the column names, inferred column types ",
+    "(integer, float, boolean, date, datetime, string or empty), the number of data rows and the first and last rows. ",
+    "Only the beginning and the end of the file are read, so it is safe to use on very large data files; ",
+    "for large files the row count is an estimate ('row_count_exact' is false). ",
+    "The delimiter is detected automatically unless specified. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct PreviewCsv {
+    /// The path of the CSV/TSV file to preview.
+    pub path: String,
+    /// Optional single-character field delimiter, e.g. "," or ";". Use "\t" for tab. Detected automatically when omitted.
+    pub delimiter: Option<String>,
+    /// Whether the first row contains column names. Default: true.
+    pub has_header: Option<bool>,
+    /// Number of rows to return from the start of the file. Default: 5.
+    #[json_schema(default = "5")]
+    pub head: Option<u64>,
+    /// Number of rows to return from the end of the file. Default: 5.
+    #[json_schema(default = "5")]
+    pub tail: Option<u64>,
+}
+
+impl PreviewCsv {
+    fn parse_delimiter(delimiter: &str) -> Result<u8, CallToolError> {
+        match delimiter {
+            "\\t" | "tab" => Ok(b'\t'),
+            d if d.len() == 1 => Ok(d.as_bytes()[0]),
+            _ => Err(CallToolError::new(ServiceError::InvalidArgument(format!(
+                "Invalid delimiter '{delimiter}': expected a single ASCII character."
+            )))),
+        }
+    }
+
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let delimiter = params
+            .delimiter
+            .as_deref()
+            .map(Self::parse_delimiter)
+            .transpose()?;
+
+        let preview = context
+            .preview_csv(
+                Path::new(&params.path),
+                delimiter,
+                params.has_header.unwrap_or(true),
+                params.head.unwrap_or(5) as usize,
+                params.tail.unwrap_or(5) as usize,
+            )
+            .await
+            .map_err(CallToolError::new)?;
+
+        let content = serde_json::to_string_pretty(&preview).map_err(CallToolError::new)?;
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            content,
+        )]))
+    }
+}
@@ -0,0 +1,109 @@
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+use serde_json::{Map, Value};
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::fs_service::{FileSystemService, MatchRegions, utils::OutputFormat};
+
+/// Lines of context around each match unless `context_lines` is given.
+const DEFAULT_CONTEXT_LINES: u64 = 3;
+/// Matches returned unless `max_matches` is given.
+const DEFAULT_MAX_MATCHES: u64 = 50;
+
+#[mcp_tool(
+    name = "read_around_match",
+    title="Read around matches",
+    description = concat!("Searches a text file for a query and returns the lines around each match in one call, ",
+    "instead of a content search followed by several read_file_lines calls. ",
+    "The query is a case-insensitive literal, or a regular expression when 'is_regex' is true. ",
+    "Each match gets 'context_lines' lines before and after it (default 3); overlapping windows are merged into one region. ",
+    "In the text output, regions are separated by '--' and lines are prefixed with their line number, followed by ':' for matching lines and '-' for context lines, like grep. ",
+    "At most 'max_matches' matches are returned (default 50); '_meta.total_matches' holds the number of matches in the file. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct ReadAroundMatch {
+    /// The path of the file to search.
+    pub path: String,
+    /// Text or regex pattern to find.
+    pub query: String,
+    /// Whether the query is a regular expression (default: false).
+    #[serde(default)]
+    pub is_regex: bool,
+    /// Number of lines returned before and after each match (default: 3).
+    #[json_schema(default = "3")]
+    pub context_lines: Option<u64>,
+    /// Maximum number of matches returned (default: 50).
+    #[json_schema(default = "50")]
+    pub max_matches: Option<u64>,
+    /// Specify the output format, accepts either `text` or `json` (default: text).
+    pub output_format: Option<OutputFormat>,
+}
+
+impl ReadAroundMatch {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let max_matches = params.max_matches.unwrap_or(DEFAULT_MAX_MATCHES) as usize;
+        let result = context
+            .read_around_match(
+                Path::new(&params.path),
+                &params.query,
+                params.is_regex,
+                params.context_lines.unwrap_or(DEFAULT_CONTEXT_LINES) as usize,
+                Some(max_matches),
+            )
+            .await
+            .map_err(CallToolError::new)?;
+
+        let content =
+            Self::format_output(&result, params.output_format.unwrap_or(OutputFormat::Text))?;
+
+        let mut meta = Map::new();
+        meta.insert(
+            "total_matches".to_string(),
+            Value::from(result.total_matches),
+        );
+        meta.insert(
+            "truncated".to_string(),
+            Value::Bool(result.total_matches > max_matches),
+        );
+        Ok(CallToolResult::text_content(vec![TextContent::from(content)]).with_meta(Some(meta)))
+    }
+
+    fn format_output(
+        result: &MatchRegions,
+        output_format: OutputFormat,
+    ) -> std::result::Result<String, CallToolError> {
+        if let OutputFormat::Json = output_format {
+            return serde_json::to_string_pretty(result).map_err(CallToolError::new);
+        }
+
+        if result.regions.is_empty() {
+            return Ok("No matches found.".to_string());
+        }
+        let mut output = String::new();
+        for (index, region) in result.regions.iter().enumerate() {
+            if index > 0 {
+                output.push_str("--\n");
+            }
+            for (line_number, line) in (region.start_line..).zip(&region.lines) {
+                let separator = if region.match_lines.contains(&line_number) {
+                    ':'
+                } else {
+                    '-'
+                };
+                let _ = writeln!(output, "{line_number}{separator}{line}");
+            }
+        }
+        Ok(output)
+    }
+}
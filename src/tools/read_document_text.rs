@@ -0,0 +1,60 @@
+use std::path::Path;
+
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+
+use crate::fs_service::{DocumentKind, DocumentText, FileSystemService};
+
+#[mcp_tool(
+    name = "read_document_text",
+    title="Read text from an office document",
+    description = concat!("Extracts plain text from Office Open XML documents: Word (.docx), Excel (.xlsx) and PowerPoint (.pptx). ",
+    "Spreadsheets are returned sheet by sheet with rows as tab-separated values; presentations slide by slide. ",
+    "Use 'sections' to only return specific sheets (by name) or slides (e.g. 'Slide 3'), or any section by its 1-based position. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct ReadDocumentText {
+    /// The path of the .docx, .xlsx or .pptx file to read.
+    pub path: String,
+    /// Optional list of sheet names, slide names (e.g. 'Slide 2') or 1-based section positions to extract.
+    pub sections: Option<Vec<String>>,
+}
+
+impl ReadDocumentText {
+    fn format_output(document: DocumentText) -> String {
+        if document.kind == DocumentKind::Docx {
+            return document
+                .sections
+                .into_iter()
+                .map(|section| section.text)
+                .collect();
+        }
+        document
+            .sections
+            .into_iter()
+            .map(|section| format!("## {}\n{}", section.name, section.text.trim_end()))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let document = context
+            .read_document_text(Path::new(&params.path), params.sections)
+            .await
+            .map_err(CallToolError::new)?;
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            Self::format_output(document),
+        )]))
+    }
+}
@@ -0,0 +1,81 @@
+use std::path::Path;
+
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+use serde_json::{Map, Value};
+
+use crate::fs_service::{
+    ChunkPosition, FileSystemService,
+    utils::{decode_cursor, encode_cursor},
+};
+
+#[mcp_tool(
+    name = "read_file_chunk",
+    title="Read a file in chunks",
+    description = concat!("Reads a text file of any size chunk by chunk. ",
+    "Each call returns at most 'max_bytes' bytes (default 65536) and, if 'max_lines' is set, at most that many whole lines. ",
+    "The '_meta' of the result holds 'next_cursor', to pass as 'cursor' on the next call to receive the following chunk, ",
+    "along with 'start_line' (the 1-based line the chunk starts in), 'next_offset', 'total_bytes' and 'eof'. ",
+    "'next_cursor' is absent once the end of the file is reached. ",
+    "Chunks never split a UTF-8 character. If the file is modified between calls, the cursor is rejected and reading must restart. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct ReadFileChunk {
+    /// The path of the file to read.
+    pub path: String,
+    /// Opaque cursor returned as `next_cursor` in the `_meta` of a previous response.
+    pub cursor: Option<String>,
+    /// Maximum number of bytes returned per call. Default: 65536.
+    #[json_schema(default = "65536")]
+    pub max_bytes: Option<u64>,
+    /// Maximum number of lines returned per call.
+    pub max_lines: Option<u64>,
+}
+
+impl ReadFileChunk {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let from = params
+            .cursor
+            .as_deref()
+            .map(decode_cursor::<ChunkPosition>)
+            .transpose()
+            .map_err(CallToolError::new)?;
+
+        let chunk = context
+            .read_file_chunk(
+                Path::new(&params.path),
+                from,
+                params.max_bytes.map(|v| v as usize),
+                params.max_lines.map(|v| v as usize),
+            )
+            .await
+            .map_err(CallToolError::new)?;
+
+        let mut meta = Map::new();
+        if !chunk.eof {
+            meta.insert(
+                "next_cursor".to_string(),
+                Value::String(encode_cursor(&chunk.position).map_err(CallToolError::new)?),
+            );
+        }
+        meta.insert("start_line".to_string(), chunk.start_line.into());
+        meta.insert("next_offset".to_string(), chunk.position.offset.into());
+        meta.insert("total_bytes".to_string(), chunk.total_bytes.into());
+        meta.insert("eof".to_string(), Value::Bool(chunk.eof));
+
+        Ok(
+            CallToolResult::text_content(vec![TextContent::from(chunk.content)])
+                .with_meta(Some(meta)),
+        )
+    }
+}
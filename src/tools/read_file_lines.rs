@@ -4,8 +4,11 @@ use rust_mcp_sdk::{
     macros::{JsonSchema, mcp_tool},
     schema::{CallToolResult, TextContent, schema_utils::CallToolError},
 };
+use serde_json::{Map, Value};
 
-use crate::fs_service::FileSystemService;
+use crate::{
+    error::ServiceError, fs_service::FileSystemService, tools::read_text_file::not_modified,
+};
 
 // read_file_lines
 #[mcp_tool(
@@ -15,6 +18,11 @@ use crate::fs_service::FileSystemService;
     "By default, reads from the beginning: skips 'offset' lines (0-based) and then reads up to 'limit' lines if specified, or reads until EOF otherwise.",
     "When 'from_end' is true, reads from the file's end: 'offset' lines are skipped from the end, and 'limit' lines are read backwards (output preserves original order).",
     "Examples: offset=0,limit=10 reads first 10 lines; from_end=true,limit=10 reads last 10 lines; offset=5,limit=20 reads lines 6-25.",
+    "Alternatively, 'byte_offset' and/or 'byte_limit' address the file by bytes: the read seeks directly to 'byte_offset' and returns up to 'byte_limit' bytes (and at most 'limit' lines if given), without counting the lines before it, which is much faster deep into large files. ",
+    "Byte ranges never split a UTF-8 character; the '_meta' of the result holds 'next_byte_offset' to continue reading and the file's 'total_bytes'. Byte addressing cannot be combined with 'offset' or 'from_end'. ",
+    "When 'show_line_numbers' is true, each line is prefixed with its absolute 1-based line number followed by a tab, e.g. to reference precise locations in a later edit; the prefixes are not part of the file content. ",
+    "To re-check a file already read, pass the hash of its content as 'if_none_match_sha256': if the file is unchanged, ",
+    "a short 'not modified' response is returned instead of the lines, otherwise the '_meta' of the result holds the new 'sha256'. ",
     "Useful for partial reads, pagination, log tailing, or previewing sections of large text files.",
     "Only works within allowed directories."),
     destructive_hint = false,
@@ -34,6 +42,19 @@ pub struct ReadFileLines {
     /// If true, reads from the end of the file instead of the beginning. Default: false.
     #[serde(default)]
     pub from_end: bool,
+    /// Byte position to start reading at. Enables byte addressing: 'limit' then caps the number
+    /// of lines read from there.
+    pub byte_offset: Option<u64>,
+    /// Maximum number of bytes to read in byte addressing, to the end of the file by default.
+    pub byte_limit: Option<u64>,
+    /// If true, prefixes each line with its absolute line number and a tab. Not supported with
+    /// byte addressing. Default: false.
+    #[serde(default)]
+    pub show_line_numbers: bool,
+    /// SHA-256 hash (hex) of the whole file content as last read. If the file still has this
+    /// content, only a 'not modified' notice is returned.
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub if_none_match_sha256: Option<String>,
 }
 
 impl ReadFileLines {
@@ -41,18 +62,83 @@ impl ReadFileLines {
         params: Self,
         context: &FileSystemService,
     ) -> std::result::Result<CallToolResult, CallToolError> {
+        let Some(expected) = params.if_none_match_sha256.clone() else {
+            return Self::read(params, context).await;
+        };
+        let current = context
+            .file_sha256(Path::new(&params.path))
+            .await
+            .map_err(CallToolError::new)?;
+        if current.eq_ignore_ascii_case(expected.trim()) {
+            return Ok(not_modified(&params.path, current));
+        }
+        let mut result = Self::read(params, context).await?;
+        result
+            .meta
+            .get_or_insert_with(Map::new)
+            .insert("sha256".to_string(), Value::String(current));
+        Ok(result)
+    }
+
+    async fn read(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        if params.byte_offset.is_some() || params.byte_limit.is_some() {
+            return Self::run_byte_range(params, context).await;
+        }
+        let path = Path::new(&params.path);
+        let offset = params.offset as usize;
+        let limit = params.limit.map(|v| v as usize);
+        let result = if params.show_line_numbers {
+            context
+                .read_file_lines_numbered(path, offset, limit, params.from_end)
+                .await
+        } else {
+            context
+                .read_file_lines(path, offset, limit, params.from_end)
+                .await
+        }
+        .map_err(CallToolError::new)?;
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            result,
+        )]))
+    }
+
+    async fn run_byte_range(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        if params.from_end || params.offset != 0 {
+            return Err(CallToolError::new(ServiceError::InvalidArgument(
+                "'byte_offset' and 'byte_limit' cannot be combined with 'offset' or 'from_end'."
+                    .to_string(),
+            )));
+        }
+        if params.show_line_numbers {
+            return Err(CallToolError::new(ServiceError::InvalidArgument(
+                "'show_line_numbers' is not supported with 'byte_offset' and 'byte_limit', line numbers are unknown when seeking by bytes."
+                    .to_string(),
+            )));
+        }
         let result = context
-            .read_file_lines(
+            .read_file_byte_range(
                 Path::new(&params.path),
-                params.offset as usize,
+                params.byte_offset.unwrap_or_default(),
+                params.byte_limit,
                 params.limit.map(|v| v as usize),
-                params.from_end,
             )
             .await
             .map_err(CallToolError::new)?;
 
-        Ok(CallToolResult::text_content(vec![TextContent::from(
-            result,
-        )]))
+        let mut meta = Map::new();
+        meta.insert("byte_offset".to_string(), Value::from(result.start));
+        meta.insert("next_byte_offset".to_string(), Value::from(result.end));
+        meta.insert("total_bytes".to_string(), Value::from(result.total_bytes));
+        Ok(
+            CallToolResult::text_content(vec![TextContent::from(result.content)])
+                .with_meta(Some(meta)),
+        )
     }
 }
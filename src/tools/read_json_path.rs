@@ -0,0 +1,48 @@
+use std::path::Path;
+
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+
+use crate::fs_service::FileSystemService;
+
+#[mcp_tool(
+    name = "read_json_path",
+    title="Query a JSON file",
+    description = concat!("Reads a JSON file and evaluates a JSONPath expression against it, returning only the selected values ",
+    "as a JSON array in document order. ",
+    "Supports the standard JSONPath syntax (RFC 9535), e.g. '$.name', '$.dependencies.serde', '$.items[0]', ",
+    "'$.items[*].id', '$..version' or filters like '$.items[?@.enabled == true]'. ",
+    "Use this instead of reading large JSON files (lock files, configs, API dumps) when only a few keys are needed. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct ReadJsonPath {
+    /// The path of the JSON file to read.
+    pub path: String,
+    /// The JSONPath expression to evaluate, starting with '$'.
+    pub query: String,
+}
+
+impl ReadJsonPath {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let values = context
+            .read_json_path(Path::new(&params.path), &params.query)
+            .await
+            .map_err(CallToolError::new)?;
+
+        let content = serde_json::to_string_pretty(&values).map_err(CallToolError::new)?;
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            content,
+        )]))
+    }
+}
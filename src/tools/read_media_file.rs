@@ -5,7 +5,7 @@ use rust_mcp_sdk::schema::{AudioContent, ImageContent};
 use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
 
 use crate::error::ServiceError;
-use crate::fs_service::FileSystemService;
+use crate::fs_service::{FileSystemService, ImageResizeOptions};
 
 #[mcp_tool(
     name = "read_media_file",
@@ -13,6 +13,8 @@ use crate::fs_service::FileSystemService;
     description = concat!("Reads an image or audio file and returns its Base64-encoded content along with the corresponding MIME type. ",
         "The max_bytes argument could be used to enforce an upper limit on the size of a file to read ",
         "if the media file exceeds this limit, the operation will return an error instead of reading the media file. ",
+        "For images, max_width/max_height downscale the image (preserving aspect ratio) and quality re-encodes it as JPEG, ",
+        "which keeps large screenshots within context limits; max_bytes then applies to the resized image. ",
     "Access is restricted to files within allowed directories only."),
     destructive_hint = false,
     idempotent_hint = false,
@@ -25,17 +27,35 @@ pub struct ReadMediaFile {
     pub path: String,
     /// Maximum allowed file size (in bytes) to be read.
     pub max_bytes: Option<u64>,
+    /// Optional maximum width (in pixels) for images. Larger images are downscaled.
+    pub max_width: Option<u32>,
+    /// Optional maximum height (in pixels) for images. Larger images are downscaled.
+    pub max_height: Option<u32>,
+    /// Optional JPEG quality (1-100). When set, images are re-encoded as JPEG.
+    pub quality: Option<u8>,
 }
 
 impl ReadMediaFile {
+    fn resize_options(&self) -> Option<ImageResizeOptions> {
+        if self.max_width.is_none() && self.max_height.is_none() && self.quality.is_none() {
+            return None;
+        }
+        Some(ImageResizeOptions {
+            max_width: self.max_width,
+            max_height: self.max_height,
+            quality: self.quality,
+        })
+    }
+
     pub async fn run_tool(
         params: Self,
         context: &FileSystemService,
     ) -> std::result::Result<CallToolResult, CallToolError> {
         let (kind, content) = context
-            .read_media_file(
+            .read_media_file_with_options(
                 Path::new(&params.path),
                 params.max_bytes.map(|v| v as usize),
+                params.resize_options().as_ref(),
             )
             .await
             .map_err(CallToolError::new)?;
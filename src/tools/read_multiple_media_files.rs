@@ -1,6 +1,7 @@
 use crate::fs_service::FileSystemService;
+use crate::tools::read_multiple_text_files::read_report;
 use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
-use rust_mcp_sdk::schema::{AudioContent, ContentBlock, ImageContent};
+use rust_mcp_sdk::schema::{AudioContent, ContentBlock, ImageContent, TextContent};
 use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
 
 #[mcp_tool(
@@ -9,7 +10,8 @@ use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
     description = concat!("Reads multiple image or audio files and returns their Base64-encoded contents along with corresponding MIME types. ",
     "This method is more efficient than reading files individually. ",
     "The max_bytes argument could be used to enforce an upper limit on the size of a file to read ",
-    "Failed reads for specific files are skipped without interrupting the entire operation. ",
+    "Failed reads for specific files do not interrupt the entire operation: they are reported in a text block ",
+    "and listed with their error in the structured 'errors'. ",
     "Only works within allowed directories."),
     destructive_hint = false,
     idempotent_hint = false,
@@ -29,14 +31,21 @@ impl ReadMultipleMediaFiles {
         params: Self,
         context: &FileSystemService,
     ) -> std::result::Result<CallToolResult, CallToolError> {
-        let result = context
+        let results = context
             .read_media_files(params.paths, params.max_bytes.map(|v| v as usize))
-            .await
-            .map_err(CallToolError::new)?;
+            .await;
 
-        let content: Vec<_> = result
+        let mut errors = vec![];
+        let mut content: Vec<_> = results
             .into_iter()
-            .filter_map(|(kind, content)| {
+            .filter_map(|(path, result)| {
+                let (kind, content) = match result {
+                    Ok(read) => read,
+                    Err(err) => {
+                        errors.push((path, err.to_string()));
+                        return None;
+                    }
+                };
                 let mime_type = kind.mime_type().to_string();
 
                 match kind.matcher_type() {
@@ -46,16 +55,35 @@ impl ReadMultipleMediaFiles {
                     infer::MatcherType::Audio => Some(ContentBlock::AudioContent(
                         AudioContent::new(content, mime_type, None, None),
                     )),
-                    _ => None,
+                    _ => {
+                        errors.push((
+                            path,
+                            format!("Not an image or audio file (mime: {mime_type})."),
+                        ));
+                        None
+                    }
                 }
             })
             .collect();
+        let files_read = content.len();
+        if !errors.is_empty() {
+            let failures: Vec<String> = errors
+                .iter()
+                .map(|(path, error)| format!("{path}: Error - {error}"))
+                .collect();
+            content.push(ContentBlock::TextContent(TextContent::from(format!(
+                "Failed to read {} of {} files:\n{}",
+                errors.len(),
+                files_read + errors.len(),
+                failures.join("\n")
+            ))));
+        }
 
         Ok(CallToolResult {
             content,
             is_error: None,
             meta: None,
-            structured_content: None,
+            structured_content: Some(read_report(files_read, errors)),
         })
     }
 }
@@ -1,9 +1,8 @@
 use crate::fs_service::FileSystemService;
-use futures::future::join_all;
 use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
 use rust_mcp_sdk::schema::TextContent;
 use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
-use std::path::Path;
+use serde_json::{Map, Value, json};
 
 #[mcp_tool(
     name = "read_multiple_text_files",
@@ -12,7 +11,8 @@ use std::path::Path;
     "This is more efficient than reading files one by one when you need to analyze ",
     "or compare multiple files. Each file's content is returned with its ",
     "path as a reference. Failed reads for individual files won't stop ",
-    "the entire operation. Only works within allowed directories."),
+    "the entire operation: they are reported inline and listed with their error in the structured 'errors'. ",
+    "Only works within allowed directories."),
     destructive_hint = false,
     idempotent_hint = false,
     open_world_hint = false,
@@ -29,28 +29,42 @@ impl ReadMultipleTextFiles {
         params: Self,
         context: &FileSystemService,
     ) -> std::result::Result<CallToolResult, CallToolError> {
-        let content_futures: Vec<_> = params
-            .paths
-            .iter()
-            .map(|path| async move {
-                {
-                    let content = context
-                        .read_text_file(Path::new(&path))
-                        .await
-                        .map_err(CallToolError::new);
+        let results = context.read_text_files(params.paths).await;
 
-                    content.map_or_else(
-                        |err| format!("{path}: Error - {err}"),
-                        |value| format!("{path}:\n{value}\n"),
-                    )
+        let mut errors = vec![];
+        let contents: Vec<String> = results
+            .into_iter()
+            .map(|(path, result)| match result {
+                Ok(value) => format!("{path}:\n{value}\n"),
+                Err(err) => {
+                    let text = format!("{path}: Error - {err}");
+                    errors.push((path, err.to_string()));
+                    text
                 }
             })
             .collect();
+        let files_read = contents.len() - errors.len();
 
-        let contents = join_all(content_futures).await;
-
-        Ok(CallToolResult::text_content(vec![TextContent::from(
-            contents.join("\n---\n"),
-        )]))
+        Ok(
+            CallToolResult::text_content(vec![TextContent::from(contents.join("\n---\n"))])
+                .with_structured_content(read_report(files_read, errors)),
+        )
     }
 }
+
+/// Structured report of a multi-file read: how many files were read and the error of each
+/// path that could not be.
+pub(crate) fn read_report(files_read: usize, errors: Vec<(String, String)>) -> Map<String, Value> {
+    let mut report = Map::new();
+    report.insert("files_read".to_string(), json!(files_read));
+    report.insert(
+        "errors".to_string(),
+        Value::Array(
+            errors
+                .into_iter()
+                .map(|(path, error)| json!({ "path": path, "error": error }))
+                .collect(),
+        ),
+    );
+    report
+}
@@ -0,0 +1,53 @@
+use std::path::Path;
+
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+
+use crate::fs_service::{FileSystemService, StructuredFormat};
+
+#[mcp_tool(
+    name = "read_structured_file",
+    title="Read a structured file",
+    description = concat!("Reads a YAML, TOML or JSON file and returns its content as normalized, pretty-printed JSON. ",
+    "The format is detected from the file extension (.json, .yaml, .yml, .toml) unless 'format' is specified. ",
+    "Optionally, 'select' takes a dotted path (e.g. 'package.name' or 'services.web.ports.0') to return only that value. ",
+    "Useful for inspecting configuration files without parsing them manually. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct ReadStructuredFile {
+    /// The path of the file to read.
+    pub path: String,
+    /// The file format: "json", "yaml" or "toml". Detected from the file extension when omitted.
+    pub format: Option<StructuredFormat>,
+    /// Optional dotted path selecting a single value, e.g. 'dependencies.serde'. Numeric segments index into arrays.
+    pub select: Option<String>,
+}
+
+impl ReadStructuredFile {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let value = context
+            .read_structured_file(
+                Path::new(&params.path),
+                params.format,
+                params.select.as_deref(),
+            )
+            .await
+            .map_err(CallToolError::new)?;
+
+        let content = serde_json::to_string_pretty(&value).map_err(CallToolError::new)?;
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            content,
+        )]))
+    }
+}
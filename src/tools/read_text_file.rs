@@ -4,7 +4,8 @@ use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
 use rust_mcp_sdk::schema::TextContent;
 use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
 
-use crate::fs_service::FileSystemService;
+use crate::fs_service::{FileSystemService, utils::sha256_hex};
+use serde_json::{Map, Value, json};
 
 #[mcp_tool(
     name = "read_text_file",
@@ -12,7 +13,12 @@ use crate::fs_service::FileSystemService;
     description = concat!("Read the complete contents of a text file from the file system as text. ",
     "Handles various text encodings and provides detailed error messages if the ",
     "file cannot be read. Use this tool when you need to examine the contents of ",
-    "a single file. Only works within allowed directories."),
+    "a single file. Large files are truncated after 'max_bytes' (a server default applies) or 'max_lines', ",
+    "with a notice giving the line offset to continue from with 'read_file_lines'. ",
+    "The '_meta' of the result holds the 'sha256' of the file when it was read completely. ",
+    "To re-check a file already read, pass that hash as 'if_none_match_sha256': if the file is unchanged, ",
+    "a short 'not modified' response is returned instead of the content. ",
+    "Only works within allowed directories."),
     destructive_hint = false,
     idempotent_hint = false,
     open_world_hint = false,
@@ -22,6 +28,16 @@ use crate::fs_service::FileSystemService;
 pub struct ReadTextFile {
     /// The path of the file to read.
     pub path: String,
+    /// Maximum number of bytes to return. Defaults to the server's limit.
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub max_bytes: Option<u64>,
+    /// Maximum number of lines to return.
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub max_lines: Option<u64>,
+    /// SHA-256 hash (hex) of the file content as last read. If the file still has this content,
+    /// only a 'not modified' notice is returned.
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub if_none_match_sha256: Option<String>,
 }
 
 impl ReadTextFile {
@@ -29,13 +45,74 @@ impl ReadTextFile {
         params: Self,
         context: &FileSystemService,
     ) -> std::result::Result<CallToolResult, CallToolError> {
-        let content = context
-            .read_text_file(Path::new(&params.path))
+        let current_sha256 = match &params.if_none_match_sha256 {
+            Some(expected) => {
+                let current = context
+                    .file_sha256(Path::new(&params.path))
+                    .await
+                    .map_err(CallToolError::new)?;
+                if current.eq_ignore_ascii_case(expected.trim()) {
+                    return Ok(not_modified(&params.path, current));
+                }
+                Some(current)
+            }
+            None => None,
+        };
+        let read = context
+            .read_text_file_limited(
+                Path::new(&params.path),
+                params
+                    .max_bytes
+                    .map(|max_bytes| usize::try_from(max_bytes).unwrap_or(usize::MAX)),
+                params.max_lines.map(|max_lines| max_lines as usize),
+            )
             .await
             .map_err(CallToolError::new)?;
 
-        Ok(CallToolResult::text_content(vec![TextContent::from(
-            content,
-        )]))
+        if !read.truncated {
+            // the complete content is exactly the content of the file
+            let sha256 = current_sha256.unwrap_or_else(|| sha256_hex(read.content.as_bytes()));
+            let mut meta = Map::new();
+            meta.insert("sha256".to_string(), Value::String(sha256));
+            return Ok(
+                CallToolResult::text_content(vec![TextContent::from(read.content)])
+                    .with_meta(Some(meta)),
+            );
+        }
+        let returned_bytes = read.content.len();
+        let notice = format!(
+            "[File truncated: {} lines ({returned_bytes} of {} bytes) returned. To read the rest, use 'read_file_lines' with offset {}.]",
+            read.returned_lines, read.total_bytes, read.returned_lines
+        );
+        let mut structured = Map::new();
+        structured.insert("truncated".to_string(), Value::Bool(true));
+        structured.insert("total_bytes".to_string(), json!(read.total_bytes));
+        structured.insert("returned_bytes".to_string(), json!(returned_bytes));
+        structured.insert("returned_lines".to_string(), json!(read.returned_lines));
+        structured.insert("next_line_offset".to_string(), json!(read.returned_lines));
+        let result = CallToolResult::text_content(vec![
+            TextContent::from(read.content),
+            TextContent::from(notice),
+        ])
+        .with_structured_content(structured);
+        Ok(match current_sha256 {
+            Some(sha256) => {
+                let mut meta = Map::new();
+                meta.insert("sha256".to_string(), Value::String(sha256));
+                result.with_meta(Some(meta))
+            }
+            None => result,
+        })
     }
 }
+
+/// The response to a conditional read of `path` whose content still has the hash `sha256`.
+pub(crate) fn not_modified(path: &str, sha256: String) -> CallToolResult {
+    let mut meta = Map::new();
+    meta.insert("not_modified".to_string(), Value::Bool(true));
+    meta.insert("sha256".to_string(), Value::String(sha256));
+    CallToolResult::text_content(vec![TextContent::from(format!(
+        "Not modified: '{path}' still has the content with the given sha256."
+    ))])
+    .with_meta(Some(meta))
+}
@@ -0,0 +1,123 @@
+use rust_mcp_sdk::schema::{CallToolResult, ContentBlock, EmbeddedResourceResource, TextContent};
+use serde_json::{Map, Value, json};
+
+/// Size of the content returned by a tool call. Binary content is counted in its base64 form.
+pub fn result_size(result: &CallToolResult) -> u64 {
+    result.content.iter().map(block_size).sum::<usize>() as u64
+}
+
+fn block_size(block: &ContentBlock) -> usize {
+    match block {
+        ContentBlock::TextContent(text) => text.text.len(),
+        ContentBlock::ImageContent(image) => image.data.len(),
+        ContentBlock::AudioContent(audio) => audio.data.len(),
+        ContentBlock::EmbeddedResource(embedded) => match &embedded.resource {
+            EmbeddedResourceResource::TextResourceContents(text) => text.text.len(),
+            EmbeddedResourceResource::BlobResourceContents(blob) => blob.blob.len(),
+        },
+        ContentBlock::ResourceLink(_) => 0,
+    }
+}
+
+/// Cuts `text` to at most `max_bytes`, at the end of the last complete line if there is one.
+fn truncate_text(text: &str, max_bytes: usize) -> &str {
+    let mut end = max_bytes.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let text = &text[..end];
+    match text.rfind('\n') {
+        Some(index) => &text[..=index],
+        None => text,
+    }
+}
+
+fn json_size<T: ::serde::Serialize>(value: &T) -> usize {
+    serde_json::to_vec(value).map_or(usize::MAX, |bytes| bytes.len())
+}
+
+/// Keeps structured content within `max_bytes` of JSON, dropping the last items of its arrays
+/// (e.g. the matches of a search) as needed. Returns `None` if it cannot be made small enough.
+fn trim_structured_content(
+    mut structured: Map<String, Value>,
+    max_bytes: usize,
+) -> Option<Map<String, Value>> {
+    let mut excess = json_size(&structured).saturating_sub(max_bytes);
+    for value in structured.values_mut() {
+        let Value::Array(items) = value else {
+            continue;
+        };
+        while excess > 0
+            && let Some(item) = items.pop()
+        {
+            // one more byte for the separating comma
+            excess = excess.saturating_sub(json_size(&item) + 1);
+        }
+    }
+    (json_size(&structured) <= max_bytes).then_some(structured)
+}
+
+/// Keeps the content of a tool result within `max_bytes`. Text is cut at a line boundary, binary
+/// content that does not fit is dropped, and so is every block after the budget is used up.
+/// Structured content is kept within the same budget on its own, dropping trailing array items
+/// or, failing that, the whole of it.
+/// A truncated result carries `truncated: true` and the sizes in its `_meta`, followed by a note
+/// telling the client how to fetch the remainder.
+pub fn limit_response_size(mut result: CallToolResult, max_bytes: usize) -> CallToolResult {
+    let original_bytes = result_size(&result);
+    if original_bytes <= max_bytes as u64 {
+        return result;
+    }
+
+    let mut remaining = max_bytes;
+    let mut omitted_blocks = 0;
+    let mut content = Vec::with_capacity(result.content.len());
+    for block in result.content {
+        let size = block_size(&block);
+        if size <= remaining {
+            remaining -= size;
+            content.push(block);
+            continue;
+        }
+        match block {
+            ContentBlock::TextContent(mut text) if remaining > 0 => {
+                let truncated = truncate_text(&text.text, remaining);
+                remaining = 0;
+                if truncated.is_empty() {
+                    omitted_blocks += 1;
+                } else {
+                    text.text = truncated.to_string();
+                    content.push(ContentBlock::TextContent(text));
+                }
+            }
+            _ => omitted_blocks += 1,
+        }
+    }
+
+    result.content = content;
+    let returned_bytes = result_size(&result);
+    result.content.push(ContentBlock::TextContent(TextContent::from(format!(
+        "[Response truncated: {returned_bytes} of {original_bytes} bytes returned{}. To get the remainder, request a smaller part of it: use offsets or pagination parameters of the tool where available (e.g. read_file_lines for text files), narrow the path or pattern, or pass a larger `_meta.maxResponseBytes` argument, up to the server limit.]",
+        if omitted_blocks > 0 {
+            format!(", {omitted_blocks} content blocks omitted")
+        } else {
+            String::new()
+        }
+    ))));
+    // structured content duplicates the full output, so it gets the budget of its own
+    let structured_bytes = result.structured_content.as_ref().map(json_size);
+    result.structured_content = result
+        .structured_content
+        .take()
+        .and_then(|structured| trim_structured_content(structured, max_bytes));
+
+    let meta = result.meta.get_or_insert_with(Map::new);
+    meta.insert("truncated".to_string(), Value::Bool(true));
+    meta.insert("originalBytes".to_string(), json!(original_bytes));
+    meta.insert("returnedBytes".to_string(), json!(returned_bytes));
+    meta.insert("maxResponseBytes".to_string(), json!(max_bytes));
+    if structured_bytes.is_some_and(|bytes| bytes > max_bytes) {
+        meta.insert("structuredContentTruncated".to_string(), Value::Bool(true));
+    }
+    result
+}
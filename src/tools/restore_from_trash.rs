@@ -0,0 +1,66 @@
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::fs_service::FileSystemService;
+
+#[mcp_tool(
+    name = "restore_from_trash",
+    title="Restore from trash",
+    description = concat!("Restores a file or directory that was deleted by another tool while the server runs with `--use-trash`. ",
+    "`path` is the original path of the deleted item, the most recently deleted item at that path is moved back. ",
+    "Fails if something exists at that path again. Without `path`, lists the items in the trash. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = false
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct RestoreFromTrash {
+    /// The original path of the deleted file or directory. Omit it to list the trash.
+    pub path: Option<String>,
+}
+
+impl RestoreFromTrash {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let Some(path) = params.path else {
+            let entries = context.list_trash().await.map_err(CallToolError::new)?;
+            let mut output = if entries.is_empty() {
+                "The trash is empty.".to_string()
+            } else {
+                format!("{} items in the trash:\n", entries.len())
+            };
+            for entry in entries {
+                writeln!(
+                    output,
+                    "  {} (deleted {})",
+                    entry.original_path.display(),
+                    entry.deleted_at
+                )
+                .map_err(CallToolError::new)?;
+            }
+            return Ok(CallToolResult::text_content(vec![TextContent::from(
+                output,
+            )]));
+        };
+
+        let entry = context
+            .restore_from_trash(Path::new(&path))
+            .await
+            .map_err(CallToolError::new)?;
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            format!(
+                "Successfully restored {} (deleted {})",
+                entry.original_path.display(),
+                entry.deleted_at
+            ),
+        )]))
+    }
+}
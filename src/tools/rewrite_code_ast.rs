@@ -0,0 +1,116 @@
+use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
+use rust_mcp_sdk::schema::TextContent;
+use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
+use std::fmt::Write;
+
+use crate::fs_service::{AstRewriteResult, AstSearchLimits, FileSystemService};
+
+#[mcp_tool(
+    name = "rewrite_code_ast",
+    title = "Rewrite code using AST patterns",
+    description = concat!(
+        "Performs a structural search-and-replace across files using Abstract Syntax Tree (AST) pattern matching. ",
+        "Every match of 'astPattern' is replaced by the 'rewrite' template, in which the $UPPERCASE ",
+        "(and $$$MULTI) wildcards captured by the pattern are substituted.\n\n",
+        "Examples:\n",
+        "- astPattern: 'console.log($MSG)', rewrite: 'logger.info($MSG)'\n",
+        "- astPattern: '$A.unwrap()', rewrite: '$A.expect(\"TODO\")'\n\n",
+        "Returns a git-style diff for each modified file. Use 'dryRun' to preview the changes without applying them. ",
+        "Nothing is rewritten when more files than `max_files` (default: the server's `--ast-max-files`, 10000) would have to be parsed. ",
+        "Only works within allowed directories."
+    ),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = false
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+/// A tool for rewriting code using AST (Abstract Syntax Tree) pattern matching.
+pub struct RewriteCodeAst {
+    /// The directory path to search in.
+    pub path: String,
+    /// The file glob pattern to match (e.g., "**/*.ts", "src/**/*.rs").
+    pub pattern: String,
+    /// The AST pattern to search for (e.g., "console.log($MSG)").
+    /// Use $UPPERCASE for wildcards that match any AST node.
+    #[serde(rename = "astPattern")]
+    pub ast_pattern: String,
+    /// The replacement template (e.g., "logger.info($MSG)"). Wildcards captured by the pattern are substituted.
+    pub rewrite: String,
+    /// The programming language to parse.
+    /// Supported: typescript, javascript, rust, python, go, java, cpp, c, csharp, swift, ruby, php, html, css, etc.
+    pub language: String,
+    #[serde(rename = "excludePatterns")]
+    /// Optional list of glob patterns to exclude from the search.
+    pub exclude_patterns: Option<Vec<String>>,
+    #[serde(rename = "fileExtensions")]
+    /// Optional list of file extensions to filter (e.g., ["ts", "tsx"]).
+    pub file_extensions: Option<Vec<String>>,
+    /// Refuse the rewrite if more than this many files would have to be parsed (default: the server's `--ast-max-files`, 10000).
+    pub max_files: Option<u64>,
+    /// Preview changes using git-style diff format without applying them.
+    #[serde(
+        rename = "dryRun",
+        default,
+        skip_serializing_if = "std::option::Option::is_none"
+    )]
+    pub dry_run: Option<bool>,
+}
+
+impl RewriteCodeAst {
+    fn format_result(results: &[AstRewriteResult], dry_run: bool) -> String {
+        let replacements: usize = results.iter().map(|r| r.replacements).sum();
+        let mut output = String::new();
+        for result in results {
+            // Format diff with appropriate number of backticks
+            let mut num_backticks = 3;
+            while result.diff.contains(&"`".repeat(num_backticks)) {
+                num_backticks += 1;
+            }
+            let fence = "`".repeat(num_backticks);
+            let _ = writeln!(output, "{fence}diff\n{}{fence}\n", result.diff);
+        }
+        let _ = write!(
+            output,
+            "{} {} replacement(s) in {} file(s).",
+            if dry_run { "Would apply" } else { "Applied" },
+            replacements,
+            results.len()
+        );
+        output
+    }
+
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let dry_run = params.dry_run.unwrap_or(false);
+        let results = context
+            .rewrite_files_ast(
+                &params.path,
+                &params.pattern,
+                &params.ast_pattern,
+                &params.rewrite,
+                &params.language,
+                params.exclude_patterns,
+                params.file_extensions,
+                AstSearchLimits {
+                    max_file_size: None,
+                    max_files: params.max_files.map(|max_files| max_files as usize),
+                },
+                dry_run,
+            )
+            .await
+            .map_err(CallToolError::new)?;
+
+        if results.is_empty() {
+            return Ok(CallToolResult::text_content(vec![TextContent::from(
+                "No matches found to rewrite.".to_string(),
+            )]));
+        }
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            Self::format_result(&results, dry_run),
+        )]))
+    }
+}
@@ -0,0 +1,123 @@
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+use serde_json::{Map, Value, json};
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::fs_service::{ArchiveSearchResult, FileSystemService, WalkOptions, utils::OutputFormat};
+
+#[mcp_tool(
+    name = "search_archive_content",
+    title="Search archive content",
+    description = concat!("Searches for text or regex patterns in the entries of zip archives, without extracting them to disk. ",
+    "'path' is either a zip file or a directory whose archives matching 'archive_pattern' (default: '*.zip') are searched. ",
+    "'entry_pattern' is a glob restricting which entries are searched (e.g., '*.txt', 'docs/**/*.md'). ",
+    "The 'query' parameter is literal text by default, or a regex when 'is_regex' is true; the search is case-insensitive. ",
+    "Binary entries are skipped. Each match is reported as 'archive.zip!entry/path:line: snippet'. ",
+    "Set 'output_format' to `json` to receive structured results in `structuredContent`. ",
+    "Only searches within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct SearchArchiveContent {
+    /// A zip file, or a directory to search for zip files in.
+    pub path: String,
+    /// Search term to find in the entries. Literal text unless `is_regex` is true.
+    pub query: String,
+    /// Whether the query is a regular expression (default: false).
+    pub is_regex: Option<bool>,
+    /// Glob pattern of the archives to search when `path` is a directory (default: "*.zip").
+    #[json_schema(default = "*.zip")]
+    pub archive_pattern: Option<String>,
+    /// Glob pattern of the entries to search within each archive (default: all entries).
+    pub entry_pattern: Option<String>,
+    /// Optional list of glob patterns of archives to exclude.
+    pub exclude_patterns: Option<Vec<String>>,
+    /// Skip paths ignored by .gitignore and .ignore files (default: true).
+    #[json_schema(default = "true")]
+    pub respect_gitignore: Option<bool>,
+    /// Include hidden files and directories (default: false).
+    #[json_schema(default = "false")]
+    pub include_hidden: Option<bool>,
+    /// Specify the output format, accepts either `text` or `json` (default: text).
+    pub output_format: Option<OutputFormat>,
+}
+
+impl SearchArchiveContent {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let results = context
+            .search_archive_content(
+                Path::new(&params.path),
+                params.archive_pattern.as_deref(),
+                params.entry_pattern.as_deref(),
+                &params.query,
+                params.is_regex.unwrap_or_default(),
+                params.exclude_patterns.unwrap_or_default(),
+                WalkOptions::new(params.respect_gitignore, params.include_hidden, None),
+            )
+            .await
+            .map_err(CallToolError::new)?;
+
+        if let Some(OutputFormat::Json) = params.output_format {
+            let structured = Self::format_json(&results);
+            let json_str = serde_json::to_string(&structured).map_err(CallToolError::new)?;
+            return Ok(
+                CallToolResult::text_content(vec![TextContent::from(json_str)])
+                    .with_structured_content(structured),
+            );
+        }
+
+        if results.is_empty() {
+            return Ok(CallToolResult::text_content(vec![TextContent::from(
+                "No matches found in the archives.".to_string(),
+            )]));
+        }
+        let mut output = String::new();
+        for result in &results {
+            let location = result.location();
+            for m in &result.matches {
+                writeln!(output, "{location}:{}: {}", m.line_number, m.line_text)
+                    .map_err(CallToolError::new)?;
+            }
+        }
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            output,
+        )]))
+    }
+
+    fn format_json(results: &[ArchiveSearchResult]) -> Map<String, Value> {
+        let results: Vec<Value> = results
+            .iter()
+            .map(|result| {
+                json!({
+                    "archive": result.archive_path.display().to_string(),
+                    "entry": result.entry,
+                    "location": result.location(),
+                    "matches": result
+                        .matches
+                        .iter()
+                        .map(|m| {
+                            json!({
+                                "line": m.line_number,
+                                "column": m.start_pos,
+                                "end_column": m.end_pos,
+                                "snippet": m.line_text,
+                            })
+                        })
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        let mut structured = Map::new();
+        structured.insert("results".to_string(), Value::Array(results));
+        structured
+    }
+}
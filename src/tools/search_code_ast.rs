@@ -1,9 +1,31 @@
 use crate::error::ServiceError;
-use crate::fs_service::{AstFileSearchResult, FileSystemService};
+use crate::fs_service::{
+    AstFileSearchResult, AstSearchLimits, AstSearchOutcome, FileSystemService, OperationContext,
+    WalkOptions,
+    utils::{OutputFormat, search_summary_meta},
+};
+use crate::tools::pagination::{MatchPage, cursor_offset, page_limits};
 use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
 use rust_mcp_sdk::schema::TextContent;
 use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
+use serde_json::{Map, Value, json};
 use std::fmt::Write;
+use std::time::Instant;
+
+/// How much of each match `search_code_ast` returns.
+#[derive(
+    ::serde::Deserialize, ::serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AstOutputMode {
+    /// Every match with its code, captures and context.
+    #[default]
+    Matches,
+    /// The number of matches in each file.
+    Count,
+    /// The paths of the files with matches.
+    FilesOnly,
+}
 
 #[mcp_tool(
     name = "search_code_ast",
@@ -18,7 +40,25 @@ use std::fmt::Write;
         "- Pattern: 'const $VAR = $VALUE' matches all const declarations\n",
         "- Pattern: 'import { $ITEMS } from \"$MODULE\"' matches named imports\n\n",
         "Supported languages: TypeScript, JavaScript, Rust, Python, Go, Java, C/C++, and more.\n",
-        "Use 'fileExtensions' to filter files (e.g., [\"ts\", \"tsx\"] for TypeScript files)."
+        "For relational constraints, pass a full ast-grep YAML rule in 'rule_yaml' instead of 'astPattern', e.g.:\n",
+        "rule:\n  pattern: await $CALL\n  inside:\n    kind: for_statement\n    stopBy: end\n",
+        "Rules support pattern/kind/regex, inside/has/follows/precedes, all/any/not and constraints ",
+        "(https://ast-grep.github.io/reference/rule.html).\n",
+        "Set 'injected_language' to search code embedded in the files, e.g. language 'html' with injected_language 'javascript' or 'css' ",
+        "searches <script> and <style> elements; the pattern is then written in the injected language and positions refer to the host file. ",
+        "Use 'fileExtensions' to filter files (e.g., [\"ts\", \"tsx\"] for TypeScript files). ",
+        "The text captured by each named wildcard ($NAME, $$$ARGS) is returned with every match. ",
+        "Files are sorted by path and matches by position, after a summary of the number of files matched and total matches. ",
+        "Use 'max_results' and 'max_matches_per_file' to limit the output; when results are truncated, ",
+        "pass the 'next_cursor' value from the result '_meta' as 'cursor' to fetch the next page. ",
+        "Set 'output_mode' to `count` for the number of matches in each file, most matches first, or to `files_only` for the paths of the matching files, ",
+        "e.g. for audits over many files; these modes report every matching file and ignore 'max_results' and 'cursor'. ",
+        "Set 'output_format' to `json` to receive structured results in `structuredContent`. ",
+        "The result `_meta` reports `files_scanned`, `files_matched`, `matches`, `duration_ms` and `truncated`, which is true when the search stopped at its maximum number of files or the output was limited. ",
+        "Set `respect_gitignore` to false to include git-ignored paths, and `include_hidden` to true to include dotfiles. `follow_symlinks` follows symlinks that stay within allowed directories, visiting each linked directory once. ",
+        "Files larger than `max_file_size` bytes are skipped and the search stops after `max_files` files (server defaults: 1 MiB and 10000); ",
+        "the result `_meta` reports `skipped_large_files` and, when the search stopped early, `max_files`. ",
+        "Set `threads` to bound the number of threads searching in parallel, or `low_priority` to true to search on a single thread."
     ),
     destructive_hint = false,
     idempotent_hint = false,
@@ -34,11 +74,18 @@ pub struct SearchCodeAst {
     pub pattern: String,
     /// The AST pattern to search for (e.g., "function $NAME($ARGS) { $BODY }").
     /// Use $UPPERCASE for wildcards that match any AST node.
-    #[serde(rename = "astPattern")]
-    pub ast_pattern: String,
+    /// Either 'astPattern' or 'rule_yaml' must be provided.
+    #[serde(rename = "astPattern", default)]
+    pub ast_pattern: Option<String>,
+    /// An ast-grep YAML rule to match instead of 'astPattern', supporting relational (inside/has/follows/precedes),
+    /// composite (all/any/not) and regex constraints. See https://ast-grep.github.io/reference/rule.html
+    pub rule_yaml: Option<String>,
     /// The programming language to parse.
     /// Supported: typescript, javascript, rust, python, go, java, cpp, c, csharp, swift, ruby, php, html, css, etc.
     pub language: String,
+    /// Match the pattern against code of this language embedded in the files instead, e.g.
+    /// 'javascript' or 'css' inside 'html' files. The pattern is then written in this language.
+    pub injected_language: Option<String>,
     #[serde(rename = "excludePatterns")]
     /// Optional list of glob patterns to exclude from the search.
     pub exclude_patterns: Option<Vec<String>>,
@@ -49,23 +96,65 @@ pub struct SearchCodeAst {
     /// Optional: Maximum lines to show per match (default: unlimited).
     /// Useful for limiting output when matches are very large.
     pub max_lines: Option<u64>,
+    /// Optional number of source lines to show before and after each match (default: 0).
+    pub context_lines: Option<u64>,
+    /// If true, shows the enclosing function/class/impl of each match, e.g. 'class View > method render' (default: false).
+    pub include_enclosing: Option<bool>,
+    /// Skip paths ignored by .gitignore and .ignore files (default: true).
+    pub respect_gitignore: Option<bool>,
+    /// Include hidden files and directories (default: false).
+    pub include_hidden: Option<bool>,
+    /// Follow symlinks whose targets are inside the allowed directories (default: false).
+    pub follow_symlinks: Option<bool>,
+    /// Number of threads searching in parallel, 0 for one per CPU core (default: the server's `--search-threads`).
+    pub threads: Option<u64>,
+    /// Search on a single thread to leave the CPU cores to other work (default: the server's `--low-priority-search`).
+    pub low_priority: Option<bool>,
+    /// Skip files larger than this many bytes (default: the server's `--ast-max-file-size`, 1 MiB).
+    pub max_file_size: Option<u64>,
+    /// Stop the search after parsing this many files (default: the server's `--ast-max-files`, 10000).
+    pub max_files: Option<u64>,
+    /// Maximum number of matches to return in a single response (optional).
+    pub max_results: Option<u64>,
+    /// Maximum number of matches to report for each file (optional).
+    pub max_matches_per_file: Option<u64>,
+    /// Opaque continuation cursor returned as `next_cursor` in the `_meta` of a previous truncated response.
+    pub cursor: Option<String>,
+    /// Return the `matches`, only their `count` per file, or the matching files (`files_only`) (default: matches).
+    #[json_schema(default = "matches")]
+    pub output_mode: Option<AstOutputMode>,
+    /// Specify the output format, accepts either `text` or `json` (default: text).
+    #[json_schema(default = "text")]
+    pub output_format: Option<OutputFormat>,
 }
 
 impl SearchCodeAst {
-    fn format_result(&self, results: Vec<AstFileSearchResult>) -> String {
+    fn format_result(&self, page: MatchPage<AstFileSearchResult>) -> String {
         let estimated_capacity = 4096;
         let mut output = String::with_capacity(estimated_capacity);
+        let _ = writeln!(
+            output,
+            "{} files matched, {} total matches\n",
+            page.total_files, page.total_matches
+        );
 
-        for file_result in results {
+        for file_result in page.results {
             let _ = writeln!(output, "{}", file_result.file_path.display());
 
             for m in &file_result.matches {
                 // Format: "  line:col-range: matched code"
+                let enclosing = match (&m.enclosing, self.include_enclosing) {
+                    (Some(enclosing), Some(true)) => format!(" in {enclosing}"),
+                    _ => String::new(),
+                };
                 let _ = writeln!(
                     output,
-                    "  {}:{} (bytes {}-{}):",
-                    m.line_number, m.column, m.byte_range.0, m.byte_range.1
+                    "  {}:{} (bytes {}-{}){}:",
+                    m.line_number, m.column, m.byte_range.0, m.byte_range.1, enclosing
                 );
+                for line in &m.context_before {
+                    let _ = writeln!(output, "    | {}", line);
+                }
 
                 // Handle line limiting
                 let lines: Vec<&str> = m.matched_code.lines().collect();
@@ -92,6 +181,20 @@ impl SearchCodeAst {
                         let _ = writeln!(output, "    {}", line);
                     }
                 }
+                for line in &m.context_after {
+                    let _ = writeln!(output, "    | {}", line);
+                }
+                for (name, value) in &m.captures {
+                    // Only the first line of multi-line captures is shown, the full code is above
+                    let mut value_lines = value.lines();
+                    let first_line = value_lines.next().unwrap_or_default();
+                    let ellipsis = if value_lines.next().is_some() {
+                        " ..."
+                    } else {
+                        ""
+                    };
+                    let _ = writeln!(output, "    ${name} = {first_line}{ellipsis}");
+                }
                 output.push('\n');
             }
 
@@ -101,37 +204,289 @@ impl SearchCodeAst {
         output
     }
 
+    fn format_json(&self, page: &MatchPage<AstFileSearchResult>) -> Map<String, Value> {
+        let json_results: Vec<Value> = page
+            .results
+            .iter()
+            .map(|file_result| {
+                json!({
+                    "path": file_result.file_path.display().to_string(),
+                    "matches": file_result
+                        .matches
+                        .iter()
+                        .map(|m| {
+                            let mut value = json!({
+                                "line": m.line_number,
+                                "column": m.column,
+                                "byte_range": [m.byte_range.0, m.byte_range.1],
+                                "code": m.matched_code,
+                                "captures": m.captures,
+                            });
+                            if self.include_enclosing == Some(true) {
+                                value["enclosing"] = json!(m.enclosing);
+                            }
+                            if self.context_lines.is_some_and(|n| n > 0) {
+                                value["context_before"] = json!(m.context_before);
+                                value["context_after"] = json!(m.context_after);
+                            }
+                            value
+                        })
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+
+        let mut structured = Map::new();
+        structured.insert(
+            "summary".to_string(),
+            json!({
+                "files_matched": page.total_files,
+                "total_matches": page.total_matches,
+            }),
+        );
+        structured.insert("results".to_string(), Value::Array(json_results));
+        structured
+    }
+
+    /// Formats the number of matches of each file, most matches first, as text or JSON.
+    fn format_aggregate(
+        &self,
+        results: &[AstFileSearchResult],
+        mode: AstOutputMode,
+    ) -> (String, Map<String, Value>) {
+        let total_matches: usize = results.iter().map(|r| r.matches.len()).sum();
+        let mut counts: Vec<_> = results
+            .iter()
+            .map(|r| (r.file_path.display().to_string(), r.matches.len()))
+            .collect();
+        if mode == AstOutputMode::Count {
+            counts.sort_by(|(a_path, a_count), (b_path, b_count)| {
+                b_count.cmp(a_count).then(a_path.cmp(b_path))
+            });
+        }
+
+        let mut output = format!(
+            "{} files matched, {total_matches} total matches\n\n",
+            results.len()
+        );
+        for (path, count) in &counts {
+            let _ = match mode {
+                AstOutputMode::Count => writeln!(output, "{count}\t{path}"),
+                _ => writeln!(output, "{path}"),
+            };
+        }
+
+        let mut structured = Map::new();
+        structured.insert(
+            "summary".to_string(),
+            json!({
+                "files_matched": results.len(),
+                "total_matches": total_matches,
+            }),
+        );
+        let files = match mode {
+            AstOutputMode::Count => counts
+                .into_iter()
+                .map(|(path, count)| json!({ "path": path, "count": count }))
+                .collect(),
+            _ => counts.into_iter().map(|(path, _)| json!(path)).collect(),
+        };
+        structured.insert("files".to_string(), Value::Array(files));
+        (output, structured)
+    }
+
+    /// Describes the files left out by the size and file count limits, or returns `None` if
+    /// none were hit.
+    fn limit_notice(outcome: &AstSearchOutcome) -> Option<String> {
+        let mut notices = vec![];
+        if outcome.skipped_large_files > 0 {
+            notices.push(format!(
+                "Skipped {} files larger than {} bytes, raise 'max_file_size' to include them.",
+                outcome.skipped_large_files, outcome.max_file_size
+            ));
+        }
+        if outcome.reached_file_limit {
+            notices.push(format!(
+                "Stopped after {} files, results may be incomplete. Narrow the search with 'pattern' or 'excludePatterns', or raise 'max_files'.",
+                outcome.max_files
+            ));
+        }
+        (!notices.is_empty()).then(|| notices.join(" "))
+    }
+
+    fn limits_meta(outcome: &AstSearchOutcome) -> Map<String, Value> {
+        let mut meta = Map::new();
+        if outcome.skipped_large_files > 0 {
+            meta.insert(
+                "skipped_large_files".to_string(),
+                outcome.skipped_large_files.into(),
+            );
+            meta.insert("max_file_size".to_string(), outcome.max_file_size.into());
+        }
+        if outcome.reached_file_limit {
+            meta.insert("max_files".to_string(), outcome.max_files.into());
+        }
+        meta
+    }
+
     pub async fn run_tool(
         params: Self,
         context: &FileSystemService,
     ) -> std::result::Result<CallToolResult, CallToolError> {
+        Self::run_tool_with_context(params, context, &OperationContext::default()).await
+    }
+
+    /// Runs the search, reporting its progress through `operation`.
+    pub async fn run_tool_with_context(
+        params: Self,
+        context: &FileSystemService,
+        operation: &OperationContext,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let started = Instant::now();
+        let offset = cursor_offset(params.cursor.as_deref())?;
+        let (max_results, max_matches_per_file) =
+            page_limits(params.max_results, params.max_matches_per_file)?;
+        let limits = AstSearchLimits {
+            max_file_size: params.max_file_size,
+            max_files: params.max_files.map(|max_files| max_files as usize),
+        };
         let exclude_patterns = params.exclude_patterns.clone();
         let file_extensions = params.file_extensions.clone();
 
-        match context
-            .search_files_ast(
-                &params.path,
-                &params.pattern,
-                &params.ast_pattern,
-                &params.language,
-                exclude_patterns,
-                file_extensions,
-            )
-            .await
-        {
-            Ok(results) => {
-                if results.is_empty() {
-                    return Ok(CallToolResult::with_error(CallToolError::new(
-                        ServiceError::FromString(
-                            "No AST pattern matches found in the files.".into(),
+        let search_result = match (&params.ast_pattern, &params.rule_yaml) {
+            (Some(ast_pattern), None) => {
+                context
+                    .search_files_ast_with_outcome(
+                        &params.path,
+                        &params.pattern,
+                        ast_pattern,
+                        &params.language,
+                        params.injected_language.as_deref(),
+                        exclude_patterns,
+                        file_extensions,
+                        params.context_lines.map(|n| n as usize),
+                        WalkOptions::new(
+                            params.respect_gitignore,
+                            params.include_hidden,
+                            params.follow_symlinks,
+                        )
+                        .with_threads(
+                            params.threads.map(|threads| threads as usize),
+                            params.low_priority,
+                        ),
+                        limits,
+                        operation,
+                    )
+                    .await
+            }
+            (None, Some(rule_yaml)) => {
+                context
+                    .search_files_ast_rule_with_outcome(
+                        &params.path,
+                        &params.pattern,
+                        rule_yaml,
+                        &params.language,
+                        params.injected_language.as_deref(),
+                        exclude_patterns,
+                        file_extensions,
+                        params.context_lines.map(|n| n as usize),
+                        WalkOptions::new(
+                            params.respect_gitignore,
+                            params.include_hidden,
+                            params.follow_symlinks,
+                        )
+                        .with_threads(
+                            params.threads.map(|threads| threads as usize),
+                            params.low_priority,
                         ),
+                        limits,
+                        operation,
+                    )
+                    .await
+            }
+            _ => Err(ServiceError::InvalidArgument(
+                "Exactly one of 'astPattern' or 'rule_yaml' must be provided.".into(),
+            )),
+        };
+
+        match search_result {
+            Ok(outcome) => {
+                let limit_notice = Self::limit_notice(&outcome);
+                if outcome.results.is_empty() {
+                    let message = match &limit_notice {
+                        Some(notice) => {
+                            format!("No AST pattern matches found in the files. {notice}")
+                        }
+                        None => "No AST pattern matches found in the files.".to_string(),
+                    };
+                    return Err(CallToolError::new(ServiceError::NoMatch(message)));
+                }
+                let limits_meta = Self::limits_meta(&outcome);
+                let mode = params.output_mode.unwrap_or_default();
+                if mode != AstOutputMode::Matches {
+                    let total_matches = outcome.results.iter().map(|r| r.matches.len()).sum();
+                    let mut meta = search_summary_meta(
+                        outcome.files_scanned,
+                        outcome.results.len(),
+                        total_matches,
+                        started,
+                        outcome.reached_file_limit,
+                    );
+                    meta.extend(limits_meta);
+                    let (mut output, structured) = params.format_aggregate(&outcome.results, mode);
+                    let result = match params.output_format {
+                        Some(OutputFormat::Json) => {
+                            let json_str =
+                                serde_json::to_string(&structured).map_err(CallToolError::new)?;
+                            CallToolResult::text_content(vec![TextContent::from(json_str)])
+                                .with_structured_content(structured)
+                        }
+                        _ => {
+                            if let Some(notice) = &limit_notice {
+                                let _ = writeln!(output, "\n[{notice}]");
+                            }
+                            CallToolResult::text_content(vec![TextContent::from(output)])
+                        }
+                    };
+                    return Ok(result.with_meta(Some(meta)));
+                }
+                let results = outcome.results;
+                let page = MatchPage::new(results, max_results, max_matches_per_file, offset);
+                if page.results.is_empty() {
+                    return Err(CallToolError::new(ServiceError::NoMatch(
+                        "No more matches found for the given cursor.".into(),
                     )));
                 }
-                Ok(CallToolResult::text_content(vec![TextContent::from(
-                    params.format_result(results),
-                )]))
+                let pagination = page.meta()?;
+                let mut meta = search_summary_meta(
+                    outcome.files_scanned,
+                    page.total_files,
+                    page.total_matches,
+                    started,
+                    outcome.reached_file_limit || pagination.is_some(),
+                );
+                meta.extend(pagination.unwrap_or_default());
+                meta.extend(limits_meta);
+                if let Some(OutputFormat::Json) = params.output_format {
+                    let structured = params.format_json(&page);
+                    let json_str =
+                        serde_json::to_string(&structured).map_err(CallToolError::new)?;
+                    return Ok(
+                        CallToolResult::text_content(vec![TextContent::from(json_str)])
+                            .with_structured_content(structured)
+                            .with_meta(Some(meta)),
+                    );
+                }
+                let mut output = params.format_result(page);
+                if let Some(notice) = &limit_notice {
+                    let _ = writeln!(output, "[{notice}]");
+                }
+                Ok(
+                    CallToolResult::text_content(vec![TextContent::from(output)])
+                        .with_meta(Some(meta)),
+                )
             }
-            Err(err) => Ok(CallToolResult::with_error(CallToolError::new(err))),
+            Err(err) => Err(CallToolError::new(err)),
         }
     }
 }
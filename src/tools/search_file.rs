@@ -1,10 +1,19 @@
 use std::path::Path;
+use std::time::Instant;
 
+use chrono::{DateTime, Utc};
 use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
 use rust_mcp_sdk::schema::TextContent;
 use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
+use serde_json::{Map, Value, json};
+
+use crate::fs_service::{
+    FileSystemService, SearchSortBy, SortOrder, WalkOptions, sort_search_results,
+    utils::{OutputFormat, format_bytes, format_system_time, search_summary_meta},
+};
+/// Number of results returned when `max_results` is not given.
+const DEFAULT_MAX_RESULTS: usize = 1000;
 
-use crate::fs_service::FileSystemService;
 #[mcp_tool(
     name = "search_files",
     title="Search files",
@@ -13,7 +22,13 @@ use crate::fs_service::FileSystemService;
   "and matches partial names. Returns full paths to all matching items.",
   "Optional 'min_bytes' and 'max_bytes' arguments can be used to filter files by size, ",
   "ensuring that only files within the specified byte range are included in the search. ",
-  "This tool is great for finding files when you don't know their exact location or find files by their size.",
+  "This tool is great for finding files when you don't know their exact location or find files by their size. ",
+  "Results are sorted by path; set `sort_by` to `size` or `mtime` to list the largest or most recently modified first, and `order` (`asc` or `desc`) to reverse it. ",
+  "At most `max_results` results are returned (default: 1000); when more are found, `_meta` reports `truncated` and the total found. ",
+  "`_meta` also reports the entries scanned (`files_scanned`), `files_matched`, `matches` and `duration_ms` of the search. ",
+  "Set `include_metadata` to true to annotate each result with its size and modification time. ",
+  "Set 'output_format' to `json` to receive structured results in `structuredContent`. ",
+  "Set `respect_gitignore` to false to include git-ignored paths, and `include_hidden` to true to include dotfiles. `follow_symlinks` follows symlinks that stay within allowed directories, visiting each linked directory once. Set `max_depth` to search only the first levels of a large tree. ",
   "Only searches within allowed directories."),
     destructive_hint = false,
     idempotent_hint = false,
@@ -38,34 +53,159 @@ pub struct SearchFiles {
     pub min_bytes: Option<u64>,
     /// Maximum file size (in bytes) to include in the search (optional).
     pub max_bytes: Option<u64>,
+    /// Skip paths ignored by .gitignore and .ignore files (default: true).
+    pub respect_gitignore: Option<bool>,
+    /// Include hidden files and directories (default: false).
+    pub include_hidden: Option<bool>,
+    /// Follow symlinks whose targets are inside the allowed directories (default: false).
+    pub follow_symlinks: Option<bool>,
+    /// Maximum number of directory levels below `path` to search, 1 searches only its direct entries (default: 20).
+    #[json_schema(default = "20")]
+    pub max_depth: Option<u64>,
+    /// Sort results by `path`, `size` or `mtime` (default: path).
+    #[json_schema(default = "path")]
+    pub sort_by: Option<SearchSortBy>,
+    /// Sort order, `asc` or `desc` (default: asc for path, desc for size and mtime).
+    pub order: Option<SortOrder>,
+    /// Include the size and modification time of each result (default: false).
+    #[json_schema(default = "false")]
+    pub include_metadata: Option<bool>,
+    /// Maximum number of results to return (default: 1000).
+    #[json_schema(default = "1000")]
+    pub max_results: Option<u64>,
+    /// Specify the output format, accepts either `text` or `json` (default: text).
+    #[json_schema(default = "text")]
+    pub output_format: Option<OutputFormat>,
 }
 impl SearchFiles {
     pub async fn run_tool(
         params: Self,
         context: &FileSystemService,
     ) -> std::result::Result<CallToolResult, CallToolError> {
-        let list = context
-            .search_files(
+        let started = Instant::now();
+        let outcome = context
+            .search_files_with_outcome(
                 Path::new(&params.path),
                 params.pattern,
                 params.exclude_patterns.unwrap_or_default(),
                 params.file_extensions,
                 params.min_bytes,
                 params.max_bytes,
+                WalkOptions::new(
+                    params.respect_gitignore,
+                    params.include_hidden,
+                    params.follow_symlinks,
+                )
+                .with_max_depth(params.max_depth.map(|depth| depth as usize)),
             )
             .await
             .map_err(CallToolError::new)?;
+        let mut list = outcome.entries;
+        let sort_by = params.sort_by.unwrap_or_default();
+        sort_search_results(
+            &mut list,
+            sort_by,
+            params.order.unwrap_or(sort_by.default_order()),
+        );
+        let include_metadata = params.include_metadata.unwrap_or(false);
+        let total_found = list.len();
+        let max_results = params
+            .max_results
+            .map_or(DEFAULT_MAX_RESULTS, |max| max.max(1) as usize);
+        list.truncate(max_results);
+        let truncation = Self::truncation_meta(total_found, list.len());
+        let mut meta = search_summary_meta(
+            outcome.entries_scanned,
+            total_found,
+            total_found,
+            started,
+            truncation.is_some(),
+        );
+        if let Some(truncation) = &truncation {
+            meta.extend(truncation.clone());
+        }
+        let meta = Some(meta);
+
+        if let Some(OutputFormat::Json) = params.output_format {
+            let results: Vec<Value> = list
+                .iter()
+                .map(|entry| {
+                    let mut result = json!({
+                        "path": entry.path().display().to_string(),
+                        "is_dir": entry.file_type().is_some_and(|ft| ft.is_dir()),
+                    });
+                    if include_metadata && let Ok(metadata) = entry.metadata() {
+                        if metadata.is_file() {
+                            result["size"] = json!(metadata.len());
+                        }
+                        if let Ok(modified) = metadata.modified() {
+                            let modified: DateTime<Utc> = modified.into();
+                            result["modified"] =
+                                json!(modified.format("%Y-%m-%dT%H:%M:%SZ").to_string());
+                        }
+                    }
+                    result
+                })
+                .collect();
+            let mut structured = Map::new();
+            structured.insert("results".to_string(), Value::Array(results));
+            let json_str = serde_json::to_string(&structured).map_err(CallToolError::new)?;
+            return Ok(
+                CallToolResult::text_content(vec![TextContent::from(json_str)])
+                    .with_structured_content(structured)
+                    .with_meta(meta),
+            );
+        }
 
         let result = if !list.is_empty() {
             list.iter()
-                .map(|entry| entry.path().display().to_string())
+                .map(|entry| {
+                    let path = entry.path().display().to_string();
+                    match entry.metadata() {
+                        Ok(metadata) if include_metadata => {
+                            let mut details = vec![];
+                            if metadata.is_file() {
+                                details.push(format_bytes(metadata.len()));
+                            }
+                            if let Ok(modified) = metadata.modified() {
+                                details.push(format!("modified {}", format_system_time(modified)));
+                            }
+                            format!("{path} ({})", details.join(", "))
+                        }
+                        _ => path,
+                    }
+                })
                 .collect::<Vec<_>>()
                 .join("\n")
         } else {
             "No matches found".to_string()
         };
-        Ok(CallToolResult::text_content(vec![TextContent::from(
-            result,
-        )]))
+        let result = match &truncation {
+            Some(meta) => format!(
+                "{result}\n[{}]",
+                meta["warning"].as_str().unwrap_or_default()
+            ),
+            None => result,
+        };
+        Ok(CallToolResult::text_content(vec![TextContent::from(result)]).with_meta(meta))
+    }
+
+    /// Returns the `_meta` of a result cut down from `total_found` to `returned` paths, with a
+    /// warning suggesting how to narrow the search, or `None` if nothing was cut.
+    fn truncation_meta(total_found: usize, returned: usize) -> Option<Map<String, Value>> {
+        if returned >= total_found {
+            return None;
+        }
+        let mut meta = Map::new();
+        meta.insert("truncated".to_string(), Value::Bool(true));
+        meta.insert("total_found".to_string(), total_found.into());
+        meta.insert("returned".to_string(), returned.into());
+        meta.insert(
+            "warning".to_string(),
+            Value::String(format!(
+                "Found {total_found} matches, only the first {returned} are returned. Refine the pattern, add exclude patterns or a max_depth, or raise max_results."
+            )),
+        );
+        Some(meta)
     }
 }
@@ -1,18 +1,42 @@
 use crate::error::ServiceError;
-use crate::fs_service::{FileSearchResult, FileSystemService};
+use crate::fs_service::utils::{OutputFormat, search_summary_meta};
+use crate::fs_service::{
+    ContentSearchOptions, ContentSearchOutcome, FileSearchResult, FileSystemService,
+    LongLineAction, OperationContext, WalkOptions,
+};
+use crate::tools::pagination::{MatchPage, cursor_offset, page_limits};
 use rust_mcp_sdk::macros::{JsonSchema, mcp_tool};
 use rust_mcp_sdk::schema::TextContent;
 use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
+use serde_json::{Map, Value, json};
 use std::fmt::Write;
+use std::time::Instant;
+/// Markers wrapping the matched text of snippets when highlighting is enabled.
+const DEFAULT_HIGHLIGHT_START: &str = "«";
+const DEFAULT_HIGHLIGHT_END: &str = "»";
+
 #[mcp_tool(
     name = "search_files_content",
     title="Search files content",
     description = concat!("Searches for text or regex patterns in the content of files. ",
-                          "The 'pattern' parameter uses GLOB syntax to filter which files to search (e.g., '*.rs' for Rust files). ",
+                          "The 'pattern' parameter uses GLOB syntax to filter which files to search (e.g., '*.rs' for Rust files); ",
+                          "a pattern containing '/' such as 'src/**/*.rs' is matched against the path relative to 'path'. ",
                           "The 'query' parameter is the search term: literal text by default, or regex when 'is_regex' is true. ",
                           "Note: 'query' does NOT use glob syntax - use standard regex patterns like '.*match' instead of '*match'. ",
-                          "Returns detailed matches with file path, line number, column number and a preview of matched text. ",
+                          "Returns detailed matches with file path, line number, column number, the byte column where the match ends and a preview of matched text. ",
+                          "Files are sorted by path and matches by line, after a summary of the number of files matched and total matches. ",
                           "Optional 'min_bytes' and 'max_bytes' arguments can be used to filter files by size. ",
+                          "Use 'max_results' and 'max_matches_per_file' to limit the output; when results are truncated, ",
+                          "pass the 'next_cursor' value from the result '_meta' as 'cursor' to fetch the next page. ",
+                          "The result '_meta' also reports 'files_scanned', 'files_matched', 'matches', 'duration_ms' and whether the output was 'truncated'. ",
+                          "Binary files are skipped unless 'skip_binary' is false. Set 'max_line_length' to skip files with longer matching lines, ",
+                          "such as minified bundles, or to truncate those lines when 'long_lines' is `truncate`; skipped files are counted in the result '_meta'. ",
+                          "Set 'invert_match' to true to list the lines that do NOT match the query (like grep -v), ",
+                          "and 'path_regex' to only search files whose path relative to 'path' matches a regular expression. ",
+                          "Set 'highlight' to true to wrap the matched text of each snippet in markers (`«` and `»` unless 'highlight_start' and 'highlight_end' are given). ",
+                          "Set 'output_format' to `json` to receive structured results (path, line, column, end_column, snippet) in `structuredContent`. ",
+                          "Set `respect_gitignore` to false to include git-ignored paths, and `include_hidden` to true to include dotfiles. `follow_symlinks` follows symlinks that stay within allowed directories, visiting each linked directory once. Set `max_depth` to search only the first levels of a large tree. ",
+                          "Set `threads` to bound the number of threads searching in parallel, or `low_priority` to true to search on a single thread. ",
                           "Ideal for finding specific code, comments, or text when you don't know their exact location."),
     destructive_hint = false,
     idempotent_hint = false,
@@ -25,7 +49,7 @@ use std::fmt::Write;
 pub struct SearchFilesContent {
     /// The file or directory path to search in.
     pub path: String,
-    /// File glob pattern to filter which files to search (e.g., "*.rs", "*.{js,ts}"). This does NOT affect the content search.
+    /// File glob pattern to filter which files to search (e.g., "*.rs", "*.{js,ts}", "src/**/*.rs"). This does NOT affect the content search.
     pub pattern: String,
     /// Search term to find in file contents. Use literal text (default) or regex patterns when is_regex=true (e.g., 'TODO', '^function\\s+', '.*Error'). NOT glob syntax.
     pub query: String,
@@ -38,14 +62,64 @@ pub struct SearchFilesContent {
     pub min_bytes: Option<u64>,
     /// Maximum file size (in bytes) to include in the search (optional).
     pub max_bytes: Option<u64>,
+    /// Maximum number of matches to return in a single response (optional).
+    pub max_results: Option<u64>,
+    /// Maximum number of matches to report for each file (optional).
+    pub max_matches_per_file: Option<u64>,
+    /// Opaque continuation cursor returned as `next_cursor` in the `_meta` of a previous truncated response.
+    pub cursor: Option<String>,
+    /// Skip paths ignored by .gitignore and .ignore files (default: true).
+    pub respect_gitignore: Option<bool>,
+    /// Include hidden files and directories (default: false).
+    pub include_hidden: Option<bool>,
+    /// Follow symlinks whose targets are inside the allowed directories (default: false).
+    pub follow_symlinks: Option<bool>,
+    /// Maximum number of directory levels below `path` to search, 1 searches only its direct entries (default: 20).
+    #[json_schema(default = "20")]
+    pub max_depth: Option<u64>,
+    /// Number of threads searching in parallel, 0 for one per CPU core (default: the server's `--search-threads`).
+    pub threads: Option<u64>,
+    /// Search on a single thread to leave the CPU cores to other work (default: the server's `--low-priority-search`).
+    pub low_priority: Option<bool>,
+    /// Skip binary files, i.e. files containing NUL bytes (default: true).
+    #[json_schema(default = "true")]
+    pub skip_binary: Option<bool>,
+    /// Matching lines longer than this many characters are handled according to `long_lines` (optional).
+    pub max_line_length: Option<u64>,
+    /// What to do with a file whose matching line exceeds `max_line_length`: `skip` the file or `truncate` the line (default: skip).
+    #[json_schema(default = "skip")]
+    pub long_lines: Option<LongLineAction>,
+    /// Return the lines that do NOT match the query instead, like `grep -v` (default: false).
+    #[json_schema(default = "false")]
+    pub invert_match: Option<bool>,
+    /// Only search files whose path relative to `path` matches this regular expression, e.g. `^src/.*_test\\.rs$` (optional).
+    pub path_regex: Option<String>,
+    /// Wrap the matched text of each snippet in highlight markers (default: false).
+    #[json_schema(default = "false")]
+    pub highlight: Option<bool>,
+    /// Marker inserted before the matched text when `highlight` is true (default: «).
+    #[json_schema(default = "«")]
+    pub highlight_start: Option<String>,
+    /// Marker inserted after the matched text when `highlight` is true (default: »).
+    #[json_schema(default = "»")]
+    pub highlight_end: Option<String>,
+    /// Specify the output format, accepts either `text` or `json` (default: text).
+    #[json_schema(default = "text")]
+    pub output_format: Option<OutputFormat>,
 }
 
 impl SearchFilesContent {
-    fn format_result(&self, results: Vec<FileSearchResult>) -> String {
+    fn format_result(&self, page: MatchPage<FileSearchResult>) -> String {
         // TODO: improve capacity estimation
         let estimated_capacity = 2048;
 
         let mut output = String::with_capacity(estimated_capacity);
+        let _ = writeln!(
+            output,
+            "{} files matched, {} total matches\n",
+            page.total_files, page.total_matches
+        );
+        let results = page.results;
 
         for file_result in results {
             // Push file path
@@ -67,13 +141,96 @@ impl SearchFilesContent {
 
         output
     }
+
+    fn format_json(&self, page: &MatchPage<FileSearchResult>) -> Map<String, Value> {
+        let results: Vec<Value> = page
+            .results
+            .iter()
+            .map(|file_result| {
+                json!({
+                    "path": file_result.file_path.display().to_string(),
+                    "matches": file_result
+                        .matches
+                        .iter()
+                        .map(|m| {
+                            json!({
+                                "line": m.line_number,
+                                "column": m.start_pos,
+                                "end_column": m.end_pos,
+                                "snippet": m.line_text,
+                            })
+                        })
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+
+        let mut structured = Map::new();
+        structured.insert(
+            "summary".to_string(),
+            json!({
+                "files_matched": page.total_files,
+                "total_matches": page.total_matches,
+            }),
+        );
+        structured.insert("results".to_string(), Value::Array(results));
+        structured
+    }
+
+    /// Describes the files left out of the search, or returns `None` if there are none.
+    fn skipped_summary(outcome: &ContentSearchOutcome) -> Option<String> {
+        let mut skipped = vec![];
+        if outcome.skipped_binary_files > 0 {
+            skipped.push(format!("{} binary files", outcome.skipped_binary_files));
+        }
+        if outcome.skipped_long_line_files > 0 {
+            skipped.push(format!(
+                "{} files with lines longer than max_line_length",
+                outcome.skipped_long_line_files
+            ));
+        }
+        (!skipped.is_empty()).then(|| format!("Skipped {}.", skipped.join(" and ")))
+    }
+
     pub async fn run_tool(
         params: Self,
         context: &FileSystemService,
     ) -> std::result::Result<CallToolResult, CallToolError> {
+        Self::run_tool_with_context(params, context, &OperationContext::default()).await
+    }
+
+    /// Runs the search, reporting its progress through `operation`.
+    pub async fn run_tool_with_context(
+        params: Self,
+        context: &FileSystemService,
+        operation: &OperationContext,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let started = Instant::now();
         let is_regex = params.is_regex.unwrap_or_default();
+        let offset = cursor_offset(params.cursor.as_deref())?;
+        let (max_results, max_matches_per_file) =
+            page_limits(params.max_results, params.max_matches_per_file)?;
+        let options = ContentSearchOptions {
+            skip_binary: params.skip_binary.unwrap_or(true),
+            max_line_length: params.max_line_length.map(|length| length as usize),
+            long_lines: params.long_lines.unwrap_or_default(),
+            invert_match: params.invert_match.unwrap_or(false),
+            path_regex: params.path_regex.clone(),
+            highlight: params.highlight.unwrap_or(false).then(|| {
+                (
+                    params
+                        .highlight_start
+                        .clone()
+                        .unwrap_or_else(|| DEFAULT_HIGHLIGHT_START.to_string()),
+                    params
+                        .highlight_end
+                        .clone()
+                        .unwrap_or_else(|| DEFAULT_HIGHLIGHT_END.to_string()),
+                )
+            }),
+        };
         match context
-            .search_files_content(
+            .search_files_content_with_options(
                 &params.path,
                 &params.pattern,
                 &params.query,
@@ -81,20 +238,77 @@ impl SearchFilesContent {
                 params.exclude_patterns.to_owned(),
                 params.min_bytes,
                 params.max_bytes,
+                WalkOptions::new(
+                    params.respect_gitignore,
+                    params.include_hidden,
+                    params.follow_symlinks,
+                )
+                .with_max_depth(params.max_depth.map(|depth| depth as usize))
+                .with_threads(
+                    params.threads.map(|threads| threads as usize),
+                    params.low_priority,
+                ),
+                options,
+                operation,
             )
             .await
         {
-            Ok(results) => {
-                if results.is_empty() {
-                    return Ok(CallToolResult::with_error(CallToolError::new(
-                        ServiceError::FromString("No matches found in the files content.".into()),
+            Ok(outcome) => {
+                let skipped = Self::skipped_summary(&outcome);
+                if outcome.results.is_empty() {
+                    let message = match &skipped {
+                        Some(skipped) => {
+                            format!("No matches found in the files content. {skipped}")
+                        }
+                        None => "No matches found in the files content.".to_string(),
+                    };
+                    return Err(CallToolError::new(ServiceError::NoMatch(message)));
+                }
+                let page =
+                    MatchPage::new(outcome.results, max_results, max_matches_per_file, offset);
+                if page.results.is_empty() {
+                    return Err(CallToolError::new(ServiceError::NoMatch(
+                        "No more matches found for the given cursor.".into(),
                     )));
                 }
-                Ok(CallToolResult::text_content(vec![TextContent::from(
-                    params.format_result(results),
-                )]))
+                let pagination = page.meta()?;
+                let mut meta = search_summary_meta(
+                    outcome.files_scanned,
+                    page.total_files,
+                    page.total_matches,
+                    started,
+                    pagination.is_some(),
+                );
+                meta.extend(pagination.unwrap_or_default());
+                if skipped.is_some() {
+                    meta.insert(
+                        "skipped_binary_files".to_string(),
+                        outcome.skipped_binary_files.into(),
+                    );
+                    meta.insert(
+                        "skipped_long_line_files".to_string(),
+                        outcome.skipped_long_line_files.into(),
+                    );
+                }
+                let result = match params.output_format.clone().unwrap_or(OutputFormat::Text) {
+                    OutputFormat::Text => {
+                        let mut output = params.format_result(page);
+                        if let Some(skipped) = &skipped {
+                            let _ = writeln!(output, "[{skipped}]");
+                        }
+                        CallToolResult::text_content(vec![TextContent::from(output)])
+                    }
+                    OutputFormat::Json => {
+                        let structured = params.format_json(&page);
+                        let json_str =
+                            serde_json::to_string(&structured).map_err(CallToolError::new)?;
+                        CallToolResult::text_content(vec![TextContent::from(json_str)])
+                            .with_structured_content(structured)
+                    }
+                };
+                Ok(result.with_meta(Some(meta)))
             }
-            Err(err) => Ok(CallToolResult::with_error(CallToolError::new(err))),
+            Err(err) => Err(CallToolError::new(err)),
         }
     }
 }
@@ -0,0 +1,82 @@
+use base64::{Engine, engine::general_purpose};
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+use std::path::Path;
+
+use crate::{error::ServiceError, fs_service::FileSystemService};
+
+/// How the value of an extended attribute is encoded in the request.
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Copy, Debug, Default, JsonSchema)]
+pub enum AttributeEncoding {
+    #[default]
+    #[serde(rename = "utf8")]
+    Utf8,
+    #[serde(rename = "base64")]
+    Base64,
+}
+
+#[mcp_tool(
+    name = "set_extended_attribute",
+    title="Set extended attribute",
+    description = concat!("Sets an extended attribute (xattr) of a file or directory, e.g. to tag build artifacts, ",
+    "or writes an NTFS alternate data stream on Windows. Omit 'value' to remove the attribute. ",
+    "On Linux, attributes set by regular users must be in the 'user.' namespace. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = true,
+    open_world_hint = false,
+    read_only_hint = false
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct SetExtendedAttribute {
+    /// The path of the file or directory.
+    pub path: String,
+    /// The name of the attribute, e.g. 'user.comment'.
+    pub name: String,
+    /// The new value of the attribute. The attribute is removed if omitted.
+    pub value: Option<String>,
+    /// Encoding of `value`, `utf8` or `base64` for binary values (default: utf8).
+    pub encoding: Option<AttributeEncoding>,
+}
+
+impl SetExtendedAttribute {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let value = match (&params.value, params.encoding.unwrap_or_default()) {
+            (None, _) => None,
+            (Some(value), AttributeEncoding::Utf8) => Some(value.as_bytes().to_vec()),
+            (Some(value), AttributeEncoding::Base64) => {
+                Some(general_purpose::STANDARD.decode(value).map_err(|err| {
+                    CallToolError::new(ServiceError::InvalidArgument(format!(
+                        "Invalid base64 value: {err}"
+                    )))
+                })?)
+            }
+        };
+
+        context
+            .set_extended_attribute(Path::new(&params.path), &params.name, value.as_deref())
+            .await
+            .map_err(CallToolError::new)?;
+
+        let message = match value {
+            Some(value) => format!(
+                "Successfully set extended attribute '{}' on {} ({} bytes)",
+                params.name,
+                params.path,
+                value.len()
+            ),
+            None => format!(
+                "Successfully removed extended attribute '{}' from {}",
+                params.name, params.path
+            ),
+        };
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            message,
+        )]))
+    }
+}
@@ -0,0 +1,79 @@
+use std::path::Path;
+
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+use serde_json::{Map, Value};
+
+use crate::fs_service::{
+    FileSystemService, TailPosition,
+    utils::{decode_cursor, encode_cursor},
+};
+
+#[mcp_tool(
+    name = "tail_file",
+    title="Follow a growing file",
+    description = concat!("Incrementally reads a growing file such as a log. ",
+    "The first call (without 'cursor') returns the last 'lines' lines of the file (default 10). ",
+    "Every response includes a 'next_cursor' in its '_meta'; pass it as 'cursor' on the next call to receive only the ",
+    "content appended since then, instead of re-reading the tail. ",
+    "At most 'max_bytes' (default 65536) of whole lines are returned per call; '_meta.has_more' is true when more content is available. ",
+    "If the file was truncated or rotated, reading restarts from its beginning and '_meta.rotated' is true. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct TailFile {
+    /// The path of the file to follow.
+    pub path: String,
+    /// Opaque cursor returned as `next_cursor` in the `_meta` of a previous response.
+    pub cursor: Option<String>,
+    /// Number of lines returned from the end of the file on the first call. Default: 10.
+    #[json_schema(default = "10")]
+    pub lines: Option<u64>,
+    /// Maximum number of bytes returned per call. Default: 65536.
+    #[json_schema(default = "65536")]
+    pub max_bytes: Option<u64>,
+}
+
+impl TailFile {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let from = params
+            .cursor
+            .as_deref()
+            .map(decode_cursor::<TailPosition>)
+            .transpose()
+            .map_err(CallToolError::new)?;
+
+        let chunk = context
+            .tail_file(
+                Path::new(&params.path),
+                from,
+                params.lines.unwrap_or(10) as usize,
+                params.max_bytes.map(|v| v as usize),
+            )
+            .await
+            .map_err(CallToolError::new)?;
+
+        let mut meta = Map::new();
+        meta.insert(
+            "next_cursor".to_string(),
+            Value::String(encode_cursor(&chunk.position).map_err(CallToolError::new)?),
+        );
+        meta.insert("offset".to_string(), chunk.position.offset.into());
+        meta.insert("has_more".to_string(), Value::Bool(chunk.has_more));
+        meta.insert("rotated".to_string(), Value::Bool(chunk.rotated));
+
+        Ok(
+            CallToolResult::text_content(vec![TextContent::from(chunk.content)])
+                .with_meta(Some(meta)),
+        )
+    }
+}
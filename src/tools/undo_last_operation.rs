@@ -0,0 +1,57 @@
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+use std::fmt::Write;
+
+use crate::fs_service::{FileSystemService, JournalChange};
+
+#[mcp_tool(
+    name = "undo_last_operation",
+    title="Undo last operation",
+    description = concat!("Reverts the most recent operation of a tool that modified files (write, edit, move, delete, rewrite) ",
+    "by restoring the previous contents and locations. Can be called repeatedly to step back through earlier operations. ",
+    "Only available when the server runs with `--journal`. Only works within allowed directories."),
+    destructive_hint = true,
+    idempotent_hint = false,
+    open_world_hint = false,
+    read_only_hint = false
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct UndoLastOperation {}
+
+impl UndoLastOperation {
+    pub async fn run_tool(
+        _: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let entry = context
+            .undo_last_operation()
+            .await
+            .map_err(CallToolError::new)?;
+
+        let mut output = format!("Undid '{}' from {}:\n", entry.operation, entry.timestamp);
+        for change in entry.changes.iter().rev() {
+            let line = match change {
+                JournalChange::Write {
+                    path,
+                    backup: Some(_),
+                } => format!("restored the previous content of {}", path.display()),
+                JournalChange::Write { path, backup: None } => {
+                    format!("removed {}", path.display())
+                }
+                JournalChange::Move { from, to } => {
+                    format!("moved {} back to {}", to.display(), from.display())
+                }
+                JournalChange::CreateDirectory { path } => {
+                    format!("removed directory {}", path.display())
+                }
+                JournalChange::Delete { path, .. } => format!("restored {}", path.display()),
+            };
+            writeln!(output, "  {line}").map_err(CallToolError::new)?;
+        }
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            output,
+        )]))
+    }
+}
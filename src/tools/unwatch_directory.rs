@@ -0,0 +1,47 @@
+use std::path::Path;
+
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+
+use crate::fs_service::FileSystemService;
+
+#[mcp_tool(
+    name = "unwatch_directory",
+    title="Stop watching a directory",
+    description = concat!("Stops watching a directory previously watched with watch_directory, ",
+    "so no more change notifications are sent for it. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = true,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct UnwatchDirectory {
+    /// The directory to stop watching.
+    pub path: String,
+}
+
+impl UnwatchDirectory {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let (path, stopped) = context
+            .unwatch_directory(Path::new(&params.path))
+            .await
+            .map_err(CallToolError::new)?;
+
+        let message = if stopped {
+            format!("Stopped watching {}.", path.display())
+        } else {
+            format!("{} is not being watched.", path.display())
+        };
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            message,
+        )]))
+    }
+}
@@ -0,0 +1,52 @@
+use std::path::Path;
+
+use rust_mcp_sdk::{
+    macros::{JsonSchema, mcp_tool},
+    schema::{CallToolResult, TextContent, schema_utils::CallToolError},
+};
+
+use crate::fs_service::FileSystemService;
+
+#[mcp_tool(
+    name = "watch_directory",
+    title="Watch a directory for changes",
+    description = concat!("Starts watching a directory for changes to its files. ",
+    "While the watch is active, every file creation, modification, removal or rename is sent to the client ",
+    "as a 'notifications/message' logging notification (logger 'file_watcher') containing the path and the kind of change. ",
+    "Subdirectories are watched too unless 'recursive' is false. ",
+    "Use unwatch_directory to stop watching. ",
+    "Only works within allowed directories."),
+    destructive_hint = false,
+    idempotent_hint = true,
+    open_world_hint = false,
+    read_only_hint = true
+)]
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug, JsonSchema)]
+pub struct WatchDirectory {
+    /// The directory to watch.
+    pub path: String,
+    /// Whether changes in subdirectories are reported too. Default: true.
+    pub recursive: Option<bool>,
+}
+
+impl WatchDirectory {
+    pub async fn run_tool(
+        params: Self,
+        context: &FileSystemService,
+    ) -> std::result::Result<CallToolResult, CallToolError> {
+        let (path, started) = context
+            .watch_directory(Path::new(&params.path), params.recursive.unwrap_or(true))
+            .await
+            .map_err(CallToolError::new)?;
+
+        let message = if started {
+            format!("Watching {} for changes.", path.display())
+        } else {
+            format!("{} is already being watched.", path.display())
+        };
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            message,
+        )]))
+    }
+}
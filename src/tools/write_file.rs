@@ -7,6 +7,7 @@ use std::path::Path;
 use rust_mcp_sdk::schema::{CallToolResult, schema_utils::CallToolError};
 
 use crate::fs_service::FileSystemService;
+use crate::fs_service::utils::sha256_hex;
 #[mcp_tool(
     name = "write_file",
     title="Write file",
@@ -24,6 +25,14 @@ pub struct WriteFile {
     pub path: String,
     /// The content to write to the file.
     pub content: String,
+    /// Save the previous content of the file as '<name>.bak' (or into the server's backup
+    /// directory) before overwriting it.
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub backup: Option<bool>,
+    /// SHA-256 hash (hex) of the file content as last read. The file is only written if it
+    /// still has this content, otherwise a conflict error is returned.
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub if_match_sha256: Option<String>,
 }
 
 impl WriteFile {
@@ -31,13 +40,31 @@ impl WriteFile {
         params: Self,
         context: &FileSystemService,
     ) -> std::result::Result<CallToolResult, CallToolError> {
-        context
-            .write_file(Path::new(&params.path), &params.content)
+        let backup_path = context
+            .write_file(
+                Path::new(&params.path),
+                &params.content,
+                params.backup.unwrap_or(false),
+                params.if_match_sha256.as_deref(),
+            )
             .await
             .map_err(CallToolError::new)?;
 
+        let mut message = format!("Successfully wrote to {}", &params.path);
+        if params.if_match_sha256.is_some() {
+            message.push_str(&format!(
+                "\nsha256 of the new content: {}",
+                sha256_hex(params.content.as_bytes())
+            ));
+        }
+        if let Some(backup_path) = backup_path {
+            message.push_str(&format!(
+                "\nPrevious content backed up to {}",
+                backup_path.display()
+            ));
+        }
         Ok(CallToolResult::text_content(vec![TextContent::from(
-            format!("Successfully wrote to {}", &params.path),
+            message,
         )]))
     }
 }
@@ -56,6 +56,32 @@ pub fn create_temp_file(dir: &Path, name: &str, content: &str) -> PathBuf {
     file_path
 }
 
+// Helper to create a zip archive (e.g. an office document) with the given entries
+pub fn create_temp_zip(dir: &Path, name: &str, entries: &[(&str, &str)]) -> PathBuf {
+    let file_path = dir.join(name);
+    let mut writer = zip::ZipWriter::new(File::create(&file_path).unwrap());
+    for (entry_name, content) in entries {
+        writer
+            .start_file(*entry_name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(content.as_bytes()).unwrap();
+    }
+    writer.finish().unwrap();
+    file_path
+}
+
+// Helper to run a git command in a test repository
+pub fn run_git(repo: &Path, args: &[&str]) {
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {args:?} failed");
+}
+
 // Helper to create a temporary file and get its FileInfo
 pub fn create_temp_file_info(content: &[u8]) -> (PathBuf, FileInfo) {
     let dir = get_temp_dir();
@@ -65,15 +91,7 @@ pub fn create_temp_file_info(content: &[u8]) -> (PathBuf, FileInfo) {
     file.flush().unwrap();
 
     let metadata = fs::metadata(&file_path).unwrap();
-    let file_info = FileInfo {
-        size: metadata.len(),
-        created: metadata.created().ok(),
-        modified: metadata.modified().ok(),
-        accessed: metadata.accessed().ok(),
-        is_directory: metadata.is_dir(),
-        is_file: metadata.is_file(),
-        metadata,
-    };
+    let file_info = FileInfo::new(&file_path, metadata);
     (dir, file_info)
 }
 
@@ -81,15 +99,7 @@ pub fn create_temp_file_info(content: &[u8]) -> (PathBuf, FileInfo) {
 pub fn create_temp_dir() -> (TempDir, FileInfo) {
     let dir = TempDir::new().unwrap();
     let metadata = fs::metadata(dir.path()).unwrap();
-    let file_info = FileInfo {
-        size: metadata.len(),
-        created: metadata.created().ok(),
-        modified: metadata.modified().ok(),
-        accessed: metadata.accessed().ok(),
-        is_directory: metadata.is_dir(),
-        is_file: metadata.is_file(),
-        metadata,
-    };
+    let file_info = FileInfo::new(dir.path(), metadata);
     (dir, file_info)
 }
 
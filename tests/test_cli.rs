@@ -2,6 +2,8 @@
 pub mod common;
 
 use common::parse_args;
+use rust_mcp_filesystem::cli::TransportKind;
+use rust_mcp_filesystem::fs_service::DirectoryMode;
 
 #[test]
 fn test_parse_with_single_directory() {
@@ -80,3 +82,160 @@ fn test_invalid_flag() {
         assert_eq!(e.kind(), clap::error::ErrorKind::UnknownArgument);
     }
 }
+
+#[test]
+fn test_parse_unix_transport() {
+    let args = [
+        "mcp-server",
+        "--transport",
+        "unix",
+        "--socket",
+        "/tmp/mcp.sock",
+        "--socket-mode",
+        "660",
+        "/path/to/dir",
+    ];
+    let result = parse_args(&args).unwrap();
+    assert_eq!(result.transport, TransportKind::Unix);
+    assert_eq!(result.socket.as_deref(), Some("/tmp/mcp.sock"));
+    assert_eq!(result.socket_mode, 0o660);
+    assert!(result.validate().is_ok());
+}
+
+#[test]
+fn test_unix_transport_requires_socket() {
+    let args = ["mcp-server", "--transport", "unix", "/path/to/dir"];
+    let result = parse_args(&args).unwrap();
+    assert_eq!(result.socket_mode, 0o600);
+    assert!(matches!(result.validate(), Err(message) if message.contains("--socket")));
+}
+
+#[test]
+fn test_unix_transport_rejects_roots() {
+    let args = [
+        "mcp-server",
+        "--transport",
+        "unix",
+        "--socket",
+        "/tmp/mcp.sock",
+        "--enable-roots",
+    ];
+    let result = parse_args(&args).unwrap();
+    assert!(matches!(result.validate(), Err(message) if message.contains("--enable-roots")));
+}
+
+#[test]
+fn test_invalid_socket_mode() {
+    let args = ["mcp-server", "--socket-mode", "999", "/path/to/dir"];
+    assert!(parse_args(&args).is_err());
+}
+
+#[test]
+fn test_parse_directory_modes() {
+    let args = ["mcp-server", "/src:rw", "/vendor:ro", "/docs", "C:\\data"];
+    let result = parse_args(&args).unwrap();
+    let (directories, modes) = result.directory_entries();
+    assert_eq!(directories, vec!["/src", "/vendor", "/docs", "C:\\data"]);
+    assert_eq!(
+        modes,
+        vec![
+            ("/src".to_string(), DirectoryMode::ReadWrite),
+            ("/vendor".to_string(), DirectoryMode::ReadOnly),
+        ]
+    );
+    assert_eq!(result.default_directory_mode(), DirectoryMode::ReadOnly);
+}
+
+#[test]
+fn test_parse_deny_globs() {
+    let args = [
+        "mcp-server",
+        "--deny-glob",
+        "**/.env",
+        "--deny-glob",
+        "**/secrets/**,*.pem",
+        "/path/to/dir",
+    ];
+    let result = parse_args(&args).unwrap();
+    assert_eq!(result.deny_globs, vec!["**/.env", "**/secrets/**", "*.pem"]);
+}
+
+#[test]
+fn test_parse_tool_limits() {
+    let args = ["mcp-server", "/path/to/dir"];
+    let result = parse_args(&args).unwrap();
+    assert_eq!(result.max_concurrent_heavy, None);
+    assert_eq!(result.max_calls_per_minute, None);
+
+    let args = [
+        "mcp-server",
+        "--max-concurrent-heavy",
+        "2",
+        "--max-calls-per-minute",
+        "120",
+        "/path/to/dir",
+    ];
+    let result = parse_args(&args).unwrap();
+    assert_eq!(result.max_concurrent_heavy, Some(2));
+    assert_eq!(result.max_calls_per_minute, Some(120));
+}
+
+#[test]
+fn test_parse_use_trash() {
+    let args = ["mcp-server", "/path/to/dir"];
+    assert!(!parse_args(&args).unwrap().use_trash);
+
+    let args = ["mcp-server", "--use-trash", "/path/to/dir"];
+    assert!(parse_args(&args).unwrap().use_trash);
+}
+
+#[test]
+fn test_parse_journal() {
+    let args = ["mcp-server", "/path/to/dir"];
+    assert!(!parse_args(&args).unwrap().journal);
+
+    let args = ["mcp-server", "--journal", "/path/to/dir"];
+    assert!(parse_args(&args).unwrap().journal);
+}
+
+#[test]
+fn test_parse_no_atomic_writes() {
+    let args = ["mcp-server", "/path/to/dir"];
+    assert!(!parse_args(&args).unwrap().no_atomic_writes);
+
+    let args = ["mcp-server", "--no-atomic-writes", "/path/to/dir"];
+    assert!(parse_args(&args).unwrap().no_atomic_writes);
+}
+
+#[test]
+fn test_parse_backup_dir() {
+    let args = ["mcp-server", "/path/to/dir"];
+    assert_eq!(parse_args(&args).unwrap().backup_dir, None);
+
+    let args = ["mcp-server", "--backup-dir", "/backups", "/path/to/dir"];
+    assert_eq!(
+        parse_args(&args).unwrap().backup_dir.as_deref(),
+        Some("/backups")
+    );
+}
+
+#[test]
+fn test_parse_read_max_bytes() {
+    let args = ["mcp-server", "/path/to/dir"];
+    assert_eq!(parse_args(&args).unwrap().read_max_bytes, None);
+
+    let args = ["mcp-server", "--read-max-bytes", "1024", "/path/to/dir"];
+    assert_eq!(parse_args(&args).unwrap().read_max_bytes, Some(1024));
+}
+
+#[test]
+fn test_parse_tool_timeout() {
+    let args = ["mcp-server", "/path/to/dir"];
+    assert_eq!(parse_args(&args).unwrap().tool_timeout, None);
+
+    let args = ["mcp-server", "--tool-timeout", "30", "/path/to/dir"];
+    assert_eq!(parse_args(&args).unwrap().tool_timeout, Some(30));
+
+    let args = ["mcp-server", "--tool-timeout", "soon", "/path/to/dir"];
+    assert!(parse_args(&args).is_err());
+}
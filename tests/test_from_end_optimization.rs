@@ -77,3 +77,36 @@ async fn test_from_end_with_trailing_newline() {
     assert_eq!(result, "line2\nline3\n");
     println!("✓ Test with trailing newline passed");
 }
+
+#[tokio::test]
+async fn test_from_end_large_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("large.txt");
+
+    // Larger than the threshold above which the `mmap` feature maps files
+    let content = (1..=200_000)
+        .map(|i| format!("line{}\r\n", i))
+        .collect::<String>();
+    fs::write(&file_path, &content).unwrap();
+
+    let allowed_dirs = vec![temp_dir.path().to_string_lossy().to_string()];
+    let service = FileSystemService::try_new(&allowed_dirs).unwrap();
+
+    let result = service
+        .read_file_lines(&file_path, 0, Some(2), true)
+        .await
+        .unwrap();
+    assert_eq!(result, "line199999\r\nline200000\r\n");
+
+    let result = service
+        .read_file_lines(&file_path, 3, Some(2), true)
+        .await
+        .unwrap();
+    assert_eq!(result, "line199996\r\nline199997");
+
+    let result = service
+        .read_file_lines(&file_path, 0, None, true)
+        .await
+        .unwrap();
+    assert_eq!(result, content);
+}
@@ -1,25 +1,60 @@
 #[path = "common/common.rs"]
 pub mod common;
 
-use async_zip::tokio::write::ZipFileWriter;
 use common::create_temp_dir;
 use common::create_temp_file;
 use common::create_temp_file_info;
+use common::create_temp_zip;
 use common::get_temp_dir;
+use common::run_git;
 use common::setup_service;
 use dirs::home_dir;
 use grep::matcher::Match;
 use rust_mcp_filesystem::error::ServiceError;
+use rust_mcp_filesystem::fs_service::AstSearchLimits;
+use rust_mcp_filesystem::fs_service::AstSearchOutcome;
+use rust_mcp_filesystem::fs_service::BrokenSymlinkReason;
+use rust_mcp_filesystem::fs_service::CancellationToken;
+use rust_mcp_filesystem::fs_service::ChunkPosition;
+use rust_mcp_filesystem::fs_service::CleanupAction;
+use rust_mcp_filesystem::fs_service::ColumnType;
+use rust_mcp_filesystem::fs_service::ContentSearchOptions;
+use rust_mcp_filesystem::fs_service::DEFAULT_TODO_TAGS;
+use rust_mcp_filesystem::fs_service::DiffHunk;
+use rust_mcp_filesystem::fs_service::DirectoryListing;
+use rust_mcp_filesystem::fs_service::DirectoryMode;
+use rust_mcp_filesystem::fs_service::DirectorySortBy;
+use rust_mcp_filesystem::fs_service::DocumentKind;
+use rust_mcp_filesystem::fs_service::DuplicateAction;
+use rust_mcp_filesystem::fs_service::FileChangeKind;
 use rust_mcp_filesystem::fs_service::FileInfo;
 use rust_mcp_filesystem::fs_service::FileSystemService;
+use rust_mcp_filesystem::fs_service::ImageResizeOptions;
+use rust_mcp_filesystem::fs_service::LongLineAction;
+use rust_mcp_filesystem::fs_service::OperationContext;
+use rust_mcp_filesystem::fs_service::OverwritePolicy;
+use rust_mcp_filesystem::fs_service::ResourceContent;
+use rust_mcp_filesystem::fs_service::SearchSortBy;
+use rust_mcp_filesystem::fs_service::SizeGroupBy;
+use rust_mcp_filesystem::fs_service::SortOrder;
+use rust_mcp_filesystem::fs_service::StructuredFormat;
+use rust_mcp_filesystem::fs_service::TRASH_DIR_NAME;
+use rust_mcp_filesystem::fs_service::TempPathKind;
+use rust_mcp_filesystem::fs_service::TreeFilter;
+use rust_mcp_filesystem::fs_service::WalkOptions;
+use rust_mcp_filesystem::fs_service::file_uri_to_path;
+use rust_mcp_filesystem::fs_service::fuzzy_score;
+use rust_mcp_filesystem::fs_service::markdown_outline;
+use rust_mcp_filesystem::fs_service::path_to_file_uri;
+use rust_mcp_filesystem::fs_service::sort_search_results;
 use rust_mcp_filesystem::fs_service::utils::*;
 use rust_mcp_filesystem::tools::EditOperation;
+use rust_mcp_filesystem::tools::MatchMode;
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 use tokio::fs as tokio_fs;
-use tokio_util::compat::TokioAsyncReadCompatExt;
 
 use crate::common::create_sub_dir;
 use crate::common::create_test_file;
@@ -68,7 +103,7 @@ async fn test_validate_path_denied() {
     let (temp_dir, service, allowed_dirs) = setup_service(vec!["dir1".to_string()]);
     let outside_path = temp_dir.join("dir2").join("test.txt");
     let result = service.validate_path(&outside_path, allowed_dirs);
-    assert!(matches!(result, Err(ServiceError::FromString(_))));
+    assert!(matches!(result, Err(ServiceError::PathDenied(_))));
 }
 
 #[test]
@@ -100,11 +135,66 @@ fn test_contains_symlink_with_symlink() {
     assert!(result);
 }
 
+#[cfg(unix)]
+#[tokio::test]
+async fn test_validate_path_after_move() {
+    let (temp_dir, service, allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let dir1 = temp_dir.join("dir1");
+    create_temp_file(&dir1, "inside.txt", "inside");
+    create_temp_file(&temp_dir, "outside.txt", "outside");
+    let link_path = dir1.join("link.txt");
+    std::os::unix::fs::symlink(dir1.join("inside.txt"), &link_path).unwrap();
+    assert!(
+        service
+            .validate_path(&link_path, allowed_dirs.clone())
+            .is_ok()
+    );
+
+    // the link moved away and replaced must be resolved again
+    service
+        .move_file(&link_path, &dir1.join("moved.txt"), OverwritePolicy::Error)
+        .await
+        .unwrap();
+    std::os::unix::fs::symlink(temp_dir.join("outside.txt"), &link_path).unwrap();
+    let result = service.validate_path(&link_path, allowed_dirs);
+    assert!(result.is_err());
+    assert!(
+        result
+            .unwrap_err()
+            .to_string()
+            .contains("a symlink target path is outside allowed directories")
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_validate_path_rechecks_replaced_symlink() {
+    let (temp_dir, service, allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let dir1 = temp_dir.join("dir1");
+    create_temp_file(&dir1, "inside.txt", "inside");
+    create_temp_file(&temp_dir, "outside.txt", "outside");
+    let link_path = dir1.join("link.txt");
+    std::os::unix::fs::symlink(dir1.join("inside.txt"), &link_path).unwrap();
+    assert!(
+        service
+            .validate_path(&link_path, allowed_dirs.clone())
+            .is_ok()
+    );
+
+    // replaced outside of the server, without any watcher noticing it
+    fs::remove_file(&link_path).unwrap();
+    std::os::unix::fs::symlink(temp_dir.join("outside.txt"), &link_path).unwrap();
+    assert!(matches!(
+        service.validate_path(&link_path, allowed_dirs),
+        Err(ServiceError::PathDenied(_))
+    ));
+}
+
 #[tokio::test]
 async fn test_get_file_stats() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
     let file_path = create_temp_file(temp_dir.join("dir1").as_path(), "test.txt", "content");
-    let result = service.get_file_stats(&file_path).await.unwrap();
+    let result = service.get_file_stats(&file_path, true).await.unwrap();
     assert_eq!(result.size, 7); // "content" is 7 bytes
     assert!(result.is_file);
     assert!(!result.is_directory);
@@ -114,116 +204,143 @@ async fn test_get_file_stats() {
 }
 
 #[tokio::test]
-async fn test_zip_directory() {
+async fn test_get_file_stats_mime_type() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-
-    let dir_path = temp_dir.join("dir1");
-    create_temp_file(&dir_path, "file1.txt", "content1");
-    create_temp_file(&dir_path, "file2.txt", "content2");
-    let zip_path = dir_path.join("output.zip");
-    let result = service
-        .zip_directory(
-            dir_path.to_str().unwrap().to_string(),
-            "*.txt".to_string(),
-            zip_path.to_str().unwrap().to_string(),
-        )
-        .await
-        .unwrap();
-    assert!(zip_path.exists());
-    assert!(result.contains("Successfully compressed"));
-    assert!(result.contains("output.zip"));
+    let dir = temp_dir.join("dir1");
+    let text_path = create_temp_file(&dir, "notes.txt", "plain text");
+    let binary_path = create_temp_file(&dir, "blob.bin", "\0\x01\x02");
+    let png_path = dir.join("image.png");
+    fs::write(&png_path, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+
+    let text = service.get_file_stats(&text_path, true).await.unwrap();
+    assert_eq!(text.mime_type.as_deref(), Some("text/plain"));
+    let binary = service.get_file_stats(&binary_path, true).await.unwrap();
+    assert_eq!(
+        binary.mime_type.as_deref(),
+        Some("application/octet-stream")
+    );
+    let png = service.get_file_stats(&png_path, true).await.unwrap();
+    assert_eq!(png.mime_type.as_deref(), Some("image/png"));
+    let directory = service.get_file_stats(&dir, true).await.unwrap();
+    assert_eq!(directory.mime_type, None);
 }
 
+#[cfg(unix)]
 #[tokio::test]
-async fn test_zip_directory_already_exists() {
+async fn test_get_file_stats_symlink_and_ownership() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let dir_path = temp_dir.join("dir1");
-    let zip_path = create_temp_file(&dir_path, "output.zip", "dummy");
-    let result = service
-        .zip_directory(
-            dir_path.to_str().unwrap().to_string(),
-            "*.txt".to_string(),
-            zip_path.to_str().unwrap().to_string(),
-        )
-        .await;
-    assert!(matches!(
-        result,
-        Err(ServiceError::IoError(ref e)) if e.kind() == std::io::ErrorKind::AlreadyExists
-    ));
+    let dir = temp_dir.join("dir1");
+    let file_path = create_temp_file(&dir, "target.txt", "content");
+    let link_path = dir.join("link.txt");
+    std::os::unix::fs::symlink(&file_path, &link_path).unwrap();
+    fs::hard_link(&file_path, dir.join("hard.txt")).unwrap();
+
+    let followed = service.get_file_stats(&link_path, true).await.unwrap();
+    assert!(followed.is_file);
+    assert!(!followed.is_symlink);
+    assert_eq!(followed.symlink_target, None);
+    assert_eq!(followed.hard_links, Some(2));
+    assert_eq!(followed.size, 7);
+    assert!(followed.uid.is_some() && followed.gid.is_some());
+    assert!(followed.inode.is_some() && followed.device.is_some());
+
+    let link = service.get_file_stats(&link_path, false).await.unwrap();
+    assert!(link.is_symlink);
+    assert!(!link.is_file);
+    assert_eq!(link.symlink_target, Some(file_path.clone()));
+    assert_eq!(link.mime_type, None);
+    assert_ne!(link.inode, followed.inode);
+    assert!(
+        link.to_string()
+            .contains(&format!("symlinkTarget: {}", file_path.display()))
+    );
 }
 
+#[cfg(unix)]
 #[tokio::test]
-async fn test_zip_files() {
+async fn test_get_file_stats_symlink_outside_allowed_dirs() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let dir_path = temp_dir.join("dir1");
+    let outside = create_temp_file(&temp_dir, "secret.txt", "secret");
+    let link_path = temp_dir.join("dir1").join("link.txt");
+    std::os::unix::fs::symlink(&outside, &link_path).unwrap();
 
-    let file1 = create_temp_file(dir_path.as_path(), "file1.txt", "content1");
-    let file2 = create_temp_file(dir_path.as_path(), "file2.txt", "content2");
-    let zip_path = dir_path.join("output.zip");
-    let result = service
-        .zip_files(
-            vec![
-                file1.to_str().unwrap().to_string(),
-                file2.to_str().unwrap().to_string(),
-            ],
-            zip_path.to_str().unwrap().to_string(),
-        )
-        .await
-        .unwrap();
-    assert!(zip_path.exists());
-    assert!(result.contains("Successfully compressed 2 files"));
-    assert!(result.contains("output.zip"));
+    assert!(service.get_file_stats(&link_path, true).await.is_err());
+    let link = service.get_file_stats(&link_path, false).await.unwrap();
+    assert_eq!(link.symlink_target, Some(outside));
 }
 
+#[cfg(unix)]
 #[tokio::test]
-async fn test_zip_files_empty_input() {
+async fn test_extended_attributes() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let zip_path = temp_dir.join("output.zip");
-    let result = service
-        .zip_files(vec![], zip_path.to_str().unwrap().to_string())
-        .await;
-    assert!(matches!(
-        result,
-        Err(ServiceError::IoError(ref e)) if e.kind() == std::io::ErrorKind::InvalidInput
-    ));
-}
+    let file_path = create_temp_file(&temp_dir.join("dir1"), "artifact.bin", "content");
 
-#[tokio::test]
-async fn test_unzip_file() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let dir_path = temp_dir.join("dir1");
-    let file1 = create_temp_file(&dir_path, "file1.txt", "content1");
-    let zip_path = dir_path.join("output.zip");
     service
-        .zip_files(
-            vec![file1.to_str().unwrap().to_string()],
-            zip_path.to_str().unwrap().to_string(),
-        )
+        .set_extended_attribute(&file_path, "user.tag", Some(b"release"))
         .await
         .unwrap();
-    let extract_dir = dir_path.join("extracted");
-    let result = service
-        .unzip_file(zip_path.to_str().unwrap(), extract_dir.to_str().unwrap())
+    service
+        .set_extended_attribute(&file_path, "user.checksum", Some(&[0xff, 0x00]))
+        .await
+        .unwrap();
+    let attributes = service.list_extended_attributes(&file_path).await.unwrap();
+    let names: Vec<_> = attributes
+        .iter()
+        .map(|attribute| (attribute.name.as_str(), attribute.size))
+        .collect();
+    assert_eq!(names, vec![("user.checksum", 2), ("user.tag", 7)]);
+    assert_eq!(
+        service
+            .get_extended_attribute(&file_path, "user.tag")
+            .await
+            .unwrap(),
+        b"release"
+    );
+
+    service
+        .set_extended_attribute(&file_path, "user.tag", None)
         .await
         .unwrap();
-    assert!(extract_dir.join("file1.txt").exists());
-    assert!(result.contains("Successfully extracted 1 file"));
+    let err = service
+        .get_extended_attribute(&file_path, "user.tag")
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("has no extended attribute 'user.tag'")
+    );
+    assert!(
+        service
+            .set_extended_attribute(&file_path, "user.tag", None)
+            .await
+            .is_err()
+    );
+    assert!(
+        service
+            .get_extended_attribute(&file_path, "user/tag")
+            .await
+            .is_err()
+    );
+
+    let outside = create_temp_file(&temp_dir, "outside.txt", "content");
+    assert!(
+        service
+            .set_extended_attribute(&outside, "user.tag", Some(b"x"))
+            .await
+            .is_err()
+    );
 }
 
+#[cfg(any(unix, windows))]
 #[tokio::test]
-async fn test_unzip_file_non_existent() {
+async fn test_disk_space() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let temp_dir = temp_dir.join("dir1");
-    let zip_path = temp_dir.join("non_existent.zip");
-    let extract_dir = temp_dir.join("extracted");
-    let result = service
-        .unzip_file(zip_path.to_str().unwrap(), extract_dir.to_str().unwrap())
-        .await;
+    let space = service.disk_space(&temp_dir.join("dir1")).await.unwrap();
+    assert!(space.total_bytes > 0);
+    assert!(space.free_bytes <= space.total_bytes);
+    assert!(space.available_bytes <= space.free_bytes);
 
-    assert!(matches!(
-        result,
-        Err(ServiceError::IoError(ref e)) if e.kind() == std::io::ErrorKind::NotFound
-    ));
+    assert!(service.disk_space(&temp_dir).await.is_err());
 }
 
 #[tokio::test]
@@ -244,17 +361,158 @@ async fn test_create_directory() {
     assert!(new_dir.is_dir());
 }
 
+#[tokio::test]
+async fn test_create_temp_path() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let dir = temp_dir.join("dir1");
+    let file = service
+        .create_temp_path(&dir, TempPathKind::File, "build-", ".json", None)
+        .await
+        .unwrap();
+    let name = file.file_name().unwrap().to_str().unwrap();
+    assert!(name.starts_with("build-") && name.ends_with(".json"));
+    assert!(file.is_file());
+    assert_eq!(fs::read(&file).unwrap().len(), 0);
+
+    let other = service
+        .create_temp_path(&dir, TempPathKind::File, "build-", ".json", None)
+        .await
+        .unwrap();
+    assert_ne!(file, other);
+
+    let scratch = service
+        .create_temp_path(
+            &dir,
+            TempPathKind::Directory,
+            "",
+            "",
+            Some(Duration::from_millis(50)),
+        )
+        .await
+        .unwrap();
+    assert!(scratch.is_dir());
+    fs::write(scratch.join("partial.txt"), "data").unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(!scratch.exists());
+
+    assert!(
+        service
+            .create_temp_path(&dir, TempPathKind::File, "../", "", None)
+            .await
+            .is_err()
+    );
+    assert!(
+        service
+            .create_temp_path(&temp_dir, TempPathKind::File, "", "", None)
+            .await
+            .is_err()
+    );
+}
+
 #[tokio::test]
 async fn test_move_file() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
     let src_path = create_temp_file(temp_dir.join("dir1").as_path(), "src.txt", "content");
     let dest_path = temp_dir.join("dir1").join("dest.txt");
-    let result = service.move_file(&src_path, &dest_path).await;
+    let result = service
+        .move_file(&src_path, &dest_path, OverwritePolicy::Error)
+        .await;
     assert!(result.is_ok());
     assert!(!src_path.exists());
     assert!(dest_path.exists());
 }
 
+#[tokio::test]
+async fn test_move_file_overwrite() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let service = service.with_journal(temp_dir.join("journal"));
+    let dir = temp_dir.join("dir1");
+    let dest = create_temp_file(&dir, "dest.txt", "dest");
+
+    let src = create_temp_file(&dir, "a.txt", "a");
+    let result = service.move_file(&src, &dest, OverwritePolicy::Error).await;
+    assert!(matches!(result, Err(ServiceError::Conflict(_))));
+    assert!(src.exists());
+
+    let moved = service
+        .move_file(&src, &dest, OverwritePolicy::Rename)
+        .await
+        .unwrap();
+    assert_eq!(moved, dir.join("dest (1).txt"));
+    assert_eq!(fs::read_to_string(&moved).unwrap(), "a");
+    assert_eq!(fs::read_to_string(&dest).unwrap(), "dest");
+
+    let src = create_temp_file(&dir, "b.txt", "b");
+    let moved = service
+        .move_file(&src, &dest, OverwritePolicy::Replace)
+        .await
+        .unwrap();
+    assert_eq!(moved, dest);
+    assert_eq!(fs::read_to_string(&dest).unwrap(), "b");
+
+    // undoing the replacement brings both files back
+    service.undo_last_operation().await.unwrap();
+    assert_eq!(fs::read_to_string(&src).unwrap(), "b");
+    assert_eq!(fs::read_to_string(&dest).unwrap(), "dest");
+}
+
+#[tokio::test]
+async fn test_copy_file() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let service = service
+        .with_journal(temp_dir.join("journal"))
+        .with_deny_globs(&["**/.env".to_string()])
+        .unwrap();
+    let dir = temp_dir.join("dir1");
+    let src = create_temp_file(&dir.join("src"), "a.txt", "a");
+    create_temp_file(&dir.join("src").join("nested"), "b.txt", "b");
+    create_temp_file(&dir.join("src"), ".env", "secret");
+    let dest = create_temp_file(&dir, "dest.txt", "dest");
+
+    let result = service.copy_file(&src, &dest, OverwritePolicy::Error).await;
+    assert!(matches!(result, Err(ServiceError::Conflict(_))));
+
+    let copied = service
+        .copy_file(&src, &dest, OverwritePolicy::Rename)
+        .await
+        .unwrap();
+    assert_eq!(copied, dir.join("dest (1).txt"));
+    assert_eq!(fs::read_to_string(&copied).unwrap(), "a");
+    assert_eq!(fs::read_to_string(&src).unwrap(), "a");
+
+    let copied = service
+        .copy_file(&src, &dest, OverwritePolicy::Replace)
+        .await
+        .unwrap();
+    assert_eq!(copied, dest);
+    assert_eq!(fs::read_to_string(&dest).unwrap(), "a");
+
+    let tree = service
+        .copy_file(&dir.join("src"), &dir.join("copy"), OverwritePolicy::Error)
+        .await
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(tree.join("nested").join("b.txt")).unwrap(),
+        "b"
+    );
+    assert!(!tree.join(".env").exists());
+    assert!(
+        service
+            .copy_file(
+                &dir.join("src"),
+                &dir.join("src/inner"),
+                OverwritePolicy::Error
+            )
+            .await
+            .is_err()
+    );
+
+    // undoing the directory copy removes it, leaving the source alone
+    service.undo_last_operation().await.unwrap();
+    assert!(!dir.join("copy").exists());
+    assert!(dir.join("src").join("nested").join("b.txt").exists());
+}
+
 #[tokio::test]
 async fn test_list_directory() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
@@ -272,1761 +530,5916 @@ async fn test_list_directory() {
 }
 
 #[tokio::test]
-async fn test_write_file() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path = temp_dir.join("dir1").join("test.txt");
-    let content = "new content".to_string();
-    let result = service.write_file(&file_path, &content).await;
-    assert!(result.is_ok());
-    assert_eq!(tokio_fs::read_to_string(&file_path).await.unwrap(), content);
-}
-
-#[tokio::test]
-async fn test_search_files() {
+async fn test_list_directory_with_sizes_depth() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
     let dir_path = temp_dir.join("dir1");
-    create_temp_file(&dir_path, "test1.txt", "content");
-    create_temp_file(&dir_path, "test2.doc", "content");
-    let result = service
-        .search_files(&dir_path, "*.txt".to_string(), vec![], None, None, None)
+    create_temp_file(&dir_path, "top.txt", "12");
+    create_temp_file(&dir_path.join("sub"), "a.txt", "123");
+    create_temp_file(&dir_path.join("sub").join("deep"), "b.txt", "12345");
+
+    let entries = service
+        .list_directory_with_sizes(&dir_path, None)
         .await
         .unwrap();
-    let names: Vec<_> = result
-        .into_iter()
-        .map(|e| e.file_name().to_str().unwrap().to_string())
-        .collect();
-    assert_eq!(names, vec!["test1.txt"]);
-}
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].name, "sub");
+    assert_eq!(entries[0].size, None);
+    assert_eq!(entries[1].size, Some(2));
 
-#[tokio::test]
-async fn test_search_files_with_exclude() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let dir_path = temp_dir.join("dir1");
-    create_temp_file(&dir_path, "test1.txt", "content");
-    create_temp_file(&dir_path, "test2.txt", "content");
-    let result = service
-        .search_files(
-            &dir_path,
-            "*.txt".to_string(),
-            vec!["test2.txt".to_string()],
-            None,
-            None,
-            None,
-        )
+    let entries = service
+        .list_directory_with_sizes(&dir_path, Some(1))
         .await
         .unwrap();
-    let names: Vec<_> = result
-        .into_iter()
-        .map(|e| e.file_name().to_str().unwrap().to_string())
+    assert_eq!(entries[0].size, Some(8));
+    assert!(entries[0].children.is_empty());
+
+    let entries = service
+        .list_directory_with_sizes(&dir_path, Some(2))
+        .await
+        .unwrap();
+    let children: Vec<_> = entries[0]
+        .children
+        .iter()
+        .map(|child| (child.name.as_str(), child.is_directory, child.size))
         .collect();
-    assert_eq!(names, vec!["test1.txt"]);
+    assert_eq!(
+        children,
+        vec![("a.txt", false, Some(3)), ("deep", true, Some(5))]
+    );
+    assert!(entries[0].children[1].children.is_empty());
 }
 
 #[tokio::test]
-async fn test_search_files_ast_respects_filters() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["project".to_string()]);
-    let project_root = temp_dir.join("project");
-
-    // File that should match
-    let keep = create_temp_file(&project_root, "keep.rs", "fn keep() { let x = 1; }");
-
-    // File that should be filtered by extension
-    create_temp_file(&project_root, "skip.txt", "fn keep() { let x = 2; }");
+async fn test_list_directory_page() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let dir_path = temp_dir.join("dir1");
+    create_temp_file(&dir_path, "b.json", "12345");
+    create_temp_file(&dir_path, "a.json", "1");
+    create_temp_file(&dir_path, "c.txt", "123");
+    create_temp_file(&dir_path, "d.json", "123");
+    fs::create_dir(dir_path.join("sub")).unwrap();
+    let names = |listing: &DirectoryListing| -> Vec<String> {
+        listing
+            .entries
+            .iter()
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .collect()
+    };
 
-    // File that should be excluded by glob pattern
-    create_temp_file(
-        &project_root.join("node_modules"),
-        "ignore.rs",
-        "fn ignored() {}",
+    let listing = service
+        .list_directory_page(&dir_path, DirectorySortBy::Name, None, 0, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        names(&listing),
+        vec!["a.json", "b.json", "c.txt", "d.json", "sub"]
     );
+    assert_eq!(listing.entries_total, 5);
+    assert_eq!(listing.next_offset, None);
 
-    let results = service
-        .search_files_ast(
-            &project_root,
-            "**/*",
-            "fn $NAME($ARGS) { $BODY }",
-            "rust",
-            Some(vec!["/node_modules/".to_string()]),
-            Some(vec!["rs".to_string()]),
-        )
+    let listing = service
+        .list_directory_page(&dir_path, DirectorySortBy::Size, Some("*.json"), 0, Some(2))
         .await
-        .expect("AST search should succeed");
+        .unwrap();
+    assert_eq!(names(&listing), vec!["b.json", "d.json"]);
+    assert_eq!(listing.entries_total, 3);
+    assert_eq!(listing.next_offset, Some(2));
+    let listing = service
+        .list_directory_page(&dir_path, DirectorySortBy::Size, Some("*.json"), 2, Some(2))
+        .await
+        .unwrap();
+    assert_eq!(names(&listing), vec!["a.json"]);
+    assert_eq!(listing.next_offset, None);
 
-    assert_eq!(results.len(), 1, "Only files passing filters should match");
-    assert_eq!(results[0].file_path, keep);
-    assert_eq!(results[0].matches.len(), 1);
-}
+    let old = fs::File::options()
+        .write(true)
+        .open(dir_path.join("c.txt"))
+        .unwrap();
+    old.set_modified(std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1))
+        .unwrap();
+    let listing = service
+        .list_directory_page(&dir_path, DirectorySortBy::Mtime, None, 0, None)
+        .await
+        .unwrap();
+    assert_eq!(names(&listing).last().unwrap(), "c.txt");
 
-#[test]
-fn test_create_unified_diff() {
-    let (_, service, _) = setup_service(vec![]);
-    let original = "line1\nline2\nline3".to_string();
-    let new = "line1\nline4\nline3".to_string();
-    let diff = service.create_unified_diff(&original, &new, Some("test.txt".to_string()));
-    assert!(diff.contains("Index: test.txt"));
-    assert!(diff.contains("--- test.txt\toriginal"));
-    assert!(diff.contains("+++ test.txt\tmodified"));
-    assert!(diff.contains("-line2"));
-    assert!(diff.contains("+line4"));
+    assert!(
+        service
+            .list_directory_page(&dir_path, DirectorySortBy::Name, Some("[a"), 0, None)
+            .await
+            .is_err()
+    );
 }
 
 #[tokio::test]
-async fn test_apply_file_edits() {
+async fn test_read_text_file_limited() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
     let file_path = create_temp_file(
         temp_dir.join("dir1").as_path(),
         "test.txt",
-        "line1\nline2\nline3",
+        "line1\nline2\nline3\n",
     );
-    let edits = vec![EditOperation {
-        old_text: "line2".to_string(),
-        new_text: "line4".to_string(),
-    }];
-    let result = service
-        .apply_file_edits(&file_path, edits, Some(false), None)
+
+    let read = service
+        .read_text_file_limited(&file_path, None, None)
         .await
         .unwrap();
-    assert!(result.contains("Index:"));
-    assert!(result.contains("-line2"));
-    assert!(result.contains("+line4"));
-    let new_content = tokio_fs::read_to_string(&file_path).await.unwrap();
-    assert_eq!(new_content, "line1\nline4\nline3");
-}
+    assert!(!read.truncated);
+    assert_eq!(read.content, "line1\nline2\nline3\n");
+    assert_eq!(read.total_bytes, 18);
 
-#[tokio::test]
-async fn test_apply_file_edits_dry_run() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path = create_temp_file(
-        temp_dir.join("dir1").as_path(),
-        "test.txt",
-        "line1\nline2\nline3",
-    );
-    let edits = vec![EditOperation {
-        old_text: "line2".to_string(),
-        new_text: "line4".to_string(),
-    }];
-    let result = service
-        .apply_file_edits(&file_path, edits, Some(true), None)
+    // cut at the last complete line
+    let read = service
+        .read_text_file_limited(&file_path, Some(14), None)
         .await
         .unwrap();
-    assert!(result.contains("Index:"));
-    assert!(result.contains("-line2"));
-    assert!(result.contains("+line4"));
-    let content = tokio_fs::read_to_string(&file_path).await.unwrap();
-    assert_eq!(content, "line1\nline2\nline3"); // Unchanged due to dry run
-}
+    assert!(read.truncated);
+    assert_eq!(read.content, "line1\nline2\n");
+    assert_eq!(read.returned_lines, 2);
 
-#[tokio::test]
-async fn test_apply_file_edits_no_match() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path = create_temp_file(
-        temp_dir.join("dir1").as_path(),
-        "test.txt",
-        "line1\nline2\nline3",
+    let read = service
+        .read_text_file_limited(&file_path, None, Some(1))
+        .await
+        .unwrap();
+    assert!(read.truncated);
+    assert_eq!(read.content, "line1\n");
+    let read = service
+        .read_text_file_limited(&file_path, None, Some(3))
+        .await
+        .unwrap();
+    assert!(!read.truncated);
+
+    // the largest limit reads the whole file
+    let read = service
+        .read_text_file_limited(&file_path, Some(usize::MAX), None)
+        .await
+        .unwrap();
+    assert!(!read.truncated);
+    assert_eq!(read.content, "line1\nline2\nline3\n");
+
+    // the server default applies, without splitting a character
+    let file_path = create_temp_file(temp_dir.join("dir1").as_path(), "long.txt", "ééé");
+    let service = service.with_read_max_bytes(3);
+    let read = service
+        .read_text_file_limited(&file_path, None, None)
+        .await
+        .unwrap();
+    assert!(read.truncated);
+    assert_eq!(read.content, "é");
+}
+
+#[tokio::test]
+async fn test_write_file() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = temp_dir.join("dir1").join("test.txt");
+    let content = "new content".to_string();
+    let result = service.write_file(&file_path, &content, false, None).await;
+    assert!(result.is_ok());
+    assert_eq!(tokio_fs::read_to_string(&file_path).await.unwrap(), content);
+}
+
+#[tokio::test]
+async fn test_write_file_atomic() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let dir = temp_dir.join("dir1");
+    let file_path = create_temp_file(&dir, "test.txt", "old content");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&file_path, std::fs::Permissions::from_mode(0o640)).unwrap();
+    }
+
+    assert!(service.atomic_writes());
+    service
+        .write_file(&file_path, &"new content".to_string(), false, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        tokio_fs::read_to_string(&file_path).await.unwrap(),
+        "new content"
+    );
+    // the staging file was renamed over the target
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&file_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+    }
+
+    let service = service.with_atomic_writes(false);
+    service
+        .write_file(&file_path, &"in place".to_string(), false, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        tokio_fs::read_to_string(&file_path).await.unwrap(),
+        "in place"
     );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_write_file_atomic_keeps_links() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let dir = temp_dir.join("dir1");
+    let target = create_temp_file(&dir, "target.txt", "old");
+    let link = dir.join("link.txt");
+    std::os::unix::fs::symlink(&target, &link).unwrap();
+    let hard_link = dir.join("hard.txt");
+    fs::hard_link(&target, &hard_link).unwrap();
+
+    // a symlink is written through, and the hard link sees the new content
+    service
+        .write_file(&link, &"via symlink".to_string(), false, None)
+        .await
+        .unwrap();
+    assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+    assert_eq!(fs::read_to_string(&target).unwrap(), "via symlink");
+    assert_eq!(fs::read_to_string(&hard_link).unwrap(), "via symlink");
+
     let edits = vec![EditOperation {
-        old_text: "non_existent".to_string(),
-        new_text: "line4".to_string(),
+        old_text: "via symlink".to_string(),
+        new_text: "edited".to_string(),
+        ..Default::default()
+    }];
+    service
+        .apply_edits_batch(vec![(link.clone(), edits)], false)
+        .await
+        .unwrap();
+    assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+    assert_eq!(fs::read_to_string(&hard_link).unwrap(), "edited");
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+}
+
+#[tokio::test]
+async fn test_write_file_atomic_concurrent() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let dir = temp_dir.join("dir1");
+    let file_path = create_temp_file(&dir, "test.txt", "old");
+
+    let contents: Vec<String> = (0..8).map(|i| format!("content {i}")).collect();
+    let results = futures::future::join_all(
+        contents
+            .iter()
+            .map(|content| service.write_file(&file_path, content, false, None)),
+    )
+    .await;
+    assert!(results.iter().all(|result| result.is_ok()));
+    assert!(contents.contains(&fs::read_to_string(&file_path).unwrap()));
+    // no staging file was left behind
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+}
+
+#[tokio::test]
+async fn test_write_and_edit_backups() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_temp_file(&temp_dir.join("dir1"), "test.txt", "v1");
+
+    // no backup unless asked for, nor for a new file
+    let new_file = temp_dir.join("dir1").join("new.txt");
+    assert_eq!(
+        service
+            .write_file(&new_file, &"v1".to_string(), true, None)
+            .await
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        service
+            .write_file(&file_path, &"v2".to_string(), false, None)
+            .await
+            .unwrap(),
+        None
+    );
+
+    let backup = service
+        .write_file(&file_path, &"v3".to_string(), true, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(backup, file_path.with_file_name("test.txt.bak"));
+    assert_eq!(tokio_fs::read_to_string(&backup).await.unwrap(), "v2");
+
+    let edits = vec![EditOperation {
+        old_text: "v3".to_string(),
+        new_text: "v4".to_string(),
+        ..Default::default()
     }];
     let result = service
-        .apply_file_edits(&file_path, edits, Some(false), None)
+        .apply_file_edits(&file_path, edits, Some(false), None, true, None)
+        .await
+        .unwrap();
+    assert!(result.diff.contains("test.txt.bak"));
+    assert_eq!(tokio_fs::read_to_string(&backup).await.unwrap(), "v3");
+
+    // a backup directory mirrors the absolute path of the files
+    let backup_dir = temp_dir.join("backups");
+    let service = service.with_backup_dir(backup_dir.clone());
+    let backup = service
+        .write_file(&file_path, &"v5".to_string(), false, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(backup.starts_with(&backup_dir));
+    assert!(backup.ends_with("dir1/test.txt"));
+    assert_eq!(tokio_fs::read_to_string(&backup).await.unwrap(), "v4");
+}
+
+#[tokio::test]
+async fn test_write_and_edit_if_match_sha256() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_temp_file(&temp_dir.join("dir1"), "test.txt", "v1");
+    let v1_hash = sha256_hex(b"v1");
+
+    service
+        .write_file(&file_path, &"v2".to_string(), false, Some(&v1_hash))
+        .await
+        .unwrap();
+    // the file changed since v1 was read
+    let result = service
+        .write_file(&file_path, &"v3".to_string(), false, Some(&v1_hash))
+        .await;
+    assert!(matches!(result, Err(ServiceError::Conflict(_))));
+    assert_eq!(tokio_fs::read_to_string(&file_path).await.unwrap(), "v2");
+
+    let edits = || {
+        vec![EditOperation {
+            old_text: "v2".to_string(),
+            new_text: "v3".to_string(),
+            ..Default::default()
+        }]
+    };
+    let result = service
+        .apply_file_edits(
+            &file_path,
+            edits(),
+            Some(false),
+            None,
+            false,
+            Some(&v1_hash),
+        )
+        .await;
+    assert!(matches!(result, Err(ServiceError::Conflict(_))));
+    let diff = service
+        .apply_file_edits(
+            &file_path,
+            edits(),
+            Some(false),
+            None,
+            false,
+            Some(&sha256_hex(b"v2").to_uppercase()),
+        )
+        .await
+        .unwrap();
+    assert!(diff.diff.contains(&sha256_hex(b"v3")));
+    assert_eq!(tokio_fs::read_to_string(&file_path).await.unwrap(), "v3");
+
+    // a file that does not exist anymore is a conflict too
+    let missing = temp_dir.join("dir1").join("missing.txt");
+    let result = service
+        .write_file(&missing, &"v1".to_string(), false, Some(&v1_hash))
         .await;
-    assert!(matches!(result, Err(ServiceError::RpcError(_))));
+    assert!(matches!(result, Err(ServiceError::Conflict(_))));
 }
 
-#[test]
-fn test_format_system_time() {
-    let now = SystemTime::now();
-    let formatted = format_system_time(now);
-    // Check that the output matches the expected format (e.g., "Sat Apr 12 2025 14:30:45 +00:00")
-    assert!(formatted.contains("202")); // Year should appear
-    assert!(formatted.contains(":")); // Time should have colons
-    assert!(formatted.contains("+") || formatted.contains("-")); // Timezone offset
+#[tokio::test]
+async fn test_search_files() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let dir_path = temp_dir.join("dir1");
+    create_temp_file(&dir_path, "test1.txt", "content");
+    create_temp_file(&dir_path, "test2.doc", "content");
+    let result = service
+        .search_files(
+            &dir_path,
+            "*.txt".to_string(),
+            vec![],
+            None,
+            None,
+            None,
+            WalkOptions::default(),
+        )
+        .await
+        .unwrap();
+    let names: Vec<_> = result
+        .into_iter()
+        .map(|e| e.file_name().to_str().unwrap().to_string())
+        .collect();
+    assert_eq!(names, vec!["test1.txt"]);
 }
 
-#[cfg(unix)]
-#[test]
-fn test_format_permissions_unix() {
-    use rust_mcp_filesystem::fs_service::utils::format_permissions;
+#[tokio::test]
+async fn test_search_files_with_exclude() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let dir_path = temp_dir.join("dir1");
+    create_temp_file(&dir_path, "test1.txt", "content");
+    create_temp_file(&dir_path, "test2.txt", "content");
+    let result = service
+        .search_files(
+            &dir_path,
+            "*.txt".to_string(),
+            vec!["test2.txt".to_string()],
+            None,
+            None,
+            None,
+            WalkOptions::default(),
+        )
+        .await
+        .unwrap();
+    let names: Vec<_> = result
+        .into_iter()
+        .map(|e| e.file_name().to_str().unwrap().to_string())
+        .collect();
+    assert_eq!(names, vec!["test1.txt"]);
+}
 
-    let temp_dir = get_temp_dir();
-    let file_path = temp_dir.join("test.txt");
-    File::create(&file_path).unwrap();
+#[tokio::test]
+async fn test_sort_search_results() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let dir_path = temp_dir.join("dir1");
+    let old = create_temp_file(&dir_path, "b.txt", "longest content");
+    create_temp_file(&dir_path, "a.txt", "short");
+    create_temp_file(&dir_path, "c.txt", "short");
+    File::options()
+        .write(true)
+        .open(&old)
+        .unwrap()
+        .set_modified(SystemTime::now() - std::time::Duration::from_secs(3600))
+        .unwrap();
+    let search = || async {
+        service
+            .search_files(
+                &dir_path,
+                "*.txt".to_string(),
+                vec![],
+                None,
+                None,
+                None,
+                WalkOptions::default(),
+            )
+            .await
+            .unwrap()
+    };
+    let names = |entries: Vec<ignore::DirEntry>| -> Vec<String> {
+        entries
+            .iter()
+            .map(|e| e.file_name().to_str().unwrap().to_string())
+            .collect()
+    };
 
-    // Set specific permissions (e.g., rw-r--r--)
-    fs::set_permissions(&file_path, fs::Permissions::from_mode(0o644)).unwrap();
-    let metadata = fs::metadata(&file_path).unwrap();
-    let formatted = format_permissions(&metadata);
-    assert_eq!(formatted, "0644");
+    let mut entries = search().await;
+    sort_search_results(&mut entries, SearchSortBy::Path, SortOrder::Desc);
+    assert_eq!(names(entries), vec!["c.txt", "b.txt", "a.txt"]);
+
+    // ties are ordered by path
+    let mut entries = search().await;
+    sort_search_results(&mut entries, SearchSortBy::Size, SortOrder::Desc);
+    assert_eq!(names(entries), vec!["b.txt", "a.txt", "c.txt"]);
+    let mut entries = search().await;
+    sort_search_results(&mut entries, SearchSortBy::Size, SortOrder::Asc);
+    assert_eq!(names(entries), vec!["a.txt", "c.txt", "b.txt"]);
+
+    let mut entries = search().await;
+    sort_search_results(&mut entries, SearchSortBy::Mtime, SortOrder::Asc);
+    assert_eq!(names(entries)[0], "b.txt");
+}
 
-    // Test directory permissions
-    let dir_metadata = fs::metadata(temp_dir).unwrap();
-    let dir_formatted = format_permissions(&dir_metadata);
-    assert!(dir_formatted.starts_with("0")); // Should be octal
+#[tokio::test]
+async fn test_search_files_max_depth() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let dir_path = temp_dir.join("dir1");
+    create_temp_file(&dir_path, "top.txt", "needle");
+    create_temp_file(&dir_path.join("a"), "mid.txt", "needle");
+    create_temp_file(&dir_path.join("a").join("b"), "deep.txt", "needle");
+
+    for (max_depth, expected) in [
+        (Some(1), vec!["top.txt"]),
+        (Some(2), vec!["mid.txt", "top.txt"]),
+        (None, vec!["deep.txt", "mid.txt", "top.txt"]),
+    ] {
+        let walk_options = WalkOptions::default().with_max_depth(max_depth);
+        let mut names: Vec<_> = service
+            .search_files(
+                &dir_path,
+                "*.txt".to_string(),
+                vec![],
+                None,
+                None,
+                None,
+                walk_options,
+            )
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.file_name().to_str().unwrap().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, expected);
+
+        let mut names: Vec<_> = service
+            .search_files_content(
+                &dir_path,
+                "*.txt",
+                "needle",
+                false,
+                None,
+                None,
+                None,
+                walk_options,
+                &OperationContext::default(),
+            )
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|result| {
+                result
+                    .file_path
+                    .file_name()
+                    .unwrap()
+                    .to_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        names.sort();
+        assert_eq!(names, expected);
+    }
 }
 
-#[cfg(windows)]
-#[test]
-fn test_format_permissions_windows() {
-    let temp_dir = get_temp_dir();
-    let file_path = temp_dir.join("test.txt");
-    let mut file = File::create(&file_path).unwrap();
-    file.write_all(b"test").unwrap();
-    file.flush().unwrap();
+#[tokio::test]
+async fn test_search_threads() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let dir_path = temp_dir.join("dir1");
+    for i in 0..10 {
+        create_temp_file(&dir_path.join(format!("sub{i}")), "file.txt", "needle");
+    }
 
-    // Set read-only
-    let mut perms = fs::metadata(&file_path).unwrap().permissions();
-    perms.set_readonly(true);
-    fs::set_permissions(&file_path, perms).unwrap();
+    assert_eq!(service.search_threads(WalkOptions::default()), 0);
+    let service = service.with_search_threads(4, false);
+    assert_eq!(service.search_threads(WalkOptions::default()), 4);
+    assert_eq!(
+        service.search_threads(WalkOptions::default().with_threads(Some(2), None)),
+        2
+    );
+    assert_eq!(
+        service.search_threads(WalkOptions::default().with_threads(Some(2), Some(true))),
+        1
+    );
+    let service = service.with_search_threads(4, true);
+    assert_eq!(service.search_threads(WalkOptions::default()), 1);
+    assert_eq!(
+        service.search_threads(WalkOptions::default().with_threads(None, Some(false))),
+        4
+    );
 
-    let metadata = fs::metadata(&file_path).unwrap();
-    let formatted = format_permissions(&metadata);
-    assert_eq!(formatted, "-r"); // Regular file, read-only
+    for low_priority in [true, false] {
+        let results = service
+            .search_files_content(
+                &dir_path,
+                "*.txt",
+                "needle",
+                false,
+                None,
+                None,
+                None,
+                WalkOptions::default().with_threads(None, Some(low_priority)),
+                &OperationContext::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 10);
+    }
+}
 
-    // Test directory
-    let dir_metadata = fs::metadata(temp_dir).unwrap();
-    let dir_formatted = format_permissions(&dir_metadata);
-    assert_eq!(dir_formatted, "dw"); // Directory, typically writable
+#[tokio::test]
+async fn test_search_files_ast_respects_filters() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["project".to_string()]);
+    let project_root = temp_dir.join("project");
+
+    // File that should match
+    let keep = create_temp_file(&project_root, "keep.rs", "fn keep() { let x = 1; }");
+
+    // File that should be filtered by extension
+    create_temp_file(&project_root, "skip.txt", "fn keep() { let x = 2; }");
+
+    // File that should be excluded by glob pattern
+    create_temp_file(
+        &project_root.join("node_modules"),
+        "ignore.rs",
+        "fn ignored() {}",
+    );
+
+    let results = service
+        .search_files_ast(
+            &project_root,
+            "**/*",
+            "fn $NAME($$$ARGS) { $$$BODY }",
+            "rust",
+            Some(vec!["/node_modules/".to_string()]),
+            Some(vec!["rs".to_string()]),
+            None,
+            WalkOptions::default(),
+            &OperationContext::default(),
+        )
+        .await
+        .expect("AST search should succeed");
+
+    assert_eq!(results.len(), 1, "Only files passing filters should match");
+    assert_eq!(results[0].file_path, keep);
+    assert_eq!(results[0].matches.len(), 1);
 }
 
-#[test]
-fn test_normalize_path() {
-    let temp_dir = get_temp_dir();
-    let file_path = temp_dir.join("test.txt");
-    File::create(&file_path).unwrap();
+#[tokio::test]
+async fn test_search_files_ast_limits() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["project".to_string()]);
+    let project_root = temp_dir.join("project");
+    create_temp_file(&project_root, "a.rs", "fn a() {}");
+    create_temp_file(&project_root, "b.rs", "fn b() {}");
+    create_temp_file(
+        &project_root,
+        "generated.rs",
+        &"fn generated() {}\n".repeat(100),
+    );
+    async fn search(
+        service: &FileSystemService,
+        root: &Path,
+        limits: AstSearchLimits,
+    ) -> AstSearchOutcome {
+        service
+            .search_files_ast_with_outcome(
+                root,
+                "**/*.rs",
+                "fn $NAME() {}",
+                "rust",
+                None,
+                None,
+                None,
+                None,
+                WalkOptions::default().with_threads(Some(1), None),
+                limits,
+                &OperationContext::default(),
+            )
+            .await
+            .unwrap()
+    }
 
-    let normalized = normalize_path(&file_path);
-    assert_eq!(normalized, file_path);
+    let outcome = search(&service, &project_root, AstSearchLimits::default()).await;
+    assert_eq!(outcome.files_scanned, 3);
+    assert_eq!(outcome.skipped_large_files, 0);
+    assert!(!outcome.reached_file_limit);
+
+    let outcome = search(
+        &service,
+        &project_root,
+        AstSearchLimits {
+            max_file_size: Some(100),
+            max_files: None,
+        },
+    )
+    .await;
+    assert_eq!(outcome.files_scanned, 2);
+    assert_eq!(outcome.skipped_large_files, 1);
+    assert_eq!(outcome.max_file_size, 100);
+
+    // the server limits apply unless a call overrides them
+    let outcome = search(
+        &service.with_ast_limits(100, 1),
+        &project_root,
+        AstSearchLimits::default(),
+    )
+    .await;
+    assert_eq!(outcome.files_scanned, 1);
+    assert_eq!(outcome.skipped_large_files, 1);
+    assert!(outcome.reached_file_limit);
+    assert_eq!(outcome.max_files, 1);
+}
+
+#[tokio::test]
+async fn test_search_files_ast_reparses_modified_files() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["project".to_string()]);
+    let project_root = temp_dir.join("project");
+    let file = create_temp_file(&project_root, "lib.rs", "fn a() {}\n");
+    let names = async |service: &FileSystemService| -> Vec<String> {
+        service
+            .search_files_ast(
+                &project_root,
+                "**/*.rs",
+                "fn $NAME() {}",
+                "rust",
+                None,
+                None,
+                None,
+                WalkOptions::default(),
+                &OperationContext::default(),
+            )
+            .await
+            .unwrap()
+            .into_iter()
+            .flat_map(|result| result.matches)
+            .map(|m| m.captures["NAME"].clone())
+            .collect()
+    };
+
+    assert_eq!(names(&service).await, ["a"]);
+    // served from the cached parse tree
+    assert_eq!(names(&service).await, ["a"]);
+
+    fs::write(&file, "fn a() {}\nfn b() {}\n").unwrap();
+    assert_eq!(names(&service).await, ["a", "b"]);
+
+    // same size, different modification time
+    fs::write(&file, "fn c() {}\nfn d() {}\n").unwrap();
+    File::options()
+        .write(true)
+        .open(&file)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(60))
+        .unwrap();
+    assert_eq!(names(&service).await, ["c", "d"]);
+}
+
+#[tokio::test]
+async fn test_search_files_ast_injected_language() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["project".to_string()]);
+    let project_root = temp_dir.join("project");
+    create_temp_file(
+        &project_root,
+        "index.html",
+        "<html>\n<script>\nconst answer = 42;\n</script>\n<style>\np { color: red; }\n</style>\n</html>\n",
+    );
+    let search = async |pattern: &str, injected_language: &str| {
+        service
+            .search_files_ast_with_outcome(
+                &project_root,
+                "**/*.html",
+                pattern,
+                "html",
+                Some(injected_language),
+                None,
+                None,
+                None,
+                WalkOptions::default(),
+                AstSearchLimits::default(),
+                &OperationContext::default(),
+            )
+            .await
+    };
+
+    let outcome = search("const $NAME = $VALUE", "javascript").await.unwrap();
+    assert_eq!(outcome.results.len(), 1);
+    let found = &outcome.results[0].matches[0];
+    assert_eq!(found.line_number, 3);
+    assert_eq!(found.matched_code, "const answer = 42;");
+    assert_eq!(found.captures["VALUE"], "42");
+
+    let outcome = search("p { color: $COLOR; }", "css").await.unwrap();
+    assert_eq!(outcome.results[0].matches[0].captures["COLOR"], "red");
+    assert_eq!(outcome.results[0].matches[0].line_number, 6);
+
+    // rust is never embedded in html
+    assert!(search("let $A = $B;", "rust").await.is_err());
+}
+
+#[tokio::test]
+async fn test_search_files_ast_enclosing_context() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["project".to_string()]);
+    let project_root = temp_dir.join("project");
+    create_temp_file(
+        &project_root,
+        "lib.rs",
+        r#"fn top() {
+    helper(1);
+}
+
+impl Runner for Job {
+    fn run(&self) {
+        let a = 0;
+        helper(2);
+    }
+}
+"#,
+    );
+
+    let results = service
+        .search_files_ast(
+            &project_root,
+            "**/*.rs",
+            "helper($X)",
+            "rust",
+            None,
+            None,
+            Some(1),
+            WalkOptions::default(),
+            &OperationContext::default(),
+        )
+        .await
+        .unwrap();
+    let mut matches = results[0].matches.clone();
+    matches.sort_by_key(|m| m.line_number);
+
+    assert_eq!(matches[0].enclosing.as_deref(), Some("function top"));
+    assert_eq!(matches[0].context_before, vec!["fn top() {"]);
+    assert_eq!(matches[0].context_after, vec!["}"]);
+
+    assert_eq!(
+        matches[1].enclosing.as_deref(),
+        Some("impl Runner for Job > method run")
+    );
+    assert_eq!(matches[1].context_before, vec!["        let a = 0;"]);
+    assert_eq!(matches[1].context_after, vec!["    }"]);
+
+    // no context lines by default
+    let results = service
+        .search_files_ast(
+            &project_root,
+            "**/*.rs",
+            "helper($X)",
+            "rust",
+            None,
+            None,
+            None,
+            WalkOptions::default(),
+            &OperationContext::default(),
+        )
+        .await
+        .unwrap();
+    assert!(results[0].matches[0].context_before.is_empty());
+    assert!(results[0].matches[0].enclosing.is_some());
+}
+
+#[tokio::test]
+async fn test_search_files_ast_captures() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["project".to_string()]);
+    let project_root = temp_dir.join("project");
+    create_temp_file(&project_root, "app.ts", "const answer = compute(1, 2);\n");
+
+    let results = service
+        .search_files_ast(
+            &project_root,
+            "**/*.ts",
+            "const $NAME = $FN($$$ARGS)",
+            "typescript",
+            None,
+            None,
+            None,
+            WalkOptions::default(),
+            &OperationContext::default(),
+        )
+        .await
+        .unwrap();
+    let captures = &results[0].matches[0].captures;
+    assert_eq!(captures.len(), 3);
+    assert_eq!(captures["NAME"], "answer");
+    assert_eq!(captures["FN"], "compute");
+    assert_eq!(captures["ARGS"], "1, 2");
+
+    // anonymous wildcards are not captured
+    let results = service
+        .search_files_ast(
+            &project_root,
+            "**/*.ts",
+            "const $_ = $FN($$$)",
+            "typescript",
+            None,
+            None,
+            None,
+            WalkOptions::default(),
+            &OperationContext::default(),
+        )
+        .await
+        .unwrap();
+    let captures = &results[0].matches[0].captures;
+    assert_eq!(captures.keys().collect::<Vec<_>>(), vec!["FN"]);
+}
+
+#[tokio::test]
+async fn test_search_files_ast_rule() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["project".to_string()]);
+    let project_root = temp_dir.join("project");
+    create_temp_file(
+        &project_root,
+        "app.js",
+        r#"async function run(items) {
+  await setup();
+  for (const item of items) {
+    await save(item);
+  }
+}
+"#,
+    );
+
+    // relational rule: awaits inside a loop only
+    let rule = r#"
+id: no-await-in-loop
+language: javascript
+rule:
+  pattern: await $CALL
+  inside:
+    kind: for_in_statement
+    stopBy: end
+"#;
+    let results = service
+        .search_files_ast_rule(
+            &project_root,
+            "**/*.js",
+            rule,
+            "javascript",
+            None,
+            None,
+            None,
+            WalkOptions::default(),
+            &OperationContext::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].matches.len(), 1);
+    assert_eq!(results[0].matches[0].matched_code, "await save(item)");
+    assert_eq!(results[0].matches[0].line_number, 4);
+
+    // constraints on meta-variables
+    let rule = r#"
+rule:
+  pattern: await $FN($$$ARGS)
+constraints:
+  FN:
+    regex: ^set
+"#;
+    let results = service
+        .search_files_ast_rule(
+            &project_root,
+            "**/*.js",
+            rule,
+            "javascript",
+            None,
+            None,
+            None,
+            WalkOptions::default(),
+            &OperationContext::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(results[0].matches.len(), 1);
+    assert_eq!(results[0].matches[0].matched_code, "await setup()");
+
+    let result = service
+        .search_files_ast_rule(
+            &project_root,
+            "**/*.js",
+            "rule: [",
+            "javascript",
+            None,
+            None,
+            None,
+            WalkOptions::default(),
+            &OperationContext::default(),
+        )
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_rewrite_files_ast() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["project".to_string()]);
+    let project_root = temp_dir.join("project");
+    let app = create_temp_file(
+        &project_root,
+        "app.js",
+        "console.log(a);\nfoo(console.log(b, c));\n",
+    );
+    let untouched = create_temp_file(&project_root, "other.js", "alert(1);\n");
+
+    // dry run only returns diffs
+    let results = service
+        .rewrite_files_ast(
+            &project_root,
+            "**/*.js",
+            "console.log($$$ARGS)",
+            "logger.info($$$ARGS)",
+            "javascript",
+            None,
+            None,
+            AstSearchLimits::default(),
+            true,
+        )
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].file_path, app);
+    assert_eq!(results[0].replacements, 2);
+    assert!(results[0].diff.contains("+foo(logger.info(b, c));"));
+    assert_eq!(
+        fs::read_to_string(&app).unwrap(),
+        "console.log(a);\nfoo(console.log(b, c));\n"
+    );
+
+    service
+        .rewrite_files_ast(
+            &project_root,
+            "**/*.js",
+            "console.log($$$ARGS)",
+            "logger.info($$$ARGS)",
+            "javascript",
+            None,
+            None,
+            AstSearchLimits::default(),
+            false,
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(&app).unwrap(),
+        "logger.info(a);\nfoo(logger.info(b, c));\n"
+    );
+    assert_eq!(fs::read_to_string(&untouched).unwrap(), "alert(1);\n");
+}
+
+#[tokio::test]
+async fn test_rewrite_files_ast_nested_matches() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["project".to_string()]);
+    let project_root = temp_dir.join("project");
+    let file = create_temp_file(&project_root, "lib.rs", "fn f() { g(g(1)); }\n");
+
+    let results = service
+        .rewrite_files_ast(
+            &project_root,
+            "**/*.rs",
+            "g($X)",
+            "h($X)",
+            "rust",
+            None,
+            None,
+            AstSearchLimits::default(),
+            false,
+        )
+        .await
+        .unwrap();
+    // the inner call is part of the outer match and is kept as captured
+    assert_eq!(results[0].replacements, 1);
+    assert_eq!(fs::read_to_string(&file).unwrap(), "fn f() { h(g(1)); }\n");
+}
+
+#[tokio::test]
+async fn test_rewrite_files_ast_refuses_partial_rewrite() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["project".to_string()]);
+    let project_root = temp_dir.join("project");
+    let first = create_temp_file(&project_root, "a.rs", "fn f() { g(1); }\n");
+    let second = create_temp_file(&project_root, "b.rs", "fn f() { g(2); }\n");
+
+    for dry_run in [true, false] {
+        let result = service
+            .rewrite_files_ast(
+                &project_root,
+                "**/*.rs",
+                "g($X)",
+                "h($X)",
+                "rust",
+                None,
+                None,
+                AstSearchLimits {
+                    max_file_size: None,
+                    max_files: Some(1),
+                },
+                dry_run,
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::InvalidArgument(_))));
+    }
+    assert_eq!(fs::read_to_string(&first).unwrap(), "fn f() { g(1); }\n");
+    assert_eq!(fs::read_to_string(&second).unwrap(), "fn f() { g(2); }\n");
+}
+
+#[test]
+fn test_create_unified_diff() {
+    let (_, service, _) = setup_service(vec![]);
+    let original = "line1\nline2\nline3".to_string();
+    let new = "line1\nline4\nline3".to_string();
+    let diff = service.create_unified_diff(&original, &new, Some("test.txt".to_string()));
+    assert!(diff.contains("Index: test.txt"));
+    assert!(diff.contains("--- test.txt\toriginal"));
+    assert!(diff.contains("+++ test.txt\tmodified"));
+    assert!(diff.contains("-line2"));
+    assert!(diff.contains("+line4"));
+}
+
+#[tokio::test]
+async fn test_apply_file_edits() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_temp_file(
+        temp_dir.join("dir1").as_path(),
+        "test.txt",
+        "line1\nline2\nline3",
+    );
+    let edits = vec![EditOperation {
+        old_text: "line2".to_string(),
+        new_text: "line4".to_string(),
+        ..Default::default()
+    }];
+    let result = service
+        .apply_file_edits(&file_path, edits, Some(false), None, false, None)
+        .await
+        .unwrap();
+    assert!(result.diff.contains("Index:"));
+    assert!(result.diff.contains("-line2"));
+    assert!(result.diff.contains("+line4"));
+    let new_content = tokio_fs::read_to_string(&file_path).await.unwrap();
+    assert_eq!(new_content, "line1\nline4\nline3");
+}
+
+#[tokio::test]
+async fn test_apply_file_edits_dry_run() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_temp_file(
+        temp_dir.join("dir1").as_path(),
+        "test.txt",
+        "line1\nline2\nline3",
+    );
+    let edits = vec![EditOperation {
+        old_text: "line2".to_string(),
+        new_text: "line4".to_string(),
+        ..Default::default()
+    }];
+    let result = service
+        .apply_file_edits(&file_path, edits, Some(true), None, false, None)
+        .await
+        .unwrap();
+    assert!(result.diff.contains("Index:"));
+    assert!(result.diff.contains("-line2"));
+    assert!(result.diff.contains("+line4"));
+    let content = tokio_fs::read_to_string(&file_path).await.unwrap();
+    assert_eq!(content, "line1\nline2\nline3"); // Unchanged due to dry run
+}
+
+#[tokio::test]
+async fn test_apply_file_edits_no_match() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_temp_file(
+        temp_dir.join("dir1").as_path(),
+        "test.txt",
+        "line1\nline2\nline3",
+    );
+    let edits = vec![EditOperation {
+        old_text: "non_existent".to_string(),
+        new_text: "line4".to_string(),
+        ..Default::default()
+    }];
+    let result = service
+        .apply_file_edits(&file_path, edits, Some(false), None, false, None)
+        .await;
+    assert!(matches!(result, Err(ServiceError::NoMatch(_))));
+}
+
+#[tokio::test]
+async fn test_apply_edits_batch() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let dir = temp_dir.join("dir1");
+    let file_a = create_temp_file(&dir, "a.txt", "alpha\nbeta\n");
+    let file_b = create_temp_file(&dir, "b.txt", "gamma\r\ndelta\r\n");
+    let edit = |old: &str, new: &str| EditOperation {
+        old_text: old.to_string(),
+        new_text: new.to_string(),
+        ..Default::default()
+    };
+
+    // one edit does not apply: no file is changed
+    let result = service
+        .apply_edits_batch(
+            vec![
+                (file_a.clone(), vec![edit("beta", "BETA")]),
+                (file_b.clone(), vec![edit("missing", "x")]),
+            ],
+            false,
+        )
+        .await;
+    assert!(result.unwrap_err().to_string().contains("b.txt"));
+    assert_eq!(
+        tokio_fs::read_to_string(&file_a).await.unwrap(),
+        "alpha\nbeta\n"
+    );
+
+    // the same file listed twice is rejected
+    let result = service
+        .apply_edits_batch(
+            vec![
+                (file_a.clone(), vec![edit("beta", "BETA")]),
+                (file_a.clone(), vec![edit("alpha", "ALPHA")]),
+            ],
+            false,
+        )
+        .await;
+    assert!(result.is_err());
+
+    let diffs = service
+        .apply_edits_batch(
+            vec![
+                (file_a.clone(), vec![edit("beta", "BETA")]),
+                (file_b.clone(), vec![edit("delta", "DELTA")]),
+            ],
+            true,
+        )
+        .await
+        .unwrap();
+    assert!(diffs.diff.contains("+BETA") && diffs.diff.contains("+DELTA"));
+    assert_eq!(
+        tokio_fs::read_to_string(&file_a).await.unwrap(),
+        "alpha\nbeta\n"
+    );
+
+    service
+        .apply_edits_batch(
+            vec![
+                (file_a.clone(), vec![edit("beta", "BETA")]),
+                (file_b.clone(), vec![edit("delta", "DELTA")]),
+            ],
+            false,
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        tokio_fs::read_to_string(&file_a).await.unwrap(),
+        "alpha\nBETA\n"
+    );
+    // original line endings are kept
+    assert_eq!(
+        tokio_fs::read_to_string(&file_b).await.unwrap(),
+        "gamma\r\nDELTA\r\n"
+    );
+    // no staged file is left behind
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+}
+
+#[tokio::test]
+async fn test_apply_file_edits_line_range() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_temp_file(
+        temp_dir.join("dir1").as_path(),
+        "test.txt",
+        "line1\r\nline2\r\nline3\r\nline4\r\n",
+    );
+    let range = |start: u64, end: u64, new: &str, hash: Option<String>| EditOperation {
+        new_text: new.to_string(),
+        start_line: Some(start),
+        end_line: Some(end),
+        range_sha256: hash,
+        ..Default::default()
+    };
+
+    let result = service
+        .apply_file_edits(
+            &file_path,
+            vec![range(2, 3, "two\nthree", Some(sha256_hex(b"line1")))],
+            Some(false),
+            None,
+            false,
+            None,
+        )
+        .await;
+    assert!(matches!(result, Err(ServiceError::Conflict(_))));
+    let result = service
+        .apply_file_edits(
+            &file_path,
+            vec![range(4, 5, "", None)],
+            Some(false),
+            None,
+            false,
+            None,
+        )
+        .await;
+    assert!(result.is_err());
+
+    let diff = service
+        .apply_file_edits(
+            &file_path,
+            vec![
+                range(2, 3, "two\nthree", Some(sha256_hex(b"line2\nline3"))),
+                // edits apply in order: line 4 is still line4
+                range(4, 4, "", None),
+            ],
+            Some(false),
+            None,
+            false,
+            None,
+        )
+        .await
+        .unwrap();
+    assert!(diff.diff.contains("+two"));
+    assert_eq!(
+        tokio_fs::read_to_string(&file_path).await.unwrap(),
+        "line1\r\ntwo\r\nthree\r\n"
+    );
+}
+
+#[tokio::test]
+async fn test_apply_file_edits_match_mode() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_temp_file(
+        temp_dir.join("dir1").as_path(),
+        "test.txt",
+        "fn main() {\n    let total = count(items);\n    println!(\"{total}\");\n}\n",
+    );
+    let edit = |old: &str, mode: MatchMode, threshold: Option<f64>| EditOperation {
+        old_text: old.to_string(),
+        new_text: "let total = items.len();".to_string(),
+        match_mode: Some(mode),
+        similarity_threshold: threshold,
+        ..Default::default()
+    };
+    let apply = |edit: EditOperation| {
+        service.apply_file_edits(&file_path, vec![edit], Some(true), None, false, None)
+    };
+
+    // surrounding whitespace only matches when not exact
+    let unindented = "let total = count(items);";
+    assert!(
+        apply(edit(unindented, MatchMode::Exact, None))
+            .await
+            .is_ok()
+    );
+    let indented = "  let total = count(items);  ";
+    assert!(apply(edit(indented, MatchMode::Exact, None)).await.is_err());
+    let result = apply(edit(indented, MatchMode::WhitespaceInsensitive, None))
+        .await
+        .unwrap();
+    assert!(result.diff.contains("whitespace-insensitive match"));
+
+    // a typo only matches fuzzily, above the threshold
+    let typo = "let totl = count(itms);";
+    assert!(
+        apply(edit(typo, MatchMode::WhitespaceInsensitive, None))
+            .await
+            .is_err()
+    );
+    let result = apply(edit(typo, MatchMode::Fuzzy, None)).await.unwrap();
+    assert!(result.diff.contains("fuzzy match, similarity 0.9"));
+    assert!(result.diff.contains("-    let total = count(items);"));
+    assert!(
+        apply(edit(typo, MatchMode::Fuzzy, Some(0.99)))
+            .await
+            .is_err()
+    );
+    assert!(
+        apply(edit(typo, MatchMode::Fuzzy, Some(1.5)))
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_apply_file_edits_summary() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let content: String = (1..=20).map(|i| format!("line{i}\n")).collect();
+    let file_path = create_temp_file(temp_dir.join("dir1").as_path(), "test.txt", &content);
+    let edit = |old: &str, new: &str| EditOperation {
+        old_text: old.to_string(),
+        new_text: new.to_string(),
+        ..Default::default()
+    };
+
+    let result = service
+        .apply_file_edits(
+            &file_path,
+            vec![
+                edit("line2\n", "two\n2\n"),
+                edit("line18\nline19\n", "nineteen\n"),
+            ],
+            Some(true),
+            None,
+            false,
+            None,
+        )
+        .await
+        .unwrap();
+    let summary = result.summary;
+    assert_eq!(summary.files_changed, 1);
+    assert_eq!(summary.lines_added, 3);
+    assert_eq!(summary.lines_removed, 3);
+    let file = &summary.files[0];
+    assert!(file.path.ends_with("test.txt"));
+    assert_eq!(
+        file.hunks,
+        vec![
+            DiffHunk {
+                old_start: 1,
+                old_lines: 6,
+                new_start: 1,
+                new_lines: 7,
+            },
+            DiffHunk {
+                old_start: 14,
+                old_lines: 7,
+                new_start: 15,
+                new_lines: 6,
+            },
+        ]
+    );
+
+    // a no-op edit changes no file
+    let result = service
+        .apply_edits_batch(vec![(file_path, vec![edit("line5", "line5")])], true)
+        .await
+        .unwrap();
+    assert_eq!(result.summary.files_changed, 0);
+    assert_eq!(result.summary.files.len(), 1);
+}
+
+#[test]
+fn test_format_system_time() {
+    let now = SystemTime::now();
+    let formatted = format_system_time(now);
+    // Check that the output matches the expected format (e.g., "Sat Apr 12 2025 14:30:45 +00:00")
+    assert!(formatted.contains("202")); // Year should appear
+    assert!(formatted.contains(":")); // Time should have colons
+    assert!(formatted.contains("+") || formatted.contains("-")); // Timezone offset
+}
+
+#[cfg(unix)]
+#[test]
+fn test_format_permissions_unix() {
+    use rust_mcp_filesystem::fs_service::utils::format_permissions;
+
+    let temp_dir = get_temp_dir();
+    let file_path = temp_dir.join("test.txt");
+    File::create(&file_path).unwrap();
+
+    // Set specific permissions (e.g., rw-r--r--)
+    fs::set_permissions(&file_path, fs::Permissions::from_mode(0o644)).unwrap();
+    let metadata = fs::metadata(&file_path).unwrap();
+    let formatted = format_permissions(&metadata);
+    assert_eq!(formatted, "0644");
+
+    // Test directory permissions
+    let dir_metadata = fs::metadata(temp_dir).unwrap();
+    let dir_formatted = format_permissions(&dir_metadata);
+    assert!(dir_formatted.starts_with("0")); // Should be octal
+}
+
+#[cfg(windows)]
+#[test]
+fn test_format_permissions_windows() {
+    let temp_dir = get_temp_dir();
+    let file_path = temp_dir.join("test.txt");
+    let mut file = File::create(&file_path).unwrap();
+    file.write_all(b"test").unwrap();
+    file.flush().unwrap();
+
+    // Set read-only
+    let mut perms = fs::metadata(&file_path).unwrap().permissions();
+    perms.set_readonly(true);
+    fs::set_permissions(&file_path, perms).unwrap();
+
+    let metadata = fs::metadata(&file_path).unwrap();
+    let formatted = format_permissions(&metadata);
+    assert_eq!(formatted, "-r"); // Regular file, read-only
+
+    // Test directory
+    let dir_metadata = fs::metadata(temp_dir).unwrap();
+    let dir_formatted = format_permissions(&dir_metadata);
+    assert_eq!(dir_formatted, "dw"); // Directory, typically writable
+}
+
+#[test]
+fn test_normalize_path() {
+    let temp_dir = get_temp_dir();
+    let file_path = temp_dir.join("test.txt");
+    File::create(&file_path).unwrap();
+
+    let normalized = normalize_path(&file_path);
+    assert_eq!(normalized, file_path);
+
+    // Test non-existent path
+    let non_existent = Path::new("/does/not/exist");
+    let normalized_non_existent = normalize_path(non_existent);
+    assert_eq!(normalized_non_existent, non_existent.to_path_buf());
+}
+
+#[test]
+fn test_expand_home() {
+    // Test with ~ path
+    let home_path = PathBuf::from("~/test");
+    let expanded = expand_home(home_path.clone());
+    if let Some(home) = home_dir() {
+        assert_eq!(expanded, home.join("test"));
+    } else {
+        assert_eq!(expanded, home_path); // No home dir, return original
+    }
+
+    // Test non-~ path
+    let regular_path = PathBuf::from("/absolute/path");
+    let expanded_regular = expand_home(regular_path.clone());
+    assert_eq!(expanded_regular, regular_path);
+}
+
+#[test]
+fn test_format_bytes() {
+    assert_eq!(format_bytes(500), "500 bytes");
+    assert_eq!(format_bytes(1024), "1.00 KB");
+    assert_eq!(format_bytes(1500), "1.46 KB");
+    assert_eq!(format_bytes(1024 * 1024), "1.00 MB");
+    assert_eq!(format_bytes(1024 * 1024 * 1024), "1.00 GB");
+    assert_eq!(format_bytes(1024 * 1024 * 1024 * 1024), "1.00 TB");
+    assert_eq!(format_bytes(1500 * 1024 * 1024), "1.46 GB");
+}
+
+#[test]
+fn test_file_info_for_regular_file() {
+    let (_dir, file_info) = create_temp_file_info(b"Hello, world!");
+    assert_eq!(file_info.size, 13); // "Hello, world!" is 13 bytes
+    assert!(file_info.is_file);
+    assert!(!file_info.is_directory);
+    assert!(file_info.created.is_some());
+    assert!(file_info.modified.is_some());
+    assert!(file_info.accessed.is_some());
+}
+
+#[test]
+fn test_file_info_for_directory() {
+    let (_dir, file_info) = create_temp_dir();
+    assert!(file_info.is_directory);
+    assert!(!file_info.is_file);
+    assert!(file_info.created.is_some());
+    assert!(file_info.modified.is_some());
+    assert!(file_info.accessed.is_some());
+}
+
+#[test]
+fn test_display_format_for_file() {
+    let (_dir, file_info) = create_temp_file_info(b"Test content");
+    let display_output = file_info.to_string();
+
+    // Since permissions and exact times may vary, we just checking the key parts
+    assert!(display_output.contains("size: 12"));
+    assert!(display_output.contains("isDirectory: false"));
+    assert!(display_output.contains("isFile: true"));
+    assert!(display_output.contains("created:"));
+    assert!(display_output.contains("modified:"));
+    assert!(display_output.contains("accessed:"));
+    assert!(display_output.contains("permissions:"));
+}
+
+#[test]
+fn test_display_format_for_empty_timestamps() {
+    // Create a FileInfo with no timestamps
+    let metadata = fs::metadata(".").unwrap();
+    let file_info = FileInfo {
+        size: 123,
+        created: None,
+        modified: None,
+        accessed: None,
+        is_directory: false,
+        is_file: true,
+        is_symlink: false,
+        symlink_target: None,
+        mime_type: None,
+        uid: None,
+        gid: None,
+        user: None,
+        group: None,
+        inode: None,
+        device: None,
+        hard_links: None,
+        metadata: metadata.clone(),
+    };
+
+    let display_output = file_info.to_string();
+
+    // Only key parts
+    assert!(display_output.contains("size: 123"));
+    assert!(display_output.contains("created: \n"));
+    assert!(display_output.contains("modified: \n"));
+    assert!(display_output.contains("accessed: \n"));
+    assert!(display_output.contains("isDirectory: false"));
+    assert!(display_output.contains("isFile: true"));
+}
+
+#[tokio::test]
+async fn test_apply_file_edits_mixed_indentation() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_temp_file(
+        temp_dir.join("dir1").as_path(),
+        "test_indent.txt",
+        r#"
+            // some descriptions
+			const categories = [
+				{
+					title: 'Подготовка и исследование',
+					keywords: ['изуч', 'исследов', 'анализ', 'подготов', 'планиров'],
+					tasks: [] as any[]
+				},
+			];
+		// some other descriptions
+        "#,
+    );
+    // different indentation
+    let edits = vec![EditOperation {
+        old_text: r#"const categories = [
+				{
+					title: 'Подготовка и исследование',
+						keywords: ['изуч', 'исследов', 'анализ', 'подготов', 'планиров'],
+					tasks: [] as any[]
+				},
+			];"#
+        .to_string(),
+        new_text: r#"const categories = [
+				{
+					title: 'Подготовка и исследование',
+					description: 'Анализ требований и подготовка к разработке',
+					keywords: ['изуч', 'исследов', 'анализ', 'подготов', 'планиров'],
+					tasks: [] as any[]
+				},
+			];"#
+        .to_string(),
+        ..Default::default()
+    }];
+
+    let out_file = temp_dir.join("dir1").join("out_indent.txt");
+
+    let result = service
+        .apply_file_edits(
+            &file_path,
+            edits,
+            Some(false),
+            Some(out_file.as_path()),
+            false,
+            None,
+        )
+        .await;
+
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn test_apply_file_edits_mixed_indentation_2() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_temp_file(
+        temp_dir.join("dir1").as_path(),
+        "test_indent.txt",
+        r#"
+            // some descriptions
+			const categories = [
+				{
+					title: 'Подготовка и исследование',
+					keywords: ['изуч', 'исследов', 'анализ', 'подготов', 'планиров'],
+					tasks: [] as any[]
+				},
+			];
+		// some other descriptions
+        "#,
+    );
+    // different indentation
+    let edits = vec![EditOperation {
+        old_text: r#"const categories = [
+				{
+					title: 'Подготовка и исследование',
+			keywords: ['изуч', 'исследов', 'анализ', 'подготов', 'планиров'],
+					tasks: [] as any[]
+				},
+			];"#
+        .to_string(),
+        new_text: r#"const categories = [
+				{
+					title: 'Подготовка и исследование',
+					description: 'Анализ требований и подготовка к разработке',
+					keywords: ['изуч', 'исследов', 'анализ', 'подготов', 'планиров'],
+					tasks: [] as any[]
+				},
+			];"#
+        .to_string(),
+        ..Default::default()
+    }];
+
+    let out_file = temp_dir.join("dir1").join("out_indent.txt");
+
+    let result = service
+        .apply_file_edits(
+            &file_path,
+            edits,
+            Some(false),
+            Some(out_file.as_path()),
+            false,
+            None,
+        )
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn test_exact_match() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+
+    let file = create_temp_file(
+        &temp_dir.as_path().join("dir1"),
+        "tets_file1.txt",
+        "hello world\n",
+    );
+
+    let edit = EditOperation {
+        old_text: "hello world".to_string(),
+        new_text: "hello universe".to_string(),
+        ..Default::default()
+    };
+
+    let result = service
+        .apply_file_edits(file.as_path(), vec![edit], Some(false), None, false, None)
+        .await
+        .unwrap();
+
+    let modified_content = fs::read_to_string(file.as_path()).unwrap();
+    assert_eq!(modified_content, "hello universe\n");
+    assert!(result.diff.contains("-hello world\n+hello universe"));
+}
+
+#[tokio::test]
+async fn test_exact_match_edit2() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file = create_temp_file(
+        &temp_dir.as_path().join("dir1"),
+        "test_file1.txt",
+        "hello world\n",
+    );
+
+    let edits = vec![EditOperation {
+        old_text: "hello world\n".into(),
+        new_text: "hello Rust\n".into(),
+        ..Default::default()
+    }];
+
+    let result = service
+        .apply_file_edits(&file, edits, Some(false), None, false, None)
+        .await;
+
+    assert!(result.is_ok());
+    let updated_content = fs::read_to_string(&file).unwrap();
+    assert_eq!(updated_content, "hello Rust\n");
+}
+
+#[tokio::test]
+async fn test_line_by_line_match_with_indent() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file = create_temp_file(
+        &temp_dir.as_path().join("dir1"),
+        "test_file2.rs",
+        "    let x = 42;\n    println!(\"{}\");\n",
+    );
+
+    let edits = vec![EditOperation {
+        old_text: "let x = 42;\nprintln!(\"{}\");\n".into(),
+        new_text: "let x = 43;\nprintln!(\"x = {}\", x)".into(),
+        ..Default::default()
+    }];
+
+    let result = service
+        .apply_file_edits(&file, edits, Some(false), None, false, None)
+        .await;
+
+    assert!(result.is_ok());
+
+    let content = fs::read_to_string(&file).unwrap();
+    assert!(content.contains("let x = 43;"));
+    assert!(content.contains("println!(\"x = {}\", x)"));
+}
+
+#[tokio::test]
+async fn test_dry_run_mode() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file = create_temp_file(
+        &temp_dir.as_path().join("dir1"),
+        "test_file4.sh",
+        "echo hello\n",
+    );
+
+    let edits = vec![EditOperation {
+        old_text: "echo hello\n".into(),
+        new_text: "echo world\n".into(),
+        ..Default::default()
+    }];
+
+    let result = service
+        .apply_file_edits(&file, edits, Some(true), None, false, None)
+        .await;
+    assert!(result.is_ok());
+
+    let content = fs::read_to_string(&file).unwrap();
+    assert_eq!(content, "echo hello\n"); // Should not be modified
+}
+
+#[tokio::test]
+async fn test_save_to_different_path() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let orig_file = create_temp_file(
+        &temp_dir.as_path().join("dir1"),
+        "test_file5.txt",
+        "foo = 1\n",
+    );
+
+    let save_to = temp_dir.as_path().join("dir1").join("saved_output.txt");
+
+    let edits = vec![EditOperation {
+        old_text: "foo = 1\n".into(),
+        new_text: "foo = 2\n".into(),
+        ..Default::default()
+    }];
+
+    let result = service
+        .apply_file_edits(&orig_file, edits, Some(false), Some(&save_to), false, None)
+        .await;
+
+    assert!(result.is_ok());
+
+    let original_content = fs::read_to_string(&orig_file).unwrap();
+    let saved_content = fs::read_to_string(&save_to).unwrap();
+    assert_eq!(original_content, "foo = 1\n");
+    assert_eq!(saved_content, "foo = 2\n");
+}
+
+#[tokio::test]
+async fn test_diff_backtick_formatting() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file = create_temp_file(
+        &temp_dir.as_path().join("dir1"),
+        "test_file6.md",
+        "```\nhello\n```\n",
+    );
+
+    let edits = vec![EditOperation {
+        old_text: "```\nhello\n```".into(),
+        new_text: "```\nworld\n```".into(),
+        ..Default::default()
+    }];
+
+    let result = service
+        .apply_file_edits(&file, edits, Some(true), None, false, None)
+        .await;
+    assert!(result.is_ok());
+
+    let diff = result.unwrap();
+    assert!(diff.diff.contains("diff"));
+    assert!(diff.diff.starts_with("```")); // Should start with fenced backticks
+}
+
+#[tokio::test]
+async fn test_no_edits_provided() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file = create_temp_file(
+        &temp_dir.as_path().join("dir1"),
+        "test_file7.toml",
+        "enabled = true\n",
+    );
+
+    let result = service
+        .apply_file_edits(&file, vec![], Some(false), None, false, None)
+        .await;
+    assert!(result.is_ok());
+
+    let content = fs::read_to_string(&file).unwrap();
+    assert_eq!(content, "enabled = true\n");
+}
+
+#[tokio::test]
+async fn test_preserve_windows_line_endings() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file = create_temp_file(
+        &temp_dir.as_path().join("dir1"),
+        "test_file.txt",
+        "line1\r\nline2\r\n",
+    );
+
+    let edits = vec![EditOperation {
+        old_text: "line1\nline2".into(), // normalized format
+        new_text: "updated1\nupdated2".into(),
+        ..Default::default()
+    }];
+
+    let result = service
+        .apply_file_edits(&file, edits, Some(false), None, false, None)
+        .await;
+    assert!(result.is_ok());
+
+    let output = std::fs::read_to_string(&file).unwrap();
+    assert_eq!(output, "updated1\r\nupdated2\r\n"); // Line endings preserved!
+}
+
+#[tokio::test]
+async fn test_preserve_unix_line_endings() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file = create_temp_file(
+        &temp_dir.as_path().join("dir1"),
+        "unix_line_file.txt",
+        "line1\nline2\n",
+    );
+
+    let edits = vec![EditOperation {
+        old_text: "line1\nline2".into(),
+        new_text: "updated1\nupdated2".into(),
+        ..Default::default()
+    }];
+
+    let result = service
+        .apply_file_edits(&file, edits, Some(false), None, false, None)
+        .await;
+
+    assert!(result.is_ok());
+
+    let updated = std::fs::read_to_string(&file).unwrap();
+    assert_eq!(updated, "updated1\nupdated2\n"); // Still uses \n endings
+}
+
+#[tokio::test]
+// Issue #19: https://github.com/rust-mcp-stack/rust-mcp-filesystem/issues/19
+async fn test_panic_on_out_of_bounds_edit() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+
+    // Set up an edit that expects to match 5 lines
+    let edit = EditOperation {
+        old_text: "line e\n".repeat(41).to_string(),
+        new_text: "replaced content".to_string(),
+        ..Default::default()
+    };
+
+    // Set up your file content with only 2 lines
+    let file_content = "line A\nline B\n";
+    let test_path = create_temp_file(
+        &temp_dir.as_path().join("dir1"),
+        "test_input.txt",
+        file_content,
+    );
+
+    let result = service
+        .apply_file_edits(&test_path, vec![edit], Some(true), None, false, None)
+        .await;
+
+    // It should panic without the fix, or return an error after applying the fix
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_content_search() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir_search".to_string()]);
+    let file = create_temp_file(
+        &temp_dir.as_path().join("dir_search"),
+        "file_to_search.txt",
+        r#"For the Doctor Watsons of this world, as opposed to the Sherlock
+        Holmeses, success in the province of detective work must always
+        be, to a very large extent, the result of luck. Sherlock Holmes
+        can extract a clew from a wisp of straw or a flake of cigar ash;
+        but Doctor Watso2n has to have it taken out for him and dusted,
+        and exhibited clearly, with Watso\d*n a label attached."#,
+    );
+
+    let query = r#"Watso\d*n"#;
+
+    // search as regex
+    let result = service.content_search(query, &file, Some(true)).unwrap();
+
+    assert!(result.is_some());
+    let result = result.unwrap();
+
+    assert_eq!(result.file_path, file);
+    assert_eq!(result.matches.len(), 2);
+    assert_eq!(result.matches[0].line_number, 1);
+    assert_eq!(result.matches[1].line_number, 5);
+    assert_eq!(
+        result.matches[0].line_text.trim(),
+        "For the Doctor Watsons of this world, as opposed to the Sherlock"
+    );
+    assert_eq!(
+        result.matches[1].line_text.trim(),
+        "but Doctor Watso2n has to have it taken out for him and dusted,"
+    );
+
+    // search as literal
+    let result = service.content_search(query, &file, Some(false)).unwrap();
+    assert!(result.is_some());
+    let result = result.unwrap();
+    assert_eq!(result.matches.len(), 1);
+    assert_eq!(result.matches[0].line_number, 6);
+    assert_eq!(
+        result.matches[0].line_text.trim(),
+        "and exhibited clearly, with Watso\\d*n a label attached."
+    );
+}
+
+#[test]
+fn test_match_near_start_short_line() {
+    let (_, service, _) = setup_service(vec!["dir_search".to_string()]);
+
+    let line = "match this text";
+    let m = Match::new(0, 5);
+    let result = service.extract_snippet(line, m, Some(20), Some(5));
+
+    // Start at 0, should not prepend ...
+    // Full line is shorter than SNIPPET_MAX_LENGTH
+    assert_eq!(result, "match this text");
+}
+
+#[tokio::test]
+async fn test_snippet_back_chars() {
+    let (_, service, _) = setup_service(vec!["dir_search".to_string()]);
+    let line = "this is a long enough line for testing match in middle";
+    let m = Match::new(40, 45);
+    let result = service.extract_snippet(line, m, Some(20), Some(5));
+
+    assert!(result.starts_with("..."));
+    assert!(!result.ends_with("..."));
+    assert!(result.contains("match"));
+
+    // larger text, truncates at the end
+    let line = "this is a long enough line for testing match in middles .";
+    let m = Match::new(40, 45);
+    let result = service.extract_snippet(line, m, Some(20), Some(5));
+    assert!(result.starts_with("..."));
+    assert!(result.ends_with("..."));
+    assert!(result.contains("match"));
+}
+
+#[test]
+fn test_match_triggers_only_end_ellipsis() {
+    let (_, service, _) = setup_service(vec!["dir_search".to_string()]);
+
+    let line = "match is at start but line is long";
+    let m = Match::new(0, 5);
+
+    let result = service.extract_snippet(line, m, Some(10), Some(5));
+
+    // Only ends in ellipsis
+    assert!(!result.starts_with("..."));
+    assert!(result.ends_with("..."));
+}
+
+#[test]
+fn test_match_triggers_only_start_ellipsis() {
+    let (_, service, _) = setup_service(vec!["dir_search".to_string()]);
+
+    let line = "line is long and match is near end";
+    let m = Match::new(31, 36);
+    let result = service.extract_snippet(line, m, Some(10), Some(5));
+    // Only starts with ellipsis
+    assert!(result.starts_with("..."));
+    assert!(!result.ends_with("..."));
+}
+
+#[test]
+fn test_trim_applied() {
+    let (_, service, _) = setup_service(vec!["dir_search".to_string()]);
+
+    let line = "     match here with spaces    ";
+    let m = Match::new(5, 10);
+
+    let result = service.extract_snippet(line, m, Some(10), Some(5));
+
+    // Ensure whitespace is trimmed before slicing
+    assert!(!result.contains("     "));
+    assert!(result.contains("match"));
+}
+
+#[test]
+fn test_exact_snippet_end() {
+    let (_, service, _allowed_dirs) = setup_service(vec!["dir_search".to_string()]);
+    let line = "some content with match inside";
+    let m = Match::new(18, 23);
+    let result = service.extract_snippet(line, m, Some(line.len()), Some(18));
+    // Full trimmed line, no ellipses
+    assert_eq!(result, "some content with match inside");
+}
+
+#[tokio::test]
+async fn search_files_content() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir_search".to_string()]);
+
+    create_temp_file(
+        &temp_dir.as_path().join("dir_search"),
+        "file1.txt",
+        r#"For the Doctor Watsons of this world, as opposed to the Sherlock
+        Holmeses, success in the province of detective work must always
+        be, to a very large extent, the result of luck. Sherlock Holmes
+        can extract a clew from a wisp of straw or a flake of cigar ash;
+        but Doctor Watson has to have it taken out for him and dusted,
+        and exhibited clearly, with Watso2n a label attached."#,
+    );
+    create_temp_file(
+        &temp_dir.as_path().join("dir_search"),
+        "file2.txt",
+        r#"For the Doctor Watsons of this world, as opposed to the Sherlock
+        Holmeses, success in the province of detective work must always
+        be, to a very large extent, the result of luck. Sherlock Holmes
+        can extract a clew from a wisp of straw or a flake of cigar ash;
+        but Doctor Watson has to have it taken out for him and dusted,
+        and exhibited clearly, with Watso2n a label attached."#,
+    );
+
+    let query = r#"Watso\d*n"#;
+
+    let results = service
+        .search_files_content(
+            temp_dir.as_path().join("dir_search"),
+            "*.txt",
+            query,
+            true,
+            None, // exclude_patterns
+            None, // min_bytes
+            None,
+            WalkOptions::default(), // max_bytes
+            &OperationContext::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 2);
+    // Each file should have 3 matches: "Watsons", "Watson", "Watso2n"
+    assert_eq!(results[0].matches.len(), 3);
+    assert_eq!(results[1].matches.len(), 3);
+}
+
+#[tokio::test]
+async fn search_files_content_respects_excludes() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir_search".to_string()]);
+    let dir = temp_dir.as_path().join("dir_search");
+
+    create_temp_file(&dir, "keep.txt", "Sherlock Holmes finds clues.");
+    create_temp_file(&dir, "skip.txt", "Sherlock Holmes overlooks this clue.");
+
+    let results = service
+        .search_files_content(
+            dir.clone(),
+            "*.txt",
+            "Holmes",
+            false,
+            Some(vec!["skip.txt".to_string()]),
+            None,
+            None,
+            WalkOptions::default(),
+            &OperationContext::default(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(results.len(), 1);
+    assert!(results[0].file_path.to_string_lossy().ends_with("keep.txt"));
+    assert_eq!(results[0].matches.len(), 1);
+}
+
+#[tokio::test]
+async fn test_head_file_normal() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_test_file_with_line_ending(
+        &temp_dir,
+        "dir1/test.txt",
+        vec!["line1", "line2", "line3", "line4", "line5"],
+        "\n",
+    )
+    .await;
+
+    let result = service
+        .read_file_lines(&file_path, 0, Some(3), false)
+        .await
+        .unwrap();
+    assert_eq!(result, "line1\nline2\nline3\n");
+}
+
+#[tokio::test]
+async fn test_head_file_empty_file() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path =
+        create_test_file_with_line_ending(&temp_dir, "dir1/empty.txt", vec![], "\n").await;
+
+    let result = service
+        .read_file_lines(&file_path, 0, Some(5), false)
+        .await
+        .unwrap();
+    assert_eq!(result, "");
+}
+
+#[tokio::test]
+async fn test_head_file_n_zero() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_test_file_with_line_ending(
+        &temp_dir,
+        "dir1/test.txt",
+        vec!["line1", "line2", "line3"],
+        "\n",
+    )
+    .await;
+
+    let result = service
+        .read_file_lines(&file_path, 0, Some(0), false)
+        .await
+        .unwrap();
+    assert_eq!(result, "");
+}
+
+#[tokio::test]
+async fn test_head_file_n_larger_than_file() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path =
+        create_test_file_with_line_ending(&temp_dir, "dir1/test.txt", vec!["line1", "line2"], "\n")
+            .await;
+
+    let result = service
+        .read_file_lines(&file_path, 0, Some(5), false)
+        .await
+        .unwrap();
+    assert_eq!(result, "line1\nline2");
+}
+
+#[tokio::test]
+async fn test_head_file_no_trailing_newline() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    // Create file without trailing newline
+    let file_path = temp_dir.join("dir1/test.txt");
+    tokio::fs::create_dir_all(file_path.parent().unwrap())
+        .await
+        .unwrap();
+    let mut file = File::create(&file_path).unwrap();
+    file.write_all(b"line1\nline2\nline3").unwrap();
+
+    let result = service
+        .read_file_lines(&file_path, 0, Some(3), false)
+        .await
+        .unwrap();
+    assert_eq!(result, "line1\nline2\nline3");
+}
+
+#[tokio::test]
+async fn test_head_file_single_line() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path =
+        create_test_file_with_line_ending(&temp_dir, "dir1/test.txt", vec!["line1"], "\n").await;
+
+    let result = service
+        .read_file_lines(&file_path, 0, Some(1), false)
+        .await
+        .unwrap();
+    assert_eq!(result, "line1");
+}
+
+#[tokio::test]
+async fn test_head_file_windows_line_endings() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_test_file_with_line_ending(
+        &temp_dir,
+        "dir1/test.txt",
+        vec!["line1", "line2", "line3"],
+        "\r\n",
+    )
+    .await;
+
+    let result = service
+        .read_file_lines(&file_path, 0, Some(2), false)
+        .await
+        .unwrap();
+    assert_eq!(result, "line1\r\nline2\r\n");
+}
+
+#[tokio::test]
+async fn test_head_file_invalid_path() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let invalid_path = temp_dir.join("dir2/test.txt"); // Outside allowed_dirs
+
+    let result = service
+        .read_file_lines(&invalid_path, 0, Some(3), false)
+        .await;
+    assert!(result.is_err(), "Expected error for invalid path");
+}
+
+#[tokio::test]
+async fn test_tail_file_normal() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_test_file_with_line_ending(
+        &temp_dir.to_path_buf(),
+        "dir1/test.txt",
+        vec!["line1", "line2", "line3", "line4", "line5"],
+        "\n",
+    )
+    .await;
+
+    let result = service
+        .read_file_lines(&file_path, 0, Some(3), true)
+        .await
+        .unwrap();
+    assert_eq!(result, "line3\nline4\nline5"); // No trailing newline
+}
+
+#[tokio::test]
+async fn test_tail_file_empty_file() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path =
+        create_test_file_with_line_ending(&temp_dir.to_path_buf(), "dir1/empty.txt", vec![], "\n")
+            .await;
+
+    let result = service
+        .read_file_lines(&file_path, 0, Some(5), true)
+        .await
+        .unwrap();
+    assert_eq!(result, "");
+}
+
+#[tokio::test]
+async fn test_tail_file_n_zero() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_test_file_with_line_ending(
+        &temp_dir.to_path_buf(),
+        "dir1/test.txt",
+        vec!["line1", "line2", "line3"],
+        "\n",
+    )
+    .await;
+
+    let result = service
+        .read_file_lines(&file_path, 0, Some(0), true)
+        .await
+        .unwrap();
+    assert_eq!(result, "");
+}
+
+#[tokio::test]
+async fn test_tail_file_n_larger_than_file() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_test_file_with_line_ending(
+        &temp_dir.to_path_buf(),
+        "dir1/test.txt",
+        vec!["line1", "line2"],
+        "\n",
+    )
+    .await;
+
+    let result = service
+        .read_file_lines(&file_path, 0, Some(5), true)
+        .await
+        .unwrap();
+    assert_eq!(result, "line1\nline2"); // No trailing newline
+}
+
+#[tokio::test]
+async fn test_tail_file_no_newline_at_end() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_temp_file(
+        &temp_dir.join("dir1"),
+        "test.txt",
+        "line1\nline2\nline3", // No newline at end
+    );
+    println!(">>>  {file_path:?} ");
 
-    // Test non-existent path
-    let non_existent = Path::new("/does/not/exist");
-    let normalized_non_existent = normalize_path(non_existent);
-    assert_eq!(normalized_non_existent, non_existent.to_path_buf());
+    let result = service
+        .read_file_lines(&file_path, 0, Some(2), true)
+        .await
+        .unwrap();
+    assert_eq!(result, "line2\nline3");
 }
 
-#[test]
-fn test_expand_home() {
-    // Test with ~ path
-    let home_path = PathBuf::from("~/test");
-    let expanded = expand_home(home_path.clone());
-    if let Some(home) = home_dir() {
-        assert_eq!(expanded, home.join("test"));
-    } else {
-        assert_eq!(expanded, home_path); // No home dir, return original
-    }
+#[tokio::test]
+async fn test_tail_file_single_line() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_test_file_with_line_ending(
+        &temp_dir.to_path_buf(),
+        "dir1/test.txt",
+        vec!["line1"],
+        "\n",
+    )
+    .await;
 
-    // Test non-~ path
-    let regular_path = PathBuf::from("/absolute/path");
-    let expanded_regular = expand_home(regular_path.clone());
-    assert_eq!(expanded_regular, regular_path);
+    let result = service
+        .read_file_lines(&file_path, 0, Some(1), true)
+        .await
+        .unwrap();
+    assert_eq!(result, "line1"); // No trailing newline
 }
 
-#[test]
-fn test_format_bytes() {
-    assert_eq!(format_bytes(500), "500 bytes");
-    assert_eq!(format_bytes(1024), "1.00 KB");
-    assert_eq!(format_bytes(1500), "1.46 KB");
-    assert_eq!(format_bytes(1024 * 1024), "1.00 MB");
-    assert_eq!(format_bytes(1024 * 1024 * 1024), "1.00 GB");
-    assert_eq!(format_bytes(1024 * 1024 * 1024 * 1024), "1.00 TB");
-    assert_eq!(format_bytes(1500 * 1024 * 1024), "1.46 GB");
+#[tokio::test]
+async fn test_tail_file_windows_line_endings() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_test_file_with_line_ending(
+        &temp_dir.to_path_buf(),
+        "dir1/test.txt",
+        vec!["line1", "line2", "line3"],
+        "\r\n",
+    )
+    .await;
+
+    let result = service
+        .read_file_lines(&file_path, 0, Some(2), true)
+        .await
+        .unwrap();
+    assert_eq!(result, "line2\r\nline3"); // No trailing newline
 }
 
 #[tokio::test]
-async fn test_write_zip_entry() {
-    let temp_dir = get_temp_dir();
-    let input_path = temp_dir.join("input.txt");
-    let zip_path = temp_dir.join("output.zip");
+async fn test_tail_file_invalid_path() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let invalid_path = temp_dir.join("dir2/test.txt"); // Outside allowed_dirs
 
-    // Create a test file
-    let content = b"Hello, zip!";
-    let mut input_file = File::create(&input_path).unwrap();
-    input_file.write_all(content).unwrap();
-    input_file.flush().unwrap();
+    let result = service
+        .read_file_lines(&invalid_path, 0, Some(3), true)
+        .await;
+    assert!(result.is_err(), "Expected error for invalid path");
+}
 
-    // Create zip file
-    let zip_file = tokio::fs::File::create(&zip_path).await.unwrap();
-    let mut zip_writer = ZipFileWriter::new(zip_file.compat());
+#[tokio::test]
+async fn test_read_file_lines_normal() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_test_file(
+        &temp_dir,
+        "dir1/test.txt",
+        vec!["line1", "line2", "line3", "line4", "line5"],
+    )
+    .await;
 
-    // Write zip entry
-    let result = write_zip_entry("test.txt", &input_path, &mut zip_writer).await;
-    assert!(result.is_ok());
+    let result = service
+        .read_file_lines(&file_path, 1, Some(2), false)
+        .await
+        .unwrap();
+    assert_eq!(result, "line2\nline3\n"); // No trailing newline
+}
 
-    // Close the zip writer
-    zip_writer.close().await.unwrap();
+#[tokio::test]
+async fn test_read_file_lines_empty_file() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_test_file(&temp_dir, "dir1/empty.txt", vec![]).await;
 
-    // Verify the zip file exists and has content
-    let zip_metadata = fs::metadata(&zip_path).unwrap();
-    assert!(zip_metadata.len() > 0);
+    let result = service
+        .read_file_lines(&file_path, 0, Some(5), false)
+        .await
+        .unwrap();
+    assert_eq!(result, "");
 }
 
 #[tokio::test]
-async fn test_write_zip_entry_non_existent_file() {
-    let temp_dir = get_temp_dir();
-    let zip_path = temp_dir.join("output.zip");
-    let non_existent_path = temp_dir.join("does_not_exist.txt");
-
-    let zip_file = tokio::fs::File::create(&zip_path).await.unwrap();
-    let mut zip_writer = ZipFileWriter::new(zip_file.compat());
+async fn test_read_file_lines_offset_beyond_file() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_test_file(&temp_dir, "dir1/test.txt", vec!["line1", "line2"]).await;
 
-    let result = write_zip_entry("test.txt", &non_existent_path, &mut zip_writer).await;
-    assert!(result.is_err());
+    let result = service
+        .read_file_lines(&file_path, 5, Some(3), false)
+        .await
+        .unwrap();
+    assert_eq!(result, "");
 }
 
-#[test]
-fn test_file_info_for_regular_file() {
-    let (_dir, file_info) = create_temp_file_info(b"Hello, world!");
-    assert_eq!(file_info.size, 13); // "Hello, world!" is 13 bytes
-    assert!(file_info.is_file);
-    assert!(!file_info.is_directory);
-    assert!(file_info.created.is_some());
-    assert!(file_info.modified.is_some());
-    assert!(file_info.accessed.is_some());
-}
+#[tokio::test]
+async fn test_read_file_lines_no_limit() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_test_file(
+        &temp_dir,
+        "dir1/test.txt",
+        vec!["line1", "line2", "line3", "line4"],
+    )
+    .await;
 
-#[test]
-fn test_file_info_for_directory() {
-    let (_dir, file_info) = create_temp_dir();
-    assert!(file_info.is_directory);
-    assert!(!file_info.is_file);
-    assert!(file_info.created.is_some());
-    assert!(file_info.modified.is_some());
-    assert!(file_info.accessed.is_some());
+    let result = service
+        .read_file_lines(&file_path, 2, None, false)
+        .await
+        .unwrap();
+    assert_eq!(result, "line3\nline4"); // No trailing newline
 }
 
-#[test]
-fn test_display_format_for_file() {
-    let (_dir, file_info) = create_temp_file_info(b"Test content");
-    let display_output = file_info.to_string();
+#[tokio::test]
+async fn test_read_file_lines_limit_zero() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path =
+        create_test_file(&temp_dir, "dir1/test.txt", vec!["line1", "line2", "line3"]).await;
 
-    // Since permissions and exact times may vary, we just checking the key parts
-    assert!(display_output.contains("size: 12"));
-    assert!(display_output.contains("isDirectory: false"));
-    assert!(display_output.contains("isFile: true"));
-    assert!(display_output.contains("created:"));
-    assert!(display_output.contains("modified:"));
-    assert!(display_output.contains("accessed:"));
-    assert!(display_output.contains("permissions:"));
+    let result = service
+        .read_file_lines(&file_path, 1, Some(0), false)
+        .await
+        .unwrap();
+    assert_eq!(result, "");
 }
 
-#[test]
-fn test_display_format_for_empty_timestamps() {
-    // Create a FileInfo with no timestamps
-    let metadata = fs::metadata(".").unwrap();
-    let file_info = FileInfo {
-        size: 123,
-        created: None,
-        modified: None,
-        accessed: None,
-        is_directory: false,
-        is_file: true,
-        metadata: metadata.clone(),
-    };
+#[tokio::test]
+async fn test_read_file_lines_exact_file_length() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path =
+        create_test_file(&temp_dir, "dir1/test.txt", vec!["line1", "line2", "line3"]).await;
 
-    let display_output = file_info.to_string();
+    let result = service
+        .read_file_lines(&file_path, 0, Some(3), false)
+        .await
+        .unwrap();
+    assert_eq!(result, "line1\nline2\nline3"); // No trailing newline
+}
 
-    // Only key parts
-    assert!(display_output.contains("size: 123"));
-    assert!(display_output.contains("created: \n"));
-    assert!(display_output.contains("modified: \n"));
-    assert!(display_output.contains("accessed: \n"));
-    assert!(display_output.contains("isDirectory: false"));
-    assert!(display_output.contains("isFile: true"));
+#[tokio::test]
+async fn test_read_file_lines_no_newline_at_end() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_temp_file(
+        &temp_dir.join("dir1"),
+        "test.txt",
+        "line1\nline2\nline3", // No newline at end
+    );
+
+    let result = service
+        .read_file_lines(&file_path, 1, Some(2), false)
+        .await
+        .unwrap();
+    assert_eq!(result, "line2\nline3"); // No trailing newline
 }
 
 #[tokio::test]
-async fn test_apply_file_edits_mixed_indentation() {
+async fn test_read_file_lines_windows_line_endings() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+
+    // Override to use \r\n explicitly
     let file_path = create_temp_file(
-        temp_dir.join("dir1").as_path(),
-        "test_indent.txt",
-        r#"
-            // some descriptions
-			const categories = [
-				{
-					title: 'Подготовка и исследование',
-					keywords: ['изуч', 'исследов', 'анализ', 'подготов', 'планиров'],
-					tasks: [] as any[]
-				},
-			];
-		// some other descriptions
-        "#,
+        &temp_dir.join("dir1"),
+        "test.txt",
+        "line1\r\nline2\r\nline3",
     );
-    // different indentation
-    let edits = vec![EditOperation {
-        old_text: r#"const categories = [
-				{
-					title: 'Подготовка и исследование',
-						keywords: ['изуч', 'исследов', 'анализ', 'подготов', 'планиров'],
-					tasks: [] as any[]
-				},
-			];"#
-        .to_string(),
-        new_text: r#"const categories = [
-				{
-					title: 'Подготовка и исследование',
-					description: 'Анализ требований и подготовка к разработке',
-					keywords: ['изуч', 'исследов', 'анализ', 'подготов', 'планиров'],
-					tasks: [] as any[]
-				},
-			];"#
-        .to_string(),
-    }];
 
-    let out_file = temp_dir.join("dir1").join("out_indent.txt");
+    let result = service
+        .read_file_lines(&file_path, 1, Some(2), false)
+        .await
+        .unwrap();
+    assert_eq!(result, "line2\r\nline3"); // No trailing newline
+}
+
+#[tokio::test]
+async fn test_read_file_lines_invalid_path() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let invalid_path = temp_dir.join("dir2/test.txt"); // Outside allowed_dirs
 
     let result = service
-        .apply_file_edits(&file_path, edits, Some(false), Some(out_file.as_path()))
+        .read_file_lines(&invalid_path, 0, Some(3), false)
         .await;
-
-    assert!(result.is_ok());
+    assert!(result.is_err(), "Expected error for invalid path");
 }
 
 #[tokio::test]
-async fn test_apply_file_edits_mixed_indentation_2() {
+async fn test_read_file_byte_range() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path = create_temp_file(
-        temp_dir.join("dir1").as_path(),
-        "test_indent.txt",
-        r#"
-            // some descriptions
-			const categories = [
-				{
-					title: 'Подготовка и исследование',
-					keywords: ['изуч', 'исследов', 'анализ', 'подготов', 'планиров'],
-					tasks: [] as any[]
-				},
-			];
-		// some other descriptions
-        "#,
-    );
-    // different indentation
-    let edits = vec![EditOperation {
-        old_text: r#"const categories = [
-				{
-					title: 'Подготовка и исследование',
-			keywords: ['изуч', 'исследов', 'анализ', 'подготов', 'планиров'],
-					tasks: [] as any[]
-				},
-			];"#
-        .to_string(),
-        new_text: r#"const categories = [
-				{
-					title: 'Подготовка и исследование',
-					description: 'Анализ требований и подготовка к разработке',
-					keywords: ['изуч', 'исследов', 'анализ', 'подготов', 'планиров'],
-					tasks: [] as any[]
-				},
-			];"#
-        .to_string(),
-    }];
+    let file_path = create_temp_file(&temp_dir.join("dir1"), "text.txt", "ab\néé\ncd\n");
 
-    let out_file = temp_dir.join("dir1").join("out_indent.txt");
+    // a limit splitting a character leaves it for the next read
+    let result = service
+        .read_file_byte_range(&file_path, 0, Some(4), None)
+        .await
+        .unwrap();
+    assert_eq!(result.content, "ab\n");
+    assert_eq!((result.start, result.end, result.total_bytes), (0, 3, 11));
 
+    // an offset inside a character starts at the next one
     let result = service
-        .apply_file_edits(&file_path, edits, Some(false), Some(out_file.as_path()))
-        .await;
-    assert!(result.is_ok());
+        .read_file_byte_range(&file_path, 4, Some(3), None)
+        .await
+        .unwrap();
+    assert_eq!(result.content, "é");
+    assert_eq!((result.start, result.end), (5, 7));
+
+    let result = service
+        .read_file_byte_range(&file_path, 3, None, Some(1))
+        .await
+        .unwrap();
+    assert_eq!(result.content, "éé\n");
+    assert_eq!(result.end, 8);
+
+    let result = service
+        .read_file_byte_range(&file_path, result.end, None, None)
+        .await
+        .unwrap();
+    assert_eq!(result.content, "cd\n");
+    assert_eq!(result.end, result.total_bytes);
+
+    let result = service
+        .read_file_byte_range(&file_path, 100, None, None)
+        .await
+        .unwrap();
+    assert_eq!(result.content, "");
+    assert_eq!((result.start, result.end), (11, 11));
 }
 
 #[tokio::test]
-async fn test_exact_match() {
+async fn test_read_file_lines_numbered() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let lines: Vec<String> = (1..=12).map(|n| format!("line{n}")).collect();
+    let file_path = create_temp_file(&temp_dir.join("dir1"), "text.txt", &lines.join("\n"));
 
-    let file = create_temp_file(
-        &temp_dir.as_path().join("dir1"),
-        "tets_file1.txt",
-        "hello world\n",
-    );
-
-    let edit = EditOperation {
-        old_text: "hello world".to_string(),
-        new_text: "hello universe".to_string(),
-    };
+    let result = service
+        .read_file_lines_numbered(&file_path, 7, Some(3), false)
+        .await
+        .unwrap();
+    assert_eq!(result, " 8\tline8\n 9\tline9\n10\tline10\n");
 
     let result = service
-        .apply_file_edits(file.as_path(), vec![edit], Some(false), None)
+        .read_file_lines_numbered(&file_path, 0, Some(2), true)
         .await
         .unwrap();
+    assert_eq!(result, "11\tline11\n12\tline12");
 
-    let modified_content = fs::read_to_string(file.as_path()).unwrap();
-    assert_eq!(modified_content, "hello universe\n");
-    assert!(result.contains("-hello world\n+hello universe"));
+    let result = service
+        .read_file_lines_numbered(&file_path, 20, Some(2), false)
+        .await
+        .unwrap();
+    assert_eq!(result, "");
 }
 
 #[tokio::test]
-async fn test_exact_match_edit2() {
+async fn test_read_file_chunk() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file = create_temp_file(
-        &temp_dir.as_path().join("dir1"),
-        "test_file1.txt",
-        "hello world\n",
+    let file_path = create_temp_file(
+        &temp_dir.join("dir1"),
+        "text.txt",
+        "line1\nline2\nline3\nline4\nline5",
     );
 
-    let edits = vec![EditOperation {
-        old_text: "hello world\n".into(),
-        new_text: "hello Rust\n".into(),
-    }];
+    // a byte limit within the third line ends the chunk after the second line
+    let chunk = service
+        .read_file_chunk(&file_path, None, Some(15), Some(10))
+        .await
+        .unwrap();
+    assert_eq!(chunk.content, "line1\nline2\n");
+    assert_eq!((chunk.start_line, chunk.position.line), (1, 3));
+    assert_eq!(chunk.total_bytes, 29);
+    assert!(!chunk.eof);
+
+    let chunk = service
+        .read_file_chunk(&file_path, Some(chunk.position), None, Some(2))
+        .await
+        .unwrap();
+    assert_eq!(chunk.content, "line3\nline4\n");
+    assert_eq!(chunk.start_line, 3);
+
+    let position = chunk.position.clone();
+    let chunk = service
+        .read_file_chunk(&file_path, Some(chunk.position), None, None)
+        .await
+        .unwrap();
+    assert_eq!(chunk.content, "line5");
+    assert_eq!(chunk.start_line, 5);
+    assert!(chunk.eof);
+
+    // a cursor is rejected once the file changed
+    let stale = ChunkPosition {
+        modified: position.modified.map(|modified| modified + 1),
+        ..position
+    };
+    assert!(
+        service
+            .read_file_chunk(&file_path, Some(stale), None, None)
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_read_around_match() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let lines: Vec<String> = (1..=20)
+        .map(|n| match n {
+            5 | 7 | 15 => format!("line{n} Target"),
+            _ => format!("line{n}"),
+        })
+        .collect();
+    let file_path = create_temp_file(&temp_dir.join("dir1"), "text.txt", &lines.join("\n"));
 
     let result = service
-        .apply_file_edits(&file, edits, Some(false), None)
-        .await;
+        .read_around_match(&file_path, "target", false, 1, None)
+        .await
+        .unwrap();
+    assert_eq!(result.total_matches, 3);
+    assert_eq!(result.regions.len(), 2);
+    // the overlapping windows of lines 5 and 7 are merged
+    assert_eq!(
+        (result.regions[0].start_line, result.regions[0].end_line),
+        (4, 8)
+    );
+    assert_eq!(result.regions[0].match_lines, vec![5, 7]);
+    assert_eq!(result.regions[0].lines[1], "line5 Target");
+    assert_eq!(
+        (result.regions[1].start_line, result.regions[1].end_line),
+        (14, 16)
+    );
 
-    assert!(result.is_ok());
-    let updated_content = fs::read_to_string(&file).unwrap();
-    assert_eq!(updated_content, "hello Rust\n");
+    let result = service
+        .read_around_match(&file_path, r"line1\d", true, 0, Some(2))
+        .await
+        .unwrap();
+    assert_eq!(result.total_matches, 10);
+    assert_eq!(result.regions.len(), 1);
+    assert_eq!(result.regions[0].match_lines, vec![10, 11]);
 }
 
 #[tokio::test]
-async fn test_line_by_line_match_with_indent() {
+async fn test_read_json_path() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file = create_temp_file(
-        &temp_dir.as_path().join("dir1"),
-        "test_file2.rs",
-        "    let x = 42;\n    println!(\"{}\");\n",
+    let file_path = create_temp_file(
+        &temp_dir.join("dir1"),
+        "package.json",
+        r#"{"name": "demo", "dependencies": {"a": "1.0", "b": "2.0"}, "items": [{"id": 1, "enabled": true}, {"id": 2, "enabled": false}]}"#,
     );
 
-    let edits = vec![EditOperation {
-        old_text: "let x = 42;\nprintln!(\"{}\");\n".into(),
-        new_text: "let x = 43;\nprintln!(\"x = {}\", x)".into(),
-    }];
+    let result = service.read_json_path(&file_path, "$.name").await.unwrap();
+    assert_eq!(result, vec![serde_json::json!("demo")]);
 
     let result = service
-        .apply_file_edits(&file, edits, Some(false), None)
-        .await;
+        .read_json_path(&file_path, "$.items[*].id")
+        .await
+        .unwrap();
+    assert_eq!(result, vec![serde_json::json!(1), serde_json::json!(2)]);
 
-    assert!(result.is_ok());
+    let result = service
+        .read_json_path(&file_path, "$.items[?@.enabled == true].id")
+        .await
+        .unwrap();
+    assert_eq!(result, vec![serde_json::json!(1)]);
 
-    let content = fs::read_to_string(&file).unwrap();
-    assert!(content.contains("let x = 43;"));
-    assert!(content.contains("println!(\"x = {}\", x)"));
+    let result = service
+        .read_json_path(&file_path, "$.missing")
+        .await
+        .unwrap();
+    assert!(result.is_empty());
 }
 
 #[tokio::test]
-async fn test_dry_run_mode() {
+async fn test_read_json_path_errors() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file = create_temp_file(
-        &temp_dir.as_path().join("dir1"),
-        "test_file4.sh",
-        "echo hello\n",
+    let json_file = create_temp_file(&temp_dir.join("dir1"), "a.json", r#"{"a": 1}"#);
+    let text_file = create_temp_file(&temp_dir.join("dir1"), "a.txt", "not json");
+
+    let result = service.read_json_path(&json_file, "$[").await;
+    assert!(matches!(result, Err(ServiceError::JsonPathError(_))));
+
+    let result = service.read_json_path(&text_file, "$.a").await;
+    assert!(matches!(result, Err(ServiceError::SerdeJsonError(_))));
+}
+
+#[tokio::test]
+async fn test_read_structured_file_formats() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let dir = temp_dir.join("dir1");
+    let toml_file = create_temp_file(
+        &dir,
+        "Cargo.toml",
+        "[package]\nname = \"demo\"\nreleased = 1979-05-27T07:32:00Z\n\n[[bin]]\nname = \"cli\"\n",
     );
+    let yaml_file = create_temp_file(
+        &dir,
+        "config.yml",
+        "services:\n  web:\n    ports:\n      - 8080\n      - 8443\n",
+    );
+    let json_file = create_temp_file(&dir, "data.json", r#"{"a": {"b": [1, 2]}}"#);
 
-    let edits = vec![EditOperation {
-        old_text: "echo hello\n".into(),
-        new_text: "echo world\n".into(),
-    }];
+    let value = service
+        .read_structured_file(&toml_file, None, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!({
+            "package": {"name": "demo", "released": "1979-05-27T07:32:00Z"},
+            "bin": [{"name": "cli"}]
+        })
+    );
 
-    let result = service
-        .apply_file_edits(&file, edits, Some(true), None)
-        .await;
-    assert!(result.is_ok());
+    let value = service
+        .read_structured_file(&yaml_file, None, Some("services.web.ports.1"))
+        .await
+        .unwrap();
+    assert_eq!(value, serde_json::json!(8443));
 
-    let content = fs::read_to_string(&file).unwrap();
-    assert_eq!(content, "echo hello\n"); // Should not be modified
+    let value = service
+        .read_structured_file(&json_file, None, Some("a.b"))
+        .await
+        .unwrap();
+    assert_eq!(value, serde_json::json!([1, 2]));
 }
 
 #[tokio::test]
-async fn test_save_to_different_path() {
+async fn test_read_structured_file_errors() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let orig_file = create_temp_file(
-        &temp_dir.as_path().join("dir1"),
-        "test_file5.txt",
-        "foo = 1\n",
-    );
+    let dir = temp_dir.join("dir1");
+    let no_extension = create_temp_file(&dir, "config", "key: value\n");
 
-    let save_to = temp_dir.as_path().join("dir1").join("saved_output.txt");
+    // unknown extension requires an explicit format
+    let result = service
+        .read_structured_file(&no_extension, None, None)
+        .await;
+    assert!(result.is_err());
+    let value = service
+        .read_structured_file(&no_extension, Some(StructuredFormat::Yaml), Some("key"))
+        .await
+        .unwrap();
+    assert_eq!(value, serde_json::json!("value"));
 
-    let edits = vec![EditOperation {
-        old_text: "foo = 1\n".into(),
-        new_text: "foo = 2\n".into(),
-    }];
+    let result = service
+        .read_structured_file(
+            &no_extension,
+            Some(StructuredFormat::Yaml),
+            Some("key.missing"),
+        )
+        .await;
+    assert!(result.unwrap_err().to_string().contains("key.missing"));
 
     let result = service
-        .apply_file_edits(&orig_file, edits, Some(false), Some(&save_to))
+        .read_structured_file(&no_extension, Some(StructuredFormat::Toml), None)
         .await;
+    assert!(result.unwrap_err().to_string().starts_with("Invalid TOML"));
+}
 
-    assert!(result.is_ok());
+#[tokio::test]
+async fn test_preview_csv_small_file() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_temp_file(
+        &temp_dir.join("dir1"),
+        "data.csv",
+        "id,name,price,active,created\n1,apple,1.5,true,2024-01-01\n2,\"pear, green\",2,false,2024-02-01\n3,plum,,true,2024-03-01\n",
+    );
 
-    let original_content = fs::read_to_string(&orig_file).unwrap();
-    let saved_content = fs::read_to_string(&save_to).unwrap();
-    assert_eq!(original_content, "foo = 1\n");
-    assert_eq!(saved_content, "foo = 2\n");
+    let preview = service
+        .preview_csv(&file_path, None, true, 2, 1)
+        .await
+        .unwrap();
+    assert_eq!(preview.delimiter, ",");
+    assert_eq!(preview.row_count, 3);
+    assert!(preview.row_count_exact);
+    let columns: Vec<_> = preview
+        .columns
+        .iter()
+        .map(|c| (c.name.as_str(), c.column_type))
+        .collect();
+    assert_eq!(
+        columns,
+        vec![
+            ("id", ColumnType::Integer),
+            ("name", ColumnType::String),
+            ("price", ColumnType::Float),
+            ("active", ColumnType::Boolean),
+            ("created", ColumnType::Date),
+        ]
+    );
+    assert_eq!(preview.first_rows.len(), 2);
+    assert_eq!(preview.first_rows[1][1], "pear, green");
+    assert_eq!(
+        preview.last_rows,
+        vec![vec!["3", "plum", "", "true", "2024-03-01"]]
+    );
 }
 
 #[tokio::test]
-async fn test_diff_backtick_formatting() {
+async fn test_preview_csv_large_file_and_tsv() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file = create_temp_file(
-        &temp_dir.as_path().join("dir1"),
-        "test_file6.md",
-        "```\nhello\n```\n",
+    let mut content = String::from("n\tlabel\n");
+    for i in 0..5000 {
+        content.push_str(&format!("{i}\trow-{i:04}\n"));
+    }
+    let file_path = create_temp_file(&temp_dir.join("dir1"), "data.tsv", &content);
+
+    let preview = service
+        .preview_csv(&file_path, None, true, 1, 2)
+        .await
+        .unwrap();
+    assert_eq!(preview.delimiter, "\t");
+    assert!(!preview.row_count_exact);
+    assert!((4500..=5500).contains(&preview.row_count));
+    assert_eq!(preview.first_rows, vec![vec!["0", "row-0000"]]);
+    assert_eq!(
+        preview.last_rows,
+        vec![vec!["4998", "row-4998"], vec!["4999", "row-4999"]]
     );
 
-    let edits = vec![EditOperation {
-        old_text: "```\nhello\n```".into(),
-        new_text: "```\nworld\n```".into(),
-    }];
+    // without header, columns get generated names
+    let preview = service
+        .preview_csv(&file_path, None, false, 1, 0)
+        .await
+        .unwrap();
+    assert_eq!(preview.columns[0].name, "column_1");
+    assert_eq!(preview.columns[0].column_type, ColumnType::String);
+    assert!(preview.last_rows.is_empty());
+}
 
-    let result = service
-        .apply_file_edits(&file, edits, Some(true), None)
-        .await;
-    assert!(result.is_ok());
+#[tokio::test]
+async fn test_get_media_info_image() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = temp_dir.join("dir1").join("image.png");
+    image::RgbaImage::new(16, 9).save(&file_path).unwrap();
+
+    let info = service.get_media_info(&file_path).await.unwrap();
+    assert_eq!(info.mime_type, "image/png");
+    let image = info.image.unwrap();
+    assert_eq!(image.format, "png");
+    assert_eq!((image.width, image.height), (16, 9));
+    assert_eq!(image.bits_per_pixel, Some(32));
+    assert_eq!(image.channels, Some(4));
+    assert!(info.audio.is_none());
+}
 
-    let diff = result.unwrap();
-    assert!(diff.contains("diff"));
-    assert!(diff.starts_with("```")); // Should start with fenced backticks
+#[tokio::test]
+async fn test_get_media_info_audio() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = temp_dir.join("dir1").join("sound.wav");
+
+    // one second of 16-bit mono silence at 8kHz
+    let sample_rate: u32 = 8000;
+    let data_len: u32 = sample_rate * 2;
+    let mut wav = Vec::new();
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    wav.resize(wav.len() + data_len as usize, 0);
+    fs::write(&file_path, wav).unwrap();
+
+    let info = service.get_media_info(&file_path).await.unwrap();
+    let audio = info.audio.unwrap();
+    assert_eq!(audio.duration_ms, 1000);
+    assert_eq!(audio.sample_rate, Some(8000));
+    assert_eq!(audio.channels, Some(1));
+    assert_eq!(audio.bit_depth, Some(16));
+    assert!(info.image.is_none());
 }
 
 #[tokio::test]
-async fn test_no_edits_provided() {
+async fn test_read_media_file_with_resize() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file = create_temp_file(
-        &temp_dir.as_path().join("dir1"),
-        "test_file7.toml",
-        "enabled = true\n",
-    );
+    let file_path = temp_dir.join("dir1").join("screenshot.png");
+    image::RgbImage::from_fn(400, 200, |x, y| {
+        image::Rgb([(x % 256) as u8, (y % 256) as u8, 0])
+    })
+    .save(&file_path)
+    .unwrap();
+    let original_size = fs::metadata(&file_path).unwrap().len() as usize;
+
+    // downscaled image keeps its format and aspect ratio
+    let options = ImageResizeOptions {
+        max_width: Some(100),
+        ..Default::default()
+    };
+    let (kind, content) = service
+        .read_media_file_with_options(&file_path, None, Some(&options))
+        .await
+        .unwrap();
+    assert_eq!(kind.mime_type(), "image/png");
+    let bytes =
+        base64::Engine::decode(&base64::engine::general_purpose::STANDARD, content).unwrap();
+    let resized = image::load_from_memory(&bytes).unwrap();
+    assert_eq!((resized.width(), resized.height()), (100, 50));
 
+    // max_bytes applies to the re-encoded image
     let result = service
-        .apply_file_edits(&file, vec![], Some(false), None)
+        .read_media_file(&file_path, Some(original_size - 1))
         .await;
-    assert!(result.is_ok());
+    assert!(matches!(result, Err(ServiceError::FileTooLarge(_))));
+    let options = ImageResizeOptions {
+        max_height: Some(50),
+        quality: Some(50),
+        ..Default::default()
+    };
+    let (kind, _) = service
+        .read_media_file_with_options(&file_path, Some(original_size - 1), Some(&options))
+        .await
+        .unwrap();
+    assert_eq!(kind.mime_type(), "image/jpeg");
 
-    let content = fs::read_to_string(&file).unwrap();
-    assert_eq!(content, "enabled = true\n");
+    // images are never upscaled
+    let options = ImageResizeOptions {
+        max_width: Some(1000),
+        max_height: Some(1000),
+        ..Default::default()
+    };
+    let (_, content) = service
+        .read_media_file_with_options(&file_path, None, Some(&options))
+        .await
+        .unwrap();
+    let bytes =
+        base64::Engine::decode(&base64::engine::general_purpose::STANDARD, content).unwrap();
+    let image = image::load_from_memory(&bytes).unwrap();
+    assert_eq!((image.width(), image.height()), (400, 200));
 }
 
 #[tokio::test]
-async fn test_preserve_windows_line_endings() {
+async fn test_get_media_info_not_media() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file = create_temp_file(
-        &temp_dir.as_path().join("dir1"),
-        "test_file.txt",
-        "line1\r\nline2\r\n",
-    );
+    let file_path = create_temp_file(&temp_dir.join("dir1"), "doc.pdf", "%PDF-1.4\n");
 
-    let edits = vec![EditOperation {
-        old_text: "line1\nline2".into(), // normalized format
-        new_text: "updated1\nupdated2".into(),
-    }];
+    let result = service.get_media_info(&file_path).await;
+    assert!(matches!(result, Err(ServiceError::InvalidMediaFile(_))));
+}
 
-    let result = service
-        .apply_file_edits(&file, edits, Some(false), None)
-        .await;
-    assert!(result.is_ok());
+#[tokio::test]
+async fn test_read_document_text_docx() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_temp_zip(
+        &temp_dir.join("dir1"),
+        "report.docx",
+        &[(
+            "word/document.xml",
+            r#"<?xml version="1.0"?><w:document xmlns:w="w"><w:body>
+<w:p><w:r><w:t>Hello</w:t></w:r><w:r><w:t xml:space="preserve"> world &amp; friends</w:t></w:r></w:p>
+<w:p><w:r><w:t>A</w:t><w:tab/><w:t>B</w:t></w:r></w:p>
+</w:body></w:document>"#,
+        )],
+    );
 
-    let output = std::fs::read_to_string(&file).unwrap();
-    assert_eq!(output, "updated1\r\nupdated2\r\n"); // Line endings preserved!
+    let document = service.read_document_text(&file_path, None).await.unwrap();
+    assert_eq!(document.kind, DocumentKind::Docx);
+    assert_eq!(document.sections.len(), 1);
+    assert_eq!(document.sections[0].text, "Hello world & friends\nA\tB\n");
 }
 
 #[tokio::test]
-async fn test_preserve_unix_line_endings() {
+async fn test_read_document_text_xlsx() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file = create_temp_file(
-        &temp_dir.as_path().join("dir1"),
-        "unix_line_file.txt",
-        "line1\nline2\n",
+    let file_path = create_temp_zip(
+        &temp_dir.join("dir1"),
+        "data.xlsx",
+        &[
+            (
+                "xl/workbook.xml",
+                r#"<workbook xmlns:r="r"><sheets><sheet name="Summary" sheetId="1" r:id="rId1"/><sheet name="Raw" sheetId="2" r:id="rId2"/></sheets></workbook>"#,
+            ),
+            (
+                "xl/_rels/workbook.xml.rels",
+                r#"<Relationships><Relationship Id="rId1" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Target="/xl/worksheets/sheet2.xml"/></Relationships>"#,
+            ),
+            (
+                "xl/sharedStrings.xml",
+                r#"<sst><si><t>name</t></si><si><r><t>tot</t></r><r><t>al</t></r></si></sst>"#,
+            ),
+            (
+                "xl/worksheets/sheet1.xml",
+                r#"<worksheet><sheetData><row r="1"><c r="A1" t="s"><v>0</v></c><c r="C1" t="s"><v>1</v></c></row><row r="2"><c r="A2" t="inlineStr"><is><t>x</t></is></c><c r="B2" t="b"><v>1</v></c><c r="C2"><v>42</v></c></row></sheetData></worksheet>"#,
+            ),
+            (
+                "xl/worksheets/sheet2.xml",
+                r#"<worksheet><sheetData><row r="1"><c r="A1"><v>1.5</v></c><c r="ZZZZZZZZZZZZZZ1"><v>9</v></c><c r="XFE1"><v>8</v></c><c r="B1"><v>2</v></c></row></sheetData></worksheet>"#,
+            ),
+        ],
     );
 
-    let edits = vec![EditOperation {
-        old_text: "line1\nline2".into(),
-        new_text: "updated1\nupdated2".into(),
-    }];
+    let document = service.read_document_text(&file_path, None).await.unwrap();
+    let sections: Vec<_> = document
+        .sections
+        .iter()
+        .map(|s| (s.name.as_str(), s.text.as_str()))
+        .collect();
+    assert_eq!(
+        sections,
+        vec![("Summary", "name\t\ttotal\nx\tTRUE\t42"), ("Raw", "1.5\t2")]
+    );
+
+    let document = service
+        .read_document_text(&file_path, Some(vec!["raw".to_string()]))
+        .await
+        .unwrap();
+    assert_eq!(document.sections.len(), 1);
+    assert_eq!(document.sections[0].name, "Raw");
 
     let result = service
-        .apply_file_edits(&file, edits, Some(false), None)
+        .read_document_text(&file_path, Some(vec!["Missing".to_string()]))
         .await;
+    assert!(result.is_err());
+}
 
-    assert!(result.is_ok());
+#[tokio::test]
+async fn test_read_document_text_pptx() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let slide = |text: &str| {
+        format!(r#"<p:sld xmlns:a="a" xmlns:p="p"><a:p><a:r><a:t>{text}</a:t></a:r></a:p></p:sld>"#)
+    };
+    let (slide1, slide2, slide10) = (slide("First"), slide("Second"), slide("Tenth"));
+    let file_path = create_temp_zip(
+        &temp_dir.join("dir1"),
+        "deck.pptx",
+        &[
+            ("ppt/slides/slide10.xml", &slide10),
+            ("ppt/slides/slide2.xml", &slide2),
+            ("ppt/slides/slide1.xml", &slide1),
+        ],
+    );
 
-    let updated = std::fs::read_to_string(&file).unwrap();
-    assert_eq!(updated, "updated1\nupdated2\n"); // Still uses \n endings
+    let document = service.read_document_text(&file_path, None).await.unwrap();
+    let names: Vec<_> = document.sections.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["Slide 1", "Slide 2", "Slide 10"]);
+    assert_eq!(document.sections[2].text, "Tenth\n");
+
+    let document = service
+        .read_document_text(&file_path, Some(vec!["2".to_string()]))
+        .await
+        .unwrap();
+    assert_eq!(document.sections.len(), 1);
+    assert_eq!(document.sections[0].text, "Second\n");
 }
 
 #[tokio::test]
-// Issue #19: https://github.com/rust-mcp-stack/rust-mcp-filesystem/issues/19
-async fn test_panic_on_out_of_bounds_edit() {
+async fn test_read_document_text_unsupported() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_temp_file(&temp_dir.join("dir1"), "notes.odt", "content");
+    assert!(service.read_document_text(&file_path, None).await.is_err());
 
-    // Set up an edit that expects to match 5 lines
-    let edit = EditOperation {
-        old_text: "line e\n".repeat(41).to_string(),
-        new_text: "replaced content".to_string(),
-    };
+    // a .docx that is not a zip archive
+    let file_path = create_temp_file(&temp_dir.join("dir1"), "broken.docx", "content");
+    assert!(service.read_document_text(&file_path, None).await.is_err());
+}
 
-    // Set up your file content with only 2 lines
-    let file_content = "line A\nline B\n";
-    let test_path = create_temp_file(
-        &temp_dir.as_path().join("dir1"),
-        "test_input.txt",
-        file_content,
+#[test]
+fn test_markdown_outline() {
+    let content = r#"---
+title: Not a heading
+---
+# Guide
+
+Intro text.
+
+## Install ##
+```sh
+# not a heading
+```
+
+Usage
+-----
+
+### Options
+#hashtag is not a heading
+- list item
+---
+# Appendix
+"#;
+    let outline: Vec<_> = markdown_outline(content)
+        .into_iter()
+        .map(|h| (h.level, h.text, h.line, h.end_line))
+        .collect();
+    assert_eq!(
+        outline,
+        vec![
+            (1, "Guide".to_string(), 4, 19),
+            (2, "Install".to_string(), 8, 12),
+            (2, "Usage".to_string(), 13, 19),
+            (3, "Options".to_string(), 16, 19),
+            (1, "Appendix".to_string(), 20, 20),
+        ]
     );
+}
 
-    let result = service
-        .apply_file_edits(&test_path, vec![edit], Some(true), None)
-        .await;
+#[tokio::test]
+async fn test_markdown_outline_max_level() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_temp_file(&temp_dir.join("dir1"), "README.md", "# A\n## B\n### C\n");
 
-    // It should panic without the fix, or return an error after applying the fix
-    assert!(result.is_err());
+    let headings = service.markdown_outline(&file_path, Some(2)).await.unwrap();
+    let texts: Vec<_> = headings.iter().map(|h| h.text.as_str()).collect();
+    assert_eq!(texts, vec!["A", "B"]);
+    assert_eq!(headings[1].end_line, 3);
 }
 
 #[tokio::test]
-async fn test_content_search() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir_search".to_string()]);
-    let file = create_temp_file(
-        &temp_dir.as_path().join("dir_search"),
-        "file_to_search.txt",
-        r#"For the Doctor Watsons of this world, as opposed to the Sherlock
-        Holmeses, success in the province of detective work must always
-        be, to a very large extent, the result of luck. Sherlock Holmes
-        can extract a clew from a wisp of straw or a flake of cigar ash;
-        but Doctor Watso2n has to have it taken out for him and dusted,
-        and exhibited clearly, with Watso\d*n a label attached."#,
-    );
+async fn test_get_code_outline_rust() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = create_temp_file(
+        &temp_dir.join("dir1"),
+        "lib.rs",
+        r#"struct Point {
+    x: i32,
+}
 
-    let query = r#"Watso\d*n"#;
+impl Display for Point {
+    fn fmt(&self) {}
+}
 
-    // search as regex
-    let result = service.content_search(query, &file, Some(true)).unwrap();
+trait Shape {
+    fn area(&self) -> f64;
+}
 
-    assert!(result.is_some());
-    let result = result.unwrap();
+mod geometry {
+    pub fn distance() {}
+}
+"#,
+    );
 
-    assert_eq!(result.file_path, file);
-    assert_eq!(result.matches.len(), 2);
-    assert_eq!(result.matches[0].line_number, 1);
-    assert_eq!(result.matches[1].line_number, 5);
+    let symbols = service.get_code_outline(&file_path, None).await.unwrap();
+    let outline: Vec<_> = symbols
+        .iter()
+        .map(|s| (s.kind, s.name.as_str(), s.start_line, s.end_line, s.depth))
+        .collect();
     assert_eq!(
-        result.matches[0].line_text.trim(),
-        "For the Doctor Watsons of this world, as opposed to the Sherlock"
+        outline,
+        vec![
+            ("struct", "Point", 1, 3, 0),
+            ("impl", "Display for Point", 5, 7, 0),
+            ("method", "fmt", 6, 6, 1),
+            ("trait", "Shape", 9, 11, 0),
+            ("method", "area", 10, 10, 1),
+            ("module", "geometry", 13, 15, 0),
+            ("function", "distance", 14, 14, 1),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_get_code_outline_languages() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let dir = temp_dir.join("dir1");
+
+    let python = create_temp_file(
+        &dir,
+        "app.py",
+        "class Service:\n    @property\n    def name(self):\n        pass\n\ndef main():\n    pass\n",
     );
+    let symbols = service.get_code_outline(&python, None).await.unwrap();
+    let outline: Vec<_> = symbols.iter().map(|s| (s.kind, s.name.as_str())).collect();
     assert_eq!(
-        result.matches[1].line_text.trim(),
-        "but Doctor Watso2n has to have it taken out for him and dusted,"
+        outline,
+        vec![
+            ("class", "Service"),
+            ("method", "name"),
+            ("function", "main")
+        ]
     );
 
-    // search as literal
-    let result = service.content_search(query, &file, Some(false)).unwrap();
-    assert!(result.is_some());
-    let result = result.unwrap();
-    assert_eq!(result.matches.len(), 1);
-    assert_eq!(result.matches[0].line_number, 6);
+    let typescript = create_temp_file(
+        &dir,
+        "app.ts",
+        "interface Props { id: number }\nexport class View {\n  render() {}\n}\nfunction helper() {}\n",
+    );
+    let symbols = service.get_code_outline(&typescript, None).await.unwrap();
+    let outline: Vec<_> = symbols.iter().map(|s| (s.kind, s.name.as_str())).collect();
     assert_eq!(
-        result.matches[0].line_text.trim(),
-        "and exhibited clearly, with Watso\\d*n a label attached."
+        outline,
+        vec![
+            ("interface", "Props"),
+            ("class", "View"),
+            ("method", "render"),
+            ("function", "helper")
+        ]
+    );
+
+    let c_file = create_temp_file(
+        &dir,
+        "main.c",
+        "struct node { int v; };\nstatic int *find(struct node *n) { return 0; }\n",
+    );
+    let symbols = service.get_code_outline(&c_file, None).await.unwrap();
+    let outline: Vec<_> = symbols.iter().map(|s| (s.kind, s.name.as_str())).collect();
+    assert_eq!(outline, vec![("struct", "node"), ("function", "find")]);
+
+    // explicit language for unknown extensions, and unsupported languages
+    let script = create_temp_file(&dir, "script", "def run():\n    pass\n");
+    assert!(service.get_code_outline(&script, None).await.is_err());
+    let symbols = service
+        .get_code_outline(&script, Some("python"))
+        .await
+        .unwrap();
+    assert_eq!(symbols[0].name, "run");
+    assert!(
+        service
+            .get_code_outline(&script, Some("json"))
+            .await
+            .is_err()
     );
 }
 
 #[test]
-fn test_match_near_start_short_line() {
+fn test_extract_snippet_bug_37() {
     let (_, service, _) = setup_service(vec!["dir_search".to_string()]);
 
-    let line = "match this text";
-    let m = Match::new(0, 5);
-    let result = service.extract_snippet(line, m, Some(20), Some(5));
+    // Input string :  ’ starts spans 3 bytes: 0xE2 0x80 0x99.
+    let line = "If and when that happens, however, we will not be able to declare victory quite yet. Defeating the open conspiracy to deprive students of physical access to books will do little to counteract the more diffuse confluence of forces that are depriving students of their education with a curly apostrophe ’ followed by more text";
 
-    // Start at 0, should not prepend ...
-    // Full line is shorter than SNIPPET_MAX_LENGTH
-    assert_eq!(result, "match this text");
-}
+    let curly_pos = line.find("’").unwrap();
 
-#[tokio::test]
-async fn test_snippet_back_chars() {
-    let (_, service, _) = setup_service(vec!["dir_search".to_string()]);
-    let line = "this is a long enough line for testing match in middle";
-    let m = Match::new(40, 45);
-    let result = service.extract_snippet(line, m, Some(20), Some(5));
+    println!("Curly apostrophe at byte: {curly_pos}"); //position: 301
 
-    assert!(result.starts_with("..."));
-    assert!(!result.ends_with("..."));
-    assert!(result.contains("match"));
+    // Simulate a match just after the curly apostrophe
+    let match_start = curly_pos + 3; // Start of "followed"
+    let match_end = match_start + 8; // End of "followed"
+    let match_result = Match::new(match_start, match_end);
 
-    // larger text, truncates at the end
-    let line = "this is a long enough line for testing match in middles .";
-    let m = Match::new(40, 45);
-    let result = service.extract_snippet(line, m, Some(20), Some(5));
-    assert!(result.starts_with("..."));
-    assert!(result.ends_with("..."));
-    assert!(result.contains("match"));
+    // Parameters to make snippet_start in extract_snippet() function to land inside ’ (e.g., byte 302)
+    let backward_chars = match_start - (curly_pos + 1); // Land on second byte of ’
+    println!(
+        "match_start: {match_start}, match_end: {match_end},  backward_chars  {backward_chars} "
+    );
+
+    let result = service.extract_snippet(
+        line,
+        match_result,
+        Some(5), // max_length
+        Some(backward_chars),
+    );
+
+    println!("Snippet: {result}");
 }
 
-#[test]
-fn test_match_triggers_only_end_ellipsis() {
-    let (_, service, _) = setup_service(vec!["dir_search".to_string()]);
+#[tokio::test]
+async fn test_calculate_directory_size_normal() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    create_temp_file(&temp_dir.join("dir1"), "file1.txt", "content1");
+    create_temp_file(&temp_dir.join("dir1"), "file2.txt", "content22");
 
-    let line = "match is at start but line is long";
-    let m = Match::new(0, 5);
+    let size = service
+        .calculate_directory_size(&temp_dir.join("dir1"))
+        .await
+        .unwrap();
+    assert_eq!(size, 17); // "content1" (8 bytes) + "content22" (9 bytes) = 17 bytes
+}
 
-    let result = service.extract_snippet(line, m, Some(10), Some(5));
+#[tokio::test]
+async fn test_calculate_directory_size_empty_dir() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    create_sub_dir(&temp_dir, "dir1").await;
 
-    // Only ends in ellipsis
-    assert!(!result.starts_with("..."));
-    assert!(result.ends_with("..."));
+    let size = service
+        .calculate_directory_size(&temp_dir.join("dir1"))
+        .await
+        .unwrap();
+    assert_eq!(size, 0);
 }
 
-#[test]
-fn test_match_triggers_only_start_ellipsis() {
-    let (_, service, _) = setup_service(vec!["dir_search".to_string()]);
+#[tokio::test]
+async fn test_calculate_directory_size_nested_files() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    create_temp_file(&temp_dir.join("dir1"), "file1.txt", "content1");
+    create_temp_file(&temp_dir.join("dir1/subdir"), "file2.txt", "content22");
 
-    let line = "line is long and match is near end";
-    let m = Match::new(31, 36);
-    let result = service.extract_snippet(line, m, Some(10), Some(5));
-    // Only starts with ellipsis
-    assert!(result.starts_with("..."));
-    assert!(!result.ends_with("..."));
+    let size = service
+        .calculate_directory_size(&temp_dir.join("dir1"))
+        .await
+        .unwrap();
+    assert_eq!(size, 17); // "content1" (8 bytes) + "content22" (9 bytes) = 17 bytes
 }
 
-#[test]
-fn test_trim_applied() {
-    let (_, service, _) = setup_service(vec!["dir_search".to_string()]);
+#[tokio::test]
+async fn test_calculate_directory_size_invalid_path() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let invalid_path = temp_dir.join("dir2");
 
-    let line = "     match here with spaces    ";
-    let m = Match::new(5, 10);
+    let result = service.calculate_directory_size(&invalid_path).await;
+    assert!(result.is_err(), "Expected error for invalid path");
+}
 
-    let result = service.extract_snippet(line, m, Some(10), Some(5));
+#[tokio::test]
+async fn test_directory_size_breakdown() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let root = temp_dir.join("dir1");
+    create_temp_file(&root, "readme", "12");
+    create_temp_file(&root, "a.TXT", "1234");
+    create_temp_file(&root.join("src"), "main.rs", "123456");
+    create_temp_file(&root.join("src"), "lib.rs", "12");
+    create_temp_file(&root.join("docs"), "b.txt", "123");
+    let groups = |group_by| {
+        let service = &service;
+        let root = root.clone();
+        async move {
+            service
+                .directory_size_breakdown(&root, group_by)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|group| (group.group, group.files, group.bytes))
+                .collect::<Vec<_>>()
+        }
+    };
 
-    // Ensure whitespace is trimmed before slicing
-    assert!(!result.contains("     "));
-    assert!(result.contains("match"));
+    assert_eq!(
+        groups(SizeGroupBy::Extension).await,
+        vec![
+            ("rs".to_string(), 2, 8),
+            ("txt".to_string(), 2, 7),
+            ("(no extension)".to_string(), 1, 2)
+        ]
+    );
+    assert_eq!(
+        groups(SizeGroupBy::TopLevelDir).await,
+        vec![
+            ("src".to_string(), 2, 8),
+            (".".to_string(), 2, 6),
+            ("docs".to_string(), 1, 3)
+        ]
+    );
+    assert_eq!(
+        groups(SizeGroupBy::None).await,
+        vec![("*".to_string(), 5, 17)]
+    );
 }
 
-#[test]
-fn test_exact_snippet_end() {
-    let (_, service, _allowed_dirs) = setup_service(vec!["dir_search".to_string()]);
-    let line = "some content with match inside";
-    let m = Match::new(18, 23);
-    let result = service.extract_snippet(line, m, Some(line.len()), Some(18));
-    // Full trimmed line, no ellipses
-    assert_eq!(result, "some content with match inside");
+#[tokio::test]
+async fn test_directory_stats() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let root = temp_dir.join("dir1");
+    create_temp_file(&root, "main.rs", "fn main() {\n}\n");
+    create_temp_file(&root.join("src"), "lib.rs", "a\nb\nc");
+    create_temp_file(&root.join("src"), "notes.MD", "# notes\n");
+    create_temp_file(&root.join("vendor"), "dep.rs", "ignored\n");
+    fs::write(root.join("src").join("blob.bin"), b"\0\x01\n\n").unwrap();
+
+    let stats = service
+        .directory_stats(
+            &root,
+            vec!["vendor".to_string()],
+            true,
+            WalkOptions::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(stats.files, 4);
+    assert_eq!(stats.directories, 1);
+    assert_eq!(stats.bytes, 14 + 5 + 8 + 4);
+    assert_eq!(stats.lines, Some(2 + 3 + 1));
+    let by_extension: Vec<_> = stats
+        .by_extension
+        .iter()
+        .map(|stats| (stats.extension.as_str(), stats.files, stats.lines))
+        .collect();
+    assert_eq!(
+        by_extension,
+        vec![("rs", 2, Some(5)), ("md", 1, Some(1)), ("bin", 1, Some(0))]
+    );
+
+    let stats = service
+        .directory_stats(&root, vec![], false, WalkOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(stats.files, 5);
+    assert_eq!(stats.directories, 2);
+    assert_eq!(stats.lines, None);
+    assert!(stats.by_extension.iter().all(|stats| stats.lines.is_none()));
 }
 
 #[tokio::test]
-async fn search_files_content() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir_search".to_string()]);
-
-    create_temp_file(
-        &temp_dir.as_path().join("dir_search"),
-        "file1.txt",
-        r#"For the Doctor Watsons of this world, as opposed to the Sherlock
-        Holmeses, success in the province of detective work must always
-        be, to a very large extent, the result of luck. Sherlock Holmes
-        can extract a clew from a wisp of straw or a flake of cigar ash;
-        but Doctor Watson has to have it taken out for him and dusted,
-        and exhibited clearly, with Watso2n a label attached."#,
+async fn test_collect_todos() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let root = temp_dir.join("dir1");
+    let main = create_temp_file(
+        &root,
+        "main.rs",
+        "// TODO: handle errors\nfn main() {} // FIXME - leaks\n/* HACK */\n// TODOS are not markers\n",
     );
-    create_temp_file(
-        &temp_dir.as_path().join("dir_search"),
-        "file2.txt",
-        r#"For the Doctor Watsons of this world, as opposed to the Sherlock
-        Holmeses, success in the province of detective work must always
-        be, to a very large extent, the result of luck. Sherlock Holmes
-        can extract a clew from a wisp of straw or a flake of cigar ash;
-        but Doctor Watson has to have it taken out for him and dusted,
-        and exhibited clearly, with Watso2n a label attached."#,
+    let lib = create_temp_file(
+        &root.join("src"),
+        "lib.py",
+        "# XXX check\n# TODO(bob): rename\n",
     );
+    fs::write(root.join("blob.bin"), b"\0TODO binary").unwrap();
 
-    let query = r#"Watso\d*n"#;
+    let tags: Vec<String> = DEFAULT_TODO_TAGS
+        .iter()
+        .map(|tag| tag.to_string())
+        .collect();
+    let groups = service
+        .collect_todos(&root, &tags, vec![], WalkOptions::default())
+        .await
+        .unwrap();
+    let summary: Vec<_> = groups
+        .iter()
+        .map(|group| {
+            (
+                group.tag.as_str(),
+                group
+                    .items
+                    .iter()
+                    .map(|item| (item.path.clone(), item.line, item.text.as_str()))
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (
+                "TODO",
+                vec![
+                    (main.clone(), 1, "handle errors"),
+                    (lib.clone(), 2, "(bob): rename")
+                ]
+            ),
+            ("FIXME", vec![(main.clone(), 2, "leaks")]),
+            ("HACK", vec![(main.clone(), 3, "")]),
+        ]
+    );
 
-    let results = service
-        .search_files_content(
-            temp_dir.as_path().join("dir_search"),
-            "*.txt",
-            query,
-            true,
-            None, // exclude_patterns
-            None, // min_bytes
-            None, // max_bytes
+    let groups = service
+        .collect_todos(
+            &root,
+            &["XXX".to_string(), "NOTE".to_string()],
+            vec![],
+            WalkOptions::default(),
         )
         .await
         .unwrap();
-    assert_eq!(results.len(), 2);
-    // Each file should have 3 matches: "Watsons", "Watson", "Watso2n"
-    assert_eq!(results[0].matches.len(), 3);
-    assert_eq!(results[1].matches.len(), 3);
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].items[0].text, "check");
+
+    assert!(
+        service
+            .collect_todos(&root, &[" ".to_string()], vec![], WalkOptions::default())
+            .await
+            .is_err()
+    );
 }
 
 #[tokio::test]
-async fn search_files_content_respects_excludes() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir_search".to_string()]);
-    let dir = temp_dir.as_path().join("dir_search");
+async fn test_find_empty_directories_normal() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    create_sub_dir(&temp_dir, "dir1/empty1").await;
+    create_sub_dir(&temp_dir, "dir1/empty2").await;
+    create_temp_file(&temp_dir.join("dir1/non_empty"), "file.txt", "content");
 
-    create_temp_file(&dir, "keep.txt", "Sherlock Holmes finds clues.");
-    create_temp_file(&dir, "skip.txt", "Sherlock Holmes overlooks this clue.");
+    let result = service
+        .find_empty_directories(&temp_dir.join("dir1"), None)
+        .await
+        .unwrap();
+    let expected = [
+        temp_dir.join("dir1/empty1").to_str().unwrap().to_string(),
+        temp_dir.join("dir1/empty2").to_str().unwrap().to_string(),
+    ];
+    assert_eq!(result.len(), 2);
+    assert!(result.iter().all(|path| expected.contains(path)));
+}
 
-    let results = service
-        .search_files_content(
-            dir.clone(),
-            "*.txt",
-            "Holmes",
-            false,
-            Some(vec!["skip.txt".to_string()]),
-            None,
-            None,
-        )
+#[tokio::test]
+async fn test_delete_empty_directories() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    create_sub_dir(&temp_dir, "dir1/parent/child").await;
+    create_temp_file(&temp_dir.join("dir1/mac"), ".DS_Store", "metadata");
+    create_temp_file(&temp_dir.join("dir1/hidden"), ".keep", "");
+    create_temp_file(&temp_dir.join("dir1/non_empty"), "file.txt", "content");
+
+    let empty_dirs = service
+        .find_empty_directories(&temp_dir.join("dir1"), None)
         .await
         .unwrap();
+    let removal = service.delete_empty_directories(empty_dirs).await.unwrap();
+
+    let dir = |name: &str| temp_dir.join("dir1").join(name);
+    // deepest first, so that the parent is empty once its child is removed
+    let child_position = removal
+        .removed
+        .iter()
+        .position(|d| Path::new(d) == dir("parent/child"));
+    let parent_position = removal
+        .removed
+        .iter()
+        .position(|d| Path::new(d) == dir("parent"));
+    assert!(child_position.unwrap() < parent_position.unwrap());
+    assert!(removal.removed.iter().any(|d| Path::new(d) == dir("mac")));
+    assert!(!dir("parent").exists());
+    assert!(!dir("mac").exists());
+    // hidden files are not seen by the search but prevent the removal
+    assert_eq!(removal.skipped.len(), 1);
+    assert_eq!(Path::new(&removal.skipped[0].0), dir("hidden"));
+    assert!(dir("hidden/.keep").exists());
+    assert!(dir("non_empty/file.txt").exists());
+}
 
-    assert_eq!(results.len(), 1);
-    assert!(results[0].file_path.to_string_lossy().ends_with("keep.txt"));
-    assert_eq!(results[0].matches.len(), 1);
+#[cfg(unix)]
+#[tokio::test]
+async fn test_find_broken_symlinks() {
+    use std::os::unix::fs::symlink;
+
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let dir1 = temp_dir.join("dir1");
+    let target = create_temp_file(&dir1, "target.txt", "content");
+    create_temp_file(&temp_dir, "outside.txt", "secret");
+    symlink(&target, dir1.join("valid")).unwrap();
+    symlink("missing.txt", dir1.join("dangling")).unwrap();
+    symlink(temp_dir.join("outside.txt"), dir1.join("escape")).unwrap();
+    symlink("loop", dir1.join("loop")).unwrap();
+
+    let broken = service
+        .find_broken_symlinks(&dir1, None, WalkOptions::default())
+        .await
+        .unwrap();
+    let found: Vec<_> = broken
+        .iter()
+        .map(|link| {
+            (
+                Path::new(&link.path).file_name().unwrap().to_str().unwrap(),
+                link.reason,
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("dangling", BrokenSymlinkReason::MissingTarget),
+            ("escape", BrokenSymlinkReason::OutsideAllowedDirectories),
+            ("loop", BrokenSymlinkReason::MissingTarget),
+        ]
+    );
+    assert_eq!(broken[0].target, "missing.txt");
 }
 
 #[tokio::test]
-async fn test_head_file_normal() {
+async fn test_trash_and_restore() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path = create_test_file_with_line_ending(
-        &temp_dir,
-        "dir1/test.txt",
-        vec!["line1", "line2", "line3", "line4", "line5"],
-        "\n",
-    )
-    .await;
+    let service = service.with_trash(true);
+    let dir1 = temp_dir.join("dir1");
+    let file = create_temp_file(&dir1, "file.txt", "content");
+    let copy = create_temp_file(&dir1.join("sub"), "file.txt", "content");
 
-    let result = service
-        .read_file_lines(&file_path, 0, Some(3), false)
+    let groups = vec![vec![
+        file.to_str().unwrap().to_string(),
+        copy.to_str().unwrap().to_string(),
+    ]];
+    let resolutions = service
+        .resolve_duplicates(groups, DuplicateAction::DeleteNewest, false)
         .await
         .unwrap();
-    assert_eq!(result, "line1\nline2\nline3\n");
+    let deleted = PathBuf::from(&resolutions[0].resolved[0]);
+    assert!(!deleted.exists());
+
+    let trash = service.list_trash().await.unwrap();
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0].original_path, deleted);
+    assert!(
+        dir1.join(TRASH_DIR_NAME)
+            .join("items")
+            .join(&trash[0].id)
+            .exists()
+    );
+
+    let restored = service.restore_from_trash(&deleted).await.unwrap();
+    assert_eq!(restored.original_path, deleted);
+    assert_eq!(fs::read_to_string(&deleted).unwrap(), "content");
+    assert!(service.list_trash().await.unwrap().is_empty());
+
+    let result = service.restore_from_trash(&deleted).await;
+    assert!(
+        result
+            .unwrap_err()
+            .to_string()
+            .contains("not found in the trash")
+    );
 }
 
 #[tokio::test]
-async fn test_head_file_empty_file() {
+async fn test_restore_from_trash_rejects_forged_manifest() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path =
-        create_test_file_with_line_ending(&temp_dir, "dir1/empty.txt", vec![], "\n").await;
+    let service = service.with_trash(true);
+    let dir1 = temp_dir.join("dir1");
+    let outside = create_temp_file(&temp_dir.join("dir2"), "secret.txt", "secret");
+    let file = create_temp_file(&dir1, "file.txt", "content");
+    service.move_to_trash(&file).await.unwrap();
+
+    // the write tools cannot reach the trash
+    let manifest = dir1.join(TRASH_DIR_NAME).join("manifest.json");
+    let err = service
+        .write_file(&manifest, &"[]".to_string(), false, None)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ServiceError::PathDenied(_)));
+
+    // an id pointing outside the trash is refused
+    let forged = serde_json::json!([{
+        "id": "../../../dir2/secret.txt",
+        "original_path": dir1.join("stolen.txt"),
+        "deleted_at": "2024-01-01T00:00:00Z",
+    }]);
+    fs::write(&manifest, forged.to_string()).unwrap();
+    let err = service
+        .restore_from_trash(&dir1.join("stolen.txt"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ServiceError::PathDenied(_)));
+    assert!(outside.exists());
+    assert!(!dir1.join("stolen.txt").exists());
+
+    // so is an item reached through a symlinked items directory
+    #[cfg(unix)]
+    {
+        let items = dir1.join(TRASH_DIR_NAME).join("items");
+        fs::rename(&items, dir1.join(TRASH_DIR_NAME).join("old-items")).unwrap();
+        std::os::unix::fs::symlink(temp_dir.join("dir2"), &items).unwrap();
+        let forged = serde_json::json!([{
+            "id": "secret.txt",
+            "original_path": dir1.join("stolen.txt"),
+            "deleted_at": "2024-01-01T00:00:00Z",
+        }]);
+        fs::write(&manifest, forged.to_string()).unwrap();
+        let err = service
+            .restore_from_trash(&dir1.join("stolen.txt"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ServiceError::PathDenied(_)));
+        assert!(outside.exists());
+    }
+}
+
+#[tokio::test]
+async fn test_read_only_service() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let service = service
+        .with_journal(temp_dir.join("journal"))
+        .with_trash(true)
+        .with_read_only(true);
+    let dir1 = temp_dir.join("dir1");
+    let file = create_temp_file(&dir1, "file.txt", "original");
+    assert!(service.is_read_only());
+    let is_no_write_access = |err: ServiceError| matches!(err, ServiceError::NoWriteAccess);
 
+    let err = service
+        .write_file(&file, &"overwritten".to_string(), false, None)
+        .await
+        .unwrap_err();
+    assert!(is_no_write_access(err));
+    let edits = vec![EditOperation {
+        old_text: "original".to_string(),
+        new_text: "edited".to_string(),
+        ..Default::default()
+    }];
+    let err = service
+        .apply_file_edits(&file, edits.clone(), None, None, false, None)
+        .await
+        .unwrap_err();
+    assert!(is_no_write_access(err));
+    let err = service
+        .create_directory(&dir1.join("new"))
+        .await
+        .unwrap_err();
+    assert!(is_no_write_access(err));
+    let err = service
+        .move_file(&file, &dir1.join("moved.txt"), OverwritePolicy::Error)
+        .await
+        .unwrap_err();
+    assert!(is_no_write_access(err));
+    let err = service
+        .copy_file(&file, &dir1.join("copy.txt"), OverwritePolicy::Error)
+        .await
+        .unwrap_err();
+    assert!(is_no_write_access(err));
+    let err = service
+        .create_temp_path(&dir1, TempPathKind::File, "", "", None)
+        .await
+        .unwrap_err();
+    assert!(is_no_write_access(err));
+    let err = service.move_to_trash(&file).await.unwrap_err();
+    assert!(is_no_write_access(err));
+    let err = service.undo_last_operation().await.unwrap_err();
+    assert!(is_no_write_access(err));
+
+    // reading and previewing still work, and nothing was modified
     let result = service
-        .read_file_lines(&file_path, 0, Some(5), false)
+        .apply_file_edits(&file, edits, Some(true), None, false, None)
         .await
         .unwrap();
-    assert_eq!(result, "");
+    assert!(result.diff.contains("+edited"));
+    assert_eq!(fs::read_to_string(&file).unwrap(), "original");
+    assert_eq!(fs::read_dir(&dir1).unwrap().count(), 1);
 }
 
 #[tokio::test]
-async fn test_head_file_n_zero() {
+async fn test_undo_last_operation() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path = create_test_file_with_line_ending(
-        &temp_dir,
-        "dir1/test.txt",
-        vec!["line1", "line2", "line3"],
-        "\n",
-    )
-    .await;
+    let service = service.with_journal(temp_dir.join("journal"));
+    let dir1 = temp_dir.join("dir1");
+    let file = create_temp_file(&dir1, "file.txt", "original");
 
-    let result = service
-        .read_file_lines(&file_path, 0, Some(0), false)
+    service
+        .write_file(&file, &"overwritten".to_string(), false, None)
         .await
         .unwrap();
-    assert_eq!(result, "");
+    let edits = vec![EditOperation {
+        old_text: "overwritten".to_string(),
+        new_text: "edited".to_string(),
+        ..Default::default()
+    }];
+    service
+        .apply_file_edits(&file, edits, Some(false), None, false, None)
+        .await
+        .unwrap();
+    service
+        .create_directory(&dir1.join("a").join("b"))
+        .await
+        .unwrap();
+    service
+        .move_file(
+            &file,
+            &dir1.join("a").join("b").join("moved.txt"),
+            OverwritePolicy::Error,
+        )
+        .await
+        .unwrap();
+    let operations: Vec<_> = service
+        .journal_entries()
+        .into_iter()
+        .map(|entry| entry.operation)
+        .collect();
+    assert_eq!(
+        operations,
+        vec!["move_file", "create_directory", "edit_file", "write_file"]
+    );
+
+    assert_eq!(
+        service.undo_last_operation().await.unwrap().operation,
+        "move_file"
+    );
+    assert_eq!(fs::read_to_string(&file).unwrap(), "edited");
+    service.undo_last_operation().await.unwrap();
+    assert!(!dir1.join("a").exists());
+    service.undo_last_operation().await.unwrap();
+    assert_eq!(fs::read_to_string(&file).unwrap(), "overwritten");
+    service.undo_last_operation().await.unwrap();
+    assert_eq!(fs::read_to_string(&file).unwrap(), "original");
+
+    let result = service.undo_last_operation().await;
+    assert!(
+        result
+            .unwrap_err()
+            .to_string()
+            .contains("no operation to undo")
+    );
 }
 
 #[tokio::test]
-async fn test_head_file_n_larger_than_file() {
+async fn test_undo_deletions() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path =
-        create_test_file_with_line_ending(&temp_dir, "dir1/test.txt", vec!["line1", "line2"], "\n")
-            .await;
+    let service = service.with_journal(temp_dir.join("journal"));
+    let dir1 = temp_dir.join("dir1");
+    let kept = create_temp_file(&dir1, "a.txt", "same content");
+    let copy = create_temp_file(&dir1, "b.txt", "same content");
+    service
+        .write_file(&dir1.join("new.txt"), &"new".to_string(), false, None)
+        .await
+        .unwrap();
 
-    let result = service
-        .read_file_lines(&file_path, 0, Some(5), false)
+    let groups = vec![vec![
+        kept.to_str().unwrap().to_string(),
+        copy.to_str().unwrap().to_string(),
+    ]];
+    let resolutions = service
+        .resolve_duplicates(groups, DuplicateAction::DeleteNewest, false)
         .await
         .unwrap();
-    assert_eq!(result, "line1\nline2");
+    let deleted = PathBuf::from(&resolutions[0].resolved[0]);
+    assert!(!deleted.exists());
+
+    service.undo_last_operation().await.unwrap();
+    assert_eq!(fs::read_to_string(&deleted).unwrap(), "same content");
+    // undoing the creation of a file removes it
+    service.undo_last_operation().await.unwrap();
+    assert!(!dir1.join("new.txt").exists());
 }
 
 #[tokio::test]
-async fn test_head_file_no_trailing_newline() {
+async fn test_find_empty_directories_no_empty_dirs() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    // Create file without trailing newline
-    let file_path = temp_dir.join("dir1/test.txt");
-    tokio::fs::create_dir_all(file_path.parent().unwrap())
+    create_temp_file(&temp_dir.join("dir1/dir1"), "file.txt", "content");
+    create_temp_file(&temp_dir.join("dir1/dir2"), "file.txt", "content");
+
+    let result = service
+        .find_empty_directories(&temp_dir.join("dir1"), None)
         .await
         .unwrap();
-    let mut file = File::create(&file_path).unwrap();
-    file.write_all(b"line1\nline2\nline3").unwrap();
+    assert_eq!(result, Vec::<String>::new());
+}
+
+#[tokio::test]
+async fn test_find_empty_directories_empty_root() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    create_sub_dir(&temp_dir, "dir1").await;
 
     let result = service
-        .read_file_lines(&file_path, 0, Some(3), false)
+        .find_empty_directories(&temp_dir.join("dir1"), None)
         .await
         .unwrap();
-    assert_eq!(result, "line1\nline2\nline3");
+    assert_eq!(result, Vec::<String>::new());
 }
 
 #[tokio::test]
-async fn test_head_file_single_line() {
+async fn test_find_empty_directories_invalid_path() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path =
-        create_test_file_with_line_ending(&temp_dir, "dir1/test.txt", vec!["line1"], "\n").await;
+    let invalid_path = temp_dir.join("dir2");
+
+    let result = service.find_empty_directories(&invalid_path, None).await;
+    assert!(result.is_err(), "Expected error for invalid path");
+}
+
+#[tokio::test]
+async fn test_find_duplicate_files_normal() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let content = "same content";
+    let file1 = create_temp_file(&temp_dir.join("dir1"), "file1.txt", content);
+    let file2 = create_temp_file(&temp_dir.join("dir1"), "file2.txt", content);
+    let _file3 = create_temp_file(&temp_dir.join("dir1"), "file3.txt", "different");
 
     let result = service
-        .read_file_lines(&file_path, 0, Some(1), false)
+        .find_duplicate_files(
+            &temp_dir.join("dir1"),
+            Some("*".to_string()),
+            None,
+            None,
+            None,
+            true,
+            None,
+            &OperationContext::default(),
+        )
         .await
         .unwrap();
-    assert_eq!(result, "line1");
+    let expected = vec![vec![
+        file1.to_str().unwrap().to_string(),
+        file2.to_str().unwrap().to_string(),
+    ]];
+
+    assert_eq!(result.len(), 1);
+    assert_eq!(
+        sort_duplicate_groups(result),
+        sort_duplicate_groups(expected)
+    );
 }
 
 #[tokio::test]
-async fn test_head_file_windows_line_endings() {
+async fn test_find_duplicate_files_no_duplicates() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path = create_test_file_with_line_ending(
-        &temp_dir,
-        "dir1/test.txt",
-        vec!["line1", "line2", "line3"],
-        "\r\n",
-    )
-    .await;
+    create_temp_file(&temp_dir.join("dir1"), "file1.txt", "content1");
+    create_temp_file(&temp_dir.join("dir1"), "file2.txt", "content2");
 
     let result = service
-        .read_file_lines(&file_path, 0, Some(2), false)
+        .find_duplicate_files(
+            &temp_dir.join("dir1"),
+            Some("*".to_string()),
+            None,
+            None,
+            None,
+            true,
+            None,
+            &OperationContext::default(),
+        )
         .await
         .unwrap();
-    assert_eq!(result, "line1\r\nline2\r\n");
+    assert_eq!(result, Vec::<Vec<String>>::new());
 }
 
 #[tokio::test]
-async fn test_head_file_invalid_path() {
+async fn test_find_duplicate_files_with_pattern() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let invalid_path = temp_dir.join("dir2/test.txt"); // Outside allowed_dirs
+    let content = "same content";
+    create_temp_file(&temp_dir.join("dir1"), "file1.txt", content);
+    create_temp_file(&temp_dir.join("dir1"), "file2.txt", content);
+    create_temp_file(&temp_dir.join("dir1"), "file3.log", content);
 
     let result = service
-        .read_file_lines(&invalid_path, 0, Some(3), false)
-        .await;
-    assert!(result.is_err(), "Expected error for invalid path");
+        .find_duplicate_files(
+            &temp_dir.join("dir1"),
+            Some("*.txt".to_string()),
+            None,
+            None,
+            None,
+            true,
+            None,
+            &OperationContext::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.len(), 1);
+    assert!(result[0].iter().all(|p| p.ends_with(".txt")));
 }
 
 #[tokio::test]
-async fn test_tail_file_normal() {
+async fn test_find_duplicate_files_with_exclude_patterns() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path = create_test_file_with_line_ending(
-        &temp_dir.to_path_buf(),
-        "dir1/test.txt",
-        vec!["line1", "line2", "line3", "line4", "line5"],
-        "\n",
-    )
-    .await;
+    let content = "same content";
+    create_temp_file(&temp_dir.join("dir1"), "file1.txt", content);
+    create_temp_file(&temp_dir.join("dir1"), "file2.txt", content);
+    create_temp_file(&temp_dir.join("dir1"), "file3.log", content);
 
     let result = service
-        .read_file_lines(&file_path, 0, Some(3), true)
+        .find_duplicate_files(
+            &temp_dir.join("dir1"),
+            Some("*".to_string()),
+            Some(vec!["*.log".to_string()]),
+            None,
+            None,
+            true,
+            None,
+            &OperationContext::default(),
+        )
         .await
         .unwrap();
-    assert_eq!(result, "line3\nline4\nline5"); // No trailing newline
+    assert_eq!(result.len(), 1);
+    assert!(result[0].iter().all(|p| !p.ends_with(".log")));
 }
 
 #[tokio::test]
-async fn test_tail_file_empty_file() {
+async fn test_find_duplicate_files_size_filters() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path =
-        create_test_file_with_line_ending(&temp_dir.to_path_buf(), "dir1/empty.txt", vec![], "\n")
-            .await;
+    let content = "same content"; // 12 bytes
+    create_temp_file(&temp_dir.join("dir1"), "file1.txt", content);
+    create_temp_file(&temp_dir.join("dir1"), "file2.txt", content);
+    create_temp_file(&temp_dir.join("dir1"), "file3.txt", "short"); // 5 bytes
 
     let result = service
-        .read_file_lines(&file_path, 0, Some(5), true)
+        .find_duplicate_files(
+            &temp_dir.join("dir1"),
+            Some("*".to_string()),
+            None,
+            Some(10), // min 10 bytes
+            Some(15), // max 15 bytes,
+            true,
+            None,
+            &OperationContext::default(),
+        )
         .await
         .unwrap();
-    assert_eq!(result, "");
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].len(), 2); // file1.txt and file2.txt
 }
 
 #[tokio::test]
-async fn test_tail_file_n_zero() {
+async fn test_find_duplicate_files_empty_dir() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path = create_test_file_with_line_ending(
-        &temp_dir.to_path_buf(),
-        "dir1/test.txt",
-        vec!["line1", "line2", "line3"],
-        "\n",
-    )
-    .await;
+    create_sub_dir(&temp_dir, "dir1").await;
 
     let result = service
-        .read_file_lines(&file_path, 0, Some(0), true)
+        .find_duplicate_files(
+            &temp_dir.join("dir1"),
+            Some("*".to_string()),
+            None,
+            None,
+            None,
+            true,
+            None,
+            &OperationContext::default(),
+        )
         .await
         .unwrap();
-    assert_eq!(result, "");
+    assert_eq!(result, Vec::<Vec<String>>::new());
 }
 
 #[tokio::test]
-async fn test_tail_file_n_larger_than_file() {
+async fn test_find_duplicate_files_invalid_path() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path = create_test_file_with_line_ending(
-        &temp_dir.to_path_buf(),
-        "dir1/test.txt",
-        vec!["line1", "line2"],
-        "\n",
-    )
-    .await;
+    let invalid_path = temp_dir.join("dir2");
 
     let result = service
-        .read_file_lines(&file_path, 0, Some(5), true)
-        .await
-        .unwrap();
-    assert_eq!(result, "line1\nline2"); // No trailing newline
+        .find_duplicate_files(
+            &invalid_path,
+            Some("*".to_string()),
+            None,
+            None,
+            None,
+            true,
+            None,
+            &OperationContext::default(),
+        )
+        .await;
+    assert!(result.is_err(), "Expected error for invalid path");
 }
 
 #[tokio::test]
-async fn test_tail_file_no_newline_at_end() {
+async fn test_find_duplicate_files_reports_progress() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path = create_temp_file(
-        &temp_dir.join("dir1"),
-        "test.txt",
-        "line1\nline2\nline3", // No newline at end
-    );
-    println!(">>>  {file_path:?} ");
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        create_temp_file(&temp_dir.join("dir1"), name, "same content");
+    }
+    create_temp_file(&temp_dir.join("dir1"), "d.txt", "other conten");
 
+    let updates = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+    let operation = OperationContext::default().with_progress({
+        let updates = updates.clone();
+        move |update| updates.lock().unwrap().push(update)
+    });
     let result = service
-        .read_file_lines(&file_path, 0, Some(2), true)
+        .find_duplicate_files(
+            &temp_dir.join("dir1"),
+            None,
+            None,
+            None,
+            None,
+            true,
+            Some(1),
+            &operation,
+        )
         .await
         .unwrap();
-    assert_eq!(result, "line2\nline3");
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].len(), 3);
+
+    let updates = updates.lock().unwrap();
+    // the quick hash covers the four files of the same size, the full hash the three duplicates
+    let last_quick = updates.iter().find(|u| u.total == Some(4.0)).unwrap();
+    assert_eq!(last_quick.progress, 4.0);
+    let last_full = updates.last().unwrap();
+    assert_eq!(last_full.total, Some(3.0));
+    assert_eq!(
+        last_full.message.as_deref(),
+        Some("Hashed 3 of 3 candidate files")
+    );
 }
 
+#[cfg(unix)]
 #[tokio::test]
-async fn test_tail_file_single_line() {
+async fn test_find_duplicate_files_hardlinks() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path = create_test_file_with_line_ending(
-        &temp_dir.to_path_buf(),
-        "dir1/test.txt",
-        vec!["line1"],
-        "\n",
-    )
-    .await;
+    let dir1 = temp_dir.join("dir1");
+    let original = create_temp_file(&dir1, "a.txt", "same content");
+    fs::hard_link(&original, dir1.join("b.txt")).unwrap();
 
-    let result = service
-        .read_file_lines(&file_path, 0, Some(1), true)
-        .await
-        .unwrap();
-    assert_eq!(result, "line1"); // No trailing newline
+    let operation = OperationContext::default();
+    let find = |skip_hardlinks| {
+        service.find_duplicate_files(
+            &dir1,
+            None,
+            None,
+            None,
+            None,
+            skip_hardlinks,
+            None,
+            &operation,
+        )
+    };
+    assert!(find(true).await.unwrap().is_empty());
+    assert_eq!(find(false).await.unwrap().len(), 1);
+
+    // a real copy is still reported, along with a single one of the hard links
+    let copy = create_temp_file(&dir1, "c.txt", "same content");
+    let result = find(true).await.unwrap();
+    assert_eq!(
+        sort_duplicate_groups(result),
+        vec![vec![
+            original.to_str().unwrap().to_string(),
+            copy.to_str().unwrap().to_string(),
+        ]]
+    );
 }
 
 #[tokio::test]
-async fn test_tail_file_windows_line_endings() {
+async fn test_resolve_duplicates() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path = create_test_file_with_line_ending(
-        &temp_dir.to_path_buf(),
-        "dir1/test.txt",
-        vec!["line1", "line2", "line3"],
-        "\r\n",
-    )
-    .await;
+    let dir1 = temp_dir.join("dir1");
+    let now = SystemTime::now();
+    let mut paths = vec![];
+    for (name, age) in [("old.txt", 300), ("new.txt", 100), ("mid.txt", 200)] {
+        let path = create_temp_file(&dir1, name, "same content");
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(now - std::time::Duration::from_secs(age))
+            .unwrap();
+        paths.push(path.to_str().unwrap().to_string());
+    }
 
-    let result = service
-        .read_file_lines(&file_path, 0, Some(2), true)
+    // the dry run only returns the plan
+    let plan = service
+        .resolve_duplicates(vec![paths.clone()], DuplicateAction::DeleteNewest, true)
         .await
         .unwrap();
-    assert_eq!(result, "line2\r\nline3"); // No trailing newline
+    assert_eq!(plan.len(), 1);
+    assert_eq!(plan[0].kept, paths[0]);
+    assert_eq!(plan[0].resolved, vec![paths[2].clone(), paths[1].clone()]);
+    assert_eq!(plan[0].reclaimed_bytes, 24);
+    assert!(paths.iter().all(|path| Path::new(path).exists()));
+
+    let applied = service
+        .resolve_duplicates(vec![paths.clone()], DuplicateAction::DeleteOldest, false)
+        .await
+        .unwrap();
+    assert_eq!(applied[0].kept, paths[1]);
+    assert!(Path::new(&paths[1]).exists());
+    assert!(!Path::new(&paths[0]).exists());
+    assert!(!Path::new(&paths[2]).exists());
 }
 
+#[cfg(unix)]
 #[tokio::test]
-async fn test_tail_file_invalid_path() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let invalid_path = temp_dir.join("dir2/test.txt"); // Outside allowed_dirs
+async fn test_resolve_duplicates_hardlink() {
+    use std::os::unix::fs::MetadataExt;
 
-    let result = service
-        .read_file_lines(&invalid_path, 0, Some(3), true)
-        .await;
-    assert!(result.is_err(), "Expected error for invalid path");
-}
-
-#[tokio::test]
-async fn test_read_file_lines_normal() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path = create_test_file(
-        &temp_dir,
-        "dir1/test.txt",
-        vec!["line1", "line2", "line3", "line4", "line5"],
-    )
-    .await;
+    let dir1 = temp_dir.join("dir1");
+    let first = create_temp_file(&dir1, "a.txt", "same content");
+    let second = create_temp_file(&dir1, "b.txt", "same content");
+    let operation = OperationContext::default();
+    let groups = service
+        .find_duplicate_files(&dir1, None, None, None, None, true, None, &operation)
+        .await
+        .unwrap();
 
-    let result = service
-        .read_file_lines(&file_path, 1, Some(2), false)
+    let applied = service
+        .resolve_duplicates(groups, DuplicateAction::Hardlink, false)
         .await
         .unwrap();
-    assert_eq!(result, "line2\nline3\n"); // No trailing newline
+    assert_eq!(applied[0].resolved.len(), 1);
+    assert!(applied[0].errors.is_empty());
+    assert_eq!(
+        fs::metadata(&first).unwrap().ino(),
+        fs::metadata(&second).unwrap().ino()
+    );
+    assert_eq!(fs::read_to_string(&second).unwrap(), "same content");
+    assert_eq!(fs::read_dir(&dir1).unwrap().count(), 2);
+    // hard links are no longer reported as duplicates
+    let groups = service
+        .find_duplicate_files(&dir1, None, None, None, None, true, None, &operation)
+        .await
+        .unwrap();
+    assert!(groups.is_empty());
 }
 
 #[tokio::test]
-async fn test_read_file_lines_empty_file() {
+async fn test_cleanup_old_files() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path = create_test_file(&temp_dir, "dir1/empty.txt", vec![]).await;
-
-    let result = service
-        .read_file_lines(&file_path, 0, Some(5), false)
+    let service = service.with_journal(temp_dir.join("journal"));
+    let dir1 = temp_dir.join("dir1");
+    let now = SystemTime::now();
+    for (name, age_days) in [("logs/old.log", 10), ("logs/older.log", 20), ("new.log", 1)] {
+        let path = create_temp_file(&dir1, name, "log line");
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(now - std::time::Duration::from_secs(age_days * 86_400))
+            .unwrap();
+    }
+    create_temp_file(&dir1, "notes.txt", "keep");
+    let older_than = std::time::Duration::from_secs(5 * 86_400);
+
+    // the dry run only returns the plan, oldest first
+    let plan = service
+        .cleanup_old_files(
+            &dir1,
+            "*.log".to_string(),
+            vec![],
+            older_than,
+            CleanupAction::Delete,
+            None,
+            true,
+            WalkOptions::default(),
+        )
+        .await
+        .unwrap();
+    let files: Vec<_> = plan.files.iter().map(|file| file.path.as_str()).collect();
+    assert_eq!(
+        files,
+        vec![
+            dir1.join("logs/older.log").to_str().unwrap(),
+            dir1.join("logs/old.log").to_str().unwrap()
+        ]
+    );
+    assert_eq!(plan.bytes, 16);
+    assert!(dir1.join("logs/old.log").exists());
+
+    let archive = dir1.join("archive");
+    let applied = service
+        .cleanup_old_files(
+            &dir1,
+            "*.log".to_string(),
+            vec![],
+            older_than,
+            CleanupAction::Archive,
+            Some(&archive),
+            false,
+            WalkOptions::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(applied.files.len(), 2);
+    assert!(!dir1.join("logs/old.log").exists());
+    assert_eq!(
+        fs::read_to_string(archive.join("logs/old.log")).unwrap(),
+        "log line"
+    );
+    assert!(dir1.join("new.log").exists());
+
+    // archived files are not cleaned up again, and the archive can be undone
+    let plan = service
+        .cleanup_old_files(
+            &dir1,
+            "*.log".to_string(),
+            vec![],
+            older_than,
+            CleanupAction::Archive,
+            Some(&archive),
+            true,
+            WalkOptions::default(),
+        )
         .await
         .unwrap();
-    assert_eq!(result, "");
+    assert!(plan.files.is_empty());
+    service.undo_last_operation().await.unwrap();
+    assert!(dir1.join("logs/old.log").exists());
+    assert!(!archive.exists());
+
+    let applied = service
+        .cleanup_old_files(
+            &dir1,
+            "*".to_string(),
+            vec![],
+            older_than,
+            CleanupAction::Delete,
+            None,
+            false,
+            WalkOptions::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(applied.files.len(), 2);
+    assert!(!dir1.join("logs/older.log").exists());
+    assert!(dir1.join("notes.txt").exists());
 }
 
 #[tokio::test]
-async fn test_read_file_lines_offset_beyond_file() {
+async fn test_find_duplicate_files_nested_duplicates() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path = create_test_file(&temp_dir, "dir1/test.txt", vec!["line1", "line2"]).await;
+    let content = "same content";
+    let file1 = create_temp_file(&temp_dir.join("dir1"), "file1.txt", content);
+    let file2 = create_temp_file(&temp_dir.join("dir1/subdir"), "file2.txt", content);
 
     let result = service
-        .read_file_lines(&file_path, 5, Some(3), false)
+        .find_duplicate_files(
+            &temp_dir.join("dir1"),
+            Some("*".to_string()),
+            None,
+            None,
+            None,
+            true,
+            None,
+            &OperationContext::default(),
+        )
         .await
         .unwrap();
-    assert_eq!(result, "");
+    let expected = vec![vec![
+        file1.to_str().unwrap().to_string(),
+        file2.to_str().unwrap().to_string(),
+    ]];
+    assert_eq!(result.len(), 1);
+    assert_eq!(
+        sort_duplicate_groups(result),
+        sort_duplicate_groups(expected)
+    );
 }
 
 #[tokio::test]
-async fn test_read_file_lines_no_limit() {
+async fn test_find_empty_directories_exclude_patterns() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path = create_test_file(
-        &temp_dir,
-        "dir1/test.txt",
-        vec!["line1", "line2", "line3", "line4"],
-    )
-    .await;
+    let dir1 = temp_dir.join("dir1");
+
+    // Create empty directory that should be included
+    let empty1 = dir1.join("empty1");
+    tokio::fs::create_dir_all(&empty1).await.unwrap();
+
+    // Create empty directory that matches exclude pattern
+    let empty2 = dir1.join("empty2");
+    tokio::fs::create_dir_all(&empty2).await.unwrap();
+
+    // Create non-empty directory
+    let non_empty = dir1.join("non_empty");
+    tokio::fs::create_dir_all(&non_empty).await.unwrap();
+    create_temp_file(&non_empty, "file.txt", "content");
+
+    // Ensure root dir1 exists
+    tokio::fs::create_dir_all(&dir1).await.unwrap();
 
+    // Call with exclude_patterns to exclude "*2*"
     let result = service
-        .read_file_lines(&file_path, 2, None, false)
+        .find_empty_directories(&dir1, Some(vec!["*2*".to_string()]))
         .await
         .unwrap();
-    assert_eq!(result, "line3\nline4"); // No trailing newline
+
+    // Expect only empty1, not empty2 or non_empty
+    let expected = vec![empty1.to_str().unwrap().to_string()];
+    assert_eq!(result.len(), 1);
+    assert_eq!(result, expected);
 }
 
 #[tokio::test]
-async fn test_read_file_lines_limit_zero() {
+async fn test_find_empty_directories_exclude_patterns_2() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path =
-        create_test_file(&temp_dir, "dir1/test.txt", vec!["line1", "line2", "line3"]).await;
+    let root_path = temp_dir.join("dir1");
 
-    let result = service
-        .read_file_lines(&file_path, 1, Some(0), false)
+    // Create empty directories
+    tokio::fs::create_dir_all(&root_path.join("empty1"))
+        .await
+        .unwrap();
+    tokio::fs::create_dir_all(&root_path.join("empty2.log"))
+        .await
+        .unwrap();
+    tokio::fs::create_dir_all(&root_path.join("empty3"))
         .await
         .unwrap();
-    assert_eq!(result, "");
-}
 
-#[tokio::test]
-async fn test_read_file_lines_exact_file_length() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path =
-        create_test_file(&temp_dir, "dir1/test.txt", vec!["line1", "line2", "line3"]).await;
+    // Create a non-empty directory to ensure it's not returned
+    tokio::fs::create_dir_all(&root_path.join("non_empty"))
+        .await
+        .unwrap();
+    tokio::fs::write(&root_path.join("non_empty/file.txt"), b"content")
+        .await
+        .unwrap();
 
+    // Test with exclude pattern "*.log"
+    let exclude_patterns = Some(vec!["*.log".to_string()]);
     let result = service
-        .read_file_lines(&file_path, 0, Some(3), false)
+        .find_empty_directories(&root_path, exclude_patterns)
         .await
         .unwrap();
-    assert_eq!(result, "line1\nline2\nline3"); // No trailing newline
+
+    let expected = [
+        root_path.join("empty1").to_str().unwrap().to_string(),
+        root_path.join("empty3").to_str().unwrap().to_string(),
+    ];
+
+    assert_eq!(result.len(), 2);
+    assert!(result.iter().all(|path| expected.contains(path)));
+    assert!(!result.iter().any(|path| path.contains("empty2.log")));
 }
 
 #[tokio::test]
-async fn test_read_file_lines_no_newline_at_end() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let file_path = create_temp_file(
-        &temp_dir.join("dir1"),
-        "test.txt",
-        "line1\nline2\nline3", // No newline at end
+// https://github.com/rust-mcp-stack/rust-mcp-filesystem/issues/50
+async fn test_search_files_brace_expanded_github_issue_50() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["public".to_string()]);
+    let temp_path = temp_dir.join("public").to_path_buf();
+
+    // create a node_modules directory that will be ignored
+    let node_modules_dir = temp_dir.join("node_modules");
+    create_temp_file(
+        &node_modules_dir,
+        "file1.js",
+        "{const name = 'Rust MCP SDK';}",
     );
+    create_temp_file(&node_modules_dir, "file2.json", r#"{"success":true}"#);
+    create_temp_file(&temp_path.join("target"), "dont_find.ts", "");
 
-    let result = service
-        .read_file_lines(&file_path, 1, Some(2), false)
-        .await
-        .unwrap();
-    assert_eq!(result, "line2\nline3"); // No trailing newline
-}
+    /*
+    temp_dir/
+    ├── file1.ts                  ✅ match
+    ├── file2.java                ✅ match
+    ├── file3.js                  ❌ no match
+    ├── sub1/
+    │   ├── file4.ts              ✅ match
+    │   ├── file5.java            ✅ match
+    │   └── file6.js              ❌ no match
+    └── sub2/
+        └── nested/
+            ├── file7.ts          ✅ match
+            └── file8.rs          ❌ no match
+    */
+    // Top-level files
+    create_temp_file(&temp_path, "file1.ts", "console.log('hello');");
+    create_temp_file(&temp_path, "file2.java", "public class Hello {}");
+    create_temp_file(&temp_path, "file3.js", "console.log('not included');");
 
-#[tokio::test]
-async fn test_read_file_lines_windows_line_endings() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    // sub1/
+    create_temp_file(
+        &temp_path.join("sub1"),
+        "file4.ts",
+        "console.log('sub ts');",
+    );
+    create_temp_file(&temp_path.join("sub1"), "file5.java", "class Sub {}");
+    create_temp_file(
+        &temp_path.join("sub1"),
+        "file6.js",
+        "console.log('sub js');",
+    );
 
-    // Override to use \r\n explicitly
-    let file_path = create_temp_file(
-        &temp_dir.join("dir1"),
-        "test.txt",
-        "line1\r\nline2\r\nline3",
+    // sub2/nested/
+    create_temp_file(
+        &temp_path.join("sub2/nested"),
+        "file7.ts",
+        "const deep = true;",
     );
+    create_temp_file(&temp_path.join("sub2/nested"), "file8.rs", "fn main() {}");
+
+    // Perform the glob search
+    // Perform the glob search
+    // let pattern = "**/*.java".to_string();
+    let pattern = "**/*.{java,ts}".to_string();
 
     let result = service
-        .read_file_lines(&file_path, 1, Some(2), false)
+        .search_files(
+            &temp_path,
+            pattern,
+            vec![
+                "/node_modules/".to_string(),
+                "/.git/".to_string(),
+                "/target/**".to_string(),
+            ],
+            None,
+            None,
+            None,
+            WalkOptions::default(),
+        )
         .await
         .unwrap();
-    assert_eq!(result, "line2\r\nline3"); // No trailing newline
-}
 
-#[tokio::test]
-async fn test_read_file_lines_invalid_path() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let invalid_path = temp_dir.join("dir2/test.txt"); // Outside allowed_dirs
+    let names: Vec<_> = result
+        .into_iter()
+        .map(|e| e.file_name().to_str().unwrap().to_string())
+        .collect();
 
-    let result = service
-        .read_file_lines(&invalid_path, 0, Some(3), false)
-        .await;
-    assert!(result.is_err(), "Expected error for invalid path");
+    assert!(names.iter().all(|name| {
+        [
+            "file4.ts",
+            "file5.java",
+            "file1.ts",
+            "file2.java",
+            "file7.ts",
+        ]
+        .contains(&name.as_str())
+    }));
+
+    assert_eq!(names.len(), 5);
 }
 
 #[test]
-fn test_extract_snippet_bug_37() {
-    let (_, service, _) = setup_service(vec!["dir_search".to_string()]);
-
-    // Input string :  ’ starts spans 3 bytes: 0xE2 0x80 0x99.
-    let line = "If and when that happens, however, we will not be able to declare victory quite yet. Defeating the open conspiracy to deprive students of physical access to books will do little to counteract the more diffuse confluence of forces that are depriving students of their education with a curly apostrophe ’ followed by more text";
-
-    let curly_pos = line.find("’").unwrap();
+fn test_fuzzy_score_ranking() {
+    assert!(fuzzy_score("hndlr", "src/handler.rs").is_some());
+    assert!(fuzzy_score("xyz", "src/handler.rs").is_none());
+    assert!(fuzzy_score("rldnh", "src/handler.rs").is_none());
+
+    // consecutive and boundary matches beat scattered ones
+    let exact = fuzzy_score("handler", "src/handler.rs").unwrap();
+    let scattered = fuzzy_score("handler", "src/h_a_n_d_l_e_r.rs").unwrap();
+    assert!(exact > scattered);
+
+    // matches in the file name beat matches in directory names
+    let in_file_name = fuzzy_score("cli", "src/cli.rs").unwrap();
+    let in_dir_name = fuzzy_score("cli", "cli/src/main.rs").unwrap();
+    assert!(in_file_name > in_dir_name);
+
+    // case-insensitive with camelCase boundaries
+    assert!(fuzzy_score("uc", "UserController.ts").is_some());
+}
 
-    println!("Curly apostrophe at byte: {curly_pos}"); //position: 301
+#[tokio::test]
+async fn test_fuzzy_find_files() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["project".to_string()]);
+    let root = temp_dir.join("project");
+    create_temp_file(&root.join("src"), "user_controller.ts", "");
+    create_temp_file(&root.join("src"), "user_service.ts", "");
+    create_temp_file(&root.join("docs"), "readme.md", "");
 
-    // Simulate a match just after the curly apostrophe
-    let match_start = curly_pos + 3; // Start of "followed"
-    let match_end = match_start + 8; // End of "followed"
-    let match_result = Match::new(match_start, match_end);
+    let matches = service
+        .fuzzy_find_files(&root, "usrctrl", None, 10)
+        .await
+        .unwrap();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].path, root.join("src").join("user_controller.ts"));
 
-    // Parameters to make snippet_start in extract_snippet() function to land inside ’ (e.g., byte 302)
-    let backward_chars = match_start - (curly_pos + 1); // Land on second byte of ’
-    println!(
-        "match_start: {match_start}, match_end: {match_end},  backward_chars  {backward_chars} "
-    );
+    let matches = service
+        .fuzzy_find_files(&root, "user", None, 1)
+        .await
+        .unwrap();
+    assert_eq!(matches.len(), 1);
+}
 
-    let result = service.extract_snippet(
-        line,
-        match_result,
-        Some(5), // max_length
-        Some(backward_chars),
-    );
+#[tokio::test]
+async fn test_build_content_index() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["project".to_string()]);
+    let service = service.with_index_dir(temp_dir.join("index"));
+    let root = temp_dir.join("project");
+    let with_needle = create_temp_file(&root, "a.txt", "some needle here");
+    let without_needle = create_temp_file(&root, "b.txt", "nothing to see");
+    create_temp_file(&root, "c.bin", "binary\0content");
+
+    let summary = service.build_content_index(&root).await.unwrap();
+    assert_eq!(summary.indexed_files, 2);
+    assert!(summary.index_path.exists());
+    assert!(summary.index_path.starts_with(temp_dir.join("index")));
+
+    let index = service
+        .load_content_index(&root.join("a.txt"))
+        .expect("index should cover files under its root");
+    let filter = index.filter_for_query("NEEDLE").unwrap();
+    assert!(!filter.can_skip(&with_needle, fs::metadata(&with_needle).ok().as_ref()));
+    assert!(filter.can_skip(&without_needle, fs::metadata(&without_needle).ok().as_ref()));
+
+    // queries too short or non-ascii don't use the index
+    assert!(index.filter_for_query("ne").is_none());
+    assert!(index.filter_for_query("né").is_none());
+}
 
-    println!("Snippet: {result}");
+#[tokio::test]
+async fn test_content_index_folds_non_ascii_case_variants() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["project".to_string()]);
+    let service = service.with_index_dir(temp_dir.join("index"));
+    let root = temp_dir.join("project");
+    let long_s = create_temp_file(&root, "a.txt", "a \u{17F}ecret here");
+    let kelvin = create_temp_file(&root, "b.txt", "300 \u{212A}elvin");
+    let neither = create_temp_file(&root, "c.txt", "nothing to see");
+
+    service.build_content_index(&root).await.unwrap();
+    let index = service.load_content_index(&root).unwrap();
+    // the same index is reused until its file changes
+    assert!(std::sync::Arc::ptr_eq(
+        &index,
+        &service.load_content_index(&root.join("a.txt")).unwrap()
+    ));
+
+    let filter = index.filter_for_query("SECRET").unwrap();
+    assert!(!filter.can_skip(&long_s, fs::metadata(&long_s).ok().as_ref()));
+    assert!(filter.can_skip(&neither, fs::metadata(&neither).ok().as_ref()));
+    let filter = index.filter_for_query("kelvin").unwrap();
+    assert!(!filter.can_skip(&kelvin, fs::metadata(&kelvin).ok().as_ref()));
+
+    for (query, expected) in [("secret", "a.txt"), ("KELVIN", "b.txt")] {
+        let results = service
+            .search_files_content(
+                &root,
+                "*.txt",
+                query,
+                false,
+                None,
+                None,
+                None,
+                WalkOptions::default(),
+                &OperationContext::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1, "{query}");
+        assert!(results[0].file_path.ends_with(expected));
+    }
+
+    // rebuilding replaces the cached index
+    create_temp_file(&root, "d.txt", "another secret");
+    service.build_content_index(&root).await.unwrap();
+    assert_eq!(service.load_content_index(&root).unwrap().files.len(), 4);
 }
 
 #[tokio::test]
-async fn test_calculate_directory_size_normal() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    create_temp_file(&temp_dir.join("dir1"), "file1.txt", "content1");
-    create_temp_file(&temp_dir.join("dir1"), "file2.txt", "content22");
+async fn test_search_files_content_with_stale_index() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["project".to_string()]);
+    let service = service.with_index_dir(temp_dir.join("index"));
+    let root = temp_dir.join("project");
+    create_temp_file(&root, "a.txt", "some needle here");
+    let modified = create_temp_file(&root, "b.txt", "nothing to see");
 
-    let size = service
-        .calculate_directory_size(&temp_dir.join("dir1"))
+    service.build_content_index(&root).await.unwrap();
+
+    // files modified or added after indexing must still be searched
+    fs::write(&modified, "another needle, modified after indexing").unwrap();
+    create_temp_file(&root, "c.txt", "a new needle");
+
+    let results = service
+        .search_files_content(
+            &root,
+            "*.txt",
+            "needle",
+            false,
+            None,
+            None,
+            None,
+            WalkOptions::default(),
+            &OperationContext::default(),
+        )
         .await
         .unwrap();
-    assert_eq!(size, 17); // "content1" (8 bytes) + "content22" (9 bytes) = 17 bytes
+    let mut names: Vec<_> = results
+        .iter()
+        .map(|r| {
+            r.file_path
+                .file_name()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["a.txt", "b.txt", "c.txt"]);
 }
 
 #[tokio::test]
-async fn test_calculate_directory_size_empty_dir() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    create_sub_dir(&temp_dir, "dir1").await;
+async fn test_git_diff() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["repo".to_string()]);
+    let repo = temp_dir.join("repo");
+    let git = |args: &[&str]| run_git(&repo, args);
+    git(&["init", "-q"]);
+    let tracked = create_temp_file(&repo, "src/lib.rs", "fn a() {}\nfn b() {}\n");
+    create_temp_file(&repo, "big.txt", "x\n");
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "init"]);
+
+    fs::write(&tracked, "fn a() {}\nfn c() {}\n").unwrap();
+    fs::write(repo.join("big.txt"), "y\n".repeat(1000)).unwrap();
+    create_temp_file(&repo, "new.txt", "new");
+
+    let diff = service
+        .git_diff(std::slice::from_ref(&repo), false, None)
+        .await
+        .unwrap();
+    let mut files: Vec<_> = diff
+        .files
+        .iter()
+        .map(|f| (f.path.as_str(), f.additions, f.deletions, f.untracked))
+        .collect();
+    files.sort();
+    assert_eq!(
+        files,
+        vec![
+            ("big.txt", Some(1000), Some(1), false),
+            ("new.txt", None, None, true),
+            ("src/lib.rs", Some(1), Some(1), false),
+        ]
+    );
+    assert!(diff.diff.contains("+fn c() {}"));
+    assert!(diff.omitted_files.is_empty());
 
-    let size = service
-        .calculate_directory_size(&temp_dir.join("dir1"))
+    // the size budget leaves out whole files
+    let diff = service
+        .git_diff(std::slice::from_ref(&repo), false, Some(500))
         .await
         .unwrap();
-    assert_eq!(size, 0);
+    assert_eq!(diff.omitted_files, vec!["big.txt"]);
+    assert!(diff.diff.contains("+fn c() {}"));
+
+    // scoped to a path, staged changes only
+    git(&["add", "src/lib.rs"]);
+    let diff = service
+        .git_diff(std::slice::from_ref(&tracked), true, None)
+        .await
+        .unwrap();
+    assert_eq!(diff.files.len(), 1);
+    assert_eq!(diff.files[0].path, "src/lib.rs");
+    assert!(diff.diff.contains("-fn b() {}"));
 }
 
+#[cfg(unix)]
 #[tokio::test]
-async fn test_calculate_directory_size_nested_files() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    create_temp_file(&temp_dir.join("dir1"), "file1.txt", "content1");
-    create_temp_file(&temp_dir.join("dir1/subdir"), "file2.txt", "content22");
+async fn test_git_ignores_commands_in_repository_config() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["repo".to_string()]);
+    let repo = temp_dir.join("repo");
+    run_git(&repo, &["init", "-q"]);
+    let file = create_temp_file(&repo, "secret.txt", "v1\n");
+    run_git(&repo, &["add", "."]);
+    run_git(&repo, &["commit", "-q", "-m", "init"]);
+    fs::write(&file, "v2\n").unwrap();
+
+    // every command git could be tricked into running leaves a marker file
+    let marker = |name: &str| temp_dir.join(name);
+    create_temp_file(&repo, ".gitattributes", "*.txt diff=pwn\n");
+    let mut config = fs::OpenOptions::new()
+        .append(true)
+        .open(repo.join(".git").join("config"))
+        .unwrap();
+    writeln!(
+        config,
+        "[core]\n\tfsmonitor = touch {}\n[diff]\n\texternal = touch {}\n[diff \"pwn\"]\n\ttextconv = touch {} && cat",
+        marker("fsmonitor").display(),
+        marker("external").display(),
+        marker("textconv").display(),
+    )
+    .unwrap();
+    drop(config);
 
-    let size = service
-        .calculate_directory_size(&temp_dir.join("dir1"))
+    let diff = service
+        .git_diff(std::slice::from_ref(&repo), false, None)
         .await
         .unwrap();
-    assert_eq!(size, 17); // "content1" (8 bytes) + "content22" (9 bytes) = 17 bytes
+    assert!(diff.diff.contains("+v2"));
+    service.git_blame(&file, None, None).await.unwrap();
+    service.git_log(&file, None).await.unwrap();
+
+    for name in ["fsmonitor", "external", "textconv"] {
+        assert!(!marker(name).exists(), "git ran the {name} command");
+    }
 }
 
 #[tokio::test]
-async fn test_calculate_directory_size_invalid_path() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let invalid_path = temp_dir.join("dir2");
+async fn test_git_blame() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["repo".to_string()]);
+    let repo = temp_dir.join("repo");
+    run_git(&repo, &["init", "-q"]);
+    let file = create_temp_file(&repo, "main.rs", "line 1\nline 2\nline 3\n");
+    run_git(&repo, &["add", "."]);
+    run_git(&repo, &["commit", "-q", "-m", "first commit"]);
+    fs::write(&file, "line 1\nchanged\nline 3\n").unwrap();
+    run_git(
+        &repo,
+        &["commit", "-q", "-am", "second commit\n\nwith body"],
+    );
 
-    let result = service.calculate_directory_size(&invalid_path).await;
-    assert!(result.is_err(), "Expected error for invalid path");
+    let lines = service.git_blame(&file, Some(2), Some(3)).await.unwrap();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].line, 2);
+    assert_eq!(lines[0].content, "changed");
+    assert_eq!(lines[0].summary, "second commit");
+    assert_eq!(lines[0].author, "test");
+    assert_eq!(lines[0].author_email, "test@example.com");
+    assert_eq!(lines[1].content, "line 3");
+    assert_eq!(lines[1].summary, "first commit");
+    assert_ne!(lines[0].commit, lines[1].commit);
+
+    let lines = service.git_blame(&file, None, None).await.unwrap();
+    assert_eq!(lines.len(), 3);
+
+    let result = service.git_blame(&file, Some(3), Some(2)).await;
+    assert!(result.is_err());
 }
 
 #[tokio::test]
-async fn test_find_empty_directories_normal() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    create_sub_dir(&temp_dir, "dir1/empty1").await;
-    create_sub_dir(&temp_dir, "dir1/empty2").await;
-    create_temp_file(&temp_dir.join("dir1/non_empty"), "file.txt", "content");
-
-    let result = service
-        .find_empty_directories(&temp_dir.join("dir1"), None)
+async fn test_git_log() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["repo".to_string()]);
+    let repo = temp_dir.join("repo");
+    run_git(&repo, &["init", "-q"]);
+    create_temp_file(&repo, "old.txt", "v1");
+    create_temp_file(&repo, "other.txt", "other");
+    run_git(&repo, &["add", "."]);
+    run_git(&repo, &["commit", "-q", "-m", "add files"]);
+    run_git(&repo, &["mv", "old.txt", "new.txt"]);
+    run_git(&repo, &["commit", "-q", "-m", "rename"]);
+    fs::write(repo.join("new.txt"), "v2").unwrap();
+    run_git(&repo, &["commit", "-q", "-am", "update"]);
+
+    let commits = service.git_log(&repo.join("new.txt"), None).await.unwrap();
+    let subjects: Vec<_> = commits.iter().map(|c| c.subject.as_str()).collect();
+    assert_eq!(subjects, vec!["update", "rename", "add files"]);
+    assert_eq!(commits[0].author, "test");
+    assert_eq!(commits[0].hash.len(), 40);
+
+    let commits = service
+        .git_log(&repo.join("other.txt"), None)
         .await
         .unwrap();
-    let expected = [
-        temp_dir.join("dir1/empty1").to_str().unwrap().to_string(),
-        temp_dir.join("dir1/empty2").to_str().unwrap().to_string(),
-    ];
-    assert_eq!(result.len(), 2);
-    assert!(result.iter().all(|path| expected.contains(path)));
+    assert_eq!(commits.len(), 1);
+
+    let commits = service.git_log(&repo, Some(2)).await.unwrap();
+    assert_eq!(commits.len(), 2);
 }
 
 #[tokio::test]
-async fn test_find_empty_directories_no_empty_dirs() {
+async fn test_watch_directory() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    create_temp_file(&temp_dir.join("dir1/dir1"), "file.txt", "content");
-    create_temp_file(&temp_dir.join("dir1/dir2"), "file.txt", "content");
+    let dir = temp_dir.join("dir1");
+    let mut events = service.subscribe_file_changes();
+
+    let (watched, started) = service.watch_directory(&dir, true).await.unwrap();
+    assert!(started);
+    assert_eq!(service.watched_directories(), vec![watched.clone()]);
+    let (_, started) = service.watch_directory(&dir, true).await.unwrap();
+    assert!(!started);
+
+    let file = create_temp_file(&dir, "watched.txt", "content");
+    let event = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        loop {
+            let event = events.recv().await.unwrap();
+            if event.path == file {
+                return event;
+            }
+        }
+    })
+    .await
+    .expect("no change event received");
+    assert_eq!(event.kind, FileChangeKind::Created);
+    assert_eq!(event.watch_root, watched);
+
+    let (_, stopped) = service.unwatch_directory(&dir).await.unwrap();
+    assert!(stopped);
+    assert!(service.watched_directories().is_empty());
+    let (_, stopped) = service.unwatch_directory(&dir).await.unwrap();
+    assert!(!stopped);
+
+    // only directories within the allowed directories can be watched
+    let result = service.watch_directory(&temp_dir, true).await;
+    assert!(result.is_err());
+    let result = service.watch_directory(&file, true).await;
+    assert!(result.is_err());
+}
 
-    let result = service
-        .find_empty_directories(&temp_dir.join("dir1"), None)
-        .await
-        .unwrap();
-    assert_eq!(result, Vec::<String>::new());
+#[test]
+fn test_file_uri_round_trip() {
+    let path = Path::new("/tmp/dir with space/100%#file.txt");
+    let uri = path_to_file_uri(path);
+    assert_eq!(uri, "file:///tmp/dir%20with%20space/100%25%23file.txt");
+    assert_eq!(file_uri_to_path(&uri).unwrap(), path);
+    assert!(file_uri_to_path("/tmp/file.txt").is_err());
+    assert!(file_uri_to_path("file:///tmp/bad%2").is_err());
 }
 
 #[tokio::test]
-async fn test_find_empty_directories_empty_root() {
+async fn test_list_file_resources() {
+    let (temp_dir, service, _allowed_dirs) =
+        setup_service(vec!["dir1".to_string(), "dir2".to_string()]);
+    create_temp_file(&temp_dir.join("dir1"), "a.txt", "a");
+    create_temp_file(&temp_dir.join("dir1"), "sub/b.txt", "bb");
+    create_temp_file(&temp_dir.join("dir2"), "c.txt", "c");
+
+    let (page, next) = service.list_file_resources(0, 2).await.unwrap();
+    let mut names: Vec<_> = page.iter().map(|r| r.name.clone()).collect();
+    assert_eq!(page.len(), 2);
+    assert_eq!(next, Some(2));
+
+    let (page, next) = service.list_file_resources(2, 2).await.unwrap();
+    assert_eq!(page.len(), 1);
+    assert_eq!(next, None);
+    names.extend(page.iter().map(|r| r.name.clone()));
+    names.sort();
+    assert_eq!(names, vec!["a.txt", "c.txt", "sub/b.txt"]);
+
+    let (page, _) = service.list_file_resources(0, 10).await.unwrap();
+    let b = page.iter().find(|r| r.name == "sub/b.txt").unwrap();
+    assert_eq!(b.size, 2);
+    assert_eq!(b.uri, path_to_file_uri(&temp_dir.join("dir1/sub/b.txt")));
+}
+
+#[tokio::test]
+async fn test_read_file_resource() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    create_sub_dir(&temp_dir, "dir1").await;
+    let text = create_temp_file(&temp_dir.join("dir1"), "notes.md", "# Notes");
+    let binary = temp_dir.join("dir1").join("image.png");
+    fs::write(
+        &binary,
+        [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0],
+    )
+    .unwrap();
 
-    let result = service
-        .find_empty_directories(&temp_dir.join("dir1"), None)
+    let (mime, content) = service
+        .read_file_resource(&path_to_file_uri(&text))
         .await
         .unwrap();
-    assert_eq!(result, Vec::<String>::new());
+    assert_eq!(mime, "text/markdown");
+    assert_eq!(content, ResourceContent::Text("# Notes".to_string()));
+
+    let (mime, content) = service
+        .read_file_resource(&path_to_file_uri(&binary))
+        .await
+        .unwrap();
+    assert_eq!(mime, "image/png");
+    assert!(matches!(content, ResourceContent::Blob(_)));
+
+    let outside = create_temp_file(&temp_dir, "secret.txt", "secret");
+    let result = service
+        .read_file_resource(&path_to_file_uri(&outside))
+        .await;
+    assert!(result.is_err());
+    let result = service
+        .read_file_resource(&path_to_file_uri(&temp_dir.join("dir1")))
+        .await;
+    assert!(result.is_err());
 }
 
 #[tokio::test]
-async fn test_find_empty_directories_invalid_path() {
+async fn test_subscribe_resource() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let invalid_path = temp_dir.join("dir2");
+    let dir = temp_dir.join("dir1");
+    let file = create_temp_file(&dir, "subscribed.txt", "v1");
+    let uri = path_to_file_uri(&file);
+    let mut updates = service.subscribe_resource_updates();
+
+    assert!(service.subscribe_resource(&uri).await.unwrap());
+    assert!(!service.subscribe_resource(&uri).await.unwrap());
+
+    // changes of other files in the same directory are not reported
+    create_temp_file(&dir, "other.txt", "other");
+    fs::write(&file, "v2").unwrap();
+    let updated = tokio::time::timeout(std::time::Duration::from_secs(5), updates.recv())
+        .await
+        .expect("no resource update received")
+        .unwrap();
+    assert_eq!(updated, uri);
 
-    let result = service.find_empty_directories(&invalid_path, None).await;
-    assert!(result.is_err(), "Expected error for invalid path");
+    assert!(service.unsubscribe_resource(&uri).await.unwrap());
+    assert!(!service.unsubscribe_resource(&uri).await.unwrap());
+
+    let result = service.subscribe_resource(&path_to_file_uri(&dir)).await;
+    assert!(result.is_err());
+    let outside = create_temp_file(&temp_dir, "outside.txt", "outside");
+    let result = service
+        .subscribe_resource(&path_to_file_uri(&outside))
+        .await;
+    assert!(result.is_err());
 }
 
 #[tokio::test]
-async fn test_find_duplicate_files_normal() {
+async fn test_tail_file() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let content = "same content";
-    let file1 = create_temp_file(&temp_dir.join("dir1"), "file1.txt", content);
-    let file2 = create_temp_file(&temp_dir.join("dir1"), "file2.txt", content);
-    let _file3 = create_temp_file(&temp_dir.join("dir1"), "file3.txt", "different");
+    let log = create_temp_file(&temp_dir.join("dir1"), "app.log", "one\ntwo\nthree\n");
 
-    let result = service
-        .find_duplicate_files(
-            &temp_dir.join("dir1"),
-            Some("*".to_string()),
-            None,
-            None,
-            None,
-        )
+    let chunk = service.tail_file(&log, None, 2, None).await.unwrap();
+    assert_eq!(chunk.content, "two\nthree\n");
+    assert_eq!(chunk.position.offset, 14);
+    assert!(!chunk.has_more);
+
+    // nothing new yet
+    let chunk = service
+        .tail_file(&log, Some(chunk.position), 2, None)
         .await
         .unwrap();
-    let expected = vec![vec![
-        file1.to_str().unwrap().to_string(),
-        file2.to_str().unwrap().to_string(),
-    ]];
+    assert_eq!(chunk.content, "");
+    assert_eq!(chunk.position.offset, 14);
+
+    // incomplete lines are held back until they are terminated
+    let mut file = fs::OpenOptions::new().append(true).open(&log).unwrap();
+    file.write_all(b"four\nfi").unwrap();
+    let chunk = service
+        .tail_file(&log, Some(chunk.position), 2, None)
+        .await
+        .unwrap();
+    assert_eq!(chunk.content, "four\n");
+    file.write_all(b"ve\nsix\n").unwrap();
 
-    assert_eq!(result.len(), 1);
-    assert_eq!(
-        sort_duplicate_groups(result),
-        sort_duplicate_groups(expected)
-    );
+    // the byte budget splits the remaining content at line boundaries
+    let chunk = service
+        .tail_file(&log, Some(chunk.position), 2, Some(6))
+        .await
+        .unwrap();
+    assert_eq!(chunk.content, "five\n");
+    assert!(chunk.has_more);
+    let position = chunk.position.clone();
+    let chunk = service
+        .tail_file(&log, Some(chunk.position), 2, None)
+        .await
+        .unwrap();
+    assert_eq!(chunk.content, "six\n");
+    assert!(!chunk.rotated);
+
+    // truncation restarts from the beginning
+    fs::write(&log, "new\n").unwrap();
+    let chunk = service
+        .tail_file(&log, Some(position), 2, None)
+        .await
+        .unwrap();
+    assert!(chunk.rotated);
+    assert_eq!(chunk.content, "new\n");
 }
 
 #[tokio::test]
-async fn test_find_duplicate_files_no_duplicates() {
+async fn test_search_files_content_reports_progress() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    create_temp_file(&temp_dir.join("dir1"), "file1.txt", "content1");
-    create_temp_file(&temp_dir.join("dir1"), "file2.txt", "content2");
+    let dir = temp_dir.join("dir1");
+    create_temp_file(&dir, "a.txt", "needle\nneedle");
+    create_temp_file(&dir, "b.txt", "needle");
+    create_temp_file(&dir, "c.txt", "nothing");
 
-    let result = service
-        .find_duplicate_files(
-            &temp_dir.join("dir1"),
-            Some("*".to_string()),
+    let updates = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+    let operation = OperationContext::default().with_progress({
+        let updates = updates.clone();
+        move |update| updates.lock().unwrap().push(update)
+    });
+
+    service
+        .search_files_content(
+            &dir,
+            "*.txt",
+            "needle",
+            false,
             None,
             None,
             None,
+            WalkOptions::default(),
+            &operation,
         )
         .await
         .unwrap();
-    assert_eq!(result, Vec::<Vec<String>>::new());
+
+    let updates = updates.lock().unwrap();
+    let last = updates.last().expect("a final progress update");
+    assert_eq!(last.progress, 3.0);
+    assert_eq!(
+        last.message.as_deref(),
+        Some("Scanned 3 files, 3 matches so far")
+    );
 }
 
 #[tokio::test]
-async fn test_find_duplicate_files_with_pattern() {
+async fn test_cancelled_operations_abort() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let content = "same content";
-    create_temp_file(&temp_dir.join("dir1"), "file1.txt", content);
-    create_temp_file(&temp_dir.join("dir1"), "file2.txt", content);
-    create_temp_file(&temp_dir.join("dir1"), "file3.log", content);
+    let dir = temp_dir.join("dir1");
+    create_temp_file(&dir, "a.txt", "needle");
+    create_temp_file(&dir, "b.txt", "needle");
+
+    let token = CancellationToken::new();
+    token.cancel();
+    let operation = OperationContext::default().with_cancellation(token);
 
     let result = service
-        .find_duplicate_files(
-            &temp_dir.join("dir1"),
-            Some("*.txt".to_string()),
+        .search_files_content(
+            &dir,
+            "*.txt",
+            "needle",
+            false,
             None,
             None,
             None,
+            WalkOptions::default(),
+            &operation,
         )
-        .await
-        .unwrap();
-    assert_eq!(result.len(), 1);
-    assert!(result[0].iter().all(|p| p.ends_with(".txt")));
+        .await;
+    assert!(matches!(result, Err(ServiceError::Cancelled)));
+
+    let result = service
+        .find_duplicate_files(&dir, None, None, None, None, true, None, &operation)
+        .await;
+    assert!(matches!(result, Err(ServiceError::Cancelled)));
 }
 
 #[tokio::test]
-async fn test_find_duplicate_files_with_exclude_patterns() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let content = "same content";
-    create_temp_file(&temp_dir.join("dir1"), "file1.txt", content);
-    create_temp_file(&temp_dir.join("dir1"), "file2.txt", content);
-    create_temp_file(&temp_dir.join("dir1"), "file3.log", content);
+async fn test_directory_modes() {
+    let temp_dir = get_temp_dir();
+    let src = temp_dir.join("src");
+    let vendor = temp_dir.join("vendor");
+    let generated = vendor.join("generated");
+    fs::create_dir_all(&src).unwrap();
+    fs::create_dir_all(&generated).unwrap();
+    let service =
+        FileSystemService::try_new(&[src.display().to_string(), vendor.display().to_string()])
+            .unwrap()
+            .with_directory_modes(
+                DirectoryMode::ReadWrite,
+                vec![
+                    (vendor.display().to_string(), DirectoryMode::ReadOnly),
+                    (generated.display().to_string(), DirectoryMode::ReadWrite),
+                ],
+            );
 
+    service
+        .write_file(&src.join("a.txt"), &"a".to_string(), false, None)
+        .await
+        .unwrap();
     let result = service
-        .find_duplicate_files(
-            &temp_dir.join("dir1"),
-            Some("*".to_string()),
-            Some(vec!["*.log".to_string()]),
-            None,
-            None,
-        )
+        .write_file(&vendor.join("b.txt"), &"b".to_string(), false, None)
+        .await;
+    assert!(matches!(result, Err(ServiceError::ReadOnlyPath(_))));
+    assert!(!vendor.join("b.txt").exists());
+    service
+        .write_file(&generated.join("c.txt"), &"c".to_string(), false, None)
         .await
         .unwrap();
-    assert_eq!(result.len(), 1);
-    assert!(result[0].iter().all(|p| !p.ends_with(".log")));
-}
-
-#[tokio::test]
-async fn test_find_duplicate_files_size_filters() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let content = "same content"; // 12 bytes
-    create_temp_file(&temp_dir.join("dir1"), "file1.txt", content);
-    create_temp_file(&temp_dir.join("dir1"), "file2.txt", content);
-    create_temp_file(&temp_dir.join("dir1"), "file3.txt", "short"); // 5 bytes
 
+    // reads are still allowed in read-only directories
+    create_temp_file(&vendor, "d.txt", "d");
+    assert_eq!(
+        service.read_text_file(&vendor.join("d.txt")).await.unwrap(),
+        "d"
+    );
     let result = service
-        .find_duplicate_files(
-            &temp_dir.join("dir1"),
-            Some("*".to_string()),
-            None,
-            Some(10), // min 10 bytes
-            Some(15), // max 15 bytes
+        .move_file(
+            &vendor.join("d.txt"),
+            &src.join("d.txt"),
+            OverwritePolicy::Error,
         )
-        .await
-        .unwrap();
-    assert_eq!(result.len(), 1);
-    assert_eq!(result[0].len(), 2); // file1.txt and file2.txt
+        .await;
+    assert!(matches!(result, Err(ServiceError::ReadOnlyPath(_))));
 }
 
 #[tokio::test]
-async fn test_find_duplicate_files_empty_dir() {
+async fn test_deny_globs() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    create_sub_dir(&temp_dir, "dir1").await;
+    let service = service
+        .with_deny_globs(&["**/.env".to_string(), "secrets/**".to_string()])
+        .unwrap();
+    let dir = temp_dir.join("dir1");
+    fs::create_dir_all(dir.join("secrets")).unwrap();
+    create_temp_file(&dir, ".env", "TOKEN=needle");
+    create_temp_file(&dir.join("secrets"), "key.txt", "needle");
+    create_temp_file(&dir, "notes.txt", "needle");
+
+    assert!(service.read_text_file(&dir.join(".env")).await.is_err());
+    assert!(
+        service
+            .read_text_file(&dir.join("secrets").join("key.txt"))
+            .await
+            .is_err()
+    );
+    assert!(
+        service
+            .write_file(
+                &dir.join("secrets").join("new.txt"),
+                &"x".to_string(),
+                false,
+                None,
+            )
+            .await
+            .is_err()
+    );
+    assert_eq!(
+        service
+            .read_text_file(&dir.join("notes.txt"))
+            .await
+            .unwrap(),
+        "needle"
+    );
 
-    let result = service
-        .find_duplicate_files(
-            &temp_dir.join("dir1"),
-            Some("*".to_string()),
+    // denied files are skipped by searches as well
+    let results = service
+        .search_files_content(
+            &dir,
+            "*",
+            "needle",
+            false,
             None,
             None,
             None,
+            WalkOptions::default(),
+            &OperationContext::default(),
         )
         .await
         .unwrap();
-    assert_eq!(result, Vec::<Vec<String>>::new());
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].file_path, dir.join("notes.txt"));
 }
 
+#[cfg(unix)]
 #[tokio::test]
-async fn test_find_duplicate_files_invalid_path() {
+async fn test_deny_globs_through_symlinked_directory() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let invalid_path = temp_dir.join("dir2");
+    let service = service
+        .with_deny_globs(&["**/secrets/**".to_string()])
+        .unwrap();
+    let dir = temp_dir.join("dir1");
+    fs::create_dir_all(dir.join("secrets")).unwrap();
+    std::os::unix::fs::symlink(dir.join("secrets"), dir.join("link")).unwrap();
+
+    // the new file does not exist yet, so only its parent resolves into the denied tree
+    let result = service
+        .write_file(
+            &dir.join("link").join("new.env"),
+            &"TOKEN=x".to_string(),
+            false,
+            None,
+        )
+        .await;
+    assert!(matches!(result, Err(ServiceError::PathDenied(_))));
+    assert!(!dir.join("secrets").join("new.env").exists());
 
     let result = service
-        .find_duplicate_files(&invalid_path, Some("*".to_string()), None, None, None)
+        .create_directory(&dir.join("link").join("nested").join("deeper"))
         .await;
-    assert!(result.is_err(), "Expected error for invalid path");
+    assert!(matches!(result, Err(ServiceError::PathDenied(_))));
+}
+
+#[tokio::test]
+async fn test_deny_globs_hide_git_diff_and_watch_events() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["repo".to_string()]);
+    let service = service
+        .with_deny_globs(&["**/.env".to_string(), "secrets/**".to_string()])
+        .unwrap();
+    let repo = temp_dir.join("repo");
+    run_git(&repo, &["init", "-q"]);
+    create_temp_file(&repo, ".env", "TOKEN=old");
+    create_temp_file(&repo.join("secrets"), "key.txt", "old key");
+    create_temp_file(&repo, "notes.txt", "old notes");
+    run_git(&repo, &["add", "."]);
+    run_git(&repo, &["commit", "-q", "-m", "init"]);
+    fs::write(repo.join(".env"), "TOKEN=new").unwrap();
+    fs::write(repo.join("secrets").join("key.txt"), "new key").unwrap();
+    fs::write(repo.join("notes.txt"), "new notes").unwrap();
+    create_temp_file(&repo.join("secrets"), "untracked.txt", "secret");
+
+    let diff = service
+        .git_diff(std::slice::from_ref(&repo), false, None)
+        .await
+        .unwrap();
+    let files: Vec<_> = diff.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(files, vec!["notes.txt"]);
+    assert!(diff.diff.contains("+new notes"));
+    assert!(!diff.diff.contains("TOKEN"));
+    assert!(!diff.diff.contains("key"));
+
+    let mut events = service.subscribe_file_changes();
+    service.watch_directory(&repo, true).await.unwrap();
+    create_temp_file(&repo.join("secrets"), "other.txt", "secret");
+    let visible = create_temp_file(&repo, "visible.txt", "content");
+    let reported = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        let mut reported = vec![];
+        loop {
+            let event = events.recv().await.unwrap();
+            reported.push(event.path.clone());
+            if event.path == visible {
+                return reported;
+            }
+        }
+    })
+    .await
+    .expect("no change event received");
+    assert!(reported.iter().all(|path| path == &visible));
 }
 
 #[tokio::test]
-async fn test_find_duplicate_files_nested_duplicates() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let content = "same content";
-    let file1 = create_temp_file(&temp_dir.join("dir1"), "file1.txt", content);
-    let file2 = create_temp_file(&temp_dir.join("dir1/subdir"), "file2.txt", content);
+async fn adhock() {}
 
-    let result = service
-        .find_duplicate_files(
-            &temp_dir.join("dir1"),
-            Some("*".to_string()),
+#[test]
+fn test_directory_tree_filter() {
+    let (temp_dir, service, allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let root = temp_dir.join("dir1");
+    create_temp_file(&root.join("src"), "main.rs", "fn main() {}");
+    create_temp_file(&root.join("src"), "notes.md", "notes");
+    create_temp_file(&root.join("node_modules").join("pkg"), "index.js", "");
+    create_temp_file(&root.join("target").join("debug"), "app.rs", "");
+
+    let filter = TreeFilter::new(
+        Some(&["node_modules".to_string(), "target/**".to_string()]),
+        Some("*.rs"),
+    )
+    .unwrap();
+    let mut count = 0;
+    let (tree, _) = service
+        .directory_tree(
+            &root,
             None,
             None,
+            false,
+            &mut count,
+            allowed_dirs.clone(),
+            WalkOptions::default(),
+            &filter,
+        )
+        .unwrap();
+    let tree = tree.to_string();
+    assert!(tree.contains("main.rs"));
+    assert!(!tree.contains("notes.md"));
+    assert!(!tree.contains("node_modules") && !tree.contains("index.js"));
+    // the directory itself does not match `target/**`, only its contents
+    assert!(tree.contains("target/") && !tree.contains("app.rs"));
+    assert_eq!(count, 3);
+
+    let page = service
+        .directory_tree_page(
+            &root,
             None,
+            0,
+            10,
+            false,
+            allowed_dirs,
+            WalkOptions::default(),
+            &TreeFilter::new(None, Some("src/*.md")).unwrap(),
         )
-        .await
         .unwrap();
-    let expected = vec![vec![
-        file1.to_str().unwrap().to_string(),
-        file2.to_str().unwrap().to_string(),
-    ]];
-    assert_eq!(result.len(), 1);
+    let names: Vec<_> = page.entries.iter().map(|entry| &entry["n"]).collect();
+    assert!(names.contains(&&serde_json::json!("src/notes.md")));
+    assert!(!names.contains(&&serde_json::json!("src/main.rs")));
+}
+
+#[tokio::test]
+async fn test_directory_tree_pages() {
+    let (temp_dir, service, allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let root = temp_dir.join("dir1");
+    create_temp_file(&root, "b.txt", "b");
+    create_temp_file(&root.join("a"), "z.txt", "z");
+    create_temp_file(&root.join("a").join("deep"), "x.txt", "x");
+    create_temp_file(&root.join("c"), "y.txt", "y");
+
+    let mut entries = vec![];
+    let mut offset = 0;
+    loop {
+        let page = service
+            .directory_tree_page(
+                &root,
+                None,
+                offset,
+                2,
+                false,
+                allowed_dirs.clone(),
+                WalkOptions::default(),
+                &TreeFilter::default(),
+            )
+            .unwrap();
+        assert!(page.entries.len() <= 2);
+        entries.extend(page.entries.iter().map(|entry| entry["n"].clone()));
+        match page.next_offset {
+            Some(next_offset) => offset = next_offset,
+            None => break,
+        }
+    }
+    // breadth-first, sorted by name within each directory
     assert_eq!(
-        sort_duplicate_groups(result),
-        sort_duplicate_groups(expected)
+        entries,
+        vec![
+            "a/",
+            "b.txt",
+            "c/",
+            "a/deep/",
+            "a/z.txt",
+            "c/y.txt",
+            "a/deep/x.txt"
+        ]
     );
+
+    let page = service
+        .directory_tree_page(
+            &root,
+            Some(1),
+            0,
+            10,
+            true,
+            allowed_dirs.clone(),
+            WalkOptions::default(),
+            &TreeFilter::default(),
+        )
+        .unwrap();
+    let names: Vec<_> = page.entries.iter().map(|entry| &entry["n"]).collect();
+    assert_eq!(names, vec!["a/", "b.txt", "c/"]);
+    // sizes are reported for files only, modification times for every entry
+    assert_eq!(page.entries[1]["s"], 1);
+    assert!(page.entries[0].get("s").is_none());
+    assert!(page.entries.iter().all(|entry| entry["m"].is_string()));
+    assert!(page.reached_max_depth);
+    assert!(page.next_offset.is_none());
 }
 
 #[tokio::test]
-async fn test_find_empty_directories_exclude_patterns() {
+async fn test_walk_options() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let dir1 = temp_dir.join("dir1");
-
-    // Create empty directory that should be included
-    let empty1 = dir1.join("empty1");
-    tokio::fs::create_dir_all(&empty1).await.unwrap();
-
-    // Create empty directory that matches exclude pattern
-    let empty2 = dir1.join("empty2");
-    tokio::fs::create_dir_all(&empty2).await.unwrap();
+    let root = temp_dir.join("dir1");
+    create_temp_file(&root, ".ignore", "build/\n");
+    create_temp_file(&root.join("build"), "output.txt", "built");
+    create_temp_file(&root, ".env.txt", "SECRET=1");
+    create_temp_file(&root, "notes.txt", "notes");
+
+    let find = |walk_options| {
+        let service = &service;
+        let root = &root;
+        async move {
+            let mut names: Vec<String> = service
+                .search_files(
+                    root,
+                    "*.txt".to_string(),
+                    vec![],
+                    None,
+                    None,
+                    None,
+                    walk_options,
+                )
+                .await
+                .unwrap()
+                .iter()
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+        }
+    };
 
-    // Create non-empty directory
-    let non_empty = dir1.join("non_empty");
-    tokio::fs::create_dir_all(&non_empty).await.unwrap();
-    create_temp_file(&non_empty, "file.txt", "content");
+    assert_eq!(find(WalkOptions::default()).await, vec!["notes.txt"]);
+    assert_eq!(
+        find(WalkOptions::new(Some(false), None, None)).await,
+        vec!["notes.txt", "output.txt"]
+    );
+    assert_eq!(
+        find(WalkOptions::new(None, Some(true), None)).await,
+        vec![".env.txt", "notes.txt"]
+    );
+}
 
-    // Ensure root dir1 exists
-    tokio::fs::create_dir_all(&dir1).await.unwrap();
+#[cfg(unix)]
+#[tokio::test]
+async fn test_follow_symlinks() {
+    let (temp_dir, service, allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let root = temp_dir.join("dir1");
+    create_temp_file(&root.join("packages").join("lib"), "lib.txt", "lib");
+    create_temp_file(&root.join("app"), "app.txt", "app");
+    create_temp_file(&temp_dir.join("outside"), "secret.txt", "secret");
+    std::os::unix::fs::symlink(
+        root.join("packages").join("lib"),
+        root.join("app").join("lib"),
+    )
+    .unwrap();
+    // a cycle back to the root and a link leaving the allowed directories
+    std::os::unix::fs::symlink(&root, root.join("app").join("loop")).unwrap();
+    std::os::unix::fs::symlink(temp_dir.join("outside"), root.join("app").join("outside")).unwrap();
+
+    let follow = WalkOptions::new(None, None, Some(true));
+    let find = |walk_options| {
+        let service = &service;
+        let root = &root;
+        async move {
+            let mut paths: Vec<String> = service
+                .search_files(
+                    root,
+                    "*.txt".to_string(),
+                    vec![],
+                    None,
+                    None,
+                    None,
+                    walk_options,
+                )
+                .await
+                .unwrap()
+                .iter()
+                .map(|entry| {
+                    entry
+                        .path()
+                        .strip_prefix(root)
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect();
+            paths.sort();
+            paths
+        }
+    };
 
-    // Call with exclude_patterns to exclude "*2*"
-    let result = service
-        .find_empty_directories(&dir1, Some(vec!["*2*".to_string()]))
-        .await
+    assert_eq!(
+        find(WalkOptions::default()).await,
+        vec!["app/app.txt", "packages/lib/lib.txt"]
+    );
+    let found = find(follow).await;
+    assert!(found.contains(&"app/lib/lib.txt".to_string()));
+    assert!(found.iter().all(|path| !path.contains("secret")));
+
+    let mut count = 0;
+    let (tree, _) = service
+        .directory_tree(
+            &root,
+            None,
+            None,
+            false,
+            &mut count,
+            allowed_dirs,
+            follow,
+            &TreeFilter::default(),
+        )
         .unwrap();
-
-    // Expect only empty1, not empty2 or non_empty
-    let expected = vec![empty1.to_str().unwrap().to_string()];
-    assert_eq!(result.len(), 1);
-    assert_eq!(result, expected);
+    let tree = tree.to_string();
+    assert!(tree.contains(r#"{"c":[{"n":"lib.txt"}],"n":"lib@"}"#));
+    assert!(!tree.contains("secret"));
 }
 
 #[tokio::test]
-async fn test_find_empty_directories_exclude_patterns_2() {
+async fn test_create_and_compare_baseline() {
     let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
-    let root_path = temp_dir.join("dir1");
-
-    // Create empty directories
-    tokio::fs::create_dir_all(&root_path.join("empty1"))
+    let root = temp_dir.join("dir1");
+    let baseline_file = temp_dir.join("dir1").join("baseline.json");
+    create_temp_file(&root, "keep.txt", "keep");
+    create_temp_file(&root, "touch.txt", "touch");
+    let edited = create_temp_file(&root.join("src"), "edit.txt", "before");
+    let removed = create_temp_file(&root, "remove.txt", "gone");
+
+    let summary = service
+        .create_baseline(&root, Some(&baseline_file), WalkOptions::default())
         .await
         .unwrap();
-    tokio::fs::create_dir_all(&root_path.join("empty2.log"))
+    assert_eq!(summary.files, 4);
+    assert!(baseline_file.exists());
+
+    let diff = service
+        .compare_baseline(&root, Some(&baseline_file))
         .await
         .unwrap();
-    tokio::fs::create_dir_all(&root_path.join("empty3"))
-        .await
+    assert!(!diff.has_changes());
+    assert_eq!(diff.unchanged, 4);
+
+    fs::write(&edited, "after!").unwrap();
+    fs::remove_file(&removed).unwrap();
+    create_temp_file(&root, "new.txt", "new");
+    // only the modification time changes, the content does not
+    File::options()
+        .write(true)
+        .open(root.join("touch.txt"))
+        .unwrap()
+        .set_modified(SystemTime::now() + std::time::Duration::from_secs(60))
         .unwrap();
 
-    // Create a non-empty directory to ensure it's not returned
-    tokio::fs::create_dir_all(&root_path.join("non_empty"))
-        .await
-        .unwrap();
-    tokio::fs::write(&root_path.join("non_empty/file.txt"), b"content")
+    let diff = service
+        .compare_baseline(&root, Some(&baseline_file))
         .await
         .unwrap();
+    assert_eq!(diff.added, vec![PathBuf::from("new.txt")]);
+    assert_eq!(diff.removed, vec![PathBuf::from("remove.txt")]);
+    assert_eq!(diff.modified, vec![PathBuf::from("src").join("edit.txt")]);
+    assert_eq!(diff.unchanged, 2);
 
-    // Test with exclude pattern "*.log"
-    let exclude_patterns = Some(vec!["*.log".to_string()]);
     let result = service
-        .find_empty_directories(&root_path, exclude_patterns)
+        .compare_baseline(&root.join("src"), Some(&baseline_file))
+        .await;
+    assert!(result.is_err());
+    let result = service
+        .compare_baseline(&root, Some(&root.join("missing.json")))
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_clone_directory_structure() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let src = temp_dir.join("dir1").join("src");
+    create_temp_file(&src.join("a").join("b").join("c"), "deep.txt", "deep");
+    create_temp_file(&src.join("skip").join("inner"), "file.txt", "skipped");
+    create_temp_file(&src, "top.txt", "top");
+    let dest = temp_dir.join("dir1").join("out");
+
+    let created = service
+        .clone_directory_structure(
+            &src,
+            &dest,
+            Some(2),
+            vec!["skip".to_string()],
+            WalkOptions::default(),
+        )
         .await
         .unwrap();
+    assert_eq!(
+        created,
+        vec![dest.clone(), dest.join("a"), dest.join("a").join("b")]
+    );
+    assert!(!dest.join("a").join("b").join("c").exists());
+    assert!(!dest.join("skip").exists());
+    assert_eq!(fs::read_dir(&dest).unwrap().count(), 1);
 
-    let expected = [
-        root_path.join("empty1").to_str().unwrap().to_string(),
-        root_path.join("empty3").to_str().unwrap().to_string(),
-    ];
-
-    assert_eq!(result.len(), 2);
-    assert!(result.iter().all(|path| expected.contains(path)));
-    assert!(!result.iter().any(|path| path.contains("empty2.log")));
+    // existing directories are kept, only the missing ones are created
+    let created = service
+        .clone_directory_structure(&src, &dest, None, vec![], WalkOptions::default())
+        .await
+        .unwrap();
+    let mut created: Vec<_> = created
+        .iter()
+        .map(|dir| dir.strip_prefix(&dest).unwrap().to_path_buf())
+        .collect();
+    created.sort();
+    assert_eq!(
+        created,
+        vec![
+            PathBuf::from("a/b/c"),
+            PathBuf::from("skip"),
+            PathBuf::from("skip/inner")
+        ]
+    );
+    assert!(!dest.join("top.txt").exists());
+    assert!(!dest.join("a/b/c/deep.txt").exists());
 }
 
 #[tokio::test]
-// https://github.com/rust-mcp-stack/rust-mcp-filesystem/issues/50
-async fn test_search_files_brace_expanded_github_issue_50() {
-    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["public".to_string()]);
-    let temp_path = temp_dir.join("public").to_path_buf();
+async fn test_search_files_content_path_patterns() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let root = temp_dir.join("dir1");
+    create_temp_file(&root, "main.rs", "needle");
+    create_temp_file(&root.join("src"), "lib.rs", "needle");
+    create_temp_file(&root.join("src").join("nested"), "mod.rs", "needle");
+    create_temp_file(&root.join("tests"), "it.rs", "needle");
+
+    let search = |pattern: String| {
+        let service = &service;
+        let root = root.clone();
+        async move {
+            let mut names: Vec<String> = service
+                .search_files_content(
+                    &root,
+                    &pattern,
+                    "needle",
+                    false,
+                    None,
+                    None,
+                    None,
+                    WalkOptions::default(),
+                    &OperationContext::default(),
+                )
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|result| {
+                    result
+                        .file_path
+                        .strip_prefix(&root)
+                        .unwrap()
+                        .to_string_lossy()
+                        .replace('\\', "/")
+                })
+                .collect();
+            names.sort();
+            names
+        }
+    };
 
-    // create a node_modules directory that will be ignored
-    let node_modules_dir = temp_dir.join("node_modules");
-    create_temp_file(
-        &node_modules_dir,
-        "file1.js",
-        "{const name = 'Rust MCP SDK';}",
+    // file name patterns match files at any depth
+    assert_eq!(
+        search("*.rs".to_string()).await,
+        vec!["main.rs", "src/lib.rs", "src/nested/mod.rs", "tests/it.rs"]
     );
-    create_temp_file(&node_modules_dir, "file2.json", r#"{"success":true}"#);
-    create_temp_file(&temp_path.join("target"), "dont_find.ts", "");
+    // patterns with a separator match the path relative to the root
+    assert_eq!(
+        search("src/**/*.rs".to_string()).await,
+        vec!["src/lib.rs", "src/nested/mod.rs"]
+    );
+    assert_eq!(search("tests/*.rs".to_string()).await, vec!["tests/it.rs"]);
+    // or the absolute path
+    let absolute = format!("{}/src/nested/*.rs", root.display());
+    assert_eq!(search(absolute).await, vec!["src/nested/mod.rs"]);
+}
 
-    /*
-    temp_dir/
-    ├── file1.ts                  ✅ match
-    ├── file2.java                ✅ match
-    ├── file3.js                  ❌ no match
-    ├── sub1/
-    │   ├── file4.ts              ✅ match
-    │   ├── file5.java            ✅ match
-    │   └── file6.js              ❌ no match
-    └── sub2/
-        └── nested/
-            ├── file7.ts          ✅ match
-            └── file8.rs          ❌ no match
-    */
-    // Top-level files
-    create_temp_file(&temp_path, "file1.ts", "console.log('hello');");
-    create_temp_file(&temp_path, "file2.java", "public class Hello {}");
-    create_temp_file(&temp_path, "file3.js", "console.log('not included');");
+#[tokio::test]
+async fn test_search_files_content_binary_and_long_lines() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let root = temp_dir.join("dir1");
+    create_temp_file(&root, "plain.js", "const needle = 1;\n");
+    let bundle = format!("{}needle{}\n", "a".repeat(300), "b".repeat(300));
+    create_temp_file(&root, "bundle.min.js", &bundle);
+    fs::write(root.join("blob.js"), b"needle\0binary").unwrap();
+
+    let search = |options: ContentSearchOptions| {
+        let service = &service;
+        let root = root.clone();
+        async move {
+            let mut outcome = service
+                .search_files_content_with_options(
+                    &root,
+                    "*.js",
+                    "needle",
+                    false,
+                    None,
+                    None,
+                    None,
+                    WalkOptions::default(),
+                    options,
+                    &OperationContext::default(),
+                )
+                .await
+                .unwrap();
+            outcome
+                .results
+                .sort_by(|a, b| a.file_path.cmp(&b.file_path));
+            outcome
+        }
+    };
 
-    // sub1/
-    create_temp_file(
-        &temp_path.join("sub1"),
-        "file4.ts",
-        "console.log('sub ts');",
-    );
-    create_temp_file(&temp_path.join("sub1"), "file5.java", "class Sub {}");
-    create_temp_file(
-        &temp_path.join("sub1"),
-        "file6.js",
-        "console.log('sub js');",
-    );
+    let outcome = search(ContentSearchOptions::default()).await;
+    assert_eq!(outcome.skipped_binary_files, 1);
+    assert_eq!(outcome.skipped_long_line_files, 0);
+    assert_eq!(outcome.results.len(), 2);
 
-    // sub2/nested/
+    let outcome = search(ContentSearchOptions {
+        skip_binary: false,
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(outcome.skipped_binary_files, 0);
+    assert_eq!(outcome.results.len(), 3);
+    assert_eq!(outcome.results[0].file_path, root.join("blob.js"));
+
+    let outcome = search(ContentSearchOptions {
+        max_line_length: Some(100),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(outcome.skipped_long_line_files, 1);
+    assert_eq!(outcome.results.len(), 1);
+    assert_eq!(outcome.results[0].file_path, root.join("plain.js"));
+
+    let outcome = search(ContentSearchOptions {
+        max_line_length: Some(40),
+        long_lines: LongLineAction::Truncate,
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(outcome.skipped_long_line_files, 0);
+    assert_eq!(outcome.results.len(), 2);
+    let snippet = &outcome.results[0].matches[0].line_text;
+    assert!(snippet.contains("needle"));
+    assert!(snippet.trim_matches('.').chars().count() <= 40);
+}
+
+#[tokio::test]
+async fn test_search_files_content_highlight() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let root = temp_dir.join("dir1");
+    let long_line = format!("{}Needle{}\n", "x".repeat(100), "y".repeat(300));
     create_temp_file(
-        &temp_path.join("sub2/nested"),
-        "file7.ts",
-        "const deep = true;",
+        &root,
+        "a.txt",
+        &format!("  find the needle here\n{long_line}"),
     );
-    create_temp_file(&temp_path.join("sub2/nested"), "file8.rs", "fn main() {}");
-
-    // Perform the glob search
-    // Perform the glob search
-    // let pattern = "**/*.java".to_string();
-    let pattern = "**/*.{java,ts}".to_string();
 
-    let result = service
-        .search_files(
-            &temp_path,
-            pattern,
-            vec![
-                "/node_modules/".to_string(),
-                "/.git/".to_string(),
-                "/target/**".to_string(),
-            ],
+    let outcome = service
+        .search_files_content_with_options(
+            &root,
+            "*.txt",
+            "needle",
+            false,
             None,
             None,
             None,
+            WalkOptions::default(),
+            ContentSearchOptions {
+                highlight: Some(("[[".to_string(), "]]".to_string())),
+                ..Default::default()
+            },
+            &OperationContext::default(),
         )
         .await
         .unwrap();
+    let matches = &outcome.results[0].matches;
+    assert_eq!(matches[0].line_text, "find the [[needle]] here");
+    assert_eq!((matches[0].start_pos, matches[0].end_pos), (11, 17));
+    assert!(matches[1].line_text.starts_with("...xxx"));
+    assert!(matches[1].line_text.contains("x[[Needle]]y"));
+    assert!(matches[1].line_text.ends_with("y..."));
+    assert_eq!((matches[1].start_pos, matches[1].end_pos), (100, 106));
+}
 
-    let names: Vec<_> = result
-        .into_iter()
-        .map(|e| e.file_name().to_str().unwrap().to_string())
-        .collect();
+#[tokio::test]
+async fn test_search_archive_content() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let root = temp_dir.join("dir1");
+    let docs = create_temp_zip(
+        &root,
+        "docs.zip",
+        &[
+            ("notes/a.txt", "first line\nthe Needle is here\n"),
+            ("notes/b.md", "no match\n"),
+            ("readme.md", "another needle\n"),
+        ],
+    );
+    fs::create_dir(root.join("nested")).unwrap();
+    create_temp_zip(&root.join("nested"), "more.zip", &[("c.txt", "needle")]);
+    create_temp_file(&root, "plain.txt", "needle outside archives");
+    fs::write(root.join("broken.zip"), "not a zip").unwrap();
 
-    assert!(names.iter().all(|name| {
-        [
-            "file4.ts",
-            "file5.java",
-            "file1.ts",
-            "file2.java",
-            "file7.ts",
+    let results = service
+        .search_archive_content(
+            &root,
+            None,
+            None,
+            "needle",
+            false,
+            vec![],
+            WalkOptions::default(),
+        )
+        .await
+        .unwrap();
+    let locations: Vec<_> = results
+        .iter()
+        .map(|result| {
+            (
+                result.archive_path.file_name().unwrap().to_str().unwrap(),
+                result.entry.as_str(),
+                result.matches[0].line_number,
+            )
+        })
+        .collect();
+    assert_eq!(
+        locations,
+        vec![
+            ("docs.zip", "notes/a.txt", 2),
+            ("docs.zip", "readme.md", 1),
+            ("more.zip", "c.txt", 1),
         ]
-        .contains(&name.as_str())
-    }));
+    );
+    assert_eq!(
+        results[0].location(),
+        format!("{}!notes/a.txt", docs.display())
+    );
+    assert_eq!(results[0].matches[0].line_text, "the Needle is here");
 
-    assert_eq!(names.len(), 5);
+    let results = service
+        .search_archive_content(
+            &docs,
+            None,
+            Some("notes/*"),
+            "need.e",
+            true,
+            vec![],
+            WalkOptions::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].entry, "notes/a.txt");
 }
 
 #[tokio::test]
-async fn adhock() {}
+async fn test_search_files_content_invert_match_and_path_regex() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let root = temp_dir.join("dir1");
+    create_temp_file(
+        &root.join("src"),
+        "lib.rs",
+        "use std;\n  // needle\nfn main() {}\n",
+    );
+    create_temp_file(&root.join("src"), "lib_test.rs", "needle\n");
+    create_temp_file(&root.join("docs"), "guide.rs", "needle\n");
+
+    let search = |options: ContentSearchOptions| {
+        let service = &service;
+        let root = root.clone();
+        async move {
+            let mut results = service
+                .search_files_content_with_options(
+                    &root,
+                    "*.rs",
+                    "needle",
+                    false,
+                    None,
+                    None,
+                    None,
+                    WalkOptions::default(),
+                    options,
+                    &OperationContext::default(),
+                )
+                .await
+                .unwrap()
+                .results;
+            results.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+            results
+        }
+    };
+
+    let results = search(ContentSearchOptions {
+        invert_match: true,
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].file_path, root.join("src").join("lib.rs"));
+    let lines: Vec<_> = results[0]
+        .matches
+        .iter()
+        .map(|m| (m.line_number, m.line_text.as_str()))
+        .collect();
+    assert_eq!(lines, vec![(1, "use std;"), (3, "fn main() {}")]);
+
+    let results = search(ContentSearchOptions {
+        path_regex: Some(r"^src/.*_test\.rs$".to_string()),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].file_path, root.join("src").join("lib_test.rs"));
+
+    let results = search(ContentSearchOptions {
+        path_regex: Some("^src/".to_string()),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(results.len(), 2);
+}
@@ -0,0 +1,38 @@
+use rust_mcp_filesystem::error::ServiceError;
+use rust_mcp_filesystem::limits::ToolLimits;
+
+#[test]
+fn test_calls_per_minute() {
+    let limits = ToolLimits::new(None, Some(2));
+    assert!(limits.check_rate("read_text_file").is_ok());
+    assert!(limits.check_rate("read_text_file").is_ok());
+    let result = limits.check_rate("read_text_file");
+    assert!(matches!(result, Err(ServiceError::RateLimited(_))));
+    assert!(result.unwrap_err().to_string().contains("Retry in"));
+
+    // the limit applies per tool
+    assert!(limits.check_rate("write_file").is_ok());
+}
+
+#[test]
+fn test_concurrent_heavy_operations() {
+    let limits = ToolLimits::new(Some(1), None);
+    let permit = limits.acquire_heavy().unwrap();
+    assert!(permit.is_some());
+    assert!(matches!(
+        limits.acquire_heavy(),
+        Err(ServiceError::RateLimited(_))
+    ));
+
+    drop(permit);
+    assert!(limits.acquire_heavy().unwrap().is_some());
+}
+
+#[test]
+fn test_unlimited_by_default() {
+    let limits = ToolLimits::default();
+    for _ in 0..100 {
+        assert!(limits.check_rate("search_files").is_ok());
+    }
+    assert!(limits.acquire_heavy().unwrap().is_none());
+}
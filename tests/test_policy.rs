@@ -0,0 +1,112 @@
+#[path = "common/common.rs"]
+pub mod common;
+
+use common::get_temp_dir;
+use rust_mcp_filesystem::error::ServiceError;
+use rust_mcp_filesystem::policy::{ToolAccess, ToolPolicies};
+use rust_mcp_filesystem::tools::FileSystemTools;
+use rust_mcp_sdk::schema::CallToolRequestParams;
+use std::fs;
+
+fn load_policy(file_name: &str, content: &str) -> ToolPolicies {
+    let temp_dir = get_temp_dir();
+    let policy_path = temp_dir.join(file_name);
+    fs::write(&policy_path, content).unwrap();
+    ToolPolicies::load(&policy_path).unwrap()
+}
+
+#[test]
+fn test_policy_access() {
+    let policy = load_policy(
+        "policy.toml",
+        r#"
+[tools.move_file]
+access = "deny"
+
+[tools.Edit_File]
+access = "readonly"
+"#,
+    );
+    assert_eq!(policy.default, ToolAccess::Allow);
+    assert!(matches!(
+        policy.check("move_file", true, Some(&[])),
+        Err(ServiceError::PolicyDenied(_))
+    ));
+    assert!(matches!(
+        policy.check("edit_file", true, Some(&[])),
+        Err(ServiceError::PolicyDenied(_))
+    ));
+    assert!(policy.check("read_text_file", false, Some(&[])).is_ok());
+}
+
+#[test]
+fn test_policy_paths() {
+    let temp_dir = get_temp_dir();
+    let out_dir = temp_dir.join("out");
+    fs::create_dir_all(&out_dir).unwrap();
+    let policy = load_policy(
+        "policy.json",
+        &serde_json::json!({
+            "default": "deny",
+            "tools": {
+                "write_file": { "access": "allow", "paths": [out_dir] }
+            }
+        })
+        .to_string(),
+    );
+
+    let inside = out_dir.join("new.txt").display().to_string();
+    assert!(policy.check("write_file", true, Some(&[inside])).is_ok());
+
+    let escaped = out_dir.join("..").join("other.txt").display().to_string();
+    assert!(policy.check("write_file", true, Some(&[escaped])).is_err());
+    assert!(policy.check("read_text_file", false, Some(&[])).is_err());
+    // paths the policy cannot see are denied
+    assert!(policy.check("write_file", true, None).is_err());
+}
+
+fn tool(name: &str, arguments: serde_json::Value) -> FileSystemTools {
+    FileSystemTools::try_from(CallToolRequestParams {
+        name: name.to_string(),
+        arguments: arguments.as_object().cloned(),
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_path_arguments() {
+    let paths = tool(
+        "apply_edits_batch",
+        serde_json::json!({ "files": [
+            { "path": "/a", "edits": [] },
+            { "path": "/b", "edits": [] }
+        ]}),
+    )
+    .path_arguments();
+    assert_eq!(paths.unwrap(), vec!["/a", "/b"]);
+
+    let paths = tool(
+        "cleanup_old_files",
+        serde_json::json!({ "path": "/a", "older_than_days": 1, "archive_dir": "/c" }),
+    )
+    .path_arguments();
+    assert_eq!(paths.unwrap(), vec!["/a", "/c"]);
+
+    let paths = tool(
+        "write_file",
+        serde_json::json!({ "path": "/a", "content": "/not/a/path" }),
+    )
+    .path_arguments();
+    assert_eq!(paths.unwrap(), vec!["/a"]);
+
+    assert!(
+        tool("undo_last_operation", serde_json::json!({}))
+            .path_arguments()
+            .is_none()
+    );
+    assert!(
+        tool("get_disk_space", serde_json::json!({}))
+            .path_arguments()
+            .is_none()
+    );
+}
@@ -0,0 +1,224 @@
+#![cfg(unix)]
+#[path = "common/common.rs"]
+pub mod common;
+
+use clap::Parser;
+use rust_mcp_filesystem::cli::CommandArguments;
+use rust_mcp_filesystem::handler::FileSystemHandler;
+use rust_mcp_filesystem::server::{UnixSocketTransport, bind_socket, server_details};
+use rust_mcp_sdk::McpServer;
+use rust_mcp_sdk::TransportOptions;
+use rust_mcp_sdk::mcp_server::server_runtime;
+use std::os::unix::fs::PermissionsExt;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+
+#[tokio::test]
+async fn test_unix_socket_transport() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let socket_path = temp_dir.path().join("mcp.sock");
+    let listener = bind_socket(&socket_path, 0o600).unwrap();
+    let mode = std::fs::metadata(&socket_path)
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let args = CommandArguments::parse_from(["mcp-server", temp_dir.path().to_str().unwrap()]);
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let handler = FileSystemHandler::new(&args).unwrap();
+        let transport = UnixSocketTransport::new(stream, TransportOptions::default());
+        let server = server_runtime::create_server(server_details(), transport, handler);
+        server.start().await.unwrap();
+    });
+
+    let stream = UnixStream::connect(&socket_path).await.unwrap();
+    let (read_half, mut write_half) = stream.into_split();
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-06-18",
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        }
+    });
+    write_half
+        .write_all(format!("{request}\n").as_bytes())
+        .await
+        .unwrap();
+
+    let mut lines = BufReader::new(read_half).lines();
+    let line = lines.next_line().await.unwrap().unwrap();
+    let response: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(response["id"], 1);
+    assert_eq!(
+        response["result"]["serverInfo"]["name"],
+        "rust-mcp-filesystem"
+    );
+}
+
+#[test]
+fn test_bind_socket_refuses_regular_file() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("not-a-socket");
+    std::fs::write(&path, "data").unwrap();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let _guard = runtime.enter();
+    assert!(bind_socket(&path, 0o600).is_err());
+    assert!(path.exists());
+}
+
+#[tokio::test]
+async fn test_bind_socket_keeps_live_socket() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("mcp.sock");
+    let listener = bind_socket(&path, 0o600).unwrap();
+    let error = bind_socket(&path, 0o600).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::AddrInUse);
+    assert!(UnixStream::connect(&path).await.is_ok());
+
+    // a socket nobody listens on anymore is replaced
+    drop(listener);
+    let _listener = bind_socket(&path, 0o660).unwrap();
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o660);
+    assert!(UnixStream::connect(&path).await.is_ok());
+    assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+}
+
+/// Initializes a session on `socket_path`, calls the tool `name` with `arguments` and returns
+/// the result of the call.
+async fn call_tool(
+    socket_path: &std::path::Path,
+    name: &str,
+    arguments: serde_json::Value,
+) -> serde_json::Value {
+    let stream = UnixStream::connect(socket_path).await.unwrap();
+    let (read_half, mut write_half) = stream.into_split();
+    let messages = [
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": {"name": "test-client", "version": "1.0.0"}
+            }
+        }),
+        serde_json::json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {"name": name, "arguments": arguments}
+        }),
+    ];
+    for message in messages {
+        write_half
+            .write_all(format!("{message}\n").as_bytes())
+            .await
+            .unwrap();
+    }
+
+    let mut lines = BufReader::new(read_half).lines();
+    loop {
+        let line = lines.next_line().await.unwrap().unwrap();
+        let response: serde_json::Value = serde_json::from_str(&line).unwrap();
+        if response["id"] == 2 {
+            return response["result"].clone();
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_unix_socket_sessions_share_rate_limits() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let socket_path = temp_dir.path().join("mcp.sock");
+    let listener = bind_socket(&socket_path, 0o600).unwrap();
+
+    let args = CommandArguments::parse_from([
+        "mcp-server",
+        "--max-calls-per-minute",
+        "1",
+        temp_dir.path().to_str().unwrap(),
+    ]);
+    tokio::spawn(async move {
+        let handler = FileSystemHandler::new(&args).unwrap();
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let transport = UnixSocketTransport::new(stream, TransportOptions::default());
+            let server = server_runtime::create_server(
+                server_details(),
+                transport,
+                handler.new_session(&args),
+            );
+            tokio::spawn(async move { server.start().await });
+        }
+    });
+
+    let first = call_tool(
+        &socket_path,
+        "list_allowed_directories",
+        serde_json::json!({}),
+    )
+    .await;
+    assert_ne!(first["isError"], true);
+
+    let second = call_tool(
+        &socket_path,
+        "list_allowed_directories",
+        serde_json::json!({}),
+    )
+    .await;
+    assert_eq!(second["isError"], true);
+    assert!(second.to_string().contains("within the last minute"));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_tool_timeout_of_cancelled_search() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let socket_path = temp_dir.path().join("mcp.sock");
+    let listener = bind_socket(&socket_path, 0o600).unwrap();
+    let root = temp_dir.path().join("files");
+    std::fs::create_dir(&root).unwrap();
+    for index in 0..10000 {
+        std::fs::write(root.join(format!("{index}.txt")), "needle\n").unwrap();
+    }
+
+    let args = CommandArguments::parse_from(["mcp-server", temp_dir.path().to_str().unwrap()]);
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        // the search is cancelled long before it has read every file, and then returns normally
+        let handler = FileSystemHandler::new(&args)
+            .unwrap()
+            .with_tool_timeout(Some(std::time::Duration::from_millis(50)));
+        let transport = UnixSocketTransport::new(stream, TransportOptions::default());
+        let server = server_runtime::create_server(server_details(), transport, handler);
+        server.start().await.unwrap();
+    });
+
+    let result = call_tool(
+        &socket_path,
+        "search_files_content",
+        serde_json::json!({
+            "path": root.to_str().unwrap(),
+            "pattern": "*.txt",
+            "query": "needle"
+        }),
+    )
+    .await;
+    assert_eq!(result["isError"], true);
+    let details: serde_json::Value =
+        serde_json::from_str(result["content"][1]["text"].as_str().unwrap()).unwrap();
+    assert_eq!(details["error"]["code"], "TIMEOUT");
+    assert!(
+        details["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("'search_files_content' timed out")
+    );
+}
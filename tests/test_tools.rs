@@ -1,9 +1,19 @@
 #[path = "common/common.rs"]
 pub mod common;
 
+use common::get_temp_dir;
 use common::setup_service;
+use rust_mcp_filesystem::error::{ErrorCode, ServiceError, ToolFailure};
+use rust_mcp_filesystem::fs_service::utils::OutputFormat;
+use rust_mcp_filesystem::fs_service::{
+    CancellationToken, DirectoryMode, FileSystemService, OverwritePolicy, SearchSortBy, SortOrder,
+};
+use rust_mcp_filesystem::logging::McpLogger;
 use rust_mcp_filesystem::tools::*;
-use rust_mcp_sdk::schema::{ContentBlock, schema_utils::CallToolError};
+use rust_mcp_sdk::schema::{
+    CallToolRequestParams, CallToolResult, ContentBlock, LoggingLevel, RpcError, TextContent,
+    schema_utils::CallToolError,
+};
 use std::{collections::HashSet, fs};
 
 #[tokio::test]
@@ -165,5 +175,1617 @@ async fn ensure_tools_duplication() {
     assert_eq!(duplicate_descriptions.join(","), "");
 }
 
+#[tokio::test]
+async fn test_search_files_content_pagination() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let root = temp_dir.join("dir1");
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        fs::write(root.join(name), "needle one\nneedle two\n").unwrap();
+    }
+
+    let params = SearchFilesContent {
+        path: root.to_str().unwrap().to_string(),
+        pattern: "*.txt".to_string(),
+        query: "needle".to_string(),
+        is_regex: None,
+        exclude_patterns: None,
+        min_bytes: None,
+        max_bytes: None,
+        max_results: Some(4),
+        max_matches_per_file: None,
+        cursor: None,
+        respect_gitignore: None,
+        include_hidden: None,
+        follow_symlinks: None,
+        max_depth: None,
+        threads: None,
+        low_priority: None,
+        skip_binary: None,
+        max_line_length: None,
+        long_lines: None,
+        invert_match: None,
+        path_regex: None,
+        highlight: None,
+        highlight_start: None,
+        highlight_end: None,
+        output_format: None,
+    };
+    let result = SearchFilesContent::run_tool(params.clone(), &service)
+        .await
+        .unwrap();
+    let meta = result
+        .meta
+        .clone()
+        .expect("truncated result should carry _meta");
+    assert_eq!(meta.get("truncated"), Some(&serde_json::Value::Bool(true)));
+    assert_eq!(meta.get("total_matches"), Some(&6.into()));
+    assert_eq!(meta.get("returned_matches"), Some(&4.into()));
+    assert_eq!(meta.get("files_scanned"), Some(&3.into()));
+    assert_eq!(meta.get("files_matched"), Some(&3.into()));
+    assert_eq!(meta.get("matches"), Some(&6.into()));
+    assert!(
+        meta.get("duration_ms")
+            .is_some_and(|duration| duration.is_u64())
+    );
+    let text = match result.content.first().unwrap() {
+        ContentBlock::TextContent(text_content) => text_content.text.clone(),
+        _ => panic!("Expected TextContent result"),
+    };
+    assert!(text.contains("a.txt") && text.contains("b.txt") && !text.contains("c.txt"));
+
+    let cursor = meta
+        .get("next_cursor")
+        .unwrap()
+        .as_str()
+        .unwrap()
+        .to_string();
+    let next_page = SearchFilesContent::run_tool(
+        SearchFilesContent {
+            cursor: Some(cursor),
+            ..params.clone()
+        },
+        &service,
+    )
+    .await
+    .unwrap();
+    let meta = next_page.meta.clone().unwrap();
+    assert_eq!(meta.get("truncated"), Some(&serde_json::Value::Bool(false)));
+    assert!(meta.get("next_cursor").is_none());
+    let text = match next_page.content.first().unwrap() {
+        ContentBlock::TextContent(text_content) => text_content.text.clone(),
+        _ => panic!("Expected TextContent result"),
+    };
+    assert!(!text.contains("a.txt") && !text.contains("b.txt") && text.contains("c.txt"));
+    assert_eq!(text.matches("needle").count(), 2);
+
+    let invalid = SearchFilesContent::run_tool(
+        SearchFilesContent {
+            cursor: Some("not-a-cursor".to_string()),
+            ..params.clone()
+        },
+        &service,
+    )
+    .await;
+    assert!(invalid.is_err());
+
+    let empty_page = SearchFilesContent::run_tool(
+        SearchFilesContent {
+            max_results: Some(0),
+            ..params
+        },
+        &service,
+    )
+    .await;
+    assert!(
+        matches!(empty_page, Err(err) if err.to_string().contains("'max_results' must be at least 1"))
+    );
+}
+
+#[tokio::test]
+async fn test_search_files_content_sorted_with_summary() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let root = temp_dir.join("dir1");
+    for name in ["z.txt", "a.txt", "m.txt"] {
+        fs::write(root.join(name), "needle\nhay\nneedle\n").unwrap();
+    }
+
+    let result = SearchFilesContent::run_tool(
+        SearchFilesContent {
+            path: root.to_str().unwrap().to_string(),
+            pattern: "*.txt".to_string(),
+            query: "needle".to_string(),
+            is_regex: None,
+            exclude_patterns: None,
+            min_bytes: None,
+            max_bytes: None,
+            max_results: None,
+            max_matches_per_file: None,
+            cursor: None,
+            respect_gitignore: None,
+            include_hidden: None,
+            follow_symlinks: None,
+            max_depth: None,
+            threads: None,
+            low_priority: None,
+            skip_binary: None,
+            max_line_length: None,
+            long_lines: None,
+            invert_match: None,
+            path_regex: None,
+            highlight: None,
+            highlight_start: None,
+            highlight_end: None,
+            output_format: None,
+        },
+        &service,
+    )
+    .await
+    .unwrap();
+    let text = match result.content.first().unwrap() {
+        ContentBlock::TextContent(text_content) => text_content.text.clone(),
+        _ => panic!("Expected TextContent result"),
+    };
+    assert!(text.starts_with("3 files matched, 6 total matches\n"));
+    let positions: Vec<usize> = ["a.txt", "m.txt", "z.txt"]
+        .iter()
+        .map(|name| text.find(name).unwrap())
+        .collect();
+    assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(text.find("  1:").unwrap() < text.find("  3:").unwrap());
+}
+
+#[tokio::test]
+async fn test_search_files_content_max_matches_per_file() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let root = temp_dir.join("dir1");
+    fs::write(root.join("a.txt"), "needle\nneedle\nneedle\n").unwrap();
+
+    let params = SearchFilesContent {
+        path: root.to_str().unwrap().to_string(),
+        pattern: "*.txt".to_string(),
+        query: "needle".to_string(),
+        is_regex: None,
+        exclude_patterns: None,
+        min_bytes: None,
+        max_bytes: None,
+        max_results: None,
+        max_matches_per_file: Some(1),
+        cursor: None,
+        respect_gitignore: None,
+        include_hidden: None,
+        follow_symlinks: None,
+        max_depth: None,
+        threads: None,
+        low_priority: None,
+        skip_binary: None,
+        max_line_length: None,
+        long_lines: None,
+        invert_match: None,
+        path_regex: None,
+        highlight: None,
+        highlight_start: None,
+        highlight_end: None,
+        output_format: None,
+    };
+    let result = SearchFilesContent::run_tool(params, &service)
+        .await
+        .unwrap();
+    let meta = result.meta.expect("capped result should carry _meta");
+    assert_eq!(meta.get("capped_files"), Some(&1.into()));
+    assert!(meta.get("next_cursor").is_none());
+}
+
+#[tokio::test]
+async fn test_search_tools_json_output() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let root = temp_dir.join("dir1");
+    fs::write(
+        root.join("main.rs"),
+        "fn main() {\n    let needle = 1;\n}\n",
+    )
+    .unwrap();
+
+    let result = SearchFiles::run_tool(
+        SearchFiles {
+            path: root.to_str().unwrap().to_string(),
+            pattern: "*.rs".to_string(),
+            exclude_patterns: None,
+            file_extensions: None,
+            min_bytes: None,
+            max_bytes: None,
+            respect_gitignore: None,
+            include_hidden: None,
+            follow_symlinks: None,
+            max_depth: None,
+            sort_by: None,
+            order: None,
+            include_metadata: None,
+            max_results: None,
+            output_format: Some(OutputFormat::Json),
+        },
+        &service,
+    )
+    .await
+    .unwrap();
+    let structured = result.structured_content.unwrap();
+    assert_eq!(
+        structured["results"][0]["path"],
+        root.join("main.rs").display().to_string()
+    );
+
+    let result = SearchFilesContent::run_tool(
+        SearchFilesContent {
+            path: root.to_str().unwrap().to_string(),
+            pattern: "*.rs".to_string(),
+            query: "needle".to_string(),
+            is_regex: None,
+            exclude_patterns: None,
+            min_bytes: None,
+            max_bytes: None,
+            max_results: None,
+            max_matches_per_file: None,
+            cursor: None,
+            respect_gitignore: None,
+            include_hidden: None,
+            follow_symlinks: None,
+            max_depth: None,
+            threads: None,
+            low_priority: None,
+            skip_binary: None,
+            max_line_length: None,
+            long_lines: None,
+            invert_match: None,
+            path_regex: None,
+            highlight: None,
+            highlight_start: None,
+            highlight_end: None,
+            output_format: Some(OutputFormat::Json),
+        },
+        &service,
+    )
+    .await
+    .unwrap();
+    let structured = result.structured_content.unwrap();
+    let first_match = &structured["results"][0]["matches"][0];
+    assert_eq!(first_match["line"], 2);
+    assert_eq!(first_match["snippet"], "let needle = 1;");
+
+    let result = SearchCodeAst::run_tool(
+        SearchCodeAst {
+            path: root.to_str().unwrap().to_string(),
+            pattern: "**/*.rs".to_string(),
+            ast_pattern: Some("let $NAME = $VALUE;".to_string()),
+            rule_yaml: None,
+            language: "rust".to_string(),
+            injected_language: None,
+            exclude_patterns: None,
+            file_extensions: None,
+            max_lines: None,
+            context_lines: None,
+            include_enclosing: None,
+            respect_gitignore: None,
+            include_hidden: None,
+            follow_symlinks: None,
+            threads: None,
+            low_priority: None,
+            max_file_size: None,
+            max_files: None,
+            max_results: None,
+            max_matches_per_file: None,
+            cursor: None,
+            output_mode: None,
+            output_format: Some(OutputFormat::Json),
+        },
+        &service,
+    )
+    .await
+    .unwrap();
+    let meta = result.meta.unwrap();
+    assert_eq!(meta["files_scanned"], 1);
+    assert_eq!(meta["matches"], 1);
+    assert_eq!(meta["truncated"], false);
+    let structured = result.structured_content.unwrap();
+    assert_eq!(structured["summary"]["files_matched"], 1);
+    assert_eq!(structured["summary"]["total_matches"], 1);
+    let first_match = &structured["results"][0]["matches"][0];
+    assert_eq!(first_match["line"], 2);
+    assert_eq!(first_match["code"], "let needle = 1;");
+    assert_eq!(first_match["captures"]["NAME"], "needle");
+    assert_eq!(first_match["captures"]["VALUE"], "1");
+}
+
+#[tokio::test]
+async fn test_search_code_ast_requires_pattern_or_rule() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["project".to_string()]);
+    let root = temp_dir.join("project");
+    fs::write(root.join("lib.rs"), "fn main() {}\n").unwrap();
+
+    let params = SearchCodeAst {
+        path: root.to_str().unwrap().to_string(),
+        pattern: "**/*.rs".to_string(),
+        ast_pattern: None,
+        rule_yaml: None,
+        language: "rust".to_string(),
+        injected_language: None,
+        exclude_patterns: None,
+        file_extensions: None,
+        max_lines: None,
+        context_lines: None,
+        include_enclosing: None,
+        respect_gitignore: None,
+        include_hidden: None,
+        follow_symlinks: None,
+        threads: None,
+        low_priority: None,
+        max_file_size: None,
+        max_files: None,
+        max_results: None,
+        max_matches_per_file: None,
+        cursor: None,
+        output_mode: None,
+        output_format: None,
+    };
+    let result = SearchCodeAst::run_tool(params.clone(), &service).await;
+    assert_eq!(
+        ToolFailure::from(&result.unwrap_err()).code,
+        ErrorCode::InvalidArgument
+    );
+
+    let result = SearchCodeAst::run_tool(
+        SearchCodeAst {
+            ast_pattern: Some("fn $NAME() {}".to_string()),
+            rule_yaml: Some("rule:\n  kind: function_item\n".to_string()),
+            ..params.clone()
+        },
+        &service,
+    )
+    .await;
+    assert_eq!(
+        ToolFailure::from(&result.unwrap_err()).code,
+        ErrorCode::InvalidArgument
+    );
+
+    let result = SearchCodeAst::run_tool(
+        SearchCodeAst {
+            rule_yaml: Some("rule:\n  kind: function_item\n".to_string()),
+            ..params
+        },
+        &service,
+    )
+    .await
+    .unwrap();
+    assert_ne!(result.is_error, Some(true));
+}
+
+#[tokio::test]
+async fn test_rewrite_code_ast_max_files() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["project".to_string()]);
+    let root = temp_dir.join("project");
+    fs::write(root.join("a.rs"), "fn a() { g(1); }\n").unwrap();
+    fs::write(root.join("b.rs"), "fn b() { g(2); }\n").unwrap();
+    let params: RewriteCodeAst = serde_json::from_value(serde_json::json!({
+        "path": root.to_str().unwrap(),
+        "pattern": "**/*.rs",
+        "astPattern": "g($X)",
+        "rewrite": "h($X)",
+        "language": "rust",
+        "max_files": 1,
+    }))
+    .unwrap();
+
+    let result = RewriteCodeAst::run_tool(params.clone(), &service).await;
+    assert_eq!(
+        ToolFailure::from(&result.unwrap_err()).code,
+        ErrorCode::InvalidArgument
+    );
+    assert_eq!(
+        fs::read_to_string(root.join("a.rs")).unwrap(),
+        "fn a() { g(1); }\n"
+    );
+
+    let result = RewriteCodeAst::run_tool(
+        RewriteCodeAst {
+            max_files: Some(2),
+            ..params
+        },
+        &service,
+    )
+    .await
+    .unwrap();
+    assert_ne!(result.is_error, Some(true));
+    assert_eq!(
+        fs::read_to_string(root.join("b.rs")).unwrap(),
+        "fn b() { h(2); }\n"
+    );
+}
+
+#[tokio::test]
+async fn test_search_code_ast_pagination() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["project".to_string()]);
+    let root = temp_dir.join("project");
+    fs::write(
+        root.join("a.rs"),
+        "fn a() {\n    let x = 1;\n    let y = 2;\n    let z = 3;\n}\n",
+    )
+    .unwrap();
+    fs::write(root.join("b.rs"), "fn b() {\n    let w = 4;\n}\n").unwrap();
+    let params: SearchCodeAst = serde_json::from_value(serde_json::json!({
+        "path": root.to_str().unwrap(),
+        "pattern": "**/*.rs",
+        "astPattern": "let $NAME = $VALUE;",
+        "language": "rust",
+        "max_results": 2,
+        "output_format": "json",
+    }))
+    .unwrap();
+    let names = |result: &CallToolResult| -> Vec<String> {
+        let structured = result.structured_content.as_ref().unwrap();
+        structured["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|file| file["matches"].as_array().unwrap().clone())
+            .map(|m| m["captures"]["NAME"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let result = SearchCodeAst::run_tool(params.clone(), &service)
+        .await
+        .unwrap();
+    assert_eq!(names(&result), ["x", "y"]);
+    let meta = result.meta.unwrap();
+    assert_eq!(meta["truncated"], true);
+    assert_eq!(meta["total_matches"], 4);
+    assert_eq!(meta["returned_matches"], 2);
+    let cursor = meta["next_cursor"].as_str().unwrap().to_string();
+
+    let result = SearchCodeAst::run_tool(
+        SearchCodeAst {
+            cursor: Some(cursor),
+            ..params.clone()
+        },
+        &service,
+    )
+    .await
+    .unwrap();
+    assert_eq!(names(&result), ["z", "w"]);
+    assert!(result.meta.unwrap().get("next_cursor").is_none());
+
+    let result = SearchCodeAst::run_tool(
+        SearchCodeAst {
+            max_results: None,
+            max_matches_per_file: Some(1),
+            ..params.clone()
+        },
+        &service,
+    )
+    .await
+    .unwrap();
+    assert_eq!(names(&result), ["x", "w"]);
+    let meta = result.meta.unwrap();
+    assert_eq!(meta["truncated"], true);
+    assert_eq!(meta["capped_files"], 1);
+    assert!(meta.get("next_cursor").is_none());
+
+    let result = SearchCodeAst::run_tool(
+        SearchCodeAst {
+            cursor: Some("invalid".to_string()),
+            ..params
+        },
+        &service,
+    )
+    .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_search_code_ast_output_mode() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["project".to_string()]);
+    let root = temp_dir.join("project");
+    fs::write(root.join("a.rs"), "fn a() {\n    old_api();\n}\n").unwrap();
+    fs::write(
+        root.join("b.rs"),
+        "fn b() {\n    old_api();\n    old_api();\n}\n",
+    )
+    .unwrap();
+    fs::write(root.join("c.rs"), "fn c() {\n    new_api();\n}\n").unwrap();
+    let search = |output_mode: &str, output_format: &str| -> SearchCodeAst {
+        serde_json::from_value(serde_json::json!({
+            "path": root.to_str().unwrap(),
+            "pattern": "**/*.rs",
+            "astPattern": "old_api()",
+            "language": "rust",
+            "max_results": 1,
+            "output_mode": output_mode,
+            "output_format": output_format,
+        }))
+        .unwrap()
+    };
+
+    let result = SearchCodeAst::run_tool(search("count", "text"), &service)
+        .await
+        .unwrap();
+    let text = &result.content[0].as_text_content().unwrap().text;
+    let lines: Vec<_> = text.lines().collect();
+    assert_eq!(lines[0], "2 files matched, 3 total matches");
+    assert_eq!(lines[2], format!("2\t{}", root.join("b.rs").display()));
+    assert_eq!(lines[3], format!("1\t{}", root.join("a.rs").display()));
+    assert!(!text.contains("fn "));
+
+    let result = SearchCodeAst::run_tool(search("files_only", "json"), &service)
+        .await
+        .unwrap();
+    let structured = result.structured_content.unwrap();
+    assert_eq!(structured["summary"]["total_matches"], 3);
+    assert_eq!(
+        structured["files"],
+        serde_json::json!([
+            root.join("a.rs").display().to_string(),
+            root.join("b.rs").display().to_string()
+        ])
+    );
+    assert_eq!(result.meta.unwrap()["truncated"], false);
+}
+
+#[tokio::test]
+async fn test_edit_file_occurrence() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file = temp_dir.join("dir1").join("test.txt");
+    fs::write(&file, "a = 1\nb = 1\nc = 1\n").unwrap();
+    let edit_file = |occurrence: serde_json::Value| -> EditFile {
+        serde_json::from_value(serde_json::json!({
+            "path": file.to_str().unwrap(),
+            "edits": [{"oldText": "= 1", "newText": "= 2", "occurrence": occurrence}],
+        }))
+        .unwrap()
+    };
+
+    let result = EditFile::run_tool(edit_file(serde_json::json!(2)), &service)
+        .await
+        .unwrap();
+    let ContentBlock::TextContent(TextContent { text, .. }) = &result.content[0] else {
+        panic!("expected text content");
+    };
+    assert!(text.contains("Edit 1: 3 occurrences found, 1 replaced (exact match)."));
+    let summary = result.structured_content.as_ref().unwrap();
+    assert_eq!(summary["files_changed"], 1);
+    assert_eq!(summary["lines_added"], 1);
+    assert_eq!(fs::read_to_string(&file).unwrap(), "a = 1\nb = 2\nc = 1\n");
+
+    assert!(
+        EditFile::run_tool(edit_file(serde_json::json!(3)), &service)
+            .await
+            .is_err()
+    );
+
+    EditFile::run_tool(edit_file(serde_json::json!("all")), &service)
+        .await
+        .unwrap();
+    assert_eq!(fs::read_to_string(&file).unwrap(), "a = 2\nb = 2\nc = 2\n");
+}
+
+#[tokio::test]
+async fn test_read_multiple_files_report_errors() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let dir = temp_dir.join("dir1");
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+    fs::write(dir.join("a.txt"), "a").unwrap();
+    image::RgbaImage::new(4, 4)
+        .save(dir.join("image.png"))
+        .unwrap();
+
+    let result = ReadMultipleTextFiles::run_tool(
+        ReadMultipleTextFiles {
+            paths: vec![path("a.txt"), path("missing.txt")],
+        },
+        &service,
+    )
+    .await
+    .unwrap();
+    let report = result.structured_content.unwrap();
+    assert_eq!(report["files_read"], 1);
+    assert_eq!(report["errors"][0]["path"], path("missing.txt"));
+
+    let result = ReadMultipleMediaFiles::run_tool(
+        ReadMultipleMediaFiles {
+            paths: vec![path("image.png"), path("missing.png"), path("a.txt")],
+            max_bytes: None,
+        },
+        &service,
+    )
+    .await
+    .unwrap();
+    assert!(matches!(result.content[0], ContentBlock::ImageContent(_)));
+    let ContentBlock::TextContent(TextContent { text, .. }) = &result.content[1] else {
+        panic!("expected the failures as text");
+    };
+    assert!(text.starts_with("Failed to read 2 of 3 files"));
+    let report = result.structured_content.unwrap();
+    assert_eq!(report["files_read"], 1);
+    let failed: Vec<_> = report["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| error["path"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(failed, vec![path("missing.png"), path("a.txt")]);
+}
+
+#[tokio::test]
+async fn test_list_directory_pagination() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let dir = temp_dir.join("dir1");
+    for name in ["a.txt", "b.txt", "c.txt", "d.md"] {
+        fs::write(dir.join(name), name).unwrap();
+    }
+    let list = |cursor: Option<String>| ListDirectory {
+        path: dir.to_str().unwrap().to_string(),
+        sort_by: None,
+        filter_glob: Some("*.txt".to_string()),
+        limit: Some(2),
+        cursor,
+    };
+
+    let result = ListDirectory::run_tool(list(None), &service).await.unwrap();
+    let meta = result.meta.unwrap();
+    assert_eq!(meta["entries_total"], 3);
+    let ContentBlock::TextContent(TextContent { text, .. }) = &result.content[0] else {
+        panic!("expected text content");
+    };
+    assert_eq!(text, "[FILE] a.txt\n[FILE] b.txt");
+
+    let cursor = meta["next_cursor"].as_str().unwrap().to_string();
+    let result = ListDirectory::run_tool(list(Some(cursor)), &service)
+        .await
+        .unwrap();
+    let ContentBlock::TextContent(TextContent { text, .. }) = &result.content[0] else {
+        panic!("expected text content");
+    };
+    assert_eq!(text, "[FILE] c.txt");
+    assert!(result.meta.unwrap().get("next_cursor").is_none());
+}
+
+#[tokio::test]
+async fn test_list_directory_with_sizes_json() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let dir = temp_dir.join("dir1");
+    fs::create_dir(dir.join("sub")).unwrap();
+    fs::write(dir.join("sub").join("a.txt"), "1234").unwrap();
+    fs::write(dir.join("b.txt"), "12").unwrap();
+
+    let result = ListDirectoryWithSizes::run_tool(
+        ListDirectoryWithSizes {
+            path: dir.to_str().unwrap().to_string(),
+            depth: Some(1),
+            output_format: Some(OutputFormat::Json),
+        },
+        &service,
+    )
+    .await
+    .unwrap();
+    let ContentBlock::TextContent(TextContent { text, .. }) = &result.content[0] else {
+        panic!("expected text content");
+    };
+    let listing: serde_json::Value = serde_json::from_str(text).unwrap();
+    assert_eq!(listing["total_files"], 1);
+    assert_eq!(listing["total_directories"], 1);
+    assert_eq!(listing["total_size"], 6);
+    assert_eq!(listing["entries"][0]["name"], "b.txt");
+    assert_eq!(listing["entries"][1]["is_directory"], true);
+    assert_eq!(listing["entries"][1]["size"], 4);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_extended_attribute_tools_encoding() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let path = temp_dir.join("dir1").join("a.txt");
+    fs::write(&path, "a").unwrap();
+    let path = path.to_str().unwrap().to_string();
+
+    SetExtendedAttribute::run_tool(
+        SetExtendedAttribute {
+            path: path.clone(),
+            name: "user.blob".to_string(),
+            value: Some("/wA=".to_string()),
+            encoding: Some(AttributeEncoding::Base64),
+        },
+        &service,
+    )
+    .await
+    .unwrap();
+    let get = |name: &str| {
+        GetExtendedAttribute::run_tool(
+            GetExtendedAttribute {
+                path: path.clone(),
+                name: name.to_string(),
+            },
+            &service,
+        )
+    };
+    let value = get("user.blob").await.unwrap().structured_content.unwrap();
+    assert_eq!(value["encoding"], "base64");
+    assert_eq!(value["value"], "/wA=");
+    assert_eq!(value["size"], 2);
+
+    let listed =
+        ListExtendedAttributes::run_tool(ListExtendedAttributes { path: path.clone() }, &service)
+            .await
+            .unwrap()
+            .structured_content
+            .unwrap();
+    assert_eq!(listed["attributes"][0]["name"], "user.blob");
+
+    let invalid = SetExtendedAttribute::run_tool(
+        SetExtendedAttribute {
+            path: path.clone(),
+            name: "user.blob".to_string(),
+            value: Some("not base64!".to_string()),
+            encoding: Some(AttributeEncoding::Base64),
+        },
+        &service,
+    )
+    .await;
+    assert!(invalid.is_err());
+}
+
+#[test]
+fn test_tool_registry() {
+    let registry = ToolRegistry::new(Some(HashSet::from(["read_text_file".to_string()])), true);
+    assert!(registry.is_enabled("read_text_file"));
+    assert!(registry.is_enabled("list_allowed_directories"));
+    assert!(registry.is_enabled("configure_tools"));
+    assert!(!registry.is_enabled("write_file"));
+
+    let changed = registry
+        .configure(&["write_file".to_string()], &["read_text_file".to_string()])
+        .unwrap();
+    assert!(changed);
+    assert!(registry.is_enabled("write_file"));
+    assert!(!registry.is_enabled("read_text_file"));
+    assert!(
+        !registry
+            .configure(&["write_file".to_string()], &[])
+            .unwrap()
+    );
+
+    assert!(
+        registry
+            .configure(&[], &["configure_tools".to_string()])
+            .is_err()
+    );
+    assert!(
+        registry
+            .configure(&["no_such_tool".to_string()], &[])
+            .is_err()
+    );
+
+    // the admin tool is only available when enabled on the command line
+    let registry = ToolRegistry::new(None, false);
+    assert!(registry.is_enabled("write_file"));
+    assert!(!registry.is_enabled("configure_tools"));
+}
+
 #[tokio::test]
 async fn adhoc() {}
+
+#[test]
+fn test_logger_level_filter() {
+    let logger = McpLogger::default();
+    assert_eq!(logger.level(), LoggingLevel::Info);
+    assert!(!logger.enabled(&LoggingLevel::Debug));
+    assert!(logger.enabled(&LoggingLevel::Info));
+    assert!(logger.enabled(&LoggingLevel::Error));
+
+    logger.set_level(LoggingLevel::Warning);
+    assert!(!logger.enabled(&LoggingLevel::Info));
+    assert!(!logger.enabled(&LoggingLevel::Notice));
+    assert!(logger.enabled(&LoggingLevel::Warning));
+    assert!(logger.enabled(&LoggingLevel::Emergency));
+
+    logger.set_level(LoggingLevel::Debug);
+    assert!(logger.enabled(&LoggingLevel::Debug));
+}
+
+#[tokio::test]
+async fn test_get_server_stats() {
+    let (_temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let metrics = ServerMetrics::default();
+    metrics.record("read_text_file", false, 120, 0);
+    metrics.record("read_text_file", true, 0, 0);
+    metrics.record("write_file", false, 0, 42);
+
+    let stats = metrics.tool_stats();
+    assert_eq!(
+        stats["read_text_file"],
+        ToolStats {
+            calls: 2,
+            errors: 1,
+            bytes_read: 120,
+            bytes_written: 0
+        }
+    );
+
+    let result = GetServerStats::run_tool_with_context(GetServerStats {}, &service, &metrics)
+        .await
+        .unwrap();
+    let ContentBlock::TextContent(text) = &result.content[0] else {
+        panic!("Expected text content");
+    };
+    let report: serde_json::Value = serde_json::from_str(&text.text).unwrap();
+    assert_eq!(report["total"]["calls"], 3);
+    assert_eq!(report["total"]["bytes_written"], 42);
+    assert_eq!(report["tools"]["write_file"]["calls"], 1);
+
+    let arguments = serde_json::json!({
+        "path": "a.txt",
+        "content": "hello",
+        "edits": [{ "oldText": "a", "newText": "abc" }]
+    });
+    assert_eq!(written_size(arguments.as_object()), 8);
+}
+
+#[tokio::test]
+async fn test_list_allowed_directories() {
+    let temp_dir = get_temp_dir();
+    let src = temp_dir.join("src");
+    let vendor = temp_dir.join("vendor");
+    fs::create_dir_all(&src).unwrap();
+    fs::create_dir_all(&vendor).unwrap();
+    let service =
+        FileSystemService::try_new(&[src.display().to_string(), vendor.display().to_string()])
+            .unwrap()
+            .with_directory_modes(
+                DirectoryMode::ReadWrite,
+                vec![(vendor.display().to_string(), DirectoryMode::ReadOnly)],
+            )
+            .with_deny_globs(&["**/.env".to_string(), "secrets/**".to_string()])
+            .unwrap();
+    fs::remove_dir(&vendor).unwrap();
+
+    let result = ListAllowedDirectories::run_tool(
+        ListAllowedDirectories {
+            output_format: Some(OutputFormat::Json),
+        },
+        &service,
+    )
+    .await
+    .unwrap();
+    let ContentBlock::TextContent(text) = &result.content[0] else {
+        panic!("Expected text content");
+    };
+    let report: serde_json::Value = serde_json::from_str(&text.text).unwrap();
+    let directories = report["directories"].as_array().unwrap();
+    assert_eq!(directories[0]["mode"], "rw");
+    assert_eq!(directories[0]["exists"], true);
+    assert!(directories[0]["available_bytes"].as_u64().unwrap() > 0);
+    assert_eq!(directories[1]["mode"], "ro");
+    assert_eq!(directories[1]["exists"], false);
+    assert!(directories[1]["available_bytes"].is_null());
+    assert_eq!(
+        report["deny_globs"],
+        serde_json::json!(["**/.env", "secrets/**"])
+    );
+
+    let result = ListAllowedDirectories::run_tool(
+        ListAllowedDirectories {
+            output_format: None,
+        },
+        &service,
+    )
+    .await
+    .unwrap();
+    let ContentBlock::TextContent(text) = &result.content[0] else {
+        panic!("Expected text content");
+    };
+    assert!(
+        text.text
+            .contains(&format!("{} (ro, missing)", vendor.display()))
+    );
+    assert!(text.text.ends_with("Denied paths: **/.env, secrets/**"));
+}
+
+#[test]
+fn test_on_conflict_aliases() {
+    let params: CopyFile = serde_json::from_value(serde_json::json!({
+        "source": "a.txt",
+        "destination": "b.txt",
+        "on_conflict": "auto_rename"
+    }))
+    .unwrap();
+    assert_eq!(params.overwrite, Some(OverwritePolicy::Rename));
+    let params: MoveFile = serde_json::from_value(serde_json::json!({
+        "source": "a.txt",
+        "destination": "b.txt",
+        "on_conflict": "overwrite"
+    }))
+    .unwrap();
+    assert_eq!(params.overwrite, Some(OverwritePolicy::Replace));
+}
+
+/// Runs `read_text_file` steps directly, failing every other tool.
+struct ReadOnlyExecutor<'a> {
+    service: &'a FileSystemService,
+    cancellation: CancellationToken,
+}
+
+#[async_trait::async_trait]
+impl ToolExecutor for ReadOnlyExecutor<'_> {
+    async fn execute(
+        &self,
+        tool: String,
+        arguments: Option<serde_json::Map<String, serde_json::Value>>,
+    ) -> Result<CallToolResult, String> {
+        if tool != "read_text_file" {
+            return Err(format!("Unknown tool '{tool}'"));
+        }
+        let params: ReadTextFile =
+            serde_json::from_value(serde_json::Value::Object(arguments.unwrap_or_default()))
+                .map_err(|err| err.to_string())?;
+        ReadTextFile::run_tool(params, self.service)
+            .await
+            .map_err(|err| err.to_string())
+    }
+
+    fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+}
+
+#[tokio::test]
+async fn test_batch_execute() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let dir = temp_dir.join("dir1");
+    fs::write(dir.join("a.txt"), "first").unwrap();
+    fs::write(dir.join("b.txt"), "second").unwrap();
+    let executor = ReadOnlyExecutor {
+        service: &service,
+        cancellation: CancellationToken::new(),
+    };
+    let step = |tool: &str, path: &str| BatchStep {
+        tool: tool.to_string(),
+        arguments: Some(ToolArguments(
+            serde_json::json!({ "path": dir.join(path) })
+                .as_object()
+                .unwrap()
+                .clone(),
+        )),
+    };
+    let texts = |result: &CallToolResult| -> Vec<String> {
+        result
+            .content
+            .iter()
+            .map(|block| match block {
+                ContentBlock::TextContent(text) => text.text.clone(),
+                _ => panic!("Expected text content"),
+            })
+            .collect()
+    };
+
+    let params = BatchExecute {
+        steps: vec![
+            step("read_text_file", "a.txt"),
+            step("read_text_file", "missing.txt"),
+            step("read_text_file", "b.txt"),
+        ],
+        stop_on_error: Some(false),
+    };
+    let result = BatchExecute::run_tool_with_context(params, &service, &executor)
+        .await
+        .unwrap();
+    let texts = texts(&result);
+    assert_eq!(texts[0], "## Step 1: read_text_file (ok)");
+    assert_eq!(texts[1], "first");
+    assert_eq!(texts[2], "## Step 2: read_text_file (error)");
+    assert_eq!(texts[4], "## Step 3: read_text_file (ok)");
+    assert_eq!(texts[5], "second");
+    let structured = result.structured_content.unwrap();
+    assert_eq!(structured["completed"], 2);
+    assert_eq!(structured["failed"], 1);
+    assert_eq!(structured["steps"][1]["ok"], false);
+
+    // by default, the steps after a failure are skipped, and batches cannot be nested
+    let params = BatchExecute {
+        steps: vec![
+            step("batch_execute", "a.txt"),
+            step("read_text_file", "a.txt"),
+        ],
+        stop_on_error: None,
+    };
+    let result = BatchExecute::run_tool_with_context(params, &service, &executor)
+        .await
+        .unwrap();
+    let structured = result.structured_content.unwrap();
+    assert_eq!(structured["failed"], 1);
+    assert_eq!(structured["skipped"], 1);
+    assert_eq!(structured["steps"][0]["error"], "Batches cannot be nested.");
+
+    let params = BatchExecute {
+        steps: vec![],
+        stop_on_error: None,
+    };
+    assert!(
+        BatchExecute::run_tool_with_context(params, &service, &executor)
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_conditional_read() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file = temp_dir.join("dir1").join("file.txt");
+    fs::write(&file, "line 1\nline 2\n").unwrap();
+    let path = file.display().to_string();
+    let read_text = |if_none_match_sha256: Option<String>| ReadTextFile {
+        path: path.clone(),
+        max_bytes: None,
+        max_lines: None,
+        if_none_match_sha256,
+    };
+
+    let result = ReadTextFile::run_tool(read_text(None), &service)
+        .await
+        .unwrap();
+    let sha256 = result.meta.unwrap()["sha256"].as_str().unwrap().to_string();
+    assert_eq!(sha256, service.file_sha256(&file).await.unwrap());
+
+    let result = ReadTextFile::run_tool(read_text(Some(sha256.to_uppercase())), &service)
+        .await
+        .unwrap();
+    assert_eq!(result.meta.as_ref().unwrap()["not_modified"], true);
+    assert!(
+        result.content[0]
+            .as_text_content()
+            .unwrap()
+            .text
+            .starts_with("Not modified")
+    );
+
+    let params = ReadFileLines {
+        path: path.clone(),
+        offset: 1,
+        limit: None,
+        from_end: false,
+        byte_offset: None,
+        byte_limit: None,
+        show_line_numbers: false,
+        if_none_match_sha256: Some(sha256.clone()),
+    };
+    let result = ReadFileLines::run_tool(params.clone(), &service)
+        .await
+        .unwrap();
+    assert_eq!(result.meta.unwrap()["not_modified"], true);
+
+    // once modified, the content is returned along with the new hash
+    fs::write(&file, "line 1\nline 2 changed\n").unwrap();
+    let result = ReadFileLines::run_tool(params, &service).await.unwrap();
+    assert_eq!(
+        result.content[0].as_text_content().unwrap().text,
+        "line 2 changed\n"
+    );
+    let meta = result.meta.unwrap();
+    assert!(meta.get("not_modified").is_none());
+    assert_ne!(meta["sha256"], sha256.as_str());
+
+    let result = ReadTextFile::run_tool(read_text(Some(sha256)), &service)
+        .await
+        .unwrap();
+    assert_eq!(
+        result.content[0].as_text_content().unwrap().text,
+        "line 1\nline 2 changed\n"
+    );
+}
+
+#[test]
+fn test_limit_response_size() {
+    let result = CallToolResult::text_content(vec![TextContent::from(
+        "line 1\nline 2\nline 3\n".to_string(),
+    )]);
+
+    // within the budget, the result is unchanged
+    let unchanged = limit_response_size(result.clone(), 100);
+    assert_eq!(unchanged.content.len(), 1);
+    assert!(unchanged.meta.is_none());
+
+    let truncated = limit_response_size(result, 16);
+    let ContentBlock::TextContent(text) = &truncated.content[0] else {
+        panic!("Expected text content");
+    };
+    // cut at the end of the last complete line
+    assert_eq!(text.text, "line 1\nline 2\n");
+    let ContentBlock::TextContent(note) = &truncated.content[1] else {
+        panic!("Expected text content");
+    };
+    assert!(note.text.contains("14 of 21 bytes"));
+
+    let meta = truncated.meta.unwrap();
+    assert_eq!(meta["truncated"], true);
+    assert_eq!(meta["originalBytes"], 21);
+    assert_eq!(meta["returnedBytes"], 14);
+    assert!(meta.get("structuredContentTruncated").is_none());
+}
+
+#[test]
+fn test_limit_response_size_trims_structured_content() {
+    let result = |structured: serde_json::Value| {
+        CallToolResult::text_content(vec![TextContent::from(
+            "line 1\nline 2\nline 3\n".to_string(),
+        )])
+        .with_structured_content(structured.as_object().cloned().unwrap())
+    };
+
+    // the trailing items that do not fit are dropped
+    let truncated = limit_response_size(result(serde_json::json!({ "matches": [1, 2, 3] })), 16);
+    assert_eq!(
+        serde_json::Value::Object(truncated.structured_content.unwrap()),
+        serde_json::json!({ "matches": [1] })
+    );
+    assert_eq!(truncated.meta.unwrap()["structuredContentTruncated"], true);
+
+    // it is dropped when even empty arrays do not fit
+    let truncated = limit_response_size(
+        result(serde_json::json!({ "matches": [1], "summary": "1 match" })),
+        16,
+    );
+    assert!(truncated.structured_content.is_none());
+}
+
+#[tokio::test]
+async fn test_directory_tree_output_formats() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let root = temp_dir.join("dir1");
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("src").join("main.rs"), "fn main() {}").unwrap();
+
+    let tree_output = |output_format| {
+        let params = DirectoryTree {
+            path: root.to_str().unwrap().to_string(),
+            max_depth: None,
+            max_entries: None,
+            cursor: None,
+            exclude_patterns: None,
+            include_pattern: None,
+            include_metadata: None,
+            respect_gitignore: None,
+            include_hidden: None,
+            follow_symlinks: None,
+            max_output_bytes: None,
+            output_format: Some(output_format),
+        };
+        let service = &service;
+        async move {
+            let result = DirectoryTree::run_tool(params, service).await.unwrap();
+            let ContentBlock::TextContent(text) = &result.content[0] else {
+                panic!("Expected text content");
+            };
+            text.text.clone()
+        }
+    };
+
+    assert_eq!(
+        tree_output(TreeOutputFormat::Tree).await,
+        format!("{}\n└── src/\n    └── main.rs\n", root.display())
+    );
+    assert_eq!(
+        tree_output(TreeOutputFormat::Flat).await,
+        "src/\nsrc/main.rs\n"
+    );
+}
+
+#[tokio::test]
+async fn test_directory_tree_output_budget() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let root = temp_dir.join("dir1");
+    for i in 0..50 {
+        let dir = root.join("big").join(format!("module{i}"));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("file.rs"), "").unwrap();
+    }
+    fs::create_dir_all(root.join("small")).unwrap();
+    fs::write(root.join("small").join("a.txt"), "").unwrap();
+
+    let params = DirectoryTree {
+        path: root.to_str().unwrap().to_string(),
+        max_depth: Some(5),
+        max_entries: None,
+        cursor: None,
+        exclude_patterns: None,
+        include_pattern: None,
+        include_metadata: None,
+        respect_gitignore: None,
+        include_hidden: None,
+        follow_symlinks: None,
+        max_output_bytes: Some(200),
+        output_format: None,
+    };
+    let result = DirectoryTree::run_tool(params.clone(), &service)
+        .await
+        .unwrap();
+    let ContentBlock::TextContent(text) = &result.content[0] else {
+        panic!("Expected text content");
+    };
+    assert!(text.text.len() <= 200);
+    let tree: serde_json::Value = serde_json::from_str(&text.text).unwrap();
+    let big = tree
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|entry| entry["n"] == "big/")
+        .unwrap();
+    assert_eq!(big["omitted"], 100);
+    assert!(text.text.contains("a.txt"));
+    let meta = result.meta.unwrap();
+    assert_eq!(meta["truncated"], true);
+    assert_eq!(meta["elided"][0]["path"], "big/");
+    assert_eq!(meta["omitted_entries"], 100);
+
+    // a budget large enough leaves the tree untouched
+    let result = DirectoryTree::run_tool(
+        DirectoryTree {
+            max_output_bytes: Some(100_000),
+            ..params
+        },
+        &service,
+    )
+    .await
+    .unwrap();
+    assert!(result.meta.is_none());
+}
+
+#[tokio::test]
+async fn test_search_files_sorted_with_metadata() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let root = temp_dir.join("dir1");
+    fs::write(root.join("small.rs"), "a").unwrap();
+    fs::write(root.join("large.rs"), "abcdef").unwrap();
+
+    let params = SearchFiles {
+        path: root.to_str().unwrap().to_string(),
+        pattern: "*.rs".to_string(),
+        exclude_patterns: None,
+        file_extensions: None,
+        min_bytes: None,
+        max_bytes: None,
+        respect_gitignore: None,
+        include_hidden: None,
+        follow_symlinks: None,
+        max_depth: None,
+        sort_by: Some(SearchSortBy::Size),
+        order: None,
+        include_metadata: Some(true),
+        max_results: None,
+        output_format: Some(OutputFormat::Json),
+    };
+    let result = SearchFiles::run_tool(params.clone(), &service)
+        .await
+        .unwrap();
+    let structured = result.structured_content.unwrap();
+    assert_eq!(
+        structured["results"][0]["path"],
+        root.join("large.rs").display().to_string()
+    );
+    assert_eq!(structured["results"][0]["size"], 6);
+    assert_eq!(structured["results"][1]["size"], 1);
+    assert!(structured["results"][1]["modified"].is_string());
+
+    let result = SearchFiles::run_tool(
+        SearchFiles {
+            order: Some(SortOrder::Asc),
+            output_format: None,
+            ..params
+        },
+        &service,
+    )
+    .await
+    .unwrap();
+    let ContentBlock::TextContent(text) = &result.content[0] else {
+        panic!("Expected TextContent result");
+    };
+    let lines: Vec<&str> = text.text.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with(&format!(
+        "{} (1 bytes, modified ",
+        root.join("small.rs").display()
+    )));
+}
+
+#[tokio::test]
+async fn test_search_files_max_results() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let root = temp_dir.join("dir1");
+    for name in ["a.rs", "b.rs", "c.rs"] {
+        fs::write(root.join(name), "").unwrap();
+    }
+
+    let params = SearchFiles {
+        path: root.to_str().unwrap().to_string(),
+        pattern: "*.rs".to_string(),
+        exclude_patterns: None,
+        file_extensions: None,
+        min_bytes: None,
+        max_bytes: None,
+        respect_gitignore: None,
+        include_hidden: None,
+        follow_symlinks: None,
+        max_depth: None,
+        sort_by: None,
+        order: None,
+        include_metadata: None,
+        max_results: Some(2),
+        output_format: None,
+    };
+    let result = SearchFiles::run_tool(params.clone(), &service)
+        .await
+        .unwrap();
+    let meta = result.meta.unwrap();
+    assert_eq!(meta["truncated"], true);
+    assert_eq!(meta["total_found"], 3);
+    assert_eq!(meta["returned"], 2);
+    let ContentBlock::TextContent(text) = &result.content[0] else {
+        panic!("Expected TextContent result");
+    };
+    let lines: Vec<&str> = text.text.lines().collect();
+    assert_eq!(
+        lines[..2],
+        [
+            root.join("a.rs").display().to_string(),
+            root.join("b.rs").display().to_string()
+        ]
+    );
+    assert!(lines[2].starts_with("[Found 3 matches, only the first 2 are returned."));
+
+    let result = SearchFiles::run_tool(
+        SearchFiles {
+            max_results: None,
+            ..params
+        },
+        &service,
+    )
+    .await
+    .unwrap();
+    let meta = result.meta.unwrap();
+    assert_eq!(meta["truncated"], false);
+    assert_eq!(meta["files_matched"], 3);
+    assert!(meta["files_scanned"].as_u64().unwrap() >= 3);
+    assert!(meta.get("warning").is_none());
+}
+
+#[tokio::test]
+async fn test_search_files_content_reports_skipped_files() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let root = temp_dir.join("dir1");
+    fs::write(root.join("main.rs"), "let needle = 1;\n").unwrap();
+    fs::write(root.join("blob.rs"), b"needle\0").unwrap();
+
+    let result = SearchFilesContent::run_tool(
+        SearchFilesContent {
+            path: root.to_str().unwrap().to_string(),
+            pattern: "*.rs".to_string(),
+            query: "needle".to_string(),
+            is_regex: None,
+            exclude_patterns: None,
+            min_bytes: None,
+            max_bytes: None,
+            max_results: None,
+            max_matches_per_file: None,
+            cursor: None,
+            respect_gitignore: None,
+            include_hidden: None,
+            follow_symlinks: None,
+            max_depth: None,
+            threads: None,
+            low_priority: None,
+            skip_binary: None,
+            max_line_length: None,
+            long_lines: None,
+            invert_match: None,
+            path_regex: None,
+            highlight: None,
+            highlight_start: None,
+            highlight_end: None,
+            output_format: None,
+        },
+        &service,
+    )
+    .await
+    .unwrap();
+    let meta = result.meta.unwrap();
+    assert_eq!(meta["skipped_binary_files"], 1);
+    assert_eq!(meta["skipped_long_line_files"], 0);
+    let ContentBlock::TextContent(text) = &result.content[0] else {
+        panic!("Expected TextContent result");
+    };
+    assert!(text.text.contains("main.rs"));
+    assert!(text.text.ends_with("[Skipped 1 binary files.]\n"));
+}
+
+#[tokio::test]
+async fn test_tool_error_codes() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let error_of = |result: Result<CallToolResult, CallToolError>| {
+        let result = CallToolResult::from(ToolFailure::from(&result.unwrap_err()));
+        assert_eq!(result.is_error, Some(true));
+        let [
+            ContentBlock::TextContent(message),
+            ContentBlock::TextContent(details),
+        ] = &result.content[..]
+        else {
+            panic!("Expected a message and its details");
+        };
+        let details: serde_json::Value = serde_json::from_str(&details.text).unwrap();
+        assert_eq!(details["error"]["message"], message.text);
+        details["error"]["code"].as_str().unwrap().to_string()
+    };
+    let read_text = |path: std::path::PathBuf| ReadTextFile {
+        path: path.display().to_string(),
+        max_bytes: None,
+        max_lines: None,
+        if_none_match_sha256: None,
+    };
+
+    let result = ReadTextFile::run_tool(read_text(temp_dir.join("dir2").join("a.txt")), &service);
+    assert_eq!(error_of(result.await), "PATH_DENIED");
+    let result = ReadTextFile::run_tool(read_text(temp_dir.join("dir1").join("a.txt")), &service);
+    assert_eq!(error_of(result.await), "NOT_FOUND");
+
+    let result = SearchFiles::run_tool(
+        SearchFiles {
+            path: temp_dir.join("dir1").display().to_string(),
+            pattern: "[".to_string(),
+            exclude_patterns: None,
+            file_extensions: None,
+            min_bytes: None,
+            max_bytes: None,
+            respect_gitignore: None,
+            include_hidden: None,
+            follow_symlinks: None,
+            max_depth: None,
+            sort_by: None,
+            order: None,
+            include_metadata: None,
+            max_results: None,
+            output_format: None,
+        },
+        &service,
+    );
+    assert_eq!(error_of(result.await), "PATTERN_INVALID");
+
+    let search_content = |path: std::path::PathBuf, query: &str| SearchFilesContent {
+        path: path.display().to_string(),
+        pattern: "*.txt".to_string(),
+        query: query.to_string(),
+        is_regex: Some(true),
+        exclude_patterns: None,
+        min_bytes: None,
+        max_bytes: None,
+        max_results: None,
+        max_matches_per_file: None,
+        cursor: None,
+        respect_gitignore: None,
+        include_hidden: None,
+        follow_symlinks: None,
+        max_depth: None,
+        threads: None,
+        low_priority: None,
+        skip_binary: None,
+        max_line_length: None,
+        long_lines: None,
+        invert_match: None,
+        path_regex: None,
+        highlight: None,
+        highlight_start: None,
+        highlight_end: None,
+        output_format: None,
+    };
+    let result = SearchFilesContent::run_tool(search_content(temp_dir.join("dir1"), "("), &service);
+    assert_eq!(error_of(result.await), "PATTERN_INVALID");
+    let result = SearchFilesContent::run_tool(search_content(temp_dir.join("dir2"), "a"), &service);
+    assert_eq!(error_of(result.await), "PATH_DENIED");
+    let result = SearchFilesContent::run_tool(search_content(temp_dir.join("dir1"), "a"), &service);
+    assert_eq!(error_of(result.await), "NO_MATCH");
+
+    let search_ast = |path: std::path::PathBuf, ast_pattern: &str| SearchCodeAst {
+        path: path.display().to_string(),
+        pattern: "**/*.rs".to_string(),
+        ast_pattern: Some(ast_pattern.to_string()),
+        rule_yaml: None,
+        language: "rust".to_string(),
+        injected_language: None,
+        exclude_patterns: None,
+        file_extensions: None,
+        max_lines: None,
+        context_lines: None,
+        include_enclosing: None,
+        respect_gitignore: None,
+        include_hidden: None,
+        follow_symlinks: None,
+        threads: None,
+        low_priority: None,
+        max_file_size: None,
+        max_files: None,
+        max_results: None,
+        max_matches_per_file: None,
+        cursor: None,
+        output_mode: None,
+        output_format: None,
+    };
+    let result = SearchCodeAst::run_tool(search_ast(temp_dir.join("dir1"), "fn ("), &service);
+    assert_eq!(error_of(result.await), "PATTERN_INVALID");
+    let result = SearchCodeAst::run_tool(search_ast(temp_dir.join("dir2"), "fn $F() {}"), &service);
+    assert_eq!(error_of(result.await), "PATH_DENIED");
+
+    let read_only = service.with_read_only(true);
+    let result = WriteFile::run_tool(
+        WriteFile {
+            path: temp_dir.join("dir1").join("a.txt").display().to_string(),
+            content: "content".to_string(),
+            backup: None,
+            if_match_sha256: None,
+        },
+        &read_only,
+    );
+    assert_eq!(error_of(result.await), "READ_ONLY");
+
+    let arguments = serde_json::json!({ "path": 1 }).as_object().cloned();
+    let result = FileSystemTools::try_from(CallToolRequestParams {
+        name: "read_text_file".to_string(),
+        arguments,
+    })
+    .map(|_| CallToolResult::text_content(vec![]));
+    assert_eq!(error_of(result), "INVALID_ARGUMENT");
+}
+
+#[tokio::test]
+async fn test_tool_error_codes_of_edits() {
+    let (temp_dir, service, _allowed_dirs) = setup_service(vec!["dir1".to_string()]);
+    let file_path = temp_dir.join("dir1").join("a.txt");
+    fs::write(&file_path, "line1\nline2\n").unwrap();
+    let code_of = |result: Result<CallToolResult, CallToolError>| {
+        ToolFailure::from(&result.unwrap_err()).code
+    };
+    let edit_file = |edit: EditOperation| EditFile {
+        path: file_path.display().to_string(),
+        edits: vec![edit],
+        dry_run: Some(true),
+        backup: None,
+        if_match_sha256: None,
+    };
+
+    let result = EditFile::run_tool(
+        edit_file(EditOperation {
+            old_text: "missing".to_string(),
+            new_text: "found".to_string(),
+            ..Default::default()
+        }),
+        &service,
+    );
+    assert_eq!(code_of(result.await), ErrorCode::NoMatch);
+
+    let result = EditFile::run_tool(
+        edit_file(EditOperation {
+            new_text: "line3".to_string(),
+            start_line: Some(3),
+            end_line: Some(4),
+            ..Default::default()
+        }),
+        &service,
+    );
+    assert_eq!(code_of(result.await), ErrorCode::InvalidArgument);
+
+    let result = ReadTextFile::run_tool(
+        ReadTextFile {
+            path: temp_dir.join("dir1").display().to_string(),
+            max_bytes: None,
+            max_lines: None,
+            if_none_match_sha256: None,
+        },
+        &service,
+    );
+    assert_eq!(code_of(result.await), ErrorCode::InvalidArgument);
+
+    let err = ServiceError::from(RpcError::invalid_params().with_message("bad".to_string()));
+    assert_eq!(err.code(), ErrorCode::InvalidArgument);
+    let err = ServiceError::from(RpcError::internal_error());
+    assert_eq!(err.code(), ErrorCode::Internal);
+}